@@ -0,0 +1,242 @@
+#ifndef PNA_H
+#define PNA_H
+
+/* This file is generated by cbindgen, do not edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Kind of an entry.
+ */
+typedef enum PnaEntryKind {
+  /**
+   * Regular file
+   */
+  PNA_ENTRY_KIND_FILE = 0,
+  /**
+   * Directory
+   */
+  PNA_ENTRY_KIND_DIRECTORY = 1,
+  /**
+   * Symbolic link, the content is the path of the target.
+   */
+  PNA_ENTRY_KIND_SYMBOLIC_LINK = 2,
+  /**
+   * Hard link, the content is the name of the target entry.
+   */
+  PNA_ENTRY_KIND_HARD_LINK = 3,
+} PnaEntryKind;
+
+/**
+ * Status code returned by the functions.
+ */
+typedef enum PnaStatus {
+  /**
+   * The function succeeded.
+   */
+  PNA_STATUS_OK = 0,
+  /**
+   * There are no more entries in the archive.
+   */
+  PNA_STATUS_END = 1,
+  /**
+   * An argument is null or invalid, or a password is required but not given.
+   */
+  PNA_STATUS_ERROR_INVALID_ARGUMENT = -1,
+  /**
+   * Reading the archive failed.
+   */
+  PNA_STATUS_ERROR_IO = -2,
+  /**
+   * The archive is broken, or the password is wrong.
+   */
+  PNA_STATUS_ERROR_INVALID_DATA = -3,
+  /**
+   * The archive uses a feature that is not supported.
+   */
+  PNA_STATUS_ERROR_UNSUPPORTED = -4,
+  /**
+   * The callback returned non-zero.
+   */
+  PNA_STATUS_ERROR_ABORTED = -5,
+  /**
+   * An unexpected internal error occurred.
+   */
+  PNA_STATUS_ERROR_INTERNAL = -6,
+} PnaStatus;
+
+/**
+ * Archive opened for reading.
+ */
+typedef struct PnaArchive PnaArchive;
+
+/**
+ * Entry of an archive.
+ */
+typedef struct PnaEntry PnaEntry;
+
+/**
+ * Information of an entry, filled by [`pna_entry_stat`].
+ */
+typedef struct PnaEntryStat {
+  /**
+   * Kind of the entry.
+   */
+  enum PnaEntryKind kind;
+  /**
+   * Size of the stored data in bytes, that is, compressed and encrypted.
+   */
+  uint64_t compressed_size;
+  /**
+   * Size of the content in bytes, valid if `has_raw_size` is true.
+   */
+  uint64_t raw_size;
+  /**
+   * Whether the archive records the size of the content.
+   */
+  bool has_raw_size;
+  /**
+   * Seconds of the modification time since the Unix epoch, valid if `has_mtime` is true.
+   */
+  uint64_t mtime_sec;
+  /**
+   * Nanoseconds of the modification time, valid if `has_mtime` is true.
+   */
+  uint32_t mtime_nsec;
+  /**
+   * Whether the archive records the modification time.
+   */
+  bool has_mtime;
+} PnaEntryStat;
+
+/**
+ * Callback receiving the content of an entry from [`pna_entry_read_with`].
+ * Returning non-zero stops reading.
+ */
+typedef int (*PnaWriteCallback)(void *user_data, const uint8_t *data, size_t len);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Opens the archive at `path`, and stores the handle to `*out`.
+ *
+ * # Safety
+ *
+ * `path` must be a null-terminated UTF-8 string, and `out` must be valid for writes.
+ * The handle must be released by [`pna_archive_free`].
+ */
+enum PnaStatus pna_archive_open_path(const char *path, struct PnaArchive **out);
+
+/**
+ * Opens the archive in the `len` bytes at `data`, and stores the handle to `*out`.
+ * The bytes are copied, so `data` can be released after this call.
+ *
+ * # Safety
+ *
+ * `data` must be valid for reads of `len` bytes, and `out` must be valid for writes.
+ * The handle must be released by [`pna_archive_free`].
+ */
+enum PnaStatus pna_archive_open_memory(const uint8_t *data, size_t len, struct PnaArchive **out);
+
+/**
+ * Sets the password used for the entries returned after this call. `NULL` removes the password.
+ *
+ * # Safety
+ *
+ * `archive` must be a handle returned by an open function, and `password` must be
+ * `NULL` or a null-terminated UTF-8 string.
+ */
+enum PnaStatus pna_archive_set_password(struct PnaArchive *archive, const char *password);
+
+/**
+ * Reads the next entry, and stores the handle to `*out`.
+ * Returns [`PnaStatus::End`] when there are no more entries.
+ *
+ * Entries in solid entries are returned one by one, so the password must be set
+ * before reading encrypted solid entries.
+ *
+ * # Safety
+ *
+ * `archive` must be a handle returned by an open function, and `out` must be valid for writes.
+ * The entry handle must be released by [`pna_entry_free`], it can outlive the archive.
+ */
+enum PnaStatus pna_archive_next_entry(struct PnaArchive *archive, struct PnaEntry **out);
+
+/**
+ * Releases the archive handle. `NULL` is ignored.
+ *
+ * # Safety
+ *
+ * `archive` must be `NULL` or a handle returned by an open function that is not released yet.
+ */
+void pna_archive_free(struct PnaArchive *archive);
+
+/**
+ * Returns the name of the entry as a null-terminated UTF-8 string,
+ * valid until the entry is released. Returns `NULL` if `entry` is `NULL`.
+ *
+ * # Safety
+ *
+ * `entry` must be `NULL` or a handle returned by [`pna_archive_next_entry`].
+ */
+const char *pna_entry_name(const struct PnaEntry *entry);
+
+/**
+ * Stores the information of the entry to `*out`.
+ *
+ * # Safety
+ *
+ * `entry` must be a handle returned by [`pna_archive_next_entry`], and `out` must be valid for writes.
+ */
+enum PnaStatus pna_entry_stat(const struct PnaEntry *entry, struct PnaEntryStat *out);
+
+/**
+ * Reads the content of the entry into the `len` bytes at `buf` from where the previous call
+ * stopped, and stores the number of bytes read to `*read`. `*read` is 0 at the end of the content.
+ *
+ * # Safety
+ *
+ * `entry` must be a handle returned by [`pna_archive_next_entry`], `buf` must be valid for
+ * writes of `len` bytes, and `read` must be valid for writes.
+ */
+enum PnaStatus pna_entry_read(struct PnaEntry *entry, uint8_t *buf, size_t len, size_t *read);
+
+/**
+ * Reads the rest of the content of the entry, passing it to `callback` in pieces with `user_data`.
+ * Returns [`PnaStatus::ErrorAborted`] if `callback` returns non-zero.
+ *
+ * # Safety
+ *
+ * `entry` must be a handle returned by [`pna_archive_next_entry`], and `callback` must be
+ * safe to call with `user_data`.
+ */
+enum PnaStatus pna_entry_read_with(struct PnaEntry *entry,
+                                   PnaWriteCallback callback,
+                                   void *user_data);
+
+/**
+ * Releases the entry handle. `NULL` is ignored.
+ *
+ * # Safety
+ *
+ * `entry` must be `NULL` or a handle returned by [`pna_archive_next_entry`] that is not released yet.
+ */
+void pna_entry_free(struct PnaEntry *entry);
+
+/**
+ * Returns the message of the last error on the calling thread as a null-terminated string,
+ * or `NULL` if no error occurred. It is valid until the next failing call on the thread.
+ */
+const char *pna_last_error_message(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PNA_H */
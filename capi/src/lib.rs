@@ -0,0 +1,485 @@
+//! C ABI for reading PNA archives.
+//!
+//! Archives and entries are opaque handles created by the `pna_*_open_*` and
+//! [`pna_archive_next_entry`] functions, and released by the `pna_*_free` functions.
+//! Every fallible function returns a [`PnaStatus`], and the message of the last error
+//! on the calling thread is returned by [`pna_last_error_message`].
+#![deny(missing_docs, clippy::missing_safety_doc)]
+
+use libpna::{Archive, DataKind, NormalEntry, ReadEntry, ReadOptions};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    fs,
+    io::{self, prelude::*, BufReader, Cursor},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+/// Status code returned by the functions.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum PnaStatus {
+    /// The function succeeded.
+    Ok = 0,
+    /// There are no more entries in the archive.
+    End = 1,
+    /// An argument is null or invalid, or a password is required but not given.
+    ErrorInvalidArgument = -1,
+    /// Reading the archive failed.
+    ErrorIo = -2,
+    /// The archive is broken, or the password is wrong.
+    ErrorInvalidData = -3,
+    /// The archive uses a feature that is not supported.
+    ErrorUnsupported = -4,
+    /// The callback returned non-zero.
+    ErrorAborted = -5,
+    /// An unexpected internal error occurred.
+    ErrorInternal = -6,
+}
+
+/// Kind of an entry.
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum PnaEntryKind {
+    /// Regular file
+    File = 0,
+    /// Directory
+    Directory = 1,
+    /// Symbolic link, the content is the path of the target.
+    SymbolicLink = 2,
+    /// Hard link, the content is the name of the target entry.
+    HardLink = 3,
+}
+
+impl From<DataKind> for PnaEntryKind {
+    #[inline]
+    fn from(value: DataKind) -> Self {
+        match value {
+            DataKind::File => Self::File,
+            DataKind::Directory => Self::Directory,
+            DataKind::SymbolicLink => Self::SymbolicLink,
+            DataKind::HardLink => Self::HardLink,
+        }
+    }
+}
+
+/// Information of an entry, filled by [`pna_entry_stat`].
+#[repr(C)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct PnaEntryStat {
+    /// Kind of the entry.
+    pub kind: PnaEntryKind,
+    /// Size of the stored data in bytes, that is, compressed and encrypted.
+    pub compressed_size: u64,
+    /// Size of the content in bytes, valid if `has_raw_size` is true.
+    pub raw_size: u64,
+    /// Whether the archive records the size of the content.
+    pub has_raw_size: bool,
+    /// Seconds of the modification time since the Unix epoch, valid if `has_mtime` is true.
+    pub mtime_sec: u64,
+    /// Nanoseconds of the modification time, valid if `has_mtime` is true.
+    pub mtime_nsec: u32,
+    /// Whether the archive records the modification time.
+    pub has_mtime: bool,
+}
+
+/// Callback receiving the content of an entry from [`pna_entry_read_with`].
+/// Returning non-zero stops reading.
+pub type PnaWriteCallback =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, data: *const u8, len: usize) -> c_int>;
+
+/// Archive opened for reading.
+pub struct PnaArchive {
+    archive: Archive<Box<dyn Read>>,
+    solid: VecDeque<NormalEntry>,
+    password: Option<String>,
+    end: bool,
+}
+
+impl PnaArchive {
+    fn new(reader: Box<dyn Read>) -> io::Result<Self> {
+        Ok(Self {
+            archive: Archive::read_header(reader)?,
+            solid: VecDeque::new(),
+            password: None,
+            end: false,
+        })
+    }
+
+    /// Returns the next entry, reading the entries in solid entries one by one.
+    fn next_entry(&mut self) -> io::Result<Option<NormalEntry>> {
+        loop {
+            if let Some(entry) = self.solid.pop_front() {
+                return Ok(Some(entry));
+            }
+            if self.end {
+                return Ok(None);
+            }
+            match self.archive.entries().next().transpose()? {
+                None => {
+                    // NOTE: Nothing can be read after the end of the archive.
+                    self.end = true;
+                    return Ok(None);
+                }
+                Some(ReadEntry::Normal(entry)) => return Ok(Some(entry)),
+                Some(ReadEntry::Solid(solid)) => {
+                    self.solid = solid
+                        .entries(self.password.as_deref())?
+                        .collect::<io::Result<_>>()?;
+                }
+            }
+        }
+    }
+}
+
+/// Entry of an archive.
+pub struct PnaEntry {
+    entry: NormalEntry,
+    /// Content of the entry, decoded on the first read.
+    data: Option<Cursor<Vec<u8>>>,
+    name: CString,
+    password: Option<String>,
+}
+
+impl PnaEntry {
+    fn new(entry: NormalEntry, password: Option<String>) -> io::Result<Self> {
+        let name = CString::new(entry.header().path().as_str())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(Self {
+            entry,
+            data: None,
+            name,
+            password,
+        })
+    }
+
+    fn reader(&mut self) -> io::Result<&mut Cursor<Vec<u8>>> {
+        if self.data.is_none() {
+            let options = ReadOptions::with_password(self.password.as_deref());
+            let mut data = Vec::new();
+            self.entry.reader(options)?.read_to_end(&mut data)?;
+            self.data = Some(Cursor::new(data));
+        }
+        Ok(self.data.as_mut().expect("data was just read"))
+    }
+}
+
+struct Error {
+    status: PnaStatus,
+    message: String,
+}
+
+impl Error {
+    fn new(status: PnaStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn null(name: &str) -> Self {
+        Self::new(PnaStatus::ErrorInvalidArgument, format!("`{name}` is null"))
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        let status = match value.kind() {
+            io::ErrorKind::InvalidInput => PnaStatus::ErrorInvalidArgument,
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+                PnaStatus::ErrorInvalidData
+            }
+            io::ErrorKind::Unsupported => PnaStatus::ErrorUnsupported,
+            _ => PnaStatus::ErrorIo,
+        };
+        Self::new(status, value.to_string())
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', "\\0")).unwrap_or_default();
+    LAST_ERROR.with(|it| *it.borrow_mut() = Some(message));
+}
+
+/// Runs `f` converting its errors and panics to a status, and records the error message.
+fn guard(f: impl FnOnce() -> Result<PnaStatus, Error>) -> PnaStatus {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(status)) => status,
+        Ok(Err(e)) => {
+            set_last_error(e.message);
+            e.status
+        }
+        Err(_) => {
+            set_last_error("internal error: panicked".into());
+            PnaStatus::ErrorInternal
+        }
+    }
+}
+
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, Error> {
+    if s.is_null() {
+        return Err(Error::null(name));
+    }
+    CStr::from_ptr(s).to_str().map_err(|e| {
+        Error::new(
+            PnaStatus::ErrorInvalidArgument,
+            format!("`{name}` is not valid UTF-8: {e}"),
+        )
+    })
+}
+
+unsafe fn open(
+    out: *mut *mut PnaArchive,
+    reader: impl FnOnce() -> io::Result<Box<dyn Read>>,
+) -> Result<PnaStatus, Error> {
+    if out.is_null() {
+        return Err(Error::null("out"));
+    }
+    let archive = PnaArchive::new(reader()?)?;
+    *out = Box::into_raw(Box::new(archive));
+    Ok(PnaStatus::Ok)
+}
+
+/// Opens the archive at `path`, and stores the handle to `*out`.
+///
+/// # Safety
+///
+/// `path` must be a null-terminated UTF-8 string, and `out` must be valid for writes.
+/// The handle must be released by [`pna_archive_free`].
+#[no_mangle]
+pub unsafe extern "C" fn pna_archive_open_path(
+    path: *const c_char,
+    out: *mut *mut PnaArchive,
+) -> PnaStatus {
+    guard(|| {
+        let path = str_arg(path, "path")?;
+        open(out, || {
+            let file = fs::File::open(path)?;
+            Ok(Box::new(BufReader::new(file)))
+        })
+    })
+}
+
+/// Opens the archive in the `len` bytes at `data`, and stores the handle to `*out`.
+/// The bytes are copied, so `data` can be released after this call.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes, and `out` must be valid for writes.
+/// The handle must be released by [`pna_archive_free`].
+#[no_mangle]
+pub unsafe extern "C" fn pna_archive_open_memory(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut PnaArchive,
+) -> PnaStatus {
+    guard(|| {
+        if data.is_null() {
+            return Err(Error::null("data"));
+        }
+        let data = slice::from_raw_parts(data, len).to_vec();
+        open(out, || Ok(Box::new(Cursor::new(data))))
+    })
+}
+
+/// Sets the password used for the entries returned after this call. `NULL` removes the password.
+///
+/// # Safety
+///
+/// `archive` must be a handle returned by an open function, and `password` must be
+/// `NULL` or a null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn pna_archive_set_password(
+    archive: *mut PnaArchive,
+    password: *const c_char,
+) -> PnaStatus {
+    guard(|| {
+        let archive = archive.as_mut().ok_or_else(|| Error::null("archive"))?;
+        archive.password = if password.is_null() {
+            None
+        } else {
+            Some(str_arg(password, "password")?.into())
+        };
+        Ok(PnaStatus::Ok)
+    })
+}
+
+/// Reads the next entry, and stores the handle to `*out`.
+/// Returns [`PnaStatus::End`] when there are no more entries.
+///
+/// Entries in solid entries are returned one by one, so the password must be set
+/// before reading encrypted solid entries.
+///
+/// # Safety
+///
+/// `archive` must be a handle returned by an open function, and `out` must be valid for writes.
+/// The entry handle must be released by [`pna_entry_free`], it can outlive the archive.
+#[no_mangle]
+pub unsafe extern "C" fn pna_archive_next_entry(
+    archive: *mut PnaArchive,
+    out: *mut *mut PnaEntry,
+) -> PnaStatus {
+    guard(|| {
+        let archive = archive.as_mut().ok_or_else(|| Error::null("archive"))?;
+        if out.is_null() {
+            return Err(Error::null("out"));
+        }
+        let Some(entry) = archive.next_entry()? else {
+            return Ok(PnaStatus::End);
+        };
+        let entry = PnaEntry::new(entry, archive.password.clone())?;
+        *out = Box::into_raw(Box::new(entry));
+        Ok(PnaStatus::Ok)
+    })
+}
+
+/// Releases the archive handle. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `archive` must be `NULL` or a handle returned by an open function that is not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn pna_archive_free(archive: *mut PnaArchive) {
+    if !archive.is_null() {
+        drop(Box::from_raw(archive));
+    }
+}
+
+/// Returns the name of the entry as a null-terminated UTF-8 string,
+/// valid until the entry is released. Returns `NULL` if `entry` is `NULL`.
+///
+/// # Safety
+///
+/// `entry` must be `NULL` or a handle returned by [`pna_archive_next_entry`].
+#[no_mangle]
+pub unsafe extern "C" fn pna_entry_name(entry: *const PnaEntry) -> *const c_char {
+    entry.as_ref().map_or(ptr::null(), |it| it.name.as_ptr())
+}
+
+/// Stores the information of the entry to `*out`.
+///
+/// # Safety
+///
+/// `entry` must be a handle returned by [`pna_archive_next_entry`], and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pna_entry_stat(
+    entry: *const PnaEntry,
+    out: *mut PnaEntryStat,
+) -> PnaStatus {
+    guard(|| {
+        let entry = &entry.as_ref().ok_or_else(|| Error::null("entry"))?.entry;
+        if out.is_null() {
+            return Err(Error::null("out"));
+        }
+        let metadata = entry.metadata();
+        let raw_size = metadata.raw_file_size();
+        let mtime = metadata.modified();
+        *out = PnaEntryStat {
+            kind: entry.header().data_kind().into(),
+            compressed_size: metadata.compressed_size() as u64,
+            raw_size: raw_size.map_or(0, |it| u64::try_from(it).unwrap_or(u64::MAX)),
+            has_raw_size: raw_size.is_some(),
+            mtime_sec: mtime.map_or(0, |it| it.as_secs()),
+            mtime_nsec: mtime.map_or(0, |it| it.subsec_nanos()),
+            has_mtime: mtime.is_some(),
+        };
+        Ok(PnaStatus::Ok)
+    })
+}
+
+/// Reads the content of the entry into the `len` bytes at `buf` from where the previous call
+/// stopped, and stores the number of bytes read to `*read`. `*read` is 0 at the end of the content.
+///
+/// # Safety
+///
+/// `entry` must be a handle returned by [`pna_archive_next_entry`], `buf` must be valid for
+/// writes of `len` bytes, and `read` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn pna_entry_read(
+    entry: *mut PnaEntry,
+    buf: *mut u8,
+    len: usize,
+    read: *mut usize,
+) -> PnaStatus {
+    guard(|| {
+        let entry = entry.as_mut().ok_or_else(|| Error::null("entry"))?;
+        if buf.is_null() {
+            return Err(Error::null("buf"));
+        }
+        if read.is_null() {
+            return Err(Error::null("read"));
+        }
+        let buf = slice::from_raw_parts_mut(buf, len);
+        let reader = entry.reader()?;
+        let n = loop {
+            match reader.read(buf) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        };
+        *read = n;
+        Ok(PnaStatus::Ok)
+    })
+}
+
+/// Reads the rest of the content of the entry, passing it to `callback` in pieces with `user_data`.
+/// Returns [`PnaStatus::ErrorAborted`] if `callback` returns non-zero.
+///
+/// # Safety
+///
+/// `entry` must be a handle returned by [`pna_archive_next_entry`], and `callback` must be
+/// safe to call with `user_data`.
+#[no_mangle]
+pub unsafe extern "C" fn pna_entry_read_with(
+    entry: *mut PnaEntry,
+    callback: PnaWriteCallback,
+    user_data: *mut c_void,
+) -> PnaStatus {
+    guard(|| {
+        let entry = entry.as_mut().ok_or_else(|| Error::null("entry"))?;
+        let callback = callback.ok_or_else(|| Error::null("callback"))?;
+        let reader = entry.reader()?;
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => return Ok(PnaStatus::Ok),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let code = callback(user_data, buf.as_ptr(), n);
+            if code != 0 {
+                return Err(Error::new(
+                    PnaStatus::ErrorAborted,
+                    format!("callback returned {code}"),
+                ));
+            }
+        }
+    })
+}
+
+/// Releases the entry handle. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `entry` must be `NULL` or a handle returned by [`pna_archive_next_entry`] that is not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn pna_entry_free(entry: *mut PnaEntry) {
+    if !entry.is_null() {
+        drop(Box::from_raw(entry));
+    }
+}
+
+/// Returns the message of the last error on the calling thread as a null-terminated string,
+/// or `NULL` if no error occurred. It is valid until the next failing call on the thread.
+#[no_mangle]
+pub extern "C" fn pna_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|it| it.borrow().as_ref().map_or(ptr::null(), |it| it.as_ptr()))
+}
@@ -0,0 +1,391 @@
+//! Drives the C ABI only through `extern "C"` declarations, as a C program does.
+use libpna::{
+    Archive, CipherMode, Compression, Encryption, EntryBuilder, HashAlgorithm, SolidEntryBuilder,
+    WriteOptions,
+};
+use pna_capi as _;
+use std::{
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    fs,
+    io::Write,
+    ptr,
+    time::Duration,
+};
+
+#[repr(C)]
+struct PnaArchive {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct PnaEntry {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct PnaEntryStat {
+    kind: c_int,
+    compressed_size: u64,
+    raw_size: u64,
+    has_raw_size: bool,
+    mtime_sec: u64,
+    mtime_nsec: u32,
+    has_mtime: bool,
+}
+
+const PNA_STATUS_OK: c_int = 0;
+const PNA_STATUS_END: c_int = 1;
+const PNA_STATUS_ERROR_INVALID_ARGUMENT: c_int = -1;
+const PNA_STATUS_ERROR_IO: c_int = -2;
+const PNA_STATUS_ERROR_INVALID_DATA: c_int = -3;
+const PNA_STATUS_ERROR_ABORTED: c_int = -5;
+
+const PNA_ENTRY_KIND_FILE: c_int = 0;
+const PNA_ENTRY_KIND_DIRECTORY: c_int = 1;
+const PNA_ENTRY_KIND_SYMBOLIC_LINK: c_int = 2;
+
+type PnaWriteCallback =
+    Option<unsafe extern "C" fn(user_data: *mut c_void, data: *const u8, len: usize) -> c_int>;
+
+extern "C" {
+    fn pna_archive_open_path(path: *const c_char, out: *mut *mut PnaArchive) -> c_int;
+    fn pna_archive_open_memory(data: *const u8, len: usize, out: *mut *mut PnaArchive) -> c_int;
+    fn pna_archive_set_password(archive: *mut PnaArchive, password: *const c_char) -> c_int;
+    fn pna_archive_next_entry(archive: *mut PnaArchive, out: *mut *mut PnaEntry) -> c_int;
+    fn pna_archive_free(archive: *mut PnaArchive);
+    fn pna_entry_name(entry: *const PnaEntry) -> *const c_char;
+    fn pna_entry_stat(entry: *const PnaEntry, out: *mut PnaEntryStat) -> c_int;
+    fn pna_entry_read(entry: *mut PnaEntry, buf: *mut u8, len: usize, read: *mut usize) -> c_int;
+    fn pna_entry_read_with(
+        entry: *mut PnaEntry,
+        callback: PnaWriteCallback,
+        user_data: *mut c_void,
+    ) -> c_int;
+    fn pna_entry_free(entry: *mut PnaEntry);
+    fn pna_last_error_message() -> *const c_char;
+}
+
+const PASSWORD: &str = "password";
+
+fn file_entry(name: &str, content: &[u8], option: WriteOptions) -> libpna::NormalEntry {
+    let mut builder = EntryBuilder::new_file(name.into(), option).unwrap();
+    builder.modified(Duration::from_secs(1_000_000_000));
+    builder.write_all(content).unwrap();
+    builder.build().unwrap()
+}
+
+fn encrypted() -> WriteOptions {
+    WriteOptions::builder()
+        .encryption(Encryption::Aes)
+        .cipher_mode(CipherMode::CTR)
+        .hash_algorithm(HashAlgorithm::pbkdf2_sha256_with(Some(1)))
+        .password(Some(PASSWORD))
+        .build()
+}
+
+fn archive() -> Vec<u8> {
+    let mut archive = Archive::write_header(Vec::new()).unwrap();
+    archive
+        .add_entry(file_entry(
+            "text.txt",
+            b"text file\n",
+            WriteOptions::store(),
+        ))
+        .unwrap();
+    archive
+        .add_entry(EntryBuilder::new_dir("dir".into()).build().unwrap())
+        .unwrap();
+    archive
+        .add_entry(
+            EntryBuilder::new_symbolic_link("link.txt".into(), "text.txt".into())
+                .unwrap()
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    let mut solid = SolidEntryBuilder::new(WriteOptions::builder().build()).unwrap();
+    solid
+        .add_entry(file_entry(
+            "dir/solid.txt",
+            b"solid file\n",
+            WriteOptions::store(),
+        ))
+        .unwrap();
+    archive.add_entry(solid.build().unwrap()).unwrap();
+    archive
+        .add_entry(file_entry("secret.txt", b"secret file\n", encrypted()))
+        .unwrap();
+    let large = vec![0x55; 200 * 1024];
+    archive
+        .add_entry(file_entry(
+            "large.bin",
+            &large,
+            WriteOptions::builder()
+                .compression(Compression::ZStandard)
+                .build(),
+        ))
+        .unwrap();
+    archive.finalize().unwrap()
+}
+
+fn last_error() -> String {
+    let message = unsafe { pna_last_error_message() };
+    assert!(!message.is_null());
+    unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+struct Archived {
+    name: String,
+    stat: PnaEntryStat,
+    content: Vec<u8>,
+}
+
+/// Reads all the entries, reading the contents in `buf_len` bytes.
+unsafe fn read_all(archive: *mut PnaArchive, buf_len: usize) -> Vec<Archived> {
+    let mut entries = Vec::new();
+    loop {
+        let mut entry = ptr::null_mut();
+        match pna_archive_next_entry(archive, &mut entry) {
+            PNA_STATUS_OK => (),
+            PNA_STATUS_END => return entries,
+            status => panic!("{status}: {}", last_error()),
+        }
+        let name = CStr::from_ptr(pna_entry_name(entry))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let mut stat = PnaEntryStat::default();
+        assert_eq!(pna_entry_stat(entry, &mut stat), PNA_STATUS_OK);
+        let mut content = Vec::new();
+        let mut buf = vec![0; buf_len];
+        loop {
+            let mut read = 0;
+            let status = pna_entry_read(entry, buf.as_mut_ptr(), buf.len(), &mut read);
+            assert_eq!(status, PNA_STATUS_OK, "{name}: {}", last_error());
+            if read == 0 {
+                break;
+            }
+            content.extend_from_slice(&buf[..read]);
+        }
+        pna_entry_free(entry);
+        entries.push(Archived {
+            name,
+            stat,
+            content,
+        });
+    }
+}
+
+#[test]
+fn read_entries_from_memory() {
+    let bytes = archive();
+    unsafe {
+        let mut archive = ptr::null_mut();
+        assert_eq!(
+            pna_archive_open_memory(bytes.as_ptr(), bytes.len(), &mut archive),
+            PNA_STATUS_OK
+        );
+        let password = CString::new(PASSWORD).unwrap();
+        assert_eq!(
+            pna_archive_set_password(archive, password.as_ptr()),
+            PNA_STATUS_OK
+        );
+        let entries = read_all(archive, 7);
+        // NOTE: The end is reported again once reached.
+        let mut entry = ptr::null_mut();
+        assert_eq!(pna_archive_next_entry(archive, &mut entry), PNA_STATUS_END);
+        pna_archive_free(archive);
+
+        let names = entries
+            .iter()
+            .map(|it| it.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "text.txt",
+                "dir",
+                "link.txt",
+                "dir/solid.txt",
+                "secret.txt",
+                "large.bin"
+            ]
+        );
+        let kinds = entries.iter().map(|it| it.stat.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                PNA_ENTRY_KIND_FILE,
+                PNA_ENTRY_KIND_DIRECTORY,
+                PNA_ENTRY_KIND_SYMBOLIC_LINK,
+                PNA_ENTRY_KIND_FILE,
+                PNA_ENTRY_KIND_FILE,
+                PNA_ENTRY_KIND_FILE,
+            ]
+        );
+        assert_eq!(entries[0].content, b"text file\n");
+        assert_eq!(entries[2].content, b"text.txt");
+        assert_eq!(entries[3].content, b"solid file\n");
+        assert_eq!(entries[4].content, b"secret file\n");
+        assert_eq!(entries[5].content, vec![0x55; 200 * 1024]);
+
+        let stat = &entries[0].stat;
+        assert!(stat.has_mtime);
+        assert_eq!((stat.mtime_sec, stat.mtime_nsec), (1_000_000_000, 0));
+        assert!(stat.has_raw_size);
+        assert_eq!(stat.raw_size, 10);
+        assert_eq!(stat.compressed_size, 10);
+        assert!(!entries[1].stat.has_mtime);
+        assert!(entries[5].stat.compressed_size < entries[5].stat.raw_size);
+    }
+}
+
+#[test]
+fn read_entries_from_path() {
+    let bytes = archive();
+    let path = format!("{}/capi_read_from_path.pna", env!("CARGO_TARGET_TMPDIR"));
+    fs::write(&path, &bytes).unwrap();
+    let path = CString::new(path).unwrap();
+    unsafe {
+        let mut archive = ptr::null_mut();
+        assert_eq!(
+            pna_archive_open_path(path.as_ptr(), &mut archive),
+            PNA_STATUS_OK
+        );
+        let password = CString::new(PASSWORD).unwrap();
+        pna_archive_set_password(archive, password.as_ptr());
+        let entries = read_all(archive, 64 * 1024);
+        pna_archive_free(archive);
+        assert_eq!(entries.len(), 6);
+        assert_eq!(entries[4].content, b"secret file\n");
+    }
+}
+
+unsafe extern "C" fn collect(user_data: *mut c_void, data: *const u8, len: usize) -> c_int {
+    let out = &mut *user_data.cast::<Vec<u8>>();
+    out.extend_from_slice(std::slice::from_raw_parts(data, len));
+    0
+}
+
+unsafe extern "C" fn abort(_user_data: *mut c_void, _data: *const u8, _len: usize) -> c_int {
+    1
+}
+
+#[test]
+fn read_content_with_callback() {
+    let bytes = archive();
+    unsafe {
+        let mut archive = ptr::null_mut();
+        pna_archive_open_memory(bytes.as_ptr(), bytes.len(), &mut archive);
+        let mut entry = ptr::null_mut();
+        assert_eq!(pna_archive_next_entry(archive, &mut entry), PNA_STATUS_OK);
+
+        // NOTE: The callback receives the rest of the content.
+        let mut head = [0; 5];
+        let mut read = 0;
+        pna_entry_read(entry, head.as_mut_ptr(), head.len(), &mut read);
+        assert_eq!(&head[..read], b"text ");
+        let mut rest = Vec::<u8>::new();
+        let status = pna_entry_read_with(entry, Some(collect), (&mut rest as *mut Vec<u8>).cast());
+        assert_eq!(status, PNA_STATUS_OK);
+        assert_eq!(rest, b"file\n");
+        pna_entry_free(entry);
+
+        pna_archive_next_entry(archive, &mut entry);
+        pna_entry_free(entry);
+        pna_archive_next_entry(archive, &mut entry);
+        let status = pna_entry_read_with(entry, Some(abort), ptr::null_mut());
+        assert_eq!(status, PNA_STATUS_ERROR_ABORTED);
+        assert_eq!(last_error(), "callback returned 1");
+        pna_entry_free(entry);
+        pna_archive_free(archive);
+    }
+}
+
+#[test]
+fn entry_outlives_archive() {
+    let bytes = archive();
+    unsafe {
+        let mut archive = ptr::null_mut();
+        pna_archive_open_memory(bytes.as_ptr(), bytes.len(), &mut archive);
+        let mut entry = ptr::null_mut();
+        pna_archive_next_entry(archive, &mut entry);
+        pna_archive_free(archive);
+
+        let mut content = Vec::<u8>::new();
+        let status =
+            pna_entry_read_with(entry, Some(collect), (&mut content as *mut Vec<u8>).cast());
+        assert_eq!(status, PNA_STATUS_OK);
+        assert_eq!(content, b"text file\n");
+        pna_entry_free(entry);
+    }
+}
+
+#[test]
+fn errors() {
+    let bytes = archive();
+    unsafe {
+        let mut archive = ptr::null_mut();
+        assert_eq!(
+            pna_archive_open_memory(ptr::null(), 0, &mut archive),
+            PNA_STATUS_ERROR_INVALID_ARGUMENT
+        );
+        assert_eq!(last_error(), "`data` is null");
+        assert_eq!(
+            pna_archive_open_memory(b"not an archive".as_ptr(), 14, &mut archive),
+            PNA_STATUS_ERROR_INVALID_DATA
+        );
+        let missing = CString::new("missing/archive.pna").unwrap();
+        assert_eq!(
+            pna_archive_open_path(missing.as_ptr(), &mut archive),
+            PNA_STATUS_ERROR_IO
+        );
+        assert!(pna_entry_name(ptr::null()).is_null());
+
+        // NOTE: The encrypted entry can not be read without the password.
+        pna_archive_open_memory(bytes.as_ptr(), bytes.len(), &mut archive);
+        let mut entry = ptr::null_mut();
+        loop {
+            assert_eq!(pna_archive_next_entry(archive, &mut entry), PNA_STATUS_OK);
+            if CStr::from_ptr(pna_entry_name(entry)).to_bytes() == b"secret.txt" {
+                break;
+            }
+            pna_entry_free(entry);
+        }
+        let mut buf = [0; 16];
+        let mut read = 0;
+        assert_eq!(
+            pna_entry_read(entry, buf.as_mut_ptr(), buf.len(), &mut read),
+            PNA_STATUS_ERROR_INVALID_ARGUMENT
+        );
+        assert!(!last_error().is_empty());
+        pna_entry_free(entry);
+        pna_archive_free(archive);
+
+        // NOTE: A wrong password does not decrypt the entry.
+        pna_archive_open_memory(bytes.as_ptr(), bytes.len(), &mut archive);
+        let wrong = CString::new("wrong").unwrap();
+        pna_archive_set_password(archive, wrong.as_ptr());
+        let entries = (0..5)
+            .map(|_| {
+                let mut entry = ptr::null_mut();
+                pna_archive_next_entry(archive, &mut entry);
+                entry
+            })
+            .collect::<Vec<_>>();
+        let mut content = Vec::<u8>::new();
+        let status = pna_entry_read_with(
+            entries[4],
+            Some(collect),
+            (&mut content as *mut Vec<u8>).cast(),
+        );
+        assert!(status != PNA_STATUS_OK || content != b"secret file\n");
+        for entry in entries {
+            pna_entry_free(entry);
+        }
+        pna_archive_free(archive);
+    }
+}
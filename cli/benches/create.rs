@@ -3,8 +3,44 @@ extern crate test;
 
 use clap::Parser;
 use portable_network_archive::{cli, command};
+use std::{fs, sync::OnceLock};
 use test::Bencher;
 
+/// Creates a directory of 256 medium sized files once, and returns its path.
+fn medium_files() -> &'static str {
+    static DIR: OnceLock<String> = OnceLock::new();
+    DIR.get_or_init(|| {
+        let dir = format!("{}/bench/medium_files", env!("CARGO_TARGET_TMPDIR"));
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..256u32 {
+            let content = (0..256 * 1024u32)
+                .map(|j| (j.wrapping_mul(i + 1) % 251) as u8)
+                .collect::<Vec<_>>();
+            fs::write(format!("{dir}/file{i}.bin"), content).unwrap();
+        }
+        dir
+    })
+}
+
+fn zstd_medium_files(threads: &str) {
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "--threads",
+        threads,
+        "c",
+        &format!(
+            "{}/bench/zstd_medium_files.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--zstd",
+        "--overwrite",
+        "-r",
+        medium_files(),
+    ]))
+    .unwrap()
+}
+
 #[bench]
 fn store(b: &mut Bencher) {
     b.iter(|| {
@@ -114,3 +150,13 @@ fn zstd_keep_permission(b: &mut Bencher) {
         .unwrap()
     })
 }
+
+#[bench]
+fn zstd_medium_files_single_thread(b: &mut Bencher) {
+    b.iter(|| zstd_medium_files("1"))
+}
+
+#[bench]
+fn zstd_medium_files_parallel(b: &mut Bencher) {
+    b.iter(|| zstd_medium_files("0"))
+}
@@ -0,0 +1,47 @@
+#![feature(test)]
+extern crate test;
+
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{fs, io, sync::OnceLock};
+use test::Bencher;
+
+/// Creates an archive of 1M empty entries with synthetic names once, and returns its path.
+fn synthetic_archive() -> &'static str {
+    static ARCHIVE: OnceLock<String> = OnceLock::new();
+    ARCHIVE.get_or_init(|| {
+        let path = format!("{}/glob/synthetic.pna", env!("CARGO_TARGET_TMPDIR"));
+        fs::create_dir_all(format!("{}/glob", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+        let file = io::BufWriter::new(fs::File::create(&path).unwrap());
+        let mut writer = Archive::write_header(file).unwrap();
+        for i in 0..1_000_000 {
+            let name = format!("dir{}/sub{}/file{i}.txt", i % 1000, i % 7);
+            let builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+            writer.add_entry(builder.build().unwrap()).unwrap();
+        }
+        writer.finalize().unwrap();
+        path
+    })
+}
+
+fn list(patterns: impl Iterator<Item = String>) {
+    let archive = synthetic_archive();
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet", "ls", archive]
+            .into_iter()
+            .map(String::from)
+            .chain(patterns),
+    ))
+    .unwrap()
+}
+
+#[bench]
+fn prefixed_patterns(b: &mut Bencher) {
+    b.iter(|| list((0..100).map(|i| format!("dir{i}/sub3/*.txt"))))
+}
+
+#[bench]
+fn unprefixed_patterns(b: &mut Bencher) {
+    b.iter(|| list((0..100).map(|i| format!("*/sub{}/file{i}.txt", i % 7))))
+}
@@ -2,9 +2,37 @@
 extern crate test;
 
 use clap::Parser;
+use pna::{Archive, WriteOptions};
 use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    io::{self, Read},
+    sync::OnceLock,
+};
 use test::Bencher;
 
+/// Creates an archive of 16 files of 64 MiB once, and returns its path.
+fn large_archive() -> &'static str {
+    static ARCHIVE: OnceLock<String> = OnceLock::new();
+    ARCHIVE.get_or_init(|| {
+        let path = format!("{}/bench/large.pna", env!("CARGO_TARGET_TMPDIR"));
+        fs::create_dir_all(format!("{}/bench", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+        let file = io::BufWriter::new(fs::File::create(&path).unwrap());
+        let mut archive = Archive::write_header(file).unwrap();
+        for i in 0..16u8 {
+            archive
+                .add_entry_from_reader(
+                    format!("file{i}.bin").into(),
+                    io::repeat(i).take(64 * 1024 * 1024),
+                    WriteOptions::store(),
+                )
+                .unwrap();
+        }
+        archive.finalize().unwrap();
+        path
+    })
+}
+
 #[bench]
 fn normal(b: &mut Bencher) {
     b.iter(|| {
@@ -31,3 +59,14 @@ fn solid(b: &mut Bencher) {
         .unwrap()
     })
 }
+
+#[bench]
+fn large_data(b: &mut Bencher) {
+    let archive = large_archive();
+    b.iter(|| {
+        command::entry(cli::Cli::parse_from([
+            "pna", "--quiet", "ls", "-l", archive,
+        ]))
+        .unwrap()
+    })
+}
@@ -1,3 +1,37 @@
 mod acl;
+mod platform;
+mod signature;
 
 pub use acl::*;
+pub use platform::*;
+pub use signature::*;
+
+use pna::ChunkType;
+
+/// Chunk types read and written by this program.
+pub(crate) const KNOWN_CHUNK_TYPES: [ChunkType; 24] = [
+    ChunkType::AHED,
+    ChunkType::AEND,
+    ChunkType::ANXT,
+    ChunkType::FHED,
+    ChunkType::PHSF,
+    ChunkType::WKEY,
+    ChunkType::FDAT,
+    ChunkType::FEND,
+    ChunkType::SHED,
+    ChunkType::SDAT,
+    ChunkType::SEND,
+    ChunkType::aIDN,
+    ChunkType::fSIZ,
+    ChunkType::cTIM,
+    ChunkType::mTIM,
+    ChunkType::aTIM,
+    ChunkType::fPRM,
+    ChunkType::xATR,
+    ChunkType::xATZ,
+    faCe,
+    faCl,
+    fsIG,
+    asIG,
+    fPLT,
+];
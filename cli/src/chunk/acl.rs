@@ -2,6 +2,7 @@ use bitflags::bitflags;
 use itertools::Itertools;
 use pna::ChunkType;
 use std::{
+    collections::HashMap,
     error::Error,
     fmt::{self, Display, Formatter},
     str::{from_utf8, FromStr, Utf8Error},
@@ -22,6 +23,8 @@ pub enum AcePlatform {
     MacOs,
     Linux,
     FreeBSD,
+    NetBSD,
+    Illumos,
     Unknown(String),
 }
 
@@ -34,10 +37,16 @@ impl AcePlatform {
     pub const CURRENT: Self = Self::Linux;
     #[cfg(target_os = "freebsd")]
     pub const CURRENT: Self = Self::FreeBSD;
+    #[cfg(target_os = "netbsd")]
+    pub const CURRENT: Self = Self::NetBSD;
+    #[cfg(target_os = "illumos")]
+    pub const CURRENT: Self = Self::Illumos;
     #[cfg(not(any(
         target_os = "macos",
         target_os = "linux",
         target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "illumos",
         windows
     )))]
     pub const CURRENT: Self = Self::General;
@@ -52,6 +61,8 @@ impl Display for AcePlatform {
             Self::MacOs => f.write_str("macos"),
             Self::Linux => f.write_str("linux"),
             Self::FreeBSD => f.write_str("freebsd"),
+            Self::NetBSD => f.write_str("netbsd"),
+            Self::Illumos => f.write_str("illumos"),
             Self::Unknown(s) => f.write_str(s),
         }
     }
@@ -75,6 +86,8 @@ impl FromStr for AcePlatform {
             "macos" => Ok(Self::MacOs),
             "linux" => Ok(Self::Linux),
             "freebsd" => Ok(Self::FreeBSD),
+            "netbsd" => Ok(Self::NetBSD),
+            "illumos" => Ok(Self::Illumos),
             s => Ok(Self::Unknown(s.into())),
         }
     }
@@ -363,6 +376,19 @@ pub fn acl_convert_current_platform(src: Acl) -> Acl {
     ace_convert_platform(src, &platform)
 }
 
+/// Picks the ACL of an entry to restore on the current platform and converts it for the platform.
+///
+/// The ACL of [AcePlatform::CURRENT] is preferred, then the [AcePlatform::General] one.
+#[cfg_attr(not(feature = "acl"), allow(dead_code))]
+pub(crate) fn acl_for_current_platform(mut acls: HashMap<AcePlatform, Vec<Ace>>) -> Option<Acl> {
+    let platform = [AcePlatform::CURRENT, AcePlatform::General]
+        .into_iter()
+        .find(|it| acls.contains_key(it))
+        .or_else(|| acls.keys().min().cloned())?;
+    let entries = acls.remove(&platform)?;
+    Some(acl_convert_current_platform(Acl { platform, entries }))
+}
+
 pub fn ace_convert_platform(src: Acl, to: &AcePlatform) -> Acl {
     match to {
         AcePlatform::General | AcePlatform::Unknown(_) => ace_to_generic(src),
@@ -370,6 +396,7 @@ pub fn ace_convert_platform(src: Acl, to: &AcePlatform) -> Acl {
         AcePlatform::MacOs => ace_to_macos(src),
         AcePlatform::Linux => ace_to_linux(src),
         AcePlatform::FreeBSD => ace_to_freebsd(src),
+        AcePlatform::NetBSD | AcePlatform::Illumos => ace_to_posix(src, to),
     }
 }
 
@@ -446,7 +473,8 @@ fn ace_to_generic(src: Acl) -> Acl {
                 })
                 .collect(),
         },
-        AcePlatform::Linux => Acl {
+        // NOTE: NetBSD and illumos have the POSIX.1e ACLs as Linux does.
+        AcePlatform::Linux | AcePlatform::NetBSD | AcePlatform::Illumos => Acl {
             platform: AcePlatform::General,
             entries: src
                 .entries
@@ -544,6 +572,8 @@ fn ace_to_windows(src: Acl) -> Acl {
         | AcePlatform::MacOs
         | AcePlatform::Linux
         | AcePlatform::FreeBSD
+        | AcePlatform::NetBSD
+        | AcePlatform::Illumos
         | AcePlatform::Unknown(_) => {
             let src = ace_to_generic(src);
             Acl {
@@ -567,17 +597,43 @@ fn ace_to_windows(src: Acl) -> Acl {
 }
 
 fn ace_to_linux(src: Acl) -> Acl {
-    match src.platform {
-        AcePlatform::Linux => src,
-        AcePlatform::General
-        | AcePlatform::Windows
-        | AcePlatform::MacOs
-        | AcePlatform::FreeBSD
-        | AcePlatform::Unknown(_) => {
-            let mut src = ace_to_generic(src);
-            src.platform = AcePlatform::Linux;
-            src
-        }
+    ace_to_posix(src, &AcePlatform::Linux)
+}
+
+/// Converts an ACL to the POSIX.1e ACL of `platform`, which is Linux, NetBSD or illumos.
+fn ace_to_posix(src: Acl, platform: &AcePlatform) -> Acl {
+    if &src.platform == platform {
+        return src;
+    }
+    Acl {
+        platform: platform.clone(),
+        // NOTE: POSIX.1e ACLs express inheritance by default ACL entries,
+        //       so inheritable entries are converted to default entries in addition to access entries.
+        entries: src
+            .entries
+            .into_iter()
+            .flat_map(|ace| {
+                let inherit_only = ace.flags.intersects(Flag::DEFAULT | Flag::ONLY_INHERIT);
+                let inheritable = inherit_only
+                    || ace
+                        .flags
+                        .intersects(Flag::FILE_INHERIT | Flag::DIRECTORY_INHERIT);
+                let permission = to_general_permission(ace.permission);
+                let access = (!inherit_only).then(|| Ace {
+                    flags: Flag::empty(),
+                    owner_type: ace.owner_type.clone(),
+                    allow: ace.allow,
+                    permission,
+                });
+                let default = inheritable.then_some(Ace {
+                    flags: Flag::DEFAULT,
+                    owner_type: ace.owner_type,
+                    allow: ace.allow,
+                    permission,
+                });
+                access.into_iter().chain(default)
+            })
+            .collect(),
     }
 }
 
@@ -614,6 +670,8 @@ fn ace_to_macos(src: Acl) -> Acl {
         | AcePlatform::Windows
         | AcePlatform::Linux
         | AcePlatform::FreeBSD
+        | AcePlatform::NetBSD
+        | AcePlatform::Illumos
         | AcePlatform::Unknown(_) => {
             let src = ace_to_generic(src);
             Acl {
@@ -643,6 +701,8 @@ fn ace_to_freebsd(src: Acl) -> Acl {
         | AcePlatform::Windows
         | AcePlatform::MacOs
         | AcePlatform::Linux
+        | AcePlatform::NetBSD
+        | AcePlatform::Illumos
         | AcePlatform::Unknown(_) => {
             let mut src = ace_to_generic(src);
             src.platform = AcePlatform::FreeBSD;
@@ -747,4 +807,99 @@ mod tests {
         };
         assert_eq!(Ace::from_str(&ace.to_string()), Ok(ace));
     }
+
+    #[test]
+    fn inheritable_ace_to_linux_default() {
+        let ace = |flags| Ace {
+            flags,
+            owner_type: OwnerType::OwnerGroup,
+            allow: true,
+            permission: Permission::READ,
+        };
+        let acl = ace_to_linux(Acl {
+            platform: AcePlatform::MacOs,
+            entries: vec![
+                ace(Flag::empty()),
+                ace(Flag::FILE_INHERIT | Flag::DIRECTORY_INHERIT),
+                ace(Flag::FILE_INHERIT | Flag::ONLY_INHERIT),
+            ],
+        });
+        assert_eq!(
+            acl.entries,
+            [
+                ace(Flag::empty()),
+                ace(Flag::empty()),
+                ace(Flag::DEFAULT),
+                ace(Flag::DEFAULT),
+            ]
+        );
+
+        let acl = ace_to_linux(Acl {
+            platform: AcePlatform::General,
+            entries: vec![ace(Flag::DEFAULT)],
+        });
+        assert_eq!(acl.entries, [ace(Flag::DEFAULT)]);
+    }
+
+    #[test]
+    fn posix_platforms_to_string_from_str() {
+        for platform in [AcePlatform::NetBSD, AcePlatform::Illumos] {
+            assert_eq!(AcePlatform::from_str(&platform.to_string()), Ok(platform));
+        }
+    }
+
+    #[test]
+    fn linux_acl_to_other_posix_platforms() {
+        let ace = |flags| Ace {
+            flags,
+            owner_type: OwnerType::User(Identifier("user".into())),
+            allow: true,
+            permission: Permission::READ | Permission::WRITE,
+        };
+        let linux = Acl {
+            platform: AcePlatform::Linux,
+            entries: vec![ace(Flag::empty()), ace(Flag::DEFAULT)],
+        };
+        for platform in [AcePlatform::NetBSD, AcePlatform::Illumos] {
+            let acl = ace_convert_platform(linux.clone(), &platform);
+            assert_eq!(acl.platform, platform);
+            assert_eq!(acl.entries, linux.entries);
+            assert_eq!(ace_convert_platform(acl, &AcePlatform::Linux), linux);
+        }
+    }
+
+    #[test]
+    fn acl_for_current_platform_prefers_general_to_others() {
+        let ace = |permission| Ace {
+            flags: Flag::empty(),
+            owner_type: OwnerType::Owner,
+            allow: true,
+            permission,
+        };
+        let other = if AcePlatform::CURRENT == AcePlatform::Windows {
+            AcePlatform::MacOs
+        } else {
+            AcePlatform::Windows
+        };
+        let mut acls = HashMap::from([
+            (other, vec![ace(Permission::READ)]),
+            (AcePlatform::General, vec![ace(Permission::EXECUTE)]),
+        ]);
+        let acl = acl_for_current_platform(acls.clone()).unwrap();
+        assert_eq!(
+            acl.entries,
+            acl_convert_current_platform(Acl {
+                platform: AcePlatform::General,
+                entries: vec![ace(Permission::EXECUTE)],
+            })
+            .entries
+        );
+
+        acls.insert(AcePlatform::CURRENT, vec![ace(Permission::WRITE)]);
+        let acl = acl_for_current_platform(acls).unwrap();
+        assert_eq!(acl.platform, AcePlatform::CURRENT);
+        assert_eq!(acl.entries, [ace(Permission::WRITE)]);
+
+        assert_eq!(acl_for_current_platform(HashMap::new()), None);
+    }
 }
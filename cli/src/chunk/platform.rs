@@ -0,0 +1,108 @@
+use pna::ChunkType;
+use std::{
+    fmt::{self, Display, Formatter},
+    str::{from_utf8, FromStr, Utf8Error},
+};
+
+/// [ChunkType] File Platforms, the operating systems the entry is intended for
+#[allow(non_upper_case_globals)]
+pub const fPLT: ChunkType = unsafe { ChunkType::from_unchecked(*b"fPLT") };
+
+/// Operating systems an entry is intended for, named as [std::env::consts::OS].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Platforms(Vec<String>);
+
+impl Platforms {
+    /// Returns `true` if the entry is intended for the operating system the program runs on.
+    #[inline]
+    pub(crate) fn includes_current(&self) -> bool {
+        self.includes(std::env::consts::OS)
+    }
+
+    #[inline]
+    pub(crate) fn includes(&self, os: &str) -> bool {
+        self.0.iter().any(|it| it == os)
+    }
+
+    /// Adds the operating systems of `other` that are not included yet.
+    #[inline]
+    pub(crate) fn merge(&mut self, other: &Self) {
+        self.0.extend(other.0.iter().cloned());
+        self.0.sort_unstable();
+        self.0.dedup();
+    }
+
+    #[inline]
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    #[inline]
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        self.to_string().into_bytes()
+    }
+}
+
+impl Display for Platforms {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join(","))
+    }
+}
+
+impl FromStr for Platforms {
+    type Err = String;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut platforms = s.split(',').map(str::trim).collect::<Vec<_>>();
+        if platforms.iter().any(|it| it.is_empty()) {
+            return Err(format!("invalid platform list `{s}`"));
+        }
+        platforms.sort_unstable();
+        platforms.dedup();
+        Ok(Self(platforms.into_iter().map(String::from).collect()))
+    }
+}
+
+impl TryFrom<&[u8]> for Platforms {
+    type Error = Utf8Error;
+
+    #[inline]
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        // NOTE: Unknown or malformed names are kept as they are, since they never match the current platform.
+        Ok(Self(
+            from_utf8(value)?
+                .split(',')
+                .filter(|it| !it.is_empty())
+                .map(String::from)
+                .collect(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_platforms() {
+        let platforms = Platforms::from_str("macos, linux,linux").unwrap();
+        assert_eq!(platforms.to_string(), "linux,macos");
+        assert!(platforms.includes("linux"));
+        assert!(!platforms.includes("windows"));
+        assert!(Platforms::from_str("linux,").is_err());
+        assert!(Platforms::from_str("").is_err());
+    }
+
+    #[test]
+    fn platforms_round_trip() {
+        let mut platforms = Platforms::from_str("windows").unwrap();
+        platforms.merge(&Platforms::from_str("linux,windows").unwrap());
+        assert_eq!(
+            Platforms::try_from(platforms.to_bytes().as_slice()).unwrap(),
+            platforms
+        );
+        assert_eq!(platforms.iter().collect::<Vec<_>>(), ["linux", "windows"]);
+    }
+}
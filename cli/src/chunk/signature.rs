@@ -0,0 +1,9 @@
+use pna::ChunkType;
+
+/// [ChunkType] File Signature, an Ed25519 signature of the entry
+#[allow(non_upper_case_globals)]
+pub const fsIG: ChunkType = unsafe { ChunkType::from_unchecked(*b"fsIG") };
+
+/// [ChunkType] Archive Signature, an Ed25519 signature of the digests of all entries
+#[allow(non_upper_case_globals)]
+pub const asIG: ChunkType = unsafe { ChunkType::from_unchecked(*b"asIG") };
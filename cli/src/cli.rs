@@ -1,12 +1,22 @@
-use crate::command::{
-    append::AppendCommand, complete::CompleteCommand, concat::ConcatCommand, create::CreateCommand,
-    experimental::ExperimentalCommand, extract::ExtractCommand, list::ListCommand,
-    split::SplitCommand, strip::StripCommand,
+mod config;
+
+use crate::{
+    command::{
+        append::AppendCommand, complete::CompleteCommand, concat::ConcatCommand,
+        create::CreateCommand, experimental::ExperimentalCommand, extract::ExtractCommand,
+        list::ListCommand, split::SplitCommand, strip::StripCommand, version::VersionCommand,
+    },
+    exit_code::usage_error,
+    utils::{
+        self,
+        id_map::{IdRange, Unmapped},
+        PathFilter,
+    },
 };
 use clap::{value_parser, ArgGroup, Parser, Subcommand, ValueEnum, ValueHint};
 use log::{Level, LevelFilter};
 use pna::{ChunkType, ChunkTypeError, HashAlgorithm};
-use std::{io, path::PathBuf, str::FromStr};
+use std::{env, fs, io, path::PathBuf, str::FromStr};
 
 #[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
 #[command(
@@ -23,9 +33,44 @@ pub struct Cli {
     pub(crate) verbosity: VerbosityArgs,
     #[arg(long, global = true, help = "Declare to use unstable features")]
     pub(crate) unstable: bool,
+    #[arg(
+        long,
+        global = true,
+        default_value_t = 0,
+        help = "Number of threads to use for parallel processing (0 = auto)"
+    )]
+    pub(crate) threads: usize,
+    #[arg(
+        long,
+        global = true,
+        value_name = "PATH",
+        help = "Read default options from the given config file instead of ~/.config/pna/config.toml",
+        value_hint = ValueHint::FilePath
+    )]
+    pub(crate) config: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "config",
+        help = "Ignore default options in the config file and the PNA_OPTIONS environment variable"
+    )]
+    pub(crate) no_config: bool,
+    #[arg(skip)]
+    config_warnings: Vec<String>,
 }
 
 impl Cli {
+    /// Parses the command line arguments, filling in the options not given with the defaults
+    /// in the config file and the `PNA_OPTIONS` environment variable.
+    pub fn try_parse_with_defaults() -> Result<Self, clap::Error> {
+        let mut warnings = Vec::new();
+        let args = config::inject_defaults(env::args_os().collect(), &mut warnings)
+            .map_err(|e| clap::Error::raw(clap::error::ErrorKind::Io, format!("{e}\n")))?;
+        let mut cli = Self::try_parse_from(args)?;
+        cli.config_warnings = warnings;
+        Ok(cli)
+    }
+
     pub fn init_logger(&self) -> io::Result<()> {
         let level = self.verbosity.log_level_filter();
         let base = fern::Dispatch::new();
@@ -37,7 +82,11 @@ impl Cli {
                 Level::Info | Level::Debug | Level::Trace => out.finish(*msg),
             })
             .chain(io::stderr());
-        base.chain(stderr).apply().map_err(io::Error::other)
+        base.chain(stderr).apply().map_err(io::Error::other)?;
+        for warning in &self.config_warnings {
+            log::warn!("{warning}");
+        }
+        Ok(())
     }
 }
 
@@ -48,6 +97,12 @@ pub(crate) struct VerbosityArgs {
     quiet: bool,
     #[arg(long, global = true, help = "Make some output more verbose")]
     verbose: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Show a progress bar of the bytes processed by create and extract on stderr, only when stderr is a terminal"
+    )]
+    progress: bool,
 }
 
 impl VerbosityArgs {
@@ -60,6 +115,12 @@ impl VerbosityArgs {
             (_, _) => LevelFilter::Info,
         }
     }
+
+    /// Returns `true` if the progress bar is shown, which `--quiet` suppresses.
+    #[inline]
+    pub(crate) const fn progress(&self) -> bool {
+        self.progress && !self.quiet
+    }
 }
 
 #[derive(Subcommand, Clone, Eq, PartialEq, Hash, Debug)]
@@ -80,6 +141,8 @@ pub(crate) enum Commands {
     Strip(StripCommand),
     #[command(about = "Generate shell auto complete")]
     Complete(CompleteCommand),
+    #[command(about = "Print versions and supported format features")]
+    Version(VersionCommand),
     #[command(about = "Unstable experimental commands")]
     Experimental(ExperimentalCommand),
 }
@@ -92,8 +155,191 @@ pub(crate) struct FileArgs {
     pub(crate) files: Vec<String>,
 }
 
+/// Options to find the files to archive, shared by the commands that walk the filesystem.
+#[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[command(
+    group(ArgGroup::new("unstable-exclude").args(["exclude"]).requires("unstable")),
+    group(ArgGroup::new("unstable-files-from").args(["files_from"]).requires("unstable")),
+    group(ArgGroup::new("unstable-files-from-stdin").args(["files_from_stdin"]).requires("unstable")),
+    group(ArgGroup::new("unstable-exclude-from").args(["exclude_from"]).requires("unstable")),
+    group(ArgGroup::new("unstable-gitignore").args(["gitignore"]).requires("unstable")),
+    group(ArgGroup::new("unstable-nodump").args(["nodump"]).requires("unstable")),
+    group(ArgGroup::new("read-files-from").args(["files_from", "files_from_stdin"])),
+)]
+pub(crate) struct TraversalArgs {
+    #[arg(short, long, help = "Add the directory to the archive recursively")]
+    pub(crate) recursive: bool,
+    #[arg(long, help = "Read archiving files from given path (unstable)", value_hint = ValueHint::FilePath)]
+    pub(crate) files_from: Option<String>,
+    #[arg(long, help = "Read archiving files from stdin (unstable)")]
+    pub(crate) files_from_stdin: bool,
+    #[arg(long, help = "Exclude path glob (unstable)", value_hint = ValueHint::AnyPath)]
+    pub(crate) exclude: Option<Vec<String>>,
+    #[arg(long, help = "Read exclude path globs from given path, one per line (unstable)", value_hint = ValueHint::FilePath)]
+    pub(crate) exclude_from: Option<String>,
+    #[arg(long, help = "Ignore files from .gitignore (unstable)")]
+    pub(crate) gitignore: bool,
+    #[arg(long, help = "Exclude files with the nodump flag (unstable)")]
+    pub(crate) nodump: bool,
+    #[arg(long, help = "Follow symbolic links")]
+    pub(crate) follow_links: bool,
+}
+
+impl TraversalArgs {
+    /// Returns `files` followed by the files read from `--files-from` or `--files-from-stdin`.
+    #[inline]
+    pub(crate) fn files(&self, mut files: Vec<String>) -> io::Result<Vec<String>> {
+        if self.files_from_stdin {
+            files.extend(io::stdin().lines().collect::<io::Result<Vec<_>>>()?);
+        } else if let Some(path) = &self.files_from {
+            files.extend(utils::fs::read_to_lines(path)?);
+        }
+        Ok(files)
+    }
+
+    /// Returns the globs given by `--exclude` and `--exclude-from`, skipping blank lines of the file.
+    #[inline]
+    pub(crate) fn exclude_patterns(&self) -> io::Result<Vec<String>> {
+        let mut patterns = self.exclude.clone().unwrap_or_default();
+        if let Some(path) = &self.exclude_from {
+            patterns.extend(
+                utils::fs::read_to_lines(path)?
+                    .into_iter()
+                    .filter(|it| !it.trim().is_empty()),
+            );
+        }
+        Ok(patterns)
+    }
+}
+
+/// Options to filter the entries of an archive by their names.
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-#[command(group(ArgGroup::new("password_provider").args(["password", "password_file"])))]
+#[command(
+    group(ArgGroup::new("unstable-path-filter").args(["include", "exclude", "exclude_from", "exclude_vcs"]).multiple(true).requires("unstable")),
+)]
+pub(crate) struct PathFilterArgs {
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Process only the entries matching the given glob. This option can be specified multiple times (unstable)"
+    )]
+    pub(crate) include: Vec<String>,
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        help = "Skip the entries matching the given glob, even if they match the file arguments or --include. This option can be specified multiple times (unstable)"
+    )]
+    pub(crate) exclude: Vec<String>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read exclude globs from the given file, one per line (unstable)",
+        value_hint = ValueHint::FilePath
+    )]
+    pub(crate) exclude_from: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Skip the files and directories of the version control systems, such as `.git` and `.svn` (unstable)"
+    )]
+    pub(crate) exclude_vcs: bool,
+    #[arg(
+        long,
+        requires = "exclude_from",
+        help = "Read the globs of --exclude-from separated by null characters instead of newlines"
+    )]
+    pub(crate) null: bool,
+}
+
+impl PathFilterArgs {
+    /// Builds the filter of `--include`, `--exclude`, `--exclude-from` and `--exclude-vcs`,
+    /// skipping blank globs of the file.
+    #[inline]
+    pub(crate) fn path_filter(&self) -> io::Result<PathFilter> {
+        let mut exclude = self.exclude.clone();
+        if let Some(path) = &self.exclude_from {
+            if self.null {
+                let content = fs::read_to_string(path)?;
+                exclude.extend(content.split('\0').map(String::from));
+            } else {
+                exclude.extend(utils::fs::read_to_lines(path)?);
+            }
+            exclude.retain(|it| !it.trim().is_empty());
+        }
+        PathFilter::new(&self.include, exclude, self.exclude_vcs).map_err(usage_error)
+    }
+}
+
+/// Options to map the owner ids between this system and the archive, shared by create and extract.
+#[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[command(
+    group(ArgGroup::new("unstable-id-map").args(["uid_map", "gid_map", "unmapped", "map_names"]).multiple(true).requires("unstable")),
+)]
+pub(crate) struct IdMapArgs {
+    #[arg(
+        long,
+        value_name = "HOST_START:ARCHIVE_START:COUNT",
+        help = "Map the COUNT user ids from HOST_START on this system to the user ids from ARCHIVE_START in the archive. This option can be specified multiple times (unstable)"
+    )]
+    pub(crate) uid_map: Vec<IdRange>,
+    #[arg(
+        long,
+        value_name = "HOST_START:ARCHIVE_START:COUNT",
+        help = "Map the COUNT group ids from HOST_START on this system to the group ids from ARCHIVE_START in the archive. This option can be specified multiple times (unstable)"
+    )]
+    pub(crate) gid_map: Vec<IdRange>,
+    #[arg(
+        long,
+        value_name = "POLICY",
+        help = "How to map the ids outside every range of --uid-map and --gid-map, one of `error`, `keep` or `squash-to=ID` [default: error] (unstable)"
+    )]
+    pub(crate) unmapped: Option<Unmapped>,
+    #[arg(
+        long,
+        help = "Look up the owner names of the ids mapped by --uid-map and --gid-map, which are ignored by default since they may not correspond after mapping (unstable)"
+    )]
+    pub(crate) map_names: bool,
+}
+
+#[cfg(feature = "http")]
+#[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct HttpArgs {
+    #[arg(
+        long,
+        value_name = "NAME: VALUE",
+        help = "Add a header to the request when the archive is an http(s) URL. Can be given multiple times"
+    )]
+    pub(crate) http_header: Vec<HttpHeader>,
+}
+
+#[cfg(feature = "http")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct HttpHeader {
+    pub(crate) name: String,
+    pub(crate) value: String,
+}
+
+#[cfg(feature = "http")]
+impl FromStr for HttpHeader {
+    type Err = String;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| format!("header must be in the form of `NAME: VALUE`, but got `{s}`"))?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("header name must not be empty".into());
+        }
+        Ok(Self {
+            name: name.into(),
+            value: value.trim().into(),
+        })
+    }
+}
+
+#[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[command(group(ArgGroup::new("password_provider").args(["password", "password_file", "password_command"])))]
 pub(crate) struct PasswordArgs {
     #[arg(
         long,
@@ -102,6 +348,50 @@ pub(crate) struct PasswordArgs {
     pub(crate) password: Option<Option<String>>,
     #[arg(long, help = "Read password from specified file")]
     pub(crate) password_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        help = "Use the standard output of the command as the password, without one trailing newline. The command is run by `sh -c` (`cmd /C` on Windows)"
+    )]
+    pub(crate) password_command: Option<String>,
+}
+
+#[derive(Parser, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct FailFastArgs {
+    #[arg(
+        long,
+        overrides_with = "no_fail_fast",
+        help = "Stop at the first entry that fails (default)"
+    )]
+    fail_fast: bool,
+    #[arg(
+        long,
+        overrides_with = "fail_fast",
+        help = "Continue after entries that fail and exit with the partial success code (2)"
+    )]
+    no_fail_fast: bool,
+}
+
+impl FailFastArgs {
+    #[inline]
+    pub(crate) const fn fail_fast(&self) -> bool {
+        !self.no_fail_fast
+    }
+}
+
+#[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct ReplaceArgs {
+    #[arg(long, help = "Replace the output file even if it is not a PNA archive")]
+    pub(crate) force: bool,
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "~",
+        help = "Back up the existing output file to the name with SUFFIX appended before replacing it [SUFFIX default: ~]"
+    )]
+    pub(crate) backup: Option<String>,
 }
 
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -159,6 +449,11 @@ pub(crate) struct CompressionAlgorithmArgs {
 }
 
 impl CompressionAlgorithmArgs {
+    /// Returns `true` if a compression method is given explicitly.
+    pub(crate) const fn is_specified(&self) -> bool {
+        self.store || self.deflate.is_some() || self.zstd.is_some() || self.xz.is_some()
+    }
+
     pub(crate) fn algorithm(&self) -> (pna::Compression, Option<pna::CompressionLevel>) {
         if self.store {
             (pna::Compression::No, None)
@@ -184,6 +479,11 @@ pub(crate) struct CipherAlgorithmArgs {
 }
 
 impl CipherAlgorithmArgs {
+    /// Returns `true` if a cipher algorithm is given explicitly.
+    pub(crate) const fn is_specified(&self) -> bool {
+        self.aes.is_some() || self.camellia.is_some()
+    }
+
     pub(crate) fn algorithm(&self) -> pna::Encryption {
         if self.aes.is_some() {
             pna::Encryption::Aes
@@ -222,6 +522,11 @@ pub(crate) struct HashAlgorithmArgs {
 }
 
 impl HashAlgorithmArgs {
+    /// Returns `true` if a password hashing algorithm is given explicitly.
+    pub(crate) const fn is_specified(&self) -> bool {
+        self.argon2.is_some() || self.pbkdf2.is_some()
+    }
+
     pub(crate) fn algorithm(&self) -> HashAlgorithm {
         if let Some(Some(params)) = &self.pbkdf2 {
             HashAlgorithm::pbkdf2_sha256_with(params.rounds)
@@ -0,0 +1,306 @@
+//! Default options read from the config file and the `PNA_OPTIONS` environment variable.
+//!
+//! The defaults are injected into the command line arguments before parsing,
+//! skipping the options given on the command line and the options conflicting with them.
+//! The precedence is command line > `PNA_OPTIONS` > config file > builtin defaults.
+use super::Cli;
+use crate::exit_code::usage_error;
+use clap::{parser::ValueSource, Arg, ArgGroup, ArgMatches, Command, CommandFactory};
+use std::{
+    env,
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_ENV: &str = "PNA_CONFIG";
+const OPTIONS_ENV: &str = "PNA_OPTIONS";
+
+/// Keys of the config file that select one of the arguments in a group, such as `compression = "zstd"`.
+const GROUP_KEYS: &[(&str, &str)] = &[
+    ("compression", "compression_method"),
+    ("cipher", "cipher_algorithm"),
+    ("hash", "hash_algorithm"),
+];
+
+/// Default option resolved to an argument of the command.
+struct DefaultOption {
+    id: String,
+    args: Vec<OsString>,
+}
+
+/// Returns `args` with the default options of the subcommand appended.
+/// Warnings about the config file are pushed to `warnings`.
+pub(super) fn inject_defaults(
+    mut args: Vec<OsString>,
+    warnings: &mut Vec<String>,
+) -> io::Result<Vec<OsString>> {
+    let mut command = Cli::command();
+    // NOTE: Invalid arguments are reported by the parse after this.
+    let Ok(matches) = command.try_get_matches_from_mut(&args) else {
+        return Ok(args);
+    };
+    if matches.get_flag("no_config") {
+        return Ok(args);
+    }
+    let config_path = matches.get_one::<PathBuf>("config").cloned();
+    let mut names = Vec::new();
+    let (mut command, mut matches) = (&command, &matches);
+    while let Some((name, sub_matches)) = matches.subcommand() {
+        let Some(sub_command) = command.find_subcommand(name) else {
+            break;
+        };
+        names.push(name.to_string());
+        (command, matches) = (sub_command, sub_matches);
+    }
+    if names.is_empty() {
+        return Ok(args);
+    }
+    let mut defaults = Vec::new();
+    if let Some(config) = read_config(config_path)? {
+        defaults.extend(config_options(&config, &names, command, warnings));
+    }
+    if let Some(options) = env::var_os(OPTIONS_ENV) {
+        defaults.extend(env_options(&options.to_string_lossy(), command));
+    }
+    let defaults = resolve(defaults, command, matches);
+    let position = args.iter().position(|it| it == "--").unwrap_or(args.len());
+    args.splice(position..position, defaults);
+    Ok(args)
+}
+
+/// Default path of the config file, `~/.config/pna/config.toml`.
+fn default_config_path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+                .map(|home| Path::new(&home).join(".config"))
+        })
+        .map(|it| it.join("pna").join("config.toml"))
+}
+
+/// Reads the config file given by `--config`, `PNA_CONFIG` or at the default path.
+/// The file at the default path is optional.
+fn read_config(path: Option<PathBuf>) -> io::Result<Option<toml::Table>> {
+    let (path, required) = match (path, env::var_os(CONFIG_ENV)) {
+        (Some(path), _) => (path, true),
+        (None, Some(path)) => (PathBuf::from(path), true),
+        (None, None) => match default_config_path() {
+            Some(path) => (path, false),
+            None => return Ok(None),
+        },
+    };
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !required => return Ok(None),
+        Err(e) => {
+            return Err(io::Error::new(
+                e.kind(),
+                format!("failed to read config file {}: {e}", path.display()),
+            ))
+        }
+    };
+    content
+        .parse()
+        .map(Some)
+        .map_err(|e| usage_error(format!("invalid config file {}: {e}", path.display())))
+}
+
+/// Returns the options in the section of the subcommand, such as `[create]` or `[experimental.grep]`.
+fn config_options(
+    config: &toml::Table,
+    names: &[String],
+    command: &Command,
+    warnings: &mut Vec<String>,
+) -> Vec<DefaultOption> {
+    let mut table = config;
+    for name in names {
+        match table.get(name) {
+            Some(toml::Value::Table(section)) => table = section,
+            _ => return Vec::new(),
+        }
+    }
+    let section = names.join(".");
+    let mut options = Vec::new();
+    for (key, value) in table {
+        if let toml::Value::Table(_) = value {
+            if command.find_subcommand(key).is_some() {
+                continue;
+            }
+        }
+        let option = match GROUP_KEYS.iter().find(|(k, _)| k == key) {
+            Some((_, group)) => value
+                .as_str()
+                .and_then(|name| find_arg(command, name))
+                .filter(|arg| {
+                    command
+                        .get_groups()
+                        .any(|g| g.get_id() == group && g.get_args().any(|a| a == arg.get_id()))
+                })
+                .map(|arg| DefaultOption {
+                    id: arg.get_id().to_string(),
+                    args: vec![format!("--{}", arg.get_long().unwrap_or_default()).into()],
+                }),
+            None => find_arg(command, key).and_then(|arg| {
+                Some(DefaultOption {
+                    id: arg.get_id().to_string(),
+                    args: config_values(value)?
+                        .into_iter()
+                        .map(|value| match value {
+                            Some(value) => format!("--{key}={value}").into(),
+                            None => format!("--{key}").into(),
+                        })
+                        .collect(),
+                })
+            }),
+        };
+        match option {
+            Some(option) => options.push(option),
+            None => warnings.push(format!(
+                "Ignore unknown or invalid config `{key}` in [{section}]"
+            )),
+        }
+    }
+    options
+}
+
+/// Converts a value of the config file to the values of the option,
+/// where `None` is the option without a value.
+fn config_values(value: &toml::Value) -> Option<Vec<Option<String>>> {
+    match value {
+        toml::Value::Boolean(true) => Some(vec![None]),
+        toml::Value::Boolean(false) => Some(Vec::new()),
+        toml::Value::String(s) => Some(vec![Some(s.clone())]),
+        toml::Value::Integer(i) => Some(vec![Some(i.to_string())]),
+        toml::Value::Float(f) => Some(vec![Some(f.to_string())]),
+        toml::Value::Datetime(d) => Some(vec![Some(d.to_string())]),
+        toml::Value::Array(values) => values
+            .iter()
+            .map(|it| match it {
+                toml::Value::Array(_) | toml::Value::Table(_) => None,
+                it => config_values(it),
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|it| it.into_iter().flatten().collect()),
+        toml::Value::Table(_) => None,
+    }
+}
+
+/// Returns the options in `PNA_OPTIONS`, separated by whitespaces like `--zstd --keep-timestamp`.
+/// Options taking a value must be given in the form of `--name=value`.
+/// Options not supported by the subcommand are ignored.
+fn env_options(options: &str, command: &Command) -> Vec<DefaultOption> {
+    options
+        .split_whitespace()
+        .filter_map(|option| {
+            let name = option.strip_prefix("--")?;
+            let name = name.split_once('=').map_or(name, |(name, _)| name);
+            match find_arg(command, name) {
+                Some(arg) => Some(DefaultOption {
+                    id: arg.get_id().to_string(),
+                    args: vec![option.into()],
+                }),
+                None => {
+                    log::debug!("Ignore {option} in {OPTIONS_ENV}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn find_arg<'a>(command: &'a Command, long: &str) -> Option<&'a Arg> {
+    command
+        .get_arguments()
+        .find(|it| it.get_long() == Some(long) && !it.is_positional())
+}
+
+/// Returns the arguments of the defaults, where later defaults override earlier ones,
+/// excluding the defaults conflicting with the options given on the command line.
+fn resolve(defaults: Vec<DefaultOption>, command: &Command, matches: &ArgMatches) -> Vec<OsString> {
+    let group_mates = |id: &str| -> Vec<String> {
+        command
+            .get_groups()
+            .filter(|g| !ArgGroup::clone(g).is_multiple() && g.get_args().any(|a| a == id))
+            .flat_map(|g| g.get_args().map(|a| a.to_string()))
+            .chain([id.to_string()])
+            .collect()
+    };
+    let on_command_line = |id: &str| {
+        matches.try_get_raw(id).is_ok()
+            && matches.value_source(id) == Some(ValueSource::CommandLine)
+    };
+    let mut resolved = Vec::<DefaultOption>::new();
+    for default in defaults {
+        let mates = group_mates(&default.id);
+        resolved.retain(|it| !mates.contains(&it.id));
+        resolved.push(default);
+    }
+    resolved
+        .into_iter()
+        .filter(|default| {
+            let conflicts = command
+                .get_arguments()
+                .find(|it| it.get_id() == default.id.as_str())
+                .map(|arg| command.get_arg_conflicts_with(arg))
+                .unwrap_or_default();
+            !group_mates(&default.id)
+                .iter()
+                .any(|it| on_command_line(it))
+                && !conflicts
+                    .iter()
+                    .any(|it| on_command_line(it.get_id().as_str()))
+        })
+        .flat_map(|it| it.args)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_command() -> Command {
+        Cli::command().find_subcommand("create").unwrap().clone()
+    }
+
+    #[test]
+    fn unknown_config_key() {
+        let config = "[create]\nkeep-timestamp = true\nno-such-option = 1\n"
+            .parse::<toml::Table>()
+            .unwrap();
+        let mut warnings = Vec::new();
+        let options = config_options(
+            &config,
+            &["create".into()],
+            &create_command(),
+            &mut warnings,
+        );
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].args, [OsString::from("--keep-timestamp")]);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn group_config_key() {
+        let config = "[create]\ncompression = \"xz\"\n"
+            .parse::<toml::Table>()
+            .unwrap();
+        let options = config_options(
+            &config,
+            &["create".into()],
+            &create_command(),
+            &mut Vec::new(),
+        );
+        assert_eq!(options[0].args, [OsString::from("--xz")]);
+    }
+
+    #[test]
+    fn config_array_values() {
+        let value = toml::Value::Array(vec![toml::Value::Integer(1), "a".into()]);
+        assert_eq!(
+            config_values(&value),
+            Some(vec![Some("1".into()), Some("a".into())])
+        );
+    }
+}
@@ -1,5 +1,6 @@
 mod acl;
 pub mod append;
+mod cat;
 mod chmod;
 mod chown;
 mod chunk;
@@ -8,21 +9,55 @@ pub mod complete;
 pub(crate) mod concat;
 pub mod create;
 mod delete;
+mod diff;
 pub(super) mod experimental;
 pub mod extract;
+mod grep;
+mod info;
+mod lint;
 pub mod list;
 mod migrate;
+#[cfg(all(unix, feature = "fuse"))]
+mod mount;
+mod put;
+mod repair;
+mod signature;
 pub mod split;
 pub(crate) mod stdio;
 pub(crate) mod strip;
 pub mod update;
+mod verify;
+pub(crate) mod version;
 mod xattr;
 
-use crate::cli::{CipherAlgorithmArgs, Cli, Commands, PasswordArgs};
-use std::{fs, io};
+use crate::{
+    cli::{CipherAlgorithmArgs, Cli, Commands, PasswordArgs},
+    utils,
+};
+use pna::Identity;
+use std::{
+    fs, io,
+    path::PathBuf,
+    process::{self, Stdio},
+};
+use zeroize::{Zeroize, Zeroizing};
 
 pub fn entry(cli: Cli) -> io::Result<()> {
-    match cli.commands {
+    utils::progress::set_enabled(cli.verbosity.progress());
+    // NOTE: With a single thread, run on the current thread without building a thread pool.
+    //       The parallel code runs on the current thread outside a pool, see `run_in_pool`.
+    if cli.threads == 1 {
+        return execute(cli.commands);
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(cli.threads)
+        .build()
+        .map_err(io::Error::other)?;
+    pool.install(|| execute(cli.commands))
+}
+
+fn execute(commands: Commands) -> io::Result<()> {
+    match commands {
         Commands::Create(cmd) => cmd.execute(),
         Commands::Append(cmd) => cmd.execute(),
         Commands::Extract(cmd) => cmd.execute(),
@@ -31,25 +66,92 @@ pub fn entry(cli: Cli) -> io::Result<()> {
         Commands::Concat(cmd) => cmd.execute(),
         Commands::Strip(cmd) => cmd.execute(),
         Commands::Complete(cmd) => cmd.execute(),
+        Commands::Version(cmd) => cmd.execute(),
         Commands::Experimental(cmd) => cmd.execute(),
     }
 }
 
-fn ask_password(args: PasswordArgs) -> io::Result<Option<String>> {
+fn ask_password(args: PasswordArgs) -> io::Result<Option<Zeroizing<String>>> {
     if let Some(path) = args.password_file {
-        return Ok(Some(fs::read_to_string(path)?));
+        return Ok(Some(Zeroizing::new(fs::read_to_string(path)?)));
     };
+    if let Some(command) = args.password_command {
+        return run_password_command(&command).map(Some);
+    }
     Ok(match args.password {
         Some(password @ Some(_)) => {
             log::warn!("Using a password on the command line interface can be insecure.");
-            password
+            password.map(Zeroizing::new)
         }
-        Some(None) => Some(gix_prompt::securely("Enter password: ").map_err(io::Error::other)?),
+        Some(None) => Some(Zeroizing::new(
+            gix_prompt::securely("Enter password: ").map_err(io::Error::other)?,
+        )),
         None => None,
     })
 }
 
-fn check_password(password: &Option<String>, cipher_args: &CipherAlgorithmArgs) {
+/// Runs `command` with the shell and returns its standard output without one trailing newline.
+fn run_password_command(command: &str) -> io::Result<Zeroizing<String>> {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = process::Command::new("cmd");
+        cmd.arg("/C").arg(command);
+        cmd
+    };
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = process::Command::new("sh");
+        cmd.arg("-c").arg(command);
+        cmd
+    };
+    // NOTE: Inherit stdin so that the password manager can ask for its own passphrase.
+    let output = cmd
+        .stdin(Stdio::inherit())
+        .output()
+        .map_err(|e| io::Error::new(e.kind(), format!("failed to run password command: {e}")))?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "password command failed with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )));
+    }
+    let mut password = Zeroizing::new(String::from_utf8(output.stdout).map_err(|e| {
+        e.into_bytes().zeroize();
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "password command printed a password that is not valid UTF-8",
+        )
+    })?);
+    if password.ends_with('\n') {
+        password.pop();
+        if password.ends_with('\r') {
+            password.pop();
+        }
+    }
+    Ok(password)
+}
+
+fn read_identities(paths: &[PathBuf]) -> io::Result<Vec<Identity>> {
+    let mut identities = Vec::new();
+    for path in paths {
+        for line in fs::read_to_string(path)?.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            identities.push(line.parse().map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{}: invalid identity: {e}", path.display()),
+                )
+            })?);
+        }
+    }
+    Ok(identities)
+}
+
+fn check_password(password: &Option<Zeroizing<String>>, cipher_args: &CipherAlgorithmArgs) {
     if password.is_some() {
         return;
     }
@@ -1,6 +1,8 @@
 use crate::{
     chunk::{Ace, AcePlatform, Flag, Identifier, OwnerType, Permission},
-    cli::{PasswordArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs},
+    cli::{
+        PasswordArgs, ReplaceArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs,
+    },
     command::{
         ask_password,
         commons::{
@@ -8,6 +10,7 @@ use crate::{
         },
         Command,
     },
+    exit_code::usage_error,
     ext::NormalEntryExt,
     utils::{GlobPatterns, PathPartExt},
 };
@@ -79,6 +82,8 @@ pub(crate) struct SetAclCommand {
     #[command(flatten)]
     transform_strategy: SolidEntriesTransformStrategyArgs,
     #[command(flatten)]
+    replace: ReplaceArgs,
+    #[command(flatten)]
     password: PasswordArgs,
 }
 
@@ -212,12 +217,11 @@ fn archive_get_acl(args: GetAclCommand) -> io::Result<()> {
     if args.files.is_empty() {
         return Ok(());
     }
-    let globs = GlobPatterns::new(args.files)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs = GlobPatterns::new(args.files).map_err(usage_error)?;
 
     run_entries(
         &args.archive,
-        || password.as_deref(),
+        || password.as_deref().map(String::as_str),
         |entry| {
             let entry = entry?;
             let name = entry.header().path();
@@ -244,14 +248,13 @@ fn archive_set_acl(args: SetAclCommand) -> io::Result<()> {
     if args.files.is_empty() {
         return Ok(());
     }
-    let globs = GlobPatterns::new(args.files)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs = GlobPatterns::new(args.files).map_err(usage_error)?;
 
     match args.transform_strategy.strategy() {
         SolidEntriesTransformStrategy::UnSolid => run_transform_entry(
             args.archive.remove_part().unwrap(),
             &args.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 if globs.matches_any(entry.header().path()) {
@@ -265,11 +268,12 @@ fn archive_set_acl(args: SetAclCommand) -> io::Result<()> {
                 }
             },
             TransformStrategyUnSolid,
+            &args.replace,
         ),
         SolidEntriesTransformStrategy::KeepSolid => run_transform_entry(
             args.archive.remove_part().unwrap(),
             &args.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 if globs.matches_any(entry.header().path()) {
@@ -283,6 +287,7 @@ fn archive_set_acl(args: SetAclCommand) -> io::Result<()> {
                 }
             },
             TransformStrategyKeepSolid,
+            &args.replace,
         ),
     }
 }
@@ -1,29 +1,40 @@
+#[cfg(feature = "memmap")]
+use crate::command::commons::run_read_entries_mem as run_read_entries;
+#[cfg(not(feature = "memmap"))]
+use crate::command::commons::run_read_entries_path as run_read_entries;
 use crate::{
     cli::{
         CipherAlgorithmArgs, CompressionAlgorithmArgs, FileArgs, HashAlgorithmArgs, PasswordArgs,
+        TraversalArgs,
     },
     command::{
         ask_password, check_password,
         commons::{
-            collect_items, create_entry, entry_option, CreateOptions, KeepOptions, OwnerOptions,
+            check_split_part_size, collect_split_archives, collect_traversal_items, create_entry,
+            entry_name, entry_option_with_existing, run_in_pool, split_archive_overhead_bytes,
+            split_to_parts, temp_part_path, CreateOptions, ExistingSettings, KeepOptions,
+            OutputArchive, OwnerOptions, PlatformTags, TransformStrategy,
+            TransformStrategyKeepSolid,
         },
         Command,
     },
-    utils::{self, PathPartExt},
+    utils::{self, env::temp_dir, PathPartExt},
+};
+use bytesize::ByteSize;
+use clap::{ArgGroup, Parser};
+use pna::{Archive, EntryName, EntryPart, NormalEntry, ReadEntry, MIN_CHUNK_BYTES_SIZE};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::Receiver,
+    time::{Duration, SystemTime},
 };
-use clap::{ArgGroup, Parser, ValueHint};
-use pna::Archive;
-use std::{fs::File, io, path::PathBuf};
 
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[command(
     group(ArgGroup::new("unstable-acl").args(["keep_acl"]).requires("unstable")),
-    group(ArgGroup::new("unstable-append-exclude").args(["exclude"]).requires("unstable")),
-    group(ArgGroup::new("unstable-files-from").args(["files_from"]).requires("unstable")),
-    group(ArgGroup::new("unstable-files-from-stdin").args(["files_from_stdin"]).requires("unstable")),
-    group(ArgGroup::new("unstable-exclude-from").args(["exclude_from"]).requires("unstable")),
-    group(ArgGroup::new("unstable-gitignore").args(["gitignore"]).requires("unstable")),
-    group(ArgGroup::new("read-files-from").args(["files_from", "files_from_stdin"])),
     group(ArgGroup::new("store-uname").args(["uname"]).requires("keep_permission")),
     group(ArgGroup::new("store-gname").args(["gname"]).requires("keep_permission")),
     group(ArgGroup::new("store-numeric-owner").args(["numeric_owner"]).requires("keep_permission")),
@@ -34,8 +45,8 @@ use std::{fs::File, io, path::PathBuf};
     group(ArgGroup::new("windows-unstable-keep-permission").args(["keep_permission"]).requires("unstable")),
 ))]
 pub(crate) struct AppendCommand {
-    #[arg(short, long, help = "Add the directory to the archive recursively")]
-    pub(crate) recursive: bool,
+    #[command(flatten)]
+    pub(crate) traversal: TraversalArgs,
     #[arg(long, help = "Archiving the directories")]
     pub(crate) keep_dir: bool,
     #[arg(long, help = "Archiving the timestamp of the files")]
@@ -44,6 +55,12 @@ pub(crate) struct AppendCommand {
     pub(crate) keep_permission: bool,
     #[arg(long, help = "Archiving the extended attributes of the files")]
     pub(crate) keep_xattr: bool,
+    #[arg(
+        long,
+        requires = "keep_xattr",
+        help = "Compress the values of the extended attributes larger than 4 KiB with zstd. The compressed attributes cannot be read by pna 0.21.0 or earlier (unstable)"
+    )]
+    pub(crate) compress_xattr: bool,
     #[arg(long, help = "Archiving the acl of the files")]
     pub(crate) keep_acl: bool,
     #[arg(long, help = "Archiving user to the entries from given name")]
@@ -65,16 +82,35 @@ pub(crate) struct AppendCommand {
         help = "This is equivalent to --uname \"\" --gname \"\". It causes user and group names to not be stored in the archive"
     )]
     pub(crate) numeric_owner: bool,
-    #[arg(long, help = "Read archiving files from given path (unstable)", value_hint = ValueHint::FilePath)]
-    pub(crate) files_from: Option<String>,
-    #[arg(long, help = "Read archiving files from stdin (unstable)")]
-    pub(crate) files_from_stdin: bool,
-    #[arg(long, help = "Read exclude files from given path (unstable)", value_hint = ValueHint::FilePath)]
-    pub(crate) exclude_from: Option<String>,
-    #[arg(long, help = "Ignore files from .gitignore (unstable)")]
-    pub(crate) gitignore: bool,
-    #[arg(long, help = "Follow symbolic links")]
-    pub(crate) follow_links: bool,
+    #[arg(
+        long,
+        help = "Use the compression, encryption and password hashing settings of the existing entries for the settings not specified"
+    )]
+    pub(crate) match_existing: bool,
+    #[arg(
+        long,
+        overrides_with = "no_replace",
+        help = "Replace the entries of the same name in the archive. The archive is rewritten instead of appended to when any entry is replaced"
+    )]
+    pub(crate) replace: bool,
+    #[arg(
+        long,
+        overrides_with = "replace",
+        help = "Keep the entries of the same name in the archive and warn about them (default)"
+    )]
+    pub(crate) no_replace: bool,
+    #[arg(
+        long,
+        conflicts_with = "no_replace",
+        help = "Replace the entries of the same name only when the files are newer than them. This compares mtime in seconds and implies --replace"
+    )]
+    pub(crate) add_newer_only: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["replace", "add_newer_only"],
+        help = "Split archive by total entry size, the entries that do not fit in the last part are written to new parts"
+    )]
+    pub(crate) split: Option<Option<ByteSize>>,
     #[command(flatten)]
     pub(crate) compression: CompressionAlgorithmArgs,
     #[command(flatten)]
@@ -85,8 +121,6 @@ pub(crate) struct AppendCommand {
     pub(crate) hash: HashAlgorithmArgs,
     #[command(flatten)]
     pub(crate) file: FileArgs,
-    #[arg(long, help = "Exclude path glob (unstable)", value_hint = ValueHint::AnyPath)]
-    pub(crate) exclude: Option<Vec<PathBuf>>,
 }
 
 impl Command for AppendCommand {
@@ -99,63 +133,46 @@ impl Command for AppendCommand {
 fn append_to_archive(args: AppendCommand) -> io::Result<()> {
     let password = ask_password(args.password)?;
     check_password(&password, &args.cipher);
-    let archive_path = args.file.archive;
-    if !archive_path.exists() {
-        return Err(io::Error::new(
-            io::ErrorKind::NotFound,
-            format!("{} is not exists", archive_path.display()),
-        ));
+    let max_file_size = args
+        .split
+        .map(|it| it.unwrap_or(ByteSize::gb(1)).0 as usize);
+    if let Some(size) = max_file_size {
+        check_split_part_size(size)?;
     }
-    let mut num = 1;
-    let file = File::options().write(true).read(true).open(&archive_path)?;
-    let mut archive = Archive::read_header(file)?;
-    let mut archive = loop {
-        archive.seek_to_end()?;
-        if !archive.has_next_archive() {
-            break archive;
-        }
-        num += 1;
-        let file = File::options()
-            .write(true)
-            .read(true)
-            .open(archive_path.with_part(num).unwrap())?;
-        archive = archive.read_next_archive(file)?;
-    };
+    // NOTE: The first part is read first, even if another part of a split archive is given.
+    let archive_path = collect_split_archives(&args.file.archive)?.swap_remove(0);
+    let mut target_items = collect_traversal_items(
+        args.traversal.files(args.file.files)?,
+        &args.traversal,
+        args.keep_dir,
+        Some(&OutputArchive::new(&archive_path)),
+    )?;
 
-    let mut files = args.file.files;
-    if args.files_from_stdin {
-        files.extend(io::stdin().lines().collect::<io::Result<Vec<_>>>()?);
-    } else if let Some(path) = args.files_from {
-        files.extend(utils::fs::read_to_lines(path)?);
-    }
-    let exclude = if args.exclude.is_some() || args.exclude_from.is_some() {
-        let mut exclude = Vec::new();
-        if let Some(e) = args.exclude {
-            exclude.extend(e);
-        }
-        if let Some(p) = args.exclude_from {
-            exclude.extend(utils::fs::read_to_lines(p)?.into_iter().map(PathBuf::from));
+    let (tx, rx) = std::sync::mpsc::channel();
+    let password = password.as_deref().map(String::as_str);
+    let existing = if args.match_existing {
+        let existing = ExistingSettings::detect(&archive_path)?;
+        if existing.is_none() {
+            log::info!("No entries found in the archive, use the default settings");
         }
-        Some(exclude)
+        existing
     } else {
         None
     };
-    let target_items = collect_items(
-        &files,
-        args.recursive,
-        args.keep_dir,
-        args.gitignore,
-        args.follow_links,
-        exclude,
-    )?;
-
-    let (tx, rx) = std::sync::mpsc::channel();
-    let password = password.as_deref();
-    let option = entry_option(args.compression, args.cipher, args.hash, password);
+    let option = entry_option_with_existing(
+        args.compression,
+        args.cipher,
+        args.hash,
+        password,
+        existing.as_ref(),
+    );
     let keep_options = KeepOptions {
         keep_timestamp: args.keep_timestamp,
-        keep_permission: args.keep_permission,
+        clamp_time: None,
+        keep_owner: args.keep_permission,
+        keep_mode: args.keep_permission,
         keep_xattr: args.keep_xattr,
+        compress_xattr: args.compress_xattr,
         keep_acl: args.keep_acl,
     };
     let owner_options = OwnerOptions::new(
@@ -169,23 +186,229 @@ fn append_to_archive(args: AppendCommand) -> io::Result<()> {
         option,
         keep_options,
         owner_options,
+        platform_tags: PlatformTags::default(),
     };
+    let replace = args.replace || args.add_newer_only;
+    let existing_entries = read_entry_names(&archive_path, password)?;
+    let mut replaced = HashSet::new();
+    let mut duplicated = Vec::new();
+    target_items.retain(|path| {
+        // NOTE: An invalid name is reported when the entry is created.
+        let Ok(name) = entry_name(path) else {
+            return true;
+        };
+        let Some(modified) = existing_entries.get(&name) else {
+            return true;
+        };
+        if !replace {
+            duplicated.push(name);
+        } else if args.add_newer_only && !is_newer(path, *modified) {
+            log::debug!(
+                "Skip {}: the entry in the archive is not older",
+                path.display()
+            );
+            return false;
+        } else {
+            replaced.insert(name);
+        }
+        true
+    });
+    if !duplicated.is_empty() {
+        log::warn!(
+            "The archive already contains the entries of the same name, use --replace to replace them: {}",
+            duplicated
+                .iter()
+                .map(EntryName::as_str)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
     for file in target_items {
         let tx = tx.clone();
-        rayon::scope_fifo(|s| {
-            s.spawn_fifo(|_| {
-                log::debug!("Adding: {}", file.display());
-                tx.send(create_entry(&file, &create_options))
-                    .unwrap_or_else(|e| panic!("{e}: {}", file.display()));
-            })
+        run_in_pool(|| {
+            log::debug!("Adding: {}", file.display());
+            tx.send(create_entry(&file, &create_options))
+                .unwrap_or_else(|e| panic!("{e}: {}", file.display()));
         });
     }
 
     drop(tx);
 
-    for entry in rx.into_iter() {
-        archive.add_entry(entry?)?;
+    if replaced.is_empty() {
+        append_entries(&archive_path, rx, max_file_size)
+    } else {
+        log::info!(
+            "Rewriting the archive to replace {} entries instead of appending to it",
+            replaced.len()
+        );
+        rewrite_entries(&archive_path, password, &replaced, rx)
+    }
+}
+
+/// Collects the names of the entries in the archive with their modification times.
+pub(crate) fn read_entry_names(
+    archive_path: &Path,
+    password: Option<&str>,
+) -> io::Result<HashMap<EntryName, Option<Duration>>> {
+    let mut names = HashMap::new();
+    run_read_entries(archive_path, |entry| {
+        match entry? {
+            ReadEntry::Solid(solid) => match solid.entries(password) {
+                Ok(entries) => {
+                    for entry in entries {
+                        let entry = entry?;
+                        names.insert(entry.header().path().clone(), entry.metadata().modified());
+                    }
+                }
+                Err(e) => log::warn!("Could not check the names in a solid entry: {e}"),
+            },
+            ReadEntry::Normal(entry) => {
+                names.insert(entry.header().path().clone(), entry.metadata().modified());
+            }
+        }
+        Ok(())
+    })?;
+    Ok(names)
+}
+
+/// Returns `true` if the file is newer than the entry modified at `stored`.
+/// Timestamps of whole seconds are compared in seconds, because archives made by the older versions
+/// do not store the fractional part.
+fn is_newer(path: &Path, stored: Option<Duration>) -> bool {
+    let Some(stored) = stored else {
+        return true;
+    };
+    let Some(modified) = fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|it| it.duration_since(SystemTime::UNIX_EPOCH).ok())
+    else {
+        return true;
+    };
+    if stored.subsec_nanos() == 0 {
+        stored.as_secs() < modified.as_secs()
+    } else {
+        stored < modified
+    }
+}
+
+/// Appends `entries` to the last part of the archive at `archive_path`.
+///
+/// When `max_file_size` is given, the entries that do not fit in the last part
+/// are written to new parts of at most `max_file_size` bytes.
+/// An archive that was not split is renamed to its first part if new parts are written.
+pub(crate) fn append_entries(
+    archive_path: &Path,
+    entries: impl IntoIterator<Item = io::Result<NormalEntry>>,
+    max_file_size: Option<usize>,
+) -> io::Result<()> {
+    let parts = collect_split_archives(archive_path)?;
+    let archive_path = parts[0].as_path();
+    let open = |path: &Path| File::options().write(true).read(true).open(path);
+    let mut num = 1;
+    let mut last_path = archive_path.to_path_buf();
+    let mut archive = Archive::read_header(open(archive_path)?)?;
+    let mut archive = loop {
+        archive.seek_to_end()?;
+        if !archive.has_next_archive() {
+            break archive;
+        }
+        num += 1;
+        last_path = parts
+            .get(num - 1)
+            .cloned()
+            .unwrap_or_else(|| archive_path.with_part(num).unwrap());
+        archive = archive.read_next_archive(open(&last_path)?)?;
+    };
+    let Some(max_file_size) = max_file_size else {
+        for entry in entries {
+            archive.add_entry(entry?)?;
+        }
+        archive.finalize()?;
+        return Ok(());
+    };
+    let last_part_len = fs::metadata(&last_path)?.len() as usize;
+    let overhead = split_archive_overhead_bytes(archive.archive_id());
+    let max_file_size = max_file_size - overhead;
+    // NOTE: The last part has no ANXT chunk, which is counted in the overhead.
+    let mut written_entry_size = (last_part_len + MIN_CHUNK_BYTES_SIZE).saturating_sub(overhead);
+    // Part being written to a temporary path, renamed to its final path once completed.
+    let mut writing = None;
+    for entry in entries {
+        let p = EntryPart::from(entry?);
+        let entry_parts = split_to_parts(
+            p.as_ref(),
+            max_file_size.saturating_sub(written_entry_size),
+            max_file_size,
+        )?;
+        for part in entry_parts {
+            if written_entry_size + part.bytes_len() > max_file_size {
+                num += 1;
+                let path = archive_path.with_part(num).unwrap();
+                let temp_path = temp_part_path(&path);
+                let (completed, next) = archive.finalize_and_split(File::create(&temp_path)?)?;
+                completed.sync_all()?;
+                drop(completed);
+                if let Some((temp_path, path)) = writing.replace((temp_path, path)) {
+                    fs::rename(temp_path, path)?;
+                }
+                archive = next;
+                written_entry_size = 0;
+            }
+            written_entry_size += archive.add_entry_part(part)?;
+        }
+    }
+    let file = archive.finalize()?;
+    if let Some((temp_path, path)) = writing {
+        file.sync_all()?;
+        drop(file);
+        fs::rename(temp_path, path)?;
+        if archive_path.remove_part().as_deref() == Some(archive_path) {
+            fs::rename(archive_path, archive_path.with_part(1).unwrap())?;
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_entries(
+    archive_path: &Path,
+    password: Option<&str>,
+    replaced: &HashSet<EntryName>,
+    entries: Receiver<io::Result<NormalEntry>>,
+) -> io::Result<()> {
+    let random = rand::random::<usize>();
+    let temp_dir_path = temp_dir().unwrap_or_else(|| {
+        archive_path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    });
+    fs::create_dir_all(&temp_dir_path)?;
+    let outfile_path = temp_dir_path.join(format!("{}.pna.tmp", random));
+    let outfile = fs::File::create(&outfile_path)?;
+    let mut out_archive = Archive::write_header(outfile)?;
+
+    run_read_entries(archive_path, |entry| {
+        TransformStrategyKeepSolid::transform(&mut out_archive, password, entry, |entry| {
+            let entry = entry?;
+            if replaced.contains(entry.header().path()) {
+                log::debug!("Replacing: {}", entry.header().path());
+                return Ok(None);
+            }
+            Ok(Some(entry))
+        })
+    })?;
+    for entry in entries {
+        TransformStrategyKeepSolid::transform(
+            &mut out_archive,
+            password,
+            entry.map(Into::into),
+            |entry| entry.map(Some),
+        )?;
     }
-    archive.finalize()?;
+    out_archive.finalize()?;
+
+    utils::fs::mv(outfile_path, archive_path.remove_part().unwrap())?;
     Ok(())
 }
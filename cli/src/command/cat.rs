@@ -0,0 +1,354 @@
+use crate::{
+    cli::PasswordArgs,
+    command::{
+        ask_password,
+        commons::{run_process_archive, PathArchiveProvider},
+        Command,
+    },
+    exit_code::{with_status, ExitStatus, StatusReader},
+    utils::str::edit_distance,
+};
+use clap::{Parser, ValueHint};
+use pna::{Encryption, NormalEntry, ReadOptions};
+use std::{
+    io::{self, prelude::*, BufReader, IsTerminal},
+    path::PathBuf,
+    str::FromStr,
+};
+
+/// Size of the head of contents that is checked for binary data.
+const BINARY_CHECK_SIZE: usize = 8 * 1024;
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct CatCommand {
+    #[arg(
+        long,
+        value_name = "START-END",
+        conflicts_with = "lines",
+        help = "Print only the bytes from START to END inclusive, END can be omitted to print to the end"
+    )]
+    bytes: Option<ByteRange>,
+    #[arg(long, value_name = "N", help = "Print only the first N lines")]
+    lines: Option<usize>,
+    #[arg(long, help = "Print binary contents even if stdout is a terminal")]
+    force_binary: bool,
+    #[command(flatten)]
+    password: PasswordArgs,
+    #[arg(value_hint = ValueHint::FilePath)]
+    archive: PathBuf,
+    #[arg(required = true, help = "Names of the entries to print in order")]
+    files: Vec<String>,
+}
+
+impl Command for CatCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        cat_archive(self)
+    }
+}
+
+/// Inclusive range of bytes given by `START-END` or `START-`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+struct ByteRange {
+    start: u64,
+    end: Option<u64>,
+}
+
+impl ByteRange {
+    /// Number of bytes in the range, or `None` for a range up to the end.
+    #[inline]
+    fn len(&self) -> Option<u64> {
+        self.end.map(|end| end - self.start + 1)
+    }
+}
+
+impl FromStr for ByteRange {
+    type Err = String;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("expected START-END, but got `{s}`"))?;
+        let start = start
+            .parse::<u64>()
+            .map_err(|e| format!("invalid START `{start}`: {e}"))?;
+        let end = match end {
+            "" => None,
+            end => Some(
+                end.parse::<u64>()
+                    .map_err(|e| format!("invalid END `{end}`: {e}"))?,
+            ),
+        };
+        if end.is_some_and(|end| end < start) {
+            return Err(format!("END must not be less than START, but got `{s}`"));
+        }
+        Ok(Self { start, end })
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct CatOptions {
+    bytes: Option<ByteRange>,
+    lines: Option<usize>,
+    refuse_binary: bool,
+}
+
+fn cat_archive(args: CatCommand) -> io::Result<()> {
+    let password = ask_password(args.password)?;
+    let read_options = ReadOptions::with_password(password.as_deref());
+    let mut stdout = io::stdout().lock();
+    let options = CatOptions {
+        bytes: args.bytes,
+        lines: args.lines,
+        refuse_binary: !args.force_binary && stdout.is_terminal(),
+    };
+
+    // NOTE: Contents are written in the order of the arguments. Contents found
+    // before the ones of the preceding arguments are kept until those are written.
+    let mut contents = vec![None; args.files.len()];
+    let mut next = 0;
+    let mut names = Vec::new();
+    run_process_archive(
+        PathArchiveProvider::new(&args.archive),
+        || password.as_deref().map(String::as_str),
+        |entry| {
+            let entry = entry?;
+            let name = entry.header().path().to_string();
+            let targets = (next..args.files.len())
+                .filter(|&i| contents[i].is_none() && args.files[i] == name)
+                .collect::<Vec<_>>();
+            names.push(name);
+            match targets[..] {
+                [] => return Ok(()),
+                [i] if i == next => {
+                    cat_entry(entry, &read_options, options, &mut stdout)?;
+                    contents[i] = Some(Vec::new());
+                }
+                _ => {
+                    let mut content = Vec::new();
+                    cat_entry(entry, &read_options, options, &mut content)?;
+                    for i in targets {
+                        contents[i] = Some(content.clone());
+                    }
+                }
+            }
+            while let Some(Some(content)) = contents.get_mut(next) {
+                stdout.write_all(&std::mem::take(content))?;
+                next += 1;
+            }
+            Ok(())
+        },
+    )?;
+
+    let mut not_found = Vec::new();
+    for (name, content) in args.files.iter().zip(&contents).skip(next) {
+        match content {
+            Some(content) => stdout.write_all(content)?,
+            None => not_found.push(not_found_message(name, &names)),
+        }
+    }
+    stdout.flush()?;
+    if not_found.is_empty() {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        not_found.join("\n"),
+    ))
+}
+
+fn cat_entry(
+    entry: NormalEntry,
+    read_options: &ReadOptions,
+    options: CatOptions,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let data_error = if entry.header().encryption() == Encryption::No {
+        ExitStatus::CorruptArchive
+    } else {
+        ExitStatus::Crypto
+    };
+    let reader = entry
+        .reader(read_options)
+        .map_err(|e| with_status(data_error, e))?;
+    write_contents(StatusReader::new(reader, data_error), options, out).map_err(|e| {
+        let status = ExitStatus::from_error(&e);
+        let e = io::Error::new(e.kind(), format!("{}: {e}", entry.header().path()));
+        with_status(status, e)
+    })
+}
+
+/// Writes the part of `reader` selected by `options` to `out`.
+fn write_contents(
+    mut reader: impl Read,
+    options: CatOptions,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let mut reader = match options.bytes {
+        Some(range) => {
+            io::copy(&mut reader.by_ref().take(range.start), &mut io::sink())?;
+            reader.take(range.len().unwrap_or(u64::MAX))
+        }
+        None => reader.take(u64::MAX),
+    };
+    let mut head = Vec::with_capacity(BINARY_CHECK_SIZE);
+    reader
+        .by_ref()
+        .take(BINARY_CHECK_SIZE as u64)
+        .read_to_end(&mut head)?;
+    if options.refuse_binary && head.contains(&0) {
+        return Err(io::Error::other(
+            "refusing to print binary contents to a terminal, use --force-binary to print them",
+        ));
+    }
+    let mut reader = head.as_slice().chain(reader);
+    match options.lines {
+        Some(lines) => {
+            let mut reader = BufReader::new(reader);
+            let mut line = Vec::new();
+            for _ in 0..lines {
+                line.clear();
+                if reader.read_until(b'\n', &mut line)? == 0 {
+                    break;
+                }
+                out.write_all(&line)?;
+            }
+        }
+        None => {
+            io::copy(&mut reader, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// Message for an entry `name` that is not found, with the entries in `names` that have a similar name.
+fn not_found_message(name: &str, names: &[String]) -> String {
+    let file_name = |s: &str| s.rsplit('/').next().unwrap_or(s).to_string();
+    let threshold = (name.chars().count() / 3).max(1);
+    let mut candidates = names
+        .iter()
+        .filter_map(|it| {
+            let distance = edit_distance(name, it);
+            if distance <= threshold {
+                Some((distance, it))
+            } else if file_name(it) == file_name(name) {
+                Some((usize::MAX, it))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    candidates.sort();
+    candidates.dedup();
+    let candidates = candidates
+        .into_iter()
+        .take(5)
+        .map(|(_, it)| format!("`{it}`"))
+        .collect::<Vec<_>>();
+    if candidates.is_empty() {
+        format!("{name}: entry not found")
+    } else {
+        format!(
+            "{name}: entry not found, did you mean {}?",
+            candidates.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cat(
+        data: &[u8],
+        bytes: Option<&str>,
+        lines: Option<usize>,
+        refuse_binary: bool,
+    ) -> io::Result<Vec<u8>> {
+        let options = CatOptions {
+            bytes: bytes.map(|it| it.parse().unwrap()),
+            lines,
+            refuse_binary,
+        };
+        let mut out = Vec::new();
+        write_contents(data, options, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn parse_byte_range() {
+        assert_eq!(
+            "0-1023".parse(),
+            Ok(ByteRange {
+                start: 0,
+                end: Some(1023)
+            })
+        );
+        assert_eq!(
+            "10-".parse(),
+            Ok(ByteRange {
+                start: 10,
+                end: None
+            })
+        );
+        assert_eq!("5-5".parse::<ByteRange>().unwrap().len(), Some(1));
+        assert!("5-4".parse::<ByteRange>().is_err());
+        assert!("-4".parse::<ByteRange>().is_err());
+        assert!("4".parse::<ByteRange>().is_err());
+    }
+
+    #[test]
+    fn byte_range() {
+        let data = b"0123456789";
+        assert_eq!(cat(data, None, None, false).unwrap(), data);
+        assert_eq!(cat(data, Some("0-0"), None, false).unwrap(), b"0");
+        assert_eq!(cat(data, Some("2-5"), None, false).unwrap(), b"2345");
+        assert_eq!(cat(data, Some("7-"), None, false).unwrap(), b"789");
+        assert_eq!(cat(data, Some("8-100"), None, false).unwrap(), b"89");
+        assert_eq!(cat(data, Some("20-30"), None, false).unwrap(), b"");
+    }
+
+    #[test]
+    fn line_limit() {
+        let data = b"a\nb\r\nc\nd";
+        assert_eq!(cat(data, None, Some(0), false).unwrap(), b"");
+        assert_eq!(cat(data, None, Some(2), false).unwrap(), b"a\nb\r\n");
+        assert_eq!(cat(data, None, Some(10), false).unwrap(), data);
+    }
+
+    #[test]
+    fn line_limit_after_binary_check_size() {
+        let mut data = vec![b'a'; BINARY_CHECK_SIZE + 10];
+        data.extend_from_slice(b"\nb\n");
+        assert_eq!(
+            cat(&data, None, Some(1), true).unwrap(),
+            &data[..data.len() - 2]
+        );
+    }
+
+    #[test]
+    fn refuse_binary() {
+        let data = b"text\0binary";
+        assert!(cat(data, None, None, true).is_err());
+        assert_eq!(cat(data, None, None, false).unwrap(), data);
+        assert_eq!(cat(data, Some("0-3"), None, true).unwrap(), b"text");
+    }
+
+    #[test]
+    fn not_found_suggestion() {
+        let names = ["dir/text.txt".to_string(), "dir/other.bin".to_string()];
+        assert_eq!(
+            not_found_message("dir/txet.txt", &names),
+            "dir/txet.txt: entry not found, did you mean `dir/text.txt`?"
+        );
+        assert_eq!(
+            not_found_message("text.txt", &names),
+            "text.txt: entry not found, did you mean `dir/text.txt`?"
+        );
+        assert_eq!(
+            not_found_message("missing", &names),
+            "missing: entry not found"
+        );
+    }
+}
@@ -1,15 +1,18 @@
 use crate::{
-    cli::{PasswordArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs},
+    cli::{
+        PasswordArgs, ReplaceArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs,
+    },
     command::{
         ask_password,
         commons::{run_transform_entry, TransformStrategyKeepSolid, TransformStrategyUnSolid},
         Command,
     },
+    exit_code::usage_error,
     utils::{GlobPatterns, PathPartExt},
 };
 use bitflags::bitflags;
 use clap::{Parser, ValueHint};
-use pna::NormalEntry;
+use pna::{DataKind, NormalEntry};
 use std::{io, path::PathBuf, str::FromStr};
 
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -20,9 +23,16 @@ pub(crate) struct ChmodCommand {
     mode: Mode,
     #[arg(value_hint = ValueHint::AnyPath)]
     files: Vec<String>,
+    #[arg(
+        long,
+        help = "Match the name patterns only against the entry names themselves, not against the entries under the directories they name"
+    )]
+    no_recursion: bool,
     #[command(flatten)]
     transform_strategy: SolidEntriesTransformStrategyArgs,
     #[command(flatten)]
+    replace: ReplaceArgs,
+    #[command(flatten)]
     password: PasswordArgs,
 }
 
@@ -38,13 +48,13 @@ fn archive_chmod(args: ChmodCommand) -> io::Result<()> {
     if args.files.is_empty() {
         return Ok(());
     }
-    let globs = GlobPatterns::new(args.files)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs =
+        GlobPatterns::with_recursion(args.files, !args.no_recursion).map_err(usage_error)?;
     match args.transform_strategy.strategy() {
         SolidEntriesTransformStrategy::UnSolid => run_transform_entry(
             args.archive.remove_part().unwrap(),
             &args.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 if globs.matches_any(entry.header().path()) {
@@ -54,11 +64,12 @@ fn archive_chmod(args: ChmodCommand) -> io::Result<()> {
                 }
             },
             TransformStrategyUnSolid,
+            &args.replace,
         ),
         SolidEntriesTransformStrategy::KeepSolid => run_transform_entry(
             args.archive.remove_part().unwrap(),
             &args.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 if globs.matches_any(entry.header().path()) {
@@ -68,6 +79,7 @@ fn archive_chmod(args: ChmodCommand) -> io::Result<()> {
                 }
             },
             TransformStrategyKeepSolid,
+            &args.replace,
         ),
     }
 }
@@ -75,8 +87,9 @@ fn archive_chmod(args: ChmodCommand) -> io::Result<()> {
 #[inline]
 fn transform_entry<T>(entry: NormalEntry<T>, mode: Mode) -> NormalEntry<T> {
     let metadata = entry.metadata().clone();
+    let kind = entry.header().data_kind();
     let permission = metadata.permission().map(|p| {
-        let mode = mode.apply_to(p.permissions());
+        let mode = mode.apply_to(p.permissions(), kind);
         pna::Permission::new(p.uid(), p.uname().into(), p.gid(), p.gname().into(), mode)
     });
     entry.with_metadata(metadata.with_permission(permission))
@@ -121,31 +134,68 @@ impl Mode {
     const OWNER_MASK: u16 = 0o700;
     const GROUP_MASK: u16 = 0o070;
     const OTHER_MASK: u16 = 0o007;
+    const SETUID: u16 = 0o4000;
+    const SETGID: u16 = 0o2000;
+    const STICKY: u16 = 0o1000;
+    const ANY_EXECUTE: u16 = 0o111;
+
+    /// `X` of the symbolic mode: execute, only for directories and entries already executable by anyone.
+    const CONDITIONAL_EXECUTE_FLAG: u8 = 0o10;
+    /// `s` of the symbolic mode: setuid for the user and setgid for the group.
+    const SET_ID_FLAG: u8 = 0o20;
+    /// `t` of the symbolic mode: sticky, for the others.
+    const STICKY_FLAG: u8 = 0o40;
+
     #[inline]
-    pub(crate) const fn apply_to(&self, mode: u16) -> u16 {
+    pub(crate) const fn apply_to(&self, mode: u16, kind: DataKind) -> u16 {
         match self {
             Mode::Num(mode) => *mode,
             Mode::Equal(t, m) => {
                 let owner_mode = if t.contains(Target::User) {
-                    Target::User.apply_to(*m as u16)
+                    0
                 } else {
-                    mode & Self::OWNER_MASK
+                    mode & (Self::OWNER_MASK | Self::SETUID)
                 };
                 let group_mode = if t.contains(Target::Group) {
-                    Target::Group.apply_to(*m as u16)
+                    0
                 } else {
-                    mode & Self::GROUP_MASK
+                    mode & (Self::GROUP_MASK | Self::SETGID)
                 };
                 let other_mode = if t.contains(Target::Other) {
-                    Target::Other.apply_to(*m as u16)
+                    0
                 } else {
-                    mode & Self::OTHER_MASK
+                    mode & (Self::OTHER_MASK | Self::STICKY)
                 };
-                owner_mode | group_mode | other_mode
+                owner_mode | group_mode | other_mode | Self::bits(*t, *m, mode, kind)
+            }
+            Mode::Plus(t, m) => mode | Self::bits(*t, *m, mode, kind),
+            Mode::Minus(t, m) => mode & !Self::bits(*t, *m, mode, kind),
+        }
+    }
+
+    /// Returns the mode bits that the symbolic mode `m` of the targets `t` stands for,
+    /// resolving `X` against the current `mode` and `kind` of the entry.
+    #[inline]
+    const fn bits(t: Target, m: u8, mode: u16, kind: DataKind) -> u16 {
+        let mut permission = (m & 0o7) as u16;
+        if m & Self::CONDITIONAL_EXECUTE_FLAG != 0
+            && (matches!(kind, DataKind::Directory) || mode & Self::ANY_EXECUTE != 0)
+        {
+            permission |= 0o1;
+        }
+        let mut bits = t.apply_to(permission);
+        if m & Self::SET_ID_FLAG != 0 {
+            if t.contains(Target::User) {
+                bits |= Self::SETUID;
+            }
+            if t.contains(Target::Group) {
+                bits |= Self::SETGID;
             }
-            Mode::Plus(t, m) => mode | t.apply_to(*m as u16),
-            Mode::Minus(t, m) => mode & !t.apply_to(*m as u16),
         }
+        if m & Self::STICKY_FLAG != 0 && t.contains(Target::Other) {
+            bits |= Self::STICKY;
+        }
+        bits
     }
 }
 
@@ -161,11 +211,14 @@ impl FromStr for Mode {
                     'x' => mode |= 1,
                     'w' => mode |= 2,
                     'r' => mode |= 4,
+                    'X' => mode |= Mode::CONDITIONAL_EXECUTE_FLAG,
+                    's' => mode |= Mode::SET_ID_FLAG,
+                    't' => mode |= Mode::STICKY_FLAG,
                     _ => {
                         return Err(format!(
-                            "unexpected character '{}'. excepted one of 'r', 'w' or 'x'",
-                            c
-                        ))
+                        "unexpected character '{}'. excepted one of 'r', 'w', 'x', 'X', 's' or 't'",
+                        c
+                    ))
                     }
                 };
             }
@@ -192,7 +245,7 @@ impl FromStr for Mode {
             return Err("mode must not be empty".into());
         }
         if s.chars().all(|c| c.is_ascii_digit()) {
-            return if s.len() == 3 {
+            return if s.len() == 3 || s.len() == 4 {
                 u16::from_str_radix(s, 8)
                     .map(Self::Num)
                     .map_err(|e| e.to_string())
@@ -229,6 +282,12 @@ mod tests {
     fn parse_digit_mode() {
         assert_eq!(Mode::from_str("755").unwrap(), Mode::Num(0o755));
         assert_eq!(Mode::from_str("000").unwrap(), Mode::Num(0o000));
+        assert_eq!(Mode::from_str("2755").unwrap(), Mode::Num(0o2755));
+        assert_eq!(Mode::from_str("7777").unwrap(), Mode::Num(0o7777));
+        assert_eq!(Mode::from_str("0644").unwrap(), Mode::Num(0o644));
+        assert!(Mode::from_str("75").is_err());
+        assert!(Mode::from_str("17777").is_err());
+        assert!(Mode::from_str("0855").is_err());
     }
 
     #[test]
@@ -271,13 +330,200 @@ mod tests {
 
     #[test]
     fn mode_apply_to() {
-        assert_eq!(Mode::from_str("755").unwrap().apply_to(0o764), 0o755);
-        assert_eq!(Mode::from_str("+x").unwrap().apply_to(0o664), 0o775);
-        assert_eq!(Mode::from_str("o+r").unwrap().apply_to(0o600), 0o604);
-        assert_eq!(Mode::from_str("u-r").unwrap().apply_to(0o600), 0o200);
-        assert_eq!(Mode::from_str("g=rw").unwrap().apply_to(0o777), 0o767);
-        assert_eq!(Mode::from_str("u=rw").unwrap().apply_to(0o000), 0o600);
-        assert_eq!(Mode::from_str("go-x").unwrap().apply_to(0o777), 0o766);
-        assert_eq!(Mode::from_str("go=r").unwrap().apply_to(0o777), 0o744);
+        assert_eq!(
+            Mode::from_str("755")
+                .unwrap()
+                .apply_to(0o764, DataKind::File),
+            0o755
+        );
+        assert_eq!(
+            Mode::from_str("+x")
+                .unwrap()
+                .apply_to(0o664, DataKind::File),
+            0o775
+        );
+        assert_eq!(
+            Mode::from_str("o+r")
+                .unwrap()
+                .apply_to(0o600, DataKind::File),
+            0o604
+        );
+        assert_eq!(
+            Mode::from_str("u-r")
+                .unwrap()
+                .apply_to(0o600, DataKind::File),
+            0o200
+        );
+        assert_eq!(
+            Mode::from_str("g=rw")
+                .unwrap()
+                .apply_to(0o777, DataKind::File),
+            0o767
+        );
+        assert_eq!(
+            Mode::from_str("u=rw")
+                .unwrap()
+                .apply_to(0o000, DataKind::File),
+            0o600
+        );
+        assert_eq!(
+            Mode::from_str("go-x")
+                .unwrap()
+                .apply_to(0o777, DataKind::File),
+            0o766
+        );
+        assert_eq!(
+            Mode::from_str("go=r")
+                .unwrap()
+                .apply_to(0o777, DataKind::File),
+            0o744
+        );
+    }
+
+    #[test]
+    fn parse_special_mode() {
+        assert_eq!(
+            Mode::from_str("a+rX").unwrap(),
+            Mode::Plus(Target::All, 0o4 | Mode::CONDITIONAL_EXECUTE_FLAG),
+        );
+        assert_eq!(
+            Mode::from_str("u+s").unwrap(),
+            Mode::Plus(Target::User, Mode::SET_ID_FLAG),
+        );
+        assert_eq!(
+            Mode::from_str("g-s").unwrap(),
+            Mode::Minus(Target::Group, Mode::SET_ID_FLAG),
+        );
+        assert_eq!(
+            Mode::from_str("+t").unwrap(),
+            Mode::Plus(Target::All, Mode::STICKY_FLAG),
+        );
+        assert_eq!(
+            Mode::from_str("o=rwxt").unwrap(),
+            Mode::Equal(Target::Other, 0o7 | Mode::STICKY_FLAG),
+        );
+        assert!(Mode::from_str("u+S").is_err());
+        assert!(Mode::from_str("u+T").is_err());
+    }
+
+    #[test]
+    fn mode_apply_conditional_execute() {
+        let mode = Mode::from_str("a+X").unwrap();
+        assert_eq!(mode.apply_to(0o644, DataKind::File), 0o644);
+        assert_eq!(mode.apply_to(0o744, DataKind::File), 0o755);
+        assert_eq!(mode.apply_to(0o644, DataKind::Directory), 0o755);
+        assert_eq!(mode.apply_to(0o644, DataKind::SymbolicLink), 0o644);
+        let mode = Mode::from_str("go=rX").unwrap();
+        assert_eq!(mode.apply_to(0o700, DataKind::File), 0o755);
+        assert_eq!(mode.apply_to(0o600, DataKind::File), 0o644);
+        assert_eq!(mode.apply_to(0o600, DataKind::Directory), 0o655);
+        assert_eq!(
+            Mode::from_str("u-X")
+                .unwrap()
+                .apply_to(0o755, DataKind::File),
+            0o655
+        );
+    }
+
+    #[test]
+    fn mode_apply_special_bits() {
+        assert_eq!(
+            Mode::from_str("u+s")
+                .unwrap()
+                .apply_to(0o755, DataKind::File),
+            0o4755
+        );
+        assert_eq!(
+            Mode::from_str("g+s")
+                .unwrap()
+                .apply_to(0o755, DataKind::Directory),
+            0o2755
+        );
+        assert_eq!(
+            Mode::from_str("+s")
+                .unwrap()
+                .apply_to(0o755, DataKind::File),
+            0o6755
+        );
+        assert_eq!(
+            Mode::from_str("o+s")
+                .unwrap()
+                .apply_to(0o755, DataKind::File),
+            0o755
+        );
+        assert_eq!(
+            Mode::from_str("+t")
+                .unwrap()
+                .apply_to(0o777, DataKind::Directory),
+            0o1777
+        );
+        assert_eq!(
+            Mode::from_str("u+t")
+                .unwrap()
+                .apply_to(0o777, DataKind::Directory),
+            0o777
+        );
+        assert_eq!(
+            Mode::from_str("ug-s")
+                .unwrap()
+                .apply_to(0o6755, DataKind::File),
+            0o755
+        );
+        assert_eq!(
+            Mode::from_str("-t")
+                .unwrap()
+                .apply_to(0o1777, DataKind::Directory),
+            0o777
+        );
+    }
+
+    #[test]
+    fn mode_apply_special_bits_with_equal() {
+        assert_eq!(
+            Mode::from_str("u=rwx")
+                .unwrap()
+                .apply_to(0o4755, DataKind::File),
+            0o755
+        );
+        assert_eq!(
+            Mode::from_str("u=rwxs")
+                .unwrap()
+                .apply_to(0o755, DataKind::File),
+            0o4755
+        );
+        assert_eq!(
+            Mode::from_str("g=rx")
+                .unwrap()
+                .apply_to(0o6755, DataKind::File),
+            0o4755
+        );
+        assert_eq!(
+            Mode::from_str("o=rx")
+                .unwrap()
+                .apply_to(0o1777, DataKind::Directory),
+            0o775
+        );
+        assert_eq!(
+            Mode::from_str("a=rwxt")
+                .unwrap()
+                .apply_to(0o000, DataKind::Directory),
+            0o1777
+        );
+    }
+
+    #[test]
+    fn mode_apply_num_replaces_special_bits() {
+        assert_eq!(
+            Mode::from_str("2755")
+                .unwrap()
+                .apply_to(0o4644, DataKind::Directory),
+            0o2755
+        );
+        assert_eq!(
+            Mode::from_str("755")
+                .unwrap()
+                .apply_to(0o4644, DataKind::File),
+            0o755
+        );
     }
 }
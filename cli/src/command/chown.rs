@@ -1,12 +1,15 @@
 #[cfg(any(unix, windows))]
 use crate::utils::fs::{Group, User};
 use crate::{
-    cli::{PasswordArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs},
+    cli::{
+        PasswordArgs, ReplaceArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs,
+    },
     command::{
         ask_password,
         commons::{run_transform_entry, TransformStrategyKeepSolid, TransformStrategyUnSolid},
         Command,
     },
+    exit_code::usage_error,
     utils::{GlobPatterns, PathPartExt},
 };
 use clap::{Parser, ValueHint};
@@ -18,13 +21,22 @@ use std::{io, path::PathBuf, str::FromStr};
 pub(crate) struct ChownCommand {
     #[arg(value_hint = ValueHint::FilePath)]
     archive: PathBuf,
-    #[arg(help = "owner[:group]|:group")]
+    #[arg(
+        help = "owner[:group]|:group, where the owner and group are names or numeric ids prefixed with `#`"
+    )]
     owner: Owner,
     #[arg(value_hint = ValueHint::AnyPath)]
     files: Vec<String>,
+    #[arg(
+        long,
+        help = "Match the name patterns only against the entry names themselves, not against the entries under the directories they name"
+    )]
+    no_recursion: bool,
     #[command(flatten)]
     transform_strategy: SolidEntriesTransformStrategyArgs,
     #[command(flatten)]
+    replace: ReplaceArgs,
+    #[command(flatten)]
     password: PasswordArgs,
 }
 
@@ -40,97 +52,113 @@ fn archive_chown(args: ChownCommand) -> io::Result<()> {
     if args.files.is_empty() {
         return Ok(());
     }
-    let globs = GlobPatterns::new(args.files)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let owner = args.owner.resolve()?;
+    let globs =
+        GlobPatterns::with_recursion(args.files, !args.no_recursion).map_err(usage_error)?;
     match args.transform_strategy.strategy() {
         SolidEntriesTransformStrategy::UnSolid => run_transform_entry(
             args.archive.remove_part().unwrap(),
             &args.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 if globs.matches_any(entry.header().path()) {
-                    Ok(Some(transform_entry(entry, &args.owner)))
+                    Ok(Some(transform_entry(entry, &owner)))
                 } else {
                     Ok(Some(entry))
                 }
             },
             TransformStrategyUnSolid,
+            &args.replace,
         ),
         SolidEntriesTransformStrategy::KeepSolid => run_transform_entry(
             args.archive.remove_part().unwrap(),
             &args.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 if globs.matches_any(entry.header().path()) {
-                    Ok(Some(transform_entry(entry, &args.owner)))
+                    Ok(Some(transform_entry(entry, &owner)))
                 } else {
                     Ok(Some(entry))
                 }
             },
             TransformStrategyKeepSolid,
+            &args.replace,
         ),
     }
 }
 
 #[inline]
-fn transform_entry<T>(entry: NormalEntry<T>, owner: &Owner) -> NormalEntry<T> {
+fn transform_entry<T>(entry: NormalEntry<T>, owner: &ResolvedOwner) -> NormalEntry<T> {
     let metadata = entry.metadata().clone();
     let permission = metadata.permission().map(|p| {
-        let user = owner.user();
-        #[cfg(unix)]
-        let user = user.and_then(|it| {
-            User::from_name(it)
-                .ok()
-                .map(|it| (it.as_raw().into(), it.name().into()))
-        });
-        #[cfg(windows)]
-        let user = user.and_then(|it| {
-            User::from_name(it)
-                .ok()
-                .map(|it| (u64::MAX, it.name().into()))
-        });
-        #[cfg(not(any(unix, windows)))]
-        let user = user.map(|_| (p.uid(), p.uname().into()));
-        let (uid, uname) = user.unwrap_or_else(|| (p.uid(), p.uname().into()));
-
-        let group = owner.group();
-        #[cfg(unix)]
-        let group = group.and_then(|it| {
-            Group::from_name(it)
-                .ok()
-                .map(|it| (it.as_raw().into(), it.name().into()))
-        });
-        #[cfg(windows)]
-        let group = group.and_then(|it| {
-            Group::from_name(it)
-                .ok()
-                .map(|it| (u64::MAX, it.name().into()))
-        });
-        #[cfg(not(any(unix, windows)))]
-        let group = group.map(|_| (p.gid(), p.gname().into()));
-        let (gid, gname) = group.unwrap_or_else(|| (p.gid(), p.gname().into()));
-        pna::Permission::new(uid, uname, gid, gname, p.permissions())
+        pna::Permission::new(
+            owner.uid.unwrap_or(p.uid()),
+            owner.uname.clone().unwrap_or_else(|| p.uname().into()),
+            owner.gid.unwrap_or(p.gid()),
+            owner.gname.clone().unwrap_or_else(|| p.gname().into()),
+            p.permissions(),
+        )
     });
     entry.with_metadata(metadata.with_permission(permission))
 }
 
+/// User or group given by name, or by numeric id prefixed with `#`.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) enum OwnerId {
+    Name(String),
+    Id(u64),
+}
+
+impl FromStr for OwnerId {
+    type Err = String;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix('#') {
+            Some(id) => id
+                .parse()
+                .map(Self::Id)
+                .map_err(|e| format!("invalid numeric id `{s}`: {e}")),
+            None => Ok(Self::Name(s.into())),
+        }
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub(crate) struct Owner {
-    user: Option<String>,
-    group: Option<String>,
+    user: Option<OwnerId>,
+    group: Option<OwnerId>,
 }
 
 impl Owner {
     #[inline]
-    pub(crate) fn user(&self) -> Option<&str> {
-        self.user.as_deref()
+    pub(crate) fn user(&self) -> Option<&OwnerId> {
+        self.user.as_ref()
     }
 
     #[inline]
-    pub(crate) fn group(&self) -> Option<&str> {
-        self.group.as_deref()
+    pub(crate) fn group(&self) -> Option<&OwnerId> {
+        self.group.as_ref()
+    }
+
+    /// Looks up the ids and names of the user and group to set.
+    fn resolve(&self) -> io::Result<ResolvedOwner> {
+        let (uid, uname) = match self.user() {
+            Some(user) => resolve_user(user)?,
+            None => (None, None),
+        };
+        let (gid, gname) = match self.group() {
+            Some(group) => resolve_group(group)?,
+            None => (None, None),
+        };
+        Ok(ResolvedOwner {
+            uid,
+            uname,
+            gid,
+            gname,
+        })
     }
 }
 
@@ -143,16 +171,103 @@ impl FromStr for Owner {
         }
         let (user, group) = if let Some((user, group)) = s.split_once(':') {
             (
-                user.is_empty().not().then(|| user.into()),
-                group.is_empty().not().then(|| group.into()),
+                user.is_empty().not().then(|| user.parse()).transpose()?,
+                group.is_empty().not().then(|| group.parse()).transpose()?,
             )
         } else {
-            (Some(s.into()), None)
+            (Some(s.parse()?), None)
         };
         Ok(Self { user, group })
     }
 }
 
+/// Ids and names to set to the entries. `None` keeps the one stored in the entry.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+struct ResolvedOwner {
+    uid: Option<u64>,
+    uname: Option<String>,
+    gid: Option<u64>,
+    gname: Option<String>,
+}
+
+/// Numeric ids are stored with the name of the user if it exists, otherwise with an empty name.
+#[cfg(unix)]
+fn resolve_user(user: &OwnerId) -> io::Result<(Option<u64>, Option<String>)> {
+    Ok(match user {
+        OwnerId::Name(name) => {
+            let user = User::from_name(name)?;
+            (Some(user.as_raw().into()), Some(user.name().into()))
+        }
+        OwnerId::Id(id) => {
+            let user = u32::try_from(*id)
+                .ok()
+                .and_then(|id| User::from_uid(id.into()).ok());
+            (
+                Some(*id),
+                Some(user.map_or_else(String::new, |it| it.name().into())),
+            )
+        }
+    })
+}
+
+#[cfg(unix)]
+fn resolve_group(group: &OwnerId) -> io::Result<(Option<u64>, Option<String>)> {
+    Ok(match group {
+        OwnerId::Name(name) => {
+            let group = Group::from_name(name)?;
+            (Some(group.as_raw().into()), Some(group.name().into()))
+        }
+        OwnerId::Id(id) => {
+            let group = u32::try_from(*id)
+                .ok()
+                .and_then(|id| Group::from_gid(id.into()).ok());
+            (
+                Some(*id),
+                Some(group.map_or_else(String::new, |it| it.name().into())),
+            )
+        }
+    })
+}
+
+#[cfg(windows)]
+fn resolve_user(user: &OwnerId) -> io::Result<(Option<u64>, Option<String>)> {
+    Ok(match user {
+        OwnerId::Name(name) => (Some(u64::MAX), Some(User::from_name(name)?.name().into())),
+        OwnerId::Id(id) => (Some(*id), None),
+    })
+}
+
+#[cfg(windows)]
+fn resolve_group(group: &OwnerId) -> io::Result<(Option<u64>, Option<String>)> {
+    Ok(match group {
+        OwnerId::Name(name) => (Some(u64::MAX), Some(Group::from_name(name)?.name().into())),
+        OwnerId::Id(id) => (Some(*id), None),
+    })
+}
+
+/// Names can not be looked up on this platform, so only numeric ids are applied.
+#[cfg(not(any(unix, windows)))]
+fn resolve_user(user: &OwnerId) -> io::Result<(Option<u64>, Option<String>)> {
+    Ok(match user {
+        OwnerId::Name(name) => {
+            log::warn!("Ignore the user name {name}: names can not be resolved on this platform");
+            (None, None)
+        }
+        OwnerId::Id(id) => (Some(*id), None),
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
+fn resolve_group(group: &OwnerId) -> io::Result<(Option<u64>, Option<String>)> {
+    Ok(match group {
+        OwnerId::Name(name) => {
+            log::warn!("Ignore the group name {name}: names can not be resolved on this platform");
+            (None, None)
+        }
+        OwnerId::Id(id) => (Some(*id), None),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -162,7 +277,7 @@ mod tests {
         assert_eq!(
             Owner::from_str("user").unwrap(),
             Owner {
-                user: Some("user".into()),
+                user: Some(OwnerId::Name("user".into())),
                 group: None,
             }
         );
@@ -174,7 +289,7 @@ mod tests {
             Owner::from_str(":group").unwrap(),
             Owner {
                 user: None,
-                group: Some("group".into()),
+                group: Some(OwnerId::Name("group".into())),
             }
         );
     }
@@ -184,9 +299,65 @@ mod tests {
         assert_eq!(
             Owner::from_str("user:group").unwrap(),
             Owner {
-                user: Some("user".into()),
-                group: Some("group".into()),
+                user: Some(OwnerId::Name("user".into())),
+                group: Some(OwnerId::Name("group".into())),
             }
         );
     }
+
+    #[test]
+    fn numeric_ids() {
+        assert_eq!(
+            Owner::from_str("#1000:#100").unwrap(),
+            Owner {
+                user: Some(OwnerId::Id(1000)),
+                group: Some(OwnerId::Id(100)),
+            }
+        );
+        assert_eq!(
+            Owner::from_str("user:#100").unwrap(),
+            Owner {
+                user: Some(OwnerId::Name("user".into())),
+                group: Some(OwnerId::Id(100)),
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_owner() {
+        assert!(Owner::from_str("").is_err());
+        assert!(Owner::from_str(":").is_err());
+        assert!(Owner::from_str("#user").is_err());
+        assert!(Owner::from_str(":#-1").is_err());
+    }
+
+    #[test]
+    fn transform_keeps_other_side() {
+        let mut builder =
+            pna::EntryBuilder::new_file("a.txt".into(), pna::WriteOptions::store()).unwrap();
+        builder.permission(pna::Permission::new(
+            1000,
+            "user".into(),
+            100,
+            "group".into(),
+            0o644,
+        ));
+        let entry = builder.build().unwrap();
+        let owner = ResolvedOwner {
+            gid: Some(200),
+            gname: Some("other".into()),
+            ..Default::default()
+        };
+        let entry = transform_entry(entry, &owner);
+        assert_eq!(
+            entry.metadata().permission(),
+            Some(&pna::Permission::new(
+                1000,
+                "user".into(),
+                200,
+                "other".into(),
+                0o644
+            ))
+        );
+    }
 }
@@ -1,33 +1,85 @@
+#[cfg(feature = "http")]
+use crate::cli::{HttpArgs, HttpHeader};
 use crate::{
-    cli::{CipherAlgorithmArgs, CompressionAlgorithmArgs, HashAlgorithmArgs},
-    utils::{self, env::temp_dir, PathPartExt},
+    chunk::{self, Platforms},
+    cli::{
+        CipherAlgorithmArgs, CompressionAlgorithmArgs, HashAlgorithmArgs, IdMapArgs, ReplaceArgs,
+        TraversalArgs,
+    },
+    exit_code::usage_error,
+    utils::{
+        self,
+        env::temp_dir,
+        fs::FileId,
+        hash::{DigestWriter, PartDigest},
+        id_map::IdMap,
+        progress::Progress,
+        resume::{CountingReader, ResumeState},
+        solid_cache::SolidCache,
+        PathPartExt,
+    },
 };
 use normalize_path::*;
 use pna::{
-    prelude::*, Archive, EntryBuilder, EntryName, EntryPart, EntryReference, NormalEntry,
-    ReadEntry, SolidEntryBuilder, WriteOptions, MIN_CHUNK_BYTES_SIZE, PNA_HEADER,
+    prelude::*, Archive, ArchiveId, EntryBuilder, EntryName, EntryNameError, EntryPart,
+    EntryReference, EntrySummaries, EntrySummary, EntryWriter, ExtendedAttribute, HashAlgorithm,
+    NormalEntry, Permission, RawChunk, ReadEntry, SolidEntryBuilder, WriteOptions,
+    MIN_CHUNK_BYTES_SIZE, PNA_HEADER,
 };
+use rayon::prelude::*;
 use std::{
+    collections::HashMap,
+    ffi::OsString,
     fs,
     io::{self, prelude::*},
+    mem,
     path::{Path, PathBuf},
-    time::UNIX_EPOCH,
+    sync::{mpsc, Arc},
+    time::{Duration, UNIX_EPOCH},
 };
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub(crate) struct KeepOptions {
     pub(crate) keep_timestamp: bool,
-    pub(crate) keep_permission: bool,
+    /// Latest timestamp to store, the later timestamps of the files are stored as it.
+    pub(crate) clamp_time: Option<Duration>,
+    /// Whether the owners of the files are kept, stored as user and group id 0 without names otherwise.
+    pub(crate) keep_owner: bool,
+    /// Whether the modes of the files are kept, stored as [`placeholder_mode`] otherwise.
+    pub(crate) keep_mode: bool,
     pub(crate) keep_xattr: bool,
+    pub(crate) compress_xattr: bool,
     pub(crate) keep_acl: bool,
 }
 
+/// Returns the mode stored in place of the mode of the file when only its owner is kept.
+#[cfg(any(unix, windows))]
+#[inline]
+const fn placeholder_mode(is_dir: bool) -> u16 {
+    if is_dir {
+        0o755
+    } else {
+        0o644
+    }
+}
+
+/// Extended attribute values larger than this are compressed when `--compress-xattr` is given.
+#[cfg(unix)]
+const COMPRESS_XATTR_THRESHOLD: usize = 4 * 1024;
+
 #[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub(crate) struct OwnerOptions {
     pub(crate) uname: Option<String>,
     pub(crate) gname: Option<String>,
     pub(crate) uid: Option<u32>,
     pub(crate) gid: Option<u32>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pub(crate) uid_map: IdMap,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pub(crate) gid_map: IdMap,
+    /// Whether the names of the mapped owners are looked up, instead of being ignored.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    pub(crate) map_names: bool,
 }
 
 impl OwnerOptions {
@@ -52,81 +104,435 @@ impl OwnerOptions {
             },
             uid,
             gid,
+            uid_map: IdMap::default(),
+            gid_map: IdMap::default(),
+            map_names: false,
         }
     }
+
+    /// Maps the owner ids between this system and the archive as given by `args`.
+    #[inline]
+    pub(crate) fn with_id_maps(self, args: IdMapArgs) -> io::Result<Self> {
+        let unmapped = args.unmapped.unwrap_or_default();
+        Ok(Self {
+            uid_map: IdMap::new(args.uid_map, unmapped)?,
+            gid_map: IdMap::new(args.gid_map, unmapped)?,
+            map_names: args.map_names,
+            ..self
+        })
+    }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Clone, Debug)]
 pub(crate) struct CreateOptions {
     pub(crate) option: WriteOptions,
     pub(crate) keep_options: KeepOptions,
     pub(crate) owner_options: OwnerOptions,
+    pub(crate) platform_tags: PlatformTags,
+}
+
+/// Platforms attached to the entries whose names match the patterns, given by `--platform-tag`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PlatformTags(Vec<(Platforms, globset::GlobMatcher)>);
+
+impl PlatformTags {
+    /// Parses the pairs of a selector like `os=linux,macos` and a glob pattern of entry names.
+    pub(crate) fn parse(args: &[String]) -> io::Result<Self> {
+        args.chunks(2)
+            .map(|pair| {
+                let [selector, pattern] = pair else {
+                    return Err(usage_error(
+                        "--platform-tag requires a selector and a pattern",
+                    ));
+                };
+                let platforms = selector
+                    .strip_prefix("os=")
+                    .ok_or_else(|| {
+                        usage_error(format!(
+                            "unsupported platform selector `{selector}`, expected `os=OS[,OS...]`"
+                        ))
+                    })?
+                    .parse::<Platforms>()
+                    .map_err(usage_error)?;
+                let matcher = globset::Glob::new(pattern)
+                    .map_err(usage_error)?
+                    .compile_matcher();
+                Ok((platforms, matcher))
+            })
+            .collect::<io::Result<_>>()
+            .map(Self)
+    }
+
+    /// Returns the platforms of all the tags matching `name`, or `None` if no tag matches.
+    fn platforms(&self, name: &EntryName) -> Option<Platforms> {
+        self.0
+            .iter()
+            .filter(|(_, matcher)| matcher.is_match(name.as_str()))
+            .map(|(platforms, _)| platforms)
+            .fold(None, |acc: Option<Platforms>, it| match acc {
+                Some(mut acc) => {
+                    acc.merge(it);
+                    Some(acc)
+                }
+                None => Some(it.clone()),
+            })
+    }
+
+    fn apply<T: MetadataTarget>(&self, mut entry: T, name: &EntryName) -> T {
+        if let Some(platforms) = self.platforms(name) {
+            entry.add_extra_chunk(RawChunk::from_data(chunk::fPLT, platforms.to_bytes()));
+        }
+        entry
+    }
+}
+
+/// Archive being written, which must not be archived into itself.
+pub(crate) struct OutputArchive {
+    id: Option<FileId>,
+    dir: Option<PathBuf>,
+    name: OsString,
+}
+
+impl OutputArchive {
+    /// Returns the output archive at `path`.
+    /// The archive itself is identified only if it is an existing regular file, not a pipe or a device.
+    #[inline]
+    pub(crate) fn new(path: &Path) -> Self {
+        Self {
+            id: fs::metadata(path)
+                .is_ok_and(|it| it.is_file())
+                .then(|| FileId::of(path).ok())
+                .flatten(),
+            dir: canonical_parent(path),
+            name: path.file_name().unwrap_or_default().to_os_string(),
+        }
+    }
+
+    /// Returns `true` if `path` is the archive or one of its split parts.
+    fn contains(&self, path: &Path) -> bool {
+        if self.id.is_some() && FileId::of(path).ok() == self.id {
+            return true;
+        }
+        let Some(name) = path.file_name() else {
+            return false;
+        };
+        // NOTE: Parts of a split archive are named like `archive.part1.pna`.
+        let part = name
+            .to_string_lossy()
+            .split('.')
+            .find_map(|it| it.strip_prefix("part")?.parse::<usize>().ok());
+        part.and_then(|n| Path::new(&self.name).with_part(n))
+            .is_some_and(|it| it.as_os_str() == name)
+            && self.dir.is_some()
+            && canonical_parent(path) == self.dir
+    }
+}
+
+fn canonical_parent(path: &Path) -> Option<PathBuf> {
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    if parent.as_os_str().is_empty() {
+        fs::canonicalize(".").ok()
+    } else {
+        fs::canonicalize(parent).ok()
+    }
+}
+
+/// Globs of the paths excluded from the files to archive.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Exclude {
+    globs: globset::GlobSet,
+    nodump: bool,
+}
+
+impl Exclude {
+    #[inline]
+    pub(crate) fn new<I: IntoIterator<Item = S>, S: AsRef<str>>(patterns: I) -> io::Result<Self> {
+        let mut builder = globset::GlobSet::builder();
+        for pattern in patterns {
+            builder.add(globset::Glob::new(pattern.as_ref()).map_err(usage_error)?);
+        }
+        let globs = builder.build().map_err(usage_error)?;
+        Ok(Self {
+            globs,
+            nodump: false,
+        })
+    }
+
+    /// Excludes the files with the nodump flag too if `nodump` is `true`.
+    #[inline]
+    pub(crate) fn with_nodump(mut self, nodump: bool) -> Self {
+        self.nodump = nodump;
+        self
+    }
+
+    /// Returns `true` if `path` or its normalized form matches any of the globs,
+    /// or the file has the nodump flag when the nodump flag is honored.
+    #[inline]
+    pub(crate) fn is_excluded(&self, path: &Path) -> bool {
+        (!self.globs.is_empty()
+            && (self.globs.is_match(path) || self.globs.is_match(path.normalize())))
+            || (self.nodump && has_nodump_flag(path))
+    }
 }
 
+fn has_nodump_flag(path: &Path) -> bool {
+    use crate::utils::os::{Current, Platform};
+    static UNSUPPORTED: std::sync::Once = std::sync::Once::new();
+
+    match Current::is_nodump(path) {
+        Ok(nodump) => nodump,
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            UNSUPPORTED.call_once(|| log::warn!("{}", e));
+            false
+        }
+        // NOTE: The file is reported by the walk, when it cannot be read.
+        Err(e) => {
+            log::debug!("Failed to read the flags of {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+/// Collects the paths to archive from `files`, given by [`TraversalArgs::files`],
+/// skipping the paths excluded by `traversal`.
+pub(crate) fn collect_traversal_items(
+    files: Vec<String>,
+    traversal: &TraversalArgs,
+    keep_dir: bool,
+    output: Option<&OutputArchive>,
+) -> io::Result<Vec<PathBuf>> {
+    let exclude = Exclude::new(traversal.exclude_patterns()?)?.with_nodump(traversal.nodump);
+    collect_items(
+        files,
+        traversal.recursive,
+        keep_dir,
+        traversal.gitignore,
+        traversal.follow_links,
+        exclude,
+        output,
+    )
+}
+
+/// Collects the paths to archive from `files`.
+/// Directories matching `exclude` are skipped with their contents.
+/// When `output` is given, the output archive and its split parts are skipped.
 pub(crate) fn collect_items<I: IntoIterator<Item = P>, P: Into<PathBuf>>(
     files: I,
     recursive: bool,
     keep_dir: bool,
     gitignore: bool,
     follow_links: bool,
-    exclude: Option<Vec<PathBuf>>,
+    exclude: Exclude,
+    output: Option<&OutputArchive>,
 ) -> io::Result<Vec<PathBuf>> {
     let mut files = files.into_iter();
-    let exclude = exclude.into_iter().flatten().map(|path| path.normalize());
     if let Some(p) = files.next() {
         let mut builder = ignore::WalkBuilder::new(p.into());
         for p in files {
             builder.add(p.into());
         }
-        for exclude_path in exclude {
-            builder.add_ignore(exclude_path);
-        }
+        let exclude = Arc::new(exclude);
+        let filter = Arc::clone(&exclude);
         builder
+            .filter_entry(move |entry| !filter.is_excluded(entry.path()))
             .max_depth(if recursive { None } else { Some(0) })
             .hidden(false)
             .ignore(false)
             .git_ignore(gitignore)
+            // NOTE: `--gitignore` is given explicitly, so .gitignore is used outside git repositories too.
+            .require_git(false)
             .git_exclude(false)
             .git_global(false)
             .parents(false)
             .follow_links(follow_links)
             .ignore_case_insensitive(false);
         let walker = builder.build();
-        walker
+        let mut skipped = Vec::new();
+        let items = walker
             .filter_map(|path| match path {
                 Ok(path) => {
                     let path = path.into_path();
-                    (keep_dir || path.is_file()).then_some(Ok(path))
+                    // NOTE: The paths given as the roots of the walk are not filtered by the walker.
+                    if exclude.is_excluded(&path) {
+                        return None;
+                    }
+                    // NOTE: Skip directories like `.` and `/` that have no name to store.
+                    if path.is_dir()
+                        && EntryName::try_new(&path).is_err_and(|e| e == EntryNameError::Empty)
+                    {
+                        return None;
+                    }
+                    if path.is_file() && output.is_some_and(|it| it.contains(&path)) {
+                        skipped.push(path);
+                        return None;
+                    }
+                    // NOTE: A symbolic link whose target does not exist is kept as the link itself,
+                    // since `is_file` follows the link.
+                    let dangling = || path.is_symlink() && fs::metadata(&path).is_err();
+                    (keep_dir || path.is_file() || dangling()).then_some(Ok(path))
                 }
                 Err(e) => Some(Err(e)),
             })
             .collect::<Result<Vec<_>, _>>()
-            .map_err(io::Error::other)
+            .map_err(io::Error::other)?;
+        if !skipped.is_empty() {
+            log::info!(
+                "Skip the archive being written: {}",
+                skipped
+                    .iter()
+                    .map(|it| it.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        Ok(items)
     } else {
         Ok(Vec::new())
     }
 }
 
+/// Checks that the existing file at `path` can be replaced by an archive.
+/// A regular file must be empty or a PNA archive, unless `--force` is given
+/// or the file is kept by `--backup`.
+pub(crate) fn check_replaceable(path: &Path, args: &ReplaceArgs) -> io::Result<()> {
+    if args.force || args.backup.is_some() {
+        return Ok(());
+    }
+    let metadata = match fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Ok(());
+    }
+    let is_pna = match utils::fs::is_pna(path) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => false,
+        result => result?,
+    };
+    if is_pna {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!(
+            "{} is not a PNA archive, use --force to replace it",
+            path.display()
+        ),
+    ))
+}
+
+/// Renames the existing file at `path` to the name with the suffix of `--backup` appended.
+pub(crate) fn backup_existing(path: &Path, args: &ReplaceArgs) -> io::Result<()> {
+    let Some(suffix) = &args.backup else {
+        return Ok(());
+    };
+    if fs::symlink_metadata(path).is_err() {
+        return Ok(());
+    }
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(suffix);
+    log::info!(
+        "Back up {} to {}",
+        path.display(),
+        Path::new(&backup).display()
+    );
+    fs::rename(path, backup)
+}
+
+/// Returns the entry name of the file at `path`, rejecting the names that [EntryName::try_new] rejects.
+///
+/// Paths that are not valid UTF-8 are converted lossily, as they were before the validation.
+pub(crate) fn entry_name(path: &Path) -> io::Result<EntryName> {
+    EntryName::try_new(&*path.to_string_lossy()).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: invalid entry name: {}", path.display(), e),
+        )
+    })
+}
+
+/// Files larger than this are not read into memory at once.
+pub(crate) const FILE_SIZE_THRESHOLD: u64 = 50 * 1024 * 1024;
+
+/// Returns `true` if `path` is a regular file larger than [FILE_SIZE_THRESHOLD].
+pub(crate) fn is_large_file(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|meta| meta.is_file() && FILE_SIZE_THRESHOLD < meta.len())
+}
+
+/// Number of items mapped at once per thread by [`par_map_ordered`] and searched at once by grep.
+pub(crate) const MAP_WINDOW_PER_THREAD: usize = 2;
+
+/// Runs `f` on the current rayon thread pool and waits for it.
+///
+/// Outside a thread pool, as with `--threads 1`, `f` runs on the current thread,
+/// so that the global thread pool is not spawned.
+pub(crate) fn run_in_pool<F>(f: F)
+where
+    F: FnOnce() + Send,
+{
+    if rayon::current_thread_index().is_some() {
+        rayon::scope_fifo(|s| s.spawn_fifo(|_| f()));
+    } else {
+        f();
+    }
+}
+
+/// Maps `items` with `f` on the threads of the current rayon thread pool,
+/// and returns an iterator over the results in the order of `items`.
+///
+/// The items are mapped in windows of a few items per thread, so only the results of a window
+/// are kept in memory. Outside a thread pool, as with `--threads 1`, the items are mapped one by one
+/// on the current thread when the iterator is advanced.
+pub(crate) fn par_map_ordered<I, T, F>(items: Vec<I>, f: F) -> impl Iterator<Item = T>
+where
+    I: Send,
+    T: Send,
+    F: Fn(I) -> T + Sync + Send,
+{
+    let window = match rayon::current_thread_index() {
+        Some(_) => rayon::current_num_threads() * MAP_WINDOW_PER_THREAD,
+        None => 1,
+    };
+    let mut items = items.into_iter();
+    let mut results = Vec::new().into_iter();
+    std::iter::from_fn(move || {
+        if let Some(result) = results.next() {
+            return Some(result);
+        }
+        if window == 1 {
+            return items.next().map(&f);
+        }
+        let chunk = items.by_ref().take(window).collect::<Vec<_>>();
+        results = chunk
+            .into_par_iter()
+            .map(&f)
+            .collect::<Vec<_>>()
+            .into_iter();
+        results.next()
+    })
+}
+
 pub(crate) fn create_entry(
     path: &Path,
     CreateOptions {
         option,
         keep_options,
         owner_options,
+        platform_tags,
     }: &CreateOptions,
 ) -> io::Result<NormalEntry> {
+    let name = entry_name(path)?;
     if path.is_symlink() {
         let source = fs::read_link(path)?;
-        let entry = EntryBuilder::new_symbolic_link(
-            EntryName::from_lossy(path),
-            EntryReference::from_lossy(source),
-        )?;
-        return apply_metadata(entry, path, keep_options, owner_options)?.build();
+        let entry =
+            EntryBuilder::new_symbolic_link(name.clone(), EntryReference::from_lossy(source))?;
+        let entry = apply_metadata(entry, path, keep_options, owner_options)?;
+        return platform_tags.apply(entry, &name).build();
     } else if path.is_file() {
-        let mut entry = EntryBuilder::new_file(EntryName::from_lossy(path), option)?;
+        let mut entry = EntryBuilder::new_file(name.clone(), option)?;
         #[cfg(feature = "memmap")]
         {
-            const FILE_SIZE_THRESHOLD: u64 = 50 * 1024 * 1024;
             let meta = fs::metadata(path)?;
             if FILE_SIZE_THRESHOLD < meta.len() {
                 let file = utils::mmap::Mmap::open(path)?;
@@ -139,10 +545,12 @@ pub(crate) fn create_entry(
         {
             entry.write_all(&fs::read(path)?)?;
         }
-        return apply_metadata(entry, path, keep_options, owner_options)?.build();
+        let entry = apply_metadata(entry, path, keep_options, owner_options)?;
+        return platform_tags.apply(entry, &name).build();
     } else if path.is_dir() {
-        let entry = EntryBuilder::new_dir(EntryName::from_lossy(path));
-        return apply_metadata(entry, path, keep_options, owner_options)?.build();
+        let entry = EntryBuilder::new_dir(name.clone());
+        let entry = apply_metadata(entry, path, keep_options, owner_options)?;
+        return platform_tags.apply(entry, &name).build();
     }
     Err(io::Error::new(
         io::ErrorKind::Unsupported,
@@ -150,6 +558,51 @@ pub(crate) fn create_entry(
     ))
 }
 
+/// Creates a hard link entry of the file at `path` that links to the entry of the file at `original`.
+pub(crate) fn create_hard_link_entry(
+    path: &Path,
+    original: &Path,
+    CreateOptions {
+        option: _,
+        keep_options,
+        owner_options,
+        platform_tags,
+    }: &CreateOptions,
+) -> io::Result<NormalEntry> {
+    let name = entry_name(path)?;
+    let reference = utils::relative_link(name.as_str(), entry_name(original)?.as_str());
+    let entry = EntryBuilder::new_hard_link(name.clone(), EntryReference::from(reference))?;
+    let entry = apply_metadata(entry, path, keep_options, owner_options)?;
+    platform_tags.apply(entry, &name).build()
+}
+
+/// Writes the regular file at `path` into `archive` without keeping the whole data in memory,
+/// adding the bytes read from the file to `progress`.
+///
+/// The entry is left incomplete if an error occurs, so the archive should be discarded.
+pub(crate) fn write_entry_streaming<W: Write>(
+    archive: &mut Archive<W>,
+    path: &Path,
+    CreateOptions {
+        option,
+        keep_options,
+        owner_options,
+        platform_tags,
+    }: &CreateOptions,
+    progress: &Progress,
+) -> io::Result<usize> {
+    let file = fs::File::open(path)?;
+    let name = entry_name(path)?;
+    let writer = archive.entry_writer(name.clone(), option)?;
+    let writer = apply_metadata(writer, path, keep_options, owner_options)?;
+    let mut writer = platform_tags.apply(writer, &name);
+    io::copy(
+        &mut io::BufReader::with_capacity(1024 * 1024, progress.wrap_read(file)),
+        &mut writer,
+    )?;
+    writer.finish()
+}
+
 pub(crate) fn entry_option(
     compression: CompressionAlgorithmArgs,
     cipher: CipherAlgorithmArgs,
@@ -172,97 +625,304 @@ pub(crate) fn entry_option(
     option_builder.build()
 }
 
+/// Write settings used by the entries of an existing archive.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct ExistingSettings {
+    compression: pna::Compression,
+    encryption: pna::Encryption,
+    cipher_mode: pna::CipherMode,
+    hash_algorithm: Option<HashAlgorithm>,
+}
+
+impl ExistingSettings {
+    /// Settings used by `entry`.
+    pub(crate) fn of_entry<T>(entry: &NormalEntry<T>) -> Self {
+        let header = entry.header();
+        Self {
+            compression: header.compression(),
+            encryption: header.encryption(),
+            cipher_mode: header.cipher_mode(),
+            hash_algorithm: entry.password_hash().and_then(hash_algorithm_from_phc),
+        }
+    }
+
+    /// Whether the entries with these settings are encrypted.
+    #[inline]
+    pub(crate) fn is_encrypted(&self) -> bool {
+        self.encryption != pna::Encryption::No
+    }
+
+    /// Detects the settings used by the most entries of the archive at `path`.
+    /// Ties are broken in favor of the latest entry.
+    /// Returns `None` if the archive has no entries.
+    pub(crate) fn detect(path: &Path) -> io::Result<Option<Self>> {
+        let mut votes = HashMap::new();
+        let mut index = 0;
+        run_read_entries(PathArchiveProvider::new(path), |entry| {
+            let (compression, encryption, cipher_mode, password_hash) = match &entry? {
+                ReadEntry::Solid(s) => (
+                    s.header().compression(),
+                    s.header().encryption(),
+                    s.header().cipher_mode(),
+                    s.password_hash().and_then(hash_algorithm_from_phc),
+                ),
+                ReadEntry::Normal(r) => (
+                    r.header().compression(),
+                    r.header().encryption(),
+                    r.header().cipher_mode(),
+                    r.password_hash().and_then(hash_algorithm_from_phc),
+                ),
+            };
+            let vote = votes
+                .entry((compression, encryption, cipher_mode))
+                .or_insert((0, 0, None));
+            vote.0 += 1;
+            vote.1 = index;
+            vote.2 = password_hash.or(vote.2);
+            index += 1;
+            Ok(())
+        })?;
+        Ok(votes
+            .into_iter()
+            .max_by_key(|(_, (count, last, _))| (*count, *last))
+            .map(
+                |((compression, encryption, cipher_mode), (_, _, hash_algorithm))| Self {
+                    compression,
+                    encryption,
+                    cipher_mode,
+                    hash_algorithm,
+                },
+            ))
+    }
+}
+
+/// Parses the algorithm and parameters of a password hash in the PHC string format.
+fn hash_algorithm_from_phc(phc: &str) -> Option<HashAlgorithm> {
+    let mut fields = phc.split('$').skip(1);
+    let id = fields.next()?;
+    let params = fields
+        .filter(|it| it.contains('='))
+        .flat_map(|it| it.split(','))
+        .filter_map(|it| it.split_once('='))
+        .collect::<HashMap<_, _>>();
+    let param = |key: &str| params.get(key).and_then(|it: &&str| it.parse().ok());
+    match id {
+        "argon2id" => Some(HashAlgorithm::argon2id_with(
+            param("t"),
+            param("m"),
+            param("p"),
+        )),
+        "pbkdf2-sha256" => Some(HashAlgorithm::pbkdf2_sha256_with(param("i"))),
+        _ => None,
+    }
+}
+
+/// Same as [entry_option], but falls back to `existing` for the settings not given explicitly.
+pub(crate) fn entry_option_with_existing(
+    compression: CompressionAlgorithmArgs,
+    cipher: CipherAlgorithmArgs,
+    hash: HashAlgorithmArgs,
+    password: Option<&str>,
+    existing: Option<&ExistingSettings>,
+) -> WriteOptions {
+    let Some(existing) = existing else {
+        return entry_option(compression, cipher, hash, password);
+    };
+    let encrypted = existing.encryption != pna::Encryption::No;
+    if encrypted && password.is_none() {
+        log::warn!(
+            "Existing entries are encrypted, but new entries are not since no password is given"
+        );
+    }
+    let (algorithm, level) = if compression.is_specified() {
+        compression.algorithm()
+    } else {
+        (existing.compression, None)
+    };
+    let (encryption, cipher_mode) = if cipher.is_specified() || !encrypted {
+        (cipher.algorithm(), cipher.mode())
+    } else {
+        (existing.encryption, existing.cipher_mode)
+    };
+    let hash_algorithm = match existing.hash_algorithm {
+        Some(hash_algorithm) if !hash.is_specified() => hash_algorithm,
+        _ => hash.algorithm(),
+    };
+    let mut option_builder = WriteOptions::builder();
+    option_builder
+        .compression(algorithm)
+        .compression_level(level.unwrap_or_default())
+        .encryption(if password.is_some() {
+            encryption
+        } else {
+            pna::Encryption::No
+        })
+        .cipher_mode(cipher_mode)
+        .hash_algorithm(hash_algorithm)
+        .password(password);
+    option_builder.build()
+}
+
+/// Entry under construction that the metadata of a file can be applied to.
+#[cfg_attr(not(all(unix, feature = "acl")), allow(dead_code))]
+pub(crate) trait MetadataTarget {
+    fn created(&mut self, since_unix_epoch: Duration);
+    fn modified(&mut self, since_unix_epoch: Duration);
+    fn accessed(&mut self, since_unix_epoch: Duration);
+    fn permission(&mut self, permission: Permission);
+    fn add_xattr(&mut self, xattr: ExtendedAttribute);
+    fn add_extra_chunk(&mut self, chunk: RawChunk);
+}
+
+impl MetadataTarget for EntryBuilder {
+    #[inline]
+    fn created(&mut self, since_unix_epoch: Duration) {
+        EntryBuilder::created(self, since_unix_epoch);
+    }
+
+    #[inline]
+    fn modified(&mut self, since_unix_epoch: Duration) {
+        EntryBuilder::modified(self, since_unix_epoch);
+    }
+
+    #[inline]
+    fn accessed(&mut self, since_unix_epoch: Duration) {
+        EntryBuilder::accessed(self, since_unix_epoch);
+    }
+
+    #[inline]
+    fn permission(&mut self, permission: Permission) {
+        EntryBuilder::permission(self, permission);
+    }
+
+    #[inline]
+    fn add_xattr(&mut self, xattr: ExtendedAttribute) {
+        EntryBuilder::add_xattr(self, xattr);
+    }
+
+    #[inline]
+    fn add_extra_chunk(&mut self, chunk: RawChunk) {
+        EntryBuilder::add_extra_chunk(self, chunk);
+    }
+}
+
+impl<W: Write> MetadataTarget for EntryWriter<'_, W> {
+    #[inline]
+    fn created(&mut self, since_unix_epoch: Duration) {
+        EntryWriter::created(self, since_unix_epoch);
+    }
+
+    #[inline]
+    fn modified(&mut self, since_unix_epoch: Duration) {
+        EntryWriter::modified(self, since_unix_epoch);
+    }
+
+    #[inline]
+    fn accessed(&mut self, since_unix_epoch: Duration) {
+        EntryWriter::accessed(self, since_unix_epoch);
+    }
+
+    #[inline]
+    fn permission(&mut self, permission: Permission) {
+        EntryWriter::permission(self, permission);
+    }
+
+    #[inline]
+    fn add_xattr(&mut self, xattr: ExtendedAttribute) {
+        EntryWriter::add_xattr(self, xattr);
+    }
+
+    #[inline]
+    fn add_extra_chunk(&mut self, chunk: RawChunk) {
+        EntryWriter::add_extra_chunk(self, chunk);
+    }
+}
+
 #[cfg_attr(target_os = "wasi", allow(unused_variables))]
-pub(crate) fn apply_metadata(
-    mut entry: EntryBuilder,
+pub(crate) fn apply_metadata<T: MetadataTarget>(
+    mut entry: T,
     path: &Path,
     keep_options: &KeepOptions,
     owner_options: &OwnerOptions,
-) -> io::Result<EntryBuilder> {
-    if keep_options.keep_timestamp || keep_options.keep_permission {
+) -> io::Result<T> {
+    if keep_options.keep_timestamp || keep_options.keep_owner || keep_options.keep_mode {
         let meta = fs::metadata(path)?;
         if keep_options.keep_timestamp {
+            let clamp = |time: Duration| keep_options.clamp_time.map_or(time, |it| time.min(it));
             if let Ok(c) = meta.created() {
                 if let Ok(created_since_unix_epoch) = c.duration_since(UNIX_EPOCH) {
-                    entry.created(created_since_unix_epoch);
+                    entry.created(clamp(created_since_unix_epoch));
                 }
             }
             if let Ok(m) = meta.modified() {
                 if let Ok(modified_since_unix_epoch) = m.duration_since(UNIX_EPOCH) {
-                    entry.modified(modified_since_unix_epoch);
+                    entry.modified(clamp(modified_since_unix_epoch));
                 }
             }
             if let Ok(a) = meta.accessed() {
                 if let Ok(accessed_since_unix_epoch) = a.duration_since(UNIX_EPOCH) {
-                    entry.accessed(accessed_since_unix_epoch);
+                    entry.accessed(clamp(accessed_since_unix_epoch));
                 }
             }
         }
         #[cfg(unix)]
-        if keep_options.keep_permission {
-            use crate::utils::fs::{Group, User};
-            use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        if keep_options.keep_owner || keep_options.keep_mode {
+            use std::os::unix::fs::PermissionsExt;
 
-            let mode = meta.permissions().mode() as u16;
-            let uid = owner_options.uid.unwrap_or(meta.uid());
-            let gid = owner_options.gid.unwrap_or(meta.gid());
+            let mode = if keep_options.keep_mode {
+                meta.permissions().mode() as u16
+            } else {
+                placeholder_mode(meta.is_dir())
+            };
+            let (uid, uname, gid, gname) = if keep_options.keep_owner {
+                owner_of(&meta, owner_options)?
+            } else {
+                (0, String::new(), 0, String::new())
+            };
             entry.permission(pna::Permission::new(
                 uid.into(),
-                match owner_options.uname.as_deref() {
-                    None => User::from_uid(uid.into())?.name().into(),
-                    Some(uname) => uname.into(),
-                },
+                uname,
                 gid.into(),
-                match owner_options.gname.as_deref() {
-                    None => Group::from_gid(gid.into())?.name().into(),
-                    Some(gname) => gname.into(),
-                },
+                gname,
                 mode,
             ));
         }
         #[cfg(windows)]
-        if keep_options.keep_permission {
+        if keep_options.keep_owner || keep_options.keep_mode {
             use crate::utils::os::windows::{fs::stat, security::SecurityDescriptor};
 
             let sd = SecurityDescriptor::try_from(path)?;
-            let stat = stat(sd.path.as_ptr() as _)?;
-            let mode = stat.st_mode;
-            let user = sd.owner_sid()?;
-            let group = sd.group_sid()?;
-            entry.permission(pna::Permission::new(
-                u64::MAX,
-                owner_options.uname.clone().unwrap_or(user.name),
-                u64::MAX,
-                owner_options.gname.clone().unwrap_or(group.name),
-                mode,
-            ));
+            let mode = if keep_options.keep_mode {
+                stat(sd.path.as_ptr() as _)?.st_mode
+            } else {
+                placeholder_mode(meta.is_dir())
+            };
+            let (uid, uname, gid, gname) = if keep_options.keep_owner {
+                (
+                    u64::MAX,
+                    owner_options.uname.clone().unwrap_or(sd.owner_sid()?.name),
+                    u64::MAX,
+                    owner_options.gname.clone().unwrap_or(sd.group_sid()?.name),
+                )
+            } else {
+                (0, String::new(), 0, String::new())
+            };
+            entry.permission(pna::Permission::new(uid, uname, gid, gname, mode));
         }
     }
     #[cfg(feature = "acl")]
-    {
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "freebsd",
-            target_os = "macos",
-            windows
-        ))]
-        if keep_options.keep_acl {
-            use crate::chunk;
-            use pna::RawChunk;
-            let acl = utils::acl::get_facl(path)?;
-            entry.add_extra_chunk(RawChunk::from_data(chunk::faCl, acl.platform.to_bytes()));
-            for ace in acl.entries {
-                entry.add_extra_chunk(RawChunk::from_data(chunk::faCe, ace.to_bytes()));
+    if keep_options.keep_acl {
+        use crate::chunk;
+        use crate::utils::os::{Current, Platform};
+        match Current::get_facl(path) {
+            Ok(acl) => {
+                entry.add_extra_chunk(RawChunk::from_data(chunk::faCl, acl.platform.to_bytes()));
+                for ace in acl.entries {
+                    entry.add_extra_chunk(RawChunk::from_data(chunk::faCe, ace.to_bytes()));
+                }
             }
-        }
-        #[cfg(not(any(
-            target_os = "linux",
-            target_os = "freebsd",
-            target_os = "macos",
-            windows
-        )))]
-        if keep_options.keep_acl {
-            log::warn!("Currently acl is not supported on this platform.");
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => log::warn!("{}", e),
+            Err(e) => return Err(e),
         }
     }
     #[cfg(not(feature = "acl"))]
@@ -272,7 +932,9 @@ pub(crate) fn apply_metadata(
     #[cfg(unix)]
     if keep_options.keep_xattr {
         for attr in utils::os::unix::fs::xattrs::get_xattrs(path)? {
-            entry.add_xattr(attr);
+            let compress =
+                keep_options.compress_xattr && attr.value().len() > COMPRESS_XATTR_THRESHOLD;
+            entry.add_xattr(attr.with_compression(compress));
         }
     }
     #[cfg(not(unix))]
@@ -282,16 +944,65 @@ pub(crate) fn apply_metadata(
     Ok(entry)
 }
 
+/// Returns the uid, user name, gid and group name of the owner of the file to store in the archive.
+#[cfg(unix)]
+fn owner_of(
+    meta: &fs::Metadata,
+    owner_options: &OwnerOptions,
+) -> io::Result<(u32, String, u32, String)> {
+    use crate::utils::fs::{Group, User};
+    use std::os::unix::fs::MetadataExt;
+
+    let uid = match owner_options.uid {
+        Some(uid) => uid,
+        None => owner_options.uid_map.to_archive(meta.uid())?,
+    };
+    let gid = match owner_options.gid {
+        Some(gid) => gid,
+        None => owner_options.gid_map.to_archive(meta.gid())?,
+    };
+    // NOTE: The names of the mapped ids may not correspond to the owners on this system.
+    let uname = match owner_options.uname.as_deref() {
+        Some(uname) => uname.into(),
+        None if owner_options.uid_map.is_empty() => User::from_uid(uid.into())?.name().into(),
+        None if owner_options.map_names => User::from_uid(uid.into())
+            .map(|it| it.name().into())
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+    let gname = match owner_options.gname.as_deref() {
+        Some(gname) => gname.into(),
+        None if owner_options.gid_map.is_empty() => Group::from_gid(gid.into())?.name().into(),
+        None if owner_options.map_names => Group::from_gid(gid.into())
+            .map(|it| it.name().into())
+            .unwrap_or_default(),
+        None => String::new(),
+    };
+    Ok((uid, uname, gid, gname))
+}
+
+/// Divides `entry_part` into the parts of at most `max` bytes, the first of which is at most `first` bytes.
+///
+/// Fails if a chunk that cannot be divided does not fit in a part of `max` bytes.
 pub(crate) fn split_to_parts(
     mut entry_part: EntryPart<&[u8]>,
     first: usize,
     max: usize,
-) -> Vec<EntryPart<&[u8]>> {
+) -> io::Result<Vec<EntryPart<&[u8]>>> {
     let mut parts = vec![];
     let mut split_size = first;
     loop {
         match entry_part.split(split_size) {
             (write_part, Some(remaining_part)) => {
+                if write_part.bytes_len() == 0 && split_size >= max {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "A chunk of {} bytes does not fit in a part of {max} bytes",
+                            remaining_part.max_unsplittable_len()
+                        ),
+                    ));
+                }
                 parts.push(write_part);
                 entry_part = remaining_part;
                 split_size = max;
@@ -302,13 +1013,20 @@ pub(crate) fn split_to_parts(
             }
         }
     }
-    parts
+    Ok(parts)
 }
 
 pub(crate) trait ArchiveProvider {
     type Source: Read;
     fn initial_source(&self) -> io::Result<Self::Source>;
     fn next_source(&self, n: usize) -> io::Result<Self::Source>;
+
+    /// Returns the summaries of the remaining entries of `archive`.
+    /// Sources that can seek override this to seek over the data of the entries.
+    #[inline]
+    fn entry_summaries(archive: &mut Archive<Self::Source>) -> EntrySummaries<'_, Self::Source> {
+        archive.entries_metadata()
+    }
 }
 
 pub(crate) struct PathArchiveProvider<'p>(&'p Path);
@@ -325,12 +1043,18 @@ impl ArchiveProvider for PathArchiveProvider<'_> {
 
     #[inline]
     fn initial_source(&self) -> io::Result<Self::Source> {
-        fs::File::open(self.0)
+        fs::File::open(self.0).map_err(|e| detect_incomplete_part(self.0, e))
     }
 
     #[inline]
     fn next_source(&self, n: usize) -> io::Result<Self::Source> {
-        fs::File::open(self.0.with_part(n).unwrap())
+        let path = self.0.with_part(n).unwrap();
+        fs::File::open(&path).map_err(|e| detect_incomplete_part(&path, e))
+    }
+
+    #[inline]
+    fn entry_summaries(archive: &mut Archive<Self::Source>) -> EntrySummaries<'_, Self::Source> {
+        archive.entries_metadata_with_seek()
     }
 }
 
@@ -357,6 +1081,112 @@ impl ArchiveProvider for StdinArchiveProvider {
     }
 }
 
+/// Provides an archive file for `--resume`, counting the bytes read from its first part
+/// and seeking past the entries completed by the previous extraction.
+pub(crate) struct ResumeArchiveProvider<'p> {
+    inner: PathArchiveProvider<'p>,
+    state: &'p ResumeState,
+}
+
+impl<'p> ResumeArchiveProvider<'p> {
+    #[inline]
+    pub(crate) const fn new(path: &'p Path, state: &'p ResumeState) -> Self {
+        Self {
+            inner: PathArchiveProvider::new(path),
+            state,
+        }
+    }
+}
+
+impl ArchiveProvider for ResumeArchiveProvider<'_> {
+    type Source = io::Chain<io::Cursor<Vec<u8>>, CountingReader<fs::File>>;
+
+    #[inline]
+    fn initial_source(&self) -> io::Result<Self::Source> {
+        let mut file = self.inner.initial_source()?;
+        // NOTE: The archive header is read as is, and is followed by the chunks from the offset.
+        let mut header = Vec::new();
+        if let Some(offset) = self.state.offset() {
+            header.resize(self.state.header_len() as usize, 0);
+            file.read_exact(&mut header)?;
+            file.seek(io::SeekFrom::Start(offset))?;
+            log::info!("Resume reading the archive at byte {offset}");
+        }
+        Ok(io::Cursor::new(header).chain(CountingReader::new(file, self.state.position())))
+    }
+
+    #[inline]
+    fn next_source(&self, n: usize) -> io::Result<Self::Source> {
+        self.state.leave_first_part();
+        let file = self.inner.next_source(n)?;
+        Ok(io::Cursor::new(Vec::new()).chain(CountingReader::new(file, self.state.position())))
+    }
+}
+
+/// Provides an archive streamed from an http(s) URL.
+#[cfg(feature = "http")]
+pub(crate) struct HttpArchiveProvider<'a> {
+    url: &'a str,
+    headers: &'a [HttpHeader],
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "http")]
+impl<'a> HttpArchiveProvider<'a> {
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+    const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+    #[inline]
+    pub(crate) fn new(url: &'a str, args: &'a HttpArgs) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(Self::CONNECT_TIMEOUT)
+            .timeout_read(Self::READ_TIMEOUT)
+            .build();
+        Self {
+            url,
+            headers: &args.http_header,
+            agent,
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl ArchiveProvider for HttpArchiveProvider<'_> {
+    type Source = Box<dyn Read + Send + Sync>;
+
+    #[inline]
+    fn initial_source(&self) -> io::Result<Self::Source> {
+        let mut request = self.agent.get(self.url);
+        for header in self.headers {
+            request = request.set(&header.name, &header.value);
+        }
+        match request.call() {
+            Ok(response) => Ok(response.into_reader()),
+            Err(ureq::Error::Status(code, _)) => Err(io::Error::new(
+                if code == 404 {
+                    io::ErrorKind::NotFound
+                } else {
+                    io::ErrorKind::Other
+                },
+                format!("{}: server responded with status {code}", self.url),
+            )),
+            Err(e) => Err(io::Error::other(format!("{}: {e}", self.url))),
+        }
+    }
+
+    #[inline]
+    fn next_source(&self, n: usize) -> io::Result<Self::Source> {
+        // NOTE: Not NotFound, which is taken as a missing part and ends reading quietly.
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "{}: reading split archives over http(s) is not supported, but part {n} is required",
+                self.url
+            ),
+        ))
+    }
+}
+
 pub(crate) trait TransformStrategy {
     fn transform<W, T, F>(
         archive: &mut Archive<W>,
@@ -466,7 +1296,9 @@ where
         processor(&mut archive)?;
         if archive.has_next_archive() {
             num_archive += 1;
-            let next_reader = provider.next_source(num_archive)?;
+            let Some(next_reader) = next_source(&provider, &archive, num_archive)? else {
+                break;
+            };
             archive = archive.read_next_archive(next_reader)?;
         } else {
             break;
@@ -475,9 +1307,52 @@ where
     Ok(())
 }
 
+/// Opens the next part of a split archive, which the part read so far promised with ANXT.
+/// Returns `None` when the next part is missing but every entry read so far is complete,
+/// so that a part split on entry boundaries can be read on its own.
+/// A missing part is an error when an entry continues into it.
+fn next_source<P: ArchiveProvider, T>(
+    provider: &P,
+    archive: &Archive<T>,
+    n: usize,
+) -> io::Result<Option<P::Source>> {
+    match provider.next_source(n) {
+        Ok(source) => Ok(Some(source)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound && !archive.has_incomplete_entry() => {
+            log::warn!(
+                "Part {n} of the archive was not found, so stop reading at part {}",
+                n - 1
+            );
+            Ok(None)
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "an entry of part {} of the archive continues into part {n}, but it could not be opened: {e}",
+                n - 1
+            ),
+        )),
+        Err(e) => Err(e),
+    }
+}
+
 pub(crate) fn run_process_archive<'p, Provider, F>(
+    archive_provider: impl ArchiveProvider,
+    password_provider: Provider,
+    processor: F,
+) -> io::Result<()>
+where
+    Provider: FnMut() -> Option<&'p str>,
+    F: FnMut(io::Result<NormalEntry>) -> io::Result<()>,
+{
+    run_process_archive_with_cache(archive_provider, password_provider, None, processor)
+}
+
+/// Same as [run_process_archive], but reads the contents of solid entries through `solid_cache`.
+pub(crate) fn run_process_archive_with_cache<'p, Provider, F>(
     archive_provider: impl ArchiveProvider,
     mut password_provider: Provider,
+    solid_cache: Option<&SolidCache>,
     mut processor: F,
 ) -> io::Result<()>
 where
@@ -485,14 +1360,28 @@ where
     F: FnMut(io::Result<NormalEntry>) -> io::Result<()>,
 {
     let password = password_provider();
-    run_read_entries(archive_provider, |entry| match entry? {
-        ReadEntry::Solid(solid) => {
-            for s in solid.entries(password)? {
-                processor(s)?;
+    let mut solid_index = 0;
+    run_across_archive(archive_provider, |archive| {
+        let archive_id = archive.archive_id();
+        for entry in archive.entries() {
+            match entry? {
+                ReadEntry::Solid(solid) => {
+                    let entries: Box<dyn Iterator<Item = io::Result<NormalEntry>> + '_> =
+                        match solid_cache {
+                            Some(cache) => {
+                                cache.entries(&solid, archive_id, solid_index, password)?
+                            }
+                            None => Box::new(solid.entries(password)?),
+                        };
+                    solid_index += 1;
+                    for s in entries {
+                        processor(s)?;
+                    }
+                }
+                ReadEntry::Normal(regular) => processor(Ok(regular))?,
             }
-            Ok(())
         }
-        ReadEntry::Normal(regular) => processor(Ok(regular)),
+        Ok(())
     })
 }
 
@@ -513,7 +1402,9 @@ where
     {
         processor(&mut archive)?;
         if archive.has_next_archive() {
-            let next_reader = provider.next_source(num_archive)?;
+            let Some(next_reader) = next_source(&provider, &archive, num_archive)? else {
+                return Ok(());
+            };
             let file = utils::mmap::Mmap::try_from(next_reader)?;
             inner(
                 num_archive + 1,
@@ -547,8 +1438,24 @@ where
 
 #[cfg(feature = "memmap")]
 pub(crate) fn run_entries<'p, P, Provider, F>(
+    path: P,
+    password_provider: Provider,
+    processor: F,
+) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    Provider: FnMut() -> Option<&'p str>,
+    F: FnMut(io::Result<NormalEntry<std::borrow::Cow<[u8]>>>) -> io::Result<()>,
+{
+    run_entries_with_cache(path, password_provider, None, processor)
+}
+
+/// Same as [run_entries], but reads the contents of solid entries through `solid_cache`.
+#[cfg(feature = "memmap")]
+pub(crate) fn run_entries_with_cache<'p, P, Provider, F>(
     path: P,
     mut password_provider: Provider,
+    solid_cache: Option<&SolidCache>,
     mut processor: F,
 ) -> io::Result<()>
 where
@@ -557,14 +1464,24 @@ where
     F: FnMut(io::Result<NormalEntry<std::borrow::Cow<[u8]>>>) -> io::Result<()>,
 {
     let password = password_provider();
-    run_read_entries_mem(path, |entry| {
-        match entry? {
-            ReadEntry::Solid(s) => {
-                for r in s.entries(password)? {
-                    processor(r.map(Into::into))?;
+    let mut solid_index = 0;
+    run_across_archive_mem(path, |archive| {
+        let archive_id = archive.archive_id();
+        for entry in archive.entries_slice() {
+            match entry? {
+                ReadEntry::Solid(s) => {
+                    let entries: Box<dyn Iterator<Item = io::Result<NormalEntry>> + '_> =
+                        match solid_cache {
+                            Some(cache) => cache.entries(&s, archive_id, solid_index, password)?,
+                            None => Box::new(s.entries(password)?),
+                        };
+                    solid_index += 1;
+                    for r in entries {
+                        processor(r.map(Into::into))?;
+                    }
                 }
+                ReadEntry::Normal(r) => processor(Ok(r))?,
             }
-            ReadEntry::Normal(r) => processor(Ok(r))?,
         }
         Ok(())
     })
@@ -577,6 +1494,7 @@ pub(crate) fn run_transform_entry<'p, O, P, Provider, F, Transform>(
     mut password_provider: Provider,
     mut processor: F,
     _strategy: Transform,
+    replace: &ReplaceArgs,
 ) -> io::Result<()>
 where
     O: AsRef<Path>,
@@ -589,6 +1507,7 @@ where
 {
     let password = password_provider();
     let output_path = output_path.as_ref();
+    check_replaceable(output_path, replace)?;
     let random = rand::random::<usize>();
     let temp_dir_path = temp_dir().unwrap_or_else(|| {
         output_path
@@ -609,6 +1528,7 @@ where
     if let Some(parent) = output_path.parent() {
         fs::create_dir_all(parent)?;
     }
+    backup_existing(output_path, replace)?;
     utils::fs::mv(temp_path, output_path)?;
     Ok(())
 }
@@ -628,6 +1548,28 @@ where
     })
 }
 
+/// Reads the summaries of the entries without reading the data of file entries,
+/// and returns the number of solid entries skipped.
+pub(crate) fn run_read_entry_summaries<P, F>(
+    archive_provider: P,
+    mut processor: F,
+) -> io::Result<usize>
+where
+    P: ArchiveProvider,
+    F: FnMut(io::Result<EntrySummary>) -> io::Result<()>,
+{
+    let mut solid_entries = 0;
+    run_across_archive(archive_provider, |archive| {
+        let mut summaries = P::entry_summaries(archive);
+        for summary in &mut summaries {
+            processor(summary)?;
+        }
+        solid_entries += summaries.solid_entry_count();
+        Ok(())
+    })?;
+    Ok(solid_entries)
+}
+
 #[cfg(not(feature = "memmap"))]
 pub(crate) fn run_read_entries_path<F>(path: impl AsRef<Path>, processor: F) -> io::Result<()>
 where
@@ -643,6 +1585,7 @@ pub(crate) fn run_transform_entry<'p, O, P, Provider, F, Transform>(
     mut password_provider: Provider,
     mut processor: F,
     _strategy: Transform,
+    replace: &ReplaceArgs,
 ) -> io::Result<()>
 where
     O: AsRef<Path>,
@@ -653,6 +1596,7 @@ where
 {
     let password = password_provider();
     let output_path = output_path.as_ref();
+    check_replaceable(output_path, replace)?;
     let random = rand::random::<usize>();
     let temp_dir_path = temp_dir().unwrap_or_else(|| {
         output_path
@@ -673,6 +1617,7 @@ where
     if let Some(parent) = output_path.parent() {
         fs::create_dir_all(parent)?;
     }
+    backup_existing(output_path, replace)?;
     utils::fs::mv(temp_path, output_path)?;
     Ok(())
 }
@@ -693,84 +1638,346 @@ where
     run_process_archive(provider, password_provider, processor)
 }
 
+/// Where entries are divided into split archives.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub(crate) enum PartBoundary {
+    /// Entries are divided at chunk boundaries to fill each archive up to the maximum size.
+    Chunk,
+    /// Every entry stays within one archive, so that each archive can be read on its own.
+    /// Entries larger than the maximum size are divided at chunk boundaries unless `strict` is set.
+    Entry {
+        entries_per_part: Option<usize>,
+        strict: bool,
+    },
+}
+
+/// Bytes of the aIDN chunk, which is written only to the parts of an archive with an archive id.
+const ARCHIVE_ID_CHUNK_BYTES: usize = MIN_CHUNK_BYTES_SIZE + mem::size_of::<ArchiveId>();
+
+/// Bytes of a split archive taken by the chunks other than the entries,
+/// that is PNA_HEADER, AHED, aIDN, ANXT and AEND.
+pub(crate) const SPLIT_ARCHIVE_OVERHEAD_BYTES: usize =
+    PNA_HEADER.len() + MIN_CHUNK_BYTES_SIZE * 3 + 8 + ARCHIVE_ID_CHUNK_BYTES;
+
+/// Returns the bytes of a part taken by the chunks other than the entries,
+/// which include the aIDN chunk only if the archive has `archive_id`.
+#[inline]
+pub(crate) const fn split_archive_overhead_bytes(archive_id: Option<ArchiveId>) -> usize {
+    match archive_id {
+        Some(_) => SPLIT_ARCHIVE_OVERHEAD_BYTES,
+        None => SPLIT_ARCHIVE_OVERHEAD_BYTES - ARCHIVE_ID_CHUNK_BYTES,
+    }
+}
+
+/// Bytes of the smallest chunk that cannot be divided across parts,
+/// that is FHED of an entry with a one byte name.
+const MIN_UNSPLITTABLE_CHUNK_BYTES: usize = MIN_CHUNK_BYTES_SIZE + 6 + 1;
+
+/// Minimum size of a part of a split archive, whose capacity left after [SPLIT_ARCHIVE_OVERHEAD_BYTES]
+/// holds the smallest chunk that cannot be divided.
+/// Parts for entries with larger headers are rejected when the entries are written.
+pub(crate) const MIN_SPLIT_PART_BYTES: usize =
+    SPLIT_ARCHIVE_OVERHEAD_BYTES + MIN_UNSPLITTABLE_CHUNK_BYTES;
+
+pub(crate) fn check_split_part_size(max_file_size: usize) -> io::Result<()> {
+    let capacity = max_file_size.saturating_sub(SPLIT_ARCHIVE_OVERHEAD_BYTES);
+    if capacity < MIN_UNSPLITTABLE_CHUNK_BYTES {
+        return Err(usage_error(format!(
+            "The maximum size of a part must be at least {MIN_SPLIT_PART_BYTES} bytes, but {max_file_size} bytes was given, which leaves {capacity} bytes for the entries after {SPLIT_ARCHIVE_OVERHEAD_BYTES} bytes of the archive chunks"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the maximum size of a part that divides `entries` into `parts` parts.
+///
+/// Parts are filled up to the returned size, so the last part may be smaller than the others.
+/// The size leaves room for the chunks that cannot be divided, or for whole entries
+/// when `boundary` keeps every entry within one part, so that no more than `parts` parts are written.
+pub(crate) fn split_part_size(
+    entries: impl Iterator<Item = io::Result<impl Entry + Sized>>,
+    parts: usize,
+    boundary: PartBoundary,
+) -> io::Result<usize> {
+    let mut total = 0;
+    let mut unsplittable = 0;
+    for entry in entries {
+        let p = EntryPart::from(entry?);
+        total += p.bytes_len();
+        unsplittable = unsplittable.max(match boundary {
+            PartBoundary::Chunk => p.max_unsplittable_len(),
+            PartBoundary::Entry { .. } => p.bytes_len(),
+        });
+    }
+    // NOTE: A part ends when the next chunk does not fit in it, and the rest of a divided chunk
+    //       gets a new chunk header in the next part.
+    let slack = unsplittable.max(MIN_CHUNK_BYTES_SIZE) + MIN_CHUNK_BYTES_SIZE;
+    Ok((total.div_ceil(parts) + slack + SPLIT_ARCHIVE_OVERHEAD_BYTES).max(MIN_SPLIT_PART_BYTES))
+}
+
 pub(crate) fn write_split_archive(
     archive: impl AsRef<Path>,
+    archive_id: Option<ArchiveId>,
     entries: impl Iterator<Item = io::Result<impl Entry + Sized>>,
     max_file_size: usize,
+    boundary: PartBoundary,
 ) -> io::Result<()> {
+    let mut log_digest = |part: PartDigest| {
+        log::debug!(
+            "Part {}: {} bytes, sha256 {}",
+            part.part_number,
+            part.bytes_written,
+            utils::hash::hex(part.digest)
+        );
+        Ok(())
+    };
     write_split_archive_path(
         archive,
+        archive_id,
         entries,
         |base, n| base.with_part(n).unwrap(),
         max_file_size,
+        boundary,
+        log::log_enabled!(log::Level::Debug).then_some(&mut log_digest),
     )
 }
 
+/// Writes entries into split archives at the paths given by `get_part_path`.
+///
+/// Each part is written to a temporary path ending with `.tmp` and renamed to its final path
+/// only after it has been completely written and synced, so an interrupted run leaves
+/// a `.tmp` part behind instead of a part that looks complete.
 pub(crate) fn write_split_archive_path<F, P>(
     archive: impl AsRef<Path>,
+    archive_id: Option<ArchiveId>,
     entries: impl Iterator<Item = io::Result<impl Entry + Sized>>,
     mut get_part_path: F,
     max_file_size: usize,
+    boundary: PartBoundary,
+    on_part: Option<&mut dyn FnMut(PartDigest) -> io::Result<()>>,
 ) -> io::Result<()>
 where
     F: FnMut(&Path, usize) -> P,
     P: AsRef<Path>,
 {
+    check_split_part_size(max_file_size)?;
     let archive = archive.as_ref();
-    let first_item_path = get_part_path(archive, 1);
-    let first_item_path = first_item_path.as_ref();
-    let file = fs::File::create(first_item_path)?;
-    write_split_archive_writer(
+    let first_item_path = get_part_path(archive, 1).as_ref().to_path_buf();
+    let file = TempPartFile::create(first_item_path.clone())?;
+    let parts = write_split_archive_writer(
         file,
+        archive_id,
         entries,
-        |n| fs::File::create(get_part_path(archive, n)),
+        |n| TempPartFile::create(get_part_path(archive, n).as_ref().to_path_buf()),
         max_file_size,
-        |n| {
-            if n == 1 {
-                fs::rename(first_item_path, archive)?;
-            };
-            Ok(())
-        },
+        boundary,
+        on_part,
+    )?;
+    if parts == 1 {
+        fs::rename(first_item_path, archive)?;
+    }
+    Ok(())
+}
+
+/// Destination of a part of a split archive.
+pub(crate) trait PartWrite: Write {
+    /// Called once the part has been completely written.
+    fn complete(self) -> io::Result<()>;
+}
+
+/// Part of a split archive that is written to a temporary path and renamed to its final path once completed.
+pub(crate) struct TempPartFile {
+    file: fs::File,
+    temp_path: PathBuf,
+    path: PathBuf,
+}
+
+impl TempPartFile {
+    #[inline]
+    pub(crate) fn create(path: PathBuf) -> io::Result<Self> {
+        let temp_path = temp_part_path(&path);
+        let file = fs::File::create(&temp_path)?;
+        Ok(Self {
+            file,
+            temp_path,
+            path,
+        })
+    }
+}
+
+impl Write for TempPartFile {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl PartWrite for TempPartFile {
+    #[inline]
+    fn complete(self) -> io::Result<()> {
+        self.file.sync_all()?;
+        drop(self.file);
+        fs::rename(self.temp_path, self.path)
+    }
+}
+
+/// Temporary path that a part is written to until it is completed.
+pub(crate) fn temp_part_path(path: &Path) -> PathBuf {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    temp_path.into()
+}
+
+/// Fails with an "incomplete split set" error when the part at `path` was not found
+/// because it was never completely written.
+fn detect_incomplete_part(path: &Path, e: io::Error) -> io::Error {
+    if e.kind() != io::ErrorKind::NotFound || !temp_part_path(path).exists() {
+        return e;
+    }
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!(
+            "incomplete split set: {} was not completely written",
+            path.display()
+        ),
     )
 }
 
-pub(crate) fn write_split_archive_writer<W, F, C>(
+/// Collects the parts of the archive at `path`, which is either the archive, one of its parts
+/// or the name of the archive without the part number, in the order of the part numbers.
+///
+/// An archive that is not split is returned as the only part.
+pub(crate) fn collect_split_archives(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let base = path.remove_part().unwrap_or_else(|| path.to_path_buf());
+    if base == path && path.exists() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let mut parts = Vec::new();
+    let mut num = 1;
+    while let Some(part) = base.with_part(num).filter(|it| it.exists()) {
+        parts.push(part);
+        num += 1;
+    }
+    if !parts.is_empty() {
+        return Ok(parts);
+    }
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} is not exists", path.display()),
+    ))
+}
+
+/// Writes entries into split archives.
+/// When `archive_id` is `None`, no archive id is recorded, so that pna 0.21.0 or earlier can read the parts.
+///
+/// When `on_part` is given, the digest of each part is computed while it is written
+/// and passed to `on_part` once the part is completed.
+///
+/// Returns the number of the written parts.
+pub(crate) fn write_split_archive_writer<W, F>(
     initial_writer: W,
+    archive_id: Option<ArchiveId>,
     entries: impl Iterator<Item = io::Result<impl Entry + Sized>>,
     mut get_next_writer: F,
     max_file_size: usize,
-    mut on_complete: C,
-) -> io::Result<()>
+    boundary: PartBoundary,
+    mut on_part: Option<&mut dyn FnMut(PartDigest) -> io::Result<()>>,
+) -> io::Result<usize>
 where
-    W: Write,
+    W: PartWrite,
     F: FnMut(usize) -> io::Result<W>,
-    C: FnMut(usize) -> io::Result<()>,
 {
+    check_split_part_size(max_file_size)?;
+    let (report, digests) = mpsc::channel();
+    let report = on_part.is_some().then_some(report);
+    let mut get_next_writer =
+        |n| get_next_writer(n).map(|it| DigestWriter::new(it, n, report.clone()));
     let mut part_num = 1;
-    let mut writer = Archive::write_header(initial_writer)?;
+    let initial_writer = DigestWriter::new(initial_writer, part_num, report.clone());
+    let mut writer = Archive::write_header_with_id(initial_writer, archive_id)?;
 
-    // NOTE: max_file_size - (PNA_HEADER + AHED + ANXT + AEND)
-    let max_file_size = max_file_size - (PNA_HEADER.len() + MIN_CHUNK_BYTES_SIZE * 3 + 8);
+    let max_file_size = max_file_size - split_archive_overhead_bytes(archive_id);
     let mut written_entry_size = 0;
+    let mut written_entries = 0;
     for entry in entries {
         let p = EntryPart::from(entry?);
+        if let PartBoundary::Entry {
+            entries_per_part,
+            strict,
+        } = boundary
+        {
+            let is_full = entries_per_part.is_some_and(|n| n <= written_entries);
+            let overflow = written_entry_size + p.bytes_len() > max_file_size;
+            if written_entries != 0 && (is_full || overflow) {
+                part_num += 1;
+                let file = get_next_writer(part_num)?;
+                let (completed, next) = writer.finalize_and_split(file)?;
+                completed.finish()?.complete()?;
+                writer = next;
+                report_parts(digests.try_iter(), &mut on_part)?;
+                written_entry_size = 0;
+                written_entries = 0;
+            }
+            written_entries += 1;
+            if p.bytes_len() <= max_file_size {
+                written_entry_size += writer.add_entry_part(p)?;
+                continue;
+            }
+            if strict {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "An entry of {} bytes does not fit in a part of {} bytes",
+                        p.bytes_len(),
+                        max_file_size
+                    ),
+                ));
+            }
+            log::warn!(
+                "An entry of {} bytes does not fit in a part of {} bytes, so it is divided across parts",
+                p.bytes_len(),
+                max_file_size
+            );
+        }
         let parts = split_to_parts(
             p.as_ref(),
             max_file_size - written_entry_size,
             max_file_size,
-        );
+        )?;
         for part in parts {
             if written_entry_size + part.bytes_len() > max_file_size {
                 part_num += 1;
                 let file = get_next_writer(part_num)?;
-                writer = writer.split_to_next_archive(file)?;
+                let (completed, next) = writer.finalize_and_split(file)?;
+                completed.finish()?.complete()?;
+                writer = next;
+                report_parts(digests.try_iter(), &mut on_part)?;
                 written_entry_size = 0;
             }
             written_entry_size += writer.add_entry_part(part)?;
         }
     }
-    writer.finalize()?;
-    on_complete(part_num)?;
+    writer.finalize()?.finish()?.complete()?;
+    drop(report);
+    // NOTE: Waits for the digests of the remaining parts.
+    report_parts(digests.iter(), &mut on_part)?;
+    Ok(part_num)
+}
+
+fn report_parts(
+    parts: impl Iterator<Item = PartDigest>,
+    on_part: &mut Option<&mut dyn FnMut(PartDigest) -> io::Result<()>>,
+) -> io::Result<()> {
+    if let Some(on_part) = on_part {
+        for part in parts {
+            on_part(part)?;
+        }
+    }
     Ok(())
 }
 
@@ -779,13 +1986,38 @@ mod tests {
     use super::*;
     use std::collections::HashSet;
 
+    #[test]
+    fn entry_name_of_path() {
+        assert_eq!(
+            entry_name(Path::new("./dir//file.txt")).unwrap(),
+            "dir/file.txt"
+        );
+        assert_eq!(
+            entry_name(Path::new("../dir/.hidden")).unwrap(),
+            "dir/.hidden"
+        );
+        for path in ["dir/../file.txt", "new\nline", "."] {
+            let err = entry_name(Path::new(path)).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{path:?}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn entry_name_of_non_utf8_path() {
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"dir/\xfffile"));
+        assert_eq!(entry_name(path).unwrap(), "dir/\u{FFFD}file");
+    }
+
     #[test]
     fn collect_items_only_file() {
         let source = [format!(
             "{}/../resources/test/raw",
             env!("CARGO_MANIFEST_DIR")
         )];
-        let items = collect_items(source, false, false, false, false, None).unwrap();
+        let items =
+            collect_items(source, false, false, false, false, Exclude::default(), None).unwrap();
         assert_eq!(
             items.into_iter().collect::<HashSet<_>>(),
             [].into_iter().collect::<HashSet<_>>()
@@ -798,7 +2030,8 @@ mod tests {
             "{}/../resources/test/raw",
             env!("CARGO_MANIFEST_DIR")
         )];
-        let items = collect_items(source, false, true, false, false, None).unwrap();
+        let items =
+            collect_items(source, false, true, false, false, Exclude::default(), None).unwrap();
         assert_eq!(
             items.into_iter().collect::<HashSet<_>>(),
             [format!(
@@ -811,13 +2044,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn collect_items_nodump() {
+        let source = || {
+            [format!(
+                "{}/../resources/test/raw",
+                env!("CARGO_MANIFEST_DIR")
+            )]
+        };
+        // NOTE: The files without the nodump flag are collected, even where the flag is not supported.
+        let exclude = Exclude::default().with_nodump(true);
+        let items = collect_items(source(), true, false, false, false, exclude, None).unwrap();
+        let expected = collect_items(
+            source(),
+            true,
+            false,
+            false,
+            false,
+            Exclude::default(),
+            None,
+        )
+        .unwrap();
+        assert!(!items.is_empty());
+        assert_eq!(items, expected);
+    }
+
     #[test]
     fn collect_items_recursive() {
         let source = [format!(
             "{}/../resources/test/raw",
             env!("CARGO_MANIFEST_DIR")
         )];
-        let items = collect_items(source, true, false, false, false, None).unwrap();
+        let items =
+            collect_items(source, true, false, false, false, Exclude::default(), None).unwrap();
         assert_eq!(
             items.into_iter().collect::<HashSet<_>>(),
             [
@@ -863,4 +2122,211 @@ mod tests {
             .collect::<HashSet<_>>()
         );
     }
+
+    #[test]
+    fn split_archive_part_digests() {
+        use sha2::{Digest, Sha256};
+
+        let dir = std::env::temp_dir().join("pna_split_part_digests");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let entries = (0..8).map(|i| {
+            let mut builder =
+                EntryBuilder::new_file(format!("{i}.txt").into(), WriteOptions::store())?;
+            builder.write_all(&[i as u8; 1000])?;
+            builder.build()
+        });
+        let mut parts = Vec::new();
+        write_split_archive_path(
+            dir.join("archive.pna"),
+            None,
+            entries,
+            |base, n| base.with_part(n).unwrap(),
+            3000,
+            PartBoundary::Chunk,
+            Some(&mut |part| {
+                parts.push(part);
+                Ok(())
+            }),
+        )
+        .unwrap();
+        parts.sort_by_key(|it| it.part_number);
+        assert!(parts.len() > 1);
+        for (i, part) in parts.into_iter().enumerate() {
+            assert_eq!(part.part_number, i + 1);
+            let written = fs::read(dir.join("archive.pna").with_part(i + 1).unwrap()).unwrap();
+            assert_eq!(part.bytes_written, written.len() as u64);
+            assert_eq!(part.digest, <[u8; 32]>::from(Sha256::digest(&written)));
+        }
+    }
+
+    #[test]
+    fn split_archive_into_parts() {
+        let dir = std::env::temp_dir().join("pna_split_archive_into_parts");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let entries = || {
+            (0..8).map(|i| {
+                let mut builder =
+                    EntryBuilder::new_file(format!("{i}.txt").into(), WriteOptions::store())?;
+                builder.write_all(&[i as u8; 1000])?;
+                builder.build()
+            })
+        };
+        for (parts, boundary) in [
+            (3, PartBoundary::Chunk),
+            (5, PartBoundary::Chunk),
+            (
+                3,
+                PartBoundary::Entry {
+                    entries_per_part: None,
+                    strict: true,
+                },
+            ),
+        ] {
+            let archive = dir.join(format!("{parts}-{boundary:?}.pna"));
+            let max_file_size = split_part_size(entries(), parts, boundary).unwrap();
+            write_split_archive_path(
+                &archive,
+                None,
+                entries(),
+                |base, n| base.with_part(n).unwrap(),
+                max_file_size,
+                boundary,
+                None,
+            )
+            .unwrap();
+            assert!(archive.with_part(parts).unwrap().exists());
+            assert!(!archive.with_part(parts + 1).unwrap().exists());
+        }
+    }
+
+    #[test]
+    fn split_archive_part_too_small() {
+        let dir = std::env::temp_dir().join("pna_split_archive_part_too_small");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.pna");
+        let err = write_split_archive_path(
+            &archive,
+            None,
+            std::iter::empty::<io::Result<NormalEntry>>(),
+            |base, n| base.with_part(n).unwrap(),
+            MIN_SPLIT_PART_BYTES - 1,
+            PartBoundary::Chunk,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!temp_part_path(&archive.with_part(1).unwrap()).exists());
+    }
+
+    #[test]
+    fn write_split_archive_with_min_part_size() {
+        let dir = std::env::temp_dir().join("pna_write_split_archive_with_min_part_size");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.pna");
+        let entry = |name: &str| {
+            let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store())?;
+            builder.write_all(&[0; 100])?;
+            builder.build()
+        };
+        // NOTE: The minimum size leaves room for the aIDN chunk of the parts with an archive id.
+        let archive_id = Some(ArchiveId::default());
+        write_split_archive_path(
+            &archive,
+            archive_id,
+            [entry("a")].into_iter(),
+            |base, n| base.with_part(n).unwrap(),
+            MIN_SPLIT_PART_BYTES,
+            PartBoundary::Chunk,
+            None,
+        )
+        .unwrap();
+        assert!(archive.with_part(2).unwrap().exists());
+        for path in collect_split_archives(&archive.with_part(1).unwrap()).unwrap() {
+            assert!(fs::metadata(path).unwrap().len() <= MIN_SPLIT_PART_BYTES as u64);
+        }
+
+        let err = write_split_archive_path(
+            &archive,
+            archive_id,
+            [entry("long_name.txt")].into_iter(),
+            |base, n| base.with_part(n).unwrap(),
+            MIN_SPLIT_PART_BYTES,
+            PartBoundary::Chunk,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn split_to_parts_rejects_chunk_larger_than_part() {
+        let entry =
+            EntryBuilder::new_file("a/long/name/of/the/entry.txt".into(), WriteOptions::store())
+                .unwrap()
+                .build()
+                .unwrap();
+        let part = EntryPart::from(entry);
+        let err = split_to_parts(part.as_ref(), 30, 30).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let parts = split_to_parts(part.as_ref(), 0, part.max_unsplittable_len()).unwrap();
+        assert_eq!(
+            parts.iter().map(EntryPart::bytes_len).sum::<usize>(),
+            part.bytes_len()
+        );
+    }
+
+    #[test]
+    fn interrupted_split_archive() {
+        let dir = std::env::temp_dir().join("pna_interrupted_split_archive");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.pna");
+        let entries = (0..8).map(|i| {
+            let mut builder =
+                EntryBuilder::new_file(format!("{i}.txt").into(), WriteOptions::store())?;
+            builder.write_all(&[i as u8; 1000])?;
+            builder.build()
+        });
+        let err = write_split_archive_writer(
+            TempPartFile::create(archive.with_part(1).unwrap()).unwrap(),
+            None,
+            entries,
+            |n| match n {
+                3 => Err(io::Error::other("interrupted")),
+                n => TempPartFile::create(archive.with_part(n).unwrap()),
+            },
+            3000,
+            PartBoundary::Chunk,
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "interrupted");
+
+        // NOTE: Only the parts that were completely written have their final names.
+        let part1 = archive.with_part(1).unwrap();
+        let part2 = archive.with_part(2).unwrap();
+        assert!(part1.exists());
+        assert!(!temp_part_path(&part1).exists());
+        assert!(!part2.exists());
+        assert!(temp_part_path(&part2).exists());
+        assert!(!temp_part_path(&archive.with_part(3).unwrap()).exists());
+
+        let err = run_read_entries(PathArchiveProvider::new(&part1), |entry| {
+            entry?;
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "incomplete split set: {} was not completely written",
+                part2.display()
+            )
+        );
+    }
 }
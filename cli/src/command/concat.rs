@@ -4,13 +4,18 @@ use crate::command::commons::run_across_archive_mem;
 use crate::command::commons::{run_across_archive, PathArchiveProvider};
 use crate::{cli::FileArgs, command::Command, utils};
 use clap::Parser;
-use pna::Archive;
+use pna::{Archive, Keep};
 use std::{fs, io};
 
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub(crate) struct ConcatCommand {
     #[arg(long, help = "Overwrite file")]
     overwrite: bool,
+    #[arg(
+        long,
+        help = "Merge archives whose entries are sorted by name, keeping entries sorted. Entries with the same name are all kept"
+    )]
+    merge_sorted: bool,
     #[command(flatten)]
     files: FileArgs,
 }
@@ -37,9 +42,33 @@ fn concat_entry(args: ConcatCommand) -> io::Result<()> {
             ));
         }
     }
+    let archive_id = match args.files.files.first() {
+        Some(item) => Archive::read_header(fs::File::open(item)?)?.archive_id(),
+        None => None,
+    };
     let file = fs::File::create(&args.files.archive)?;
-    let mut archive = Archive::write_header(file)?;
+    let mut archive = match archive_id {
+        Some(id) => Archive::write_header_with_id(file, Some(id))?,
+        None => Archive::write_header(file)?,
+    };
 
+    if args.merge_sorted {
+        let mut sources = args
+            .files
+            .files
+            .iter()
+            .map(|item| Archive::read_header(fs::File::open(item)?))
+            .collect::<io::Result<Vec<_>>>()?;
+        archive.merge_sorted(&mut sources, |_, _| Keep::Both)?;
+        if sources.iter().any(|it| it.has_next_archive()) {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Merging split archives is not supported",
+            ));
+        }
+        archive.finalize()?;
+        return Ok(());
+    }
     for item in &args.files.files {
         #[cfg(feature = "memmap")]
         run_across_archive_mem(item, |reader| {
@@ -1,64 +1,159 @@
 use crate::{
     cli::{
-        CipherAlgorithmArgs, CompressionAlgorithmArgs, FileArgs, HashAlgorithmArgs, PasswordArgs,
+        CipherAlgorithmArgs, CompressionAlgorithmArgs, FailFastArgs, FileArgs, HashAlgorithmArgs,
+        IdMapArgs, PasswordArgs, ReplaceArgs, TraversalArgs,
     },
     command::{
         ask_password, check_password,
         commons::{
-            collect_items, create_entry, entry_option, write_split_archive, CreateOptions,
-            KeepOptions, OwnerOptions,
+            backup_existing, check_replaceable, collect_traversal_items, create_entry,
+            create_hard_link_entry, entry_name, entry_option, is_large_file, par_map_ordered,
+            write_entry_streaming, write_split_archive, CreateOptions, KeepOptions, OutputArchive,
+            OwnerOptions, PartBoundary, PlatformTags,
         },
         Command,
     },
-    utils::{self, fmt::DurationDisplay},
+    exit_code::{usage_error, FailurePolicy},
+    utils::{
+        dedup::find_duplicates, fmt::DurationDisplay, progress::Progress, resolve_link,
+        url::is_stdio, zstd_dict::train_dictionary, LinkTargets,
+    },
 };
 use bytesize::ByteSize;
-use clap::{ArgGroup, Parser, ValueHint};
-use pna::{Archive, SolidEntryBuilder, WriteOptions};
+use chrono::DateTime;
+use clap::{ArgGroup, Parser, ValueEnum};
+use pna::{
+    Archive, ArchiveId, EntryName, EntryNameError, NormalEntry, Recipient, SolidEntryBuilder,
+    WriteOptions,
+};
+use sha2::{Digest, Sha256};
 use std::{
+    collections::{HashMap, HashSet},
+    env,
     fs::{self, File},
     io::{self, prelude::*},
+    mem,
     path::{Path, PathBuf},
-    time::Instant,
+    time::{Duration, Instant},
 };
 
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[command(
     group(ArgGroup::new("unstable-acl").args(["keep_acl"]).requires("unstable")),
-    group(ArgGroup::new("unstable-create-exclude").args(["exclude"]).requires("unstable")),
-    group(ArgGroup::new("unstable-files-from").args(["files_from"]).requires("unstable")),
-    group(ArgGroup::new("unstable-files-from-stdin").args(["files_from_stdin"]).requires("unstable")),
-    group(ArgGroup::new("unstable-exclude-from").args(["exclude_from"]).requires("unstable")),
-    group(ArgGroup::new("unstable-gitignore").args(["gitignore"]).requires("unstable")),
-    group(ArgGroup::new("read-files-from").args(["files_from", "files_from_stdin"])),
-    group(ArgGroup::new("store-uname").args(["uname"]).requires("keep_permission")),
-    group(ArgGroup::new("store-gname").args(["gname"]).requires("keep_permission")),
-    group(ArgGroup::new("store-numeric-owner").args(["numeric_owner"]).requires("keep_permission")),
+    group(ArgGroup::new("unstable-recipient").args(["recipient"]).requires("unstable")),
+    group(ArgGroup::new("unstable-packed-metadata").args(["packed_metadata"]).requires("unstable")),
+    group(ArgGroup::new("unstable-platform-tag").args(["platform_tag"]).requires("unstable")),
+    group(ArgGroup::new("unstable-dedup").args(["dedup"]).requires("unstable")),
+    group(ArgGroup::new("unstable-sort").args(["sort"]).requires("unstable")),
+    group(ArgGroup::new("unstable-reproducible").args(["reproducible", "mtime"]).multiple(true).requires("unstable")),
+    group(ArgGroup::new("unstable-solid-block-size").args(["solid_block_size"]).requires("unstable")),
+    group(ArgGroup::new("unstable-zstd-dict").args(["zstd_dict", "train_dict"]).requires("unstable")),
+    group(ArgGroup::new("unstable-archive-id").args(["archive_id"]).requires("unstable")),
+    group(ArgGroup::new("unstable-check-links").args(["check_links", "check_links_allow_external"]).multiple(true).requires("unstable")),
+    group(ArgGroup::new("keep-owner").args(["keep_permission", "keep_owner"]).multiple(true)),
+    group(ArgGroup::new("store-uname").args(["uname"]).requires("keep-owner")),
+    group(ArgGroup::new("store-gname").args(["gname"]).requires("keep-owner")),
+    group(ArgGroup::new("store-numeric-owner").args(["numeric_owner"]).requires("keep-owner")),
     group(ArgGroup::new("user-flag").args(["numeric_owner", "uname"])),
     group(ArgGroup::new("group-flag").args(["numeric_owner", "gname"])),
 )]
 #[cfg_attr(windows, command(
-    group(ArgGroup::new("windows-unstable-keep-permission").args(["keep_permission"]).requires("unstable")),
+    group(ArgGroup::new("windows-unstable-keep-permission").args(["keep_permission", "keep_owner", "keep_mode"]).multiple(true).requires("unstable")),
 ))]
 pub(crate) struct CreateCommand {
-    #[arg(short, long, help = "Add the directory to the archive recursively")]
-    pub(crate) recursive: bool,
+    #[command(flatten)]
+    pub(crate) traversal: TraversalArgs,
     #[arg(long, help = "Overwrite file")]
     pub(crate) overwrite: bool,
+    #[command(flatten)]
+    pub(crate) replace: ReplaceArgs,
     #[arg(long, help = "Archiving the directories")]
     pub(crate) keep_dir: bool,
+    #[arg(
+        long,
+        value_name = "ORDER",
+        default_value = "none",
+        help = "Order of the entries in the archive. Directories are still stored before their contents with --keep-dir (unstable)"
+    )]
+    pub(crate) sort: SortOrder,
+    #[arg(
+        long,
+        conflicts_with_all = ["sort", "keep_xattr", "keep_acl", "uname", "gname", "uid", "gid", "recipient"],
+        help = "Create the same archive from the same files: sort the entries by name, clamp the timestamps to --mtime or SOURCE_DATE_EPOCH, store the owners as user and group id 0 without names and skip the extended attributes and acl. Encryption is not allowed since its salts and IVs are random (unstable)"
+    )]
+    pub(crate) reproducible: bool,
+    #[arg(
+        long,
+        value_name = "TIME",
+        requires = "reproducible",
+        value_parser = parse_mtime,
+        help = "Latest timestamp stored with --reproducible, as seconds since the unix epoch or an RFC 3339 date. Defaults to SOURCE_DATE_EPOCH, or the unix epoch if it is not set (unstable)"
+    )]
+    pub(crate) mtime: Option<Duration>,
     #[arg(long, help = "Archiving the timestamp of the files")]
     pub(crate) keep_timestamp: bool,
-    #[arg(long, help = "Archiving the permissions of the files")]
+    #[arg(
+        long,
+        help = "Archiving the permissions of the files. This is equivalent to --keep-owner --keep-mode"
+    )]
     pub(crate) keep_permission: bool,
+    #[arg(
+        long,
+        overrides_with = "no_keep_owner",
+        help = "Archiving the owners of the files. Without --keep-mode, the modes are stored as 755 for directories and 644 for the others"
+    )]
+    pub(crate) keep_owner: bool,
+    #[arg(
+        long,
+        overrides_with = "keep_owner",
+        help = "Do not archive the owners of the files even with --keep-permission. The owners are stored as user and group id 0 without names"
+    )]
+    pub(crate) no_keep_owner: bool,
+    #[arg(
+        long,
+        overrides_with = "no_keep_mode",
+        help = "Archiving the modes of the files. Without --keep-owner, the owners are stored as user and group id 0 without names"
+    )]
+    pub(crate) keep_mode: bool,
+    #[arg(
+        long,
+        overrides_with = "keep_mode",
+        help = "Do not archive the modes of the files even with --keep-permission. The modes are stored as 755 for directories and 644 for the others"
+    )]
+    pub(crate) no_keep_mode: bool,
     #[arg(long, help = "Archiving the extended attributes of the files")]
     pub(crate) keep_xattr: bool,
+    #[arg(
+        long,
+        requires = "keep_xattr",
+        help = "Compress the values of the extended attributes larger than 4 KiB with zstd. The compressed attributes cannot be read by pna 0.21.0 or earlier (unstable)"
+    )]
+    pub(crate) compress_xattr: bool,
     #[arg(long, help = "Archiving the acl of the files")]
     pub(crate) keep_acl: bool,
     #[arg(long, help = "Split archive by total entry size")]
     pub(crate) split: Option<Option<ByteSize>>,
+    #[arg(
+        long,
+        requires = "split",
+        help = "Record an archive id in each part of a split archive, so that the parts of different archives are not mixed up. The parts cannot be read by pna 0.21.0 or earlier (unstable)"
+    )]
+    pub(crate) archive_id: bool,
     #[arg(long, help = "Solid mode archive")]
     pub(crate) solid: bool,
+    #[arg(
+        long,
+        requires = "solid",
+        help = "Compact the chunk overhead of each entry in solid mode. The archive cannot be read by pna 0.21.0 or earlier (unstable)"
+    )]
+    pub(crate) packed_metadata: bool,
+    #[arg(
+        long,
+        value_name = "SIZE",
+        requires = "solid",
+        help = "Start a new solid entry when the entries in the current one exceed the given size. An entry larger than the size is stored in a solid entry of its own (unstable)"
+    )]
+    pub(crate) solid_block_size: Option<ByteSize>,
     #[arg(long, help = "Archiving user to the entries from given name")]
     pub(crate) uname: Option<String>,
     #[arg(long, help = "Archiving group to the entries from given name")]
@@ -78,16 +173,8 @@ pub(crate) struct CreateCommand {
         help = "This is equivalent to --uname \"\" --gname \"\". It causes user and group names to not be stored in the archive"
     )]
     pub(crate) numeric_owner: bool,
-    #[arg(long, help = "Read archiving files from given path (unstable)", value_hint = ValueHint::FilePath)]
-    pub(crate) files_from: Option<String>,
-    #[arg(long, help = "Read archiving files from stdin (unstable)")]
-    pub(crate) files_from_stdin: bool,
-    #[arg(long, help = "Read exclude files from given path (unstable)", value_hint = ValueHint::FilePath)]
-    pub(crate) exclude_from: Option<String>,
-    #[arg(long, help = "Ignore files from .gitignore (unstable)")]
-    pub(crate) gitignore: bool,
-    #[arg(long, help = "Follow symbolic links")]
-    pub(crate) follow_links: bool,
+    #[command(flatten)]
+    pub(crate) id_map: IdMapArgs,
     #[command(flatten)]
     pub(crate) compression: CompressionAlgorithmArgs,
     #[command(flatten)]
@@ -96,10 +183,123 @@ pub(crate) struct CreateCommand {
     pub(crate) hash: HashAlgorithmArgs,
     #[command(flatten)]
     pub(crate) password: PasswordArgs,
+    #[arg(
+        long,
+        value_name = "RECIPIENT",
+        conflicts_with_all = ["solid", "password", "password_file", "password_command"],
+        help = "Encrypt entries to the given age public key instead of a password. This option can be specified multiple times (unstable)"
+    )]
+    pub(crate) recipient: Vec<Recipient>,
+    #[command(flatten)]
+    pub(crate) fail_fast: FailFastArgs,
     #[command(flatten)]
     pub(crate) file: FileArgs,
-    #[arg(long, help = "Exclude path glob (unstable)", value_hint = ValueHint::AnyPath)]
-    pub(crate) exclude: Option<Vec<PathBuf>>,
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["os=OS[,OS...]", "PATTERN"],
+        help = "Mark the entries whose names match the glob PATTERN as intended only for the given operating systems, such as `linux`, `macos` or `windows`. Extraction on other platforms skips them. This option can be specified multiple times (unstable)"
+    )]
+    pub(crate) platform_tag: Vec<String>,
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "error",
+        help = "Check that the archived symbolic links resolve to other archived entries, and fail (`error`) or warn (`warn`) on the dangling ones. Absolute targets and targets outside the given files are dangling [MODE default: error] (unstable)"
+    )]
+    pub(crate) check_links: Option<CheckLinks>,
+    #[arg(
+        long,
+        requires = "check_links",
+        help = "With --check-links, allow the symbolic links to absolute targets and targets outside the given files (unstable)"
+    )]
+    pub(crate) check_links_allow_external: bool,
+    #[arg(
+        long,
+        help = "Store the regular files with the same contents as an earlier file as hard links to it. The contents are compared byte by byte after hashing (unstable)"
+    )]
+    pub(crate) dedup: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["store", "deflate", "xz"],
+        help = "Compress the entries with the given zstd dictionary. The dictionary is stored in the archive (unstable)"
+    )]
+    pub(crate) zstd_dict: Option<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with_all = ["zstd_dict", "store", "deflate", "xz"],
+        help = "Compress the entries with a zstd dictionary trained from the heads of the input files. The dictionary is stored in the archive (unstable)"
+    )]
+    pub(crate) train_dict: bool,
+}
+
+/// Order of the entries given by `--sort`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, ValueEnum)]
+pub(crate) enum SortOrder {
+    /// The order the files are found in
+    #[default]
+    None,
+    /// Byte-wise order of the paths
+    Name,
+    /// Oldest modified first
+    Mtime,
+    /// Smallest first
+    Size,
+}
+
+/// What to do on the dangling symbolic links found by `--check-links`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum)]
+pub(crate) enum CheckLinks {
+    /// Fail to create the archive
+    Error,
+    /// Warn and create the archive
+    Warn,
+}
+
+/// Parses the seconds since the unix epoch or an RFC 3339 date of `--mtime`.
+fn parse_mtime(s: &str) -> Result<Duration, String> {
+    if let Ok(secs) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    let time = DateTime::parse_from_rfc3339(s).map_err(|e| e.to_string())?;
+    let secs = u64::try_from(time.timestamp()).map_err(|_| "time before the unix epoch")?;
+    Ok(Duration::new(secs, time.timestamp_subsec_nanos()))
+}
+
+/// Returns the latest timestamp stored with `--reproducible`, given by `mtime` or the SOURCE_DATE_EPOCH environment variable.
+fn reproducible_time(mtime: Option<Duration>) -> io::Result<Duration> {
+    if let Some(mtime) = mtime {
+        return Ok(mtime);
+    }
+    match env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch
+            .trim()
+            .parse()
+            .map(Duration::from_secs)
+            .map_err(|e| usage_error(format!("invalid SOURCE_DATE_EPOCH `{epoch}`: {e}"))),
+        Err(env::VarError::NotPresent) => Ok(Duration::ZERO),
+        Err(e) => Err(usage_error(e)),
+    }
+}
+
+/// Returns the archive id of a `--reproducible` split archive, derived from the items to archive and the clamped timestamp.
+///
+/// Archives of the same items get the same id, while the parts of archives of other items are still distinguished.
+fn reproducible_archive_id(clamp_time: Duration, items: &[PathBuf]) -> ArchiveId {
+    let mut hasher = Sha256::new();
+    hasher.update(clamp_time.as_secs().to_be_bytes());
+    hasher.update(clamp_time.subsec_nanos().to_be_bytes());
+    for item in items {
+        hasher.update(item.to_string_lossy().as_bytes());
+        hasher.update([0]);
+    }
+    let mut id = ArchiveId::default();
+    let len = id.len();
+    id.copy_from_slice(&hasher.finalize()[..len]);
+    id
 }
 
 impl Command for CreateCommand {
@@ -110,85 +310,150 @@ impl Command for CreateCommand {
 }
 
 fn create_archive(args: CreateCommand) -> io::Result<()> {
+    let platform_tags = PlatformTags::parse(&args.platform_tag)?;
     let password = ask_password(args.password)?;
-    check_password(&password, &args.cipher);
+    if args.reproducible && password.is_some() {
+        return Err(usage_error(
+            "--reproducible can not be used with encryption, since the salts and IVs of the encrypted entries are random",
+        ));
+    }
+    let clamp_time = args
+        .reproducible
+        .then(|| reproducible_time(args.mtime))
+        .transpose()?;
+    if args.recipient.is_empty() {
+        check_password(&password, &args.cipher);
+    }
     let start = Instant::now();
     let archive = &args.file.archive;
-    if !args.overwrite && archive.exists() {
+    let to_stdout = is_stdio(archive);
+    if to_stdout && args.split.is_some() {
+        return Err(usage_error(
+            "--split can not be used to write the archive to the standard output",
+        ));
+    }
+    // NOTE: Backing up the existing archive is also a way to replace it.
+    if !to_stdout && !args.overwrite && args.replace.backup.is_none() && archive.exists() {
         return Err(io::Error::new(
             io::ErrorKind::AlreadyExists,
             format!("{} is already exists", archive.display()),
         ));
     }
-    log::info!("Create an archive: {}", archive.display());
-    let mut files = args.file.files;
-    if args.files_from_stdin {
-        files.extend(io::stdin().lines().collect::<io::Result<Vec<_>>>()?);
-    } else if let Some(path) = args.files_from {
-        files.extend(utils::fs::read_to_lines(path)?);
+    if !to_stdout {
+        check_replaceable(archive, &args.replace)?;
     }
-    let exclude = if args.exclude.is_some() || args.exclude_from.is_some() {
-        let mut exclude = Vec::new();
-        if let Some(e) = args.exclude {
-            exclude.extend(e);
-        }
-        if let Some(p) = args.exclude_from {
-            exclude.extend(utils::fs::read_to_lines(p)?.into_iter().map(PathBuf::from));
-        }
-        Some(exclude)
-    } else {
-        None
+    log::info!("Create an archive: {}", archive.display());
+    let files = args.traversal.files(args.file.files)?;
+    // NOTE: The entry names of the given files are the roots that the archived symbolic links may point within.
+    let roots = match args.check_links {
+        Some(_) => files
+            .iter()
+            .map(|it| root_name(Path::new(it)))
+            .collect::<io::Result<Vec<_>>>()?,
+        None => Vec::new(),
     };
-    let target_items = collect_items(
-        &files,
-        args.recursive,
+    let target_items = collect_traversal_items(
+        files,
+        &args.traversal,
         args.keep_dir,
-        args.gitignore,
-        args.follow_links,
-        exclude,
+        (!to_stdout).then(|| OutputArchive::new(archive)).as_ref(),
     )?;
+    let sort = if args.reproducible {
+        SortOrder::Name
+    } else {
+        args.sort
+    };
+    let target_items = sort_items(target_items, sort)?;
+    if let Some(mode) = args.check_links {
+        check_links(&target_items, &roots, mode, args.check_links_allow_external)?;
+    }
 
-    if let Some(parent) = archive.parent() {
-        fs::create_dir_all(parent)?;
+    if !to_stdout {
+        if let Some(parent) = archive.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        backup_existing(archive, &args.replace)?;
     }
     let max_file_size = args
         .split
         .map(|it| it.unwrap_or(ByteSize::gb(1)).0 as usize);
+    let solid_block_size = args.solid_block_size.map(|it| it.0 as usize);
 
     let keep_options = KeepOptions {
         keep_timestamp: args.keep_timestamp,
-        keep_permission: args.keep_permission,
+        clamp_time,
+        keep_owner: (args.keep_permission || args.keep_owner) && !args.no_keep_owner,
+        keep_mode: (args.keep_permission || args.keep_mode) && !args.no_keep_mode,
         keep_xattr: args.keep_xattr,
+        compress_xattr: args.compress_xattr,
         keep_acl: args.keep_acl,
     };
-    let owner_options = OwnerOptions::new(
-        args.uname,
-        args.gname,
-        args.uid,
-        args.gid,
-        args.numeric_owner,
-    );
-    let password = password.as_deref();
-    let write_option = entry_option(args.compression, args.cipher, args.hash, password);
+    let owner_options = if args.reproducible {
+        OwnerOptions::new(None, None, Some(0), Some(0), true)
+    } else {
+        OwnerOptions::new(
+            args.uname,
+            args.gname,
+            args.uid,
+            args.gid,
+            args.numeric_owner,
+        )
+    }
+    .with_id_maps(args.id_map)?;
+    let password = password.as_deref().map(String::as_str);
+    let (cipher_algorithm, cipher_mode) = (args.cipher.algorithm(), args.cipher.mode());
+    let mut write_option = entry_option(args.compression, args.cipher, args.hash, password);
+    if !args.recipient.is_empty() {
+        write_option = write_option
+            .into_builder()
+            .encryption(cipher_algorithm)
+            .cipher_mode(cipher_mode)
+            .recipients(args.recipient)
+            .build();
+    }
+    let zstd_dictionary = if let Some(path) = &args.zstd_dict {
+        Some(fs::read(path)?)
+    } else if args.train_dict {
+        match train_dictionary(&target_items) {
+            Ok(dictionary) => Some(dictionary),
+            Err(e) => {
+                log::warn!(
+                    "Failed to train a zstd dictionary, creating the archive without it: {e}"
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+    if zstd_dictionary.is_some() {
+        write_option = write_option
+            .into_builder()
+            .zstd_dictionary(zstd_dictionary)
+            .build();
+    }
+    let options = CreateArchiveOptions {
+        write_option,
+        keep_options,
+        owner_options,
+        platform_tags,
+        solid: SolidOptions {
+            mode: SolidMode::new(args.solid, args.packed_metadata),
+            block_size: solid_block_size,
+        },
+        dedup: args.dedup,
+        fail_fast: args.fail_fast.fail_fast(),
+        archive_id: args.archive_id.then(|| match clamp_time {
+            Some(time) => reproducible_archive_id(time, &target_items),
+            None => rand::random(),
+        }),
+    };
     if let Some(size) = max_file_size {
-        create_archive_with_split(
-            &args.file.archive,
-            write_option,
-            keep_options,
-            owner_options,
-            args.solid,
-            target_items,
-            size,
-        )?;
+        create_archive_with_split(&args.file.archive, options, target_items, size)?;
+    } else if to_stdout {
+        create_archive_file(|| Ok(io::stdout().lock()), options, target_items)?;
     } else {
-        create_archive_file(
-            || File::create(&args.file.archive),
-            write_option,
-            keep_options,
-            owner_options,
-            args.solid,
-            target_items,
-        )?;
+        create_archive_file(|| File::create(&args.file.archive), options, target_items)?;
     }
     log::info!(
         "Successfully created an archive in {}",
@@ -197,20 +462,197 @@ fn create_archive(args: CreateCommand) -> io::Result<()> {
     Ok(())
 }
 
+/// Sorts `items` in `order`, breaking the ties by the paths.
+fn sort_items(mut items: Vec<PathBuf>, order: SortOrder) -> io::Result<Vec<PathBuf>> {
+    match order {
+        SortOrder::None => Ok(items),
+        SortOrder::Name => {
+            // NOTE: Byte-wise order already puts every path after its ancestors.
+            items.sort_by(|a, b| {
+                a.as_os_str()
+                    .as_encoded_bytes()
+                    .cmp(b.as_os_str().as_encoded_bytes())
+            });
+            Ok(items)
+        }
+        SortOrder::Mtime => sort_by_metadata(items, |it| it.modified()),
+        SortOrder::Size => sort_by_metadata(items, |it| Ok(it.len())),
+    }
+}
+
+/// Sorts `items` by the `key` of their metadata and then by the paths,
+/// keeping each directory in `items` before the items under it so that extraction creates the parents first.
+fn sort_by_metadata<K: Ord>(
+    items: Vec<PathBuf>,
+    key: impl Fn(&fs::Metadata) -> io::Result<K>,
+) -> io::Result<Vec<PathBuf>> {
+    let mut keyed = items
+        .into_iter()
+        .map(|it| Ok((key(&fs::symlink_metadata(&it)?)?, it)))
+        .collect::<io::Result<Vec<_>>>()?;
+    keyed.sort_by(|(a, a_path), (b, b_path)| {
+        a.cmp(b).then_with(|| {
+            a_path
+                .as_os_str()
+                .as_encoded_bytes()
+                .cmp(b_path.as_os_str().as_encoded_bytes())
+        })
+    });
+    let mut pending = keyed
+        .iter()
+        .map(|(_, it)| it.clone())
+        .collect::<HashSet<_>>();
+    let mut sorted = Vec::with_capacity(keyed.len());
+    for (_, item) in keyed {
+        if !pending.remove(&item) {
+            continue;
+        }
+        let mut ancestors = item
+            .ancestors()
+            .skip(1)
+            .filter_map(|it| pending.take(it))
+            .collect::<Vec<_>>();
+        ancestors.reverse();
+        sorted.extend(ancestors);
+        sorted.push(item);
+    }
+    Ok(sorted)
+}
+
+/// Returns the entry name of the root `path` of the walk, which is empty for the paths like `.` that have no name.
+fn root_name(path: &Path) -> io::Result<String> {
+    match EntryName::try_new(path) {
+        Ok(name) => Ok(name.to_string()),
+        Err(EntryNameError::Empty) => Ok(String::new()),
+        Err(_) => entry_name(path).map(|it| it.to_string()),
+    }
+}
+
+/// Reports the symbolic links in `items` whose targets are not archived with them.
+/// Links to absolute targets and targets outside all the `roots` are reported too unless `allow_external` is set.
+fn check_links(
+    items: &[PathBuf],
+    roots: &[String],
+    mode: CheckLinks,
+    allow_external: bool,
+) -> io::Result<()> {
+    let within_roots = |target: &str| {
+        roots.iter().any(|root| {
+            root.is_empty()
+                || target
+                    .strip_prefix(root.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+    };
+    let mut targets = LinkTargets::default();
+    let mut links = Vec::new();
+    for item in items {
+        let name = entry_name(item)?;
+        targets.insert(name.as_str());
+        if item.is_symlink() {
+            links.push((name, fs::read_link(item)?));
+        }
+    }
+    let mut dangling = 0;
+    for (name, link) in links {
+        let link = link.to_string_lossy();
+        let reason = match resolve_link(name.as_str(), &link) {
+            Some(target) if within_roots(&target) => {
+                if targets.contains(&target) {
+                    continue;
+                }
+                "does not resolve to an archived entry"
+            }
+            _ if allow_external => continue,
+            _ => "points outside the archived files",
+        };
+        match mode {
+            CheckLinks::Error => log::error!("{name}: symbolic link to `{link}` {reason}"),
+            CheckLinks::Warn => log::warn!("{name}: symbolic link to `{link}` {reason}"),
+        }
+        dangling += 1;
+    }
+    if dangling == 0 || mode == CheckLinks::Warn {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{dangling} symbolic links dangle in the archive"),
+    ))
+}
+
+/// How the entries are stored in a solid entry.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) enum SolidMode {
+    /// Entries are stored as they are, not in a solid entry.
+    Off,
+    /// Entries are stored in a solid entry.
+    Solid,
+    /// Entries are stored in a solid entry in the packed encoding.
+    Packed,
+}
+
+impl SolidMode {
+    #[inline]
+    pub(crate) const fn new(solid: bool, packed: bool) -> Self {
+        match (solid, packed) {
+            (false, _) => Self::Off,
+            (true, false) => Self::Solid,
+            (true, true) => Self::Packed,
+        }
+    }
+
+    #[inline]
+    const fn is_solid(self) -> bool {
+        !matches!(self, Self::Off)
+    }
+}
+
+/// Options of a solid archive.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct SolidOptions {
+    pub(crate) mode: SolidMode,
+    /// Maximum size of the data of the entries in a solid entry, or `None` to put all the entries in one.
+    pub(crate) block_size: Option<usize>,
+}
+
+/// Options of how [create_archive_file] and [create_archive_with_split] write the archive.
+#[derive(Clone, Debug)]
+pub(crate) struct CreateArchiveOptions {
+    pub(crate) write_option: WriteOptions,
+    pub(crate) keep_options: KeepOptions,
+    pub(crate) owner_options: OwnerOptions,
+    pub(crate) platform_tags: PlatformTags,
+    pub(crate) solid: SolidOptions,
+    pub(crate) dedup: bool,
+    pub(crate) fail_fast: bool,
+    /// Archive id recorded in the parts of a split archive, or `None` to record no archive id.
+    pub(crate) archive_id: Option<ArchiveId>,
+}
+
 pub(crate) fn create_archive_file<W, F>(
     mut get_writer: F,
-    write_option: WriteOptions,
-    keep_options: KeepOptions,
-    owner_options: OwnerOptions,
-    solid: bool,
+    CreateArchiveOptions {
+        write_option,
+        keep_options,
+        owner_options,
+        platform_tags,
+        solid:
+            SolidOptions {
+                mode: solid,
+                block_size: solid_block_size,
+            },
+        dedup,
+        fail_fast,
+        archive_id: _,
+    }: CreateArchiveOptions,
     target_items: Vec<PathBuf>,
 ) -> io::Result<()>
 where
     W: Write,
     F: FnMut() -> io::Result<W>,
 {
-    let (tx, rx) = std::sync::mpsc::channel();
-    let option = if solid {
+    let option = if solid.is_solid() {
         WriteOptions::store()
     } else {
         write_option.clone()
@@ -219,48 +661,197 @@ where
         option,
         keep_options,
         owner_options,
+        platform_tags,
     };
-    for file in target_items {
-        let tx = tx.clone();
-        rayon::scope_fifo(|s| {
-            s.spawn_fifo(|_| {
-                log::debug!("Adding: {}", file.display());
-                tx.send(create_entry(&file, &create_options))
-                    .unwrap_or_else(|e| panic!("{e}: {}", file.display()));
-            })
-        });
-    }
-
-    drop(tx);
+    let duplicates = if dedup {
+        find_duplicates(&target_items)
+    } else {
+        HashMap::new()
+    };
+    let progress = Progress::new(Some(contents_size(&target_items, &duplicates)));
+    let entries = par_map_ordered(target_items, |file| {
+        if let Some(original) = duplicates.get(&file) {
+            log::debug!("Adding: {} as a hard link", file.display());
+            return create_hard_link_entry(&file, original, &create_options)
+                .map(|entry| PendingEntry::Created(Box::new(entry)));
+        }
+        // NOTE: Large files are streamed into the archive when it is written,
+        //       instead of creating the whole entry in memory.
+        if !solid.is_solid() && is_large_file(&file) {
+            return Ok(PendingEntry::Streaming(file));
+        }
+        log::debug!("Adding: {}", file.display());
+        progress.start(&file);
+        create_entry(&file, &create_options)
+            .inspect(|entry| progress.inc(raw_file_size(entry)))
+            .map(|entry| PendingEntry::Created(Box::new(entry)))
+    });
 
+    let mut policy = FailurePolicy::new(fail_fast);
     let file = get_writer()?;
-    if solid {
-        let mut writer = Archive::write_solid_header(file, write_option)?;
-        for entry in rx.into_iter() {
-            writer.add_entry(entry?)?;
+    if let (true, Some(block_size)) = (solid.is_solid(), solid_block_size) {
+        let mut writer = Archive::write_header(file)?;
+        let mut blocks = SolidBlocks::new(solid, write_option, block_size)?;
+        for entry in entries {
+            match policy.check(entry)? {
+                Some(PendingEntry::Created(entry)) => {
+                    if let Some(block) = blocks.push(*entry)? {
+                        writer.add_entry(block.build()?)?;
+                    }
+                }
+                Some(PendingEntry::Streaming(_)) => {
+                    unreachable!("solid entries are always created in memory")
+                }
+                None => (),
+            }
+        }
+        if let Some(block) = blocks.finish() {
+            writer.add_entry(block.build()?)?;
+        }
+        writer.finalize()?;
+    } else if solid.is_solid() {
+        let mut writer = if solid == SolidMode::Packed {
+            Archive::write_packed_solid_header(file, write_option)?
+        } else {
+            Archive::write_solid_header(file, write_option)?
+        };
+        for entry in entries {
+            match policy.check(entry)? {
+                Some(PendingEntry::Created(entry)) => {
+                    writer.add_entry(*entry)?;
+                }
+                Some(PendingEntry::Streaming(_)) => {
+                    unreachable!("solid entries are always created in memory")
+                }
+                None => (),
+            }
         }
         writer.finalize()?;
     } else {
         let mut writer = Archive::write_header(file)?;
-        for entry in rx.into_iter() {
-            writer.add_entry(entry?)?;
+        for entry in entries {
+            match policy.check(entry)? {
+                Some(PendingEntry::Created(entry)) => {
+                    writer.add_entry(*entry)?;
+                }
+                Some(PendingEntry::Streaming(path)) => {
+                    log::debug!("Streaming: {}", path.display());
+                    // NOTE: A failure while streaming leaves an incomplete entry in the archive,
+                    //       so it cannot be skipped even if the failure policy allows it.
+                    progress.start(&path);
+                    write_entry_streaming(&mut writer, &path, &create_options, &progress)?;
+                }
+                None => (),
+            }
         }
         writer.finalize()?;
     }
-    Ok(())
+    policy.finish()
+}
+
+/// Groups the entries into solid entries that hold at most `block_size` bytes of data each.
+///
+/// Entries are never split across solid entries, so an entry larger than `block_size` is put in a solid entry of its own.
+struct SolidBlocks {
+    solid: SolidMode,
+    option: WriteOptions,
+    block_size: usize,
+    builder: SolidEntryBuilder,
+    size: usize,
+}
+
+impl SolidBlocks {
+    fn new(solid: SolidMode, option: WriteOptions, block_size: usize) -> io::Result<Self> {
+        Ok(Self {
+            builder: Self::builder(solid, &option)?,
+            solid,
+            option,
+            block_size,
+            size: 0,
+        })
+    }
+
+    fn builder(solid: SolidMode, option: &WriteOptions) -> io::Result<SolidEntryBuilder> {
+        if solid == SolidMode::Packed {
+            SolidEntryBuilder::new_packed(option.clone())
+        } else {
+            SolidEntryBuilder::new(option.clone())
+        }
+    }
+
+    /// Adds `entry` to the current block, or to a new block if it does not fit in the current one.
+    /// Returns the current block when a new block is started.
+    fn push(&mut self, entry: NormalEntry) -> io::Result<Option<SolidEntryBuilder>> {
+        // NOTE: Entries in solid mode are created with `WriteOptions::store()`,
+        //       so the compressed size is the size of the raw data.
+        let size = entry.metadata().compressed_size();
+        let full = if self.builder.entries_len() != 0
+            && self.block_size < self.size.saturating_add(size)
+        {
+            self.size = 0;
+            Some(mem::replace(
+                &mut self.builder,
+                Self::builder(self.solid, &self.option)?,
+            ))
+        } else {
+            None
+        };
+        self.builder.add_entry(entry)?;
+        self.size += size;
+        Ok(full)
+    }
+
+    /// Returns the last block, or `None` if it is empty.
+    fn finish(self) -> Option<SolidEntryBuilder> {
+        (self.builder.entries_len() != 0).then_some(self.builder)
+    }
+}
+
+/// Returns the total size of the regular files in `items` whose contents are archived,
+/// that is, except the duplicates archived as hard links.
+fn contents_size(items: &[PathBuf], duplicates: &HashMap<PathBuf, PathBuf>) -> u64 {
+    items
+        .iter()
+        .filter(|it| !duplicates.contains_key(*it))
+        .filter_map(|it| fs::symlink_metadata(it).ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+#[inline]
+fn raw_file_size(entry: &NormalEntry) -> u64 {
+    entry.metadata().raw_file_size().unwrap_or(0) as u64
+}
+
+/// Entry waiting to be written into the archive.
+enum PendingEntry {
+    /// Entry created in memory, boxed since it is much larger than a path.
+    Created(Box<NormalEntry>),
+    /// Regular file streamed into the archive when it is written.
+    Streaming(PathBuf),
 }
 
 fn create_archive_with_split(
     archive: &Path,
-    write_option: WriteOptions,
-    keep_options: KeepOptions,
-    owner_options: OwnerOptions,
-    solid: bool,
+    CreateArchiveOptions {
+        write_option,
+        keep_options,
+        owner_options,
+        platform_tags,
+        solid:
+            SolidOptions {
+                mode: solid,
+                block_size: solid_block_size,
+            },
+        dedup,
+        fail_fast,
+        archive_id,
+    }: CreateArchiveOptions,
     target_items: Vec<PathBuf>,
     max_file_size: usize,
 ) -> io::Result<()> {
-    let (tx, rx) = std::sync::mpsc::channel();
-    let option = if solid {
+    let option = if solid.is_solid() {
         WriteOptions::store()
     } else {
         write_option.clone()
@@ -269,29 +860,51 @@ fn create_archive_with_split(
         option,
         keep_options,
         owner_options,
+        platform_tags,
     };
-    for file in target_items {
-        let tx = tx.clone();
-        rayon::scope_fifo(|s| {
-            s.spawn_fifo(|_| {
-                log::debug!("Adding: {}", file.display());
-                tx.send(create_entry(&file, &create_options))
-                    .unwrap_or_else(|e| panic!("{e}: {}", file.display()));
-            })
-        });
-    }
-
-    drop(tx);
+    let duplicates = if dedup {
+        find_duplicates(&target_items)
+    } else {
+        HashMap::new()
+    };
+    let progress = Progress::new(Some(contents_size(&target_items, &duplicates)));
+    let entries = par_map_ordered(target_items, |file| {
+        if let Some(original) = duplicates.get(&file) {
+            log::debug!("Adding: {} as a hard link", file.display());
+            return create_hard_link_entry(&file, original, &create_options);
+        }
+        log::debug!("Adding: {}", file.display());
+        progress.start(&file);
+        create_entry(&file, &create_options).inspect(|entry| progress.inc(raw_file_size(entry)))
+    });
 
-    if solid {
-        let mut entries_builder = SolidEntryBuilder::new(write_option)?;
-        for entry in rx.into_iter() {
-            entries_builder.add_entry(entry?)?;
+    let mut policy = FailurePolicy::new(fail_fast);
+    if solid.is_solid() {
+        let mut blocks =
+            SolidBlocks::new(solid, write_option, solid_block_size.unwrap_or(usize::MAX))?;
+        let mut full_blocks = Vec::new();
+        for entry in entries {
+            if let Some(entry) = policy.check(entry)? {
+                full_blocks.extend(blocks.push(entry)?);
+            }
         }
-        let entries = entries_builder.build();
-        write_split_archive(archive, [entries].into_iter(), max_file_size)?;
+        full_blocks.extend(blocks.finish());
+        write_split_archive(
+            archive,
+            archive_id,
+            full_blocks.into_iter().map(SolidEntryBuilder::build),
+            max_file_size,
+            PartBoundary::Chunk,
+        )?;
     } else {
-        write_split_archive(archive, rx.into_iter(), max_file_size)?;
+        let entries = entries.filter_map(|entry| policy.check(entry).transpose());
+        write_split_archive(
+            archive,
+            archive_id,
+            entries,
+            max_file_size,
+            PartBoundary::Chunk,
+        )?;
     }
-    Ok(())
+    policy.finish()
 }
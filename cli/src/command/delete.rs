@@ -1,12 +1,14 @@
 use crate::{
     cli::{
-        FileArgs, PasswordArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs,
+        FileArgs, PasswordArgs, ReplaceArgs, SolidEntriesTransformStrategy,
+        SolidEntriesTransformStrategyArgs,
     },
     command::{
         ask_password,
         commons::{run_transform_entry, TransformStrategyKeepSolid, TransformStrategyUnSolid},
         Command,
     },
+    exit_code::usage_error,
     utils::{GlobPatterns, PathPartExt},
 };
 use clap::{ArgGroup, Parser, ValueHint};
@@ -24,6 +26,14 @@ pub(crate) struct DeleteCommand {
     #[command(flatten)]
     pub(crate) transform_strategy: SolidEntriesTransformStrategyArgs,
     #[command(flatten)]
+    replace: ReplaceArgs,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Match the name patterns only against the entry names themselves, not against the entries under the directories they name"
+    )]
+    no_recursion: bool,
+    #[command(flatten)]
     file: FileArgs,
 }
 
@@ -36,16 +46,16 @@ impl Command for DeleteCommand {
 
 fn delete_file_from_archive(args: DeleteCommand) -> io::Result<()> {
     let password = ask_password(args.password)?;
-    let globs = GlobPatterns::new(args.file.files)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
-    let exclude_globs = GlobPatterns::try_from(args.exclude.unwrap_or_default())
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs =
+        GlobPatterns::with_recursion(args.file.files, !args.no_recursion).map_err(usage_error)?;
+    let exclude_globs =
+        GlobPatterns::try_from(args.exclude.unwrap_or_default()).map_err(usage_error)?;
     match args.transform_strategy.strategy() {
         SolidEntriesTransformStrategy::UnSolid => run_transform_entry(
             args.output
                 .unwrap_or_else(|| args.file.archive.remove_part().unwrap()),
             &args.file.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 let entry_path = entry.header().path();
@@ -55,12 +65,13 @@ fn delete_file_from_archive(args: DeleteCommand) -> io::Result<()> {
                 Ok(Some(entry))
             },
             TransformStrategyUnSolid,
+            &args.replace,
         ),
         SolidEntriesTransformStrategy::KeepSolid => run_transform_entry(
             args.output
                 .unwrap_or_else(|| args.file.archive.remove_part().unwrap()),
             &args.file.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 let entry_path = entry.header().path();
@@ -70,6 +81,7 @@ fn delete_file_from_archive(args: DeleteCommand) -> io::Result<()> {
                 Ok(Some(entry))
             },
             TransformStrategyKeepSolid,
+            &args.replace,
         ),
     }
 }
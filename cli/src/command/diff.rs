@@ -0,0 +1,538 @@
+use crate::{
+    cli::{FileArgs, PasswordArgs},
+    command::{
+        ask_password,
+        commons::{run_process_archive, PathArchiveProvider},
+        Command,
+    },
+    exit_code::{has_status, usage_error, with_status, ExitStatus, StatusReader},
+    utils::{fmt::DataKindDisplay, fs::FileId, GlobPatterns},
+};
+use chrono::DateTime;
+use clap::{Parser, ValueEnum, ValueHint};
+use pna::{DataKind, Encryption, EntryReference, NormalEntry, ReadOptions};
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{self, prelude::*},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::UNIX_EPOCH,
+};
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct DiffCommand {
+    #[arg(
+        short = 'C',
+        long,
+        value_name = "DIRECTORY",
+        help = "Compare with the files under the directory instead of the current directory",
+        value_hint = ValueHint::DirPath
+    )]
+    directory: Option<PathBuf>,
+    #[arg(long, help = "Compare extended attributes")]
+    check_xattr: bool,
+    #[arg(long, help = "Compare ACLs of the entries that have ACLs")]
+    check_acl: bool,
+    #[arg(
+        long,
+        help = "Report files that cannot be read as unreadable and continue, instead of stopping"
+    )]
+    keep_going: bool,
+    #[arg(
+        long,
+        value_name = "KIND=CODE",
+        value_delimiter = ',',
+        help = "Exit code for each kind of differences, content, metadata or unreadable. The largest code of the found kinds is used [default: content=1,metadata=1,unreadable=2]"
+    )]
+    exit_code_map: Vec<ExitCodeMapping>,
+    #[command(flatten)]
+    password: PasswordArgs,
+    #[command(flatten)]
+    file: FileArgs,
+}
+
+impl Command for DiffCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        diff_archive(self)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum)]
+enum DifferenceKind {
+    /// Missing files, and differences of the types, contents and link targets
+    Content,
+    /// Differences of the timestamps, permissions, owners, extended attributes and ACLs
+    Metadata,
+    /// Files that cannot be read
+    Unreadable,
+}
+
+impl DifferenceKind {
+    #[inline]
+    const fn default_status(self) -> ExitStatus {
+        match self {
+            Self::Content | Self::Metadata => ExitStatus::Failure,
+            Self::Unreadable => ExitStatus::PartialFailure,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct ExitCodeMapping {
+    kind: DifferenceKind,
+    status: ExitStatus,
+}
+
+impl FromStr for ExitCodeMapping {
+    type Err = String;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, code) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected KIND=CODE, but got `{s}`"))?;
+        let kind = <DifferenceKind as ValueEnum>::from_str(kind, true)?;
+        let code = code
+            .parse::<u8>()
+            .map_err(|e| format!("invalid CODE `{code}`: {e}"))?;
+        Ok(Self {
+            kind,
+            status: ExitStatus::try_from(code)?,
+        })
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+struct Difference {
+    kind: DifferenceKind,
+    aspect: &'static str,
+    detail: Option<String>,
+}
+
+impl Difference {
+    #[inline]
+    const fn new(kind: DifferenceKind, aspect: &'static str) -> Self {
+        Self {
+            kind,
+            aspect,
+            detail: None,
+        }
+    }
+
+    #[inline]
+    fn with_detail(kind: DifferenceKind, aspect: &'static str, detail: String) -> Self {
+        Self {
+            kind,
+            aspect,
+            detail: Some(detail),
+        }
+    }
+}
+
+/// Error while comparing an entry, distinguishing the side it comes from.
+enum DiffError {
+    Archive(io::Error),
+    Filesystem(io::Error),
+}
+
+#[cfg_attr(not(any(unix, feature = "acl")), allow(dead_code))]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+struct DiffOptions {
+    check_xattr: bool,
+    check_acl: bool,
+}
+
+fn diff_archive(args: DiffCommand) -> io::Result<()> {
+    let password = ask_password(args.password)?;
+    let read_options = ReadOptions::with_password(password.as_deref());
+    let globs = GlobPatterns::new(args.file.files).map_err(usage_error)?;
+    #[cfg(not(unix))]
+    if args.check_xattr {
+        log::warn!("Currently extended attribute is not supported on this platform.");
+    }
+    #[cfg(not(feature = "acl"))]
+    if args.check_acl {
+        log::warn!("Please enable `acl` feature and rebuild and install pna.");
+    }
+    let options = DiffOptions {
+        check_xattr: args.check_xattr,
+        check_acl: args.check_acl,
+    };
+    let base = args.directory.unwrap_or_else(|| PathBuf::from("."));
+
+    let mut stdout = io::stdout().lock();
+    let mut counts = BTreeMap::<DifferenceKind, usize>::new();
+    run_process_archive(
+        PathArchiveProvider::new(&args.file.archive),
+        || password.as_deref().map(String::as_str),
+        |entry| {
+            let entry = entry?;
+            let name = entry.header().path();
+            if !globs.is_empty() && !globs.matches_any(name) {
+                return Ok(());
+            }
+            let path = base.join(name);
+            let differences = match diff_entry(&entry, &path, &read_options, options) {
+                Ok(differences) => differences,
+                Err(DiffError::Filesystem(e)) if args.keep_going => vec![Difference::with_detail(
+                    DifferenceKind::Unreadable,
+                    "unreadable",
+                    e.to_string(),
+                )],
+                Err(DiffError::Filesystem(e)) => {
+                    return Err(io::Error::new(e.kind(), format!("{}: {e}", path.display())))
+                }
+                Err(DiffError::Archive(e)) => return Err(e),
+            };
+            for difference in differences {
+                *counts.entry(difference.kind).or_default() += 1;
+                write!(stdout, "{}: {name}", difference.aspect)?;
+                if let Some(detail) = difference.detail {
+                    write!(stdout, ": {detail}")?;
+                }
+                writeln!(stdout)?;
+            }
+            Ok(())
+        },
+    )?;
+    stdout.flush()?;
+    globs.ensure_all_matched()?;
+
+    let status = counts
+        .keys()
+        .map(|kind| {
+            args.exit_code_map
+                .iter()
+                .rev()
+                .find(|it| it.kind == *kind)
+                .map_or_else(|| kind.default_status(), |it| it.status)
+        })
+        .max();
+    match status {
+        None | Some(ExitStatus::Success) => Ok(()),
+        Some(status) => Err(with_status(
+            status,
+            io::Error::other(format!(
+                "{} differences found",
+                counts.values().sum::<usize>()
+            )),
+        )),
+    }
+}
+
+#[cfg_attr(not(any(unix, feature = "acl")), allow(unused_variables))]
+fn diff_entry(
+    entry: &NormalEntry,
+    path: &Path,
+    read_options: &ReadOptions,
+    options: DiffOptions,
+) -> Result<Vec<Difference>, DiffError> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Ok(vec![Difference::new(DifferenceKind::Content, "missing")])
+        }
+        Err(e) => return Err(DiffError::Filesystem(e)),
+    };
+    let data_kind = entry.header().data_kind();
+    let file_type = meta.file_type();
+    let same_type = match data_kind {
+        DataKind::File | DataKind::HardLink => file_type.is_file(),
+        DataKind::Directory => file_type.is_dir(),
+        DataKind::SymbolicLink => file_type.is_symlink(),
+    };
+    if !same_type {
+        return Ok(vec![Difference::with_detail(
+            DifferenceKind::Content,
+            "type",
+            format!(
+                "archive {}, filesystem {}",
+                DataKindDisplay(data_kind),
+                FileTypeDisplay(file_type)
+            ),
+        )]);
+    }
+
+    let mut differences = Vec::new();
+    match data_kind {
+        DataKind::File => {
+            let size_differs = entry
+                .metadata()
+                .raw_file_size()
+                .is_some_and(|size| size != u128::from(meta.len()));
+            let file = fs::File::open(path).map_err(DiffError::Filesystem)?;
+            if size_differs || !same_contents(entry_reader(entry, read_options)?, file)? {
+                differences.push(Difference::new(DifferenceKind::Content, "content"));
+            }
+        }
+        DataKind::SymbolicLink => {
+            let target = read_reference(entry, read_options)?;
+            let fs_target =
+                EntryReference::from_lossy(fs::read_link(path).map_err(DiffError::Filesystem)?);
+            if target != fs_target {
+                differences.push(Difference::with_detail(
+                    DifferenceKind::Content,
+                    "link",
+                    format!("archive -> {target}, filesystem -> {fs_target}"),
+                ));
+            }
+            // NOTE: The metadata of symbolic links is the one of their targets.
+            return Ok(differences);
+        }
+        DataKind::HardLink => {
+            let target = read_reference(entry, read_options)?;
+            let target_path = match path.parent() {
+                Some(parent) => parent.join(target.as_path()),
+                None => target.as_path().to_path_buf(),
+            };
+            let linked = match FileId::of(&target_path) {
+                Ok(id) => FileId::of(path).map_err(DiffError::Filesystem)? == id,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+                Err(e) => return Err(DiffError::Filesystem(e)),
+            };
+            if !linked {
+                differences.push(Difference::with_detail(
+                    DifferenceKind::Content,
+                    "hardlink",
+                    format!("not linked to {target}"),
+                ));
+            }
+        }
+        DataKind::Directory => (),
+    }
+
+    diff_metadata(entry, &meta, &mut differences);
+    #[cfg(unix)]
+    if options.check_xattr {
+        diff_xattrs(entry, path, &mut differences)?;
+    }
+    #[cfg(feature = "acl")]
+    if options.check_acl {
+        diff_acl(entry, path, &mut differences)?;
+    }
+    Ok(differences)
+}
+
+fn entry_reader<'e>(
+    entry: &'e NormalEntry,
+    read_options: &ReadOptions,
+) -> Result<impl Read + 'e, DiffError> {
+    let data_error = if entry.header().encryption() == Encryption::No {
+        ExitStatus::CorruptArchive
+    } else {
+        ExitStatus::Crypto
+    };
+    let reader = entry
+        .reader(read_options)
+        .map_err(|e| DiffError::Archive(with_status(data_error, e)))?;
+    Ok(StatusReader::new(reader, data_error))
+}
+
+fn read_reference(
+    entry: &NormalEntry,
+    read_options: &ReadOptions,
+) -> Result<EntryReference, DiffError> {
+    let target =
+        io::read_to_string(entry_reader(entry, read_options)?).map_err(DiffError::Archive)?;
+    Ok(EntryReference::from_lossy(target))
+}
+
+fn same_contents(archive: impl Read, file: impl Read) -> Result<bool, DiffError> {
+    // NOTE: Every read error of the archive has a status attached by [StatusReader],
+    //       so the other errors come from the filesystem.
+    pna::compare_readers(archive, file).map_err(|e| {
+        if has_status(&e) {
+            DiffError::Archive(e)
+        } else {
+            DiffError::Filesystem(e)
+        }
+    })
+}
+
+fn diff_metadata(entry: &NormalEntry, meta: &fs::Metadata, differences: &mut Vec<Difference>) {
+    let metadata = entry.metadata();
+    if let (Some(archive), Ok(fs)) = (metadata.modified(), meta.modified()) {
+        if let Ok(fs) = fs.duration_since(UNIX_EPOCH) {
+            if archive.as_secs() != fs.as_secs() {
+                differences.push(Difference::with_detail(
+                    DifferenceKind::Metadata,
+                    "mtime",
+                    format!(
+                        "archive {}, filesystem {}",
+                        TimestampDisplay(archive.as_secs()),
+                        TimestampDisplay(fs.as_secs())
+                    ),
+                ));
+            }
+        }
+    }
+    #[cfg(unix)]
+    if let Some(permission) = metadata.permission() {
+        use std::os::unix::fs::MetadataExt;
+
+        let (archive_mode, fs_mode) = (permission.permissions() & 0o7777, meta.mode() & 0o7777);
+        if u32::from(archive_mode) != fs_mode {
+            differences.push(Difference::with_detail(
+                DifferenceKind::Metadata,
+                "mode",
+                format!("archive {archive_mode:o}, filesystem {fs_mode:o}"),
+            ));
+        }
+        let archive_owner = (permission.uid(), permission.gid());
+        let fs_owner = (u64::from(meta.uid()), u64::from(meta.gid()));
+        if archive_owner != fs_owner {
+            differences.push(Difference::with_detail(
+                DifferenceKind::Metadata,
+                "owner",
+                format!(
+                    "archive {}:{}, filesystem {}:{}",
+                    archive_owner.0, archive_owner.1, fs_owner.0, fs_owner.1
+                ),
+            ));
+        }
+    }
+}
+
+#[cfg(unix)]
+fn diff_xattrs(
+    entry: &NormalEntry,
+    path: &Path,
+    differences: &mut Vec<Difference>,
+) -> Result<(), DiffError> {
+    let archive = entry
+        .xattrs()
+        .iter()
+        .map(|it| (it.name(), it.value()))
+        .collect::<BTreeMap<_, _>>();
+    let fs_xattrs =
+        crate::utils::os::unix::fs::xattrs::get_xattrs(path).map_err(DiffError::Filesystem)?;
+    let fs = fs_xattrs
+        .iter()
+        .map(|it| (it.name(), it.value()))
+        .collect::<BTreeMap<_, _>>();
+    let names = archive
+        .keys()
+        .chain(fs.keys())
+        .collect::<std::collections::BTreeSet<_>>();
+    for name in names {
+        let detail = match (archive.get(name), fs.get(name)) {
+            (Some(a), Some(b)) if a == b => continue,
+            (Some(_), Some(_)) => format!("{name} differs"),
+            (Some(_), None) => format!("{name} is missing in filesystem"),
+            (None, _) => format!("{name} is not in archive"),
+        };
+        differences.push(Difference::with_detail(
+            DifferenceKind::Metadata,
+            "xattr",
+            detail,
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "acl")]
+fn diff_acl(
+    entry: &NormalEntry,
+    path: &Path,
+    differences: &mut Vec<Difference>,
+) -> Result<(), DiffError> {
+    use crate::{
+        chunk::acl_for_current_platform,
+        ext::*,
+        utils::os::{Current, Platform},
+    };
+
+    let acls = entry.acl().map_err(DiffError::Archive)?;
+    // NOTE: Entries without ACLs did not record them, so there is nothing to compare.
+    let Some(acl) = acl_for_current_platform(acls) else {
+        return Ok(());
+    };
+    let mut archive = acl.entries;
+    let mut fs = match Current::get_facl(path) {
+        Ok(acl) => acl.entries,
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            log::warn!("{}", e);
+            return Ok(());
+        }
+        Err(e) => return Err(DiffError::Filesystem(e)),
+    };
+    archive.sort();
+    fs.sort();
+    if archive != fs {
+        differences.push(Difference::new(DifferenceKind::Metadata, "acl"));
+    }
+    Ok(())
+}
+
+struct FileTypeDisplay(fs::FileType);
+
+impl fmt::Display for FileTypeDisplay {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_file() {
+            "file"
+        } else if self.0.is_dir() {
+            "directory"
+        } else if self.0.is_symlink() {
+            "symbolic link"
+        } else {
+            "other"
+        })
+    }
+}
+
+struct TimestampDisplay(u64);
+
+impl fmt::Display for TimestampDisplay {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match i64::try_from(self.0)
+            .ok()
+            .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        {
+            Some(time) => write!(f, "{}", time.format("%Y-%m-%dT%H:%M:%SZ")),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_exit_code_mapping() {
+        assert_eq!(
+            "metadata=0".parse(),
+            Ok(ExitCodeMapping {
+                kind: DifferenceKind::Metadata,
+                status: ExitStatus::Success,
+            })
+        );
+        assert_eq!(
+            "Content=2".parse(),
+            Ok(ExitCodeMapping {
+                kind: DifferenceKind::Content,
+                status: ExitStatus::PartialFailure,
+            })
+        );
+        assert!("metadata".parse::<ExitCodeMapping>().is_err());
+        assert!("unknown=1".parse::<ExitCodeMapping>().is_err());
+        assert!("metadata=9".parse::<ExitCodeMapping>().is_err());
+    }
+
+    #[test]
+    fn compare_contents() {
+        let same = |a: &[u8], b: &[u8]| same_contents(a, b).ok().unwrap();
+        assert!(same(b"", b""));
+        assert!(same(b"text", b"text"));
+        assert!(!same(b"text", b"test"));
+        assert!(!same(b"text", b"text!"));
+        assert!(!same(b"text!", b"text"));
+        let large = vec![1; 200 * 1024];
+        assert!(same(&large, &large));
+        assert!(!same(&large, &large[1..]));
+    }
+}
@@ -22,6 +22,18 @@ impl Command for ExperimentalCommand {
             ExperimentalCommands::Acl(cmd) => cmd.execute(),
             ExperimentalCommands::Migrate(cmd) => cmd.execute(),
             ExperimentalCommands::Chunk(cmd) => cmd.execute(),
+            ExperimentalCommands::Grep(cmd) => cmd.execute(),
+            ExperimentalCommands::Lint(cmd) => cmd.execute(),
+            ExperimentalCommands::Sign(cmd) => cmd.execute(),
+            ExperimentalCommands::VerifySignatures(cmd) => cmd.execute(),
+            ExperimentalCommands::Cat(cmd) => cmd.execute(),
+            ExperimentalCommands::Diff(cmd) => cmd.execute(),
+            ExperimentalCommands::Info(cmd) => cmd.execute(),
+            ExperimentalCommands::Repair(cmd) => cmd.execute(),
+            ExperimentalCommands::Verify(cmd) => cmd.execute(),
+            ExperimentalCommands::Put(cmd) => cmd.execute(),
+            #[cfg(all(unix, feature = "fuse"))]
+            ExperimentalCommands::Mount(cmd) => cmd.execute(),
         }
     }
 }
@@ -46,4 +58,27 @@ pub(crate) enum ExperimentalCommands {
     Migrate(command::migrate::MigrateCommand),
     #[command(about = "Chunk level operation")]
     Chunk(command::chunk::ChunkCommand),
+    #[command(about = "Search contents of entries for a pattern")]
+    Grep(command::grep::GrepCommand),
+    #[command(about = "Check entry names and links for cross-platform portability problems")]
+    Lint(command::lint::LintCommand),
+    #[command(about = "Sign entries and the archive with an Ed25519 key")]
+    Sign(command::signature::SignCommand),
+    #[command(about = "Verify Ed25519 signatures of entries and the archive")]
+    VerifySignatures(command::signature::VerifySignaturesCommand),
+    #[command(about = "Print contents of entries")]
+    Cat(command::cat::CatCommand),
+    #[command(about = "Compare entries of archive with files")]
+    Diff(command::diff::DiffCommand),
+    #[command(about = "Show format level information of archive from its chunk headers")]
+    Info(command::info::InfoCommand),
+    #[command(about = "Salvage the intact entries of a damaged archive into a new archive")]
+    Repair(command::repair::RepairCommand),
+    #[command(about = "Verify that every entry of archive can be read and decoded")]
+    Verify(command::verify::VerifyCommand),
+    #[command(about = "Replace the content of an entry with the data read from stdin")]
+    Put(command::put::PutCommand),
+    #[cfg(all(unix, feature = "fuse"))]
+    #[command(about = "Mount archive as a read-only filesystem")]
+    Mount(command::mount::MountCommand),
 }
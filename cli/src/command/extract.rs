@@ -1,52 +1,210 @@
 #[cfg(feature = "memmap")]
-use crate::command::commons::run_entries;
-#[cfg(not(feature = "memmap"))]
-use crate::command::commons::PathArchiveProvider;
+use crate::command::commons::run_entries_with_cache;
+#[cfg(windows)]
+use crate::utils::fs::chown;
 #[cfg(any(unix, windows))]
-use crate::utils::fs::{chown, Group, User};
+use crate::utils::fs::{Group, User};
+#[cfg(unix)]
+use crate::utils::id_map::IdMap;
+#[cfg(feature = "http")]
+use crate::{cli::HttpArgs, command::commons::HttpArchiveProvider};
 use crate::{
-    cli::{FileArgs, PasswordArgs},
+    cli::{FailFastArgs, FileArgs, IdMapArgs, PasswordArgs, PathFilterArgs},
     command::{
         ask_password,
-        commons::{run_process_archive, ArchiveProvider, KeepOptions, OwnerOptions},
-        Command,
+        commons::{
+            run_in_pool, run_process_archive, run_process_archive_with_cache, run_read_entries,
+            ArchiveProvider, KeepOptions, OwnerOptions, PathArchiveProvider, ResumeArchiveProvider,
+            StdinArchiveProvider,
+        },
+        read_identities, Command,
+    },
+    exit_code::{usage_error, with_status, ExitStatus, FailurePolicy, StatusReader},
+    ext::*,
+    utils::{
+        self,
+        fmt::{DataKindDisplay, DurationDisplay},
+        fs::FileId,
+        is_windows_reserved_name,
+        progress::Progress,
+        resume::ResumeState,
+        solid_cache::SolidCache,
+        transform::{NameKind, SubstitutionRule, Transformer},
+        url::ArchiveLocation,
+        GlobPatterns, PathFilter,
     },
-    utils::{self, fmt::DurationDisplay, GlobPatterns},
 };
-use clap::{ArgGroup, Parser, ValueHint};
-use pna::{prelude::*, DataKind, EntryReference, NormalEntry, Permission, ReadOptions};
+use bytesize::ByteSize;
+use clap::{ArgGroup, Parser, ValueEnum, ValueHint};
+use pna::{
+    prelude::*, DataKind, Encryption, EntryHeader, EntryReference, ExtendedAttribute, Identity,
+    Metadata, NormalEntry, Permission, ReadEntry, ReadOptions,
+};
+use serde::Serialize;
 #[cfg(target_os = "macos")]
 use std::os::macos::fs::FileTimesExt;
 #[cfg(windows)]
 use std::os::windows::fs::FileTimesExt;
-#[cfg(feature = "memmap")]
-use std::path::Path;
-use std::{borrow::Cow, fs, io, path::PathBuf, time::Instant};
+#[cfg(unix)]
+use std::sync::{Mutex, PoisonError};
+use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, UNIX_EPOCH},
+};
 
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[command(
     group(ArgGroup::new("unstable-acl").args(["keep_acl"]).requires("unstable")),
+    group(ArgGroup::new("unstable-xattr-filter").args(["xattr_max_size", "xattr_namespace"]).multiple(true).requires("unstable")),
+    group(ArgGroup::new("unstable-identity").args(["identity"]).requires("unstable")),
+    group(ArgGroup::new("unstable-solid-cache").args(["solid_cache", "solid_cache_limit", "solid_cache_encrypted"]).multiple(true).requires("unstable")),
+    group(ArgGroup::new("unstable-metadata-only").args(["metadata_only"]).requires("unstable")),
+    group(ArgGroup::new("unstable-merge").args(["merge"]).requires("unstable")),
+    group(ArgGroup::new("unstable-resume").args(["resume"]).requires("unstable")),
+    group(ArgGroup::new("unstable-skip-existing").args(["skip_existing", "keep_newer_files"]).requires("unstable")),
     group(ArgGroup::new("user-flag").args(["numeric_owner", "uname"])),
     group(ArgGroup::new("group-flag").args(["numeric_owner", "gname"])),
 )]
 #[cfg_attr(windows, command(
-    group(ArgGroup::new("windows-unstable-keep-permission").args(["keep_permission"]).requires("unstable")),
+    group(ArgGroup::new("windows-unstable-keep-permission").args(["keep_permission", "keep_owner", "keep_mode"]).multiple(true).requires("unstable")),
 ))]
 pub(crate) struct ExtractCommand {
     #[arg(long, help = "Overwrite file")]
     pub(crate) overwrite: bool,
+    #[arg(
+        long,
+        conflicts_with = "overwrite",
+        help = "Merge the entries into the existing files. Missing files are created, files that differ from the entries are overwritten and identical files are left as they are. Existing files are never deleted, and those of another type are reported as conflicts (unstable)"
+    )]
+    pub(crate) merge: bool,
+    #[arg(
+        long,
+        requires = "merge",
+        help = "With --merge, compare the contents of the existing files instead of their sizes and modification times"
+    )]
+    pub(crate) merge_verify: bool,
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "merge",
+        help = "With --merge, write the action taken for each entry to the given file as JSON lines",
+        value_hint = ValueHint::FilePath
+    )]
+    pub(crate) report_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "STATE",
+        conflicts_with = "metadata_only",
+        help = "Record the completed entries to the given state file, and continue an interrupted extraction from it. Entries recorded as completed are skipped, and files left by the interrupted extraction are overwritten (unstable)",
+        value_hint = ValueHint::FilePath
+    )]
+    pub(crate) resume: Option<PathBuf>,
+    #[arg(
+        long,
+        conflicts_with_all = ["merge", "keep_newer_files"],
+        help = "Skip the entries whose files already exist with the same size and modification time, symbolic links with the same target and hard links to the same file. Other existing files are overwritten. Use with --keep-timestamp to continue an interrupted extraction (unstable)"
+    )]
+    pub(crate) skip_existing: bool,
+    #[arg(
+        long,
+        conflicts_with = "merge",
+        help = "Skip the entries whose files already exist and are newer than the entries, symbolic links with the same target and hard links to the same file. Other existing files are overwritten (unstable)"
+    )]
+    pub(crate) keep_newer_files: bool,
     #[arg(long, help = "Output directory of extracted files", value_hint = ValueHint::DirPath)]
     pub(crate) out_dir: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "MODE",
+        value_parser = parse_dir_mode,
+        help = "Permissions in octal of the directories created by extraction, such as the output directory and its parents. Directories restored from entries are not affected"
+    )]
+    pub(crate) out_dir_mode: Option<u32>,
     #[command(flatten)]
     pub(crate) password: PasswordArgs,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read identities to decrypt entries encrypted to recipients from the given file. This option can be specified multiple times (unstable)",
+        value_hint = ValueHint::FilePath
+    )]
+    pub(crate) identity: Vec<PathBuf>,
+    #[arg(
+        long,
+        help = "Skip the directory entries, which are restored by default. Directories are still created as the parents of the extracted files"
+    )]
+    pub(crate) no_keep_dir: bool,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Match the name patterns only against the entry names themselves, not against the entries under the directories they name"
+    )]
+    pub(crate) no_recursion: bool,
+    #[command(flatten)]
+    pub(crate) path_filter: PathFilterArgs,
+    #[arg(
+        long,
+        value_name = "MODE",
+        default_value = "metadata",
+        help = "How to create the parent directories of the extracted entries whose own directory entries are not selected by the file filters"
+    )]
+    pub(crate) implicit_dirs: ImplicitDirs,
     #[arg(long, help = "Restore the timestamp of the files")]
     pub(crate) keep_timestamp: bool,
-    #[arg(long, help = "Restore the permissions of the files")]
+    #[arg(
+        long,
+        help = "Restore the permissions of the files. This is equivalent to --keep-owner --keep-mode"
+    )]
     pub(crate) keep_permission: bool,
+    #[arg(
+        long,
+        visible_alias = "same-owner",
+        overrides_with = "no_keep_owner",
+        help = "Restore the owners of the files. Owners that cannot be restored without privileges are left as the extracting user with a warning"
+    )]
+    pub(crate) keep_owner: bool,
+    #[arg(
+        long,
+        visible_alias = "no-same-owner",
+        overrides_with = "keep_owner",
+        help = "Do not restore the owners of the files even with --keep-permission, leaving them owned by the extracting user"
+    )]
+    pub(crate) no_keep_owner: bool,
+    #[arg(
+        long,
+        overrides_with = "no_keep_mode",
+        help = "Restore the modes of the files"
+    )]
+    pub(crate) keep_mode: bool,
+    #[arg(
+        long,
+        overrides_with = "keep_mode",
+        help = "Do not restore the modes of the files even with --keep-permission"
+    )]
+    pub(crate) no_keep_mode: bool,
     #[arg(long, help = "Restore the extended attributes of the files")]
     pub(crate) keep_xattr: bool,
     #[arg(long, help = "Restore the acl of the files")]
     pub(crate) keep_acl: bool,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        requires = "keep_xattr",
+        help = "Skip extended attributes larger than the given size (unstable)"
+    )]
+    pub(crate) xattr_max_size: Option<ByteSize>,
+    #[arg(
+        long,
+        value_name = "NAMESPACE",
+        requires = "keep_xattr",
+        help = "Restore only extended attributes in the given namespace. This option can be specified multiple times (unstable)"
+    )]
+    pub(crate) xattr_namespace: Vec<XattrNamespace>,
     #[arg(long, help = "Restore user from given name")]
     pub(crate) uname: Option<String>,
     #[arg(long, help = "Restore group from given name")]
@@ -67,7 +225,135 @@ pub(crate) struct ExtractCommand {
     )]
     pub(crate) numeric_owner: bool,
     #[command(flatten)]
+    pub(crate) id_map: IdMapArgs,
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "keep",
+        help = "How to restore the owners in the archive that don't exist on this system, with --keep-permission"
+    )]
+    pub(crate) unknown_owner: UnknownOwner,
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Cache decompressed solid entries in the given directory to speed up repeated extraction (unstable)",
+        value_hint = ValueHint::DirPath
+    )]
+    pub(crate) solid_cache: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "SIZE",
+        requires = "solid_cache",
+        help = "Maximum total size of the solid cache, least recently used entries are evicted (unstable)"
+    )]
+    pub(crate) solid_cache_limit: Option<ByteSize>,
+    #[arg(
+        long,
+        requires = "solid_cache",
+        help = "Also cache encrypted solid entries. The cache holds their decrypted contents (unstable)"
+    )]
+    pub(crate) solid_cache_encrypted: bool,
+    #[arg(
+        long,
+        value_name = "MODE",
+        default_value = "allow",
+        help = "How to extract entries whose names are reserved on Windows, such as `con` or `aux.txt`"
+    )]
+    pub(crate) windows_compat: WindowsCompat,
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        default_value = "_",
+        allow_hyphen_values = true,
+        value_parser = parse_windows_compat_suffix,
+        help = "Suffix appended to the base name of reserved names with `--windows-compat rename`"
+    )]
+    pub(crate) windows_compat_suffix: String,
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "error",
+        help = "How to extract entries whose names differ only in case, when the output directory is on a case-insensitive filesystem"
+    )]
+    pub(crate) case_collision: CaseCollision,
+    #[arg(
+        long,
+        help = "Convert backslashes in entry names to slashes and collapse duplicate slashes before extracting them"
+    )]
+    pub(crate) normalize_paths: bool,
+    #[arg(
+        long,
+        value_name = "EXPRESSION",
+        value_parser = SubstitutionRule::parse_gnu,
+        help = "Rewrite entry names with a sed-like expression `s/regex/replacement/flags` before extracting them, as GNU tar does. The flags `S`, `H` and `R` exclude symbolic link targets, hard link targets and entry names. This option can be specified multiple times, and the expressions are applied in order"
+    )]
+    pub(crate) transform: Vec<SubstitutionRule>,
+    #[arg(
+        short = 's',
+        value_name = "PATTERN",
+        value_parser = SubstitutionRule::parse_bsd,
+        conflicts_with = "transform",
+        help = "Rewrite entry names with a pattern `/regex/replacement/flags` before extracting them, as BSD tar does. This option can be specified multiple times, and only the first matching pattern is applied"
+    )]
+    pub(crate) substitution: Vec<SubstitutionRule>,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Remove N leading components from entry names before extracting them, after --transform or -s is applied. Entries with no more components are skipped. Link targets inside the archive are stripped as well, so that they point to the same entries"
+    )]
+    pub(crate) strip_components: Option<usize>,
+    #[arg(
+        long,
+        help = "Extract also the entries tagged with --platform-tag for other operating systems, which are skipped by default"
+    )]
+    pub(crate) all_platforms: bool,
+    #[arg(
+        long,
+        help = "Print the metadata of the entries instead of extracting them (unstable)",
+        long_help = METADATA_ONLY_HELP
+    )]
+    pub(crate) metadata_only: bool,
+    #[arg(
+        short = 'O',
+        long,
+        conflicts_with_all = ["metadata_only", "merge", "resume"],
+        help = "Write the contents of the file entries matching the given patterns to stdout in archive order instead of creating files. Directories and links are skipped, and the patterns are required"
+    )]
+    pub(crate) to_stdout: bool,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "jsonl",
+        requires = "metadata_only",
+        help = "Output format of --metadata-only"
+    )]
+    pub(crate) format: MetadataFormat,
+    #[command(flatten)]
+    pub(crate) fail_fast: FailFastArgs,
+    #[command(flatten)]
     pub(crate) file: FileArgs,
+    #[cfg(feature = "http")]
+    #[command(flatten)]
+    pub(crate) http: HttpArgs,
+}
+
+const METADATA_ONLY_HELP: &str = "Print the metadata of the entries instead of extracting them, without writing any file (unstable).
+
+Each entry is printed as a JSON object on a line, with the fields:
+  header: {path, data_kind, compression, encryption, cipher_mode}
+  solid: true if the entry is in a solid entry
+  metadata: {raw_file_size, compressed_size, created, modified, accessed, permission}
+    created, modified and accessed are {seconds, nanoseconds} since the unix epoch, or null
+    permission is {uid, uname, gid, gname, mode}, or null
+  xattrs: [{name, size, value}], where value is encoded in base64
+  acl: {platform: [ace]}
+  extra_chunks: [chunk type]";
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, ValueEnum)]
+pub(crate) enum MetadataFormat {
+    /// One JSON object per line
+    #[default]
+    Jsonl,
 }
 
 impl Command for ExtractCommand {
@@ -76,14 +362,116 @@ impl Command for ExtractCommand {
         extract_archive(self)
     }
 }
+fn parse_dir_mode(s: &str) -> Result<u32, String> {
+    match u32::from_str_radix(s, 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        Ok(_) => Err(format!("mode {s} is out of range")),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_windows_compat_suffix(s: &str) -> Result<String, String> {
+    if s.is_empty() || s.contains(['.', '/', '\\']) {
+        return Err("suffix must not be empty or contain `.`, `/` or `\\`".into());
+    }
+    Ok(s.into())
+}
+
 fn extract_archive(args: ExtractCommand) -> io::Result<()> {
+    let location = ArchiveLocation::parse(&args.file.archive)?;
+    let filter = args.path_filter.path_filter()?;
+    if args.metadata_only {
+        return match &location {
+            ArchiveLocation::Path(path) => dump_metadata(
+                PathArchiveProvider::new(path),
+                args.password,
+                &args.file.files,
+                !args.no_recursion,
+                &filter,
+            ),
+            ArchiveLocation::Stdio => dump_metadata(
+                StdinArchiveProvider::new(),
+                args.password,
+                &args.file.files,
+                !args.no_recursion,
+                &filter,
+            ),
+            #[cfg(feature = "http")]
+            ArchiveLocation::Url(url) => dump_metadata(
+                HttpArchiveProvider::new(url, &args.http),
+                args.password,
+                &args.file.files,
+                !args.no_recursion,
+                &filter,
+            ),
+        };
+    }
+    if args.to_stdout {
+        if args.file.files.is_empty() {
+            return Err(usage_error(
+                "--to-stdout requires the names or patterns of the entries to write",
+            ));
+        }
+        return match &location {
+            ArchiveLocation::Path(path) => write_to_stdout(
+                PathArchiveProvider::new(path),
+                args.password,
+                &args.identity,
+                &args.file.files,
+                !args.no_recursion,
+                &filter,
+            ),
+            ArchiveLocation::Stdio => write_to_stdout(
+                StdinArchiveProvider::new(),
+                args.password,
+                &args.identity,
+                &args.file.files,
+                !args.no_recursion,
+                &filter,
+            ),
+            #[cfg(feature = "http")]
+            ArchiveLocation::Url(url) => write_to_stdout(
+                HttpArchiveProvider::new(url, &args.http),
+                args.password,
+                &args.identity,
+                &args.file.files,
+                !args.no_recursion,
+                &filter,
+            ),
+        };
+    }
+    let resume = match (&args.resume, &location) {
+        (None, _) => None,
+        (Some(state), ArchiveLocation::Path(path)) => Some(ResumeState::open(state, path)?),
+        (Some(_), ArchiveLocation::Stdio) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "--resume is not supported for archives read from the standard input",
+            ))
+        }
+        #[cfg(feature = "http")]
+        (Some(_), ArchiveLocation::Url(_)) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "--resume is not supported for archives read from a URL",
+            ))
+        }
+    };
+    // NOTE: Create the output directory before asking the password to fail early.
+    if let Some(out_dir) = &args.out_dir {
+        utils::fs::create_dir_all_with_mode(out_dir, args.out_dir_mode)?;
+    }
     let password = ask_password(args.password)?;
+    let identities = read_identities(&args.identity)?;
     let start = Instant::now();
     log::info!("Extract archive {}", args.file.archive.display());
     let keep_options = KeepOptions {
         keep_timestamp: args.keep_timestamp,
-        keep_permission: args.keep_permission,
+        clamp_time: None,
+        keep_owner: (args.keep_permission || args.keep_owner) && !args.no_keep_owner,
+        keep_mode: (args.keep_permission || args.keep_mode) && !args.no_keep_mode,
         keep_xattr: args.keep_xattr,
+        compress_xattr: false,
         keep_acl: args.keep_acl,
     };
     let owner_options = OwnerOptions::new(
@@ -92,27 +480,121 @@ fn extract_archive(args: ExtractCommand) -> io::Result<()> {
         args.uid,
         args.gid,
         args.numeric_owner,
-    );
+    )
+    .with_id_maps(args.id_map)?;
     let output_options = OutputOption {
-        overwrite: args.overwrite,
+        // NOTE: Files of the entries not recorded as completed may be left half-written by the interruption.
+        overwrite: args.overwrite || resume.as_ref().is_some_and(ResumeState::is_resuming),
+        merge: match (args.merge, args.merge_verify) {
+            (false, _) => MergeMode::Off,
+            (true, false) => MergeMode::Fast,
+            (true, true) => MergeMode::Verify,
+        },
+        skip_existing: match (args.skip_existing, args.keep_newer_files) {
+            (true, _) => SkipExisting::Same,
+            (false, true) => SkipExisting::Newer,
+            (false, false) => SkipExisting::Off,
+        },
+        report_file: args.report_file,
         out_dir: args.out_dir,
+        out_dir_mode: args.out_dir_mode,
+        keep_dir: !args.no_keep_dir,
+        implicit_dirs: args.implicit_dirs,
+        recursion: !args.no_recursion,
         keep_options,
         owner_options,
+        unknown_owner: args.unknown_owner,
+        xattr_options: XattrOptions {
+            max_size: args.xattr_max_size.map(|it| it.as_u64() as usize),
+            namespaces: args.xattr_namespace,
+        },
+        windows_compat: WindowsCompatOptions {
+            mode: args.windows_compat,
+            suffix: args.windows_compat_suffix,
+        },
+        case_collision: args.case_collision,
+        normalize_paths: args.normalize_paths,
+        transformer: if args.substitution.is_empty() {
+            Transformer::gnu(args.transform)
+        } else {
+            Transformer::bsd(args.substitution)
+        },
+        strip_components: args.strip_components.unwrap_or(0),
+        all_platforms: args.all_platforms,
+        identities,
     };
-    #[cfg(not(feature = "memmap"))]
-    run_extract_archive_reader(
-        PathArchiveProvider::new(&args.file.archive),
-        args.file.files,
-        || password.as_deref(),
-        output_options,
-    )?;
-    #[cfg(feature = "memmap")]
-    run_extract_archive(
-        args.file.archive,
-        args.file.files,
-        || password.as_deref(),
-        output_options,
-    )?;
+    let solid_cache = args
+        .solid_cache
+        .map(|dir| {
+            SolidCache::new(
+                dir,
+                args.solid_cache_limit.map(|it| it.as_u64()),
+                args.solid_cache_encrypted,
+            )
+        })
+        .transpose()?;
+    match location {
+        ArchiveLocation::Path(path) if resume.is_some() => run_extract_archive_reader(
+            ResumeArchiveProvider::new(&path, resume.as_ref().unwrap()),
+            args.file.files,
+            &filter,
+            || password.as_deref().map(String::as_str),
+            output_options,
+            args.fail_fast.fail_fast(),
+            ReuseOptions {
+                solid_cache: solid_cache.as_ref(),
+                resume: resume.as_ref(),
+            },
+        )?,
+        #[cfg(not(feature = "memmap"))]
+        ArchiveLocation::Path(path) => run_extract_archive_reader(
+            PathArchiveProvider::new(&path),
+            args.file.files,
+            &filter,
+            || password.as_deref().map(String::as_str),
+            output_options,
+            args.fail_fast.fail_fast(),
+            ReuseOptions {
+                solid_cache: solid_cache.as_ref(),
+                resume: None,
+            },
+        )?,
+        #[cfg(feature = "memmap")]
+        ArchiveLocation::Path(path) => run_extract_archive(
+            path,
+            args.file.files,
+            &filter,
+            || password.as_deref().map(String::as_str),
+            output_options,
+            args.fail_fast.fail_fast(),
+            solid_cache.as_ref(),
+        )?,
+        ArchiveLocation::Stdio => run_extract_archive_reader(
+            StdinArchiveProvider::new(),
+            args.file.files,
+            &filter,
+            || password.as_deref().map(String::as_str),
+            output_options,
+            args.fail_fast.fail_fast(),
+            ReuseOptions {
+                solid_cache: solid_cache.as_ref(),
+                resume: None,
+            },
+        )?,
+        #[cfg(feature = "http")]
+        ArchiveLocation::Url(url) => run_extract_archive_reader(
+            HttpArchiveProvider::new(&url, &args.http),
+            args.file.files,
+            &filter,
+            || password.as_deref().map(String::as_str),
+            output_options,
+            args.fail_fast.fail_fast(),
+            ReuseOptions {
+                solid_cache: solid_cache.as_ref(),
+                resume: None,
+            },
+        )?,
+    }
     log::info!(
         "Successfully extracted an archive in {}",
         DurationDisplay(start.elapsed())
@@ -120,207 +602,1441 @@ fn extract_archive(args: ExtractCommand) -> io::Result<()> {
     Ok(())
 }
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub(crate) struct OutputOption {
-    pub(crate) overwrite: bool,
-    pub(crate) out_dir: Option<PathBuf>,
-    pub(crate) keep_options: KeepOptions,
-    pub(crate) owner_options: OwnerOptions,
+#[derive(Serialize)]
+struct EntryMetadata<'a> {
+    header: &'a EntryHeader,
+    solid: bool,
+    metadata: &'a Metadata,
+    xattrs: &'a [ExtendedAttribute],
+    acl: BTreeMap<String, Vec<String>>,
+    extra_chunks: Vec<String>,
+}
+
+impl<'a> EntryMetadata<'a> {
+    fn new(entry: &'a NormalEntry, solid: bool) -> io::Result<Self> {
+        Ok(Self {
+            header: entry.header(),
+            solid,
+            metadata: entry.metadata(),
+            xattrs: entry.xattrs(),
+            acl: entry
+                .acl()?
+                .into_iter()
+                .map(|(platform, aces)| {
+                    (
+                        platform.to_string(),
+                        aces.into_iter().map(|it| it.to_string()).collect(),
+                    )
+                })
+                .collect(),
+            extra_chunks: entry
+                .extra_chunks()
+                .iter()
+                .map(|it| it.ty().to_string())
+                .collect(),
+        })
+    }
+}
+
+/// Prints the metadata of the entries as JSON lines, without writing any file.
+fn dump_metadata(
+    archive_provider: impl ArchiveProvider,
+    password: PasswordArgs,
+    files: &[String],
+    recursion: bool,
+    filter: &PathFilter,
+) -> io::Result<()> {
+    let password = ask_password(password)?;
+    let globs = GlobPatterns::with_recursion(files, recursion).map_err(usage_error)?;
+    let password = password.as_deref().map(String::as_str);
+    let mut stdout = io::stdout().lock();
+    let mut write = |entry: &NormalEntry, solid: bool| -> io::Result<()> {
+        let path = entry.header().path().as_str();
+        if !filter.is_excluded(path) && (globs.is_empty() || globs.matches_any(path)) {
+            serde_json::to_writer(&mut stdout, &EntryMetadata::new(entry, solid)?)?;
+            stdout.write_all(b"\n")?;
+        }
+        Ok(())
+    };
+    run_read_entries(archive_provider, |entry| {
+        match entry? {
+            ReadEntry::Solid(solid) => {
+                for entry in solid.entries(password)? {
+                    write(&entry?, true)?;
+                }
+            }
+            ReadEntry::Normal(entry) => write(&entry, false)?,
+        }
+        Ok(())
+    })?;
+    globs.ensure_all_matched()
+}
+
+/// Writes the data of the file entries matching `files` to stdout in archive order, without writing any file.
+fn write_to_stdout(
+    archive_provider: impl ArchiveProvider,
+    password: PasswordArgs,
+    identity: &[PathBuf],
+    files: &[String],
+    recursion: bool,
+    filter: &PathFilter,
+) -> io::Result<()> {
+    let password = ask_password(password)?;
+    let identities = read_identities(identity)?;
+    let globs = GlobPatterns::with_recursion(files, recursion).map_err(usage_error)?;
+    let read_options = ReadOptions::builder()
+        .password(password.as_deref())
+        .identities(identities)
+        .build();
+    let mut stdout = io::stdout().lock();
+    run_process_archive(
+        archive_provider,
+        || password.as_deref().map(String::as_str),
+        |entry| {
+            let entry = entry?;
+            let path = entry.header().path();
+            if filter.is_excluded(path.as_str()) || !globs.matches_any(path.as_str()) {
+                return Ok(());
+            }
+            if entry.header().data_kind() != DataKind::File {
+                log::debug!("Skip {path}: not a file");
+                return Ok(());
+            }
+            io::copy(&mut entry.reader(read_options.clone())?, &mut stdout)?;
+            Ok(())
+        },
+    )?;
+    stdout.flush()?;
+    globs.ensure_all_matched()
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct OutputOption {
+    pub(crate) overwrite: bool,
+    pub(crate) merge: MergeMode,
+    pub(crate) skip_existing: SkipExisting,
+    pub(crate) report_file: Option<PathBuf>,
+    pub(crate) out_dir: Option<PathBuf>,
+    pub(crate) out_dir_mode: Option<u32>,
+    pub(crate) keep_dir: bool,
+    pub(crate) implicit_dirs: ImplicitDirs,
+    /// Whether the name patterns also match the entries under the directories they name.
+    pub(crate) recursion: bool,
+    pub(crate) keep_options: KeepOptions,
+    pub(crate) owner_options: OwnerOptions,
+    pub(crate) unknown_owner: UnknownOwner,
+    pub(crate) xattr_options: XattrOptions,
+    pub(crate) windows_compat: WindowsCompatOptions,
+    pub(crate) case_collision: CaseCollision,
+    pub(crate) normalize_paths: bool,
+    pub(crate) transformer: Transformer,
+    pub(crate) strip_components: usize,
+    pub(crate) all_platforms: bool,
+    pub(crate) identities: Vec<Identity>,
+}
+
+impl OutputOption {
+    /// Returns `true` if `item` is tagged for other platforms and is to be skipped.
+    fn is_foreign<T>(&self, item: &NormalEntry<T>) -> io::Result<bool>
+    where
+        pna::RawChunk<T>: Chunk,
+    {
+        if self.all_platforms {
+            return Ok(false);
+        }
+        Ok(match item.platforms()? {
+            Some(platforms) if !platforms.includes_current() => {
+                log::debug!(
+                    "Skip {}: the entry is intended for {platforms}",
+                    item.header().path()
+                );
+                true
+            }
+            _ => false,
+        })
+    }
+
+    /// Replaces the output directory with its canonical path, which has the `\\?\` extended-length prefix,
+    /// so that paths longer than `MAX_PATH` and reserved device names can be created.
+    #[cfg(windows)]
+    fn with_extended_length_out_dir(self) -> io::Result<Self> {
+        let out_dir = self.out_dir.as_deref().unwrap_or(Path::new("."));
+        utils::fs::create_dir_all_with_mode(out_dir, self.out_dir_mode)?;
+        let out_dir = fs::canonicalize(out_dir)?;
+        Ok(Self {
+            out_dir: Some(out_dir),
+            ..self
+        })
+    }
+}
+
+/// How the existing files are compared with the entries by `--merge`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub(crate) enum MergeMode {
+    /// Existing files are not merged
+    #[default]
+    Off,
+    /// Files of the same size and modification time are identical
+    Fast,
+    /// Files of the same size and contents are identical
+    Verify,
+}
+
+/// Which existing files are left as they are by `--skip-existing` and `--keep-newer-files`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub(crate) enum SkipExisting {
+    /// Existing files are not skipped
+    #[default]
+    Off,
+    /// Files of the same size and modification time are skipped
+    Same,
+    /// Files newer than the entries are skipped
+    Newer,
+}
+
+/// Action taken for an entry by the extraction.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExtractAction {
+    /// The file did not exist and was created
+    Created,
+    /// The existing file differed from the entry and was overwritten
+    Updated,
+    /// The existing file was identical to the entry and was left as it is
+    Unchanged,
+    /// The existing file is of another type and was left as it is
+    Conflict,
+    /// The entry was not extracted
+    Skipped,
+}
+
+impl ExtractAction {
+    #[inline]
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Updated => "updated",
+            Self::Unchanged => "unchanged",
+            Self::Conflict => "conflict",
+            Self::Skipped => "skipped",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct MergeRecord {
+    name: String,
+    action: ExtractAction,
+}
+
+/// Actions taken for the entries by `--merge`, reported when the extraction finishes.
+#[derive(Default)]
+struct MergeReport(Vec<MergeRecord>);
+
+impl MergeReport {
+    #[inline]
+    fn record(&mut self, name: String, action: ExtractAction) {
+        self.0.push(MergeRecord { name, action });
+    }
+
+    /// Logs the actions and their counts, and writes them to `report_file` as JSON lines.
+    fn finish(mut self, report_file: Option<&Path>) -> io::Result<()> {
+        self.0.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut counts = BTreeMap::<ExtractAction, usize>::new();
+        for record in &self.0 {
+            *counts.entry(record.action).or_default() += 1;
+            match record.action {
+                ExtractAction::Created | ExtractAction::Updated => {
+                    log::info!("{}: {}", record.action.as_str(), record.name)
+                }
+                ExtractAction::Unchanged | ExtractAction::Conflict | ExtractAction::Skipped => {
+                    log::debug!("{}: {}", record.action.as_str(), record.name)
+                }
+            }
+        }
+        log::info!(
+            "Merged {} entries: {}",
+            self.0.len(),
+            counts
+                .iter()
+                .map(|(action, count)| format!("{count} {}", action.as_str()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        if let Some(path) = report_file {
+            let mut writer = io::BufWriter::new(fs::File::create(path)?);
+            for record in &self.0 {
+                serde_json::to_writer(&mut writer, record)?;
+                writer.write_all(b"\n")?;
+            }
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, ValueEnum)]
+pub(crate) enum WindowsCompat {
+    /// Extract with the reserved names, which Windows permits for extended-length paths
+    #[default]
+    Allow,
+    /// Append the suffix to the base name of the reserved names
+    Rename,
+    /// Skip the entries with reserved names
+    Skip,
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct WindowsCompatOptions {
+    pub(crate) mode: WindowsCompat,
+    pub(crate) suffix: String,
+}
+
+impl Default for WindowsCompatOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            mode: WindowsCompat::default(),
+            suffix: "_".into(),
+        }
+    }
+}
+
+impl WindowsCompatOptions {
+    /// Returns the name to extract the entry `name` as, or `None` if the entry should be skipped.
+    fn apply<'n>(&self, name: &'n str) -> Option<Cow<'n, str>> {
+        if !name.split('/').any(is_windows_reserved_name) {
+            return Some(Cow::Borrowed(name));
+        }
+        match self.mode {
+            WindowsCompat::Allow => Some(Cow::Borrowed(name)),
+            WindowsCompat::Skip => None,
+            WindowsCompat::Rename => {
+                let suffix = &self.suffix;
+                let components = name
+                    .split('/')
+                    .map(|component| {
+                        if !is_windows_reserved_name(component) {
+                            return Cow::Borrowed(component);
+                        }
+                        Cow::Owned(match component.split_once('.') {
+                            Some((stem, ext)) => format!("{stem}{suffix}.{ext}"),
+                            None => format!("{component}{suffix}"),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                Some(Cow::Owned(components.join("/")))
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, ValueEnum)]
+pub(crate) enum UnknownOwner {
+    /// Restore the numeric ids in the archive as they are
+    #[default]
+    Keep,
+    /// Restore the owners as the invoking user and group
+    Map,
+    /// Leave the owners of the extracted files untouched
+    Skip,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, ValueEnum)]
+pub(crate) enum ImplicitDirs {
+    /// Restore the metadata of their directory entries in the archive with the --keep-* options
+    #[default]
+    Metadata,
+    /// Create them with the default permissions
+    Bare,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, ValueEnum)]
+pub(crate) enum CaseCollision {
+    /// Fail to extract the entries whose names collide with the names of preceding entries
+    #[default]
+    Error,
+    /// Append `~N` to the base name of the colliding entries
+    Rename,
+    /// Skip the colliding entries
+    Skip,
+}
+
+/// Names that the entries are extracted as, with `--transform` or `-s`, `--strip-components`,
+/// `--normalize-paths`, `--windows-compat` and `--case-collision` applied in this order.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct DestinationNames {
+    transformer: Transformer,
+    strip_components: usize,
+    normalize_paths: bool,
+    windows_compat: WindowsCompatOptions,
+    case_collision: CaseCollision,
+    case_insensitive: bool,
+    /// Names normalized by `--normalize-paths` to the entry names.
+    normalized: HashMap<String, String>,
+    /// Number of the entry names with backslashes or duplicate slashes.
+    ambiguous: usize,
+    /// Case-folded destination names to the destination names.
+    folded: HashMap<String, String>,
+    /// Destination names of the entries renamed by `--case-collision rename` to their new names.
+    renamed: HashMap<String, String>,
+    skipped: usize,
+}
+
+impl DestinationNames {
+    /// Probes whether the output directory is on a case-insensitive filesystem.
+    /// If the probe fails, the filesystem is assumed to be case-insensitive on macOS and Windows.
+    fn new(args: &OutputOption) -> Self {
+        let out_dir = args.out_dir.as_deref().unwrap_or(Path::new("."));
+        let case_insensitive = utils::fs::is_case_insensitive(out_dir).unwrap_or_else(|e| {
+            log::debug!(
+                "Failed to probe the case sensitivity of {}: {e}",
+                out_dir.display()
+            );
+            cfg!(any(windows, target_os = "macos"))
+        });
+        Self {
+            transformer: args.transformer.clone(),
+            strip_components: args.strip_components,
+            normalize_paths: args.normalize_paths,
+            ..Self::with_options(
+                args.windows_compat.clone(),
+                args.case_collision,
+                case_insensitive,
+            )
+        }
+    }
+
+    fn with_options(
+        windows_compat: WindowsCompatOptions,
+        case_collision: CaseCollision,
+        case_insensitive: bool,
+    ) -> Self {
+        Self {
+            transformer: Transformer::default(),
+            strip_components: 0,
+            normalize_paths: false,
+            windows_compat,
+            case_collision,
+            case_insensitive,
+            normalized: HashMap::new(),
+            ambiguous: 0,
+            folded: HashMap::new(),
+            renamed: HashMap::new(),
+            skipped: 0,
+        }
+    }
+
+    /// Returns the name to extract the entry `name` as, or `None` if the entry should be skipped.
+    /// The entries must be given in the order they are extracted.
+    fn resolve(&mut self, name: &str) -> io::Result<Option<String>> {
+        let Some(name) = self.rewrite(name) else {
+            log::debug!("Skip {name}: no components are left after stripping");
+            return Ok(None);
+        };
+        let name = self.normalize(&name)?;
+        let Some(dest) = self.windows_compat.apply(&name) else {
+            log::warn!("Skip {name}: the name is reserved on Windows");
+            return Ok(None);
+        };
+        if !self.case_insensitive {
+            return Ok(Some(dest.into_owned()));
+        }
+        let dest = self.with_renamed_parents(&dest);
+        let existing = match self.folded.get(&dest.to_lowercase()) {
+            None => {
+                self.folded.insert(dest.to_lowercase(), dest.clone());
+                return Ok(Some(dest));
+            }
+            Some(existing) if *existing == dest => return Ok(Some(dest)),
+            Some(existing) => existing.clone(),
+        };
+        match self.case_collision {
+            CaseCollision::Error => Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{name} collides with {existing} on the case-insensitive filesystem, use --case-collision rename or skip to extract the others"),
+            )),
+            CaseCollision::Skip => {
+                log::warn!("Skip {name}: the name collides with {existing} on the case-insensitive filesystem");
+                self.skipped += 1;
+                Ok(None)
+            }
+            CaseCollision::Rename => {
+                let renamed = (1..)
+                    .map(|n| with_suffix(&dest, &format!("~{n}")))
+                    .find(|it| !self.folded.contains_key(&it.to_lowercase()))
+                    .expect("unused name exists");
+                log::warn!("Extract {name} as {renamed}: the name collides with {existing} on the case-insensitive filesystem");
+                self.folded.insert(renamed.to_lowercase(), renamed.clone());
+                self.renamed.insert(dest, renamed.clone());
+                Ok(Some(renamed))
+            }
+        }
+    }
+
+    /// Returns the name that the entry `name` would be extracted as, without recording it
+    /// for the detection of collisions, or `None` if it would be skipped.
+    fn peek(&self, name: &str) -> Option<String> {
+        let name = self.rewrite(name)?;
+        let name = if self.normalize_paths && utils::has_ambiguous_separator(&name) {
+            Cow::Owned(utils::normalize_separators(&name))
+        } else {
+            name
+        };
+        let dest = self.windows_compat.apply(&name)?;
+        if self.renamed.is_empty() {
+            return Some(dest.into_owned());
+        }
+        Some(self.with_renamed_parents(&dest))
+    }
+
+    /// Applies `--transform` or `-s`, then `--strip-components` to the entry `name`.
+    /// Returns `None` if no components are left.
+    fn rewrite<'n>(&self, name: &'n str) -> Option<Cow<'n, str>> {
+        match self.transformer.apply(name, NameKind::Regular) {
+            Cow::Borrowed(name) => strip_components(name, self.strip_components).map(Cow::Borrowed),
+            Cow::Owned(name) => {
+                strip_components(&name, self.strip_components).map(|it| Cow::Owned(it.into()))
+            }
+        }
+    }
+
+    /// Applies `--normalize-paths` to `name`, and counts the names with ambiguous separators.
+    /// Returns an error if `name` becomes the same as another entry name by the normalization.
+    fn normalize<'n>(&mut self, name: &'n str) -> io::Result<Cow<'n, str>> {
+        let ambiguous = utils::has_ambiguous_separator(name);
+        if ambiguous {
+            self.ambiguous += 1;
+        }
+        if !self.normalize_paths {
+            return Ok(Cow::Borrowed(name));
+        }
+        let normalized = if ambiguous {
+            Cow::Owned(utils::normalize_separators(name))
+        } else {
+            Cow::Borrowed(name)
+        };
+        if normalized.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{name} becomes empty after normalizing the path separators"),
+            ));
+        }
+        match self.normalized.get(normalized.as_ref()) {
+            None => {
+                self.normalized.insert(normalized.to_string(), name.into());
+            }
+            Some(original) if original == name => (),
+            Some(original) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "{name} collides with {original} after normalizing the path separators"
+                    ),
+                ))
+            }
+        }
+        Ok(normalized)
+    }
+
+    /// Returns the name that `original`, the target of the hard link entry `name`, is extracted as,
+    /// or `None` if it is not extracted.
+    fn target<'n>(&self, name: &str, original: &'n str) -> Option<Cow<'n, str>> {
+        let original = self.transformer.apply(original, NameKind::HardlinkTarget);
+        match self.strip_target(name, original)? {
+            Cow::Borrowed(original) => self.rewritten_target(original),
+            Cow::Owned(original) => self
+                .rewritten_target(&original)
+                .map(|it| Cow::Owned(it.into_owned())),
+        }
+    }
+
+    /// Returns the target of the symbolic link entry `name` to `original`,
+    /// with `--transform` or `-s`, then `--strip-components` applied.
+    fn symlink_target<'n>(&self, name: &str, original: &'n str) -> Cow<'n, str> {
+        let original = self.transformer.apply(original, NameKind::SymlinkTarget);
+        // NOTE: A link to a stripped directory is left dangling, as the directory is not extracted.
+        self.strip_target(name, original.clone())
+            .unwrap_or(original)
+    }
+
+    /// Applies `--strip-components` to `original`, the target of the link entry `name` relative to the link,
+    /// so that it points from the stripped link to the same entry.
+    /// Returns `None` if no components of the target are left.
+    ///
+    /// Absolute targets and the targets outside the archive are not changed.
+    fn strip_target<'n>(&self, name: &str, original: Cow<'n, str>) -> Option<Cow<'n, str>> {
+        if self.strip_components == 0 {
+            return Some(original);
+        }
+        let name = self.transformer.apply(name, NameKind::Regular);
+        let Some(target) = utils::resolve_link(&name, &original) else {
+            return Some(original);
+        };
+        let name = strip_components(&name, self.strip_components)?;
+        let target = strip_components(&target, self.strip_components)?;
+        Some(Cow::Owned(utils::relative_link(name, target)))
+    }
+
+    fn rewritten_target<'n>(&self, name: &'n str) -> Option<Cow<'n, str>> {
+        if self.normalize_paths && utils::has_ambiguous_separator(name) {
+            let normalized = utils::normalize_separators(name);
+            return self
+                .rewritten_target(&normalized)
+                .map(|it| Cow::Owned(it.into_owned()));
+        }
+        let dest = self.windows_compat.apply(name)?;
+        if self.renamed.is_empty() {
+            return Some(dest);
+        }
+        Some(Cow::Owned(self.with_renamed_parents(&dest)))
+    }
+
+    /// Replaces the longest ancestor of `name`, including itself, that is renamed.
+    fn with_renamed_parents(&self, name: &str) -> String {
+        let ancestors = name
+            .match_indices('/')
+            .map(|(i, _)| i)
+            .chain([name.len()])
+            .rev();
+        for end in ancestors {
+            if let Some(renamed) = self.renamed.get(&name[..end]) {
+                return format!("{renamed}{}", &name[end..]);
+            }
+        }
+        name.into()
+    }
+
+    fn log_summary(&self) {
+        if self.ambiguous != 0 {
+            if self.normalize_paths {
+                log::info!(
+                    "Normalized the path separators of {} entries",
+                    self.ambiguous
+                );
+            } else {
+                log::warn!(
+                    "{} entry names contain backslashes or duplicate slashes, use --normalize-paths to extract them with slashes as the separators",
+                    self.ambiguous
+                );
+            }
+        }
+        if !self.renamed.is_empty() {
+            log::info!(
+                "Renamed {} entries whose names collide on the case-insensitive filesystem",
+                self.renamed.len()
+            );
+        }
+        if self.skipped != 0 {
+            log::info!(
+                "Skipped {} entries whose names collide on the case-insensitive filesystem",
+                self.skipped
+            );
+        }
+    }
+}
+
+/// Appends `suffix` to the base name of the last component of `name`, before its extension.
+fn with_suffix(name: &str, suffix: &str) -> String {
+    let (parent, file_name) = match name.rsplit_once('/') {
+        Some((parent, file_name)) => (Some(parent), file_name),
+        None => (None, name),
+    };
+    let file_name = match file_name.split_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem}{suffix}.{ext}"),
+        _ => format!("{file_name}{suffix}"),
+    };
+    match parent {
+        Some(parent) => format!("{parent}/{file_name}"),
+        None => file_name,
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum)]
+pub(crate) enum XattrNamespace {
+    User,
+    Trusted,
+    System,
+    Security,
+}
+
+#[cfg(unix)]
+impl XattrNamespace {
+    #[inline]
+    const fn prefix(&self) -> &'static str {
+        match self {
+            Self::User => "user.",
+            Self::Trusted => "trusted.",
+            Self::System => "system.",
+            Self::Security => "security.",
+        }
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub(crate) struct XattrOptions {
+    pub(crate) max_size: Option<usize>,
+    pub(crate) namespaces: Vec<XattrNamespace>,
+}
+
+#[cfg(unix)]
+impl XattrOptions {
+    #[inline]
+    fn is_target(&self, xattr: &pna::ExtendedAttribute) -> bool {
+        self.namespaces.is_empty()
+            || self
+                .namespaces
+                .iter()
+                .any(|it| xattr.name().starts_with(it.prefix()))
+    }
+}
+
+/// Work of earlier extractions reused by [run_extract_archive_reader].
+#[derive(Clone, Copy, Default)]
+pub(crate) struct ReuseOptions<'a> {
+    /// Cache of the decoded solid entries given by `--solid-cache`.
+    pub(crate) solid_cache: Option<&'a SolidCache>,
+    /// State of `--resume`, used to skip the entries already extracted.
+    pub(crate) resume: Option<&'a ResumeState>,
 }
 
 pub(crate) fn run_extract_archive_reader<'p, Provider>(
     reader: impl ArchiveProvider,
     files: Vec<String>,
+    filter: &PathFilter,
     mut password_provider: Provider,
     args: OutputOption,
+    fail_fast: bool,
+    ReuseOptions {
+        solid_cache,
+        resume,
+    }: ReuseOptions,
 ) -> io::Result<()>
 where
     Provider: FnMut() -> Option<&'p str>,
 {
     let password = password_provider();
-    let globs =
-        GlobPatterns::new(files).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs = GlobPatterns::with_recursion(files, args.recursion).map_err(usage_error)?;
+    #[cfg(windows)]
+    let args = args.with_extended_length_out_dir()?;
 
+    let mut names = DestinationNames::new(&args);
+    let mut foreign = 0;
+    // NOTE: The total size of the contents is unknown until all the entries are read.
+    let progress = Progress::new(None);
     let mut hard_link_entries = Vec::new();
+    let mut dir_entries = Vec::new();
+    let mut implicit_dirs = ImplicitDirectories::new(&args);
 
     let (tx, rx) = std::sync::mpsc::channel();
-    run_process_archive(reader, password_provider, |entry| {
+    run_process_archive_with_cache(reader, password_provider, solid_cache, |entry| {
         let item = entry?;
         let item_path = item.header().path().to_string();
+        // NOTE: Exclusions take precedence over the file arguments, so excluded entries do not count as their matches.
+        if filter.is_excluded(&item_path) {
+            log::debug!("Skip excluded: {}", item.header().path());
+            return Ok(());
+        }
         if !globs.is_empty() && !globs.matches_any(&item_path) {
             log::debug!("Skip: {}", item.header().path());
+            implicit_dirs.skip(item, &args)?;
+            return Ok(());
+        }
+        if args.is_foreign(&item)? {
+            foreign += 1;
+            return Ok(());
+        }
+        if !args.keep_dir && item.header().data_kind() == DataKind::Directory {
+            log::debug!("Skip directory: {}", item.header().path());
             return Ok(());
         }
+        let name = match names.resolve(&item_path) {
+            Ok(Some(name)) => name,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                tx.send(Err(e))
+                    .unwrap_or_else(|e| panic!("{e}: {}", item_path));
+                return Ok(());
+            }
+        };
+        implicit_dirs.select(&name);
+        if resume.is_some_and(|it| it.is_completed(&item_path)) {
+            log::debug!("Skip completed: {}", item.header().path());
+            return Ok(());
+        }
+        // NOTE: Directories are not recorded, since their metadata is restored after all entries are extracted.
+        let resume = resume
+            .filter(|_| item.header().data_kind() != DataKind::Directory)
+            .map(|it| (it, it.start()));
         if item.header().data_kind() == DataKind::HardLink {
-            hard_link_entries.push(item);
+            hard_link_entries.push((item, name, resume));
             return Ok(());
         }
+        if item.header().data_kind() == DataKind::Directory {
+            dir_entries.push((item.clone(), name.clone()));
+        }
         let tx = tx.clone();
-        rayon::scope_fifo(|s| {
-            s.spawn_fifo(|_| {
-                tx.send(extract_entry(item, password, &args))
-                    .unwrap_or_else(|e| panic!("{e}: {}", item_path));
-            })
+        let names = &names;
+        run_in_pool(|| {
+            tx.send(
+                extract_entry(item, &name, password, &args, names, &progress).and_then(|action| {
+                    record_completed(resume, &item_path)?;
+                    Ok((name.clone(), action))
+                }),
+            )
+            .unwrap_or_else(|e| panic!("{e}: {}", item_path));
         });
         Ok(())
     })?;
     drop(tx);
+    let mut policy = FailurePolicy::new(fail_fast);
+    let mut report = MergeReport::default();
     for result in rx {
-        result?;
+        if let Some((name, action)) = policy.check(result)? {
+            report.record(name, action);
+        }
     }
 
-    for item in hard_link_entries {
-        extract_entry(item, password, &args)?;
+    for (item, name, resume) in hard_link_entries {
+        let item_path = item.header().path().to_string();
+        let result = extract_entry(item, &name, password, &args, &names, &progress)
+            .and_then(|action| record_completed(resume, &item_path).map(|_| action));
+        if let Some(action) = policy.check(result)? {
+            report.record(name, action);
+        }
     }
-    Ok(())
+    dir_entries.extend(implicit_dirs.into_entries(&names));
+    restore_directories_metadata(dir_entries, &args, &mut policy)?;
+    names.log_summary();
+    log_foreign_summary(foreign);
+    if args.merge != MergeMode::Off {
+        report.finish(args.report_file.as_deref())?;
+    }
+    policy.finish()?;
+    globs.ensure_all_matched()
 }
 
 #[cfg(feature = "memmap")]
 pub(crate) fn run_extract_archive<'p, Provider>(
     path: impl AsRef<Path>,
     files: Vec<String>,
+    filter: &PathFilter,
     mut password_provider: Provider,
     args: OutputOption,
+    fail_fast: bool,
+    solid_cache: Option<&SolidCache>,
 ) -> io::Result<()>
 where
     Provider: FnMut() -> Option<&'p str>,
 {
     let password = password_provider();
-    let globs =
-        GlobPatterns::new(files).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs = GlobPatterns::with_recursion(files, args.recursion).map_err(usage_error)?;
+    #[cfg(windows)]
+    let args = args.with_extended_length_out_dir()?;
 
-    let mut hard_link_entries = Vec::<NormalEntry>::new();
+    let mut names = DestinationNames::new(&args);
+    let mut foreign = 0;
+    // NOTE: The total size of the contents is unknown until all the entries are read.
+    let progress = Progress::new(None);
+    let mut hard_link_entries = Vec::<(NormalEntry, String)>::new();
+    let mut dir_entries = Vec::<(NormalEntry, String)>::new();
+    let mut implicit_dirs = ImplicitDirectories::new(&args);
 
     let (tx, rx) = std::sync::mpsc::channel();
-    run_entries(path, password_provider, |entry| {
+    run_entries_with_cache(path, password_provider, solid_cache, |entry| {
         let item = entry?;
         let item_path = item.header().path().to_string();
+        // NOTE: Exclusions take precedence over the file arguments, so excluded entries do not count as their matches.
+        if filter.is_excluded(&item_path) {
+            log::debug!("Skip excluded: {}", item.header().path());
+            return Ok(());
+        }
         if !globs.is_empty() && !globs.matches_any(&item_path) {
             log::debug!("Skip: {}", item.header().path());
+            implicit_dirs.skip(item.into(), &args)?;
+            return Ok(());
+        }
+        if args.is_foreign(&item)? {
+            foreign += 1;
+            return Ok(());
+        }
+        if !args.keep_dir && item.header().data_kind() == DataKind::Directory {
+            log::debug!("Skip directory: {}", item.header().path());
             return Ok(());
         }
+        let name = match names.resolve(&item_path) {
+            Ok(Some(name)) => name,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                tx.send(Err(e))
+                    .unwrap_or_else(|e| panic!("{e}: {}", item_path));
+                return Ok(());
+            }
+        };
+        implicit_dirs.select(&name);
         if item.header().data_kind() == DataKind::HardLink {
-            hard_link_entries.push(item.into());
+            hard_link_entries.push((item.into(), name));
             return Ok(());
         }
+        if item.header().data_kind() == DataKind::Directory {
+            dir_entries.push((item.clone().into(), name.clone()));
+        }
         let tx = tx.clone();
-        rayon::scope_fifo(|s| {
-            s.spawn_fifo(|_| {
-                tx.send(extract_entry(item, password, &args))
-                    .unwrap_or_else(|e| panic!("{e}: {}", item_path));
-            })
+        let names = &names;
+        run_in_pool(|| {
+            tx.send(
+                extract_entry(item, &name, password, &args, names, &progress)
+                    .map(|action| (name.clone(), action)),
+            )
+            .unwrap_or_else(|e| panic!("{e}: {}", item_path));
         });
         Ok(())
     })?;
     drop(tx);
+    let mut policy = FailurePolicy::new(fail_fast);
+    let mut report = MergeReport::default();
     for result in rx {
-        result?;
+        if let Some((name, action)) = policy.check(result)? {
+            report.record(name, action);
+        }
     }
 
-    for item in hard_link_entries {
-        extract_entry(item, password, &args)?;
+    for (item, name) in hard_link_entries {
+        if let Some(action) = policy.check(extract_entry(
+            item, &name, password, &args, &names, &progress,
+        ))? {
+            report.record(name, action);
+        }
+    }
+    dir_entries.extend(implicit_dirs.into_entries(&names));
+    restore_directories_metadata(dir_entries, &args, &mut policy)?;
+    names.log_summary();
+    log_foreign_summary(foreign);
+    if args.merge != MergeMode::Off {
+        report.finish(args.report_file.as_deref())?;
+    }
+    policy.finish()?;
+    globs.ensure_all_matched()
+}
+
+/// Records the extracted entry `name` to the state of `--resume`, given with its index returned by [ResumeState::start].
+fn record_completed(resume: Option<(&ResumeState, usize)>, name: &str) -> io::Result<()> {
+    match resume {
+        Some((state, index)) => state.complete(index, name),
+        None => Ok(()),
+    }
+}
+
+fn log_foreign_summary(foreign: usize) {
+    if foreign != 0 {
+        log::info!(
+            "Skipped {foreign} entries intended for other platforms, use --all-platforms to extract them"
+        );
     }
-    Ok(())
 }
 
+/// Extracts the entry `item` as `item_name` given by [DestinationNames::resolve],
+/// adding the bytes of the extracted contents to `progress`.
 pub(crate) fn extract_entry<T>(
     item: NormalEntry<T>,
+    item_name: &str,
     password: Option<&str>,
-    OutputOption {
-        overwrite,
-        out_dir,
-        keep_options,
-        owner_options,
-    }: &OutputOption,
-) -> io::Result<()>
+    args: &OutputOption,
+    names: &DestinationNames,
+    progress: &Progress,
+) -> io::Result<ExtractAction>
 where
     T: AsRef<[u8]>,
     pna::RawChunk<T>: Chunk,
 {
-    let overwrite = *overwrite;
-    let item_path = item.header().path().as_path();
-    log::debug!("Extract: {}", item_path.display());
-    let path = if let Some(out_dir) = &out_dir {
-        Cow::from(out_dir.join(item_path))
+    let OutputOption {
+        overwrite,
+        merge,
+        skip_existing,
+        out_dir,
+        out_dir_mode,
+        keep_options,
+        identities,
+        ..
+    } = args;
+    let overwrite = *overwrite || *skip_existing != SkipExisting::Off;
+    // NOTE: Errors while reading the entry data are caused by a wrong password or identity when
+    //       the entry is encrypted, otherwise by a corrupted archive.
+    let data_error = if item.header().encryption() == Encryption::No {
+        ExitStatus::CorruptArchive
     } else {
-        Cow::from(item_path)
+        ExitStatus::Crypto
     };
-    if path.exists() && !overwrite {
-        return Err(io::Error::new(
-            io::ErrorKind::AlreadyExists,
-            format!("{} is already exists", path.display()),
-        ));
+    let read_options = ReadOptions::builder()
+        .password(password)
+        .identities(identities.iter().cloned())
+        .build();
+    let item_path = Path::new(item_name);
+    log::debug!("Extract: {}", item_path.display());
+    let path = output_path(item_path, out_dir.as_deref());
+    if *skip_existing != SkipExisting::Off
+        && is_skipped_existing(
+            &item,
+            &path,
+            &read_options,
+            data_error,
+            *skip_existing,
+            names,
+        )?
+    {
+        log::debug!(
+            "Skip {}: {} already exists",
+            item_path.display(),
+            path.display()
+        );
+        return Ok(ExtractAction::Skipped);
     }
+    let action = if *merge == MergeMode::Off {
+        match (path.exists(), overwrite) {
+            (false, _) => ExtractAction::Created,
+            (true, true) => ExtractAction::Updated,
+            (true, false) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} is already exists", path.display()),
+                ))
+            }
+        }
+    } else {
+        match merge_action(&item, &path, &read_options, data_error, *merge, names)? {
+            ExtractAction::Unchanged => {
+                if item.header().data_kind() != DataKind::Directory {
+                    restore_metadata(&item, &path, item_path, args)?;
+                }
+                return Ok(ExtractAction::Unchanged);
+            }
+            ExtractAction::Conflict => {
+                log::warn!(
+                    "Skip {}: {} exists and is not a {}",
+                    item_path.display(),
+                    path.display(),
+                    DataKindDisplay(item.header().data_kind())
+                );
+                return Ok(ExtractAction::Conflict);
+            }
+            action => action,
+        }
+    };
+    let replace = action == ExtractAction::Updated;
     log::debug!("start: {}", path.display());
     if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+        utils::fs::create_dir_all_with_mode(parent, *out_dir_mode)?;
     }
-    let permissions = if keep_options.keep_permission {
-        item.metadata()
-            .permission()
-            .and_then(|p| permissions(p, owner_options))
-    } else {
-        None
-    };
     match item.header().data_kind() {
         DataKind::File => {
             let mut file = fs::File::create(&path)?;
+            let reader = item
+                .reader(&read_options)
+                .map_err(|e| with_status(data_error, e))?;
+            progress.start(item_name);
+            io::copy(
+                &mut StatusReader::new(progress.wrap_read(reader), data_error),
+                &mut file,
+            )?;
+            // NOTE: Timestamps are restored after writing, which updates the modification time.
             if keep_options.keep_timestamp {
-                let mut times = fs::FileTimes::new();
-                if let Some(accessed) = item.metadata().accessed_time() {
-                    times = times.set_accessed(accessed);
-                }
-                if let Some(modified) = item.metadata().modified_time() {
-                    times = times.set_modified(modified);
-                }
-                #[cfg(any(windows, target_os = "macos"))]
-                if let Some(created) = item.metadata().created_time() {
-                    times = times.set_created(created);
-                }
-                file.set_times(times)?;
+                file.set_times(file_times(item.metadata()))?;
             }
-            let mut reader = item.reader(ReadOptions::with_password(password))?;
-            io::copy(&mut reader, &mut file)?;
         }
         DataKind::Directory => {
             fs::create_dir_all(&path)?;
         }
         DataKind::SymbolicLink => {
-            let reader = item.reader(ReadOptions::with_password(password))?;
-            let original = EntryReference::from_lossy(io::read_to_string(reader)?);
-            if overwrite && path.exists() {
+            let original = read_reference(&item, &read_options, data_error)?;
+            let original = names.symlink_target(item.header().path().as_str(), original.as_str());
+            if replace {
                 utils::fs::remove(&path)?;
             }
-            utils::fs::symlink(original, &path)?;
+            utils::fs::symlink(&*original, &path)?;
         }
         DataKind::HardLink => {
-            let reader = item.reader(ReadOptions::with_password(password))?;
-            let original = EntryReference::from_lossy(io::read_to_string(reader)?);
-            let mut original = Cow::from(original.as_path());
-            if let Some(parent) = path.parent() {
-                original = Cow::from(parent.join(original));
-            }
-            if overwrite && path.exists() {
+            let original = read_reference(&item, &read_options, data_error)?;
+            let Some(original) =
+                hard_link_target(item.header().path().as_str(), &original, &path, names)
+            else {
+                log::warn!(
+                    "Skip {}: the link target {} is not extracted",
+                    item_path.display(),
+                    original
+                );
+                return Ok(ExtractAction::Skipped);
+            };
+            if replace {
                 utils::fs::remove(&path)?;
             }
             fs::hard_link(original, &path)?;
         }
     }
+    // NOTE: Metadata of directories is restored after their contents are extracted,
+    //       so that restrictive permissions or default ACLs do not affect the contents.
+    if item.header().data_kind() != DataKind::Directory {
+        restore_metadata(&item, &path, item_path, args)?;
+    }
+    log::debug!("end: {}", path.display());
+    Ok(action)
+}
+
+/// Reads the link target stored in the symbolic link or hard link entry `item`.
+fn read_reference<T>(
+    item: &NormalEntry<T>,
+    read_options: &ReadOptions,
+    data_error: ExitStatus,
+) -> io::Result<EntryReference>
+where
+    T: AsRef<[u8]>,
+{
+    let reader = item
+        .reader(read_options)
+        .map_err(|e| with_status(data_error, e))?;
+    Ok(EntryReference::from_lossy(io::read_to_string(
+        StatusReader::new(reader, data_error),
+    )?))
+}
+
+/// Returns the path of the file that the hard link entry `name` extracted at `path` links to,
+/// or `None` if the target is not extracted, as it is stripped off or reserved on Windows.
+fn hard_link_target(
+    name: &str,
+    original: &EntryReference,
+    path: &Path,
+    names: &DestinationNames,
+) -> Option<PathBuf> {
+    let original = names.target(name, original.as_str())?;
+    Some(match path.parent() {
+        Some(parent) => parent.join(original.as_ref()),
+        None => PathBuf::from(original.as_ref()),
+    })
+}
+
+/// Decides the action of `--merge` for the entry `item` to be extracted to `path`,
+/// comparing it with the existing file.
+fn merge_action<T>(
+    item: &NormalEntry<T>,
+    path: &Path,
+    read_options: &ReadOptions,
+    data_error: ExitStatus,
+    mode: MergeMode,
+    names: &DestinationNames,
+) -> io::Result<ExtractAction>
+where
+    T: AsRef<[u8]>,
+    pna::RawChunk<T>: Chunk,
+{
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(ExtractAction::Created),
+        Err(e) => return Err(e),
+    };
+    let file_type = meta.file_type();
+    let data_kind = item.header().data_kind();
+    let same_type = match data_kind {
+        DataKind::File | DataKind::HardLink => file_type.is_file(),
+        DataKind::Directory => file_type.is_dir(),
+        DataKind::SymbolicLink => file_type.is_symlink(),
+    };
+    if !same_type {
+        return Ok(ExtractAction::Conflict);
+    }
+    let identical = match data_kind {
+        DataKind::Directory => true,
+        DataKind::File => {
+            let same_size = item
+                .metadata()
+                .raw_file_size()
+                .map(|size| size == u128::from(meta.len()));
+            match mode {
+                MergeMode::Verify => {
+                    same_size != Some(false)
+                        && pna::compare_readers(
+                            StatusReader::new(
+                                item.reader(read_options)
+                                    .map_err(|e| with_status(data_error, e))?,
+                                data_error,
+                            ),
+                            fs::File::open(path)?,
+                        )?
+                }
+                MergeMode::Fast | MergeMode::Off => {
+                    let fs_modified = meta.modified()?.duration_since(UNIX_EPOCH).ok();
+                    same_size == Some(true)
+                        && item.metadata().modified().map(|it| it.as_secs())
+                            == fs_modified.map(|it| it.as_secs())
+                }
+            }
+        }
+        DataKind::SymbolicLink => {
+            let original = read_reference(item, read_options, data_error)?;
+            let original = names.symlink_target(item.header().path().as_str(), original.as_str());
+            fs::read_link(path)? == Path::new(&*original)
+        }
+        DataKind::HardLink => {
+            let original = read_reference(item, read_options, data_error)?;
+            match hard_link_target(item.header().path().as_str(), &original, path, names) {
+                Some(target) => FileId::of(&target).ok() == Some(FileId::of(path)?),
+                None => false,
+            }
+        }
+    };
+    Ok(if identical {
+        ExtractAction::Unchanged
+    } else {
+        ExtractAction::Updated
+    })
+}
+
+/// Returns `true` if the existing file at `path` is left as it is by `--skip-existing` or `--keep-newer-files`
+/// instead of extracting the entry `item`.
+///
+/// Directories are never skipped, so that their metadata is restored.
+fn is_skipped_existing<T>(
+    item: &NormalEntry<T>,
+    path: &Path,
+    read_options: &ReadOptions,
+    data_error: ExitStatus,
+    mode: SkipExisting,
+    names: &DestinationNames,
+) -> io::Result<bool>
+where
+    T: AsRef<[u8]>,
+    pna::RawChunk<T>: Chunk,
+{
+    /// Difference of the modification times regarded as the same, for the file systems of coarse timestamps.
+    const MODIFIED_TOLERANCE: Duration = Duration::from_secs(1);
+
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    Ok(match item.header().data_kind() {
+        DataKind::Directory => false,
+        DataKind::File => {
+            let (Some(modified), Ok(fs_modified)) = (
+                item.metadata().modified(),
+                meta.modified()?.duration_since(UNIX_EPOCH),
+            ) else {
+                return Ok(false);
+            };
+            meta.is_file()
+                && match mode {
+                    SkipExisting::Off => false,
+                    SkipExisting::Same => {
+                        item.metadata().raw_file_size() == Some(u128::from(meta.len()))
+                            && fs_modified.abs_diff(modified) <= MODIFIED_TOLERANCE
+                    }
+                    SkipExisting::Newer => fs_modified > modified + MODIFIED_TOLERANCE,
+                }
+        }
+        DataKind::SymbolicLink => {
+            let original = read_reference(item, read_options, data_error)?;
+            let original = names.symlink_target(item.header().path().as_str(), original.as_str());
+            meta.is_symlink() && fs::read_link(path)? == Path::new(&*original)
+        }
+        DataKind::HardLink => {
+            let original = read_reference(item, read_options, data_error)?;
+            match hard_link_target(item.header().path().as_str(), &original, path, names) {
+                Some(target) => {
+                    meta.is_file() && FileId::of(&target).ok() == Some(FileId::of(path)?)
+                }
+                None => false,
+            }
+        }
+    })
+}
+
+/// Removes `n` leading components from `name`, or returns `None` if no components are left.
+fn strip_components(name: &str, n: usize) -> Option<&str> {
+    let mut name = name;
+    for _ in 0..n {
+        name = name.split_once('/')?.1;
+    }
+    (!name.is_empty()).then_some(name)
+}
+
+/// Returns the path to extract the entry at `item_path` to.
+fn output_path<'p>(item_path: &'p Path, out_dir: Option<&Path>) -> Cow<'p, Path> {
+    if let Some(out_dir) = out_dir {
+        Cow::from(out_dir.join(item_path))
+    } else {
+        Cow::from(item_path)
+    }
+}
+
+fn file_times(metadata: &pna::Metadata) -> fs::FileTimes {
+    let mut times = fs::FileTimes::new();
+    if let Some(accessed) = metadata.accessed_time() {
+        times = times.set_accessed(accessed);
+    }
+    if let Some(modified) = metadata.modified_time() {
+        times = times.set_modified(modified);
+    }
+    #[cfg(any(windows, target_os = "macos"))]
+    if let Some(created) = metadata.created_time() {
+        times = times.set_created(created);
+    }
+    times
+}
+
+/// Directory entries not selected by the file filters, whose metadata is restored to the directories
+/// created implicitly as the parents of the extracted entries with `--implicit-dirs metadata`.
+struct ImplicitDirectories {
+    enabled: bool,
+    /// Unselected directory entries by their entry names, the last one of the same name wins.
+    entries: HashMap<String, NormalEntry>,
+    /// Names that the selected entries are extracted as.
+    selected: HashSet<String>,
+}
+
+impl ImplicitDirectories {
+    fn new(args: &OutputOption) -> Self {
+        let keep_options = &args.keep_options;
+        Self {
+            enabled: args.keep_dir
+                && args.implicit_dirs == ImplicitDirs::Metadata
+                && (keep_options.keep_timestamp
+                    || keep_options.keep_owner
+                    || keep_options.keep_mode
+                    || keep_options.keep_xattr
+                    || keep_options.keep_acl),
+            entries: HashMap::new(),
+            selected: HashSet::new(),
+        }
+    }
+
+    /// Keeps the unselected entry `item` if it is a directory entry.
+    fn skip(&mut self, item: NormalEntry, args: &OutputOption) -> io::Result<()> {
+        if self.enabled
+            && item.header().data_kind() == DataKind::Directory
+            && !args.is_foreign(&item)?
+        {
+            self.entries.insert(item.header().path().to_string(), item);
+        }
+        Ok(())
+    }
+
+    /// Records the name that a selected entry is extracted as.
+    fn select(&mut self, name: &str) {
+        if self.enabled {
+            self.selected.insert(name.into());
+        }
+    }
+
+    /// Returns the kept directory entries with the names they are extracted as,
+    /// which are the parents of the selected entries and are not selected themselves.
+    fn into_entries(self, names: &DestinationNames) -> Vec<(NormalEntry, String)> {
+        let parents = self
+            .selected
+            .iter()
+            .flat_map(|name| name.match_indices('/').map(|(i, _)| &name[..i]))
+            .collect::<HashSet<_>>();
+        self.entries
+            .into_values()
+            .filter_map(|item| {
+                let name = names.peek(item.header().path().as_str())?;
+                (parents.contains(name.as_str()) && !self.selected.contains(&name))
+                    .then_some((item, name))
+            })
+            .collect()
+    }
+}
+
+/// Restores the metadata of the directory entries, deeper directories first.
+fn restore_directories_metadata(
+    mut dir_entries: Vec<(NormalEntry, String)>,
+    args: &OutputOption,
+    policy: &mut FailurePolicy,
+) -> io::Result<()> {
+    dir_entries.sort_by_key(|(_, name)| Reverse(name.split('/').count()));
+    for (item, name) in &dir_entries {
+        policy.check(restore_directory_metadata(item, name, args))?;
+    }
+    Ok(())
+}
+
+/// Restores the timestamps, permissions, extended attributes and ACL of the directory entry `item`
+/// extracted as `item_name`, which must be called after the contents of the directory are extracted.
+fn restore_directory_metadata<T>(
+    item: &NormalEntry<T>,
+    item_name: &str,
+    args: &OutputOption,
+) -> io::Result<()>
+where
+    T: AsRef<[u8]>,
+    pna::RawChunk<T>: Chunk,
+{
+    let item_path = Path::new(item_name);
+    let path = output_path(item_path, args.out_dir.as_deref());
+    log::debug!("Restore metadata of directory: {}", path.display());
+    // NOTE: Timestamps are restored first, because restrictive permissions may prevent opening the directory.
+    if args.keep_options.keep_timestamp {
+        #[cfg(unix)]
+        fs::File::open(&path)?.set_times(file_times(item.metadata()))?;
+        #[cfg(windows)]
+        {
+            use std::os::windows::fs::OpenOptionsExt;
+            const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+            const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+            fs::OpenOptions::new()
+                .access_mode(FILE_WRITE_ATTRIBUTES)
+                .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+                .open(&path)?
+                .set_times(file_times(item.metadata()))?;
+        }
+    }
+    restore_metadata(item, &path, item_path, args)
+}
+
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn restore_metadata<T>(
+    item: &NormalEntry<T>,
+    path: &Path,
+    item_path: &Path,
+    OutputOption {
+        keep_options,
+        owner_options,
+        unknown_owner,
+        xattr_options,
+        ..
+    }: &OutputOption,
+) -> io::Result<()>
+where
+    T: AsRef<[u8]>,
+    pna::RawChunk<T>: Chunk,
+{
+    // NOTE: The owners are not resolved unless they are restored.
+    let permissions = match item.metadata().permission() {
+        Some(p) if keep_options.keep_owner => permissions(p, owner_options, *unknown_owner)?,
+        Some(p) if keep_options.keep_mode => Some((p, None, None)),
+        _ => None,
+    };
     #[cfg(unix)]
     if let Some((p, u, g)) = permissions {
         use std::os::unix::fs::PermissionsExt;
-        match chown(&path, u, g) {
-            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
-                log::warn!("failed to restore owner of {}: {}", path.display(), e)
+        if keep_options.keep_owner {
+            match std::os::unix::fs::chown(path, u, g) {
+                Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                    log::warn!("failed to restore owner of {}: {}", path.display(), e)
+                }
+                r => r?,
             }
-            r => r?,
         }
-        fs::set_permissions(&path, fs::Permissions::from_mode(p.permissions().into()))?;
+        if keep_options.keep_mode {
+            fs::set_permissions(path, fs::Permissions::from_mode(p.permissions().into()))?;
+        }
     };
     #[cfg(windows)]
     if let Some((p, u, g)) = permissions {
-        chown(&path, u, g)?;
-        utils::os::windows::fs::chmod(&path, p.permissions())?;
+        if keep_options.keep_owner {
+            chown(path, u, g)?;
+        }
+        if keep_options.keep_mode {
+            utils::os::windows::fs::chmod(path, p.permissions())?;
+        }
     }
     #[cfg(not(any(unix, windows)))]
     if let Some(_) = permissions {
@@ -328,116 +2044,458 @@ where
     }
     #[cfg(unix)]
     if keep_options.keep_xattr {
-        utils::os::unix::fs::xattrs::set_xattrs(&path, item.xattrs())?;
+        let mut oversized = Vec::new();
+        for xattr in item.xattrs() {
+            if !xattr_options.is_target(xattr) {
+                log::debug!(
+                    "Skip extended attribute {} of {}",
+                    xattr.name(),
+                    item_path.display()
+                );
+                continue;
+            }
+            if xattr_options
+                .max_size
+                .is_some_and(|max_size| max_size < xattr.value().len())
+            {
+                oversized.push(xattr.name());
+                continue;
+            }
+            match utils::os::unix::fs::xattrs::set_xattr(path, xattr) {
+                Err(e) if is_recoverable_xattr_error(&e) => log::warn!(
+                    "Failed to restore extended attribute {} of {}: {}",
+                    xattr.name(),
+                    item_path.display(),
+                    e
+                ),
+                r => r?,
+            }
+        }
+        // NOTE: Oversized attributes are reported once per entry, not once per attribute.
+        if let Some(max_size) = xattr_options.max_size.filter(|_| !oversized.is_empty()) {
+            log::warn!(
+                "Skip extended attributes {} of {}: size exceeds the limit {}",
+                oversized.join(", "),
+                item_path.display(),
+                max_size
+            );
+        }
     }
     #[cfg(not(unix))]
     if keep_options.keep_xattr {
         log::warn!("Currently extended attribute is not supported on this platform.");
     }
     #[cfg(feature = "acl")]
-    {
-        #[cfg(any(
-            target_os = "linux",
-            target_os = "freebsd",
-            target_os = "macos",
-            windows
-        ))]
-        if keep_options.keep_acl {
-            use crate::chunk::{acl_convert_current_platform, AcePlatform, Acl};
-            use crate::ext::*;
-            use itertools::Itertools;
-
-            let platform = AcePlatform::CURRENT;
-            let acls = item.acl()?;
-            if let Some((platform, acl)) = acls.into_iter().find_or_first(|(p, _)| p.eq(&platform))
-            {
-                if !acl.is_empty() {
-                    utils::acl::set_facl(
-                        &path,
-                        acl_convert_current_platform(Acl {
-                            platform,
-                            entries: acl,
-                        }),
-                    )?;
+    if keep_options.keep_acl {
+        use crate::chunk::acl_for_current_platform;
+        use crate::ext::*;
+        use crate::utils::os::{Current, Platform};
+
+        // NOTE: The ACL of another platform is restored through the general form when the archive
+        //       has no ACL of this platform.
+        if let Some(acl) = acl_for_current_platform(item.acl()?) {
+            if !acl.entries.is_empty() {
+                match Current::set_facl(path, acl) {
+                    Err(e) if e.kind() == io::ErrorKind::Unsupported => log::warn!("{}", e),
+                    r => r?,
                 }
             }
         }
-        #[cfg(not(any(
-            target_os = "linux",
-            target_os = "freebsd",
-            target_os = "macos",
-            windows
-        )))]
-        if keep_options.keep_acl {
-            log::warn!("Currently acl is not supported on this platform.");
-        }
     }
     #[cfg(not(feature = "acl"))]
     if keep_options.keep_acl {
         log::warn!("Please enable `acl` feature and rebuild and install pna.");
     }
-    log::debug!("end: {}", path.display());
     Ok(())
 }
 
+#[cfg(unix)]
+fn is_recoverable_xattr_error(e: &io::Error) -> bool {
+    use nix::errno::Errno;
+    e.kind() == io::ErrorKind::Unsupported
+        || e.raw_os_error()
+            .is_some_and(|code| code == Errno::ENOSPC as i32 || code == Errno::E2BIG as i32)
+}
+
 #[cfg(not(any(unix, windows)))]
 fn permissions<'p>(
     p: &'p Permission,
     _: &'_ OwnerOptions,
-) -> Option<(&'p Permission, Option<()>, Option<()>)> {
-    Some((p, None, None))
+    _: UnknownOwner,
+) -> io::Result<Option<(&'p Permission, Option<()>, Option<()>)>> {
+    Ok(Some((p, None, None)))
 }
 
 #[cfg(windows)]
 fn permissions<'p>(
     p: &'p Permission,
     _: &'_ OwnerOptions,
-) -> Option<(&'p Permission, Option<User>, Option<Group>)> {
-    Some((
+    _: UnknownOwner,
+) -> io::Result<Option<(&'p Permission, Option<User>, Option<Group>)>> {
+    Ok(Some((
         p,
         User::from_name(p.uname()).ok(),
         Group::from_name(p.gname()).ok(),
-    ))
+    )))
 }
 
+/// Permission to restore, with the ids of the user and the group to restore as its owners.
+#[cfg(unix)]
+type OwnedPermission<'p> = (&'p Permission, Option<u32>, Option<u32>);
+
 #[cfg(unix)]
 fn permissions<'p>(
     permission: &'p Permission,
     owner_options: &'_ OwnerOptions,
-) -> Option<(&'p Permission, Option<User>, Option<Group>)> {
-    let user = if let Some(uid) = owner_options.uid {
-        User::from_uid(uid.into())
-    } else {
-        search_owner(
-            owner_options.uname.as_deref().unwrap_or(permission.uname()),
+    unknown_owner: UnknownOwner,
+) -> io::Result<Option<OwnedPermission<'p>>> {
+    let user = match owner_options.uid {
+        Some(uid) => Some(uid),
+        None => match owner_to_restore(
+            permission.uname(),
             permission.uid(),
-        )
+            owner_options.uname.as_deref(),
+            &owner_options.uid_map,
+            owner_options.map_names,
+        )? {
+            (Some(name), uid) => {
+                resolve_owner(&USER_CACHE, name, uid, unknown_owner, search_owner, || {
+                    nix::unistd::geteuid().as_raw()
+                })
+            }
+            (None, uid) => u32::try_from(uid).ok(),
+        },
     };
-    let group = if let Some(gid) = owner_options.gid {
-        Group::from_gid(gid.into())
-    } else {
-        search_group(
-            owner_options.gname.as_deref().unwrap_or(permission.gname()),
+    let group = match owner_options.gid {
+        Some(gid) => Some(gid),
+        None => match owner_to_restore(
+            permission.gname(),
             permission.gid(),
-        )
+            owner_options.gname.as_deref(),
+            &owner_options.gid_map,
+            owner_options.map_names,
+        )? {
+            (Some(name), gid) => {
+                resolve_owner(&GROUP_CACHE, name, gid, unknown_owner, search_group, || {
+                    nix::unistd::getegid().as_raw()
+                })
+            }
+            (None, gid) => u32::try_from(gid).ok(),
+        },
     };
-    Some((permission, user.ok(), group.ok()))
+    Ok(Some((permission, user, group)))
+}
+
+/// Returns the name to look up and the id of the owner `name` and `id` in the archive, with `name_override`
+/// given by `--uname` or `--gname` and the id mapping `map` applied.
+/// The name is `None` if the owner is restored by the mapped id alone, since the names in the archive
+/// may not correspond to the owners of the mapped ids unless `map_names` is set.
+#[cfg(unix)]
+fn owner_to_restore<'a>(
+    name: &'a str,
+    id: u64,
+    name_override: Option<&'a str>,
+    map: &IdMap,
+    map_names: bool,
+) -> io::Result<(Option<&'a str>, u64)> {
+    if map.is_empty() {
+        return Ok((Some(name_override.unwrap_or(name)), id));
+    }
+    let id = map.to_host(id)?;
+    Ok((name_override.or(map_names.then_some(name)), id.into()))
 }
 
+/// Local ids of the owner names and ids in the archive, or `None` if they don't exist on this system.
+#[cfg(unix)]
+type OwnerCache = Mutex<BTreeMap<(String, u64), Option<u32>>>;
+
 #[cfg(unix)]
-fn search_owner(name: &str, id: u64) -> io::Result<User> {
-    let user = User::from_name(name);
-    if user.is_ok() {
-        return user;
+static USER_CACHE: OwnerCache = Mutex::new(BTreeMap::new());
+#[cfg(unix)]
+static GROUP_CACHE: OwnerCache = Mutex::new(BTreeMap::new());
+
+/// Resolves the owner `name` and `id` in the archive to a local id, applying `unknown_owner`
+/// when neither exists on this system.
+#[cfg(unix)]
+fn resolve_owner(
+    cache: &OwnerCache,
+    name: &str,
+    id: u64,
+    unknown_owner: UnknownOwner,
+    search: impl FnOnce(&str, u64) -> Option<u32>,
+    current: impl FnOnce() -> u32,
+) -> Option<u32> {
+    let key = (name.to_owned(), id);
+    let cached = cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(&key)
+        .copied();
+    let resolved = match cached {
+        Some(resolved) => resolved,
+        None => {
+            let resolved = search(name, id);
+            cache
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(key, resolved);
+            resolved
+        }
+    };
+    match (resolved, unknown_owner) {
+        (Some(id), _) => Some(id),
+        (None, UnknownOwner::Keep) => u32::try_from(id).ok(),
+        (None, UnknownOwner::Map) => Some(current()),
+        (None, UnknownOwner::Skip) => None,
     }
-    User::from_uid((id as u32).into())
 }
 
 #[cfg(unix)]
-fn search_group(name: &str, id: u64) -> io::Result<Group> {
-    let group = Group::from_name(name);
-    if group.is_ok() {
-        return group;
+fn search_owner(name: &str, id: u64) -> Option<u32> {
+    User::from_name(name)
+        .or_else(|_| User::from_uid((id as u32).into()))
+        .ok()
+        .map(|it| it.as_raw())
+}
+
+#[cfg(unix)]
+fn search_group(name: &str, id: u64) -> Option<u32> {
+    Group::from_name(name)
+        .or_else(|_| Group::from_gid((id as u32).into()))
+        .ok()
+        .map(|it| it.as_raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case_insensitive(case_collision: CaseCollision) -> DestinationNames {
+        DestinationNames::with_options(WindowsCompatOptions::default(), case_collision, true)
+    }
+
+    #[test]
+    fn case_collision_on_case_sensitive_filesystem() {
+        let mut names = DestinationNames::with_options(
+            WindowsCompatOptions::default(),
+            CaseCollision::Error,
+            false,
+        );
+        assert_eq!(names.resolve("README").unwrap().as_deref(), Some("README"));
+        assert_eq!(names.resolve("readme").unwrap().as_deref(), Some("readme"));
+    }
+
+    #[test]
+    fn case_collision_error() {
+        let mut names = case_insensitive(CaseCollision::Error);
+        assert_eq!(names.resolve("README").unwrap().as_deref(), Some("README"));
+        // NOTE: The same name extracted again is not a collision.
+        assert_eq!(names.resolve("README").unwrap().as_deref(), Some("README"));
+        let err = names.resolve("readme").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(
+            names.resolve("dir/a.txt").unwrap().as_deref(),
+            Some("dir/a.txt")
+        );
+        assert!(names.resolve("Dir/A.TXT").is_err());
+    }
+
+    #[test]
+    fn case_collision_skip() {
+        let mut names = case_insensitive(CaseCollision::Skip);
+        assert_eq!(names.resolve("README").unwrap().as_deref(), Some("README"));
+        assert_eq!(names.resolve("readme").unwrap(), None);
+        assert_eq!(names.skipped, 1);
+    }
+
+    #[test]
+    fn case_collision_rename() {
+        let mut names = case_insensitive(CaseCollision::Rename);
+        assert_eq!(names.resolve("README").unwrap().as_deref(), Some("README"));
+        assert_eq!(
+            names.resolve("readme").unwrap().as_deref(),
+            Some("readme~1")
+        );
+        assert_eq!(
+            names.resolve("ReadMe").unwrap().as_deref(),
+            Some("ReadMe~2")
+        );
+        assert_eq!(
+            names.resolve("doc/a.tar.gz").unwrap().as_deref(),
+            Some("doc/a.tar.gz")
+        );
+        assert_eq!(
+            names.resolve("doc/A.tar.gz").unwrap().as_deref(),
+            Some("doc/A~1.tar.gz")
+        );
+        assert_eq!(names.target("link", "readme").as_deref(), Some("readme~1"));
+        assert_eq!(names.target("link", "README").as_deref(), Some("README"));
+    }
+
+    #[test]
+    fn case_collision_rename_directory() {
+        let mut names = case_insensitive(CaseCollision::Rename);
+        assert_eq!(names.resolve("Dir").unwrap().as_deref(), Some("Dir"));
+        assert_eq!(names.resolve("dir").unwrap().as_deref(), Some("dir~1"));
+        // NOTE: The entries in the renamed directory follow it.
+        assert_eq!(
+            names.resolve("dir/file.txt").unwrap().as_deref(),
+            Some("dir~1/file.txt")
+        );
+        assert_eq!(
+            names.resolve("Dir/file.txt").unwrap().as_deref(),
+            Some("Dir/file.txt")
+        );
+        assert_eq!(
+            names.target("link", "dir/file.txt").as_deref(),
+            Some("dir~1/file.txt")
+        );
+    }
+
+    #[test]
+    fn normalize_paths() {
+        let mut names = DestinationNames::with_options(
+            WindowsCompatOptions::default(),
+            CaseCollision::Error,
+            false,
+        );
+        assert_eq!(
+            names.resolve("dir\\file.txt").unwrap().as_deref(),
+            Some("dir\\file.txt")
+        );
+        assert_eq!(names.ambiguous, 1);
+
+        names.normalize_paths = true;
+        assert_eq!(
+            names.resolve("dir\\sub\\file.txt").unwrap().as_deref(),
+            Some("dir/sub/file.txt")
+        );
+        assert_eq!(
+            names.target("link", "dir\\sub\\file.txt").as_deref(),
+            Some("dir/sub/file.txt")
+        );
+        // NOTE: The same name extracted again is not a collision.
+        assert_eq!(
+            names.resolve("dir\\sub\\file.txt").unwrap().as_deref(),
+            Some("dir/sub/file.txt")
+        );
+        let err = names.resolve("dir//sub/file.txt").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(names.resolve("a.txt").unwrap().as_deref(), Some("a.txt"));
+        assert!(names.resolve("a.txt\\").is_err());
+    }
+
+    #[test]
+    fn transform_then_strip_components() {
+        let mut names = DestinationNames::with_options(
+            WindowsCompatOptions::default(),
+            CaseCollision::Error,
+            false,
+        );
+        names.transformer =
+            Transformer::gnu(vec![SubstitutionRule::parse_gnu("s,^,top/,SH").unwrap()]);
+        names.strip_components = 1;
+        // NOTE: The component added by the transformation is stripped.
+        assert_eq!(names.resolve("a.txt").unwrap().as_deref(), Some("a.txt"));
+        assert_eq!(
+            names.resolve("dir/a.txt").unwrap().as_deref(),
+            Some("dir/a.txt")
+        );
+        assert_eq!(
+            names.target("dir/hard.txt", "a.txt").as_deref(),
+            Some("a.txt")
+        );
+        assert_eq!(names.symlink_target("dir/sym.txt", "a.txt"), "a.txt");
+
+        names.strip_components = 2;
+        assert_eq!(names.resolve("a.txt").unwrap(), None);
+        assert_eq!(
+            names.resolve("dir/a.txt").unwrap().as_deref(),
+            Some("a.txt")
+        );
+        assert_eq!(
+            names.target("dir/sub/hard.txt", "../a.txt").as_deref(),
+            Some("../a.txt")
+        );
+    }
+
+    #[test]
+    fn strip_components_of_link_targets() {
+        let mut names = DestinationNames::with_options(
+            WindowsCompatOptions::default(),
+            CaseCollision::Error,
+            false,
+        );
+        names.strip_components = 1;
+        // NOTE: The targets point from the stripped links to the same entries.
+        assert_eq!(
+            names.target("src/hard.txt", "../src/a.txt").as_deref(),
+            Some("a.txt")
+        );
+        assert_eq!(
+            names.target("top/a/hard.txt", "../b/c.txt").as_deref(),
+            Some("../b/c.txt")
+        );
+        assert_eq!(
+            names.symlink_target("src/dir/sym.txt", "../../src/a.txt"),
+            "../a.txt"
+        );
+        // NOTE: The targets whose components are all stripped are not extracted.
+        assert_eq!(names.target("src/hard.txt", "../a.txt"), None);
+        assert_eq!(names.symlink_target("src/sym.txt", ".."), "..");
+        // NOTE: Absolute targets and the targets outside the archive are kept.
+        assert_eq!(
+            names.target("src/hard.txt", "../../a.txt").as_deref(),
+            Some("../../a.txt")
+        );
+        assert_eq!(
+            names.symlink_target("src/sym.txt", "/etc/hosts"),
+            "/etc/hosts"
+        );
+    }
+
+    #[test]
+    fn suffix_before_extension() {
+        assert_eq!(with_suffix("a", "~1"), "a~1");
+        assert_eq!(with_suffix("a.txt", "~1"), "a~1.txt");
+        assert_eq!(with_suffix("dir.d/.profile", "~1"), "dir.d/.profile~1");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unknown_owner_policy() {
+        const NAME: &str = "pna-unknown-owner";
+        const ID: u64 = 3_999_999_999;
+        let search = |name: &str, id: u64| {
+            assert_eq!((name, id), (NAME, ID));
+            search_owner(name, id)
+        };
+        for (policy, expected) in [
+            (UnknownOwner::Keep, Some(ID as u32)),
+            (UnknownOwner::Map, Some(1000)),
+            (UnknownOwner::Skip, None),
+        ] {
+            let cache = OwnerCache::default();
+            assert_eq!(
+                resolve_owner(&cache, NAME, ID, policy, search, || 1000),
+                expected
+            );
+            // NOTE: The result of the lookup is cached.
+            assert_eq!(
+                resolve_owner(&cache, NAME, ID, policy, |_, _| unreachable!(), || 1000),
+                expected
+            );
+        }
+        for policy in [UnknownOwner::Keep, UnknownOwner::Map, UnknownOwner::Skip] {
+            let cache = OwnerCache::default();
+            assert_eq!(
+                resolve_owner(&cache, "root", ID, policy, search_owner, || 1000),
+                Some(0)
+            );
+            assert_eq!(
+                resolve_owner(&cache, NAME, 0, policy, search_owner, || 1000),
+                Some(0)
+            );
+        }
     }
-    Group::from_gid((id as u32).into())
 }
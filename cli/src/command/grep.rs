@@ -0,0 +1,302 @@
+use crate::{
+    cli::{FileArgs, PasswordArgs},
+    command::{
+        ask_password,
+        commons::{run_process_archive, PathArchiveProvider, MAP_WINDOW_PER_THREAD},
+        Command,
+    },
+    exit_code::{usage_error, with_status, ExitStatus, StatusReader},
+    utils::GlobPatterns,
+};
+use bytesize::ByteSize;
+use clap::{Parser, ValueEnum, ValueHint};
+use pna::{DataKind, Encryption, NormalEntry, ReadOptions};
+use regex::bytes::{Regex, RegexBuilder};
+use std::{
+    collections::BTreeMap,
+    io::{self, prelude::*, BufReader},
+    sync::mpsc,
+};
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct GrepCommand {
+    #[arg(
+        short = 'l',
+        long,
+        help = "Print only the names of entries that contain matches"
+    )]
+    files_with_matches: bool,
+    #[arg(
+        short,
+        long,
+        conflicts_with = "files_with_matches",
+        help = "Print only the number of matching lines of each entry"
+    )]
+    count: bool,
+    #[arg(short, long, help = "Ignore case distinctions in the pattern")]
+    ignore_case: bool,
+    #[arg(
+        long,
+        value_name = "TYPE",
+        default_value = "skip",
+        help = "How to handle entries that contain binary data"
+    )]
+    binary_files: BinaryFiles,
+    #[arg(
+        long,
+        value_name = "BYTES",
+        help = "Skip entries larger than the given size"
+    )]
+    max_filesize: Option<ByteSize>,
+    #[arg(long, help = "Exclude path glob", value_hint = ValueHint::AnyPath)]
+    exclude: Option<Vec<globset::Glob>>,
+    #[command(flatten)]
+    password: PasswordArgs,
+    #[arg(help = "Regular expression to search for")]
+    pattern: String,
+    #[command(flatten)]
+    file: FileArgs,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum)]
+enum BinaryFiles {
+    /// Skip entries that contain binary data
+    Skip,
+    /// Search entries that contain binary data as if they were text
+    Text,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+enum OutputMode {
+    Lines,
+    FilesWithMatches,
+    Count,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+struct GrepOptions {
+    mode: OutputMode,
+    binary_files: BinaryFiles,
+}
+
+impl Command for GrepCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        grep_archive(self)
+    }
+}
+
+fn grep_archive(args: GrepCommand) -> io::Result<()> {
+    let password = ask_password(args.password)?;
+    let regex = RegexBuilder::new(&args.pattern)
+        .case_insensitive(args.ignore_case)
+        .build()
+        .map_err(usage_error)?;
+    let globs = GlobPatterns::new(args.file.files).map_err(usage_error)?;
+    let exclude_globs =
+        GlobPatterns::try_from(args.exclude.unwrap_or_default()).map_err(usage_error)?;
+    let max_filesize = args.max_filesize.map(|it| it.as_u64() as u128);
+    let options = GrepOptions {
+        mode: if args.files_with_matches {
+            OutputMode::FilesWithMatches
+        } else if args.count {
+            OutputMode::Count
+        } else {
+            OutputMode::Lines
+        },
+        binary_files: args.binary_files,
+    };
+    let read_options = ReadOptions::with_password(password.as_deref());
+
+    let is_target = |entry: &NormalEntry| {
+        let path = entry.header().path();
+        if entry.header().data_kind() != DataKind::File
+            || (!globs.is_empty() && !globs.matches_any(path))
+            || exclude_globs.matches_any(path)
+        {
+            log::debug!("Skip: {}", path);
+            return false;
+        }
+        if let (Some(max), Some(size)) = (max_filesize, entry.metadata().raw_file_size()) {
+            if max < size {
+                log::debug!("Skip large entry: {}", path);
+                return false;
+            }
+        }
+        true
+    };
+
+    let mut writer = OrderedWriter::new(io::stdout().lock());
+    let mut index = 0;
+    if rayon::current_thread_index().is_none() {
+        // NOTE: Outside a thread pool, as with `--threads 1`, the entries are searched on this thread.
+        run_process_archive(
+            PathArchiveProvider::new(&args.file.archive),
+            || password.as_deref().map(String::as_str),
+            |entry| {
+                let entry = entry?;
+                if is_target(&entry) {
+                    writer.push(index, grep_entry(entry, &regex, options, &read_options)?)?;
+                    index += 1;
+                }
+                Ok(())
+            },
+        )?;
+        writer.flush()?;
+        return globs.ensure_all_matched();
+    }
+    // NOTE: Only the entries of a window are searched at once, so that the entries read ahead
+    //       of the searches and their outputs waiting to be written are bounded.
+    let window = rayon::current_num_threads() * MAP_WINDOW_PER_THREAD;
+    let mut received = 0;
+    let (tx, rx) = mpsc::channel();
+    // NOTE: The entries are searched in a scope, which runs the remaining jobs on this thread
+    //       before it returns, so that a pool of a single thread does not wait for itself.
+    rayon::in_place_scope_fifo(|s| {
+        run_process_archive(
+            PathArchiveProvider::new(&args.file.archive),
+            || password.as_deref().map(String::as_str),
+            |entry| {
+                let entry = entry?;
+                if !is_target(&entry) {
+                    return Ok(());
+                }
+                let (tx, regex, read_options) = (tx.clone(), &regex, &read_options);
+                let i = index;
+                index += 1;
+                s.spawn_fifo(move |_| {
+                    // NOTE: The receiver has gone only when reading the archive failed.
+                    let _ = tx.send((i, grep_entry(entry, regex, options, read_options)));
+                });
+                while let Ok((i, result)) = rx.try_recv() {
+                    received += 1;
+                    writer.push(i, result?)?;
+                }
+                // NOTE: While the window is full, the queued searches are run on this thread,
+                //       and this thread waits only for the searches running on the other threads,
+                //       so that a pool of a single thread does not wait for itself.
+                while window <= index - received {
+                    let (i, result) = match rx.try_recv() {
+                        Ok(it) => it,
+                        Err(_) if rayon::yield_now() == Some(rayon::Yield::Executed) => continue,
+                        Err(_) => rx
+                            .recv()
+                            .expect("the sender is held until the archive is read"),
+                    };
+                    received += 1;
+                    writer.push(i, result?)?;
+                }
+                Ok(())
+            },
+        )
+    })?;
+    drop(tx);
+    for (i, result) in rx {
+        writer.push(i, result?)?;
+    }
+    writer.flush()?;
+    globs.ensure_all_matched()
+}
+
+fn grep_entry(
+    entry: NormalEntry,
+    regex: &Regex,
+    options: GrepOptions,
+    read_options: &ReadOptions,
+) -> io::Result<Vec<u8>> {
+    let name = entry.header().path().to_string();
+    let data_error = if entry.header().encryption() == Encryption::No {
+        ExitStatus::CorruptArchive
+    } else {
+        ExitStatus::Crypto
+    };
+    let reader = entry
+        .reader(read_options)
+        .map_err(|e| with_status(data_error, e))?;
+    let mut reader = BufReader::new(StatusReader::new(reader, data_error));
+    if options.binary_files == BinaryFiles::Skip && reader.fill_buf()?.contains(&0) {
+        log::debug!("Skip binary entry: {}", name);
+        return Ok(Vec::new());
+    }
+    let mut output = Vec::new();
+    let mut line = Vec::new();
+    let mut line_number = 0;
+    let mut count = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        if !regex.is_match(text) {
+            continue;
+        }
+        count += 1;
+        match options.mode {
+            OutputMode::Lines => {
+                write!(output, "{name}:{line_number}:")?;
+                output.extend_from_slice(text);
+                output.push(b'\n');
+            }
+            OutputMode::FilesWithMatches => {
+                writeln!(output, "{name}")?;
+                break;
+            }
+            OutputMode::Count => (),
+        }
+    }
+    if options.mode == OutputMode::Count {
+        writeln!(output, "{name}:{count}")?;
+    }
+    Ok(output)
+}
+
+/// Writes outputs produced out of order in the order of their indices.
+struct OrderedWriter<W> {
+    inner: W,
+    next: usize,
+    pending: BTreeMap<usize, Vec<u8>>,
+}
+
+impl<W: Write> OrderedWriter<W> {
+    #[inline]
+    const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    #[inline]
+    fn push(&mut self, index: usize, output: Vec<u8>) -> io::Result<()> {
+        self.pending.insert(index, output);
+        while let Some(output) = self.pending.remove(&self.next) {
+            self.inner.write_all(&output)?;
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordered_writer() {
+        let mut writer = OrderedWriter::new(Vec::new());
+        writer.push(1, b"b".to_vec()).unwrap();
+        writer.push(2, b"c".to_vec()).unwrap();
+        assert!(writer.inner.is_empty());
+        writer.push(0, b"a".to_vec()).unwrap();
+        assert_eq!(writer.inner, b"abc");
+    }
+}
@@ -0,0 +1,323 @@
+use crate::{
+    chunk,
+    command::{
+        commons::{ArchiveProvider, PathArchiveProvider, StdinArchiveProvider},
+        Command,
+    },
+    utils::hash,
+};
+use clap::{Parser, ValueEnum, ValueHint};
+use pna::{ChunkType, PNA_HEADER};
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, prelude::*, SeekFrom},
+    mem,
+    path::PathBuf,
+};
+
+/// Size of the length, the type and the CRC of a chunk.
+const CHUNK_OVERHEAD: u64 = 3 * mem::size_of::<u32>() as u64;
+/// Upper bound of the data length of the chunks whose data are read.
+const MAX_HEADER_DATA_LENGTH: u64 = 64;
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct InfoCommand {
+    #[arg(long, value_enum, default_value_t = Format::Text, help = "Output format")]
+    format: Format,
+    #[arg(
+        value_hint = ValueHint::FilePath,
+        help = "Archive to inspect, or the first part of a split archive. Read from stdin if omitted"
+    )]
+    archive: Option<PathBuf>,
+}
+
+impl Command for InfoCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        archive_info(self)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+/// Counts of the chunks of an archive or of a part of it.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize)]
+struct ChunkCounts {
+    /// Size in bytes, including the PNA header.
+    size: u64,
+    chunks: u64,
+    /// Bytes other than the data of the chunks, i.e. the PNA header and the length, type and CRC of every chunk.
+    overhead: u64,
+    entries: u64,
+    solid_entries: u64,
+    unknown_chunks: u64,
+    features: BTreeSet<&'static str>,
+    chunk_types: BTreeMap<String, u64>,
+}
+
+impl ChunkCounts {
+    fn with_header() -> Self {
+        let header = PNA_HEADER.len() as u64;
+        Self {
+            size: header,
+            overhead: header,
+            ..Default::default()
+        }
+    }
+
+    fn record(&mut self, ty: ChunkType, length: u64) {
+        self.size += CHUNK_OVERHEAD + length;
+        self.overhead += CHUNK_OVERHEAD;
+        self.chunks += 1;
+        *self.chunk_types.entry(ty.to_string()).or_default() += 1;
+        match ty {
+            ChunkType::FHED => self.entries += 1,
+            ChunkType::SHED => self.solid_entries += 1,
+            _ => (),
+        }
+        if !chunk::KNOWN_CHUNK_TYPES.contains(&ty) {
+            self.unknown_chunks += 1;
+        }
+        if let Some(feature) = feature_of(ty) {
+            self.features.insert(feature);
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.size += other.size;
+        self.chunks += other.chunks;
+        self.overhead += other.overhead;
+        self.entries += other.entries;
+        self.solid_entries += other.solid_entries;
+        self.unknown_chunks += other.unknown_chunks;
+        self.features.extend(&other.features);
+        for (ty, count) in &other.chunk_types {
+            *self.chunk_types.entry(ty.clone()).or_default() += count;
+        }
+    }
+}
+
+/// Optional feature used by an archive that contains a chunk of type `ty`.
+fn feature_of(ty: ChunkType) -> Option<&'static str> {
+    Some(match ty {
+        ChunkType::SHED => "solid",
+        ChunkType::PHSF => "password",
+        ChunkType::WKEY => "recipients",
+        ChunkType::xATR | ChunkType::xATZ => "xattrs",
+        chunk::faCe | chunk::faCl => "acl",
+        chunk::fsIG | chunk::asIG => "signatures",
+        chunk::fPLT => "platform-tags",
+        _ => return None,
+    })
+}
+
+/// Information read from the chunk headers of a part of an archive.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+struct PartInfo {
+    version: String,
+    archive_number: u32,
+    archive_id: Option<String>,
+    has_next_part: bool,
+    #[serde(flatten)]
+    counts: ChunkCounts,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+struct ArchiveInfo {
+    parts: Vec<PartInfo>,
+    total: ChunkCounts,
+}
+
+fn archive_info(args: InfoCommand) -> io::Result<()> {
+    let parts = match &args.archive {
+        Some(path) => scan_archive(PathArchiveProvider::new(path), |file, n| {
+            file.seek(SeekFrom::Current(n as i64)).map(drop)
+        })?,
+        None => scan_archive(StdinArchiveProvider::new(), |stdin, n| {
+            let skipped = io::copy(&mut stdin.take(n), &mut io::sink())?;
+            if skipped != n {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            Ok(())
+        })?,
+    };
+    let mut total = ChunkCounts::default();
+    for part in &parts {
+        total.merge(&part.counts);
+    }
+    let info = ArchiveInfo { parts, total };
+    let mut stdout = io::stdout().lock();
+    match args.format {
+        Format::Text => print_text(&mut stdout, &info)?,
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut stdout, &info)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the parts of an archive from `provider`, skipping the data of chunks with `skip`.
+fn scan_archive<P, S>(provider: P, mut skip: S) -> io::Result<Vec<PartInfo>>
+where
+    P: ArchiveProvider,
+    S: FnMut(&mut P::Source, u64) -> io::Result<()>,
+{
+    let mut source = provider.initial_source()?;
+    let mut parts = Vec::new();
+    loop {
+        let part = scan_part(&mut source, &mut skip)?;
+        let has_next_part = part.has_next_part;
+        parts.push(part);
+        if !has_next_part {
+            break;
+        }
+        let n = parts.len() + 1;
+        // NOTE: Release the current source first, since the next part from stdin locks stdin again.
+        drop(source);
+        source = match provider.next_source(n) {
+            Ok(source) => source,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                log::warn!("Part {n} of the archive was not found");
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+    }
+    Ok(parts)
+}
+
+fn scan_part<R, S>(reader: &mut R, skip: &mut S) -> io::Result<PartInfo>
+where
+    R: Read,
+    S: FnMut(&mut R, u64) -> io::Result<()>,
+{
+    let mut header = [0; PNA_HEADER.len()];
+    reader.read_exact(&mut header)?;
+    if header != *PNA_HEADER {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "It's not PNA"));
+    }
+    let mut part = PartInfo {
+        version: String::new(),
+        archive_number: 0,
+        archive_id: None,
+        has_next_part: false,
+        counts: ChunkCounts::with_header(),
+    };
+    loop {
+        let mut head = [0; 2 * mem::size_of::<u32>()];
+        reader.read_exact(&mut head)?;
+        let (length, ty) = head.split_at(mem::size_of::<u32>());
+        let length = u64::from(u32::from_be_bytes(length.try_into().unwrap()));
+        let ty: [u8; 4] = ty.try_into().unwrap();
+        if !ty.iter().all(u8::is_ascii_alphabetic) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid chunk type {ty:?}"),
+            ));
+        }
+        // SAFETY: checked that the type consists only of ascii alphabetic characters.
+        let ty = unsafe { ChunkType::from_unchecked(ty) };
+        if part.counts.chunks == 0 && ty != ChunkType::AHED {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Expected AHED chunk, but found {ty} chunk"),
+            ));
+        }
+        match ty {
+            ChunkType::AHED | ChunkType::aIDN => {
+                if length > MAX_HEADER_DATA_LENGTH {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{ty} chunk is too large ({length} bytes)"),
+                    ));
+                }
+                let mut data = vec![0; length as usize];
+                reader.read_exact(&mut data)?;
+                skip(reader, mem::size_of::<u32>() as u64)?;
+                if ty == ChunkType::AHED {
+                    let [major, minor, _, _, a, b, c, d]: [u8; 8] =
+                        data[..].try_into().map_err(|_| {
+                            io::Error::new(io::ErrorKind::InvalidData, "Invalid AHED chunk")
+                        })?;
+                    part.version = format!("{major}.{minor}");
+                    part.archive_number = u32::from_be_bytes([a, b, c, d]);
+                } else {
+                    part.archive_id = Some(hash::hex(data));
+                }
+            }
+            _ => skip(reader, length + mem::size_of::<u32>() as u64)?,
+        }
+        part.counts.record(ty, length);
+        match ty {
+            ChunkType::ANXT => part.has_next_part = true,
+            ChunkType::AEND => return Ok(part),
+            _ => (),
+        }
+    }
+}
+
+fn print_text(out: &mut impl Write, info: &ArchiveInfo) -> io::Result<()> {
+    if let Some(first) = info.parts.first() {
+        writeln!(out, "Format version: {}", first.version)?;
+        writeln!(
+            out,
+            "Archive ID: {}",
+            first.archive_id.as_deref().unwrap_or("-")
+        )?;
+    }
+    if info.parts.len() > 1 {
+        writeln!(out, "Parts: {}", info.parts.len())?;
+        for (i, part) in info.parts.iter().enumerate() {
+            writeln!(
+                out,
+                "  Part {}: archive number {}, {} bytes, {} chunks, {} entries, {} solid entries{}",
+                i + 1,
+                part.archive_number,
+                part.counts.size,
+                part.counts.chunks,
+                part.counts.entries,
+                part.counts.solid_entries,
+                if part.has_next_part {
+                    ", continues in the next part"
+                } else {
+                    ""
+                }
+            )?;
+        }
+    } else if let Some(part) = info.parts.first() {
+        writeln!(out, "Archive number: {}", part.archive_number)?;
+        writeln!(out, "Has next part: {}", part.has_next_part)?;
+    }
+    let total = &info.total;
+    writeln!(out, "Size: {} bytes", total.size)?;
+    writeln!(out, "Chunks: {}", total.chunks)?;
+    writeln!(out, "Chunk overhead: {} bytes", total.overhead)?;
+    writeln!(out, "Entries: {}", total.entries)?;
+    writeln!(out, "Solid entries: {}", total.solid_entries)?;
+    writeln!(out, "Unknown chunks: {}", total.unknown_chunks)?;
+    writeln!(
+        out,
+        "Features: {}",
+        if total.features.is_empty() {
+            "-".into()
+        } else {
+            total
+                .features
+                .iter()
+                .copied()
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    )?;
+    writeln!(out, "Chunk types:")?;
+    for (ty, count) in &total.chunk_types {
+        writeln!(out, "  {ty}: {count}")?;
+    }
+    Ok(())
+}
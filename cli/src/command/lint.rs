@@ -0,0 +1,375 @@
+use crate::{
+    cli::PasswordArgs,
+    command::{
+        ask_password,
+        commons::{run_read_entries, run_read_entry_summaries, PathArchiveProvider},
+        Command,
+    },
+    exit_code::{with_status, ExitStatus},
+    utils::{is_windows_reserved_name, resolve_link, LinkTargets},
+};
+use clap::{Parser, ValueEnum, ValueHint};
+use pna::{DataKind, NormalEntry, ReadEntry, ReadOptions};
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, prelude::*},
+    mem,
+    path::PathBuf,
+};
+
+const MAX_COMPONENT_BYTES: usize = 255;
+const MAX_PATH_CHARS: usize = 260;
+const WINDOWS_RESERVED_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*', '\\'];
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct LintCommand {
+    #[arg(
+        long,
+        value_name = "CHECK",
+        value_delimiter = ',',
+        help = "Run only the given checks [default: all checks]"
+    )]
+    check: Vec<LintCheck>,
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "text",
+        help = "Output format"
+    )]
+    format: LintFormat,
+    #[arg(long, help = "Check the entries in solid mode archive entries")]
+    solid: bool,
+    #[command(flatten)]
+    password: PasswordArgs,
+    #[arg(value_hint = ValueHint::FilePath)]
+    archive: PathBuf,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum LintCheck {
+    /// Entry names that differ only by case
+    CaseCollision,
+    /// Characters, trailing dots or spaces and device names that are not allowed on Windows
+    WindowsName,
+    /// Path components longer than 255 bytes
+    LongComponent,
+    /// Paths longer than 260 characters
+    LongPath,
+    /// Symbolic links pointing outside the archive
+    SymlinkEscape,
+    /// Symbolic links to targets that are not in the archive. Targets outside the archive are reported by symlink-escape
+    Links,
+    /// Control characters or characters replaced on conversion from non UTF-8 names
+    NonUtf8,
+}
+
+impl LintCheck {
+    #[inline]
+    const fn severity(self) -> Severity {
+        match self {
+            Self::LongPath | Self::NonUtf8 => Severity::Warning,
+            Self::CaseCollision
+            | Self::WindowsName
+            | Self::LongComponent
+            | Self::SymlinkEscape
+            | Self::Links => Severity::Error,
+        }
+    }
+}
+
+impl fmt::Display for LintCheck {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(
+            self.to_possible_value()
+                .expect("no skipped values")
+                .get_name(),
+        )
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum)]
+enum LintFormat {
+    Text,
+    Json,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Serialize)]
+struct Finding {
+    check: LintCheck,
+    severity: Severity,
+    path: String,
+    message: String,
+}
+
+impl Command for LintCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        lint_archive(self)
+    }
+}
+
+fn lint_archive(args: LintCommand) -> io::Result<()> {
+    let password = ask_password(args.password)?;
+    let checks = if args.check.is_empty() {
+        LintCheck::value_variants().to_vec()
+    } else {
+        args.check
+    };
+    let read_options = ReadOptions::with_password(password.as_deref());
+    let mut linter = Linter::new(checks);
+    let mut lint_entry = |entry: &NormalEntry| -> io::Result<()> {
+        let path = entry.header().path().as_str();
+        // NOTE: Only the targets of symbolic links are read, which are small.
+        let link = if entry.header().data_kind() == DataKind::SymbolicLink
+            && (linter.enabled(LintCheck::SymlinkEscape) || linter.enabled(LintCheck::Links))
+        {
+            Some(io::read_to_string(entry.reader(&read_options)?)?)
+        } else {
+            None
+        };
+        linter.lint(path, link.as_deref());
+        Ok(())
+    };
+    if args.solid {
+        run_read_entries(PathArchiveProvider::new(&args.archive), |entry| {
+            match entry? {
+                ReadEntry::Solid(solid) => {
+                    for entry in solid.entries(password.as_deref().map(String::as_str))? {
+                        lint_entry(&entry?)?;
+                    }
+                }
+                ReadEntry::Normal(entry) => lint_entry(&entry)?,
+            }
+            Ok(())
+        })?;
+    } else {
+        // NOTE: The checks need only the names and the targets of links, so the data of file entries is skipped.
+        let solid_entries =
+            run_read_entry_summaries(PathArchiveProvider::new(&args.archive), |summary| {
+                lint_entry(summary?.entry())
+            })?;
+        if solid_entries != 0 {
+            log::warn!(
+                "This archive contain solid mode entry. if you need to check it use --solid option."
+            );
+        }
+    }
+    let findings = linter.finish();
+    let mut stdout = io::stdout().lock();
+    match args.format {
+        LintFormat::Text => {
+            for finding in &findings {
+                writeln!(
+                    stdout,
+                    "{}: {}: {} [{}]",
+                    finding.severity, finding.path, finding.message, finding.check
+                )?;
+            }
+        }
+        LintFormat::Json => {
+            serde_json::to_writer_pretty(&mut stdout, &findings)?;
+            writeln!(stdout)?;
+        }
+    }
+    let errors = findings
+        .iter()
+        .filter(|it| it.severity == Severity::Error)
+        .count();
+    if errors == 0 {
+        return Ok(());
+    }
+    Err(with_status(
+        ExitStatus::Failure,
+        io::Error::other(format!("{errors} errors found")),
+    ))
+}
+
+struct Linter {
+    checks: Vec<LintCheck>,
+    /// Lowercased names mapped to the first name seen.
+    names: HashMap<String, String>,
+    /// Entries the symbolic links may point to, checked after all the entries are seen.
+    link_targets: LinkTargets,
+    /// Symbolic links with the entry names of their targets inside the archive.
+    links: Vec<(String, String, String)>,
+    findings: Vec<Finding>,
+}
+
+impl Linter {
+    #[inline]
+    fn new(checks: Vec<LintCheck>) -> Self {
+        Self {
+            checks,
+            names: HashMap::new(),
+            link_targets: LinkTargets::default(),
+            links: Vec::new(),
+            findings: Vec::new(),
+        }
+    }
+
+    #[inline]
+    fn enabled(&self, check: LintCheck) -> bool {
+        self.checks.contains(&check)
+    }
+
+    fn report(&mut self, check: LintCheck, path: &str, message: String) {
+        self.findings.push(Finding {
+            check,
+            severity: check.severity(),
+            path: path.into(),
+            message,
+        });
+    }
+
+    /// Checks the entry at `path`, with the target of the link if it is a symbolic link.
+    fn lint(&mut self, path: &str, link: Option<&str>) {
+        if self.enabled(LintCheck::CaseCollision) {
+            let first = self
+                .names
+                .entry(path.to_lowercase())
+                .or_insert_with(|| path.into());
+            if first != path {
+                let message = format!("differs only by case from `{first}`");
+                self.report(LintCheck::CaseCollision, path, message);
+            }
+        }
+        if self.enabled(LintCheck::WindowsName) {
+            for component in path.split('/') {
+                if let Some(reason) = windows_name_problem(component) {
+                    let message = format!("component `{component}` {reason}");
+                    self.report(LintCheck::WindowsName, path, message);
+                }
+            }
+        }
+        if self.enabled(LintCheck::LongComponent) {
+            for component in path.split('/') {
+                if component.len() > MAX_COMPONENT_BYTES {
+                    let message = format!(
+                        "component is {} bytes long, longer than {MAX_COMPONENT_BYTES}",
+                        component.len()
+                    );
+                    self.report(LintCheck::LongComponent, path, message);
+                }
+            }
+        }
+        if self.enabled(LintCheck::LongPath) {
+            let len = path.chars().count();
+            if len > MAX_PATH_CHARS {
+                let message =
+                    format!("path is {len} characters long, longer than {MAX_PATH_CHARS}");
+                self.report(LintCheck::LongPath, path, message);
+            }
+        }
+        if self.enabled(LintCheck::Links) {
+            self.link_targets.insert(path);
+            if let Some(link) = link {
+                if let Some(target) = resolve_link(path, link) {
+                    self.links.push((path.into(), link.into(), target));
+                }
+            }
+        }
+        if self.enabled(LintCheck::SymlinkEscape) {
+            if let Some(link) = link {
+                if resolve_link(path, link).is_none() {
+                    let message = format!("symbolic link to `{link}` points outside the archive");
+                    self.report(LintCheck::SymlinkEscape, path, message);
+                }
+            }
+        }
+        if self.enabled(LintCheck::NonUtf8) {
+            if path.contains(char::REPLACEMENT_CHARACTER) {
+                let message = "contains a replacement character, the name may have been converted from non UTF-8".into();
+                self.report(LintCheck::NonUtf8, path, message);
+            }
+            if path.chars().any(char::is_control) {
+                let message = "contains a control character".into();
+                self.report(LintCheck::NonUtf8, path, message);
+            }
+        }
+    }
+
+    /// Reports the symbolic links whose targets are not in the archive, and returns all the findings.
+    fn finish(mut self) -> Vec<Finding> {
+        for (path, link, target) in mem::take(&mut self.links) {
+            if !self.link_targets.contains(&target) {
+                let message = format!("symbolic link to `{link}` does not resolve to an entry");
+                self.report(LintCheck::Links, &path, message);
+            }
+        }
+        self.findings
+    }
+}
+
+/// Returns the reason why `component` can not be used as a file name on Windows.
+fn windows_name_problem(component: &str) -> Option<&'static str> {
+    if component.contains(WINDOWS_RESERVED_CHARS) {
+        return Some("contains a character not allowed on Windows");
+    }
+    if component.ends_with(['.', ' ']) && component != "." && component != ".." {
+        return Some("ends with a dot or a space");
+    }
+    if is_windows_reserved_name(component) {
+        return Some("is a reserved device name on Windows");
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_names() {
+        assert_eq!(windows_name_problem("file.txt"), None);
+        assert!(windows_name_problem("a:b").is_some());
+        assert!(windows_name_problem("name.").is_some());
+        assert!(windows_name_problem("name ").is_some());
+        assert!(windows_name_problem("con").is_some());
+        assert!(windows_name_problem("Nul.txt").is_some());
+        assert_eq!(windows_name_problem("console"), None);
+    }
+
+    #[test]
+    fn dangling_links() {
+        let mut linter = Linter::new(vec![LintCheck::Links]);
+        linter.lint("dir/link", Some("../target"));
+        linter.lint("dir/sub_link", Some("sub"));
+        linter.lint("dir/escape", Some("../../target"));
+        linter.lint("dir/sub/file.txt", None);
+        let findings = linter.finish();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].path, "dir/link");
+    }
+
+    #[test]
+    fn case_collision() {
+        let mut linter = Linter::new(vec![LintCheck::CaseCollision]);
+        linter.lint("README", None);
+        linter.lint("README", None);
+        assert!(linter.findings.is_empty());
+        linter.lint("readme", None);
+        assert_eq!(linter.findings.len(), 1);
+        assert_eq!(linter.findings[0].severity, Severity::Error);
+    }
+}
@@ -7,31 +7,37 @@ use crate::{
     cli::{FileArgs, PasswordArgs},
     command::{
         ask_password,
-        commons::{run_read_entries, ArchiveProvider},
-        Command,
+        commons::{
+            run_read_entries, run_read_entry_summaries, ArchiveProvider, StdinArchiveProvider,
+        },
+        read_identities, Command,
     },
+    exit_code::usage_error,
     ext::*,
-    utils::GlobPatterns,
+    utils::{self, url::ArchiveLocation, GlobPatterns},
 };
+#[cfg(feature = "http")]
+use crate::{cli::HttpArgs, command::commons::HttpArchiveProvider};
 use base64::Engine;
 use chrono::{DateTime, Local};
 use clap::{
     builder::styling::{AnsiColor, Color as Colour, Style},
     ArgGroup, Parser,
 };
+use indexmap::IndexMap;
+use normalize_path::*;
 use pna::{
-    prelude::*, Compression, DataKind, Encryption, ExtendedAttribute, NormalEntry, RawChunk,
-    ReadEntry, ReadOptions, SolidHeader,
+    prelude::*, Compression, DataKind, Encryption, ExtendedAttribute, Identity, NormalEntry,
+    RawChunk, ReadEntry, ReadOptions, SolidHeader,
 };
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-#[cfg(feature = "memmap")]
-use std::path::Path;
 use std::{
     borrow::Cow,
     collections::{BTreeSet, HashMap},
     fmt::{Display, Formatter},
-    io::{self, prelude::*},
+    io::{self, prelude::*, IsTerminal},
+    path::{Path, PathBuf},
     str::FromStr,
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
@@ -50,6 +56,8 @@ use tabled::{
     group(ArgGroup::new("unstable-acl").args(["show_acl"]).requires("unstable")),
     group(ArgGroup::new("unstable-private-chunk").args(["show_private"]).requires("unstable")),
     group(ArgGroup::new("unstable-format").args(["format"]).requires("unstable")),
+    group(ArgGroup::new("unstable-hardlinks").args(["hardlinks"]).requires("unstable")),
+    group(ArgGroup::new("unstable-identity").args(["identity"]).requires("unstable")),
 )]
 pub(crate) struct ListCommand {
     #[arg(short, long, help = "Display extended file metadata as a table")]
@@ -77,8 +85,32 @@ pub(crate) struct ListCommand {
         help = "When used with the -l option, display complete time information for the entry, including month, day, hour, minute, second, and year"
     )]
     pub(crate) long_time: bool,
-    #[arg(long, help = "Display format")]
+    #[arg(
+        long,
+        help = "Display format (table, jsonl, json, tree, csv, tsv). json prints a single document with the totals of the listed entries"
+    )]
     format: Option<Format>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_column,
+        requires = "format",
+        help = "Comma separated columns to display in csv or tsv format"
+    )]
+    columns: Vec<String>,
+    #[arg(
+        long,
+        value_parser = parse_delimiter,
+        requires = "format",
+        help = "Field delimiter of csv or tsv format [default: ',' for csv, '\\t' for tsv]"
+    )]
+    delimiter: Option<u8>,
+    #[arg(
+        long,
+        requires = "format",
+        help = "Omit the header row of csv or tsv format"
+    )]
+    no_header: bool,
     #[arg(
         long,
         help = "Which timestamp field to list (modified, accessed, created)"
@@ -91,10 +123,64 @@ pub(crate) struct ListCommand {
     hide_control_chars: bool,
     #[arg(long, help = "Display type indicator by entry kinds")]
     classify: bool,
+    #[arg(
+        long,
+        help = "Display hardlink groups grouped by their target entry (unstable)"
+    )]
+    hardlinks: bool,
+    #[arg(
+        long,
+        overrides_with = "no_truncate",
+        help = "Truncate the names in the middle to fit the table in the width of the terminal (default)"
+    )]
+    truncate: bool,
+    #[arg(
+        long,
+        overrides_with = "truncate",
+        help = "Do not truncate the names in the table"
+    )]
+    no_truncate: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Width to fit the table in instead of the width of the terminal, also when the output is not a terminal"
+    )]
+    width: Option<usize>,
+    #[arg(
+        long,
+        help = "Append a summary of the listed entries with their count, total sizes and compression ratio. json already contains the totals"
+    )]
+    total: bool,
+    #[arg(
+        long,
+        help = "Display sizes in the table and the summary with the units KiB, MiB and GiB"
+    )]
+    human_readable: bool,
+    #[arg(
+        long,
+        requires = "hardlinks",
+        help = "Exit with an error if a hardlink whose target does not exist in the archive is found"
+    )]
+    check: bool,
     #[command(flatten)]
     pub(crate) password: PasswordArgs,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Read identities to decrypt entries encrypted to recipients from the given file. This option can be specified multiple times (unstable)"
+    )]
+    identity: Vec<PathBuf>,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Match the name patterns only against the entry names themselves, not against the entries under the directories they name"
+    )]
+    no_recursion: bool,
     #[command(flatten)]
     pub(crate) file: FileArgs,
+    #[cfg(feature = "http")]
+    #[command(flatten)]
+    pub(crate) http: HttpArgs,
     #[arg(long, action = clap::ArgAction::Help)]
     help: Option<bool>,
 }
@@ -110,7 +196,10 @@ impl Command for ListCommand {
 pub(crate) enum Format {
     Table,
     JsonL,
+    Json,
     Tree,
+    Csv,
+    Tsv,
 }
 
 impl FromStr for Format {
@@ -121,7 +210,10 @@ impl FromStr for Format {
         match s {
             "table" => Ok(Self::Table),
             "jsonl" => Ok(Self::JsonL),
+            "json" => Ok(Self::Json),
             "tree" => Ok(Self::Tree),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
             unknown => Err(format!("unknown value: {}", unknown)),
         }
     }
@@ -161,7 +253,10 @@ struct TableRow {
     entry_type: EntryType,
     xattrs: Vec<ExtendedAttribute>,
     acl: HashMap<chunk::AcePlatform, Vec<chunk::Ace>>,
+    platforms: Option<chunk::Platforms>,
     privates: Vec<RawChunk>,
+    /// Whether the entry is in a solid entry.
+    solid: bool,
 }
 
 struct Subject {
@@ -171,16 +266,16 @@ struct Subject {
 
 impl Subject {
     #[inline]
-    fn value(self, numeric: bool) -> String {
+    fn value(&self, numeric: bool) -> String {
         if numeric {
             self.id.to_string()
         } else {
-            self.name
+            self.name.clone()
         }
     }
 }
 
-impl<T> TryFrom<(&NormalEntry<T>, Option<&str>, Option<&SolidHeader>)> for TableRow
+impl<T> TryFrom<(&NormalEntry<T>, &ReadOptions, Option<&SolidHeader>)> for TableRow
 where
     T: AsRef<[u8]> + Clone,
     RawChunk<T>: Chunk,
@@ -189,11 +284,12 @@ where
     type Error = io::Error;
     #[inline]
     fn try_from(
-        (entry, password, solid): (&NormalEntry<T>, Option<&str>, Option<&SolidHeader>),
+        (entry, read_options, solid): (&NormalEntry<T>, &ReadOptions, Option<&SolidHeader>),
     ) -> Result<Self, Self::Error> {
         let header = entry.header();
         let metadata = entry.metadata();
         let acl = entry.acl()?;
+        let platforms = entry.platforms()?;
         Ok(Self {
             encryption: match solid.map_or_else(
                 || (header.encryption(), header.cipher_mode()),
@@ -231,14 +327,14 @@ where
                 DataKind::SymbolicLink => EntryType::SymbolicLink(
                     header.path().to_string(),
                     entry
-                        .reader(ReadOptions::with_password(password))
+                        .reader(read_options)
                         .and_then(io::read_to_string)
                         .unwrap_or_else(|_| "-".into()),
                 ),
                 DataKind::HardLink => EntryType::HardLink(
                     header.path().to_string(),
                     entry
-                        .reader(ReadOptions::with_password(password))
+                        .reader(read_options)
                         .and_then(io::read_to_string)
                         .unwrap_or_else(|_| "-".into()),
                 ),
@@ -247,18 +343,22 @@ where
             },
             xattrs: entry.xattrs().to_vec(),
             acl,
+            platforms,
             privates: entry
                 .extra_chunks()
                 .iter()
-                .filter(|it| it.ty() != chunk::faCe && it.ty() != chunk::faCl)
+                .filter(|it| ![chunk::faCe, chunk::faCl, chunk::fPLT].contains(&it.ty()))
                 .map(|it| (*it).clone().into())
                 .collect::<Vec<_>>(),
+            solid: solid.is_some(),
         })
     }
 }
 
 fn list_archive(args: ListCommand) -> io::Result<()> {
     let password = ask_password(args.password)?;
+    let password = password.as_deref().map(String::as_str);
+    let identities = read_identities(&args.identity)?;
     let options = ListOptions {
         long: args.long,
         header: args.header,
@@ -276,24 +376,57 @@ fn list_archive(args: ListCommand) -> io::Result<()> {
         hide_control_chars: args.hide_control_chars,
         classify: args.classify,
         format: args.format,
+        delimited: DelimitedOptions {
+            columns: args.columns,
+            delimiter: args.delimiter,
+            header: !args.no_header,
+        },
+        hardlinks: args.hardlinks,
+        check: args.check,
+        total: args.total,
+        human_readable: args.human_readable,
+        recursion: !args.no_recursion,
+        identities,
+        archive: args.file.archive.display().to_string(),
+        width: table_width(
+            !args.no_truncate,
+            args.width,
+            io::stdout().is_terminal(),
+            || terminal_size::terminal_size().map(|(width, _)| usize::from(width.0)),
+        ),
+    };
+    let archive = match ArchiveLocation::parse(&args.file.archive)? {
+        ArchiveLocation::Path(path) => path,
+        ArchiveLocation::Stdio => {
+            return run_list_archive(
+                StdinArchiveProvider::new(),
+                password,
+                &args.file.files,
+                options,
+            )
+        }
+        #[cfg(feature = "http")]
+        ArchiveLocation::Url(url) => {
+            return run_list_archive(
+                HttpArchiveProvider::new(&url, &args.http),
+                password,
+                &args.file.files,
+                options,
+            )
+        }
     };
     #[cfg(not(feature = "memmap"))]
     {
         run_list_archive(
-            PathArchiveProvider::new(&args.file.archive),
-            password.as_deref(),
+            PathArchiveProvider::new(&archive),
+            password,
             &args.file.files,
             options,
         )
     }
     #[cfg(feature = "memmap")]
     {
-        run_list_archive_mem(
-            &args.file.archive,
-            password.as_deref(),
-            &args.file.files,
-            options,
-        )
+        run_list_archive_mem(&archive, password, &args.file.files, options)
     }
 }
 
@@ -349,6 +482,60 @@ pub(crate) struct ListOptions {
     pub(crate) hide_control_chars: bool,
     pub(crate) classify: bool,
     pub(crate) format: Option<Format>,
+    pub(crate) delimited: DelimitedOptions,
+    pub(crate) hardlinks: bool,
+    pub(crate) check: bool,
+    /// Whether a summary of the listed entries is appended.
+    pub(crate) total: bool,
+    /// Whether the sizes are displayed with binary units, except in the delimited formats.
+    pub(crate) human_readable: bool,
+    /// Whether the name patterns also match the entries under the directories they name.
+    pub(crate) recursion: bool,
+    pub(crate) identities: Vec<Identity>,
+    /// Name of the archive printed by the json format.
+    pub(crate) archive: String,
+    /// Width to fit the table in by truncating the names, or `None` to not truncate them.
+    pub(crate) width: Option<usize>,
+}
+
+/// Resolves the width to fit the table in.
+///
+/// Output that is not a terminal is never truncated unless the width is given explicitly.
+fn table_width(
+    truncate: bool,
+    width: Option<usize>,
+    is_terminal: bool,
+    terminal_width: impl FnOnce() -> Option<usize>,
+) -> Option<usize> {
+    if !truncate {
+        None
+    } else if width.is_some() {
+        width
+    } else if is_terminal {
+        terminal_width()
+    } else {
+        None
+    }
+}
+
+/// Options of the delimited formats, csv and tsv.
+pub(crate) struct DelimitedOptions {
+    /// Names of the columns to write, or empty for [DEFAULT_COLUMNS].
+    pub(crate) columns: Vec<String>,
+    /// Field delimiter, or `None` for the default delimiter of the format.
+    pub(crate) delimiter: Option<u8>,
+    pub(crate) header: bool,
+}
+
+impl Default for DelimitedOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            columns: Vec::new(),
+            delimiter: None,
+            header: true,
+        }
+    }
 }
 
 pub(crate) fn run_list_archive(
@@ -357,27 +544,40 @@ pub(crate) fn run_list_archive(
     files: &[String],
     args: ListOptions,
 ) -> io::Result<()> {
-    let globs =
-        GlobPatterns::new(files).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs = GlobPatterns::with_recursion(files, args.recursion).map_err(usage_error)?;
 
+    let read_options = ReadOptions::builder()
+        .password(password)
+        .identities(args.identities.iter().cloned())
+        .build();
     let mut entries = Vec::new();
 
-    run_read_entries(archive_provider, |entry| {
-        match entry? {
-            ReadEntry::Solid(solid) if args.solid => {
-                for entry in solid.entries(password)? {
-                    entries.push((&entry?, password, Some(solid.header())).try_into()?)
+    if args.solid {
+        run_read_entries(archive_provider, |entry| {
+            match entry? {
+                ReadEntry::Solid(solid) => {
+                    for entry in solid.entries(password)? {
+                        entries.push((&entry?, &read_options, Some(solid.header())).try_into()?)
+                    }
                 }
+                ReadEntry::Normal(item) => entries.push((&item, &read_options, None).try_into()?),
             }
-            ReadEntry::Solid(_) => {
-                log::warn!("This archive contain solid mode entry. if you need to show it use --solid option.");
-            }
-            ReadEntry::Normal(item) => entries.push((&item, password, None).try_into()?),
+            Ok(())
+        })?;
+    } else {
+        // NOTE: Listing needs only the headers and metadata, so the data of file entries is skipped.
+        let solid_entries = run_read_entry_summaries(archive_provider, |summary| {
+            entries.push((summary?.entry(), &read_options, None).try_into()?);
+            Ok(())
+        })?;
+        if solid_entries != 0 {
+            log::warn!(
+                "This archive contain solid mode entry. if you need to show it use --solid option."
+            );
         }
-        Ok(())
-    })?;
-    print_entries(entries, globs, args);
-    Ok(())
+    }
+    print_entries(entries, &globs, args)?;
+    globs.ensure_all_matched()
 }
 
 #[cfg(feature = "memmap")]
@@ -387,49 +587,178 @@ pub(crate) fn run_list_archive_mem(
     files: &[String],
     args: ListOptions,
 ) -> io::Result<()> {
-    let globs =
-        GlobPatterns::new(files).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs = GlobPatterns::with_recursion(files, args.recursion).map_err(usage_error)?;
 
+    let read_options = ReadOptions::builder()
+        .password(password)
+        .identities(args.identities.iter().cloned())
+        .build();
     let mut entries = Vec::new();
 
     run_read_entries_mem(archive_provider, |entry| {
         match entry? {
             ReadEntry::Solid(solid) if args.solid => {
                 for entry in solid.entries(password)? {
-                    entries.push((&entry?, password, Some(solid.header())).try_into()?);
+                    entries.push((&entry?, &read_options, Some(solid.header())).try_into()?);
                 }
             }
             ReadEntry::Solid(_) => {
                 log::warn!("This archive contain solid mode entry. if you need to show it use --solid option.");
             }
-            ReadEntry::Normal(item) => entries.push((&item, password, None).try_into()?),
+            ReadEntry::Normal(item) => entries.push((&item, &read_options, None).try_into()?),
         }
         Ok(())
     })?;
-    print_entries(entries, globs, args);
-    Ok(())
+    print_entries(entries, &globs, args)?;
+    globs.ensure_all_matched()
 }
 
-fn print_entries(entries: Vec<TableRow>, globs: GlobPatterns, options: ListOptions) {
-    if entries.is_empty() {
-        return;
+fn print_entries(
+    entries: Vec<TableRow>,
+    globs: &GlobPatterns,
+    options: ListOptions,
+) -> io::Result<()> {
+    let ambiguous = entries
+        .iter()
+        .filter(|r| utils::has_ambiguous_separator(r.entry_type.name()))
+        .count();
+    if ambiguous != 0 {
+        log::warn!("{ambiguous} entry names contain backslashes or duplicate slashes, which are extracted verbatim unless --normalize-paths is given");
+    }
+    if options.hardlinks {
+        return hardlink_entries(&entries, globs, options);
+    }
+    // NOTE: The json format prints a document even for an empty archive.
+    if entries.is_empty() && options.format != Some(Format::Json) {
+        if options.total {
+            print_total(&Total::default(), options.format, options.human_readable)?;
+        }
+        return Ok(());
     }
 
     let entries = if globs.is_empty() {
         entries
-    } else {
+    } else if rayon::current_thread_index().is_some() {
         entries
             .into_par_iter()
             .filter(|r| globs.matches_any(r.entry_type.name()))
             .collect()
+    } else {
+        entries
+            .into_iter()
+            .filter(|r| globs.matches_any(r.entry_type.name()))
+            .collect()
     };
+    // NOTE: The options are consumed by the formats, so the summary is taken beforehand.
+    let total = options.total.then(|| Total::of(&entries));
+    let (format, human_readable) = (options.format, options.human_readable);
     match options.format {
         Some(Format::JsonL) => json_line_entries(entries.into_iter()),
+        Some(Format::Json) => json_entries(entries, options.archive)?,
         Some(Format::Table) => detail_list_entries(entries.into_iter(), options),
         Some(Format::Tree) => tree_entries(entries, options),
+        Some(Format::Csv) => {
+            delimited_entries(entries.into_iter(), b',', &options, io::stdout().lock())?
+        }
+        Some(Format::Tsv) => {
+            delimited_entries(entries.into_iter(), b'\t', &options, io::stdout().lock())?
+        }
         None if options.long => detail_list_entries(entries.into_iter(), options),
         None => simple_list_entries(entries.into_iter(), options),
     }
+    if let Some(total) = total {
+        print_total(&total, format, human_readable)?;
+    }
+    Ok(())
+}
+
+/// Totals of the listed entries.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+struct Total {
+    entry_count: usize,
+    raw_size: u128,
+    compressed_size: usize,
+}
+
+impl Total {
+    fn of(entries: &[TableRow]) -> Self {
+        Self {
+            entry_count: entries.len(),
+            raw_size: entries.iter().filter_map(|it| it.raw_size).sum(),
+            compressed_size: entries.iter().map(|it| it.compressed_size).sum(),
+        }
+    }
+
+    /// Percentage of the compressed size to the raw size, or `None` if there is no raw data.
+    fn ratio(&self) -> Option<f64> {
+        (self.raw_size != 0).then(|| self.compressed_size as f64 / self.raw_size as f64 * 100.0)
+    }
+}
+
+/// Summary printed by `--total` in the json lines format.
+#[derive(Serialize, Deserialize, Debug)]
+struct TotalInfo {
+    entry_count: usize,
+    total_raw_size: u128,
+    total_compressed_size: usize,
+    ratio: Option<f64>,
+}
+
+/// Prints the summary line of `--total` after the entries.
+///
+/// The json format is left as is since its document already contains the totals.
+fn print_total(total: &Total, format: Option<Format>, human_readable: bool) -> io::Result<()> {
+    match format {
+        Some(Format::Json) => Ok(()),
+        Some(Format::JsonL) => {
+            let info = TotalInfo {
+                entry_count: total.entry_count,
+                total_raw_size: total.raw_size,
+                total_compressed_size: total.compressed_size,
+                ratio: total.ratio(),
+            };
+            let mut stdout = io::stdout().lock();
+            serde_json::to_writer(&mut stdout, &info)?;
+            stdout.write_all(b"\n")
+        }
+        _ => {
+            println!("{}", total_line(total, human_readable));
+            Ok(())
+        }
+    }
+}
+
+fn total_line(total: &Total, human_readable: bool) -> String {
+    format!(
+        "total: entries {}, raw size {}, compressed size {}, ratio {}",
+        total.entry_count,
+        size_string(total.raw_size, human_readable),
+        size_string(total.compressed_size as u128, human_readable),
+        total
+            .ratio()
+            .map_or_else(|| "-".into(), |it| format!("{it:.1}%")),
+    )
+}
+
+/// Formats `size` in bytes, or with the largest binary unit below it when `human_readable` is set.
+fn size_string(size: u128, human_readable: bool) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+    if !human_readable {
+        return size.to_string();
+    }
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut value = size as f64 / 1024.0;
+    let mut unit = UNITS[0];
+    for next in UNITS.into_iter().skip(1) {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = next;
+    }
+    format!("{value:.1} {unit}")
 }
 
 fn simple_list_entries(entries: impl Iterator<Item = TableRow>, options: ListOptions) {
@@ -447,7 +776,7 @@ fn simple_list_entries(entries: impl Iterator<Item = TableRow>, options: ListOpt
         if options.hide_control_chars {
             println!("{}", hide_control_chars(&path))
         } else {
-            println!("{}", path)
+            println!("{}", escape_newlines(&path))
         }
     }
 }
@@ -468,15 +797,14 @@ fn detail_list_entries(entries: impl Iterator<Item = TableRow>, options: ListOpt
     ];
     let mut acl_rows = Vec::new();
     let mut xattr_rows = Vec::new();
-    let mut builder = TableBuilder::new();
-    builder.set_empty(String::new());
+    let mut records = Vec::new();
     if options.header {
-        builder.push_record(header);
+        records.push(header.map(String::from).into());
     }
     for content in entries {
         let has_acl = !content.acl.is_empty();
         let has_xattr = !content.xattrs.is_empty();
-        builder.push_record([
+        records.push(vec![
             content.encryption,
             content.compression,
             paint_permission(
@@ -485,10 +813,11 @@ fn detail_list_entries(entries: impl Iterator<Item = TableRow>, options: ListOpt
                 has_xattr,
                 has_acl,
             ),
-            content
-                .raw_size
-                .map_or_else(|| "-".into(), |size| size.to_string()),
-            content.compressed_size.to_string(),
+            content.raw_size.map_or_else(
+                || "-".into(),
+                |size| size_string(size, options.human_readable),
+            ),
+            size_string(content.compressed_size as u128, options.human_readable),
             content
                 .user
                 .map_or_else(|| "-".into(), |it| it.value(options.numeric_owner)),
@@ -517,10 +846,17 @@ fn detail_list_entries(entries: impl Iterator<Item = TableRow>, options: ListOpt
                 if options.hide_control_chars {
                     hide_control_chars(&name)
                 } else {
-                    name
+                    escape_newlines(&name)
                 }
             },
         ]);
+        if let Some(platforms) = &content.platforms {
+            records.push(vec![
+                String::new(),
+                String::new(),
+                format!("platforms:{platforms}"),
+            ]);
+        }
         if options.show_acl {
             let acl = content.acl.into_iter().flat_map(|(platform, ace)| {
                 ace.into_iter().map(move |it| chunk::AceWithPlatform {
@@ -529,24 +865,24 @@ fn detail_list_entries(entries: impl Iterator<Item = TableRow>, options: ListOpt
                 })
             });
             for a in acl {
-                builder.push_record([String::new(), String::new(), a.to_string()]);
-                acl_rows.push(builder.count_records());
+                records.push(vec![String::new(), String::new(), a.to_string()]);
+                acl_rows.push(records.len());
             }
         }
         if options.show_xattr {
             for x in &content.xattrs {
-                builder.push_record([
+                records.push(vec![
                     String::new(),
                     String::new(),
                     x.name().into(),
                     x.value().len().to_string(),
                 ]);
-                xattr_rows.push(builder.count_records());
+                xattr_rows.push(records.len());
             }
         }
         if options.show_private {
             for c in &content.privates {
-                builder.push_record([
+                records.push(vec![
                     String::new(),
                     String::new(),
                     format!("chunk:{}", c.ty()),
@@ -555,6 +891,14 @@ fn detail_list_entries(entries: impl Iterator<Item = TableRow>, options: ListOpt
             }
         }
     }
+    if let Some(width) = options.width {
+        truncate_names(&mut records, width);
+    }
+    let mut builder = TableBuilder::new();
+    builder.set_empty(String::new());
+    for record in records {
+        builder.push_record(record);
+    }
     let mut table = builder.build();
     table
         .with(TableStyle::empty())
@@ -583,6 +927,61 @@ fn detail_list_entries(entries: impl Iterator<Item = TableRow>, options: ListOpt
     println!("{}", table);
 }
 
+/// Index of the name column in the table format.
+const NAME_COLUMN: usize = 8;
+
+/// Truncates the names of `records` in the middle so that the rows fit in `width`,
+/// never truncating the other columns.
+fn truncate_names(records: &mut [Vec<String>], width: usize) {
+    let mut widths = [0; NAME_COLUMN];
+    for record in records.iter() {
+        for (width, cell) in widths.iter_mut().zip(record) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+    // NOTE: Each column is followed by a padding of one space.
+    let others = widths.iter().map(|it| it + 1).sum::<usize>();
+    let max = width.saturating_sub(others + 1);
+    for record in records {
+        if let Some(name) = record.get_mut(NAME_COLUMN) {
+            if let Cow::Owned(truncated) = middle_ellipsis(name, max) {
+                *name = truncated;
+            }
+        }
+    }
+}
+
+/// Number of the characters displayed for `s`, ignoring the ANSI escape sequences.
+fn display_width(s: &str) -> usize {
+    let mut chars = s.chars();
+    let mut width = 0;
+    while let Some(c) = chars.next() {
+        if c == '\x1B' {
+            if chars.next() == Some('[') {
+                chars.by_ref().find(|c| ('@'..='~').contains(c));
+            }
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+/// Replaces the middle of `s` with an ellipsis so that it is at most `max` characters long.
+fn middle_ellipsis(s: &str, max: usize) -> Cow<'_, str> {
+    let len = s.chars().count();
+    if len <= max {
+        return Cow::Borrowed(s);
+    }
+    let kept = max.saturating_sub(1);
+    let tail = kept / 2;
+    let head = kept - tail;
+    let mut truncated = s.chars().take(head).collect::<String>();
+    truncated.push('…');
+    truncated.extend(s.chars().skip(len - tail));
+    Cow::Owned(truncated)
+}
+
 const DURATION_SIX_MONTH: Duration = Duration::from_secs(60 * 60 * 24 * 30 * 6);
 
 fn within_six_months(now: SystemTime, x: SystemTime) -> bool {
@@ -618,6 +1017,10 @@ fn hide_control_chars(s: &str) -> String {
         .collect()
 }
 
+fn escape_newlines(s: &str) -> String {
+    s.replace('\r', "\\r").replace('\n', "\\n")
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 struct StyledDisplay<'s, T> {
     style: &'s Style,
@@ -665,19 +1068,28 @@ fn paint_permission(kind: &EntryType, permission: u16, has_xattr: bool, has_acl:
             STYLE_HYPHEN.paint('-')
         }
     };
+    let paint_exec = |bit: u16, special_bit: u16, special: char| match (
+        permission & bit != 0,
+        permission & special_bit != 0,
+    ) {
+        (true, true) => STYLE_EXEC.paint(special),
+        (false, true) => STYLE_EXEC.paint(special.to_ascii_uppercase()),
+        (true, false) => STYLE_EXEC.paint('x'),
+        (false, false) => STYLE_HYPHEN.paint('-'),
+    };
 
     format!(
         "{}{}{}{}{}{}{}{}{}{}{}",
         kind_paint(kind),
         paint(&STYLE_READ, 'r', 0b100000000),  // owner_read
         paint(&STYLE_WRITE, 'w', 0b010000000), // owner_write
-        paint(&STYLE_EXEC, 'x', 0b001000000),  // owner_exec
+        paint_exec(0b001000000, 0o4000, 's'),  // owner_exec, setuid
         paint(&STYLE_READ, 'r', 0b000100000),  // group_read
         paint(&STYLE_WRITE, 'w', 0b000010000), // group_write
-        paint(&STYLE_EXEC, 'x', 0b000001000),  // group_exec
+        paint_exec(0b000001000, 0o2000, 's'),  // group_exec, setgid
         paint(&STYLE_READ, 'r', 0b000000100),  // other_read
         paint(&STYLE_WRITE, 'w', 0b000000010), // other_write
-        paint(&STYLE_EXEC, 'x', 0b000000001),  // other_exec
+        paint_exec(0b000000001, 0o1000, 't'),  // other_exec, sticky
         STYLE_HYPHEN.paint(if has_xattr {
             '@'
         } else if has_acl {
@@ -705,19 +1117,28 @@ fn permission_string(kind: &EntryType, permission: u16, has_xattr: bool, has_acl
             '-'
         }
     }
+    #[inline(always)]
+    fn paint_exec(permission: u16, bit: u16, special_bit: u16, special: char) -> char {
+        match (permission & bit != 0, permission & special_bit != 0) {
+            (true, true) => special,
+            (false, true) => special.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        }
+    }
 
     format!(
         "{}{}{}{}{}{}{}{}{}{}{}",
         kind_char(kind),
         paint(permission, 'r', 0b100000000), // owner_read
         paint(permission, 'w', 0b010000000), // owner_write
-        paint(permission, 'x', 0b001000000), // owner_exec
+        paint_exec(permission, 0b001000000, 0o4000, 's'), // owner_exec, setuid
         paint(permission, 'r', 0b000100000), // group_read
         paint(permission, 'w', 0b000010000), // group_write
-        paint(permission, 'x', 0b000001000), // group_exec
+        paint_exec(permission, 0b000001000, 0o2000, 's'), // group_exec, setgid
         paint(permission, 'r', 0b000000100), // other_read
         paint(permission, 'w', 0b000000010), // other_write
-        paint(permission, 'x', 0b000000001), // other_exec
+        paint_exec(permission, 0b000000001, 0o1000, 't'), // other_exec, sticky
         if has_xattr {
             '@'
         } else if has_acl {
@@ -743,6 +1164,63 @@ struct FileInfo {
     accessed: String,
     acl: Vec<AclEntry>,
     xattr: Vec<XAttr>,
+    platforms: Vec<String>,
+}
+
+impl From<TableRow> for FileInfo {
+    #[inline]
+    fn from(it: TableRow) -> Self {
+        Self {
+            filename: it.entry_type.name().into(),
+            permissions: permission_string(
+                &it.entry_type,
+                it.permission_mode,
+                !it.xattrs.is_empty(),
+                !it.acl.is_empty(),
+            ),
+            owner: it.user.map_or_else(String::new, |it| it.name),
+            group: it.group.map_or_else(String::new, |it| it.name),
+            raw_size: it.raw_size.unwrap_or_default(),
+            size: it.compressed_size,
+            encryption: it.encryption,
+            compression: it.compression,
+            created: datetime(TimeFormat::Long, it.created),
+            modified: datetime(TimeFormat::Long, it.modified),
+            accessed: datetime(TimeFormat::Long, it.accessed),
+            acl: it
+                .acl
+                .into_iter()
+                .map(|(platform, ace)| AclEntry {
+                    platform: platform.to_string(),
+                    entries: ace.into_iter().map(|it| it.to_string()).collect(),
+                })
+                .collect(),
+            xattr: it
+                .xattrs
+                .into_iter()
+                .map(|x| XAttr {
+                    key: x.name().into(),
+                    value: base64::engine::general_purpose::STANDARD.encode(x.value()),
+                })
+                .collect(),
+            platforms: it
+                .platforms
+                .map(|it| it.iter().map(String::from).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Listed entries of an archive with their totals, printed by the json format.
+#[derive(Serialize, Deserialize, Debug)]
+struct ArchiveInfo {
+    archive: String,
+    entries: Vec<FileInfo>,
+    total_raw_size: u128,
+    total_compressed_size: usize,
+    entry_count: usize,
+    /// Number of the listed entries in solid entries.
+    solid_entry_count: usize,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -759,40 +1237,7 @@ struct XAttr {
 
 fn json_line_entries(entries: impl Iterator<Item = TableRow>) {
     let mut stdout = io::stdout().lock();
-    for line in entries.map(|it| FileInfo {
-        filename: it.entry_type.name().into(),
-        permissions: permission_string(
-            &it.entry_type,
-            it.permission_mode,
-            !it.xattrs.is_empty(),
-            !it.acl.is_empty(),
-        ),
-        owner: it.user.map_or_else(String::new, |it| it.name),
-        group: it.group.map_or_else(String::new, |it| it.name),
-        raw_size: it.raw_size.unwrap_or_default(),
-        size: it.compressed_size,
-        encryption: it.encryption,
-        compression: it.compression,
-        created: datetime(TimeFormat::Long, it.created),
-        modified: datetime(TimeFormat::Long, it.modified),
-        accessed: datetime(TimeFormat::Long, it.accessed),
-        acl: it
-            .acl
-            .into_par_iter()
-            .map(|(platform, ace)| AclEntry {
-                platform: platform.to_string(),
-                entries: ace.into_par_iter().map(|it| it.to_string()).collect(),
-            })
-            .collect(),
-        xattr: it
-            .xattrs
-            .into_par_iter()
-            .map(|x| XAttr {
-                key: x.name().into(),
-                value: base64::engine::general_purpose::STANDARD.encode(x.value()),
-            })
-            .collect(),
-    }) {
+    for line in entries.map(FileInfo::from) {
         match serde_json::to_writer(&mut stdout, &line) {
             Ok(_) => stdout.write_all(b"\n").expect(""),
             Err(e) => log::info!("{}", e),
@@ -800,6 +1245,275 @@ fn json_line_entries(entries: impl Iterator<Item = TableRow>) {
     }
 }
 
+fn json_entries(entries: Vec<TableRow>, archive: String) -> io::Result<()> {
+    let info = ArchiveInfo {
+        archive,
+        total_raw_size: entries.iter().filter_map(|it| it.raw_size).sum(),
+        total_compressed_size: entries.iter().map(|it| it.compressed_size).sum(),
+        entry_count: entries.len(),
+        solid_entry_count: entries.iter().filter(|it| it.solid).count(),
+        entries: entries.into_iter().map(FileInfo::from).collect(),
+    };
+    let mut stdout = io::stdout().lock();
+    serde_json::to_writer(&mut stdout, &info)?;
+    stdout.write_all(b"\n")
+}
+
+/// Extracts the value of a column from a row.
+type ColumnValue = fn(&TableRow, &ListOptions) -> String;
+
+/// Columns available in the delimited formats.
+const COLUMNS: &[(&str, ColumnValue)] = &[
+    ("name", |row, _| row.entry_type.name().into()),
+    ("type", |row, _| {
+        match row.entry_type {
+            EntryType::File(_) => "file",
+            EntryType::Directory(_) => "directory",
+            EntryType::SymbolicLink(_, _) => "symlink",
+            EntryType::HardLink(_, _) => "hardlink",
+        }
+        .into()
+    }),
+    ("link_target", |row, _| match &row.entry_type {
+        EntryType::SymbolicLink(_, link_to) | EntryType::HardLink(_, link_to) => link_to.clone(),
+        EntryType::File(_) | EntryType::Directory(_) => String::new(),
+    }),
+    ("permissions", |row, _| {
+        permission_string(
+            &row.entry_type,
+            row.permission_mode,
+            !row.xattrs.is_empty(),
+            !row.acl.is_empty(),
+        )
+    }),
+    ("user", |row, options| {
+        row.user
+            .as_ref()
+            .map_or_else(String::new, |it| it.value(options.numeric_owner))
+    }),
+    ("group", |row, options| {
+        row.group
+            .as_ref()
+            .map_or_else(String::new, |it| it.value(options.numeric_owner))
+    }),
+    ("raw_size", |row, _| {
+        row.raw_size.map_or_else(String::new, |it| it.to_string())
+    }),
+    ("compressed_size", |row, _| row.compressed_size.to_string()),
+    ("encryption", |row, _| row.encryption.clone()),
+    ("compression", |row, _| row.compression.clone()),
+    ("created", |row, _| datetime(TimeFormat::Long, row.created)),
+    ("modified", |row, _| {
+        datetime(TimeFormat::Long, row.modified)
+    }),
+    ("accessed", |row, _| {
+        datetime(TimeFormat::Long, row.accessed)
+    }),
+];
+
+/// Columns written when `--columns` is not given.
+const DEFAULT_COLUMNS: &[&str] = &[
+    "name",
+    "permissions",
+    "user",
+    "group",
+    "raw_size",
+    "compressed_size",
+    "encryption",
+    "compression",
+    "created",
+    "modified",
+    "accessed",
+];
+
+fn find_column(name: &str) -> Result<(&'static str, ColumnValue), String> {
+    COLUMNS
+        .iter()
+        .find(|(it, _)| *it == name)
+        .copied()
+        .ok_or_else(|| {
+            format!(
+                "unknown column `{}`, available columns are: {}",
+                name,
+                COLUMNS
+                    .iter()
+                    .map(|(it, _)| *it)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+fn parse_column(name: &str) -> Result<String, String> {
+    find_column(name).map(|_| name.into())
+}
+
+fn parse_delimiter(s: &str) -> Result<u8, String> {
+    match s.as_bytes() {
+        [c] if c.is_ascii() => Ok(*c),
+        b"\\t" => Ok(b'\t'),
+        _ => Err("delimiter must be a single ASCII character".into()),
+    }
+}
+
+fn delimited_entries(
+    entries: impl Iterator<Item = TableRow>,
+    default_delimiter: u8,
+    options: &ListOptions,
+    out: impl Write,
+) -> io::Result<()> {
+    let delimited = &options.delimited;
+    let columns = if delimited.columns.is_empty() {
+        DEFAULT_COLUMNS
+            .iter()
+            .map(|it| find_column(it))
+            .collect::<Result<Vec<_>, _>>()
+    } else {
+        delimited
+            .columns
+            .iter()
+            .map(|it| find_column(it))
+            .collect::<Result<Vec<_>, _>>()
+    }
+    .map_err(usage_error)?;
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimited.delimiter.unwrap_or(default_delimiter))
+        .from_writer(out);
+    if delimited.header {
+        writer.write_record(columns.iter().map(|(name, _)| name))?;
+    }
+    for row in entries {
+        writer.write_record(columns.iter().map(|(_, value)| value(&row, options)))?;
+    }
+    writer.flush()
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct HardLinkGroup {
+    target: String,
+    size: u128,
+    links: Vec<String>,
+    dangling: bool,
+}
+
+fn hardlink_groups(entries: &[TableRow]) -> Vec<HardLinkGroup> {
+    let mut groups = IndexMap::new();
+    for entry in entries {
+        if let EntryType::File(name) = &entry.entry_type {
+            groups
+                .entry(PathBuf::from(name))
+                .or_insert_with(|| HardLinkGroup {
+                    target: name.clone(),
+                    size: entry.raw_size.unwrap_or_default(),
+                    links: Vec::new(),
+                    dangling: false,
+                });
+        }
+    }
+    let mut dangling = IndexMap::new();
+    for entry in entries {
+        if let EntryType::HardLink(name, reference) = &entry.entry_type {
+            let target = Path::new(name)
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .join(reference)
+                .normalize();
+            let group = match groups.get_mut(&target) {
+                Some(group) => group,
+                None => dangling
+                    .entry(target)
+                    .or_insert_with_key(|target| HardLinkGroup {
+                        target: target.to_string_lossy().into(),
+                        size: 0,
+                        links: Vec::new(),
+                        dangling: true,
+                    }),
+            };
+            group.links.push(name.clone());
+        }
+    }
+    groups
+        .into_values()
+        .filter(|group| !group.links.is_empty())
+        .chain(dangling.into_values())
+        .collect()
+}
+
+fn hardlink_entries(
+    entries: &[TableRow],
+    globs: &GlobPatterns,
+    options: ListOptions,
+) -> io::Result<()> {
+    let groups = hardlink_groups(entries)
+        .into_iter()
+        .filter(|group| {
+            // NOTE: Match every path without short-circuit to record the matched patterns.
+            globs.is_empty()
+                || group
+                    .links
+                    .iter()
+                    .fold(globs.matches_any(&group.target), |matched, it| {
+                        globs.matches_any(it) | matched
+                    })
+        })
+        .collect::<Vec<_>>();
+    let mut stdout = io::stdout().lock();
+    match options.format {
+        Some(Format::JsonL) => {
+            for group in &groups {
+                serde_json::to_writer(&mut stdout, group)?;
+                stdout.write_all(b"\n")?;
+            }
+        }
+        Some(Format::Json) => {
+            serde_json::to_writer(&mut stdout, &groups)?;
+            stdout.write_all(b"\n")?;
+        }
+        _ => {
+            for group in &groups {
+                if group.dangling {
+                    writeln!(
+                        stdout,
+                        "{} (dangling, {} links)",
+                        group.target,
+                        group.links.len()
+                    )?;
+                } else {
+                    writeln!(
+                        stdout,
+                        "{} ({} links, {} bytes)",
+                        group.target,
+                        group.links.len(),
+                        group.size
+                    )?;
+                }
+                for link in &group.links {
+                    writeln!(stdout, "  {}", link)?;
+                }
+            }
+            writeln!(
+                stdout,
+                "{} groups, {} links, {} bytes unique",
+                groups.len(),
+                groups.iter().map(|it| it.links.len()).sum::<usize>(),
+                groups
+                    .iter()
+                    .filter(|it| !it.dangling)
+                    .map(|it| it.size)
+                    .sum::<u128>()
+            )?;
+        }
+    }
+    let dangling = groups.iter().filter(|it| it.dangling).count();
+    if options.check && dangling != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} dangling hardlink groups found", dangling),
+        ));
+    }
+    Ok(())
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 struct TreeEntry<'s> {
     name: &'s str,
@@ -815,7 +1529,7 @@ impl<'s> TreeEntry<'s> {
 
 fn tree_entries(entries: Vec<TableRow>, options: ListOptions) {
     let entries = entries
-        .into_par_iter()
+        .into_iter()
         .map(|it| match it.entry_type {
             EntryType::File(name) => (name, DataKind::File),
             EntryType::Directory(name) => (name, DataKind::Directory),
@@ -824,7 +1538,7 @@ fn tree_entries(entries: Vec<TableRow>, options: ListOptions) {
         })
         .collect::<Vec<_>>();
     let entries = entries
-        .par_iter()
+        .iter()
         .map(|(name, kind)| (name.as_str(), *kind))
         .collect::<Vec<_>>();
     let tree = build_tree(&entries);
@@ -894,3 +1608,208 @@ fn display_tree(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, raw_size: u128) -> TableRow {
+        TableRow {
+            encryption: "-".into(),
+            compression: "zstd".into(),
+            permission_mode: 0o644,
+            raw_size: Some(raw_size),
+            compressed_size: 10,
+            user: None,
+            group: None,
+            created: None,
+            modified: None,
+            accessed: None,
+            entry_type: EntryType::File(name.into()),
+            xattrs: Vec::new(),
+            acl: HashMap::new(),
+            platforms: None,
+            privates: Vec::new(),
+            solid: false,
+        }
+    }
+
+    fn options(columns: &[&str], delimiter: Option<u8>, header: bool) -> ListOptions {
+        ListOptions {
+            long: false,
+            header: false,
+            solid: false,
+            show_xattr: false,
+            show_acl: false,
+            show_private: false,
+            time_format: TimeFormat::Long,
+            time_field: TimeField::default(),
+            numeric_owner: false,
+            hide_control_chars: false,
+            classify: false,
+            format: Some(Format::Csv),
+            delimited: DelimitedOptions {
+                columns: columns.iter().map(|it| it.to_string()).collect(),
+                delimiter,
+                header,
+            },
+            hardlinks: false,
+            check: false,
+            total: false,
+            human_readable: false,
+            recursion: true,
+            identities: Vec::new(),
+            archive: String::new(),
+            width: None,
+        }
+    }
+
+    #[test]
+    fn truncate_names_in_the_middle() {
+        let mut records = vec![
+            [
+                "-",
+                "zstd",
+                "\x1B[34m.rw-r--r--\x1B[0m",
+                "1",
+                "10",
+                "-",
+                "-",
+                "-",
+                "very/long/path/to/file.txt",
+            ]
+            .map(String::from)
+            .to_vec(),
+            ["", "", "user.name", "5"].map(String::from).to_vec(),
+        ];
+        // NOTE: The other columns take 1 + 4 + 10 + 1 + 2 + 1 + 1 + 1 + 8 paddings = 29 characters.
+        truncate_names(&mut records, 29 + 20 + 1);
+        assert_eq!(records[0][8], "very/long/…/file.txt");
+        assert_eq!(records[0][2], "\x1B[34m.rw-r--r--\x1B[0m");
+        assert_eq!(records[1], ["", "", "user.name", "5"]);
+
+        truncate_names(&mut records, 80);
+        assert_eq!(records[0][8], "very/long/…/file.txt");
+    }
+
+    #[test]
+    fn middle_ellipsis_keeps_short_names() {
+        assert_eq!(middle_ellipsis("file.txt", 8), "file.txt");
+        assert_eq!(middle_ellipsis("file.txt", 7), "fil…txt");
+        assert_eq!(middle_ellipsis("file.txt", 1), "…");
+        assert_eq!(middle_ellipsis("file.txt", 0), "…");
+    }
+
+    #[test]
+    fn table_width_of_pipes() {
+        let terminal = || Some(80);
+        assert_eq!(table_width(true, None, true, terminal), Some(80));
+        assert_eq!(table_width(true, Some(40), true, terminal), Some(40));
+        assert_eq!(table_width(false, Some(40), true, terminal), None);
+        // NOTE: Output that is not a terminal keeps the full names unless the width is explicit.
+        assert_eq!(table_width(true, None, false, terminal), None);
+        assert_eq!(table_width(true, Some(40), false, terminal), Some(40));
+    }
+
+    fn write_delimited(options: &ListOptions) -> String {
+        let mut out = Vec::new();
+        let rows = [row("a.txt", 1), row("b,c.txt", 2)];
+        delimited_entries(rows.into_iter(), b',', options, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn delimited_default_columns() {
+        let out = write_delimited(&options(&[], None, true));
+        assert_eq!(
+            out.lines().next(),
+            Some("name,permissions,user,group,raw_size,compressed_size,encryption,compression,created,modified,accessed")
+        );
+    }
+
+    #[test]
+    fn delimited_custom_column_order() {
+        let out = write_delimited(&options(&["raw_size", "name"], None, true));
+        assert_eq!(out, "raw_size,name\n1,a.txt\n2,\"b,c.txt\"\n");
+    }
+
+    #[test]
+    fn delimited_semicolon_delimiter() {
+        let out = write_delimited(&options(&["name", "raw_size"], Some(b';'), true));
+        assert_eq!(out, "name;raw_size\na.txt;1\nb,c.txt;2\n");
+    }
+
+    #[test]
+    fn delimited_no_header() {
+        let out = write_delimited(&options(&["name"], None, false));
+        assert_eq!(out, "a.txt\n\"b,c.txt\"\n");
+    }
+
+    #[test]
+    fn unknown_column() {
+        let err = parse_column("size").unwrap_err();
+        assert!(err.contains("unknown column `size`"));
+        let names = COLUMNS.iter().map(|(it, _)| *it).collect::<Vec<_>>();
+        assert!(err.contains(&names.join(", ")));
+    }
+
+    #[test]
+    fn delimiter_value() {
+        assert_eq!(parse_delimiter(";"), Ok(b';'));
+        assert_eq!(parse_delimiter("\\t"), Ok(b'\t'));
+        assert!(parse_delimiter(";;").is_err());
+        assert!(parse_delimiter("あ").is_err());
+    }
+
+    #[test]
+    fn human_readable_size() {
+        assert_eq!(size_string(1536, false), "1536");
+        assert_eq!(size_string(1023, true), "1023 B");
+        assert_eq!(size_string(1536, true), "1.5 KiB");
+        assert_eq!(size_string(5 * 1024 * 1024, true), "5.0 MiB");
+        assert_eq!(size_string(3 * 1024 * 1024 * 1024, true), "3.0 GiB");
+    }
+
+    #[test]
+    fn total_summary() {
+        let total = Total::of(&[row("a.txt", 30), row("b.txt", 2018)]);
+        assert_eq!(
+            total_line(&total, false),
+            "total: entries 2, raw size 2048, compressed size 20, ratio 1.0%"
+        );
+        assert_eq!(
+            total_line(&total, true),
+            "total: entries 2, raw size 2.0 KiB, compressed size 20 B, ratio 1.0%"
+        );
+        assert_eq!(
+            total_line(&Total::default(), false),
+            "total: entries 0, raw size 0, compressed size 0, ratio -"
+        );
+    }
+
+    #[test]
+    fn permission_string_special_bits() {
+        let file = EntryType::File("a".into());
+        let dir = EntryType::Directory("d".into());
+        assert_eq!(permission_string(&file, 0o755, false, false), ".rwxr-xr-x ");
+        assert_eq!(
+            permission_string(&file, 0o4755, false, false),
+            ".rwsr-xr-x "
+        );
+        assert_eq!(
+            permission_string(&file, 0o4644, false, false),
+            ".rwSr--r-- "
+        );
+        assert_eq!(
+            permission_string(&file, 0o2755, false, false),
+            ".rwxr-sr-x "
+        );
+        assert_eq!(
+            permission_string(&file, 0o2745, false, false),
+            ".rwxr-Sr-x "
+        );
+        assert_eq!(permission_string(&dir, 0o1777, false, false), "drwxrwxrwt ");
+        assert_eq!(permission_string(&dir, 0o1776, false, false), "drwxrwxrwT ");
+        assert_eq!(permission_string(&dir, 0o7000, false, false), "d--S--S--T ");
+    }
+}
@@ -1,5 +1,7 @@
 use crate::{
-    cli::{PasswordArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs},
+    cli::{
+        PasswordArgs, ReplaceArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs,
+    },
     command::{
         ask_password,
         commons::{run_transform_entry, TransformStrategyKeepSolid, TransformStrategyUnSolid},
@@ -16,11 +18,18 @@ pub(crate) struct MigrateCommand {
     #[command(flatten)]
     transform_strategy: SolidEntriesTransformStrategyArgs,
     #[command(flatten)]
+    replace: ReplaceArgs,
+    #[command(flatten)]
     password: PasswordArgs,
     #[arg(value_hint = ValueHint::FilePath)]
     archive: PathBuf,
     #[arg(long, help = "Output file path", value_hint = ValueHint::AnyPath)]
     output: PathBuf,
+    #[arg(
+        long,
+        help = "Store the compressed extended attributes uncompressed so that pna 0.21.0 or earlier can read them"
+    )]
+    decompress_xattr: bool,
 }
 
 impl Command for MigrateCommand {
@@ -36,22 +45,27 @@ fn migrate_metadata(args: MigrateCommand) -> io::Result<()> {
         SolidEntriesTransformStrategy::UnSolid => run_transform_entry(
             args.output,
             &args.archive,
-            || password.as_deref(),
-            |entry| Ok(Some(strip_entry_metadata(entry?)?)),
+            || password.as_deref().map(String::as_str),
+            |entry| Ok(Some(strip_entry_metadata(entry?, args.decompress_xattr)?)),
             TransformStrategyUnSolid,
+            &args.replace,
         ),
         SolidEntriesTransformStrategy::KeepSolid => run_transform_entry(
             args.output,
             &args.archive,
-            || password.as_deref(),
-            |entry| Ok(Some(strip_entry_metadata(entry?)?)),
+            || password.as_deref().map(String::as_str),
+            |entry| Ok(Some(strip_entry_metadata(entry?, args.decompress_xattr)?)),
             TransformStrategyKeepSolid,
+            &args.replace,
         ),
     }
 }
 
 #[inline]
-fn strip_entry_metadata<T>(entry: NormalEntry<T>) -> io::Result<NormalEntry<T>>
+fn strip_entry_metadata<T>(
+    entry: NormalEntry<T>,
+    decompress_xattr: bool,
+) -> io::Result<NormalEntry<T>>
 where
     T: Clone,
     RawChunk<T>: Chunk,
@@ -74,5 +88,14 @@ where
             .filter(|it| !keep_private_chunks.contains(&it.ty()))
             .cloned(),
     );
-    Ok(entry.with_extra_chunks(&acl))
+    let entry = entry.with_extra_chunks(&acl);
+    if !decompress_xattr {
+        return Ok(entry);
+    }
+    let xattrs = entry
+        .xattrs()
+        .iter()
+        .map(|it| it.clone().with_compression(false))
+        .collect::<Vec<_>>();
+    Ok(entry.with_xattrs(&xattrs))
 }
@@ -0,0 +1,319 @@
+mod tree;
+
+use crate::{
+    cli::PasswordArgs,
+    command::{ask_password, Command},
+};
+use clap::{Parser, ValueHint};
+use fuser::{
+    consts::FOPEN_DIRECT_IO, FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData,
+    ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, Request, Session,
+};
+use nix::{
+    libc,
+    sys::signal::{SigSet, Signal},
+    unistd::{getgid, getuid},
+};
+use pna::{ArchiveIndexed, DataKind, ReadOptions};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+use tree::{Node, NodeKind, Tree, ROOT};
+use zeroize::Zeroizing;
+
+/// Time for which the kernel may cache attributes and names, the archive never changes while mounted.
+const TTL: Duration = Duration::from_secs(60);
+const BLOCK_SIZE: u32 = 512;
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct MountCommand {
+    #[command(flatten)]
+    password: PasswordArgs,
+    #[arg(value_hint = ValueHint::FilePath)]
+    archive: PathBuf,
+    #[arg(value_hint = ValueHint::DirPath)]
+    mountpoint: PathBuf,
+}
+
+impl Command for MountCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        mount_archive(self)
+    }
+}
+
+fn mount_archive(args: MountCommand) -> io::Result<()> {
+    let password = ask_password(args.password)?;
+    let archive = ArchiveIndexed::open_with_password(
+        fs::File::open(&args.archive)?,
+        password.as_deref().map(String::as_str),
+    )?;
+    let filesystem = ArchiveFs::new(archive, password)?;
+    let options = [
+        MountOption::RO,
+        MountOption::FSName(args.archive.display().to_string()),
+        MountOption::Subtype("pna".into()),
+    ];
+    // NOTE: Signals are blocked before any thread is spawned, so that only the waiting thread receives them.
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGINT);
+    signals.add(Signal::SIGTERM);
+    signals.thread_block().map_err(io::Error::from)?;
+    let mut session = Session::new(filesystem, &args.mountpoint, &options)?;
+    let mut unmounter = session.unmount_callable();
+    thread::spawn(move || {
+        if let Ok(signal) = signals.wait() {
+            log::debug!("Received {signal}, unmounting");
+            if let Err(e) = unmounter.unmount() {
+                log::error!("Failed to unmount: {e}");
+            }
+        }
+    });
+    session.run()
+}
+
+/// Read-only filesystem serving the entries of an archive.
+struct ArchiveFs {
+    archive: ArchiveIndexed<fs::File>,
+    password: Option<Zeroizing<String>>,
+    tree: Tree,
+    /// Decompressed contents of the open files by file handle.
+    handles: HashMap<u64, Vec<u8>>,
+    next_handle: u64,
+    uid: u32,
+    gid: u32,
+}
+
+impl ArchiveFs {
+    fn new(
+        archive: ArchiveIndexed<fs::File>,
+        password: Option<Zeroizing<String>>,
+    ) -> io::Result<Self> {
+        let mut fs = Self {
+            archive,
+            password,
+            tree: Tree::new(),
+            handles: HashMap::new(),
+            next_handle: 1,
+            uid: getuid().as_raw(),
+            gid: getgid().as_raw(),
+        };
+        fs.build_tree()?;
+        Ok(fs)
+    }
+
+    fn read_options(&self) -> ReadOptions {
+        ReadOptions::with_password(self.password.as_deref())
+    }
+
+    /// Reads the whole contents of the entry at `index`.
+    fn read_contents(&self, index: usize) -> io::Result<Vec<u8>> {
+        let entry = self.archive.entry(index)?;
+        let mut contents = Vec::new();
+        entry
+            .reader(self.read_options())?
+            .read_to_end(&mut contents)?;
+        Ok(contents)
+    }
+
+    fn read_reference(&self, index: usize) -> io::Result<String> {
+        let contents = self.read_contents(index)?;
+        Ok(String::from_utf8_lossy(&contents).into_owned())
+    }
+
+    fn build_tree(&mut self) -> io::Result<()> {
+        let mut tree = Tree::new();
+        for (index, record) in self.archive.entries().iter().enumerate() {
+            let name = record.name().as_str();
+            let metadata = record.metadata().clone();
+            let inserted = match record.data_kind() {
+                DataKind::Directory => tree.insert(name, NodeKind::Directory, metadata),
+                DataKind::File => tree.insert(name, NodeKind::File { index }, metadata),
+                DataKind::SymbolicLink => {
+                    let target = self.read_reference(index)?;
+                    tree.insert(name, NodeKind::SymbolicLink { target }, metadata)
+                }
+                DataKind::HardLink => {
+                    let reference = self.read_reference(index)?;
+                    tree.link(name, &reference)
+                }
+            };
+            if inserted.is_none() {
+                log::warn!("Skip {name}: its parent or link target is not available");
+            }
+        }
+        self.tree = tree;
+        Ok(())
+    }
+
+    fn attr(&self, ino: u64, node: &Node) -> FileAttr {
+        let metadata = &node.metadata;
+        let (kind, size, default_perm) = match &node.kind {
+            NodeKind::Directory => (FileType::Directory, 0, 0o755),
+            NodeKind::File { .. } => (
+                FileType::RegularFile,
+                metadata.raw_file_size().unwrap_or(0) as u64,
+                0o644,
+            ),
+            NodeKind::SymbolicLink { target } => (FileType::Symlink, target.len() as u64, 0o777),
+        };
+        let (perm, uid, gid) = match metadata.permission() {
+            Some(p) => (p.permissions() & 0o7777, p.uid() as u32, p.gid() as u32),
+            None => (default_perm, self.uid, self.gid),
+        };
+        let time = |t: Option<Duration>| t.map_or(UNIX_EPOCH, |t| UNIX_EPOCH + t);
+        let mtime = time(metadata.modified());
+        FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(BLOCK_SIZE as u64),
+            atime: metadata.accessed().map_or(mtime, |t| UNIX_EPOCH + t),
+            mtime,
+            ctime: mtime,
+            crtime: time(metadata.created()),
+            kind,
+            perm,
+            nlink: node.nlink,
+            uid,
+            gid,
+            rdev: 0,
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        }
+    }
+}
+
+impl Filesystem for ArchiveFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let found = name
+            .to_str()
+            .and_then(|name| self.tree.lookup(parent, name))
+            .and_then(|ino| Some((ino, self.tree.get(ino)?)));
+        match found {
+            Some((ino, node)) => reply.entry(&TTL, &self.attr(ino, node), 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.tree.get(ino) {
+            Some(node) => reply.attr(&TTL, &self.attr(ino, node)),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        match self.tree.get(ino).map(|node| &node.kind) {
+            Some(NodeKind::SymbolicLink { target }) => reply.data(target.as_bytes()),
+            Some(_) => reply.error(libc::EINVAL),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        if flags & libc::O_ACCMODE != libc::O_RDONLY {
+            return reply.error(libc::EROFS);
+        }
+        let Some(node) = self.tree.get(ino) else {
+            return reply.error(libc::ENOENT);
+        };
+        let index = match &node.kind {
+            NodeKind::File { index } => *index,
+            NodeKind::Directory => return reply.error(libc::EISDIR),
+            NodeKind::SymbolicLink { .. } => return reply.error(libc::EINVAL),
+        };
+        // NOTE: Without the size in the archive the attribute is 0, so that reads must bypass the page cache.
+        let open_flags = if node.metadata.raw_file_size().is_some() {
+            0
+        } else {
+            FOPEN_DIRECT_IO
+        };
+        // NOTE: Entries are compressed and possibly in a solid entry, so they can not be read from the middle.
+        //       The decompressed contents are kept while the file is open to serve reads at any offset.
+        match self.read_contents(index) {
+            Ok(contents) => {
+                let fh = self.next_handle;
+                self.next_handle += 1;
+                self.handles.insert(fh, contents);
+                reply.opened(fh, open_flags);
+            }
+            Err(e) => {
+                log::error!("Failed to read entry {index}: {e}");
+                reply.error(libc::EIO);
+            }
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let Some(contents) = self.handles.get(&fh) else {
+            return reply.error(libc::EBADF);
+        };
+        let start = (offset.max(0) as usize).min(contents.len());
+        let end = start.saturating_add(size as usize).min(contents.len());
+        reply.data(&contents[start..end]);
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.handles.remove(&fh);
+        reply.ok();
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Some(node) = self.tree.get(ino) else {
+            return reply.error(libc::ENOENT);
+        };
+        if node.kind != NodeKind::Directory {
+            return reply.error(libc::ENOTDIR);
+        }
+        let parent = if ino == ROOT { ROOT } else { node.parent };
+        let entries = [(ino, "."), (parent, "..")].into_iter().chain(
+            node.children
+                .iter()
+                .map(|(name, ino)| (*ino, name.as_str())),
+        );
+        for (i, (ino, name)) in entries.enumerate().skip(offset.max(0) as usize) {
+            let kind = match self.tree.get(ino).map(|node| &node.kind) {
+                Some(NodeKind::Directory) => FileType::Directory,
+                Some(NodeKind::SymbolicLink { .. }) => FileType::Symlink,
+                _ => FileType::RegularFile,
+            };
+            // NOTE: The offset passed back is the one of the next entry.
+            if reply.add(ino, (i + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
@@ -0,0 +1,242 @@
+use pna::Metadata;
+use std::collections::BTreeMap;
+
+/// Inode number of the root directory.
+pub(crate) const ROOT: u64 = 1;
+
+/// Kind of a node of a [Tree].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) enum NodeKind {
+    Directory,
+    /// Regular file read from the entry at `index` of the archive
+    File {
+        index: usize,
+    },
+    SymbolicLink {
+        target: String,
+    },
+}
+
+/// A file of the tree, shared by all the names hard linked to it.
+#[derive(Clone, Debug)]
+pub(crate) struct Node {
+    pub(crate) kind: NodeKind,
+    pub(crate) metadata: Metadata,
+    pub(crate) parent: u64,
+    /// Names in the directory, only for directories.
+    pub(crate) children: BTreeMap<String, u64>,
+    pub(crate) nlink: u32,
+}
+
+impl Node {
+    fn new(kind: NodeKind, metadata: Metadata, parent: u64) -> Self {
+        Self {
+            kind,
+            metadata,
+            parent,
+            children: BTreeMap::new(),
+            nlink: 1,
+        }
+    }
+}
+
+/// Directory tree built from the entry names of an archive, whose inode numbers start from [ROOT].
+///
+/// Directories missing in the archive are created implicitly,
+/// and the entry added last wins when multiple entries have the same name.
+#[derive(Clone, Debug)]
+pub(crate) struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    pub(crate) fn new() -> Self {
+        Self {
+            nodes: vec![Node::new(NodeKind::Directory, Metadata::new(), ROOT)],
+        }
+    }
+
+    /// Returns the node of `ino`.
+    #[inline]
+    pub(crate) fn get(&self, ino: u64) -> Option<&Node> {
+        self.nodes.get(ino.checked_sub(ROOT)? as usize)
+    }
+
+    fn get_mut(&mut self, ino: u64) -> &mut Node {
+        &mut self.nodes[(ino - ROOT) as usize]
+    }
+
+    /// Returns the inode of `name` in the directory `parent`.
+    #[inline]
+    pub(crate) fn lookup(&self, parent: u64, name: &str) -> Option<u64> {
+        self.get(parent)?.children.get(name).copied()
+    }
+
+    /// Returns the inode of the path `name` relative to the root.
+    pub(crate) fn resolve(&self, name: &str) -> Option<u64> {
+        components(name)
+            .into_iter()
+            .try_fold(ROOT, |dir, name| self.lookup(dir, name))
+    }
+
+    fn push(&mut self, node: Node) -> u64 {
+        self.nodes.push(node);
+        self.nodes.len() as u64
+    }
+
+    /// Returns the directory of `components`, creating the missing directories.
+    /// Returns `None` if a component is not a directory.
+    fn make_dirs(&mut self, components: &[&str]) -> Option<u64> {
+        let mut dir = ROOT;
+        for name in components {
+            dir = match self.lookup(dir, name) {
+                Some(ino) if self.get(ino)?.kind == NodeKind::Directory => ino,
+                Some(_) => return None,
+                None => {
+                    let ino = self.push(Node::new(NodeKind::Directory, Metadata::new(), dir));
+                    self.get_mut(dir).children.insert((*name).into(), ino);
+                    ino
+                }
+            };
+        }
+        Some(dir)
+    }
+
+    /// Adds the entry `name` of `kind`, and returns its inode.
+    /// Returns `None` if the name is empty or a parent of it is not a directory.
+    ///
+    /// A directory keeps its contents when replaced by a later directory entry of the same name.
+    pub(crate) fn insert(&mut self, name: &str, kind: NodeKind, metadata: Metadata) -> Option<u64> {
+        let components = components(name);
+        let (name, parents) = components.split_last()?;
+        let parent = self.make_dirs(parents)?;
+        match self.lookup(parent, name) {
+            Some(ino)
+                if kind == NodeKind::Directory && self.get(ino)?.kind == NodeKind::Directory =>
+            {
+                self.get_mut(ino).metadata = metadata;
+                Some(ino)
+            }
+            existing => {
+                if let Some(ino) = existing {
+                    self.get_mut(ino).nlink -= 1;
+                }
+                let ino = self.push(Node::new(kind, metadata, parent));
+                self.get_mut(parent).children.insert((*name).into(), ino);
+                Some(ino)
+            }
+        }
+    }
+
+    /// Adds the hard link `name` to the file at `reference`, which is relative to the directory of `name`,
+    /// and returns the inode of the file.
+    /// Returns `None` if the file is not found or is a directory.
+    pub(crate) fn link(&mut self, name: &str, reference: &str) -> Option<u64> {
+        let components = components(name);
+        let (name, parents) = components.split_last()?;
+        let target = self.resolve(&format!("{}/{reference}", parents.join("/")))?;
+        if self.get(target)?.kind == NodeKind::Directory {
+            return None;
+        }
+        let parent = self.make_dirs(parents)?;
+        if let Some(existing) = self.get_mut(parent).children.insert((*name).into(), target) {
+            self.get_mut(existing).nlink -= 1;
+        }
+        self.get_mut(target).nlink += 1;
+        Some(target)
+    }
+}
+
+/// Splits `name` into its components, resolving `.` and `..` without going above the root.
+fn components(name: &str) -> Vec<&str> {
+    let mut components = Vec::new();
+    for component in name.split('/') {
+        match component {
+            "" | "." => (),
+            ".." => {
+                components.pop();
+            }
+            c => components.push(c),
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn file(index: usize) -> NodeKind {
+        NodeKind::File { index }
+    }
+
+    #[test]
+    fn implicit_directories() {
+        let mut tree = Tree::new();
+        let ino = tree.insert("a/b/c.txt", file(0), Metadata::new()).unwrap();
+        let a = tree.lookup(ROOT, "a").unwrap();
+        let b = tree.lookup(a, "b").unwrap();
+        assert_eq!(tree.get(a).unwrap().kind, NodeKind::Directory);
+        assert_eq!(tree.get(b).unwrap().parent, a);
+        assert_eq!(tree.lookup(b, "c.txt"), Some(ino));
+        assert_eq!(tree.resolve("a/./b/../b/c.txt"), Some(ino));
+        assert_eq!(tree.get(ino).unwrap().kind, file(0));
+    }
+
+    #[test]
+    fn directory_entry_after_contents() {
+        let mut tree = Tree::new();
+        tree.insert("a/b.txt", file(0), Metadata::new()).unwrap();
+        let modified = Metadata::new().with_modified(Some(Duration::from_secs(1)));
+        let a = tree.insert("a", NodeKind::Directory, modified).unwrap();
+        assert_eq!(tree.resolve("a"), Some(a));
+        assert!(tree.resolve("a/b.txt").is_some());
+        assert_eq!(
+            tree.get(a).unwrap().metadata.modified(),
+            Some(Duration::from_secs(1))
+        );
+    }
+
+    #[test]
+    fn later_entry_wins() {
+        let mut tree = Tree::new();
+        tree.insert("a.txt", file(0), Metadata::new()).unwrap();
+        let ino = tree.insert("a.txt", file(1), Metadata::new()).unwrap();
+        assert_eq!(tree.resolve("a.txt"), Some(ino));
+        assert_eq!(tree.get(ino).unwrap().kind, file(1));
+        assert_eq!(tree.get(ROOT).unwrap().children.len(), 1);
+    }
+
+    #[test]
+    fn file_as_parent() {
+        let mut tree = Tree::new();
+        tree.insert("a", file(0), Metadata::new()).unwrap();
+        assert_eq!(tree.insert("a/b.txt", file(1), Metadata::new()), None);
+        assert_eq!(tree.insert("", file(2), Metadata::new()), None);
+    }
+
+    #[test]
+    fn symbolic_link() {
+        let mut tree = Tree::new();
+        let kind = NodeKind::SymbolicLink {
+            target: "../x".into(),
+        };
+        let ino = tree
+            .insert("dir/link", kind.clone(), Metadata::new())
+            .unwrap();
+        assert_eq!(tree.get(ino).unwrap().kind, kind);
+    }
+
+    #[test]
+    fn hard_links() {
+        let mut tree = Tree::new();
+        let ino = tree.insert("dir/a.txt", file(0), Metadata::new()).unwrap();
+        assert_eq!(tree.link("dir/b.txt", "a.txt"), Some(ino));
+        assert_eq!(tree.link("other/c.txt", "../dir/a.txt"), Some(ino));
+        assert_eq!(tree.resolve("other/c.txt"), Some(ino));
+        assert_eq!(tree.get(ino).unwrap().nlink, 3);
+        assert_eq!(tree.link("dir/missing", "missing.txt"), None);
+        assert_eq!(tree.link("dir/d", "."), None);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{
+    cli::{
+        CipherAlgorithmArgs, CompressionAlgorithmArgs, HashAlgorithmArgs, PasswordArgs,
+        ReplaceArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs,
+    },
+    command::{
+        append::{append_entries, read_entry_names},
+        ask_password,
+        commons::{
+            entry_option_with_existing, run_transform_entry, ExistingSettings,
+            TransformStrategyKeepSolid, TransformStrategyUnSolid,
+        },
+        Command,
+    },
+    utils::PathPartExt,
+};
+use clap::{Parser, ValueHint};
+use pna::{EntryBuilder, EntryName, NormalEntry, WriteOptions};
+use std::{
+    io::{self, prelude::*},
+    path::PathBuf,
+};
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct PutCommand {
+    #[arg(value_hint = ValueHint::FilePath)]
+    archive: PathBuf,
+    #[arg(help = "Name of the entry to replace with the data read from stdin")]
+    name: String,
+    #[arg(long, help = "Add the entry if the archive does not contain it")]
+    create: bool,
+    #[arg(
+        long,
+        help = "Do not keep the metadata of the replaced entry, such as permissions, timestamps and extended attributes"
+    )]
+    reset_metadata: bool,
+    #[command(flatten)]
+    compression: CompressionAlgorithmArgs,
+    #[command(flatten)]
+    cipher: CipherAlgorithmArgs,
+    #[command(flatten)]
+    hash: HashAlgorithmArgs,
+    #[command(flatten)]
+    transform_strategy: SolidEntriesTransformStrategyArgs,
+    #[command(flatten)]
+    replace: ReplaceArgs,
+    #[command(flatten)]
+    password: PasswordArgs,
+}
+
+impl Command for PutCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        put_entry(self)
+    }
+}
+
+fn put_entry(args: PutCommand) -> io::Result<()> {
+    let password = ask_password(args.password)?;
+    let name = EntryName::from(args.name.as_str());
+    let mut data = Vec::new();
+    io::stdin().lock().read_to_end(&mut data)?;
+
+    if !read_entry_names(&args.archive, password.as_deref().map(String::as_str))?
+        .contains_key(&name)
+    {
+        if !args.create {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{name} is not found in the archive, use --create to add it"),
+            ));
+        }
+        let existing = ExistingSettings::detect(&args.archive)?;
+        let option = entry_option_with_existing(
+            args.compression,
+            args.cipher,
+            args.hash,
+            password.as_deref().map(String::as_str),
+            existing.as_ref(),
+        );
+        return append_entries(&args.archive, [build_entry(name, &data, option)], None);
+    }
+
+    // NOTE: Each replaced entry keeps its own compression and encryption unless overridden,
+    //       so the entries in solid entries are not encrypted again by the password.
+    let option = |existing: ExistingSettings| {
+        let encrypted = existing.is_encrypted() || args.cipher.is_specified();
+        entry_option_with_existing(
+            args.compression.clone(),
+            args.cipher.clone(),
+            args.hash.clone(),
+            password
+                .as_deref()
+                .map(String::as_str)
+                .filter(|_| encrypted),
+            Some(&existing),
+        )
+    };
+    match args.transform_strategy.strategy() {
+        SolidEntriesTransformStrategy::UnSolid => run_transform_entry(
+            args.archive.remove_part().unwrap(),
+            &args.archive,
+            || password.as_deref().map(String::as_str),
+            |entry| {
+                let entry = entry?;
+                if entry.header().path() != &name {
+                    return Ok(Some(entry));
+                }
+                let replaced = build_entry(
+                    name.clone(),
+                    &data,
+                    option(ExistingSettings::of_entry(&entry)),
+                )?;
+                Ok(Some(transform_entry(entry, replaced, args.reset_metadata)))
+            },
+            TransformStrategyUnSolid,
+            &args.replace,
+        ),
+        SolidEntriesTransformStrategy::KeepSolid => run_transform_entry(
+            args.archive.remove_part().unwrap(),
+            &args.archive,
+            || password.as_deref().map(String::as_str),
+            |entry| {
+                let entry = entry?;
+                if entry.header().path() != &name {
+                    return Ok(Some(entry));
+                }
+                let replaced = build_entry(
+                    name.clone(),
+                    &data,
+                    option(ExistingSettings::of_entry(&entry)),
+                )?;
+                Ok(Some(transform_entry(entry, replaced, args.reset_metadata)))
+            },
+            TransformStrategyKeepSolid,
+            &args.replace,
+        ),
+    }
+}
+
+fn build_entry(name: EntryName, data: &[u8], option: WriteOptions) -> io::Result<NormalEntry> {
+    let mut builder = EntryBuilder::new_file(name, option)?;
+    builder.write_all(data)?;
+    builder.build()
+}
+
+/// Carries the metadata of `entry` over to `replaced` unless `reset_metadata` is set.
+#[inline]
+fn transform_entry<T>(
+    entry: NormalEntry<T>,
+    replaced: NormalEntry,
+    reset_metadata: bool,
+) -> NormalEntry<T>
+where
+    T: Clone,
+    NormalEntry<T>: From<NormalEntry>,
+{
+    let replaced = NormalEntry::<T>::from(replaced);
+    if reset_metadata {
+        return replaced;
+    }
+    replaced
+        .with_metadata(entry.metadata().clone())
+        .with_xattrs(entry.xattrs())
+        .with_extra_chunks(entry.extra_chunks())
+}
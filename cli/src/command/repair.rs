@@ -0,0 +1,112 @@
+use crate::{
+    cli::ReplaceArgs,
+    command::{
+        commons::{backup_existing, check_replaceable},
+        Command,
+    },
+    exit_code::{with_status, ExitStatus},
+    utils::{self, env::temp_dir},
+};
+use clap::{Parser, ValueHint};
+use pna::{Archive, Damage, DamageKind, Salvaged};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct RepairCommand {
+    #[command(flatten)]
+    replace: ReplaceArgs,
+    #[arg(value_hint = ValueHint::FilePath)]
+    archive: PathBuf,
+    #[arg(long, help = "Output file path", value_hint = ValueHint::AnyPath)]
+    output: PathBuf,
+}
+
+impl Command for RepairCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        repair_archive(self)
+    }
+}
+
+fn repair_archive(args: RepairCommand) -> io::Result<()> {
+    check_replaceable(&args.output, &args.replace)?;
+    #[cfg(feature = "memmap")]
+    let bytes = utils::mmap::Mmap::open(&args.archive)?;
+    #[cfg(not(feature = "memmap"))]
+    let bytes = fs::read(&args.archive)?;
+
+    let temp_dir_path = temp_dir().unwrap_or_else(|| {
+        args.output
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    });
+    fs::create_dir_all(&temp_dir_path)?;
+    let temp_path = temp_dir_path.join(format!("{}.pna.tmp", rand::random::<usize>()));
+    let (salvaged, damages) = match salvage(&bytes, &temp_path) {
+        Ok(counts) => counts,
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+    };
+    if let Some(parent) = args.output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    backup_existing(&args.output, &args.replace)?;
+    utils::fs::mv(temp_path, &args.output)?;
+
+    log::info!("Salvaged {salvaged} entries");
+    if damages == 0 {
+        Ok(())
+    } else {
+        Err(with_status(
+            ExitStatus::PartialFailure,
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{damages} damaged parts of {} were dropped",
+                    args.archive.display()
+                ),
+            ),
+        ))
+    }
+}
+
+/// Writes the intact entries of `bytes` to a new archive at `path`,
+/// and returns the numbers of the entries written and of the parts dropped.
+fn salvage(bytes: &[u8], path: &Path) -> io::Result<(usize, usize)> {
+    let mut out_archive = Archive::write_header(fs::File::create(path)?)?;
+    let mut salvaged = 0;
+    let mut damages = 0;
+    for item in Archive::salvage_from_slice(bytes)? {
+        match item {
+            Salvaged::Entry(entry) => {
+                out_archive.add_entry(entry)?;
+                salvaged += 1;
+            }
+            Salvaged::Damage(damage) => {
+                log::warn!("{}", describe(&damage));
+                damages += 1;
+            }
+        }
+    }
+    out_archive.finalize()?;
+    Ok((salvaged, damages))
+}
+
+fn describe(damage: &Damage) -> String {
+    let reason = match damage.kind() {
+        DamageKind::BrokenChunk => "broken chunk",
+        DamageKind::BrokenEntry => "incomplete entry",
+        DamageKind::Truncated => "truncated archive",
+    };
+    let range = format!("{} bytes at offset {}", damage.length(), damage.offset());
+    match damage.name() {
+        Some(name) => format!("Dropped {name} ({range}): {reason}"),
+        None => format!("Dropped {range}: {reason}"),
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{
+    chunk,
+    command::Command,
+    exit_code::{with_status, ExitStatus},
+    utils::{self, env::temp_dir},
+};
+use base64::Engine;
+use clap::{Parser, ValueHint};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use pna::{Archive, Chunk, RawChunk, ReadEntry};
+use sha2::{Digest, Sha256};
+use std::{
+    fmt, fs,
+    io::{self, prelude::*},
+    path::{Path, PathBuf},
+};
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct SignCommand {
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        help = "File of the Ed25519 secret key encoded in base64"
+    )]
+    key: PathBuf,
+    #[arg(value_hint = ValueHint::FilePath)]
+    archive: PathBuf,
+}
+
+impl Command for SignCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        sign_archive(self)
+    }
+}
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct VerifySignaturesCommand {
+    #[arg(
+        long,
+        value_name = "FILE",
+        value_hint = ValueHint::FilePath,
+        help = "File of the Ed25519 public key encoded in base64"
+    )]
+    pubkey: PathBuf,
+    #[arg(value_hint = ValueHint::FilePath)]
+    archive: PathBuf,
+}
+
+impl Command for VerifySignaturesCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        verify_archive(self)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum SignatureStatus {
+    Ok,
+    Tampered,
+    Unsigned,
+}
+
+impl fmt::Display for SignatureStatus {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Ok => "ok",
+            Self::Tampered => "tampered",
+            Self::Unsigned => "unsigned",
+        })
+    }
+}
+
+/// Reads the first line of `path` that is neither empty nor a `#` comment, decoded from base64.
+fn read_key<const N: usize>(path: &Path) -> io::Result<[u8; N]> {
+    let content = fs::read_to_string(path)?;
+    let line = content
+        .lines()
+        .map(str::trim)
+        .find(|it| !it.is_empty() && !it.starts_with('#'))
+        .unwrap_or_default();
+    let invalid = |reason: String| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: invalid key: {reason}", path.display()),
+        )
+    };
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(line)
+        .map_err(|e| invalid(e.to_string()))?;
+    let len = bytes.len();
+    bytes
+        .try_into()
+        .map_err(|_| invalid(format!("expected {N} bytes, but got {len} bytes")))
+}
+
+/// Digest of the archive, the SHA-256 digest of the digests of all entries in order.
+fn archive_digest(entry_digests: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for digest in entry_digests {
+        hasher.update(digest);
+    }
+    hasher.finalize().into()
+}
+
+fn sign_archive(args: SignCommand) -> io::Result<()> {
+    let key = SigningKey::from_bytes(&read_key(&args.key)?);
+    let mut reader = Archive::read_header(io::BufReader::new(fs::File::open(&args.archive)?))?;
+
+    let temp_dir_path = temp_dir().unwrap_or_else(|| {
+        args.archive
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("."))
+    });
+    fs::create_dir_all(&temp_dir_path)?;
+    let temp_path = temp_dir_path.join(format!("{}.pna.tmp", rand::random::<usize>()));
+    let mut writer = Archive::write_header_with_id(
+        io::BufWriter::new(fs::File::create(&temp_path)?),
+        reader.archive_id(),
+    )?;
+
+    let mut digests = Vec::new();
+    for entry in reader.entries() {
+        match entry? {
+            ReadEntry::Normal(entry) => {
+                let digest = entry.digest(&[chunk::fsIG]);
+                let signature = key.sign(&digest);
+                // NOTE: The signature of a signed entry is replaced.
+                let mut extra = entry
+                    .extra_chunks()
+                    .iter()
+                    .filter(|it| it.ty() != chunk::fsIG)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                extra.push(RawChunk::from_data(chunk::fsIG, signature.to_bytes()));
+                digests.push(digest);
+                writer.add_entry(entry.with_extra_chunks(&extra))?;
+            }
+            ReadEntry::Solid(solid) => {
+                log::warn!("Solid entries are signed only by the archive signature");
+                digests.push(solid.digest(&[]));
+                writer.add_entry(solid)?;
+            }
+        }
+    }
+    if reader.has_next_archive() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "signing split archives is not supported",
+        ));
+    }
+    let signature = key.sign(&archive_digest(&digests));
+    let chunks = [RawChunk::from_data(chunk::asIG, signature.to_bytes())];
+    writer.finalize_with_chunks(chunks)?.flush()?;
+    utils::fs::mv(temp_path, &args.archive)
+}
+
+fn verify_signature(key: &VerifyingKey, message: &[u8], signature: &[u8]) -> SignatureStatus {
+    match Signature::from_slice(signature) {
+        Ok(signature) if key.verify_strict(message, &signature).is_ok() => SignatureStatus::Ok,
+        _ => SignatureStatus::Tampered,
+    }
+}
+
+fn verify_archive(args: VerifySignaturesCommand) -> io::Result<()> {
+    let key = VerifyingKey::from_bytes(&read_key(&args.pubkey)?).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{}: invalid key: {e}", args.pubkey.display()),
+        )
+    })?;
+    let mut stdout = io::stdout().lock();
+    let mut failures = 0;
+
+    let mut reader = Archive::read_header(io::BufReader::new(fs::File::open(&args.archive)?))?;
+    let mut digests = Vec::new();
+    for (index, entry) in reader.entries().enumerate() {
+        let (status, name) = match entry? {
+            ReadEntry::Normal(entry) => {
+                let digest = entry.digest(&[chunk::fsIG]);
+                digests.push(digest);
+                let status = match entry
+                    .extra_chunks()
+                    .iter()
+                    .find(|it| it.ty() == chunk::fsIG)
+                {
+                    Some(signature) => verify_signature(&key, &digest, signature.data()),
+                    None => SignatureStatus::Unsigned,
+                };
+                (status, entry.header().path().to_string())
+            }
+            ReadEntry::Solid(solid) => {
+                digests.push(solid.digest(&[]));
+                (SignatureStatus::Unsigned, format!("(solid entry #{index})"))
+            }
+        };
+        if status == SignatureStatus::Tampered {
+            failures += 1;
+        }
+        writeln!(stdout, "{status}: {name}")?;
+    }
+
+    // NOTE: The archive signature is written before AEND, where entries are not read.
+    let mut signature = None;
+    for it in pna::read_as_chunks(io::BufReader::new(fs::File::open(&args.archive)?))? {
+        let it = it?;
+        if it.ty() == chunk::asIG {
+            signature = Some(it.data().to_vec());
+        }
+    }
+    let status = match signature {
+        Some(signature) => verify_signature(&key, &archive_digest(&digests), &signature),
+        None => SignatureStatus::Unsigned,
+    };
+    writeln!(stdout, "{status}: (archive)")?;
+    if status != SignatureStatus::Ok {
+        failures += 1;
+    }
+
+    if failures == 0 {
+        return Ok(());
+    }
+    Err(with_status(
+        ExitStatus::Crypto,
+        io::Error::other(format!("{failures} signatures failed to verify")),
+    ))
+}
@@ -1,13 +1,20 @@
 use crate::{
-    command::{commons::write_split_archive, Command},
+    command::{
+        commons::{split_part_size, write_split_archive, PartBoundary},
+        Command,
+    },
     utils::PathPartExt,
 };
 use bytesize::ByteSize;
-use clap::{Parser, ValueHint};
+use clap::{ArgGroup, Parser, ValueHint};
 use pna::Archive;
 use std::{fs, fs::File, io, path::PathBuf};
 
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[command(
+    group(ArgGroup::new("unstable-split-on-entry").args(["split_on_entry", "entries_per_part", "strict"]).multiple(true).requires("unstable")),
+    group(ArgGroup::new("unstable-archive-id").args(["archive_id"]).requires("unstable")),
+)]
 pub(crate) struct SplitCommand {
     #[arg(value_hint = ValueHint::FilePath)]
     pub(crate) archive: PathBuf,
@@ -15,8 +22,42 @@ pub(crate) struct SplitCommand {
     pub(crate) out_dir: Option<PathBuf>,
     #[arg(long, help = "Overwrite file")]
     pub(crate) overwrite: bool,
-    #[arg(long, help = "Maximum size of split archive")]
+    #[arg(
+        long,
+        help = "Maximum size of split archive, with a decimal (kB, MB, GB) or a binary (KiB, MiB, GiB) unit such as 500MB or 1.5GiB"
+    )]
     pub(crate) max_size: Option<ByteSize>,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with_all = ["max_size", "entries_per_part"],
+        help = "Split into at most N parts of about equal size. The last part may be smaller than the others"
+    )]
+    pub(crate) parts: Option<u64>,
+    #[arg(
+        long,
+        help = "Split only at entry boundaries so that each part can be extracted on its own (unstable)"
+    )]
+    pub(crate) split_on_entry: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Maximum number of entries in each part. This implies --split-on-entry (unstable)"
+    )]
+    pub(crate) entries_per_part: Option<u64>,
+    #[arg(
+        long,
+        requires = "split_on_entry",
+        help = "With --split-on-entry, fail instead of dividing an entry larger than the maximum size across parts"
+    )]
+    pub(crate) strict: bool,
+    #[arg(
+        long,
+        help = "Record a new archive id in each part when the archive has none, so that the parts of different archives are not mixed up. The parts cannot be read by pna 0.21.0 or earlier (unstable)"
+    )]
+    pub(crate) archive_id: bool,
 }
 
 impl Command for SplitCommand {
@@ -31,12 +72,16 @@ fn split_archive(args: SplitCommand) -> io::Result<()> {
     #[cfg(not(feature = "memmap"))]
     let mut read_archive = Archive::read_header(read_file)?;
     #[cfg(not(feature = "memmap"))]
+    let archive_id = read_archive.archive_id();
+    #[cfg(not(feature = "memmap"))]
     let entries = read_archive.raw_entries();
     #[cfg(feature = "memmap")]
     let mapped_file = crate::utils::mmap::Mmap::try_from(read_file)?;
     #[cfg(feature = "memmap")]
     let mut read_archive = Archive::read_header_from_slice(&mapped_file[..])?;
     #[cfg(feature = "memmap")]
+    let archive_id = read_archive.archive_id();
+    #[cfg(feature = "memmap")]
     let entries = read_archive.raw_entries_slice();
 
     let base_out_file_name = if let Some(out_dir) = args.out_dir {
@@ -52,7 +97,39 @@ fn split_archive(args: SplitCommand) -> io::Result<()> {
             format!("{} is already exists", name.display()),
         ));
     }
-    let max_file_size = args.max_size.unwrap_or_else(|| ByteSize::gb(1)).as_u64() as usize;
+    let boundary = if args.split_on_entry || args.entries_per_part.is_some() {
+        PartBoundary::Entry {
+            entries_per_part: args.entries_per_part.map(|it| it as usize),
+            strict: args.strict,
+        }
+    } else {
+        PartBoundary::Chunk
+    };
+    let max_file_size = match (args.max_size, args.parts, args.entries_per_part) {
+        (Some(size), _, _) => size.as_u64() as usize,
+        (None, Some(parts), _) => {
+            // NOTE: The entries are read once more to sum up their sizes before splitting.
+            #[cfg(not(feature = "memmap"))]
+            let mut archive = Archive::read_header(File::open(&args.archive)?)?;
+            #[cfg(not(feature = "memmap"))]
+            let entries = archive.raw_entries();
+            #[cfg(feature = "memmap")]
+            let mut archive = Archive::read_header_from_slice(&mapped_file[..])?;
+            #[cfg(feature = "memmap")]
+            let entries = archive.raw_entries_slice();
+            split_part_size(entries, parts as usize, boundary)?
+        }
+        (None, None, Some(_)) => usize::MAX,
+        (None, None, None) => ByteSize::gb(1).as_u64() as usize,
+    };
 
-    write_split_archive(base_out_file_name, entries, max_file_size)
+    // NOTE: The parts keep the archive id of the archive, so that they can be concatenated back into it.
+    let archive_id = archive_id.or_else(|| args.archive_id.then(rand::random));
+    write_split_archive(
+        base_out_file_name,
+        archive_id,
+        entries,
+        max_file_size,
+        boundary,
+    )
 }
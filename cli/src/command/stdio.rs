@@ -3,15 +3,19 @@ use crate::{
     command::{
         ask_password, check_password,
         commons::{
-            collect_items, entry_option, KeepOptions, OwnerOptions, PathArchiveProvider,
-            StdinArchiveProvider,
+            collect_items, entry_option, Exclude, KeepOptions, OutputArchive, OwnerOptions,
+            PathArchiveProvider, PlatformTags, StdinArchiveProvider,
         },
-        create::create_archive_file,
-        extract::{run_extract_archive_reader, OutputOption},
-        list::{ListOptions, TimeField, TimeFormat},
+        create::{create_archive_file, CreateArchiveOptions, SolidMode, SolidOptions},
+        extract::{
+            run_extract_archive_reader, CaseCollision, ImplicitDirs, MergeMode, OutputOption,
+            ReuseOptions, SkipExisting, UnknownOwner, WindowsCompatOptions, XattrOptions,
+        },
+        list::{DelimitedOptions, ListOptions, TimeField, TimeFormat},
         Command,
     },
-    utils,
+    exit_code::usage_error,
+    utils::{self, transform::Transformer, PathFilter},
 };
 use clap::{ArgGroup, Args, Parser, ValueHint};
 use std::{
@@ -53,6 +57,12 @@ pub(crate) struct StdioCommand {
     keep_permission: bool,
     #[arg(long, help = "Archiving the extended attributes of the files")]
     keep_xattr: bool,
+    #[arg(
+        long,
+        requires = "keep_xattr",
+        help = "Compress the values of the extended attributes larger than 4 KiB with zstd. The compressed attributes cannot be read by pna 0.21.0 or earlier (unstable)"
+    )]
+    compress_xattr: bool,
     #[arg(long, help = "Archiving the acl of the files")]
     pub(crate) keep_acl: bool,
     #[arg(long, help = "Solid mode archive")]
@@ -148,33 +158,26 @@ fn run_create_archive(args: StdioCommand) -> io::Result<()> {
                 .map(PathBuf::from),
         );
     }
-    let exclude = if args.exclude.is_some() || args.exclude_from.is_some() {
-        let mut exclude = Vec::new();
-        if let Some(e) = args.exclude {
-            exclude.extend(e);
-        }
-        if let Some(p) = args.exclude_from {
-            exclude.extend(utils::fs::read_to_lines(p)?.into_iter().map(PathBuf::from));
-        }
-        Some(exclude)
-    } else {
-        None
-    };
+    let exclude = exclude_patterns(args.exclude, args.exclude_from)?;
     let target_items = collect_items(
         &files,
         args.recursive,
         args.keep_dir,
         args.gitignore,
         args.follow_links,
-        exclude,
+        Exclude::new(exclude)?,
+        args.file.as_deref().map(OutputArchive::new).as_ref(),
     )?;
 
-    let password = password.as_deref();
+    let password = password.as_deref().map(String::as_str);
     let cli_option = entry_option(args.compression, args.cipher, args.hash, password);
     let keep_options = KeepOptions {
         keep_timestamp: args.keep_timestamp,
-        keep_permission: args.keep_permission,
+        clamp_time: None,
+        keep_owner: args.keep_permission,
+        keep_mode: args.keep_permission,
         keep_xattr: args.keep_xattr,
+        compress_xattr: args.compress_xattr,
         keep_acl: args.keep_acl,
     };
     let owner_options = OwnerOptions::new(
@@ -184,36 +187,63 @@ fn run_create_archive(args: StdioCommand) -> io::Result<()> {
         args.gid,
         args.numeric_owner,
     );
+    let options = CreateArchiveOptions {
+        write_option: cli_option,
+        keep_options,
+        owner_options,
+        platform_tags: PlatformTags::default(),
+        solid: SolidOptions {
+            mode: SolidMode::new(args.solid, false),
+            block_size: None,
+        },
+        dedup: false,
+        fail_fast: true,
+        archive_id: None,
+    };
     if let Some(file) = args.file {
-        create_archive_file(
-            || fs::File::open(&file),
-            cli_option,
-            keep_options,
-            owner_options,
-            args.solid,
-            target_items,
-        )
+        create_archive_file(|| fs::File::open(&file), options, target_items)
     } else {
-        create_archive_file(
-            || Ok(stdout().lock()),
-            cli_option,
-            keep_options,
-            owner_options,
-            args.solid,
-            target_items,
-        )
+        create_archive_file(|| Ok(stdout().lock()), options, target_items)
+    }
+}
+
+/// Returns the globs given by `--exclude` and `--exclude-from`.
+fn exclude_patterns(
+    exclude: Option<Vec<PathBuf>>,
+    exclude_from: Option<String>,
+) -> io::Result<Vec<String>> {
+    let mut patterns = exclude
+        .unwrap_or_default()
+        .into_iter()
+        .map(|it| it.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    if let Some(p) = exclude_from {
+        patterns.extend(utils::fs::read_to_lines(p)?);
     }
+    Ok(patterns)
 }
 
 fn run_extract_archive(args: StdioCommand) -> io::Result<()> {
     let password = ask_password(args.password)?;
+    let exclude = exclude_patterns(args.exclude, args.exclude_from)?;
+    let filter = PathFilter::new(Vec::<String>::new(), exclude, false).map_err(usage_error)?;
     let out_option = OutputOption {
         overwrite: args.overwrite,
+        merge: MergeMode::Off,
+        skip_existing: SkipExisting::Off,
+        report_file: None,
         out_dir: args.out_dir,
+        out_dir_mode: None,
+        keep_dir: true,
+        implicit_dirs: ImplicitDirs::default(),
+        recursion: true,
         keep_options: KeepOptions {
             keep_timestamp: args.keep_timestamp,
-            keep_permission: args.keep_permission,
+            clamp_time: None,
+            keep_owner: args.keep_permission,
+            keep_mode: args.keep_permission,
             keep_xattr: args.keep_xattr,
+            compress_xattr: false,
             keep_acl: args.keep_acl,
         },
         owner_options: OwnerOptions::new(
@@ -223,20 +253,35 @@ fn run_extract_archive(args: StdioCommand) -> io::Result<()> {
             args.gid,
             args.numeric_owner,
         ),
+        unknown_owner: UnknownOwner::default(),
+        xattr_options: XattrOptions::default(),
+        windows_compat: WindowsCompatOptions::default(),
+        case_collision: CaseCollision::default(),
+        normalize_paths: false,
+        transformer: Transformer::default(),
+        strip_components: 0,
+        all_platforms: false,
+        identities: Vec::new(),
     };
     if let Some(file) = args.file {
         run_extract_archive_reader(
             PathArchiveProvider::new(&file),
             args.files,
-            || password.as_deref(),
+            &filter,
+            || password.as_deref().map(String::as_str),
             out_option,
+            true,
+            ReuseOptions::default(),
         )
     } else {
         run_extract_archive_reader(
             StdinArchiveProvider::new(),
             args.files,
-            || password.as_deref(),
+            &filter,
+            || password.as_deref().map(String::as_str),
             out_option,
+            true,
+            ReuseOptions::default(),
         )
     }
 }
@@ -256,18 +301,30 @@ fn run_list_archive(args: StdioCommand) -> io::Result<()> {
         hide_control_chars: false,
         classify: false,
         format: None,
+        delimited: DelimitedOptions::default(),
+        hardlinks: false,
+        identities: Vec::new(),
+        check: false,
+        total: false,
+        human_readable: false,
+        recursion: true,
+        archive: args
+            .file
+            .as_ref()
+            .map_or_else(|| "-".into(), |it| it.display().to_string()),
+        width: None,
     };
     if let Some(path) = args.file {
         crate::command::list::run_list_archive(
             PathArchiveProvider::new(&path),
-            password.as_deref(),
+            password.as_deref().map(String::as_str),
             &args.files,
             list_options,
         )
     } else {
         crate::command::list::run_list_archive(
             StdinArchiveProvider::new(),
-            password.as_deref(),
+            password.as_deref().map(String::as_str),
             &args.files,
             list_options,
         )
@@ -1,6 +1,6 @@
 use crate::{
     cli::{
-        FileArgs, PasswordArgs, PrivateChunkType, SolidEntriesTransformStrategy,
+        FileArgs, PasswordArgs, PrivateChunkType, ReplaceArgs, SolidEntriesTransformStrategy,
         SolidEntriesTransformStrategyArgs,
     },
     command::{
@@ -34,6 +34,8 @@ pub(crate) struct StripCommand {
     pub(crate) strip_options: StripOptions,
     #[command(flatten)]
     transform_strategy: SolidEntriesTransformStrategyArgs,
+    #[command(flatten)]
+    replace: ReplaceArgs,
     #[arg(long, help = "Output file path", value_hint = ValueHint::AnyPath)]
     pub(crate) output: Option<PathBuf>,
     #[command(flatten)]
@@ -56,17 +58,19 @@ fn strip_metadata(args: StripCommand) -> io::Result<()> {
             args.output
                 .unwrap_or_else(|| args.file.archive.remove_part().unwrap()),
             &args.file.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| Ok(Some(strip_entry_metadata(entry?, &args.strip_options))),
             TransformStrategyUnSolid,
+            &args.replace,
         ),
         SolidEntriesTransformStrategy::KeepSolid => run_transform_entry(
             args.output
                 .unwrap_or_else(|| args.file.archive.remove_part().unwrap()),
             &args.file.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| Ok(Some(strip_entry_metadata(entry?, &args.strip_options))),
             TransformStrategyKeepSolid,
+            &args.replace,
         ),
     }
 }
@@ -94,7 +98,8 @@ where
         .keep_private
         .as_ref()
         .is_some_and(|it| it.is_empty());
-    let mut keep_private_chunks = Vec::new();
+    // NOTE: The platform tag decides whether the entry is extracted at all, so it is always kept.
+    let mut keep_private_chunks = vec![crate::chunk::fPLT];
     if options.keep_acl {
         keep_private_chunks.push(crate::chunk::faCl);
         keep_private_chunks.push(crate::chunk::faCe);
@@ -5,19 +5,20 @@ use crate::command::commons::run_read_entries_path as run_read_entries;
 use crate::{
     cli::{
         CipherAlgorithmArgs, CompressionAlgorithmArgs, FileArgs, HashAlgorithmArgs, PasswordArgs,
-        SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs,
+        SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs, TraversalArgs,
     },
     command::{
         ask_password, check_password,
         commons::{
-            collect_items, create_entry, entry_option, CreateOptions, KeepOptions, OwnerOptions,
+            collect_items, create_entry, entry_option_with_existing, run_in_pool, CreateOptions,
+            Exclude, ExistingSettings, KeepOptions, OutputArchive, OwnerOptions, PlatformTags,
             TransformStrategy, TransformStrategyKeepSolid, TransformStrategyUnSolid,
         },
         Command,
     },
     utils::{self, env::temp_dir, PathPartExt},
 };
-use clap::{ArgGroup, Parser, ValueHint};
+use clap::{ArgGroup, Parser};
 use normalize_path::*;
 use pna::{Archive, Metadata};
 use std::{
@@ -29,12 +30,6 @@ use std::{
 #[derive(Parser, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[command(
     group(ArgGroup::new("unstable-acl").args(["keep_acl"]).requires("unstable")),
-    group(ArgGroup::new("unstable-update-exclude").args(["exclude"]).requires("unstable")),
-    group(ArgGroup::new("unstable-files-from").args(["files_from"]).requires("unstable")),
-    group(ArgGroup::new("unstable-files-from-stdin").args(["files_from_stdin"]).requires("unstable")),
-    group(ArgGroup::new("unstable-exclude-from").args(["exclude_from"]).requires("unstable")),
-    group(ArgGroup::new("unstable-gitignore").args(["gitignore"]).requires("unstable")),
-    group(ArgGroup::new("read-files-from").args(["files_from", "files_from_stdin"])),
     group(ArgGroup::new("store-uname").args(["uname"]).requires("keep_permission")),
     group(ArgGroup::new("store-gname").args(["gname"]).requires("keep_permission")),
     group(ArgGroup::new("store-numeric-owner").args(["numeric_owner"]).requires("keep_permission")),
@@ -45,8 +40,8 @@ use std::{
     group(ArgGroup::new("windows-unstable-keep-permission").args(["keep_permission"]).requires("unstable")),
 ))]
 pub(crate) struct UpdateCommand {
-    #[arg(short, long, help = "Add the directory to the archive recursively")]
-    pub(crate) recursive: bool,
+    #[command(flatten)]
+    pub(crate) traversal: TraversalArgs,
     #[arg(long, help = "Archiving the directories")]
     pub(crate) keep_dir: bool,
     #[arg(long, help = "Archiving the timestamp of the files")]
@@ -55,6 +50,12 @@ pub(crate) struct UpdateCommand {
     pub(crate) keep_permission: bool,
     #[arg(long, help = "Archiving the extended attributes of the files")]
     pub(crate) keep_xattr: bool,
+    #[arg(
+        long,
+        requires = "keep_xattr",
+        help = "Compress the values of the extended attributes larger than 4 KiB with zstd. The compressed attributes cannot be read by pna 0.21.0 or earlier (unstable)"
+    )]
+    pub(crate) compress_xattr: bool,
     #[arg(long, help = "Archiving the acl of the files")]
     pub(crate) keep_acl: bool,
     #[arg(long, help = "Archiving user to the entries from given name")]
@@ -96,12 +97,11 @@ pub(crate) struct UpdateCommand {
         help = "Only include files and directories newer than the specified date. This compares mtime entries."
     )]
     pub(crate) newer_mtime: bool,
-    #[arg(long, help = "Read archiving files from given path (unstable)", value_hint = ValueHint::FilePath)]
-    pub(crate) files_from: Option<String>,
-    #[arg(long, help = "Read archiving files from stdin (unstable)")]
-    pub(crate) files_from_stdin: bool,
-    #[arg(long, help = "Read exclude files from given path (unstable)", value_hint = ValueHint::FilePath)]
-    pub(crate) exclude_from: Option<String>,
+    #[arg(
+        long,
+        help = "Use the compression, encryption and password hashing settings of the existing entries for the settings not specified"
+    )]
+    pub(crate) match_existing: bool,
     #[command(flatten)]
     pub(crate) compression: CompressionAlgorithmArgs,
     #[command(flatten)]
@@ -114,12 +114,6 @@ pub(crate) struct UpdateCommand {
     pub(crate) transform_strategy: SolidEntriesTransformStrategyArgs,
     #[command(flatten)]
     pub(crate) file: FileArgs,
-    #[arg(long, help = "Exclude path glob (unstable)", value_hint = ValueHint::AnyPath)]
-    pub(crate) exclude: Option<Vec<PathBuf>>,
-    #[arg(long, help = "Ignore files from .gitignore (unstable)")]
-    pub(crate) gitignore: bool,
-    #[arg(long, help = "Follow symbolic links")]
-    pub(crate) follow_links: bool,
 }
 
 impl Command for UpdateCommand {
@@ -146,12 +140,30 @@ fn update_archive<Strategy: TransformStrategy>(args: UpdateCommand) -> io::Resul
             format!("{} is not exists", archive_path.display()),
         ));
     }
-    let password = password.as_deref();
-    let option = entry_option(args.compression, args.cipher, args.hash, password);
+    let password = password.as_deref().map(String::as_str);
+    let existing = if args.match_existing {
+        let existing = ExistingSettings::detect(&archive_path)?;
+        if existing.is_none() {
+            log::info!("No entries found in the archive, use the default settings");
+        }
+        existing
+    } else {
+        None
+    };
+    let option = entry_option_with_existing(
+        args.compression,
+        args.cipher,
+        args.hash,
+        password,
+        existing.as_ref(),
+    );
     let keep_options = KeepOptions {
         keep_timestamp: args.keep_timestamp,
-        keep_permission: args.keep_permission,
+        clamp_time: None,
+        keep_owner: args.keep_permission,
+        keep_mode: args.keep_permission,
         keep_xattr: args.keep_xattr,
+        compress_xattr: args.compress_xattr,
         keep_acl: args.keep_acl,
     };
     let owner_options = OwnerOptions::new(
@@ -165,34 +177,20 @@ fn update_archive<Strategy: TransformStrategy>(args: UpdateCommand) -> io::Resul
         option,
         keep_options,
         owner_options,
+        platform_tags: PlatformTags::default(),
     };
 
-    let mut files = args.file.files;
-    if args.files_from_stdin {
-        files.extend(io::stdin().lines().collect::<io::Result<Vec<_>>>()?);
-    } else if let Some(path) = args.files_from {
-        files.extend(utils::fs::read_to_lines(path)?);
-    }
-    let mut exclude = args
-        .exclude
-        .unwrap_or_default()
-        .into_iter()
-        .map(|p| p.normalize())
-        .collect::<Vec<_>>();
-    if let Some(p) = args.exclude_from {
-        exclude.extend(
-            utils::fs::read_to_lines(p)?
-                .into_iter()
-                .map(|it| PathBuf::from(it).normalize()),
-        );
-    }
+    let files = args.traversal.files(args.file.files)?;
+    let exclude =
+        Exclude::new(args.traversal.exclude_patterns()?)?.with_nodump(args.traversal.nodump);
     let mut target_items = collect_items(
-        &files,
-        args.recursive,
+        files,
+        args.traversal.recursive,
         args.keep_dir,
-        args.gitignore,
-        args.follow_links,
-        None,
+        args.traversal.gitignore,
+        args.traversal.follow_links,
+        exclude,
+        Some(&OutputArchive::new(&archive_path)),
     )?;
 
     let (tx, rx) = std::sync::mpsc::channel();
@@ -246,38 +244,36 @@ fn update_archive<Strategy: TransformStrategy>(args: UpdateCommand) -> io::Resul
             let entry = entry?;
             let file = entry.header().path().as_path();
             let normalized_path = file.normalize();
-            if target_items.contains(&normalized_path) {
-                let entry = if !exclude.contains(&normalized_path)
-                    && need_update_condition(&normalized_path, entry.metadata()).unwrap_or(true)
-                {
+            if let Some(i) = target_items
+                .iter()
+                .position(|p| p.normalize() == normalized_path)
+            {
+                let path = target_items.remove(i);
+                let entry = if need_update_condition(&path, entry.metadata()).unwrap_or(true) {
                     let tx = tx.clone();
-                    rayon::scope_fifo(|s| {
-                        s.spawn_fifo(|_| {
-                            log::debug!("Updating: {}", file.display());
-                            tx.send(create_entry(file, &create_options))
-                                .unwrap_or_else(|e| panic!("{e}: {}", file.display()));
-                        });
+                    run_in_pool(|| {
+                        log::debug!("Updating: {}", path.display());
+                        tx.send(create_entry(&path, &create_options))
+                            .unwrap_or_else(|e| panic!("{e}: {}", path.display()));
                     });
                     None
                 } else {
                     Some(entry)
                 };
-                target_items.retain(|p| p.normalize() == normalized_path);
                 return Ok(entry);
             }
-            Ok(None)
+            // NOTE: Entries of the files not given, including the excluded ones, are kept as they are.
+            Ok(Some(entry))
         })
     })?;
 
     // NOTE: Add new entries
     for file in target_items {
         let tx = tx.clone();
-        rayon::scope_fifo(|s| {
-            s.spawn_fifo(|_| {
-                log::debug!("Adding: {}", file.display());
-                tx.send(create_entry(&file, &create_options))
-                    .unwrap_or_else(|e| panic!("{e}: {}", file.display()));
-            });
+        run_in_pool(|| {
+            log::debug!("Adding: {}", file.display());
+            tx.send(create_entry(&file, &create_options))
+                .unwrap_or_else(|e| panic!("{e}: {}", file.display()));
         });
     }
 
@@ -0,0 +1,232 @@
+use crate::{
+    cli::PasswordArgs,
+    command::{
+        ask_password,
+        commons::{
+            par_map_ordered, run_read_entries, ArchiveProvider, PathArchiveProvider,
+            MAP_WINDOW_PER_THREAD,
+        },
+        Command,
+    },
+    exit_code::{with_status, ExitStatus},
+    utils::{
+        hash::{self, PartDigest},
+        PathPartExt,
+    },
+};
+use clap::{Parser, ValueEnum, ValueHint};
+use pna::{DataKind, NormalEntry, ReadEntry, ReadOptions};
+use serde::Serialize;
+use std::{
+    cell::RefCell,
+    fs,
+    io::{self, prelude::*},
+    mem,
+    path::{Path, PathBuf},
+};
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct VerifyCommand {
+    #[arg(long, value_enum, default_value_t = Format::Text, help = "Output format")]
+    format: Format,
+    #[arg(
+        long,
+        help = "Print the SHA-256 digests of the parts of the archive after the entries"
+    )]
+    part_digests: bool,
+    #[command(flatten)]
+    password: PasswordArgs,
+    #[arg(
+        value_hint = ValueHint::FilePath,
+        help = "Archive to verify, or the first part of a split archive"
+    )]
+    archive: PathBuf,
+}
+
+impl Command for VerifyCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        verify_archive(self)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum)]
+enum Format {
+    Text,
+    /// One JSON object per entry on a line
+    Jsonl,
+}
+
+/// Result of the verification of an entry.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+struct EntryStatus {
+    path: String,
+    ok: bool,
+    /// Number of the decoded bytes of a file entry.
+    size: Option<u64>,
+    error: Option<String>,
+}
+
+/// Digest of a part of the archive, printed by `--part-digests`.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+struct PartStatus {
+    part: usize,
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+/// Provides the parts of the archive at a path, recording the paths of the parts opened.
+struct RecordingArchiveProvider<'p> {
+    inner: PathArchiveProvider<'p>,
+    path: &'p Path,
+    parts: &'p RefCell<Vec<PathBuf>>,
+}
+
+impl ArchiveProvider for RecordingArchiveProvider<'_> {
+    type Source = fs::File;
+
+    #[inline]
+    fn initial_source(&self) -> io::Result<Self::Source> {
+        let source = self.inner.initial_source()?;
+        self.parts.borrow_mut().push(self.path.to_path_buf());
+        Ok(source)
+    }
+
+    #[inline]
+    fn next_source(&self, n: usize) -> io::Result<Self::Source> {
+        let source = self.inner.next_source(n)?;
+        self.parts
+            .borrow_mut()
+            .push(self.path.with_part(n).unwrap());
+        Ok(source)
+    }
+}
+
+fn verify_entry(entry: &NormalEntry, option: &ReadOptions) -> io::Result<Option<u64>> {
+    match entry.header().data_kind() {
+        DataKind::File => entry.verify(option).map(Some),
+        // NOTE: Links store their targets as the data, which are decoded to check them too.
+        DataKind::SymbolicLink | DataKind::HardLink => entry.verify(option).map(|_| None),
+        DataKind::Directory => Ok(None),
+    }
+}
+
+fn verify_archive(args: VerifyCommand) -> io::Result<()> {
+    let password = ask_password(args.password)?;
+    let option = ReadOptions::with_password(password.as_deref());
+    let mut stdout = io::stdout().lock();
+    let mut verified = 0usize;
+    let mut failed = 0usize;
+    let mut first_error = None;
+    let mut report = |path: String, result: io::Result<Option<u64>>| -> io::Result<()> {
+        let status = EntryStatus {
+            path,
+            ok: result.is_ok(),
+            size: result.as_ref().ok().copied().flatten(),
+            error: result.as_ref().err().map(ToString::to_string),
+        };
+        verified += 1;
+        if let Err(e) = result {
+            failed += 1;
+            first_error.get_or_insert(e);
+        }
+        match args.format {
+            Format::Text => match &status.error {
+                None => writeln!(stdout, "OK     {}", status.path),
+                Some(e) => writeln!(stdout, "FAILED {}: {e}", status.path),
+            },
+            Format::Jsonl => {
+                serde_json::to_writer(&mut stdout, &status)?;
+                writeln!(stdout)
+            }
+        }
+    };
+    // NOTE: The entries are verified in windows of a few entries per thread, so that only
+    //       the entries of a window are kept in memory.
+    let window = if rayon::current_thread_index().is_some() {
+        rayon::current_num_threads() * MAP_WINDOW_PER_THREAD
+    } else {
+        1
+    };
+    let mut pending = Vec::with_capacity(window);
+    let mut verify_pending = |pending: Vec<NormalEntry>| {
+        par_map_ordered(pending, |entry| {
+            let result = verify_entry(&entry, &option);
+            (entry.header().path().to_string(), result)
+        })
+        .try_for_each(|(path, result)| report(path, result))
+    };
+    let parts = RefCell::new(Vec::new());
+    let provider = RecordingArchiveProvider {
+        inner: PathArchiveProvider::new(&args.archive),
+        path: &args.archive,
+        parts: &parts,
+    };
+    let result = run_read_entries(provider, |entry| {
+        let mut push = |entry| {
+            pending.push(entry);
+            if pending.len() < window {
+                return Ok(());
+            }
+            verify_pending(mem::take(&mut pending))
+        };
+        match entry? {
+            ReadEntry::Solid(solid) => {
+                for entry in solid.entries(password.as_deref().map(String::as_str))? {
+                    push(entry?)?;
+                }
+            }
+            ReadEntry::Normal(entry) => push(entry)?,
+        }
+        Ok(())
+    });
+    // NOTE: The entries read before an error are reported before the error.
+    verify_pending(pending)?;
+    result?;
+    if args.part_digests {
+        let parts = parts.into_inner().into_iter().enumerate().collect();
+        for (digest, path) in par_map_ordered(parts, |(i, path): (usize, PathBuf)| {
+            (hash::digest_file(i + 1, &path), path)
+        }) {
+            report_part(&mut stdout, args.format, digest?, &path)?;
+        }
+    }
+    match first_error {
+        None => {
+            log::info!("{verified} entries verified");
+            Ok(())
+        }
+        Some(e) => {
+            let status = ExitStatus::from_error(&e);
+            Err(with_status(
+                status,
+                io::Error::new(
+                    e.kind(),
+                    format!("{failed} of {verified} entries failed verification"),
+                ),
+            ))
+        }
+    }
+}
+
+fn report_part(
+    mut out: impl Write,
+    format: Format,
+    digest: PartDigest,
+    path: &Path,
+) -> io::Result<()> {
+    let status = PartStatus {
+        part: digest.part_number,
+        path: path.display().to_string(),
+        size: digest.bytes_written,
+        sha256: hash::hex(digest.digest),
+    };
+    match format {
+        Format::Text => writeln!(out, "SHA256 ({}) = {}", status.path, status.sha256),
+        Format::Jsonl => {
+            serde_json::to_writer(&mut out, &status)?;
+            writeln!(out)
+        }
+    }
+}
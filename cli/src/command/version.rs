@@ -0,0 +1,177 @@
+use crate::{chunk::KNOWN_CHUNK_TYPES, command::Command};
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
+use std::io::{self, prelude::*};
+
+/// Version of the schema of the JSON output, incremented when a key is removed or its meaning changes.
+/// Keys may be added without changing it.
+const SCHEMA_VERSION: u32 = 1;
+/// Versions of the archive format, `major.minor` in the `AHED` chunk, that can be read and written.
+const ARCHIVE_FORMAT_VERSIONS: [&str; 1] = ["0.0"];
+/// Versions of the entry format, `major.minor` in the `FHED` and `SHED` chunks, that can be read and written.
+const ENTRY_FORMAT_VERSIONS: [&str; 1] = ["0.0"];
+
+#[derive(Parser, Clone, Eq, PartialEq, Hash, Debug)]
+pub(crate) struct VersionCommand {
+    #[arg(long, value_enum, default_value_t = Format::Text, help = "Output format")]
+    format: Format,
+}
+
+impl Command for VersionCommand {
+    #[inline]
+    fn execute(self) -> io::Result<()> {
+        print_version(self.format)
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, ValueEnum)]
+enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Serialize)]
+struct VersionInfo {
+    schema_version: u32,
+    versions: CrateVersions,
+    format: FormatVersions,
+    chunk_types: Vec<ChunkTypeSupport>,
+    features: Features,
+    algorithms: Algorithms,
+}
+
+#[derive(Serialize)]
+struct CrateVersions {
+    cli: &'static str,
+    pna: &'static str,
+    libpna: &'static str,
+}
+
+#[derive(Serialize)]
+struct FormatVersions {
+    archive: &'static [&'static str],
+    entry: &'static [&'static str],
+}
+
+#[derive(Serialize)]
+struct ChunkTypeSupport {
+    #[serde(rename = "type")]
+    ty: String,
+    critical: bool,
+    private: bool,
+    read: bool,
+    write: bool,
+}
+
+/// Features this binary is compiled with.
+#[derive(Serialize)]
+struct Features {
+    acl: bool,
+    memmap: bool,
+    http: bool,
+    #[serde(rename = "zlib-ng")]
+    zlib_ng: bool,
+}
+
+/// Algorithms that can be read and written.
+#[derive(Serialize)]
+struct Algorithms {
+    compression: ReadWrite,
+    encryption: ReadWrite,
+    cipher_mode: ReadWrite,
+    password_hash: ReadWrite,
+    key_wrap: ReadWrite,
+    signature: ReadWrite,
+}
+
+#[derive(Serialize)]
+struct ReadWrite {
+    read: &'static [&'static str],
+    write: &'static [&'static str],
+}
+
+impl ReadWrite {
+    #[inline]
+    const fn both(names: &'static [&'static str]) -> Self {
+        Self {
+            read: names,
+            write: names,
+        }
+    }
+}
+
+fn version_info() -> VersionInfo {
+    VersionInfo {
+        schema_version: SCHEMA_VERSION,
+        versions: CrateVersions {
+            cli: env!("CARGO_PKG_VERSION"),
+            pna: pna::VERSION,
+            libpna: pna::LIBPNA_VERSION,
+        },
+        format: FormatVersions {
+            archive: &ARCHIVE_FORMAT_VERSIONS,
+            entry: &ENTRY_FORMAT_VERSIONS,
+        },
+        chunk_types: KNOWN_CHUNK_TYPES
+            .iter()
+            .map(|ty| ChunkTypeSupport {
+                ty: ty.to_string(),
+                critical: ty.is_critical(),
+                private: ty.is_private(),
+                read: true,
+                write: true,
+            })
+            .collect(),
+        features: Features {
+            acl: cfg!(feature = "acl"),
+            memmap: cfg!(feature = "memmap"),
+            http: cfg!(feature = "http"),
+            zlib_ng: cfg!(feature = "zlib-ng"),
+        },
+        algorithms: Algorithms {
+            compression: ReadWrite::both(&["store", "deflate", "zstd", "xz"]),
+            encryption: ReadWrite::both(&["aes", "camellia"]),
+            cipher_mode: ReadWrite::both(&["cbc", "ctr"]),
+            // NOTE: Password hashes written by other implementations are verified with any variant.
+            password_hash: ReadWrite {
+                read: &[
+                    "argon2id",
+                    "argon2i",
+                    "argon2d",
+                    "pbkdf2-sha256",
+                    "pbkdf2-sha512",
+                ],
+                write: &["argon2id", "pbkdf2-sha256"],
+            },
+            key_wrap: ReadWrite::both(&["x25519"]),
+            signature: ReadWrite::both(&["ed25519"]),
+        },
+    }
+}
+
+fn print_version(format: Format) -> io::Result<()> {
+    let info = version_info();
+    let mut stdout = io::stdout().lock();
+    match format {
+        Format::Text => {
+            writeln!(stdout, "{} {}", env!("CARGO_PKG_NAME"), info.versions.cli)?;
+            writeln!(stdout, "pna {}", info.versions.pna)?;
+            writeln!(stdout, "libpna {}", info.versions.libpna)?;
+            writeln!(
+                stdout,
+                "Archive format versions: {}",
+                info.format.archive.join(", ")
+            )?;
+            writeln!(
+                stdout,
+                "Entry format versions: {}",
+                info.format.entry.join(", ")
+            )?;
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut stdout, &info)?;
+            writeln!(stdout)?;
+        }
+    }
+    Ok(())
+}
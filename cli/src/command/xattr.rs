@@ -1,5 +1,7 @@
 use crate::{
-    cli::{PasswordArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs},
+    cli::{
+        PasswordArgs, ReplaceArgs, SolidEntriesTransformStrategy, SolidEntriesTransformStrategyArgs,
+    },
     command::{
         ask_password,
         commons::{
@@ -7,6 +9,7 @@ use crate::{
         },
         Command,
     },
+    exit_code::usage_error,
     utils::{str::char_chunks, GlobPatterns, PathPartExt},
 };
 use base64::Engine;
@@ -81,6 +84,8 @@ pub(crate) struct SetXattrCommand {
     #[command(flatten)]
     transform_strategy: SolidEntriesTransformStrategyArgs,
     #[command(flatten)]
+    replace: ReplaceArgs,
+    #[command(flatten)]
     password: PasswordArgs,
 }
 
@@ -129,13 +134,12 @@ fn archive_get_xattr(args: GetXattrCommand) -> io::Result<()> {
     if args.files.is_empty() {
         return Ok(());
     }
-    let globs = GlobPatterns::new(args.files)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs = GlobPatterns::new(args.files).map_err(usage_error)?;
     let encoding = args.encoding;
 
     run_entries(
         &args.archive,
-        || password.as_deref(),
+        || password.as_deref().map(String::as_str),
         |entry| {
             let entry = entry?;
             let name = entry.header().path();
@@ -171,8 +175,7 @@ fn archive_set_xattr(args: SetXattrCommand) -> io::Result<()> {
     if args.files.is_empty() {
         return Ok(());
     }
-    let globs = GlobPatterns::new(args.files)
-        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let globs = GlobPatterns::new(args.files).map_err(usage_error)?;
     let value = args
         .value
         .as_ref()
@@ -182,7 +185,7 @@ fn archive_set_xattr(args: SetXattrCommand) -> io::Result<()> {
         SolidEntriesTransformStrategy::UnSolid => run_transform_entry(
             args.archive.remove_part().unwrap(),
             &args.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 if globs.matches_any(entry.header().path()) {
@@ -197,11 +200,12 @@ fn archive_set_xattr(args: SetXattrCommand) -> io::Result<()> {
                 }
             },
             TransformStrategyUnSolid,
+            &args.replace,
         ),
         SolidEntriesTransformStrategy::KeepSolid => run_transform_entry(
             args.archive.remove_part().unwrap(),
             &args.archive,
-            || password.as_deref(),
+            || password.as_deref().map(String::as_str),
             |entry| {
                 let entry = entry?;
                 if globs.matches_any(entry.header().path()) {
@@ -216,6 +220,7 @@ fn archive_set_xattr(args: SetXattrCommand) -> io::Result<()> {
                 }
             },
             TransformStrategyKeepSolid,
+            &args.replace,
         ),
     }
 }
@@ -230,18 +235,20 @@ fn transform_entry<T>(
     let mut xattrs = entry
         .xattrs()
         .iter()
-        .map(|it| (it.name(), it.value()))
+        .map(|it| (it.name(), (it.value(), it.is_compressed())))
         .collect::<IndexMap<_, _>>();
     if let Some(name) = name {
         let map_entry = xattrs.entry(name);
-        map_entry.or_insert(value);
+        map_entry.or_insert((value, false));
     }
     if let Some(name) = remove {
         xattrs.shift_remove_entry(name);
     }
     let xattrs = xattrs
         .into_iter()
-        .map(|(key, value)| pna::ExtendedAttribute::new(key.into(), value.into()))
+        .map(|(key, (value, compressed))| {
+            pna::ExtendedAttribute::new(key.into(), value.into()).with_compression(compressed)
+        })
         .collect::<Vec<_>>();
     entry.with_xattrs(&xattrs)
 }
@@ -0,0 +1,269 @@
+//! Exit codes of the command line interface.
+//!
+//! | Code | Meaning                                                     |
+//! |------|-------------------------------------------------------------|
+//! | 0    | Success                                                     |
+//! | 1    | Generic error                                               |
+//! | 2    | Partial success (some entries failed or patterns unmatched) |
+//! | 3    | Usage error (invalid command line arguments)                |
+//! | 4    | Password or cryptographic error                             |
+//! | 5    | Corrupt archive                                             |
+use std::{error::Error, fmt, io, process};
+
+/// Classification of the result of a command, mapped to a process exit code.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[repr(u8)]
+pub enum ExitStatus {
+    /// The command succeeded.
+    Success = 0,
+    /// The command failed for a reason not covered by the other statuses.
+    Failure = 1,
+    /// The command completed, but some entries failed or some patterns did not match.
+    PartialFailure = 2,
+    /// The command line arguments were invalid.
+    Usage = 3,
+    /// The password or identity was missing or wrong, or decryption failed.
+    Crypto = 4,
+    /// The archive is corrupted.
+    CorruptArchive = 5,
+}
+
+impl ExitStatus {
+    /// Classifies the error returned by a command.
+    #[inline]
+    pub fn from_error(error: &io::Error) -> Self {
+        if let Some(classified) = error
+            .get_ref()
+            .and_then(|it| it.downcast_ref::<ClassifiedError>())
+        {
+            return classified.status;
+        }
+        // NOTE: InvalidInput is not taken as a usage error, since the library also returns it
+        //       for broken data and wrong passwords. Usage errors are attached by [usage_error].
+        match error.kind() {
+            io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => Self::CorruptArchive,
+            _ => Self::Failure,
+        }
+    }
+
+    /// Classifies the error returned by the command line parser.
+    #[inline]
+    pub fn from_clap_error(error: &clap::Error) -> Self {
+        if error.use_stderr() {
+            Self::Usage
+        } else {
+            Self::Success
+        }
+    }
+}
+
+impl TryFrom<u8> for ExitStatus {
+    type Error = String;
+
+    #[inline]
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Ok(match value {
+            0 => Self::Success,
+            1 => Self::Failure,
+            2 => Self::PartialFailure,
+            3 => Self::Usage,
+            4 => Self::Crypto,
+            5 => Self::CorruptArchive,
+            _ => return Err(format!("unknown exit code {value}, expected 0 to 5")),
+        })
+    }
+}
+
+impl From<ExitStatus> for process::ExitCode {
+    #[inline]
+    fn from(value: ExitStatus) -> Self {
+        Self::from(value as u8)
+    }
+}
+
+#[derive(Debug)]
+struct ClassifiedError {
+    status: ExitStatus,
+    source: io::Error,
+}
+
+impl fmt::Display for ClassifiedError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.source, f)
+    }
+}
+
+impl Error for ClassifiedError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source.source()
+    }
+}
+
+/// Attaches `status` to `error`, keeping its kind and message.
+#[inline]
+pub(crate) fn with_status(status: ExitStatus, error: io::Error) -> io::Error {
+    io::Error::new(
+        error.kind(),
+        ClassifiedError {
+            status,
+            source: error,
+        },
+    )
+}
+
+/// Returns an error of the command line arguments, which exits with [ExitStatus::Usage].
+#[inline]
+pub(crate) fn usage_error<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn Error + Send + Sync>>,
+{
+    with_status(
+        ExitStatus::Usage,
+        io::Error::new(io::ErrorKind::InvalidInput, error),
+    )
+}
+
+/// Returns `true` if `error` has a status attached by [with_status].
+#[inline]
+pub(crate) fn has_status(error: &io::Error) -> bool {
+    error.get_ref().is_some_and(|it| it.is::<ClassifiedError>())
+}
+
+/// Reader that attaches a status to every read error of the inner reader.
+pub(crate) struct StatusReader<R> {
+    inner: R,
+    status: ExitStatus,
+}
+
+impl<R> StatusReader<R> {
+    #[inline]
+    pub(crate) const fn new(inner: R, status: ExitStatus) -> Self {
+        Self { inner, status }
+    }
+}
+
+impl<R: io::Read> io::Read for StatusReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner
+            .read(buf)
+            .map_err(|e| with_status(self.status, e))
+    }
+}
+
+/// Decides whether a batch command stops at the first per-entry error or continues.
+pub(crate) struct FailurePolicy {
+    fail_fast: bool,
+    failures: usize,
+}
+
+impl FailurePolicy {
+    #[inline]
+    pub(crate) const fn new(fail_fast: bool) -> Self {
+        Self {
+            fail_fast,
+            failures: 0,
+        }
+    }
+
+    /// Returns the error when failing fast, otherwise reports and records it and returns `None`.
+    #[inline]
+    pub(crate) fn check<T>(&mut self, result: io::Result<T>) -> io::Result<Option<T>> {
+        match result {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if self.fail_fast => Err(e),
+            Err(e) => {
+                log::error!("{e}");
+                self.failures += 1;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns a partial failure error if any error was recorded.
+    #[inline]
+    pub(crate) fn finish(self) -> io::Result<()> {
+        if self.failures == 0 {
+            return Ok(());
+        }
+        Err(with_status(
+            ExitStatus::PartialFailure,
+            io::Error::other(format!("{} entries failed", self.failures)),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_by_kind() {
+        assert_eq!(
+            ExitStatus::from_error(&io::Error::from(io::ErrorKind::NotFound)),
+            ExitStatus::Failure
+        );
+        assert_eq!(
+            ExitStatus::from_error(&io::Error::from(io::ErrorKind::InvalidData)),
+            ExitStatus::CorruptArchive
+        );
+        assert_eq!(
+            ExitStatus::from_error(&io::Error::from(io::ErrorKind::InvalidInput)),
+            ExitStatus::Failure
+        );
+    }
+
+    #[test]
+    fn classify_usage_error() {
+        let error = usage_error("invalid pattern");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(error.to_string(), "invalid pattern");
+        assert_eq!(ExitStatus::from_error(&error), ExitStatus::Usage);
+    }
+
+    #[test]
+    fn exit_status_from_code() {
+        for status in [
+            ExitStatus::Success,
+            ExitStatus::Failure,
+            ExitStatus::PartialFailure,
+            ExitStatus::Usage,
+            ExitStatus::Crypto,
+            ExitStatus::CorruptArchive,
+        ] {
+            assert_eq!(ExitStatus::try_from(status as u8), Ok(status));
+        }
+        assert!(ExitStatus::try_from(6).is_err());
+    }
+
+    #[test]
+    fn classify_attached_status() {
+        let error = with_status(
+            ExitStatus::Crypto,
+            io::Error::new(io::ErrorKind::InvalidData, "decryption failed"),
+        );
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "decryption failed");
+        assert_eq!(ExitStatus::from_error(&error), ExitStatus::Crypto);
+    }
+
+    #[test]
+    fn no_fail_fast_policy() {
+        let mut policy = FailurePolicy::new(false);
+        assert_eq!(policy.check(Ok(1)).unwrap(), Some(1));
+        assert_eq!(
+            policy.check::<i32>(Err(io::Error::other("e"))).unwrap(),
+            None
+        );
+        let error = policy.finish().unwrap_err();
+        assert_eq!(ExitStatus::from_error(&error), ExitStatus::PartialFailure);
+    }
+
+    #[test]
+    fn fail_fast_policy() {
+        let mut policy = FailurePolicy::new(true);
+        assert!(policy.check::<i32>(Err(io::Error::other("e"))).is_err());
+    }
+}
@@ -1,10 +1,11 @@
-use crate::chunk::{self, Ace, AcePlatform, AceWithPlatform};
+use crate::chunk::{self, Ace, AcePlatform, AceWithPlatform, Platforms};
 use pna::{prelude::*, NormalEntry, RawChunk};
 use std::collections::HashMap;
 use std::io;
 
 pub(crate) trait NormalEntryExt {
     fn acl(&self) -> io::Result<HashMap<AcePlatform, Vec<Ace>>>;
+    fn platforms(&self) -> io::Result<Option<Platforms>>;
 }
 
 impl<T> NormalEntryExt for NormalEntry<T>
@@ -35,4 +36,13 @@ where
         }
         Ok(acls)
     }
+
+    #[inline]
+    fn platforms(&self) -> io::Result<Option<Platforms>> {
+        self.extra_chunks()
+            .iter()
+            .find(|c| c.ty() == chunk::fPLT)
+            .map(|c| Platforms::try_from(c.data()).map_err(io::Error::other))
+            .transpose()
+    }
 }
@@ -1,5 +1,6 @@
 mod chunk;
 pub mod cli;
 pub mod command;
+pub mod exit_code;
 mod ext;
 mod utils;
@@ -1,9 +1,17 @@
-use clap::Parser;
-use portable_network_archive::{cli, command::Command};
-use std::io;
+use portable_network_archive::{cli, command::Command, exit_code::ExitStatus};
+use std::process::ExitCode;
 
-fn main() -> io::Result<()> {
-    let cli = cli::Cli::parse();
-    cli.init_logger()?;
-    cli.execute()
+fn main() -> ExitCode {
+    let cli = match cli::Cli::try_parse_with_defaults() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return ExitStatus::from_clap_error(&e).into();
+        }
+    };
+    if let Err(e) = cli.init_logger().and_then(|_| cli.execute()) {
+        eprintln!("error: {e}");
+        return ExitStatus::from_error(&e).into();
+    }
+    ExitCode::SUCCESS
 }
@@ -1,14 +1,23 @@
 #[cfg(feature = "acl")]
 pub(crate) mod acl;
+pub(crate) mod dedup;
 pub(crate) mod env;
 pub(crate) mod fmt;
 pub(crate) mod fs;
 mod globs;
+pub(crate) mod hash;
+pub(crate) mod id_map;
 mod io;
 #[cfg(feature = "memmap")]
 pub(crate) mod mmap;
 pub(crate) mod os;
 mod path;
+pub(crate) mod progress;
+pub(crate) mod resume;
+pub(crate) mod solid_cache;
 pub(crate) mod str;
+pub(crate) mod transform;
+pub(crate) mod url;
+pub(crate) mod zstd_dict;
 
 pub(crate) use {globs::*, path::*};
@@ -1,4 +1,2 @@
-#[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "macos"))]
-pub use super::os::unix::acl::*;
-#[cfg(windows)]
-pub use super::os::windows::acl::*;
+#[cfg(any(test, target_os = "netbsd", target_os = "illumos"))]
+pub(crate) mod posix;
@@ -0,0 +1,361 @@
+//! Text form of POSIX.1e ACLs, as read by `acl_from_text(3)` and written by `acl_to_text(3)`.
+use crate::chunk::{Ace, Flag, Identifier, OwnerType, Permission};
+use std::io;
+
+const DEFAULT_PREFIX: &str = "default:";
+
+/// Parses the entries of an ACL in the text form.
+///
+/// Entries are separated by commas or newlines, and the comments starting with `#` are ignored.
+/// The entries are flagged as [Flag::DEFAULT] if `default` is set or if they have the `default:` prefix.
+pub(crate) fn parse(text: &str, default: bool) -> io::Result<Vec<Ace>> {
+    text.split(['\n', ','])
+        .map(|it| it.split_once('#').map_or(it, |(entry, _)| entry).trim())
+        .filter(|it| !it.is_empty())
+        .map(|it| parse_entry(it, default))
+        .collect()
+}
+
+fn parse_entry(entry: &str, default: bool) -> io::Result<Ace> {
+    let (entry, default) = match entry
+        .strip_prefix(DEFAULT_PREFIX)
+        .or_else(|| entry.strip_prefix("d:"))
+    {
+        Some(entry) => (entry, true),
+        None => (entry, default),
+    };
+    let fields = entry.split(':').collect::<Vec<_>>();
+    // NOTE: NFSv4 ACLs, such as the ones of ZFS, have the entries like `owner@`.
+    if !matches!(
+        fields[0],
+        "u" | "user" | "g" | "group" | "m" | "mask" | "o" | "other"
+    ) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("unsupported acl entry `{entry}`"),
+        ));
+    }
+    let (tag, qualifier, perms) = match fields[..] {
+        [tag, qualifier, perms] => (tag, qualifier, perms),
+        // NOTE: illumos omits the empty qualifier of mask and other entries.
+        [tag @ ("m" | "mask" | "o" | "other"), perms] => (tag, "", perms),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid acl entry `{entry}`"),
+            ))
+        }
+    };
+    let owner_type = match (tag, qualifier) {
+        ("u" | "user", "") => OwnerType::Owner,
+        ("u" | "user", name) => OwnerType::User(Identifier(name.into())),
+        ("g" | "group", "") => OwnerType::OwnerGroup,
+        ("g" | "group", name) => OwnerType::Group(Identifier(name.into())),
+        ("m" | "mask", _) => OwnerType::Mask,
+        _ => OwnerType::Other,
+    };
+    let mut permission = Permission::empty();
+    for c in perms.chars() {
+        match c {
+            'r' => permission.insert(Permission::READ),
+            'w' => permission.insert(Permission::WRITE),
+            'x' => permission.insert(Permission::EXECUTE),
+            '-' => {}
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid permissions of acl entry `{entry}`"),
+                ))
+            }
+        }
+    }
+    Ok(Ace {
+        flags: if default {
+            Flag::DEFAULT
+        } else {
+            Flag::empty()
+        },
+        owner_type,
+        allow: true,
+        permission,
+    })
+}
+
+/// Writes `entries` in the text form separated by commas, with the `default:` prefix if `default` is set.
+pub(crate) fn to_text<'a>(entries: impl IntoIterator<Item = &'a Ace>, default: bool) -> String {
+    let prefix = if default { DEFAULT_PREFIX } else { "" };
+    entries
+        .into_iter()
+        .map(|ace| {
+            let (tag, qualifier) = match &ace.owner_type {
+                OwnerType::Owner => ("user", ""),
+                OwnerType::User(name) => ("user", name.0.as_str()),
+                OwnerType::OwnerGroup => ("group", ""),
+                OwnerType::Group(name) => ("group", name.0.as_str()),
+                OwnerType::Mask => ("mask", ""),
+                OwnerType::Other => ("other", ""),
+            };
+            let perm = |p, c| if ace.permission.contains(p) { c } else { '-' };
+            format!(
+                "{prefix}{tag}:{qualifier}:{}{}{}",
+                perm(Permission::READ, 'r'),
+                perm(Permission::WRITE, 'w'),
+                perm(Permission::EXECUTE, 'x'),
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Splits `entries` into the access ACL and the default ACL whose entries are not flagged anymore.
+///
+/// Deny entries cannot be represented in POSIX.1e ACLs, so they are dropped with a warning.
+pub(crate) fn split_default(entries: Vec<Ace>) -> (Vec<Ace>, Vec<Ace>) {
+    let (default, access): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .filter(|it| {
+            if !it.allow {
+                log::warn!("Deny acl entry `{it}` is not supported on this platform, skipping");
+            }
+            it.allow
+        })
+        .partition(|it| it.flags.contains(Flag::DEFAULT));
+    let default = default
+        .into_iter()
+        .map(|mut it| {
+            it.flags.remove(Flag::DEFAULT);
+            it
+        })
+        .collect();
+    (access, default)
+}
+
+/// Adds the entries required by POSIX.1e that `entries` lacks, taking their permissions from `mode`.
+///
+/// The owner, the owning group and the others are required, and the mask is required
+/// when there are entries of named users or groups.
+pub(crate) fn complete(entries: &mut Vec<Ace>, mode: u32) {
+    let bits = |shift: u32| {
+        let mut permission = Permission::empty();
+        permission.set(Permission::READ, (mode >> shift) & 0o4 != 0);
+        permission.set(Permission::WRITE, (mode >> shift) & 0o2 != 0);
+        permission.set(Permission::EXECUTE, (mode >> shift) & 0o1 != 0);
+        permission
+    };
+    let required = [
+        (OwnerType::Owner, bits(6)),
+        (OwnerType::OwnerGroup, bits(3)),
+        (OwnerType::Other, bits(0)),
+    ];
+    for (owner_type, permission) in required {
+        if !entries.iter().any(|it| it.owner_type == owner_type) {
+            entries.push(Ace {
+                flags: Flag::empty(),
+                owner_type,
+                allow: true,
+                permission,
+            });
+        }
+    }
+    let named = entries
+        .iter()
+        .filter(|it| matches!(it.owner_type, OwnerType::User(_) | OwnerType::Group(_)));
+    let has_mask = entries.iter().any(|it| it.owner_type == OwnerType::Mask);
+    if named.clone().next().is_some() && !has_mask {
+        // NOTE: The mask grants the union of the permissions of the group class.
+        let permission = named
+            .chain(
+                entries
+                    .iter()
+                    .filter(|it| it.owner_type == OwnerType::OwnerGroup),
+            )
+            .fold(Permission::empty(), |permission, it| {
+                permission | it.permission
+            });
+        entries.push(Ace {
+            flags: Flag::empty(),
+            owner_type: OwnerType::Mask,
+            allow: true,
+            permission,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ace(flags: Flag, owner_type: OwnerType, permission: Permission) -> Ace {
+        Ace {
+            flags,
+            owner_type,
+            allow: true,
+            permission,
+        }
+    }
+
+    #[test]
+    fn parse_long_text() {
+        let text =
+            "user::rw-\nuser:alice:r--\t\t#effective:r--\ngroup::r-x\nmask::r--\nother::---\n";
+        assert_eq!(
+            parse(text, false).unwrap(),
+            [
+                ace(
+                    Flag::empty(),
+                    OwnerType::Owner,
+                    Permission::READ | Permission::WRITE
+                ),
+                ace(
+                    Flag::empty(),
+                    OwnerType::User(Identifier("alice".into())),
+                    Permission::READ
+                ),
+                ace(
+                    Flag::empty(),
+                    OwnerType::OwnerGroup,
+                    Permission::READ | Permission::EXECUTE
+                ),
+                ace(Flag::empty(), OwnerType::Mask, Permission::READ),
+                ace(Flag::empty(), OwnerType::Other, Permission::empty()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_illumos_text() {
+        let text = "user::rwx,group:staff:r-x,mask:r-x,other:r--,default:user::rwx";
+        assert_eq!(
+            parse(text, false).unwrap(),
+            [
+                ace(
+                    Flag::empty(),
+                    OwnerType::Owner,
+                    Permission::READ | Permission::WRITE | Permission::EXECUTE
+                ),
+                ace(
+                    Flag::empty(),
+                    OwnerType::Group(Identifier("staff".into())),
+                    Permission::READ | Permission::EXECUTE
+                ),
+                ace(
+                    Flag::empty(),
+                    OwnerType::Mask,
+                    Permission::READ | Permission::EXECUTE
+                ),
+                ace(Flag::empty(), OwnerType::Other, Permission::READ),
+                ace(
+                    Flag::DEFAULT,
+                    OwnerType::Owner,
+                    Permission::READ | Permission::WRITE | Permission::EXECUTE
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_default_acl() {
+        assert_eq!(
+            parse("user::r--", true).unwrap(),
+            [ace(Flag::DEFAULT, OwnerType::Owner, Permission::READ)]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_text() {
+        let err = parse("user::rwz", false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = parse("user:rw-", false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = parse("owner@:rw-p--aARWcCos:-------:allow", false).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let entries = [
+            ace(
+                Flag::empty(),
+                OwnerType::Owner,
+                Permission::READ | Permission::WRITE,
+            ),
+            ace(
+                Flag::empty(),
+                OwnerType::Group(Identifier("staff".into())),
+                Permission::READ,
+            ),
+            ace(Flag::empty(), OwnerType::Mask, Permission::READ),
+            ace(Flag::empty(), OwnerType::Other, Permission::empty()),
+        ];
+        let text = to_text(&entries, false);
+        assert_eq!(text, "user::rw-,group:staff:r--,mask::r--,other::---");
+        assert_eq!(parse(&text, false).unwrap(), entries);
+
+        let text = to_text(&entries[..1], true);
+        assert_eq!(text, "default:user::rw-");
+        assert_eq!(
+            parse(&text, false).unwrap(),
+            [ace(
+                Flag::DEFAULT,
+                OwnerType::Owner,
+                Permission::READ | Permission::WRITE
+            )]
+        );
+    }
+
+    #[test]
+    fn split_default_entries() {
+        let mut deny = ace(Flag::empty(), OwnerType::Other, Permission::READ);
+        deny.allow = false;
+        let (access, default) = split_default(vec![
+            ace(Flag::empty(), OwnerType::Owner, Permission::READ),
+            ace(Flag::DEFAULT, OwnerType::Owner, Permission::WRITE),
+            deny,
+        ]);
+        assert_eq!(
+            access,
+            [ace(Flag::empty(), OwnerType::Owner, Permission::READ)]
+        );
+        assert_eq!(
+            default,
+            [ace(Flag::empty(), OwnerType::Owner, Permission::WRITE)]
+        );
+    }
+
+    #[test]
+    fn complete_required_entries() {
+        let mut entries = vec![ace(
+            Flag::empty(),
+            OwnerType::User(Identifier("alice".into())),
+            Permission::WRITE,
+        )];
+        complete(&mut entries, 0o640);
+        assert_eq!(
+            entries,
+            [
+                ace(
+                    Flag::empty(),
+                    OwnerType::User(Identifier("alice".into())),
+                    Permission::WRITE
+                ),
+                ace(
+                    Flag::empty(),
+                    OwnerType::Owner,
+                    Permission::READ | Permission::WRITE
+                ),
+                ace(Flag::empty(), OwnerType::OwnerGroup, Permission::READ),
+                ace(Flag::empty(), OwnerType::Other, Permission::empty()),
+                ace(
+                    Flag::empty(),
+                    OwnerType::Mask,
+                    Permission::READ | Permission::WRITE
+                ),
+            ]
+        );
+
+        let mut entries = vec![ace(Flag::empty(), OwnerType::Owner, Permission::READ)];
+        complete(&mut entries, 0o777);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].permission, Permission::READ);
+    }
+}
@@ -0,0 +1,139 @@
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+/// Size of the buffers used to compare the contents of the files.
+const COMPARE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Finds the regular files in `items` whose contents are the same as an earlier file in `items`,
+/// and returns the map of them to the first file with the same contents.
+///
+/// Only the files that have the same size as another file are hashed, and the files with the same hash
+/// are compared byte by byte so that a hash collision never links different files.
+/// Symbolic links and empty files are never linked, and files that cannot be read are left to fail when archived.
+pub(crate) fn find_duplicates(items: &[PathBuf]) -> HashMap<PathBuf, PathBuf> {
+    // NOTE: Symbolic links are archived as links even with `--follow-links`, so they are not candidates.
+    let sizes = items
+        .iter()
+        .filter_map(|path| match fs::symlink_metadata(path) {
+            Ok(meta) if meta.is_file() && meta.len() != 0 => Some((path, meta.len())),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut counts = HashMap::<u64, usize>::new();
+    for (_, size) in &sizes {
+        *counts.entry(*size).or_default() += 1;
+    }
+    let candidates = sizes
+        .into_iter()
+        .filter(|(_, size)| counts[size] > 1)
+        .map(|(path, _)| path)
+        .collect::<Vec<_>>();
+    let digests = if rayon::current_thread_index().is_some() {
+        candidates
+            .par_iter()
+            .map(|path| digest(path).ok())
+            .collect::<Vec<_>>()
+    } else {
+        candidates
+            .iter()
+            .map(|path| digest(path).ok())
+            .collect::<Vec<_>>()
+    };
+
+    let mut originals = HashMap::<[u8; 32], Vec<&PathBuf>>::new();
+    let mut duplicates = HashMap::new();
+    for (path, digest) in candidates.into_iter().zip(digests) {
+        let Some(digest) = digest else {
+            continue;
+        };
+        let originals = originals.entry(digest).or_default();
+        match originals
+            .iter()
+            .find(|original| same_contents(original, path).unwrap_or(false))
+        {
+            Some(original) => {
+                log::debug!("{} is the same as {}", path.display(), original.display());
+                duplicates.insert(path.clone(), (*original).clone());
+            }
+            None => originals.push(path),
+        }
+    }
+    duplicates
+}
+
+/// Computes the SHA-256 digest of the file at `path` without reading the whole file into memory.
+fn digest(path: &Path) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+/// Returns `true` if the files at `a` and `b` have the same contents.
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    let mut a = io::BufReader::with_capacity(COMPARE_BUFFER_SIZE, fs::File::open(a)?);
+    let mut b = io::BufReader::with_capacity(COMPARE_BUFFER_SIZE, fs::File::open(b)?);
+    let mut buf_a = vec![0; COMPARE_BUFFER_SIZE];
+    let mut buf_b = vec![0; COMPARE_BUFFER_SIZE];
+    loop {
+        let n = read_full(&mut a, &mut buf_a)?;
+        if n != read_full(&mut b, &mut buf_b)? || buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+        if n == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Reads into `buf` until it is full or the end of `reader`, and returns the number of bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicates() {
+        let dir = std::env::temp_dir().join("pna_find_duplicates");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let files = [
+            ("a.txt", "same"),
+            ("b.txt", "diff"),
+            ("c.txt", "same"),
+            ("d.txt", ""),
+            ("e.txt", ""),
+            ("f.txt", "same"),
+        ]
+        .map(|(name, contents)| {
+            let path = dir.join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        });
+        let duplicates = find_duplicates(&files);
+        assert_eq!(
+            duplicates,
+            HashMap::from([
+                (files[2].clone(), files[0].clone()),
+                (files[5].clone(), files[0].clone()),
+            ])
+        );
+    }
+}
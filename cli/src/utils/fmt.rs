@@ -1,3 +1,4 @@
+use pna::DataKind;
 use std::fmt;
 use std::time::Duration;
 
@@ -31,6 +32,21 @@ impl fmt::Display for DurationDisplay {
         }
     }
 }
+/// Displays the kind of an entry in words.
+pub(crate) struct DataKindDisplay(pub(crate) DataKind);
+
+impl fmt::Display for DataKindDisplay {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self.0 {
+            DataKind::File => "file",
+            DataKind::Directory => "directory",
+            DataKind::SymbolicLink => "symbolic link",
+            DataKind::HardLink => "hard link",
+        })
+    }
+}
+
 const SECOND: Duration = Duration::from_secs(1);
 const MINUTE: Duration = Duration::from_secs(60);
 const HOUR: Duration = Duration::from_secs(60 * 60);
@@ -11,11 +11,67 @@ use std::{
     path::Path,
 };
 
+#[cfg(unix)]
+type FileIdInner = (u64, u64);
+#[cfg(windows)]
+type FileIdInner = (u32, u64);
+#[cfg(not(any(unix, windows)))]
+type FileIdInner = std::path::PathBuf;
+
+/// Identity of a file, equal for every path referring to the same file.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct FileId(FileIdInner);
+
+impl FileId {
+    /// Returns the identity of the file at `path`.
+    /// It is the device and inode number on unix, the volume serial number and file index on Windows,
+    /// and the canonical path on other platforms.
+    #[inline]
+    pub(crate) fn of<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        #[cfg(unix)]
+        fn inner(path: &Path) -> io::Result<FileIdInner> {
+            use std::os::unix::fs::MetadataExt;
+            let meta = fs::symlink_metadata(path)?;
+            Ok((meta.dev(), meta.ino()))
+        }
+        #[cfg(windows)]
+        fn inner(path: &Path) -> io::Result<FileIdInner> {
+            file_index(path)
+        }
+        #[cfg(not(any(unix, windows)))]
+        fn inner(path: &Path) -> io::Result<FileIdInner> {
+            fs::canonicalize(path)
+        }
+        inner(path.as_ref()).map(Self)
+    }
+}
+
 pub(crate) fn is_pna<P: AsRef<Path>>(path: P) -> io::Result<bool> {
     let file = fs::File::open(path)?;
     super::io::is_pna(file)
 }
 
+/// Returns whether the filesystem of the directory `dir` is case-insensitive,
+/// by creating a file with a lowercase name in it and looking the file up by the uppercase name.
+pub(crate) fn is_case_insensitive<P: AsRef<Path>>(dir: P) -> io::Result<bool> {
+    fn inner(dir: &Path) -> io::Result<bool> {
+        let name = format!(".pna-case-probe-{:016x}", rand::random::<u64>());
+        let path = dir.join(&name);
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let found = fs::symlink_metadata(dir.join(name.to_uppercase()));
+        fs::remove_file(&path)?;
+        match found {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+    inner(dir.as_ref())
+}
+
 #[inline]
 pub(crate) fn remove<P: AsRef<Path>>(path: P) -> io::Result<()> {
     fn inner(path: &Path) -> io::Result<()> {
@@ -58,6 +114,43 @@ pub(crate) fn mv<Src: AsRef<Path>, Dist: AsRef<Path>>(src: Src, dist: Dist) -> i
     inner(src.as_ref(), dist.as_ref())
 }
 
+/// Creates `path` and all of its missing parents like [fs::create_dir_all].
+/// When `mode` is given, every directory created is given the mode, while existing directories are left as is.
+/// `mode` is ignored on platforms other than unix.
+#[inline]
+pub(crate) fn create_dir_all_with_mode<P: AsRef<Path>>(
+    path: P,
+    mode: Option<u32>,
+) -> io::Result<()> {
+    #[cfg(unix)]
+    fn create_dir(path: &Path, mode: u32) -> io::Result<()> {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        fs::DirBuilder::new().mode(mode).create(path)?;
+        // NOTE: The directory is created with the mode masked by umask, so set the mode explicitly.
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+    }
+    #[cfg(not(unix))]
+    fn create_dir(path: &Path, _mode: u32) -> io::Result<()> {
+        fs::create_dir(path)
+    }
+    fn inner(path: &Path, mode: u32) -> io::Result<()> {
+        if path.is_dir() {
+            return Ok(());
+        }
+        if let Some(parent) = path.parent().filter(|it| !it.as_os_str().is_empty()) {
+            inner(parent, mode)?;
+        }
+        match create_dir(path, mode) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && path.is_dir() => Ok(()),
+            result => result,
+        }
+    }
+    match mode {
+        Some(mode) => inner(path.as_ref(), mode),
+        None => fs::create_dir_all(path),
+    }
+}
+
 pub(crate) fn read_to_lines<P: AsRef<Path>>(path: P) -> io::Result<Vec<String>> {
     fn inner(path: &Path) -> io::Result<Vec<String>> {
         let file = fs::File::open(path)?;
@@ -67,23 +160,32 @@ pub(crate) fn read_to_lines<P: AsRef<Path>>(path: P) -> io::Result<Vec<String>>
     inner(path.as_ref())
 }
 
-#[cfg(any(windows, unix))]
+#[cfg(windows)]
 pub(crate) fn chown<P: AsRef<Path>>(
     path: P,
     owner: Option<User>,
     group: Option<Group>,
 ) -> io::Result<()> {
-    #[cfg(windows)]
     fn inner(path: &Path, owner: Option<User>, group: Option<Group>) -> io::Result<()> {
         windows::fs::chown(path.as_ref(), owner, group)
     }
-    #[cfg(unix)]
-    fn inner(path: &Path, owner: Option<User>, group: Option<Group>) -> io::Result<()> {
-        std::os::unix::fs::chown(
-            path,
-            owner.map(|it| it.as_raw()),
-            group.map(|it| it.as_raw()),
-        )
-    }
     inner(path.as_ref(), owner, group)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn probe_case_sensitive_filesystem() {
+        let dir = std::env::temp_dir();
+        assert!(!is_case_insensitive(&dir).unwrap());
+        // NOTE: The probe file is removed.
+        assert!(!fs::read_dir(&dir).unwrap().any(|it| it
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .starts_with(".pna-case-probe-")));
+    }
+}
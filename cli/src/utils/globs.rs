@@ -1,40 +1,336 @@
-use std::path::Path;
+use crate::exit_code::{with_status, ExitStatus};
+use std::{
+    borrow::Cow,
+    io,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
-pub(crate) struct GlobPatterns(globset::GlobSet);
+/// Characters that start the non literal part of a glob pattern.
+const GLOB_META_CHARS: [char; 6] = ['*', '?', '[', ']', '{', '\\'];
+
+pub(crate) struct GlobPatterns {
+    /// The patterns without a literal prefix, which are matched against every path.
+    set: globset::GlobSet,
+    /// Indexes in `patterns` of the patterns in `set`.
+    set_indexes: Vec<usize>,
+    /// The patterns with a literal prefix, which are matched only against the paths starting with it.
+    prefixed: PrefixIndex,
+    patterns: Vec<String>,
+    matched: Vec<AtomicBool>,
+}
+
+/// Literal prefixes of the glob patterns sorted for binary search, to shortlist the patterns
+/// that may match a path before running them.
+struct PrefixIndex {
+    /// The literal prefixes and the indexes of their patterns, sorted by the prefix.
+    prefixes: Vec<(String, usize)>,
+    /// The distinct lengths of the prefixes in ascending order.
+    lengths: Vec<usize>,
+    /// The patterns, indexed by the index of the pattern.
+    // NOTE: Each pattern is matched by a set of its own, since a set may not match the same paths
+    // as a `globset::GlobMatcher` of the pattern.
+    sets: Vec<Option<globset::GlobSet>>,
+}
+
+impl PrefixIndex {
+    /// Calls `f` with the index of each pattern that matches `path`, whose normalized bytes are `name`.
+    fn for_each_match(&self, path: &Path, name: &[u8], mut f: impl FnMut(usize)) {
+        for &len in &self.lengths {
+            let Some(key) = name.get(..len) else {
+                break;
+            };
+            let start = self
+                .prefixes
+                .partition_point(|(prefix, _)| prefix.as_bytes() < key);
+            for (_, i) in self.prefixes[start..]
+                .iter()
+                .take_while(|(prefix, _)| prefix.as_bytes() == key)
+            {
+                if self.sets[*i].as_ref().is_some_and(|set| set.is_match(path)) {
+                    f(*i);
+                }
+            }
+        }
+    }
+}
+
+#[inline]
+fn glob_set(
+    globs: impl IntoIterator<Item = globset::Glob>,
+) -> Result<globset::GlobSet, globset::Error> {
+    let mut builder = globset::GlobSet::builder();
+    for glob in globs {
+        builder.add(glob);
+    }
+    builder.build()
+}
+
+/// Returns the literal part of `pattern` before its first glob meta character.
+#[inline]
+fn literal_prefix(pattern: &str) -> &str {
+    pattern
+        .find(GLOB_META_CHARS)
+        .map_or(pattern, |end| &pattern[..end])
+}
+
+/// Returns the bytes of `path` as compared by the glob patterns.
+#[inline]
+fn normalized_bytes(path: &Path) -> Cow<'_, [u8]> {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    // NOTE: Glob patterns match the paths with their separators normalized to `/` on windows.
+    if cfg!(windows) && bytes.contains(&b'\\') {
+        Cow::Owned(
+            bytes
+                .iter()
+                .map(|b| if *b == b'\\' { b'/' } else { *b })
+                .collect(),
+        )
+    } else {
+        Cow::Borrowed(bytes)
+    }
+}
+
+/// Returns `pattern` without the leading `./` and the trailing `/`, which entry names never have.
+#[inline]
+fn normalize_pattern(pattern: &str) -> &str {
+    let mut pattern = pattern;
+    while let Some(rest) = pattern.strip_prefix("./").filter(|it| !it.is_empty()) {
+        pattern = rest.trim_start_matches('/');
+    }
+    match pattern.trim_end_matches('/') {
+        "" => pattern,
+        trimmed => trimmed,
+    }
+}
+
+/// Returns the globs matching the paths that `pattern` matches, with or without `recursion`.
+#[inline]
+fn pattern_globs(pattern: &str, recursion: bool) -> Result<Vec<globset::Glob>, globset::Error> {
+    let pattern = normalize_pattern(pattern);
+    let mut globs = vec![globset::Glob::new(pattern)?];
+    if recursion && !pattern.is_empty() && literal_prefix(pattern) == pattern {
+        globs.push(globset::Glob::new(&format!("{pattern}/**"))?);
+    }
+    Ok(globs)
+}
 
 impl GlobPatterns {
+    /// Same as [GlobPatterns::with_recursion] with recursion, as tar does by default.
     #[inline]
     pub(crate) fn new<I: IntoIterator<Item = S>, S: AsRef<str>>(
         patterns: I,
     ) -> Result<Self, globset::Error> {
-        let mut builder = globset::GlobSet::builder();
-        for pattern in patterns {
-            builder.add(globset::Glob::new(pattern.as_ref())?);
+        Self::with_recursion(patterns, true)
+    }
+
+    /// Builds the patterns matching the entry names, ignoring the leading `./` and the trailing `/` of the patterns.
+    ///
+    /// With `recursion`, a pattern without glob meta characters also matches the entries under the directory it names,
+    /// so that `dir` matches `dir/a.txt` like `dir/**` does.
+    pub(crate) fn with_recursion<I: IntoIterator<Item = S>, S: AsRef<str>>(
+        patterns: I,
+        recursion: bool,
+    ) -> Result<Self, globset::Error> {
+        let patterns = patterns
+            .into_iter()
+            .map(|it| it.as_ref().to_string())
+            .collect::<Vec<_>>();
+        let globs = patterns
+            .iter()
+            .map(|pattern| pattern_globs(pattern, recursion))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::build(patterns, globs)
+    }
+
+    /// Builds the patterns named `patterns`, each of which matches the paths matching any of its `globs`.
+    fn build(
+        patterns: Vec<String>,
+        globs: Vec<Vec<globset::Glob>>,
+    ) -> Result<Self, globset::Error> {
+        let mut unprefixed = Vec::new();
+        let mut set_indexes = Vec::new();
+        let mut prefixes = Vec::new();
+        let mut sets = Vec::with_capacity(globs.len());
+        for (i, globs) in globs.into_iter().enumerate() {
+            // NOTE: The alternatives of a pattern share its literal prefix.
+            let prefix = globs.first().map_or("", |it| literal_prefix(it.glob()));
+            if prefix.is_empty() {
+                for glob in globs {
+                    unprefixed.push(glob);
+                    set_indexes.push(i);
+                }
+                sets.push(None);
+            } else {
+                prefixes.push((prefix.to_string(), i));
+                sets.push(Some(glob_set(globs)?));
+            }
         }
-        Ok(Self(builder.build()?))
+        prefixes.sort();
+        let mut lengths = prefixes.iter().map(|(it, _)| it.len()).collect::<Vec<_>>();
+        lengths.sort_unstable();
+        lengths.dedup();
+        Ok(Self {
+            set: glob_set(unprefixed)?,
+            set_indexes,
+            prefixed: PrefixIndex {
+                prefixes,
+                lengths,
+                sets,
+            },
+            matched: patterns.iter().map(|_| AtomicBool::new(false)).collect(),
+            patterns,
+        })
     }
 
     #[inline]
     pub(crate) fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.patterns.is_empty()
     }
 
     #[inline]
     pub(crate) fn matches_any<P: AsRef<Path>>(&self, s: P) -> bool {
-        self.0.is_match(s)
+        let path = s.as_ref();
+        let mut any = false;
+        let mut mark = |i: usize| {
+            self.matched[i].store(true, Ordering::Relaxed);
+            any = true;
+        };
+        if !self.set.is_empty() {
+            for i in self.set.matches(path) {
+                mark(self.set_indexes[i]);
+            }
+        }
+        if !self.prefixed.prefixes.is_empty() {
+            self.prefixed
+                .for_each_match(path, &normalized_bytes(path), &mut mark);
+        }
+        any
+    }
+
+    /// Returns the patterns that have not matched any path yet.
+    #[inline]
+    pub(crate) fn unmatched(&self) -> impl Iterator<Item = &str> {
+        self.patterns
+            .iter()
+            .zip(&self.matched)
+            .filter(|(_, matched)| !matched.load(Ordering::Relaxed))
+            .map(|(pattern, _)| pattern.as_str())
+    }
+
+    /// Reports the patterns that have not matched any entry of the archive
+    /// and returns a partial failure error if there are any.
+    #[inline]
+    pub(crate) fn ensure_all_matched(&self) -> io::Result<()> {
+        let mut unmatched = 0;
+        for pattern in self.unmatched() {
+            log::error!("{pattern}: Not found in archive");
+            unmatched += 1;
+        }
+        if unmatched == 0 {
+            return Ok(());
+        }
+        Err(with_status(
+            ExitStatus::PartialFailure,
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{unmatched} patterns did not match any entry"),
+            ),
+        ))
     }
 }
 
+// NOTE: The globs are expected to be built with the default options, as their literal prefixes
+// are compared case-sensitively.
 impl TryFrom<Vec<globset::Glob>> for GlobPatterns {
     type Error = globset::Error;
 
     #[inline]
     fn try_from(patterns: Vec<globset::Glob>) -> Result<Self, Self::Error> {
-        let mut builder = globset::GlobSet::builder();
-        for pattern in patterns {
-            builder.add(pattern);
+        Self::build(
+            patterns.iter().map(|it| it.glob().to_string()).collect(),
+            patterns.into_iter().map(|it| vec![it]).collect(),
+        )
+    }
+}
+
+/// Globs of the names of the files and directories of the version control systems excluded by `--exclude-vcs`, as GNU tar does.
+const VCS_NAMES: [&str; 21] = [
+    "CVS",
+    ".cvsignore",
+    "RCS",
+    "SCCS",
+    ".git",
+    ".gitignore",
+    ".gitattributes",
+    ".gitmodules",
+    ".svn",
+    ".arch-ids",
+    "[{]arch[}]",
+    "=RELEASE-ID",
+    "=meta-update",
+    "=update",
+    ".bzr",
+    ".bzrignore",
+    ".bzrtags",
+    ".hg",
+    ".hgignore",
+    ".hgtags",
+    "_darcs",
+];
+
+/// Include and exclude patterns filtering the entries by their names in the archive.
+///
+/// Exclusions take precedence over inclusions, and both of them also match the entries
+/// under the directories named by literal patterns.
+pub(crate) struct PathFilter {
+    include: GlobPatterns,
+    exclude: GlobPatterns,
+}
+
+impl PathFilter {
+    /// Builds the filter, also excluding the files of the version control systems at any depth with `exclude_vcs`.
+    pub(crate) fn new<I, E, S, T>(
+        include: I,
+        exclude: E,
+        exclude_vcs: bool,
+    ) -> Result<Self, globset::Error>
+    where
+        I: IntoIterator<Item = S>,
+        E: IntoIterator<Item = T>,
+        S: AsRef<str>,
+        T: AsRef<str>,
+    {
+        let include = GlobPatterns::new(include)?;
+        let mut patterns = exclude
+            .into_iter()
+            .map(|it| it.as_ref().to_string())
+            .collect::<Vec<_>>();
+        let mut globs = patterns
+            .iter()
+            .map(|pattern| pattern_globs(pattern, true))
+            .collect::<Result<Vec<_>, _>>()?;
+        if exclude_vcs {
+            for name in VCS_NAMES {
+                patterns.push(name.to_string());
+                globs.push(vec![
+                    globset::Glob::new(&format!("**/{name}"))?,
+                    globset::Glob::new(&format!("**/{name}/**"))?,
+                ]);
+            }
         }
-        Ok(Self(builder.build()?))
+        Ok(Self {
+            include,
+            exclude: GlobPatterns::build(patterns, globs)?,
+        })
+    }
+
+    /// Returns `true` if `path` matches any of the exclude patterns, or none of the include patterns if there are any.
+    #[inline]
+    pub(crate) fn is_excluded<P: AsRef<Path>>(&self, path: P) -> bool {
+        let path = path.as_ref();
+        self.exclude.matches_any(path)
+            || (!self.include.is_empty() && !self.include.matches_any(path))
     }
 }
 
@@ -52,4 +348,160 @@ mod tests {
         let globs = GlobPatterns::new(vec!["path/**"]).unwrap();
         assert!(globs.matches_any("path/foo.pna"));
     }
+
+    #[test]
+    fn glob_unmatched() {
+        let globs = GlobPatterns::new(vec!["path/**", "other/**"]).unwrap();
+        assert!(globs.matches_any("path/foo.pna"));
+        assert_eq!(globs.unmatched().collect::<Vec<_>>(), vec!["other/**"]);
+        assert!(globs.ensure_all_matched().is_err());
+    }
+
+    #[test]
+    fn literal_prefixes() {
+        assert_eq!(literal_prefix("path/**"), "path/");
+        assert_eq!(literal_prefix("path/{a,b}.txt"), "path/");
+        assert_eq!(literal_prefix("a\\*b"), "a");
+        assert_eq!(literal_prefix("*.txt"), "");
+        assert_eq!(literal_prefix("path/file.txt"), "path/file.txt");
+    }
+
+    #[test]
+    fn glob_prefixed_and_unprefixed() {
+        let globs = GlobPatterns::new(vec!["a/*", "a/b/*", "*.txt", "b/c.txt", "ab"]).unwrap();
+        assert!(globs.matches_any("a/b/c.txt"));
+        assert!(!globs.matches_any("a"));
+        assert!(globs.matches_any("ab"));
+        assert_eq!(globs.unmatched().collect::<Vec<_>>(), vec!["b/c.txt"]);
+    }
+
+    #[test]
+    fn normalize_patterns() {
+        assert_eq!(normalize_pattern("dir"), "dir");
+        assert_eq!(normalize_pattern("dir/"), "dir");
+        assert_eq!(normalize_pattern("./dir"), "dir");
+        assert_eq!(normalize_pattern(".//./dir//"), "dir");
+        assert_eq!(normalize_pattern("./*.txt"), "*.txt");
+        assert_eq!(normalize_pattern("."), ".");
+        assert_eq!(normalize_pattern("./"), ".");
+        assert_eq!(normalize_pattern("/"), "/");
+    }
+
+    #[test]
+    fn glob_recursion() {
+        for pattern in ["dir", "dir/", "./dir"] {
+            let globs = GlobPatterns::new([pattern]).unwrap();
+            assert!(globs.matches_any("dir"), "{pattern}");
+            assert!(globs.matches_any("dir/a.txt"), "{pattern}");
+            assert!(globs.matches_any("dir/sub/b.txt"), "{pattern}");
+            assert!(!globs.matches_any("dir2/a.txt"), "{pattern}");
+            assert!(!globs.matches_any("dir.txt"), "{pattern}");
+            assert!(!globs.matches_any("other/dir/a.txt"), "{pattern}");
+        }
+        // NOTE: Patterns with glob meta characters match only as they are.
+        let globs = GlobPatterns::new(["d?r"]).unwrap();
+        assert!(globs.matches_any("dir"));
+        assert!(!globs.matches_any("dir/a.txt"));
+    }
+
+    #[test]
+    fn glob_no_recursion() {
+        let globs = GlobPatterns::with_recursion(["dir/", "./a.txt"], false).unwrap();
+        assert!(globs.matches_any("dir"));
+        assert!(!globs.matches_any("dir/a.txt"));
+        assert!(globs.matches_any("a.txt"));
+    }
+
+    #[test]
+    fn glob_recursion_unmatched() {
+        let globs = GlobPatterns::new(["dir/", "./missing", "dir/sub"]).unwrap();
+        assert!(globs.matches_any("dir/a.txt"));
+        assert!(!globs.matches_any("dirty/a.txt"));
+        assert_eq!(
+            globs.unmatched().collect::<Vec<_>>(),
+            vec!["./missing", "dir/sub"]
+        );
+        assert!(globs.ensure_all_matched().is_err());
+    }
+
+    /// Generates the pseudo random values of xorshift, reproducible without a seed from the environment.
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn string(&mut self, chars: &[&str], max_len: u64) -> String {
+            let len = self.next() % (max_len + 1);
+            (0..len)
+                .map(|_| chars[(self.next() % chars.len() as u64) as usize])
+                .collect()
+        }
+    }
+
+    #[test]
+    fn glob_matches_same_as_brute_force() {
+        const NAME_CHARS: [&str; 6] = ["a", "b", "/", ".", "\\", "é"];
+        const PATTERN_CHARS: [&str; 16] = [
+            "a", "b", "/", ".", "é", "*", "**", "?", "[ab]", "[!a]", "{a,b/}", "\\*", "a", "b",
+            "/", "/**/",
+        ];
+        let mut rng = XorShift(0x9E37_79B9_7F4A_7C15);
+        let names = (0..200)
+            .map(|_| rng.string(&NAME_CHARS, 8))
+            .collect::<Vec<_>>();
+        for _ in 0..200 {
+            let patterns = (0..=rng.next() % 4)
+                .filter_map(|_| globset::Glob::new(&rng.string(&PATTERN_CHARS, 6)).ok())
+                .collect::<Vec<_>>();
+            let globs = GlobPatterns::try_from(patterns.clone()).unwrap();
+            let set = glob_set(patterns).unwrap();
+            let mut matched = vec![false; globs.patterns.len()];
+            for name in &names {
+                let matches = set.matches(name);
+                matches.iter().for_each(|i| matched[*i] = true);
+                assert_eq!(
+                    globs.matches_any(name),
+                    !matches.is_empty(),
+                    "{name} with {:?}",
+                    globs.patterns,
+                );
+            }
+            let expected_unmatched = globs
+                .patterns
+                .iter()
+                .zip(matched)
+                .filter(|(_, matched)| !matched)
+                .map(|(it, _)| it.as_str())
+                .collect::<Vec<_>>();
+            assert_eq!(globs.unmatched().collect::<Vec<_>>(), expected_unmatched);
+        }
+    }
+
+    #[test]
+    fn path_filter() {
+        let filter = PathFilter::new(["dir", "*.txt"], ["*.png", "dir/sub/"], false).unwrap();
+        assert!(!filter.is_excluded("dir/a.jpg"));
+        assert!(!filter.is_excluded("other/a.txt"));
+        assert!(filter.is_excluded("other/a.jpg"));
+        assert!(filter.is_excluded("dir/a.png"));
+        assert!(filter.is_excluded("dir/sub"));
+        assert!(filter.is_excluded("dir/sub/a.txt"));
+    }
+
+    #[test]
+    fn path_filter_exclude_vcs() {
+        let filter = PathFilter::new(Vec::<&str>::new(), Vec::<&str>::new(), true).unwrap();
+        assert!(filter.is_excluded(".git"));
+        assert!(filter.is_excluded("src/.git/config"));
+        assert!(filter.is_excluded("src/.gitignore"));
+        assert!(filter.is_excluded("{arch}/a"));
+        assert!(!filter.is_excluded("src/.github/a"));
+        assert!(!filter.is_excluded("arch/a"));
+        assert!(!filter.is_excluded("src/main.rs"));
+    }
 }
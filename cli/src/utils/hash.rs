@@ -0,0 +1,176 @@
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    mem,
+    path::Path,
+    sync::mpsc,
+    thread,
+};
+
+/// Size of the buffers passed to the digesting thread.
+const BUFFER_SIZE: usize = 64 * 1024;
+/// Number of the buffers that may wait for the digesting thread before writing blocks.
+const PENDING_BUFFERS: usize = 16;
+
+/// SHA-256 digest of a part of a split archive.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct PartDigest {
+    pub(crate) part_number: usize,
+    pub(crate) bytes_written: u64,
+    pub(crate) digest: [u8; 32],
+}
+
+/// Writer that tees the written bytes to a thread computing their digest,
+/// so that digesting runs concurrently with writing and needs no second read pass.
+///
+/// The digest is sent to the report channel once the writer is finished and the thread has caught up.
+pub(crate) struct DigestWriter<W> {
+    inner: W,
+    digester: Option<Digester>,
+}
+
+struct Digester {
+    buf: Vec<u8>,
+    tx: mpsc::SyncSender<Vec<u8>>,
+}
+
+impl<W> DigestWriter<W> {
+    /// Creates a writer digesting the bytes as part `part_number` when `report` is given,
+    /// otherwise writing them through as is.
+    pub(crate) fn new(
+        inner: W,
+        part_number: usize,
+        report: Option<mpsc::Sender<PartDigest>>,
+    ) -> Self {
+        let digester = report.map(|report| {
+            let (tx, rx) = mpsc::sync_channel::<Vec<u8>>(PENDING_BUFFERS);
+            thread::spawn(move || {
+                let mut hasher = Sha256::new();
+                let mut bytes_written = 0;
+                for buf in rx {
+                    hasher.update(&buf);
+                    bytes_written += buf.len() as u64;
+                }
+                let _ = report.send(PartDigest {
+                    part_number,
+                    bytes_written,
+                    digest: hasher.finalize().into(),
+                });
+            });
+            Digester {
+                buf: Vec::with_capacity(BUFFER_SIZE),
+                tx,
+            }
+        });
+        Self { inner, digester }
+    }
+
+    /// Sends the remaining bytes to the digesting thread and returns the inner writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        if let Some(digester) = &mut self.digester {
+            if !digester.buf.is_empty() {
+                digester.send()?;
+            }
+        }
+        Ok(self.inner)
+    }
+}
+
+impl Digester {
+    fn send(&mut self) -> io::Result<()> {
+        let buf = mem::replace(&mut self.buf, Vec::with_capacity(BUFFER_SIZE));
+        self.tx
+            .send(buf)
+            .map_err(|_| io::Error::other("digesting thread stopped"))
+    }
+}
+
+impl<W: Write> Write for DigestWriter<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if let Some(digester) = &mut self.digester {
+            digester.buf.extend_from_slice(&buf[..n]);
+            if digester.buf.len() >= BUFFER_SIZE {
+                digester.send()?;
+            }
+        }
+        Ok(n)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Computes the digest of the part `part_number` of an archive already written at `path`.
+pub(crate) fn digest_file(part_number: usize, path: &Path) -> io::Result<PartDigest> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut bytes_written = 0;
+    let mut buf = vec![0; BUFFER_SIZE];
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hasher.update(&buf[..n]);
+        bytes_written += n as u64;
+    }
+    Ok(PartDigest {
+        part_number,
+        bytes_written,
+        digest: hasher.finalize().into(),
+    })
+}
+
+pub(crate) fn hex(bytes: impl AsRef<[u8]>) -> String {
+    bytes
+        .as_ref()
+        .iter()
+        .map(|it| format!("{it:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_written_bytes() {
+        let (report, digests) = mpsc::channel();
+        let mut writer = DigestWriter::new(Vec::new(), 3, Some(report));
+        let data = (0..BUFFER_SIZE * 3 + 7)
+            .map(|it| it as u8)
+            .collect::<Vec<_>>();
+        for chunk in data.chunks(1000) {
+            writer.write_all(chunk).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), data);
+        assert_eq!(
+            digests.into_iter().collect::<Vec<_>>(),
+            [PartDigest {
+                part_number: 3,
+                bytes_written: data.len() as u64,
+                digest: Sha256::digest(&data).into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn digest_file_equals_teed_digest() {
+        let path = std::env::temp_dir().join("pna_digest_file.bin");
+        let (report, digests) = mpsc::channel();
+        let mut writer = DigestWriter::new(fs::File::create(&path).unwrap(), 2, Some(report));
+        let data = (0..BUFFER_SIZE * 2 + 3)
+            .map(|it| (it * 7) as u8)
+            .collect::<Vec<_>>();
+        writer.write_all(&data).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(digest_file(2, &path).unwrap(), digests.recv().unwrap());
+    }
+}
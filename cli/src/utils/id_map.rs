@@ -0,0 +1,249 @@
+use crate::exit_code::usage_error;
+use std::{fmt, io, str::FromStr};
+
+/// A range of ids given by `--uid-map` or `--gid-map` as `HOST_START:ARCHIVE_START:COUNT`,
+/// which maps the `COUNT` ids from `HOST_START` on this system to the ids from `ARCHIVE_START` in the archive.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct IdRange {
+    host: u32,
+    archive: u32,
+    count: u32,
+}
+
+impl IdRange {
+    /// Returns the last id of the range starting at `start`.
+    #[inline]
+    const fn last(&self, start: u32) -> u32 {
+        start + (self.count - 1)
+    }
+
+    #[inline]
+    fn map(&self, id: u64, from: u32, to: u32) -> Option<u32> {
+        let offset = id.checked_sub(from.into())?;
+        (offset < self.count.into()).then(|| to + offset as u32)
+    }
+}
+
+impl FromStr for IdRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.split(':').map(|it| {
+            it.parse::<u32>()
+                .map_err(|e| format!("invalid id mapping {s}: {e}"))
+        });
+        let (Some(host), Some(archive), Some(count), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(format!(
+                "invalid id mapping {s}: expected HOST_START:ARCHIVE_START:COUNT"
+            ));
+        };
+        let range = Self {
+            host: host?,
+            archive: archive?,
+            count: count?,
+        };
+        if range.count == 0 {
+            return Err(format!("invalid id mapping {s}: COUNT must not be 0"));
+        }
+        if range.host.checked_add(range.count - 1).is_none()
+            || range.archive.checked_add(range.count - 1).is_none()
+        {
+            return Err(format!(
+                "invalid id mapping {s}: the range exceeds {}",
+                u32::MAX
+            ));
+        }
+        Ok(range)
+    }
+}
+
+impl fmt::Display for IdRange {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.host, self.archive, self.count)
+    }
+}
+
+/// How to map the ids outside every range of `--uid-map` and `--gid-map`, given by `--unmapped`.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub(crate) enum Unmapped {
+    /// Fail to map them
+    #[default]
+    Error,
+    /// Keep them as they are
+    Keep,
+    /// Map them to the given id
+    SquashTo(u32),
+}
+
+impl FromStr for Unmapped {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Self::Error),
+            "keep" => Ok(Self::Keep),
+            s => match s.strip_prefix("squash-to=") {
+                Some(id) => id
+                    .parse()
+                    .map(Self::SquashTo)
+                    .map_err(|e| format!("invalid id {id}: {e}")),
+                None => Err(format!(
+                    "invalid value {s}: expected keep, error or squash-to=ID"
+                )),
+            },
+        }
+    }
+}
+
+/// Mapping of the ids between this system and the archive, given by `--uid-map` or `--gid-map`.
+/// An empty mapping maps every id to itself.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub(crate) struct IdMap {
+    ranges: Vec<IdRange>,
+    unmapped: Unmapped,
+}
+
+// NOTE: The ids are mapped on unix only.
+#[cfg_attr(not(unix), allow(dead_code))]
+impl IdMap {
+    /// Creates a mapping of `ranges`, which must not overlap each other on either side.
+    pub(crate) fn new(ranges: Vec<IdRange>, unmapped: Unmapped) -> io::Result<Self> {
+        for (i, a) in ranges.iter().enumerate() {
+            for b in &ranges[i + 1..] {
+                let overlaps = |a_start: u32, b_start: u32| {
+                    a_start <= b.last(b_start) && b_start <= a.last(a_start)
+                };
+                if overlaps(a.host, b.host) || overlaps(a.archive, b.archive) {
+                    return Err(usage_error(format!("id mappings {a} and {b} overlap")));
+                }
+            }
+        }
+        Ok(Self { ranges, unmapped })
+    }
+
+    #[inline]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Maps the id `id` on this system to the id stored in the archive.
+    #[inline]
+    pub(crate) fn to_archive(&self, id: u32) -> io::Result<u32> {
+        self.map(id.into(), |it| it.map(id.into(), it.host, it.archive))
+    }
+
+    /// Maps the id `id` stored in the archive to the id on this system.
+    #[inline]
+    pub(crate) fn to_host(&self, id: u64) -> io::Result<u32> {
+        self.map(id, |it| it.map(id, it.archive, it.host))
+    }
+
+    fn map(&self, id: u64, f: impl Fn(&IdRange) -> Option<u32>) -> io::Result<u32> {
+        if self.is_empty() {
+            return u32::try_from(id).map_err(|_| unmapped_error(id));
+        }
+        if let Some(mapped) = self.ranges.iter().find_map(f) {
+            return Ok(mapped);
+        }
+        match self.unmapped {
+            Unmapped::Error => Err(unmapped_error(id)),
+            Unmapped::Keep => u32::try_from(id).map_err(|_| unmapped_error(id)),
+            Unmapped::SquashTo(to) => Ok(to),
+        }
+    }
+}
+
+#[cfg_attr(not(unix), allow(dead_code))]
+fn unmapped_error(id: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("id {id} is not in any id mapping, use --unmapped to map it"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id_map(ranges: &[&str], unmapped: Unmapped) -> io::Result<IdMap> {
+        IdMap::new(
+            ranges.iter().map(|it| it.parse().unwrap()).collect(),
+            unmapped,
+        )
+    }
+
+    #[test]
+    fn parse_range() {
+        assert_eq!(
+            "1000:0:1000".parse::<IdRange>().unwrap(),
+            IdRange {
+                host: 1000,
+                archive: 0,
+                count: 1000,
+            }
+        );
+        assert!("1000:0".parse::<IdRange>().is_err());
+        assert!("1000:0:1:1".parse::<IdRange>().is_err());
+        assert!("1000:0:0".parse::<IdRange>().is_err());
+        assert!("a:0:1".parse::<IdRange>().is_err());
+        assert!("4294967295:0:2".parse::<IdRange>().is_err());
+        assert!("4294967295:0:1".parse::<IdRange>().is_ok());
+    }
+
+    #[test]
+    fn parse_unmapped() {
+        assert_eq!("error".parse(), Ok(Unmapped::Error));
+        assert_eq!("keep".parse(), Ok(Unmapped::Keep));
+        assert_eq!("squash-to=65534".parse(), Ok(Unmapped::SquashTo(65534)));
+        assert!("squash-to=".parse::<Unmapped>().is_err());
+        assert!("squash".parse::<Unmapped>().is_err());
+    }
+
+    #[test]
+    fn map_ranges() {
+        let map = id_map(&["1000:0:1000", "5000:2000:10"], Unmapped::Error).unwrap();
+        assert_eq!(map.to_archive(1000).unwrap(), 0);
+        assert_eq!(map.to_archive(1999).unwrap(), 999);
+        assert_eq!(map.to_archive(5009).unwrap(), 2009);
+        assert_eq!(map.to_host(0).unwrap(), 1000);
+        assert_eq!(map.to_host(999).unwrap(), 1999);
+        assert_eq!(map.to_host(2000).unwrap(), 5000);
+        assert_eq!(
+            map.to_archive(2000).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert!(map.to_archive(999).is_err());
+        assert!(map.to_host(1000).is_err());
+        assert!(map.to_host(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn map_unmapped() {
+        let keep = id_map(&["1000:0:1000"], Unmapped::Keep).unwrap();
+        assert_eq!(keep.to_archive(2000).unwrap(), 2000);
+        assert_eq!(keep.to_host(1000).unwrap(), 1000);
+        assert!(keep.to_host(u64::MAX).is_err());
+        let squash = id_map(&["1000:0:1000"], Unmapped::SquashTo(65534)).unwrap();
+        assert_eq!(squash.to_archive(2000).unwrap(), 65534);
+        assert_eq!(squash.to_host(u64::MAX).unwrap(), 65534);
+    }
+
+    #[test]
+    fn empty_map_is_identity() {
+        let map = IdMap::default();
+        assert!(map.is_empty());
+        assert_eq!(map.to_archive(1000).unwrap(), 1000);
+        assert_eq!(map.to_host(1000).unwrap(), 1000);
+    }
+
+    #[test]
+    fn overlapping_ranges() {
+        assert!(id_map(&["0:0:10", "10:10:10"], Unmapped::Error).is_ok());
+        assert!(id_map(&["0:0:10", "9:100:10"], Unmapped::Error).is_err());
+        assert!(id_map(&["0:100:10", "100:109:10"], Unmapped::Error).is_err());
+        assert!(id_map(&["10:10:1", "0:0:100"], Unmapped::Error).is_err());
+    }
+}
@@ -1,3 +1,7 @@
+#[cfg(feature = "acl")]
+use crate::chunk::Acl;
+use std::{io, path::Path};
+
 #[cfg(target_os = "redox")]
 mod redox {
     pub(crate) mod fs {
@@ -8,3 +12,121 @@ mod redox {
 pub(crate) mod unix;
 #[cfg(windows)]
 pub(crate) mod windows;
+
+/// Operations on the metadata of files that only some platforms support.
+///
+/// The provided methods are the stubs for the platforms without the support, which fail with
+/// [io::ErrorKind::Unsupported], and [Current] overrides the ones its platform supports.
+pub(crate) trait Platform {
+    /// Returns the ACL of the file at `path`.
+    #[cfg(feature = "acl")]
+    fn get_facl(_path: &Path) -> io::Result<Acl> {
+        Err(unsupported("acl"))
+    }
+
+    /// Replaces the ACL of the file at `path` with `acl`.
+    #[cfg(feature = "acl")]
+    fn set_facl(_path: &Path, _acl: Acl) -> io::Result<()> {
+        Err(unsupported("acl"))
+    }
+
+    /// Returns `true` if the file at `path` has the nodump flag, which asks backup programs to skip it.
+    fn is_nodump(_path: &Path) -> io::Result<bool> {
+        Err(unsupported("nodump flag"))
+    }
+}
+
+/// The platform this program runs on.
+pub(crate) struct Current;
+
+impl Platform for Current {
+    #[cfg(feature = "acl")]
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "macos"))]
+    fn get_facl(path: &Path) -> io::Result<Acl> {
+        unix::acl::get_facl(path)
+    }
+
+    #[cfg(feature = "acl")]
+    #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "macos"))]
+    fn set_facl(path: &Path, acl: Acl) -> io::Result<()> {
+        unix::acl::set_facl(path, acl)
+    }
+
+    #[cfg(feature = "acl")]
+    #[cfg(any(target_os = "netbsd", target_os = "illumos"))]
+    fn get_facl(path: &Path) -> io::Result<Acl> {
+        unix::posix_acl::get_facl(path)
+    }
+
+    #[cfg(feature = "acl")]
+    #[cfg(any(target_os = "netbsd", target_os = "illumos"))]
+    fn set_facl(path: &Path, acl: Acl) -> io::Result<()> {
+        unix::posix_acl::set_facl(path, acl)
+    }
+
+    #[cfg(feature = "acl")]
+    #[cfg(windows)]
+    fn get_facl(path: &Path) -> io::Result<Acl> {
+        windows::acl::get_facl(path)
+    }
+
+    #[cfg(feature = "acl")]
+    #[cfg(windows)]
+    fn set_facl(path: &Path, acl: Acl) -> io::Result<()> {
+        windows::acl::set_facl(path, acl)
+    }
+
+    #[cfg(any(
+        target_os = "macos",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd",
+        target_os = "dragonfly"
+    ))]
+    fn is_nodump(path: &Path) -> io::Result<bool> {
+        unix::fs::flags::is_nodump(path)
+    }
+}
+
+#[inline]
+fn unsupported(name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Currently {name} is not supported on this platform."),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Stub;
+
+    impl Platform for Stub {}
+
+    #[cfg(feature = "acl")]
+    #[test]
+    fn stub_get_facl_unsupported() {
+        let err = Stub::get_facl(Path::new(".")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[cfg(feature = "acl")]
+    #[test]
+    fn stub_set_facl_unsupported() {
+        use crate::chunk::AcePlatform;
+
+        let acl = Acl {
+            platform: AcePlatform::CURRENT,
+            entries: Vec::new(),
+        };
+        let err = Stub::set_facl(Path::new("."), acl).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn stub_is_nodump_unsupported() {
+        let err = Stub::is_nodump(Path::new(".")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+}
@@ -2,3 +2,6 @@
 #[cfg(any(target_os = "linux", target_os = "freebsd", target_os = "macos"))]
 pub mod acl;
 pub(crate) mod fs;
+#[cfg(feature = "acl")]
+#[cfg(any(target_os = "netbsd", target_os = "illumos"))]
+pub mod posix_acl;
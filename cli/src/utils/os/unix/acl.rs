@@ -28,57 +28,68 @@ pub fn set_facl<P: AsRef<Path>>(path: P, acl: Acl) -> io::Result<()> {
     }
     #[cfg(any(target_os = "linux", target_os = "freebsd"))]
     {
-        let mut exist_user = false;
-        let mut exist_group = false;
-        let mut exist_other = false;
-        for entry in acl_entries.iter() {
-            match entry.kind {
-                exacl::AclEntryKind::User if entry.name.is_empty() => exist_user = true,
-                exacl::AclEntryKind::Group if entry.name.is_empty() => exist_group = true,
-                exacl::AclEntryKind::Other => exist_other = true,
-                _ => (),
-            }
-        }
-        if !exist_user || !exist_group || !exist_other {
+        use exacl::{AclEntryKind, Flag};
+        const REQUIRED_KINDS: [AclEntryKind; 3] =
+            [AclEntryKind::User, AclEntryKind::Group, AclEntryKind::Other];
+        // NOTE: Only directories can have the default ACL.
+        if !path.is_dir() {
+            acl_entries.retain(|it| !it.flags.contains(Flag::DEFAULT));
+        }
+        let find = |entries: &[exacl::AclEntry], kind: AclEntryKind, default: bool| {
+            entries
+                .iter()
+                .find(|it| {
+                    it.allow
+                        && it.name.is_empty()
+                        && it.kind == kind
+                        && it.flags.contains(Flag::DEFAULT) == default
+                })
+                .cloned()
+        };
+        let missing = REQUIRED_KINDS
+            .into_iter()
+            .filter(|kind| find(&acl_entries, *kind, false).is_none())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
             let facl = exacl::getfacl(path, None)?;
-            if !exist_user {
+            for kind in missing {
                 acl_entries.push(
-                    facl.iter()
-                        .find(|it| {
-                            it.allow
-                                && it.flags.is_empty()
-                                && it.name.is_empty()
-                                && it.kind == exacl::AclEntryKind::User
-                        })
-                        .expect("failed to find owner ace")
-                        .clone(),
+                    find(&facl, kind, false)
+                        .unwrap_or_else(|| panic!("failed to find {kind:?} ace")),
                 );
             }
-            if !exist_group {
-                acl_entries.push(
-                    facl.iter()
-                        .find(|it| {
-                            it.allow
-                                && it.flags.is_empty()
-                                && it.name.is_empty()
-                                && it.kind == exacl::AclEntryKind::Group
-                        })
-                        .expect("failed to find owner group ace")
-                        .clone(),
-                );
+        }
+        // NOTE: The default ACL requires the same entries as the access ACL,
+        //       so the missing ones are copied from the access ACL.
+        if acl_entries
+            .iter()
+            .any(|it| it.flags.contains(Flag::DEFAULT))
+        {
+            for kind in REQUIRED_KINDS {
+                if find(&acl_entries, kind, true).is_none() {
+                    let mut entry = find(&acl_entries, kind, false)
+                        .unwrap_or_else(|| panic!("failed to find {kind:?} ace"));
+                    entry.flags.insert(Flag::DEFAULT);
+                    acl_entries.push(entry);
+                }
             }
-            if !exist_other {
-                acl_entries.push(
-                    facl.iter()
-                        .find(|it| {
-                            it.allow
-                                && it.flags.is_empty()
-                                && it.name.is_empty()
-                                && it.kind == exacl::AclEntryKind::Other
-                        })
-                        .expect("failed to find other ace")
-                        .clone(),
-                );
+            let group_class = acl_entries
+                .iter()
+                .filter(|it| {
+                    it.flags.contains(Flag::DEFAULT)
+                        && (it.kind == AclEntryKind::Group
+                            || (it.kind == AclEntryKind::User && !it.name.is_empty()))
+                })
+                .collect::<Vec<_>>();
+            let has_named = group_class.iter().any(|it| !it.name.is_empty());
+            let has_mask = acl_entries
+                .iter()
+                .any(|it| it.kind == AclEntryKind::Mask && it.flags.contains(Flag::DEFAULT));
+            if has_named && !has_mask {
+                let perms = group_class
+                    .iter()
+                    .fold(exacl::Perm::empty(), |perms, it| perms | it.perms);
+                acl_entries.push(exacl::AclEntry::allow_mask(perms, Flag::DEFAULT));
             }
         }
     }
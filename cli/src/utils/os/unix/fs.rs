@@ -1,3 +1,11 @@
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+pub(crate) mod flags;
 #[cfg(not(target_os = "redox"))]
 pub(crate) mod owner;
 #[cfg(target_os = "redox")]
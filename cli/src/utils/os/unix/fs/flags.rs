@@ -0,0 +1,19 @@
+//! File flags of macOS and the BSDs, as set by `chflags(1)`.
+#[cfg(target_os = "dragonfly")]
+use std::os::dragonfly::fs::MetadataExt;
+#[cfg(target_os = "freebsd")]
+use std::os::freebsd::fs::MetadataExt;
+#[cfg(target_os = "macos")]
+use std::os::macos::fs::MetadataExt;
+#[cfg(target_os = "netbsd")]
+use std::os::netbsd::fs::MetadataExt;
+#[cfg(target_os = "openbsd")]
+use std::os::openbsd::fs::MetadataExt;
+use std::{fs, io, path::Path};
+
+/// `UF_NODUMP` of `<sys/stat.h>`, which has the same value on all of these platforms.
+const UF_NODUMP: u32 = 0x0000_0001;
+
+pub(crate) fn is_nodump(path: &Path) -> io::Result<bool> {
+    Ok(fs::symlink_metadata(path)?.st_flags() & UF_NODUMP != 0)
+}
@@ -4,8 +4,12 @@ use std::path::Path;
 
 pub(crate) fn get_xattrs<P: AsRef<Path>>(path: P) -> io::Result<Vec<ExtendedAttribute>> {
     fn inner(path: &Path) -> io::Result<Vec<ExtendedAttribute>> {
-        let mut xattrs = Vec::new();
-        for name in xattr::list(path)? {
+        // NOTE: The listing order depends on the file system, so sort the names to archive
+        // the same file with the same bytes.
+        let mut names = xattr::list(path)?.collect::<Vec<_>>();
+        names.sort();
+        let mut xattrs = Vec::with_capacity(names.len());
+        for name in names {
             let value = xattr::get(path, &name)?.unwrap_or_default();
             xattrs.push(ExtendedAttribute::new(name.to_string_lossy().into(), value))
         }
@@ -19,17 +23,13 @@ pub(crate) fn get_xattrs<P: AsRef<Path>>(path: P) -> io::Result<Vec<ExtendedAttr
     }
 }
 
-pub(crate) fn set_xattrs<P: AsRef<Path>>(path: P, xattrs: &[ExtendedAttribute]) -> io::Result<()> {
-    fn inner(path: &Path, xattrs: &[ExtendedAttribute]) -> io::Result<()> {
-        for x in xattrs {
-            xattr::set(path, x.name(), x.value())?;
-        }
-        Ok(())
-    }
+pub(crate) fn set_xattr<P: AsRef<Path>>(path: P, xattr: &ExtendedAttribute) -> io::Result<()> {
     if xattr::SUPPORTED_PLATFORM {
-        inner(path.as_ref(), xattrs)
+        xattr::set(path, xattr.name(), xattr.value())
     } else {
-        log::warn!("Currently extended attribute is not supported on this platform.");
-        Ok(())
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Currently extended attribute is not supported on this platform.",
+        ))
     }
 }
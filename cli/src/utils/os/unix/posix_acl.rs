@@ -0,0 +1,199 @@
+//! ACLs of the platforms without the support of `exacl`, read and written in the POSIX.1e text form.
+use crate::{
+    chunk::{AcePlatform, Acl},
+    utils::acl::posix,
+};
+use std::{
+    ffi::{CStr, CString},
+    fs, io,
+    os::unix::{ffi::OsStrExt, fs::PermissionsExt},
+    path::Path,
+};
+
+pub fn set_facl<P: AsRef<Path>>(path: P, acl: Acl) -> io::Result<()> {
+    let path = path.as_ref();
+    let mode = fs::metadata(path)?.permissions().mode();
+    let (mut access, mut default) = posix::split_default(acl.entries);
+    posix::complete(&mut access, mode);
+    // NOTE: Only directories can have the default ACL.
+    if path.is_dir() && !default.is_empty() {
+        posix::complete(&mut default, mode);
+    } else {
+        default.clear();
+    }
+    sys::set(&c_path(path)?, &access, &default)
+}
+
+pub fn get_facl<P: AsRef<Path>>(path: P) -> io::Result<Acl> {
+    let path = path.as_ref();
+    Ok(Acl {
+        platform: AcePlatform::CURRENT,
+        entries: sys::get(&c_path(path)?, path.is_dir())?,
+    })
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+fn c_text(text: String) -> io::Result<CString> {
+    CString::new(text).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// `acl_get_file(3)` and `acl_set_file(3)` of libc, ported from FreeBSD.
+#[cfg(target_os = "netbsd")]
+mod sys {
+    use super::*;
+    use crate::chunk::Ace;
+    use std::ffi::{c_char, c_int, c_void};
+
+    type AclT = *mut c_void;
+
+    const ACL_TYPE_ACCESS: c_int = 0x00000002;
+    const ACL_TYPE_DEFAULT: c_int = 0x00000003;
+
+    extern "C" {
+        fn acl_get_file(path_p: *const c_char, ty: c_int) -> AclT;
+        fn acl_set_file(path_p: *const c_char, ty: c_int, acl: AclT) -> c_int;
+        fn acl_delete_def_file(path_p: *const c_char) -> c_int;
+        fn acl_to_text(acl: AclT, len_p: *mut isize) -> *mut c_char;
+        fn acl_from_text(buf_p: *const c_char) -> AclT;
+        fn acl_free(obj_p: *mut c_void) -> c_int;
+    }
+
+    fn get_text(path: &CStr, ty: c_int) -> io::Result<String> {
+        let acl = unsafe { acl_get_file(path.as_ptr(), ty) };
+        if acl.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let text = unsafe { acl_to_text(acl, std::ptr::null_mut()) };
+        let result = if text.is_null() {
+            Err(io::Error::last_os_error())
+        } else {
+            let s = unsafe { CStr::from_ptr(text) }
+                .to_string_lossy()
+                .into_owned();
+            unsafe { acl_free(text.cast()) };
+            Ok(s)
+        };
+        unsafe { acl_free(acl) };
+        result
+    }
+
+    fn set_text(path: &CStr, ty: c_int, text: &CStr) -> io::Result<()> {
+        let acl = unsafe { acl_from_text(text.as_ptr()) };
+        if acl.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let result = unsafe { acl_set_file(path.as_ptr(), ty, acl) };
+        unsafe { acl_free(acl) };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub(super) fn get(path: &CStr, is_dir: bool) -> io::Result<Vec<Ace>> {
+        let mut entries = posix::parse(&get_text(path, ACL_TYPE_ACCESS)?, false)?;
+        if is_dir {
+            entries.extend(posix::parse(&get_text(path, ACL_TYPE_DEFAULT)?, true)?);
+        }
+        Ok(entries)
+    }
+
+    pub(super) fn set(path: &CStr, access: &[Ace], default: &[Ace]) -> io::Result<()> {
+        set_text(
+            path,
+            ACL_TYPE_ACCESS,
+            &c_text(posix::to_text(access, false))?,
+        )?;
+        if default.is_empty() {
+            if unsafe { acl_delete_def_file(path.as_ptr()) } != 0 {
+                let e = io::Error::last_os_error();
+                // NOTE: Files other than directories have no default ACL to delete.
+                if e.kind() != io::ErrorKind::InvalidInput {
+                    return Err(e);
+                }
+            }
+            return Ok(());
+        }
+        set_text(
+            path,
+            ACL_TYPE_DEFAULT,
+            &c_text(posix::to_text(default, false))?,
+        )
+    }
+}
+
+/// `acl_get(3SEC)` and `acl_set(3SEC)` of libsec, which has the access and the default ACL together.
+#[cfg(target_os = "illumos")]
+mod sys {
+    use super::*;
+    use crate::chunk::Ace;
+    use std::ffi::{c_char, c_int, c_void};
+
+    type AclT = c_void;
+
+    /// Flag of `acl_get` to not read trivial ACLs, which only mirror the permission bits.
+    const ACL_NO_TRIVIAL: c_int = 0x2;
+
+    #[link(name = "sec")]
+    extern "C" {
+        fn acl_get(path: *const c_char, flag: c_int, aclp: *mut *mut AclT) -> c_int;
+        fn acl_set(path: *const c_char, aclp: *mut AclT) -> c_int;
+        fn acl_totext(aclp: *mut AclT, flags: c_int) -> *mut c_char;
+        fn acl_fromtext(textp: *const c_char, aclp: *mut *mut AclT) -> c_int;
+        fn acl_free(aclp: *mut AclT);
+        fn acl_strerror(errnum: c_int) -> *const c_char;
+    }
+
+    extern "C" {
+        fn free(ptr: *mut c_void);
+    }
+
+    pub(super) fn get(path: &CStr, _is_dir: bool) -> io::Result<Vec<Ace>> {
+        let mut acl = std::ptr::null_mut();
+        if unsafe { acl_get(path.as_ptr(), ACL_NO_TRIVIAL, &mut acl) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // NOTE: A trivial ACL is not returned, so the file has no entries beyond its mode.
+        if acl.is_null() {
+            return Ok(Vec::new());
+        }
+        let text = unsafe { acl_totext(acl, 0) };
+        unsafe { acl_free(acl) };
+        if text.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let s = unsafe { CStr::from_ptr(text) }
+            .to_string_lossy()
+            .into_owned();
+        unsafe { free(text.cast()) };
+        posix::parse(&s, false)
+    }
+
+    pub(super) fn set(path: &CStr, access: &[Ace], default: &[Ace]) -> io::Result<()> {
+        let mut text = posix::to_text(access, false);
+        if !default.is_empty() {
+            text.push(',');
+            text.push_str(&posix::to_text(default, true));
+        }
+        let text = c_text(text)?;
+        let mut acl = std::ptr::null_mut();
+        let error = unsafe { acl_fromtext(text.as_ptr(), &mut acl) };
+        if error != 0 {
+            let message = unsafe { CStr::from_ptr(acl_strerror(error)) };
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                message.to_string_lossy().into_owned(),
+            ));
+        }
+        let result = unsafe { acl_set(path.as_ptr(), acl) };
+        unsafe { acl_free(acl) };
+        if result != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
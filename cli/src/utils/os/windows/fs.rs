@@ -3,10 +3,13 @@ pub(crate) mod owner;
 use super::security::{SecurityDescriptor, Sid};
 use crate::utils::str::encode_wide;
 use std::io;
+use std::os::windows::io::AsRawHandle;
 use std::path::Path;
 use windows::core::PCWSTR;
+use windows::Win32::Foundation::HANDLE;
 use windows::Win32::Storage::FileSystem::{
-    MoveFileExW, MOVEFILE_COPY_ALLOWED, MOVEFILE_REPLACE_EXISTING,
+    GetFileInformationByHandle, MoveFileExW, BY_HANDLE_FILE_INFORMATION, MOVEFILE_COPY_ALLOWED,
+    MOVEFILE_REPLACE_EXISTING,
 };
 
 #[inline]
@@ -21,6 +24,17 @@ pub(crate) fn move_file(src: &std::ffi::OsStr, dist: &std::ffi::OsStr) -> io::Re
     .map_err(Into::into)
 }
 
+/// Returns the volume serial number and the file index of `path`, which together identify the file.
+pub(crate) fn file_index(path: &Path) -> io::Result<(u32, u64)> {
+    let file = std::fs::File::open(path)?;
+    let mut info = BY_HANDLE_FILE_INFORMATION::default();
+    unsafe { GetFileInformationByHandle(HANDLE(file.as_raw_handle()), &mut info) }?;
+    Ok((
+        info.dwVolumeSerialNumber,
+        (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow),
+    ))
+}
+
 #[inline]
 pub(crate) fn chown<U: Into<Sid>, G: Into<Sid>>(
     path: &Path,
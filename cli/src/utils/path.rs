@@ -1,4 +1,102 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// Device names that can not be used as a file name on Windows, with or without an extension.
+const WINDOWS_RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Returns `true` if `component` is a reserved device name on Windows, such as `con` or `aux.txt`.
+#[inline]
+pub(crate) fn is_windows_reserved_name(component: &str) -> bool {
+    let stem = component.split('.').next().unwrap_or_default();
+    WINDOWS_RESERVED_NAMES
+        .iter()
+        .any(|it| it.eq_ignore_ascii_case(stem.trim_end()))
+}
+
+/// Returns `true` if the entry name contains a backslash or a duplicate slash such as `dir//file`,
+/// which are written by some writers as separators.
+#[inline]
+pub(crate) fn has_ambiguous_separator(name: &str) -> bool {
+    name.contains('\\') || name.contains("//")
+}
+
+/// Converts backslashes in the entry name to slashes and collapses duplicate slashes.
+/// `.` and `..` components are removed as well, so that the name stays within the output directory.
+#[inline]
+pub(crate) fn normalize_separators(name: &str) -> String {
+    name.split(['/', '\\'])
+        .filter(|it| !matches!(*it, "" | "." | ".."))
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Resolves the target `link` of the symbolic link entry `name` to the entry name it points to.
+/// Returns `None` if the target is absolute or resolves outside the archive root.
+pub(crate) fn resolve_link(name: &str, link: &str) -> Option<String> {
+    if link.starts_with(['/', '\\']) || link.as_bytes().get(1) == Some(&b':') {
+        return None;
+    }
+    let mut components = name.split('/').collect::<Vec<_>>();
+    components.pop();
+    for component in link.split(['/', '\\']) {
+        match component {
+            "" | "." => (),
+            ".." => {
+                components.pop()?;
+            }
+            _ => components.push(component),
+        }
+    }
+    Some(components.join("/"))
+}
+
+/// Returns the link target relative to the directory of the link entry `name` that points to the entry `target`,
+/// which [`resolve_link`] resolves back to `target`.
+pub(crate) fn relative_link(name: &str, target: &str) -> String {
+    let mut parent = name.split('/').collect::<Vec<_>>();
+    parent.pop();
+    let target = target.split('/').collect::<Vec<_>>();
+    let common = parent
+        .iter()
+        .zip(&target)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut components = vec![".."; parent.len() - common];
+    components.extend(&target[common..]);
+    components.join("/")
+}
+
+/// Names of the entries of an archive and their parent directories,
+/// which the symbolic links in the archive may point to.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub(crate) struct LinkTargets {
+    names: HashSet<String>,
+}
+
+impl LinkTargets {
+    /// Adds the entry `name` with its parent directories, which may not be stored as entries.
+    pub(crate) fn insert(&mut self, name: &str) {
+        let mut end = name.len();
+        while self.names.insert(name[..end].into()) {
+            match name[..end].rfind('/') {
+                Some(i) => end = i,
+                None => break,
+            }
+        }
+    }
+
+    /// Returns `true` if `name` resolved by [`resolve_link`] is in the archive.
+    /// The empty name is the archive root.
+    #[inline]
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        name.is_empty() || self.names.contains(name)
+    }
+}
 
 pub(crate) trait PathPartExt {
     fn with_part(&self, n: usize) -> Option<PathBuf>;
@@ -76,6 +174,75 @@ fn remove_part_n<P: AsRef<Path>>(path: P) -> Option<PathBuf> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[test]
+    fn windows_reserved_names() {
+        assert!(is_windows_reserved_name("con"));
+        assert!(is_windows_reserved_name("Nul.txt"));
+        assert!(is_windows_reserved_name("aux.tar.gz"));
+        assert!(!is_windows_reserved_name("console"));
+        assert!(!is_windows_reserved_name("con_.txt"));
+    }
+
+    #[test]
+    fn ambiguous_separators() {
+        assert!(has_ambiguous_separator("dir\\sub\\file.txt"));
+        assert!(has_ambiguous_separator("dir//file"));
+        assert!(!has_ambiguous_separator("dir/sub/file.txt"));
+        assert_eq!(
+            normalize_separators("dir\\sub\\file.txt"),
+            "dir/sub/file.txt"
+        );
+        assert_eq!(normalize_separators("dir//file"), "dir/file");
+        assert_eq!(normalize_separators("..\\..\\etc\\passwd"), "etc/passwd");
+        assert_eq!(normalize_separators("dir/file"), "dir/file");
+    }
+
+    #[test]
+    fn resolve_links() {
+        assert_eq!(resolve_link("link", "target"), Some("target".into()));
+        assert_eq!(resolve_link("dir/link", "../target"), Some("target".into()));
+        assert_eq!(
+            resolve_link("dir/link", "./sub//target"),
+            Some("dir/sub/target".into())
+        );
+        assert_eq!(
+            resolve_link("dir/link", "..\\target"),
+            Some("target".into())
+        );
+        assert_eq!(resolve_link("link", "."), Some("".into()));
+        assert_eq!(resolve_link("dir/link", "../../target"), None);
+        assert_eq!(resolve_link("link", "/etc/passwd"), None);
+        assert_eq!(resolve_link("link", "C:\\Windows"), None);
+    }
+
+    #[test]
+    fn relative_links() {
+        assert_eq!(relative_link("link", "target"), "target");
+        assert_eq!(relative_link("dir/link", "dir/target"), "target");
+        assert_eq!(relative_link("dir/link", "target"), "../target");
+        assert_eq!(relative_link("a/b/link", "a/c/target"), "../c/target");
+        assert_eq!(relative_link("link", "dir/sub/target"), "dir/sub/target");
+        for (name, target) in [("a/b/link", "a/c/target"), ("a/link", "b/target")] {
+            assert_eq!(
+                resolve_link(name, &relative_link(name, target)),
+                Some(target.into())
+            );
+        }
+    }
+
+    #[test]
+    fn link_targets() {
+        let mut targets = LinkTargets::default();
+        targets.insert("dir/sub/file.txt");
+        targets.insert("dir/other.txt");
+        assert!(targets.contains("dir/sub/file.txt"));
+        assert!(targets.contains("dir/sub"));
+        assert!(targets.contains("dir"));
+        assert!(targets.contains(""));
+        assert!(!targets.contains("dir/su"));
+        assert!(!targets.contains("file.txt"));
+    }
+
     #[test]
     fn non_part_to_part_with_extension() {
         assert_eq!(with_part_n("a.pna", 1), Some(PathBuf::from("a.part1.pna")));
@@ -0,0 +1,75 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    io::{self, IsTerminal, Read},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+/// Whether `--progress` is given and not overridden by `--quiet`.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+const TEMPLATE: &str =
+    "{spinner} [{elapsed_precise}] [{bar:30}] {bytes}/{total_bytes} (ETA {eta}) {wide_msg}";
+const TEMPLATE_UNKNOWN_TOTAL: &str =
+    "{spinner} [{elapsed_precise}] {bytes} ({bytes_per_sec}) {wide_msg}";
+
+/// Enables or disables the progress bars created after this call.
+#[inline]
+pub(crate) fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Progress bar of the bytes of the file contents processed, drawn on stderr.
+///
+/// The bar is hidden unless `--progress` is given and stderr is a terminal,
+/// so that the methods can be called unconditionally. The bar is cleared when dropped.
+pub(crate) struct Progress(ProgressBar);
+
+impl Progress {
+    /// Creates a progress bar of `total` bytes, or of an unknown total without ETA if `None`.
+    pub(crate) fn new(total: Option<u64>) -> Self {
+        if !ENABLED.load(Ordering::Relaxed) || !io::stderr().is_terminal() {
+            return Self(ProgressBar::hidden());
+        }
+        let bar = match total {
+            Some(total) => ProgressBar::new(total).with_style(style(TEMPLATE)),
+            None => ProgressBar::new_spinner().with_style(style(TEMPLATE_UNKNOWN_TOTAL)),
+        };
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Self(bar)
+    }
+
+    /// Shows `path` as the file being processed.
+    #[inline]
+    pub(crate) fn start(&self, path: impl AsRef<Path>) {
+        if !self.0.is_hidden() {
+            self.0.set_message(path.as_ref().display().to_string());
+        }
+    }
+
+    /// Adds `bytes` to the processed bytes.
+    #[inline]
+    pub(crate) fn inc(&self, bytes: u64) {
+        self.0.inc(bytes);
+    }
+
+    /// Wraps `reader` to add the bytes read through it to the processed bytes.
+    #[inline]
+    pub(crate) fn wrap_read<R: Read>(&self, reader: R) -> impl Read {
+        self.0.wrap_read(reader)
+    }
+}
+
+impl Drop for Progress {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.finish_and_clear();
+    }
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .expect("progress template must be valid")
+        .progress_chars("=> ")
+}
@@ -0,0 +1,426 @@
+use crate::utils::hash::hex;
+use pna::PNA_HEADER;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    io::{self, prelude::*, SeekFrom},
+    mem,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError,
+    },
+};
+
+/// Number of bytes at the start of an archive whose digest identifies the archive.
+const HEAD_DIGEST_SIZE: u64 = 64 * 1024;
+
+/// Identity of the archive a state file was written for.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+struct ArchiveIdentity {
+    size: u64,
+    head_digest: String,
+    archive_id: Option<String>,
+}
+
+impl ArchiveIdentity {
+    /// Reads the identity of the archive in `file`, and returns it with the length of the archive header,
+    /// i.e. the PNA header, the `AHED` chunk and the `aIDN` chunk if any.
+    fn read(file: &mut fs::File) -> io::Result<(Self, u64)> {
+        let size = file.metadata()?.len();
+        let mut hasher = Sha256::new();
+        io::copy(&mut file.take(HEAD_DIGEST_SIZE), &mut hasher)?;
+        file.seek(SeekFrom::Start(0))?;
+        let mut header = [0; PNA_HEADER.len()];
+        file.read_exact(&mut header)?;
+        if header != *PNA_HEADER {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "It's not PNA"));
+        }
+        let mut header_len = PNA_HEADER.len() as u64;
+        let mut archive_id = None;
+        loop {
+            let mut head = [0; 2 * mem::size_of::<u32>()];
+            file.read_exact(&mut head)?;
+            let (length, ty) = head.split_at(mem::size_of::<u32>());
+            let length = u32::from_be_bytes(length.try_into().unwrap());
+            if header_len == PNA_HEADER.len() as u64 {
+                if ty != b"AHED" {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Expected AHED chunk",
+                    ));
+                }
+                file.seek(SeekFrom::Current(i64::from(length) + 4))?;
+            } else if ty == b"aIDN" {
+                let mut data = vec![0; length as usize];
+                file.read_exact(&mut data)?;
+                archive_id = Some(hex(data));
+            } else {
+                break;
+            }
+            header_len += 3 * mem::size_of::<u32>() as u64 + u64::from(length);
+        }
+        file.seek(SeekFrom::Start(0))?;
+        let identity = Self {
+            size,
+            head_digest: hex(hasher.finalize()),
+            archive_id,
+        };
+        Ok((identity, header_len))
+    }
+}
+
+/// First line of a state file.
+#[derive(Serialize, Deserialize)]
+struct StateHeader {
+    archive: ArchiveIdentity,
+}
+
+/// Line of a state file written for each completed entry.
+#[derive(Serialize, Deserialize)]
+struct CompletedEntry {
+    name: String,
+    /// Offset in the first part of the archive before which every entry is completed.
+    offset: Option<u64>,
+}
+
+/// State of an extraction for `--resume`, stored as JSON lines.
+///
+/// The first line identifies the archive, and each following line records an entry whose extraction completed,
+/// with the offset in the archive from which reading can be resumed.
+/// A line is appended and flushed as soon as the entry is completed, so the state survives an interruption.
+pub(crate) struct ResumeState {
+    completed: HashSet<String>,
+    offset: Option<u64>,
+    header_len: u64,
+    position: Arc<AtomicU64>,
+    tracker: Mutex<Tracker>,
+}
+
+/// Entries being extracted, which keep the resumable offset from passing them.
+struct Tracker {
+    file: fs::File,
+    /// Offsets of the archive items that contain the entries not completed yet, keyed by the order of the entries.
+    pending: BTreeMap<usize, u64>,
+    next_index: usize,
+    /// Offset of the archive item that contains the entries being read, or `None` after leaving the first part.
+    frontier: Option<u64>,
+    /// Position of the reader when the last entry was read.
+    last_position: u64,
+}
+
+impl ResumeState {
+    /// Opens the state file at `path` for the archive at `archive`, creating it if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state file is broken or was written for another archive.
+    pub(crate) fn open(path: &Path, archive: &Path) -> io::Result<Self> {
+        let (identity, header_len) = ArchiveIdentity::read(&mut fs::File::open(archive)?)?;
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        // NOTE: A line without the trailing newline is left by an interruption while it was written.
+        let valid_len = contents.rfind('\n').map_or(0, |it| it + 1);
+        if valid_len != contents.len() {
+            log::debug!("Discard the incomplete last line of {}", path.display());
+            file.set_len(valid_len as u64)?;
+        }
+        let invalid = |e: serde_json::Error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is not a valid state file: {e}", path.display()),
+            )
+        };
+        let mut lines = contents[..valid_len].lines();
+        let mut completed = HashSet::new();
+        let mut offset = None;
+        match lines.next() {
+            Some(line) => {
+                let header = serde_json::from_str::<StateHeader>(line).map_err(invalid)?;
+                if header.archive != identity {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "{} was written for another archive than {}",
+                            path.display(),
+                            archive.display()
+                        ),
+                    ));
+                }
+                for line in lines {
+                    let entry = serde_json::from_str::<CompletedEntry>(line).map_err(invalid)?;
+                    offset = offset.max(entry.offset);
+                    completed.insert(entry.name);
+                }
+                log::info!(
+                    "Resume extraction with {} completed entries from {}",
+                    completed.len(),
+                    path.display()
+                );
+            }
+            None => {
+                let mut line = serde_json::to_vec(&StateHeader { archive: identity })?;
+                line.push(b'\n');
+                file.write_all(&line)?;
+                file.flush()?;
+            }
+        }
+        // NOTE: Reading starts from the beginning of the archive, or from the offset after reading the header.
+        let start = offset.unwrap_or(header_len);
+        Ok(Self {
+            completed,
+            offset,
+            header_len,
+            position: Arc::new(AtomicU64::new(offset.unwrap_or(0))),
+            tracker: Mutex::new(Tracker {
+                file,
+                pending: BTreeMap::new(),
+                next_index: 0,
+                frontier: Some(start),
+                last_position: start,
+            }),
+        })
+    }
+
+    /// Returns `true` if the state holds entries completed by a previous extraction.
+    #[inline]
+    pub(crate) fn is_resuming(&self) -> bool {
+        !self.completed.is_empty()
+    }
+
+    /// Returns `true` if the entry `name` was completed by a previous extraction.
+    #[inline]
+    pub(crate) fn is_completed(&self, name: &str) -> bool {
+        self.completed.contains(name)
+    }
+
+    /// Offset in the first part of the archive from which reading is resumed, if any.
+    #[inline]
+    pub(crate) fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// Length of the archive header, which is read before seeking to [ResumeState::offset].
+    #[inline]
+    pub(crate) fn header_len(&self) -> u64 {
+        self.header_len
+    }
+
+    /// Counter of the position in the first part of the archive, advanced by the reader of the archive.
+    #[inline]
+    pub(crate) fn position(&self) -> Arc<AtomicU64> {
+        Arc::clone(&self.position)
+    }
+
+    /// Stops recording offsets, since the positions in the following parts are not offsets in the first part.
+    pub(crate) fn leave_first_part(&self) {
+        self.lock().frontier = None;
+    }
+
+    /// Registers an entry that has just been read from the archive, and returns its index for [ResumeState::complete].
+    pub(crate) fn start(&self) -> usize {
+        let position = self.position.load(Ordering::Acquire);
+        let mut tracker = self.lock();
+        // NOTE: The entries of a solid entry are read at the same position,
+        //       so a new archive item begins only when the position moves.
+        if position != tracker.last_position {
+            if tracker.frontier.is_some() {
+                tracker.frontier = Some(tracker.last_position);
+            }
+            tracker.last_position = position;
+        }
+        let index = tracker.next_index;
+        tracker.next_index += 1;
+        if let Some(frontier) = tracker.frontier {
+            tracker.pending.insert(index, frontier);
+        }
+        index
+    }
+
+    /// Records that the entry `name` registered as `index` has been extracted, and flushes it to the state file.
+    pub(crate) fn complete(&self, index: usize, name: &str) -> io::Result<()> {
+        let mut tracker = self.lock();
+        tracker.pending.remove(&index);
+        let offset = tracker
+            .frontier
+            .map(|frontier| tracker.pending.values().copied().fold(frontier, u64::min));
+        let mut line = serde_json::to_vec(&CompletedEntry {
+            name: name.into(),
+            offset,
+        })?;
+        line.push(b'\n');
+        tracker.file.write_all(&line)?;
+        tracker.file.flush()
+    }
+
+    #[inline]
+    fn lock(&self) -> std::sync::MutexGuard<'_, Tracker> {
+        self.tracker.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Reader that adds the number of bytes read to a shared position.
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    position: Arc<AtomicU64>,
+}
+
+impl<R> CountingReader<R> {
+    #[inline]
+    pub(crate) const fn new(inner: R, position: Arc<AtomicU64>) -> Self {
+        Self { inner, position }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position.fetch_add(n as u64, Ordering::Release);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pna::{Archive, EntryBuilder, WriteOptions};
+
+    fn write_archive(path: &Path, names: &[&str]) {
+        let mut archive = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+        for name in names {
+            let mut builder =
+                EntryBuilder::new_file((*name).into(), WriteOptions::store()).unwrap();
+            builder.write_all(name.as_bytes()).unwrap();
+            archive.add_entry(builder.build().unwrap()).unwrap();
+        }
+        archive.finalize().unwrap();
+    }
+
+    fn setup(name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join("pna_resume_state").join(name);
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("archive.pna");
+        write_archive(&archive, &["a", "b", "c"]);
+        (archive, dir.join("state.json"))
+    }
+
+    fn read_entry(state: &ResumeState, len: u64) -> usize {
+        state.position.fetch_add(len, Ordering::Release);
+        state.start()
+    }
+
+    #[test]
+    fn offset_stops_at_first_incomplete_entry() {
+        let (archive, path) = setup("offset_stops_at_first_incomplete_entry");
+        let state = ResumeState::open(&path, &archive).unwrap();
+        assert!(!state.is_resuming());
+        let header_len = state.header_len();
+        state.position.store(header_len, Ordering::Release);
+        let a = read_entry(&state, 10);
+        // NOTE: `b` is read but left incomplete.
+        read_entry(&state, 20);
+        let c = read_entry(&state, 30);
+        state.complete(a, "a").unwrap();
+        state.complete(c, "c").unwrap();
+        drop(state);
+
+        let state = ResumeState::open(&path, &archive).unwrap();
+        assert!(state.is_resuming());
+        assert!(state.is_completed("a") && state.is_completed("c"));
+        assert!(!state.is_completed("b"));
+        assert_eq!(state.offset(), Some(header_len + 10));
+        let b = {
+            state.position.store(header_len + 10, Ordering::Release);
+            read_entry(&state, 20)
+        };
+        assert_eq!(b, 0);
+        state.complete(b, "b").unwrap();
+        drop(state);
+
+        // NOTE: The item read last may be followed by more entries of the same solid entry.
+        let state = ResumeState::open(&path, &archive).unwrap();
+        assert_eq!(state.offset(), Some(header_len + 10));
+    }
+
+    #[test]
+    fn entries_of_solid_entry_share_offset() {
+        let (archive, path) = setup("entries_of_solid_entry_share_offset");
+        let state = ResumeState::open(&path, &archive).unwrap();
+        let header_len = state.header_len();
+        state.position.store(header_len, Ordering::Release);
+        let a = read_entry(&state, 100);
+        let _b = read_entry(&state, 0);
+        let c = read_entry(&state, 10);
+        state.complete(a, "a").unwrap();
+        state.complete(c, "c").unwrap();
+        drop(state);
+        let state = ResumeState::open(&path, &archive).unwrap();
+        assert_eq!(state.offset(), Some(header_len));
+    }
+
+    #[test]
+    fn no_offset_after_first_part() {
+        let (archive, path) = setup("no_offset_after_first_part");
+        let state = ResumeState::open(&path, &archive).unwrap();
+        let header_len = state.header_len();
+        state.position.store(header_len, Ordering::Release);
+        let a = read_entry(&state, 10);
+        state.complete(a, "a").unwrap();
+        state.leave_first_part();
+        let b = read_entry(&state, 10);
+        state.complete(b, "b").unwrap();
+        drop(state);
+        let state = ResumeState::open(&path, &archive).unwrap();
+        assert_eq!(state.offset(), Some(header_len));
+        assert!(state.is_completed("b"));
+    }
+
+    #[test]
+    fn incomplete_last_line_is_discarded() {
+        let (archive, path) = setup("incomplete_last_line_is_discarded");
+        let state = ResumeState::open(&path, &archive).unwrap();
+        let a = read_entry(&state, 10);
+        state.complete(a, "a").unwrap();
+        drop(state);
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(br#"{"name":"b","off"#)
+            .unwrap();
+        let state = ResumeState::open(&path, &archive).unwrap();
+        assert!(state.is_completed("a"));
+        assert!(!state.is_completed("b"));
+        let b = read_entry(&state, 10);
+        state.complete(b, "b").unwrap();
+        drop(state);
+        let state = ResumeState::open(&path, &archive).unwrap();
+        assert!(state.is_completed("b"));
+    }
+
+    #[test]
+    fn refuse_another_archive() {
+        let (archive, path) = setup("refuse_another_archive");
+        drop(ResumeState::open(&path, &archive).unwrap());
+        write_archive(&archive, &["a", "b", "d"]);
+        let err = ResumeState::open(&path, &archive).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn header_len_of_archive() {
+        let (archive, path) = setup("header_len_of_archive");
+        let state = ResumeState::open(&path, &archive).unwrap();
+        // PNA header and AHED chunk, since `Archive::write_header` records no archive id
+        assert_eq!(state.header_len(), 8 + (12 + 8));
+    }
+}
@@ -0,0 +1,231 @@
+use crate::utils::hash::hex;
+use pna::{ArchiveId, Encryption, NormalEntry, SolidEntry};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    io::{self, prelude::*, BufReader},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+const DIGEST_SIZE: usize = 32;
+
+/// Directory that stores decrypted and decompressed contents of solid entries,
+/// so that extracting entries from the same solid entry again skips decompression.
+///
+/// Each file is keyed by the archive id, the index of the solid entry in the archive and
+/// the digest of its raw data, and starts with the digest of the cached contents.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct SolidCache {
+    dir: PathBuf,
+    limit: Option<u64>,
+    encrypted: bool,
+}
+
+impl SolidCache {
+    #[inline]
+    pub(crate) fn new(dir: PathBuf, limit: Option<u64>, encrypted: bool) -> io::Result<Self> {
+        create_private_dir(&dir)?;
+        Ok(Self {
+            dir,
+            limit,
+            encrypted,
+        })
+    }
+
+    /// Returns the entries in `solid`, reading them from the cache when it holds a valid copy.
+    pub(crate) fn entries<'a, T: AsRef<[u8]>>(
+        &self,
+        solid: &'a SolidEntry<T>,
+        archive_id: Option<ArchiveId>,
+        index: usize,
+        password: Option<&str>,
+    ) -> io::Result<Box<dyn Iterator<Item = io::Result<NormalEntry>> + 'a>> {
+        if solid.header().encryption() != Encryption::No && !self.encrypted {
+            log::debug!("Skip caching encrypted solid entry {index}");
+            return Ok(Box::new(solid.entries(password)?));
+        }
+        let path = self.dir.join(cache_file_name(solid, archive_id, index));
+        if let Some(reader) = open_valid(&path)? {
+            log::debug!("Use cached solid entry: {}", path.display());
+            return Ok(Box::new(SolidEntry::entries_from_contents(reader)));
+        }
+        log::debug!("Decompress solid entry: {}", path.display());
+        store(&path, solid.contents(password)?)?;
+        let reader = open_valid(&path)?
+            .ok_or_else(|| io::Error::other(format!("failed to store cache {}", path.display())))?;
+        self.evict(&path)?;
+        Ok(Box::new(SolidEntry::entries_from_contents(reader)))
+    }
+
+    /// Removes least recently used files until the total size fits in the limit.
+    /// The file at `keep` is never removed.
+    fn evict(&self, keep: &Path) -> io::Result<()> {
+        let Some(limit) = self.limit else {
+            return Ok(());
+        };
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if metadata.is_file() && entry.path().extension().is_some_and(|it| it == "cache") {
+                files.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        let mut total = files.iter().map(|(_, len, _)| len).sum::<u64>();
+        files.sort();
+        for (_, len, path) in files {
+            if total <= limit {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+            log::debug!("Evict cached solid entry: {}", path.display());
+            fs::remove_file(&path)?;
+            total -= len;
+        }
+        Ok(())
+    }
+}
+
+fn cache_file_name<T: AsRef<[u8]>>(
+    solid: &SolidEntry<T>,
+    archive_id: Option<ArchiveId>,
+    index: usize,
+) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(solid.header().to_bytes());
+    for data in solid.raw_data() {
+        hasher.update(data);
+    }
+    let archive_id = archive_id.map_or_else(|| "none".into(), hex);
+    format!("{archive_id}-{index}-{}.cache", hex(hasher.finalize()))
+}
+
+/// Opens the cache file at `path` after validating its contents with the stored digest.
+/// Returns `None` if the file does not exist or is broken.
+fn open_valid(path: &Path) -> io::Result<Option<impl Read>> {
+    let mut file = match fs::OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut expected = [0; DIGEST_SIZE];
+    let mut hasher = Sha256::new();
+    let valid = file.read_exact(&mut expected).is_ok()
+        && io::copy(&mut file, &mut hasher).is_ok()
+        && hasher.finalize()[..] == expected;
+    if !valid {
+        log::warn!("Remove broken cache {}", path.display());
+        fs::remove_file(path)?;
+        return Ok(None);
+    }
+    // NOTE: Update the modification time to record the use for the eviction.
+    file.set_modified(SystemTime::now())?;
+    file.seek(io::SeekFrom::Start(DIGEST_SIZE as u64))?;
+    Ok(Some(BufReader::new(file)))
+}
+
+/// Writes `contents` to `path` atomically, readable only by the current user.
+fn store(path: &Path, mut contents: impl Read) -> io::Result<()> {
+    let temp_path = path.with_extension(format!("{}.tmp", rand::random::<u64>()));
+    let result = (|| {
+        let mut file = create_private_file(&temp_path)?;
+        file.write_all(&[0; DIGEST_SIZE])?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            let n = contents.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            file.write_all(&buf[..n])?;
+        }
+        file.rewind()?;
+        file.write_all(&hasher.finalize())?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path)
+}
+
+fn create_private_file(path: &Path) -> io::Result<fs::File> {
+    let mut options = fs::OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pna::{EntryBuilder, ReadEntry, SolidEntryBuilder, WriteOptions};
+
+    fn solid_entry() -> SolidEntry {
+        let mut builder = SolidEntryBuilder::new(WriteOptions::builder().build()).unwrap();
+        let mut entry = EntryBuilder::new_file("a.txt".into(), WriteOptions::store()).unwrap();
+        entry.write_all(b"a").unwrap();
+        builder.add_entry(entry.build().unwrap()).unwrap();
+        let mut archive = pna::Archive::write_header(Vec::new()).unwrap();
+        archive.add_entry(builder.build().unwrap()).unwrap();
+        let bytes = archive.finalize().unwrap();
+        let mut archive = pna::Archive::read_header(bytes.as_slice()).unwrap();
+        match archive.entries().next().unwrap().unwrap() {
+            ReadEntry::Solid(solid) => solid,
+            ReadEntry::Normal(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn reuse_and_evict() {
+        let dir = std::env::temp_dir().join("pna_solid_cache_unit");
+        let _ = fs::remove_dir_all(&dir);
+        let cache = SolidCache::new(dir.clone(), Some(0), false).unwrap();
+        let solid = solid_entry();
+        let path = dir.join(cache_file_name(&solid, None, 0));
+
+        let entries = cache.entries(&solid, None, 0, None).unwrap().count();
+        assert_eq!(entries, 1);
+        assert!(path.exists());
+        assert!(open_valid(&path).unwrap().is_some());
+
+        // The most recently stored file is kept even if it exceeds the limit,
+        // while the older ones are evicted.
+        cache.entries(&solid, None, 1, None).unwrap().count();
+        assert!(!path.exists());
+        assert!(dir.join(cache_file_name(&solid, None, 1)).exists());
+    }
+
+    #[test]
+    fn remove_broken_cache() {
+        let dir = std::env::temp_dir().join("pna_solid_cache_broken");
+        let _ = fs::remove_dir_all(&dir);
+        let cache = SolidCache::new(dir.clone(), None, false).unwrap();
+        let solid = solid_entry();
+        let path = dir.join(cache_file_name(&solid, None, 0));
+        fs::write(&path, b"broken").unwrap();
+
+        assert_eq!(cache.entries(&solid, None, 0, None).unwrap().count(), 1);
+        assert!(open_valid(&path).unwrap().is_some());
+    }
+}
@@ -24,10 +24,41 @@ pub(crate) fn char_chunks(src: &str, chunk_size: usize) -> impl Iterator<Item =
         .into_iter()
 }
 
+/// Levenshtein distance between `a` and `b` counted in chars.
+#[inline]
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn edit_distance_cases() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("text.txt", "txet.txt"), 2);
+        assert_eq!(edit_distance("日本語", "日本"), 1);
+    }
+
     #[test]
     fn char_windows_empty() {
         let mut iter = char_chunks("", 2);
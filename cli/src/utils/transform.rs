@@ -0,0 +1,375 @@
+use regex::{Regex, RegexBuilder};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+};
+
+/// Kind of the names that a substitution rule is applied to.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum NameKind {
+    /// Names of the entries
+    Regular,
+    /// Targets of symbolic links
+    SymlinkTarget,
+    /// Targets of hard links
+    HardlinkTarget,
+}
+
+/// Substitution of entry names in the style of `sed`,
+/// given by `--transform s/REGEX/REPLACEMENT/FLAGS` (GNU tar) or `-s /REGEX/REPLACEMENT/FLAGS` (BSD tar).
+///
+/// The regex is a POSIX basic regular expression unless the `x` flag is given.
+/// `\1` to `\9` and `&` in the replacement refer to the matched groups and the whole match.
+/// The flags are:
+///
+/// - `g`: replace all matches
+/// - `N`: replace the N-th match, or the N-th and the following matches with `g`
+/// - `i`: ignore case
+/// - `x`: use extended regular expressions
+/// - `p`: print the rewritten names (BSD style)
+/// - `r`/`R`, `s`/`S`, `h`/`H`: apply or do not apply to entry names, symbolic link targets and hard link targets
+#[derive(Clone, Debug)]
+pub(crate) struct SubstitutionRule {
+    source: String,
+    regex: Regex,
+    replacement: String,
+    nth: usize,
+    global: bool,
+    print: bool,
+    regular: bool,
+    symlink: bool,
+    hardlink: bool,
+}
+
+impl SubstitutionRule {
+    /// Parses `s/REGEX/REPLACEMENT/FLAGS` given by `--transform`.
+    pub(crate) fn parse_gnu(s: &str) -> Result<Self, String> {
+        let Some(expression) = s.strip_prefix('s') else {
+            return Err(format!("`{s}` must start with `s`, such as `s/old/new/`"));
+        };
+        Self::parse_expression(s, expression)
+    }
+
+    /// Parses `/REGEX/REPLACEMENT/FLAGS` given by `-s`.
+    pub(crate) fn parse_bsd(s: &str) -> Result<Self, String> {
+        Self::parse_expression(s, s)
+    }
+
+    fn parse_expression(source: &str, expression: &str) -> Result<Self, String> {
+        let mut chars = expression.chars();
+        let Some(delimiter) = chars.next().filter(|it| *it != '\\') else {
+            return Err(format!("`{source}` has no valid delimiter"));
+        };
+        let mut fields = vec![String::new()];
+        let mut escaped = false;
+        for c in chars {
+            let n = fields.len();
+            let field = fields.last_mut().expect("fields are not empty");
+            if escaped {
+                if c != delimiter {
+                    field.push('\\');
+                }
+                field.push(c);
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == delimiter && n < 3 {
+                fields.push(String::new());
+            } else {
+                field.push(c);
+            }
+        }
+        if escaped || fields.len() != 3 {
+            return Err(format!(
+                "`{source}` must be in the form of `{delimiter}regex{delimiter}replacement{delimiter}flags`"
+            ));
+        }
+        let flags = fields.pop().expect("three fields");
+        let replacement = fields.pop().expect("three fields");
+        let pattern = fields.pop().expect("three fields");
+
+        let mut nth = None;
+        let (mut global, mut print, mut extended, mut ignore_case) = (false, false, false, false);
+        let (mut regular, mut symlink, mut hardlink) = (true, true, true);
+        for flag in flags.chars() {
+            match flag {
+                'g' => global = true,
+                'p' => print = true,
+                'x' => extended = true,
+                'i' => ignore_case = true,
+                'r' | 'R' => regular = flag == 'r',
+                's' | 'S' => symlink = flag == 's',
+                'h' | 'H' => hardlink = flag == 'h',
+                '0'..='9' => {
+                    let n = nth.unwrap_or(0usize);
+                    nth = Some(n * 10 + flag.to_digit(10).expect("digit") as usize);
+                }
+                _ => return Err(format!("unknown flag `{flag}` in `{source}`")),
+            }
+        }
+        let nth = nth.unwrap_or(1);
+        if nth == 0 {
+            return Err(format!(
+                "the occurrence in `{source}` must be greater than 0"
+            ));
+        }
+        let pattern = if extended {
+            pattern
+        } else {
+            basic_to_extended(&pattern)
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            source: source.into(),
+            regex,
+            replacement: expand_replacement(&replacement),
+            nth,
+            global,
+            print,
+            regular,
+            symlink,
+            hardlink,
+        })
+    }
+
+    #[inline]
+    fn applies_to(&self, kind: NameKind) -> bool {
+        match kind {
+            NameKind::Regular => self.regular,
+            NameKind::SymlinkTarget => self.symlink,
+            NameKind::HardlinkTarget => self.hardlink,
+        }
+    }
+
+    /// Returns the substituted `name`, or `None` if the regex does not match.
+    fn replace(&self, name: &str) -> Option<String> {
+        let mut replaced = String::new();
+        let mut last = 0;
+        let mut matched = false;
+        for caps in self.regex.captures_iter(name).skip(self.nth - 1) {
+            matched = true;
+            let m = caps.get(0).expect("whole match");
+            replaced.push_str(&name[last..m.start()]);
+            caps.expand(&self.replacement, &mut replaced);
+            last = m.end();
+            if !self.global {
+                break;
+            }
+        }
+        if !matched {
+            return None;
+        }
+        replaced.push_str(&name[last..]);
+        Some(replaced)
+    }
+}
+
+impl PartialEq for SubstitutionRule {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for SubstitutionRule {}
+
+impl PartialOrd for SubstitutionRule {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SubstitutionRule {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.source.cmp(&other.source)
+    }
+}
+
+impl Hash for SubstitutionRule {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state)
+    }
+}
+
+/// Rewrites names with the substitution rules given by `--transform` or `-s`.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub(crate) struct Transformer {
+    rules: Vec<SubstitutionRule>,
+    /// Applies only the first matching rule as BSD tar does,
+    /// otherwise applies all rules in order as GNU tar does.
+    first_match_only: bool,
+}
+
+impl Transformer {
+    #[inline]
+    pub(crate) const fn gnu(rules: Vec<SubstitutionRule>) -> Self {
+        Self {
+            rules,
+            first_match_only: false,
+        }
+    }
+
+    #[inline]
+    pub(crate) const fn bsd(rules: Vec<SubstitutionRule>) -> Self {
+        Self {
+            rules,
+            first_match_only: true,
+        }
+    }
+
+    /// Applies the rules that apply to `kind` to `name`.
+    pub(crate) fn apply<'n>(&self, name: &'n str, kind: NameKind) -> Cow<'n, str> {
+        let mut name = Cow::Borrowed(name);
+        for rule in self.rules.iter().filter(|it| it.applies_to(kind)) {
+            let Some(replaced) = rule.replace(&name) else {
+                continue;
+            };
+            if rule.print {
+                log::info!("{name} >> {replaced}");
+            }
+            name = Cow::Owned(replaced);
+            if self.first_match_only {
+                break;
+            }
+        }
+        name
+    }
+}
+
+/// Converts a POSIX basic regular expression to the syntax of [Regex],
+/// in which `(`, `)`, `{`, `}`, `|`, `+` and `?` are special unless escaped.
+fn basic_to_extended(pattern: &str) -> String {
+    const SPECIALS: [char; 7] = ['(', ')', '{', '}', '|', '+', '?'];
+    let mut extended = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c) if SPECIALS.contains(&c) => extended.push(c),
+                Some(c) => {
+                    extended.push('\\');
+                    extended.push(c);
+                }
+                None => extended.push_str(r"\\"),
+            },
+            c if SPECIALS.contains(&c) => {
+                extended.push('\\');
+                extended.push(c);
+            }
+            c => extended.push(c),
+        }
+    }
+    extended
+}
+
+/// Converts `\1` to `\9`, `&` and the escapes in a `sed` replacement to the syntax of [regex::Captures::expand].
+fn expand_replacement(replacement: &str) -> String {
+    let mut expanded = String::with_capacity(replacement.len());
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(d @ '0'..='9') => {
+                    expanded.push_str("${");
+                    expanded.push(d);
+                    expanded.push('}');
+                }
+                Some('n') => expanded.push('\n'),
+                Some('$') => expanded.push_str("$$"),
+                Some(c) => expanded.push(c),
+                None => expanded.push('\\'),
+            },
+            '&' => expanded.push_str("${0}"),
+            '$' => expanded.push_str("$$"),
+            c => expanded.push(c),
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gnu(rules: &[&str]) -> Transformer {
+        Transformer::gnu(
+            rules
+                .iter()
+                .map(|it| SubstitutionRule::parse_gnu(it).unwrap())
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(SubstitutionRule::parse_gnu("/a/b/").is_err());
+        assert!(SubstitutionRule::parse_gnu("s/a/b").is_err());
+        assert!(SubstitutionRule::parse_gnu("s/a/b/z").is_err());
+        assert!(SubstitutionRule::parse_gnu("s/a/b/0").is_err());
+        assert!(SubstitutionRule::parse_bsd("/a/b").is_err());
+        assert!(SubstitutionRule::parse_bsd("/a/b/").is_ok());
+    }
+
+    #[test]
+    fn substitute() {
+        let t = gnu(&["s/^dir/out/"]);
+        assert_eq!(t.apply("dir/a.txt", NameKind::Regular), "out/a.txt");
+        assert_eq!(t.apply("a/dir.txt", NameKind::Regular), "a/dir.txt");
+
+        let t = gnu(&["s,a,x,g"]);
+        assert_eq!(t.apply("a/ba/a", NameKind::Regular), "x/bx/x");
+        let t = gnu(&["s,a,x,2"]);
+        assert_eq!(t.apply("a/ba/a", NameKind::Regular), "a/bx/a");
+        let t = gnu(&["s,a,x,2g"]);
+        assert_eq!(t.apply("a/ba/a", NameKind::Regular), "a/bx/x");
+        let t = gnu(&["s/A/x/i"]);
+        assert_eq!(t.apply("a", NameKind::Regular), "x");
+        let t = gnu(&["s/a/\\//"]);
+        assert_eq!(t.apply("a", NameKind::Regular), "/");
+    }
+
+    #[test]
+    fn basic_and_extended_regex() {
+        let t = gnu(&[r"s/\(.*\)\.txt/\1.md/"]);
+        assert_eq!(t.apply("a/b.txt", NameKind::Regular), "a/b.md");
+        let t = gnu(&[r"s/(a)/[&]/"]);
+        assert_eq!(t.apply("(a)", NameKind::Regular), "[(a)]");
+        let t = gnu(&[r"s/(.*)\.txt/\1.md/x"]);
+        assert_eq!(t.apply("a/b.txt", NameKind::Regular), "a/b.md");
+        let t = gnu(&[r"s/a/$1/"]);
+        assert_eq!(t.apply("a", NameKind::Regular), "$1");
+    }
+
+    #[test]
+    fn rules_in_order() {
+        let t = gnu(&["s/a/b/", "s/b/c/"]);
+        assert_eq!(t.apply("a", NameKind::Regular), "c");
+        let t = Transformer::bsd(vec![
+            SubstitutionRule::parse_bsd("/a/b/").unwrap(),
+            SubstitutionRule::parse_bsd("/b/c/").unwrap(),
+        ]);
+        assert_eq!(t.apply("a", NameKind::Regular), "b");
+        assert_eq!(t.apply("b", NameKind::Regular), "c");
+    }
+
+    #[test]
+    fn scope() {
+        let t = gnu(&["s/a/b/"]);
+        assert_eq!(t.apply("a", NameKind::SymlinkTarget), "b");
+        assert_eq!(t.apply("a", NameKind::HardlinkTarget), "b");
+        let t = gnu(&["s/a/b/SH"]);
+        assert_eq!(t.apply("a", NameKind::Regular), "b");
+        assert_eq!(t.apply("a", NameKind::SymlinkTarget), "a");
+        assert_eq!(t.apply("a", NameKind::HardlinkTarget), "a");
+        let t = gnu(&["s/a/b/R"]);
+        assert_eq!(t.apply("a", NameKind::Regular), "a");
+        assert_eq!(t.apply("a", NameKind::SymlinkTarget), "b");
+    }
+}
@@ -0,0 +1,146 @@
+use crate::exit_code::usage_error;
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Archive given on the command line, a local path, `-` for the standard input or an http(s) URL.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) enum ArchiveLocation {
+    Path(PathBuf),
+    Stdio,
+    #[cfg(feature = "http")]
+    Url(String),
+}
+
+impl ArchiveLocation {
+    /// Parses the archive argument, translating a `file://` URL to a path.
+    /// `-` is the standard input, and arguments without a known scheme are paths.
+    #[inline]
+    pub(crate) fn parse(archive: &Path) -> io::Result<Self> {
+        if is_stdio(archive) {
+            return Ok(Self::Stdio);
+        }
+        let Some(s) = archive.to_str() else {
+            return Ok(Self::Path(archive.into()));
+        };
+        if let Some(rest) = strip_scheme(s, "file") {
+            return file_url_path(rest).map(Self::Path);
+        }
+        if strip_scheme(s, "http").is_some() || strip_scheme(s, "https").is_some() {
+            #[cfg(feature = "http")]
+            return Ok(Self::Url(s.into()));
+            #[cfg(not(feature = "http"))]
+            return Err(usage_error(format!(
+                "`{s}` is a URL, but reading archives over http(s) requires the `http` feature"
+            )));
+        }
+        Ok(Self::Path(archive.into()))
+    }
+}
+
+/// Returns `true` if the archive argument is `-`, which means the standard input or output.
+#[inline]
+pub(crate) fn is_stdio(archive: &Path) -> bool {
+    archive.as_os_str() == "-"
+}
+
+fn strip_scheme<'s>(s: &'s str, scheme: &str) -> Option<&'s str> {
+    let (head, rest) = s.split_once("://")?;
+    head.eq_ignore_ascii_case(scheme).then_some(rest)
+}
+
+/// Converts the part after `file://` to a path. The host must be empty or `localhost`.
+fn file_url_path(rest: &str) -> io::Result<PathBuf> {
+    let invalid = |reason: &str| usage_error(format!("invalid file URL `file://{rest}`: {reason}"));
+    let (host, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+    if !host.is_empty() && !host.eq_ignore_ascii_case("localhost") {
+        return Err(invalid("only local files are supported"));
+    }
+    if path.is_empty() {
+        return Err(invalid("path is empty"));
+    }
+    let path = percent_decode(path).ok_or_else(|| invalid("broken percent encoding"))?;
+    // NOTE: `file:///C:/dir` is `C:/dir` on Windows.
+    #[cfg(windows)]
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    Ok(PathBuf::from(path))
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> io::Result<ArchiveLocation> {
+        ArchiveLocation::parse(Path::new(s))
+    }
+
+    #[test]
+    fn plain_path() {
+        assert_eq!(
+            parse("dir/archive.pna").unwrap(),
+            ArchiveLocation::Path("dir/archive.pna".into())
+        );
+    }
+
+    #[test]
+    fn stdio() {
+        assert_eq!(parse("-").unwrap(), ArchiveLocation::Stdio);
+        assert_eq!(parse("./-").unwrap(), ArchiveLocation::Path("./-".into()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_url() {
+        assert_eq!(
+            parse("file:///tmp/my%20archive.pna").unwrap(),
+            ArchiveLocation::Path("/tmp/my archive.pna".into())
+        );
+        assert_eq!(
+            parse("FILE://localhost/tmp/a.pna").unwrap(),
+            ArchiveLocation::Path("/tmp/a.pna".into())
+        );
+    }
+
+    #[test]
+    fn invalid_file_url() {
+        assert!(parse("file://example.com/a.pna").is_err());
+        assert!(parse("file://").is_err());
+        assert!(parse("file:///a%2").is_err());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn http_url() {
+        assert_eq!(
+            parse("https://example.com/a.pna").unwrap(),
+            ArchiveLocation::Url("https://example.com/a.pna".into())
+        );
+    }
+
+    #[cfg(not(feature = "http"))]
+    #[test]
+    fn http_url_without_feature() {
+        assert_eq!(
+            parse("http://example.com/a.pna").unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    fs,
+    io::{self, Read},
+    path::PathBuf,
+};
+
+/// Maximum size of a trained dictionary, the same as the default of the zstd command.
+pub(crate) const MAX_DICTIONARY_SIZE: usize = 110 * 1024;
+/// Maximum number of bytes sampled from the head of each file.
+const MAX_SAMPLE_SIZE: usize = 128 * 1024;
+/// Maximum number of bytes sampled from all the files.
+const MAX_TOTAL_SAMPLE_SIZE: usize = 64 * 1024 * 1024;
+
+/// Trains a zstd dictionary from the heads of the regular files in `items`.
+///
+/// Symbolic links and empty files are not sampled, and files that cannot be read are left to fail when archived.
+pub(crate) fn train_dictionary(items: &[PathBuf]) -> io::Result<Vec<u8>> {
+    let mut samples = Vec::new();
+    let mut total = 0;
+    for path in items {
+        if total >= MAX_TOTAL_SAMPLE_SIZE {
+            break;
+        }
+        match fs::symlink_metadata(path) {
+            Ok(meta) if meta.is_file() && meta.len() != 0 => (),
+            _ => continue,
+        }
+        let Ok(file) = fs::File::open(path) else {
+            continue;
+        };
+        let mut sample = Vec::new();
+        file.take(MAX_SAMPLE_SIZE.min(MAX_TOTAL_SAMPLE_SIZE - total) as u64)
+            .read_to_end(&mut sample)?;
+        total += sample.len();
+        samples.push(sample);
+    }
+    log::debug!(
+        "Training a zstd dictionary from {} samples ({} bytes)",
+        samples.len(),
+        total
+    );
+    // NOTE: Training fails when the dictionary is not much smaller than the samples.
+    pna::train_zstd_dictionary(&samples, MAX_DICTIONARY_SIZE.min(total / 10))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn train() {
+        let dir = std::env::temp_dir().join("pna_train_dictionary");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let items = (0..256)
+            .map(|i| {
+                let path = dir.join(format!("{i}.json"));
+                fs::write(
+                    &path,
+                    format!(r#"{{"id":{i},"level":"info","message":"request {i} handled"}}"#),
+                )
+                .unwrap();
+                path
+            })
+            .collect::<Vec<_>>();
+        let dictionary = train_dictionary(&items).unwrap();
+        assert!(!dictionary.is_empty());
+        assert!(dictionary.len() <= MAX_DICTIONARY_SIZE);
+    }
+}
@@ -1,6 +1,33 @@
-use crate::utils::setup;
+use crate::utils::{diff::diff, setup};
 use clap::Parser;
+use pna::{CipherMode, Compression, Encryption, ReadOptions};
 use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    io::Read,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+fn entry_settings(path: &str) -> Vec<(Compression, Encryption, CipherMode, Option<String>)> {
+    let mut archive = pna::Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    archive
+        .entries_skip_solid()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let header = entry.header();
+            (
+                header.compression(),
+                header.encryption(),
+                header.cipher_mode(),
+                entry
+                    .password_hash()
+                    .and_then(|it| it.split('$').nth(1))
+                    .map(String::from),
+            )
+        })
+        .collect()
+}
 
 #[test]
 fn archive_append() {
@@ -75,3 +102,406 @@ fn archive_append_split() {
     ]))
     .unwrap();
 }
+
+fn archive_parts(name: &str) -> Vec<PathBuf> {
+    (1..)
+        .map(|n| {
+            PathBuf::from(format!(
+                "{}/{name}/archive.part{n}.pna",
+                env!("CARGO_TARGET_TMPDIR")
+            ))
+        })
+        .take_while(|it| it.exists())
+        .collect()
+}
+
+fn create_split_fixture(name: &str) -> Vec<PathBuf> {
+    let _ = fs::remove_dir_all(format!("{}/{name}", env!("CARGO_TARGET_TMPDIR")));
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/{name}/archive.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+        "--split",
+        "100kib",
+        #[cfg(windows)]
+        {
+            "--unstable"
+        },
+    ]))
+    .unwrap();
+    let parts = archive_parts(name);
+    assert_eq!(parts.len(), 2);
+    parts
+}
+
+fn extract_and_compare(name: &str, archive: &str, appended: &[&str]) {
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        archive,
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/{name}/out/", env!("CARGO_TARGET_TMPDIR")),
+    ]))
+    .unwrap();
+    diff(
+        "../resources/test/raw",
+        format!(
+            "{}/{name}/out/resources/test/raw",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+    )
+    .unwrap();
+    for file in appended {
+        assert_eq!(
+            fs::read(format!("../resources/test/{file}")).unwrap(),
+            fs::read(format!(
+                "{}/{name}/out/resources/test/{file}",
+                env!("CARGO_TARGET_TMPDIR")
+            ))
+            .unwrap(),
+        );
+    }
+}
+
+#[test]
+fn archive_append_to_last_part() {
+    setup();
+    let parts = create_split_fixture("append_last_part");
+    let first_part = fs::read(&parts[0]).unwrap();
+    // The name of the archive without the part number locates its parts.
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &format!(
+            "{}/append_last_part/archive.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "../resources/test/store.pna",
+    ]))
+    .unwrap();
+    // So does the last part.
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        parts[1].to_str().unwrap(),
+        "../resources/test/zstd.pna",
+    ]))
+    .unwrap();
+    assert_eq!(archive_parts("append_last_part"), parts);
+    assert_eq!(fs::read(&parts[0]).unwrap(), first_part);
+
+    extract_and_compare(
+        "append_last_part",
+        parts[0].to_str().unwrap(),
+        &["store.pna", "zstd.pna"],
+    );
+}
+
+#[test]
+fn archive_append_split_to_new_part() {
+    setup();
+    let parts = create_split_fixture("append_split_new_part");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        parts[1].to_str().unwrap(),
+        "--store",
+        "--split",
+        "100kib",
+        "../resources/test/zstd.pna",
+        "../resources/test/xz.pna",
+    ]))
+    .unwrap();
+    let appended = archive_parts("append_split_new_part");
+    assert!(appended.len() > parts.len());
+    for part in &appended {
+        assert!(fs::metadata(part).unwrap().len() <= 100 * 1024);
+    }
+
+    extract_and_compare(
+        "append_split_new_part",
+        appended[0].to_str().unwrap(),
+        &["zstd.pna", "xz.pna"],
+    );
+}
+
+#[test]
+fn archive_append_split_renames_archive() {
+    setup();
+    let _ = fs::remove_dir_all(format!(
+        "{}/append_split_rename",
+        env!("CARGO_TARGET_TMPDIR")
+    ));
+    let path = format!(
+        "{}/append_split_rename/archive.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &path,
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &path,
+        "--store",
+        "--split",
+        "100kib",
+        "../resources/test/zstd.pna",
+    ]))
+    .unwrap();
+    let parts = archive_parts("append_split_rename");
+    assert!(parts.len() > 1);
+    assert!(!PathBuf::from(&path).exists());
+
+    extract_and_compare(
+        "append_split_rename",
+        parts[0].to_str().unwrap(),
+        &["zstd.pna"],
+    );
+}
+
+#[test]
+fn archive_append_match_existing_compression() {
+    setup();
+    let path = format!(
+        "{}/append_match_existing_xz.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &path,
+        "--overwrite",
+        "../resources/test/raw/text.txt",
+        "--xz",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &path,
+        "--match-existing",
+        "../resources/test/raw/empty.txt",
+    ]))
+    .unwrap();
+
+    let settings = entry_settings(&path);
+    assert_eq!(settings.len(), 2);
+    assert!(settings
+        .iter()
+        .all(|(compression, ..)| *compression == Compression::XZ));
+}
+
+#[test]
+fn archive_append_match_existing_encryption() {
+    setup();
+    let path = format!(
+        "{}/append_match_existing_camellia.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &path,
+        "--overwrite",
+        "--deflate",
+        "--password",
+        "password",
+        "--camellia",
+        "cbc",
+        "--pbkdf2",
+        "r=1",
+        "../resources/test/raw/text.txt",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &path,
+        "--match-existing",
+        "--password",
+        "password",
+        "../resources/test/raw/empty.txt",
+    ]))
+    .unwrap();
+
+    let settings = entry_settings(&path);
+    assert_eq!(settings.len(), 2);
+    assert_eq!(settings[0], settings[1]);
+    assert_eq!(
+        settings[1],
+        (
+            Compression::Deflate,
+            Encryption::Camellia,
+            CipherMode::CBC,
+            Some("pbkdf2-sha256".into())
+        )
+    );
+}
+
+fn entry_contents(path: &str) -> Vec<(String, String)> {
+    let mut archive = pna::Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    archive
+        .entries_skip_solid()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let mut content = String::new();
+            entry
+                .reader(ReadOptions::builder().build())
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            (entry.header().path().to_string(), content)
+        })
+        .collect()
+}
+
+/// Creates an archive containing `config.json` written with `content`, returns the paths of them.
+fn init_config(name: &str, content: &str, modified: SystemTime) -> (String, String) {
+    let dir = format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{dir}.pna");
+    let file = format!("{dir}/config.json");
+    fs::create_dir_all(&dir).unwrap();
+    write_config(&file, content, modified);
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "--keep-timestamp",
+        &file,
+    ]))
+    .unwrap();
+    (archive, file)
+}
+
+fn write_config(path: &str, content: &str, modified: SystemTime) {
+    fs::write(path, content).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+#[test]
+fn archive_append_replace() {
+    setup();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let (archive, file) = init_config("append_replace", "old", modified);
+    write_config(&file, "new", modified);
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &archive,
+        "--replace",
+        &file,
+    ]))
+    .unwrap();
+
+    let entries = entry_contents(&archive);
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].0.ends_with("config.json"));
+    assert_eq!(entries[0].1, "new");
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn archive_append_no_replace_warns_duplicates() {
+    setup();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let (archive, file) = init_config("append_no_replace", "old", modified);
+    write_config(&file, "new", modified);
+    let assert = assert_cmd::Command::cargo_bin("pna")
+        .unwrap()
+        .args(["append", &archive, &file])
+        .assert()
+        .success();
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(stderr.contains("warning: The archive already contains the entries of the same name"));
+    assert!(stderr.contains("config.json"));
+
+    let contents = entry_contents(&archive)
+        .into_iter()
+        .map(|(_, content)| content)
+        .collect::<Vec<_>>();
+    assert_eq!(contents, ["old", "new"]);
+}
+
+#[test]
+fn archive_append_add_newer_only() {
+    setup();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let (archive, file) = init_config("append_add_newer_only", "old", modified);
+
+    // NOTE: The entry is kept when the file has the same mtime.
+    write_config(&file, "same mtime", modified);
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &archive,
+        "--add-newer-only",
+        "--keep-timestamp",
+        &file,
+    ]))
+    .unwrap();
+    let entries = entry_contents(&archive);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].1, "old");
+
+    // NOTE: The fractional part of a second is not taken into account for the entries of whole seconds.
+    write_config(&file, "same second", modified + Duration::from_millis(500));
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &archive,
+        "--add-newer-only",
+        "--keep-timestamp",
+        &file,
+    ]))
+    .unwrap();
+    let entries = entry_contents(&archive);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].1, "old");
+
+    write_config(&file, "newer", modified + Duration::from_secs(1));
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &archive,
+        "--add-newer-only",
+        "--keep-timestamp",
+        &file,
+    ]))
+    .unwrap();
+    let entries = entry_contents(&archive);
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].1, "newer");
+}
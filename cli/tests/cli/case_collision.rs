@@ -0,0 +1,68 @@
+#![cfg(any(windows, target_os = "macos"))]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{fs, io::Write, path::Path};
+
+fn init_resource(path: &str) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for name in ["README", "readme", "other.txt"] {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(name.as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) -> std::io::Result<()> {
+    let _ = fs::remove_dir_all(out_dir);
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet", "x", archive, "--out-dir", out_dir]
+            .into_iter()
+            .chain(options.iter().copied()),
+    ))
+}
+
+#[test]
+fn case_collision_error() {
+    setup();
+    let archive = format!("{}/case_collision/error.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/case_collision/error", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    assert!(extract(&archive, &out_dir, &[]).is_err());
+}
+
+#[test]
+fn case_collision_rename() {
+    setup();
+    let archive = format!("{}/case_collision/rename.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/case_collision/rename", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    extract(&archive, &out_dir, &["--case-collision", "rename"]).unwrap();
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/README")).unwrap(),
+        "README"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/readme~1")).unwrap(),
+        "readme"
+    );
+}
+
+#[test]
+fn case_collision_skip() {
+    setup();
+    let archive = format!("{}/case_collision/skip.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/case_collision/skip", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    extract(&archive, &out_dir, &["--case-collision", "skip"]).unwrap();
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/README")).unwrap(),
+        "README"
+    );
+    assert_eq!(fs::read_dir(&out_dir).unwrap().count(), 2);
+}
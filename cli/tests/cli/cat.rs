@@ -0,0 +1,85 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{create_archive, setup};
+
+const FILES: &[&str] = &["-r", "../resources/test/raw"];
+
+const TEXT: &str = "resources/test/raw/text.txt";
+const PNA_TXT: &str = "resources/test/raw/first/second/third/pna.txt";
+
+fn cat(args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "experimental", "cat"]).args(args);
+    cmd.assert()
+}
+
+fn cat_stdout(args: &[&str]) -> String {
+    let assert = cat(args).success();
+    String::from_utf8(assert.get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn cat_entries_in_argument_order() {
+    setup();
+    let path = format!("{}/cat/order.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+    assert_eq!(cat_stdout(&[&path, TEXT]), "text file\n");
+    assert_eq!(cat_stdout(&[&path, PNA_TXT, TEXT]), "pnatext file\n");
+    assert_eq!(
+        cat_stdout(&[&path, TEXT, PNA_TXT, TEXT]),
+        "text file\npnatext file\n"
+    );
+}
+
+#[test]
+fn cat_bytes_and_lines() {
+    setup();
+    let path = format!("{}/cat/range.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+    assert_eq!(cat_stdout(&[&path, "--bytes", "5-7", TEXT]), "fil");
+    assert_eq!(cat_stdout(&[&path, "--bytes", "5-", TEXT]), "file\n");
+    assert_eq!(cat_stdout(&[&path, "--lines", "1", TEXT]), "text file\n");
+    cat(&[&path, "--bytes", "7-5", TEXT]).failure();
+}
+
+#[test]
+fn cat_solid_and_encrypted_entries() {
+    setup();
+    let solid = format!("{}/cat/solid.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&solid, FILES, &["--solid"]).unwrap();
+    assert_eq!(cat_stdout(&[&solid, TEXT]), "text file\n");
+    let encrypted = format!("{}/cat/encrypted.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(
+        &encrypted,
+        FILES,
+        &["--aes", "ctr", "--password", "password"],
+    )
+    .unwrap();
+    assert_eq!(
+        cat_stdout(&[&encrypted, TEXT, "--password", "password"]),
+        "text file\n"
+    );
+}
+
+#[test]
+fn cat_binary_to_pipe() {
+    setup();
+    let path = format!("{}/cat/binary.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+    let assert = cat(&[&path, "resources/test/raw/images/icon.png"]).success();
+    assert_eq!(
+        assert.get_output().stdout,
+        std::fs::read("../resources/test/raw/images/icon.png").unwrap()
+    );
+}
+
+#[test]
+fn cat_not_found_suggestion() {
+    setup();
+    let path = format!("{}/cat/not_found.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+    let assert = cat(&[&path, "text.txt", TEXT]).failure();
+    let output = assert.get_output();
+    assert_eq!(output.stdout, b"text file\n");
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("text.txt: entry not found, did you mean `resources/test/raw/text.txt`?"));
+}
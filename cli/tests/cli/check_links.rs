@@ -0,0 +1,112 @@
+#![cfg(unix)]
+use crate::utils::{create_archive, setup};
+use std::{fs, io, os::unix::fs::symlink, path::Path};
+
+/// Creates the directory `src` with a file and a directory, and a symbolic link `link` to `target` in it.
+fn init_resource(base: &str, target: &str) -> String {
+    let _ = fs::remove_dir_all(base);
+    let src = format!("{base}/src");
+    fs::create_dir_all(format!("{src}/dir")).unwrap();
+    fs::write(format!("{src}/file.txt"), "content").unwrap();
+    fs::write(format!("{src}/dir/file.txt"), "content").unwrap();
+    fs::write(format!("{base}/outside.txt"), "content").unwrap();
+    symlink("file.txt", format!("{src}/internal")).unwrap();
+    symlink("dir", format!("{src}/dir_link")).unwrap();
+    symlink(target, format!("{src}/link")).unwrap();
+    src
+}
+
+#[test]
+fn check_links_internal() {
+    setup();
+    let base = format!("{}/check_links/internal", env!("CARGO_TARGET_TMPDIR"));
+    let src = init_resource(&base, "dir/file.txt");
+    let archive = format!("{base}/archive.pna");
+
+    create_archive(&archive, &["-r", &src], &["--unstable", "--check-links"]).unwrap();
+    assert!(Path::new(&archive).exists());
+}
+
+#[test]
+fn check_links_missing() {
+    setup();
+    let base = format!("{}/check_links/missing", env!("CARGO_TARGET_TMPDIR"));
+    let src = init_resource(&base, "missing.txt");
+    let archive = format!("{base}/archive.pna");
+
+    let err = create_archive(
+        &archive,
+        &["-r", &src],
+        &["--unstable", "--check-links=error"],
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(!Path::new(&archive).exists());
+    create_archive(
+        &archive,
+        &["-r", &src],
+        &[
+            "--unstable",
+            "--check-links",
+            "--check-links-allow-external",
+        ],
+    )
+    .unwrap_err();
+    create_archive(
+        &archive,
+        &["-r", &src],
+        &["--unstable", "--check-links=warn"],
+    )
+    .unwrap();
+    assert!(Path::new(&archive).exists());
+}
+
+#[test]
+fn check_links_external_relative() {
+    setup();
+    let base = format!("{}/check_links/external", env!("CARGO_TARGET_TMPDIR"));
+    let src = init_resource(&base, "../outside.txt");
+    let archive = format!("{base}/archive.pna");
+
+    create_archive(&archive, &["-r", &src], &["--unstable", "--check-links"]).unwrap_err();
+    assert!(!Path::new(&archive).exists());
+    create_archive(
+        &archive,
+        &["-r", &src],
+        &[
+            "--unstable",
+            "--check-links",
+            "--check-links-allow-external",
+        ],
+    )
+    .unwrap();
+    assert!(Path::new(&archive).exists());
+}
+
+#[test]
+fn check_links_absolute() {
+    setup();
+    let base = format!("{}/check_links/absolute", env!("CARGO_TARGET_TMPDIR"));
+    let target = format!("{base}/src/file.txt");
+    let src = init_resource(&base, &target);
+    let archive = format!("{base}/archive.pna");
+
+    create_archive(&archive, &["-r", &src], &["--unstable", "--check-links"]).unwrap_err();
+    assert!(!Path::new(&archive).exists());
+    create_archive(
+        &archive,
+        &["-r", &src],
+        &["--unstable", "--check-links=warn"],
+    )
+    .unwrap();
+    create_archive(
+        &archive,
+        &["-r", &src],
+        &[
+            "--unstable",
+            "--check-links",
+            "--check-links-allow-external",
+        ],
+    )
+    .unwrap();
+}
@@ -1,6 +1,8 @@
 use crate::utils::setup;
 use clap::Parser;
+use pna::{Archive, EntryBuilder, Permission, WriteOptions};
 use portable_network_archive::{cli, command};
+use std::fs;
 
 #[test]
 fn archive_chmod() {
@@ -24,7 +26,7 @@ fn archive_chmod() {
         "pna",
         "--quiet",
         "experimental",
-        "chown",
+        "chmod",
         &format!("{}/chmod.pna", env!("CARGO_TARGET_TMPDIR")),
         "--",
         "-w",
@@ -35,10 +37,90 @@ fn archive_chmod() {
         "pna",
         "--quiet",
         "experimental",
-        "chown",
+        "chmod",
         &format!("{}/chmod.pna", env!("CARGO_TARGET_TMPDIR")),
         "+w",
         "resources/test/raw/text.txt",
     ]))
     .unwrap();
 }
+
+fn stored_modes(archive: &str) -> Vec<(String, u16)> {
+    let mut archive = Archive::read_header(fs::File::open(archive).unwrap()).unwrap();
+    archive
+        .entries_skip_solid()
+        .map(Result::unwrap)
+        .map(|it| {
+            (
+                it.header().path().to_string(),
+                it.metadata().permission().unwrap().permissions(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn archive_chmod_conditional_execute_and_special_bits() {
+    setup();
+    let path = format!("{}/chmod_special.pna", env!("CARGO_TARGET_TMPDIR"));
+    let permission = |mode| Permission::new(1000, "user".into(), 1000, "group".into(), mode);
+    let mut archive = Archive::write_header(fs::File::create(&path).unwrap()).unwrap();
+    let mut dir = EntryBuilder::new_dir("dir".into());
+    dir.permission(permission(0o644));
+    archive.add_entry(dir.build().unwrap()).unwrap();
+    for (name, mode) in [("dir/file.txt", 0o644), ("dir/exec.sh", 0o744)] {
+        let mut file = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        file.permission(permission(mode));
+        archive.add_entry(file.build().unwrap()).unwrap();
+    }
+    archive.finalize().unwrap();
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "chmod",
+        &path,
+        "a+X",
+        "dir",
+    ]))
+    .unwrap();
+    assert_eq!(
+        stored_modes(&path),
+        [
+            ("dir".into(), 0o755),
+            ("dir/file.txt".into(), 0o644),
+            ("dir/exec.sh".into(), 0o755),
+        ]
+    );
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "chmod",
+        &path,
+        "+t",
+        "dir",
+        "--no-recursion",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "chmod",
+        &path,
+        "2755",
+        "dir/exec.sh",
+    ]))
+    .unwrap();
+    assert_eq!(
+        stored_modes(&path),
+        [
+            ("dir".into(), 0o1755),
+            ("dir/file.txt".into(), 0o644),
+            ("dir/exec.sh".into(), 0o2755),
+        ]
+    );
+}
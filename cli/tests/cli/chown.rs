@@ -1,15 +1,15 @@
 use crate::utils::setup;
 use clap::Parser;
+use pna::{Archive, EntryBuilder, Permission, SolidEntryBuilder, WriteOptions};
 use portable_network_archive::{cli, command};
+use std::{fs, io::Write};
 
-#[test]
-fn archive_chown() {
-    setup();
+fn create_chown_resource(path: &str) {
     command::entry(cli::Cli::parse_from([
         "pna",
         "--quiet",
         "c",
-        &format!("{}/chown.pna", env!("CARGO_TARGET_TMPDIR")),
+        path,
         "--overwrite",
         "-r",
         "../resources/test/raw",
@@ -20,14 +20,208 @@ fn archive_chown() {
         },
     ]))
     .unwrap();
+}
+
+// NOTE: Names are looked up in the system databases, so the names of the current user and group are used.
+#[cfg(unix)]
+#[test]
+fn archive_chown() {
+    setup();
+    let path = format!("{}/chown.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_chown_resource(&path);
+    let user = nix::unistd::User::from_uid(nix::unistd::getuid())
+        .unwrap()
+        .unwrap();
+    let group = nix::unistd::Group::from_gid(nix::unistd::getgid())
+        .unwrap()
+        .unwrap();
     command::entry(cli::Cli::parse_from([
         "pna",
         "--quiet",
         "experimental",
         "chown",
-        &format!("{}/chown.pna", env!("CARGO_TARGET_TMPDIR")),
-        "user:group",
+        &path,
+        &format!("{}:{}", user.name, group.name),
         "resources/test/raw/text.txt",
     ]))
     .unwrap();
 }
+
+#[test]
+fn archive_chown_numeric_id() {
+    setup();
+    let path = format!("{}/chown_numeric_id.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_chown_resource(&path);
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "chown",
+        &path,
+        "#1000:#100",
+        "resources/test/raw/text.txt",
+    ]))
+    .unwrap();
+    let permission = permissions(&path)
+        .into_iter()
+        .find(|(name, _)| name == "resources/test/raw/text.txt")
+        .unwrap()
+        .1;
+    assert_eq!((permission.uid(), permission.gid()), (1000, 100));
+}
+
+const NAMES: [&str; 3] = ["a.txt", "dir/b.txt", "dir/c.txt"];
+
+fn original_permission() -> Permission {
+    Permission::new(1000, "user".into(), 100, "group".into(), 0o644)
+}
+
+fn file_entry(name: &str) -> pna::NormalEntry {
+    let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+    builder.permission(original_permission());
+    builder.write_all(name.as_bytes()).unwrap();
+    builder.build().unwrap()
+}
+
+fn init_resource(path: &str, solid: bool) {
+    fs::create_dir_all(format!("{}/chown", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    if solid {
+        let mut builder = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        for name in NAMES {
+            builder.add_entry(file_entry(name)).unwrap();
+        }
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    } else {
+        for name in NAMES {
+            writer.add_entry(file_entry(name)).unwrap();
+        }
+    }
+    writer.finalize().unwrap();
+}
+
+fn permissions(path: &str) -> Vec<(String, Permission)> {
+    let mut archive = Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    archive
+        .entries_with_password(None)
+        .map(|entry| {
+            let entry = entry.unwrap();
+            (
+                entry.header().path().to_string(),
+                entry.metadata().permission().unwrap().clone(),
+            )
+        })
+        .collect()
+}
+
+fn chown(path: &str, args: &[&str]) {
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet", "experimental", "chown", path]
+            .iter()
+            .chain(args)
+            .copied(),
+    ))
+    .unwrap();
+}
+
+/// Checks that only the entries under `dir/` are changed to `expected`.
+fn assert_changed(path: &str, expected: &Permission) {
+    let mut permissions = permissions(path);
+    permissions.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        permissions,
+        [
+            ("a.txt".into(), original_permission()),
+            ("dir/b.txt".into(), expected.clone()),
+            ("dir/c.txt".into(), expected.clone()),
+        ]
+    );
+}
+
+#[test]
+fn chown_numeric_group_keeps_user() {
+    setup();
+    // NOTE: The name of a numeric id is looked up only on unix.
+    let gname = if cfg!(unix) { "" } else { "group" };
+    for solid in [false, true] {
+        for strategy in ["--keep-solid", "--unsolid"] {
+            let path = format!(
+                "{}/chown/numeric_group_{solid}{strategy}.pna",
+                env!("CARGO_TARGET_TMPDIR")
+            );
+            init_resource(&path, solid);
+            chown(&path, &[":#4000000000", "dir/*", strategy]);
+            assert_changed(
+                &path,
+                &Permission::new(1000, "user".into(), 4000000000, gname.into(), 0o644),
+            );
+        }
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn chown_resolves_names() {
+    setup();
+    let user = nix::unistd::User::from_uid(nix::unistd::getuid())
+        .unwrap()
+        .unwrap();
+    let group = nix::unistd::Group::from_gid(nix::unistd::getgid())
+        .unwrap()
+        .unwrap();
+    let (uid, gid) = (u64::from(user.uid.as_raw()), u64::from(group.gid.as_raw()));
+    let cases = [
+        (
+            "user",
+            user.name.clone(),
+            Permission::new(uid, user.name.clone(), 100, "group".into(), 0o644),
+        ),
+        (
+            "group",
+            format!(":{}", group.name),
+            Permission::new(1000, "user".into(), gid, group.name.clone(), 0o644),
+        ),
+        (
+            "user_group",
+            format!("{}:{}", user.name, group.name),
+            Permission::new(uid, user.name.clone(), gid, group.name.clone(), 0o644),
+        ),
+        (
+            "numeric",
+            format!("#{uid}:#{gid}"),
+            Permission::new(uid, user.name.clone(), gid, group.name.clone(), 0o644),
+        ),
+    ];
+    for solid in [false, true] {
+        for strategy in ["--keep-solid", "--unsolid"] {
+            for (name, owner, expected) in &cases {
+                let path = format!(
+                    "{}/chown/{name}_{solid}{strategy}.pna",
+                    env!("CARGO_TARGET_TMPDIR")
+                );
+                init_resource(&path, solid);
+                chown(&path, &[owner.as_str(), "dir/*", strategy]);
+                assert_changed(&path, expected);
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn chown_unknown_name() {
+    setup();
+    let path = format!("{}/chown/unknown.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&path, false);
+    let result = command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "chown",
+        &path,
+        "pna-unknown-user",
+        "dir/*",
+    ]));
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::NotFound);
+    assert_changed(&path, &original_permission());
+}
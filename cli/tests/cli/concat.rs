@@ -1,6 +1,8 @@
 use crate::utils::setup;
 use clap::Parser;
+use pna::Archive;
 use portable_network_archive::{cli, command};
+use std::fs;
 
 #[test]
 fn concat_archive() {
@@ -45,3 +47,99 @@ fn concat_archive() {
     ]))
     .unwrap();
 }
+
+#[test]
+fn concat_archive_keep_archive_id() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "create",
+        &format!("{}/concat_archive_id.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw/",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "split",
+        &format!("{}/concat_archive_id.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--max-size",
+        "100kb",
+        "--archive-id",
+        "--unstable",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "concat",
+        &format!(
+            "{}/concatenated_archive_id.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        &format!(
+            "{}/concat_archive_id.part1.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--overwrite",
+    ]))
+    .unwrap();
+
+    let read_archive_id = |name: &str| {
+        let path = format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"));
+        Archive::read_header(fs::File::open(path).unwrap())
+            .unwrap()
+            .archive_id()
+    };
+    // NOTE: An archive that is not split has no archive id, and the split parts are given a new one on request.
+    assert_eq!(read_archive_id("concat_archive_id.pna"), None);
+    let split = read_archive_id("concat_archive_id.part1.pna");
+    assert!(split.is_some());
+    assert_eq!(split, read_archive_id("concat_archive_id.part2.pna"));
+    assert_eq!(split, read_archive_id("concatenated_archive_id.pna"));
+}
+
+#[test]
+fn concat_archive_merge_sorted() {
+    setup();
+    for (name, file) in [("text", "text.txt"), ("empty", "empty.txt")] {
+        command::entry(cli::Cli::parse_from([
+            "pna",
+            "--quiet",
+            "create",
+            &format!("{}/merge_sorted_{name}.pna", env!("CARGO_TARGET_TMPDIR")),
+            "--overwrite",
+            &format!("../resources/test/raw/{file}"),
+        ]))
+        .unwrap();
+    }
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "concat",
+        &format!("{}/merge_sorted.pna", env!("CARGO_TARGET_TMPDIR")),
+        &format!("{}/merge_sorted_text.pna", env!("CARGO_TARGET_TMPDIR")),
+        &format!("{}/merge_sorted_empty.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--merge-sorted",
+    ]))
+    .unwrap();
+
+    let path = format!("{}/merge_sorted.pna", env!("CARGO_TARGET_TMPDIR"));
+    let mut archive = Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    let names = archive
+        .entries_skip_solid()
+        .map(|it| it.unwrap().header().path().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "resources/test/raw/empty.txt",
+            "resources/test/raw/text.txt"
+        ]
+    );
+}
@@ -0,0 +1,100 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use pna::Compression;
+use std::fs;
+
+fn base_dir(name: &str) -> String {
+    let dir = format!("{}/config/{name}", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn pna(config: &str, options: Option<&str>) -> assert_cmd::Command {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.env("PNA_CONFIG", config);
+    match options {
+        Some(options) => cmd.env("PNA_OPTIONS", options),
+        None => cmd.env_remove("PNA_OPTIONS"),
+    };
+    cmd
+}
+
+fn compression(path: &str) -> Compression {
+    let mut archive = pna::Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    let entry = archive.entries_skip_solid().next().unwrap().unwrap();
+    entry.header().compression()
+}
+
+#[test]
+fn config_precedence() {
+    setup();
+    let dir = base_dir("precedence");
+    let config = format!("{dir}/config.toml");
+    fs::write(&config, "[create]\ncompression = \"xz\"\n").unwrap();
+    let archive = format!("{dir}/archive.pna");
+    let create = [
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "../resources/test/raw/text.txt",
+    ];
+
+    pna(&config, None).args(create).assert().success();
+    assert_eq!(compression(&archive), Compression::XZ);
+
+    pna(&config, Some("--deflate --keep-timestamp"))
+        .args(create)
+        .assert()
+        .success();
+    assert_eq!(compression(&archive), Compression::Deflate);
+
+    pna(&config, Some("--deflate"))
+        .args(create)
+        .arg("--store")
+        .assert()
+        .success();
+    assert_eq!(compression(&archive), Compression::No);
+
+    pna(&config, Some("--deflate"))
+        .arg("--no-config")
+        .args(create)
+        .assert()
+        .success();
+    assert_eq!(compression(&archive), Compression::ZStandard);
+}
+
+#[test]
+fn config_per_command_section() {
+    setup();
+    let dir = base_dir("section");
+    let config = format!("{dir}/config.toml");
+    fs::write(
+        &config,
+        "[extract]\noverwrite = true\nno-such-option = 1\n[create]\nstore = true\n",
+    )
+    .unwrap();
+    let archive = format!("{dir}/archive.pna");
+    let out_dir = format!("{dir}/out");
+    let _ = fs::remove_dir_all(&out_dir);
+    pna(&config, None)
+        .args(["--quiet", "c", &archive, "--overwrite"])
+        .arg("../resources/test/raw/text.txt")
+        .assert()
+        .success();
+    assert_eq!(compression(&archive), Compression::No);
+
+    let extract = ["x", &archive, "--out-dir", &out_dir];
+    pna(&config, None).args(extract).assert().success();
+    let assert = pna(&config, None).args(extract).assert().success();
+    let stderr = String::from_utf8(assert.get_output().stderr.clone()).unwrap();
+    assert!(
+        stderr.contains("warning: Ignore unknown or invalid config `no-such-option` in [extract]")
+    );
+
+    pna(&config, None)
+        .arg("--no-config")
+        .args(extract)
+        .assert()
+        .failure();
+}
@@ -0,0 +1,181 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, DataKind, ReadOptions};
+use portable_network_archive::{cli, command};
+use std::{collections::HashMap, fs, io, path::Path};
+
+const DUPLICATES: [&str; 3] = ["a.txt", "sub/c.txt", "sub/deep/d.txt"];
+const UNIQUES: [&str; 2] = ["b.txt", "sub/e.txt"];
+
+fn init_resource<P: AsRef<Path>>(dir: P) {
+    let dir = dir.as_ref();
+    if dir.exists() {
+        fs::remove_dir_all(dir).unwrap();
+    }
+    fs::create_dir_all(dir.join("sub/deep")).unwrap();
+    for name in DUPLICATES {
+        fs::write(dir.join(name), "duplicated text\n").unwrap();
+    }
+    fs::write(dir.join("b.txt"), "different text\n").unwrap();
+    // NOTE: Same size as the duplicated files, but different contents.
+    fs::write(dir.join("sub/e.txt"), "duplicated texT\n").unwrap();
+}
+
+/// Returns the names of the entries relative to `root`,
+/// with the resolved targets of the hard links or `None` for the files.
+fn read_entries(path: &str, root: &str) -> HashMap<String, Option<String>> {
+    let mut entries = HashMap::new();
+    let mut archive = Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    for entry in archive.entries_skip_solid() {
+        let entry = entry.unwrap();
+        let name = entry.header().path().to_string();
+        let target = match entry.header().data_kind() {
+            DataKind::File => None,
+            DataKind::HardLink => {
+                let reader = entry.reader(ReadOptions::builder().build()).unwrap();
+                let mut components = name.split('/').collect::<Vec<_>>();
+                components.pop();
+                let reference = io::read_to_string(reader).unwrap();
+                for component in reference.split('/') {
+                    match component {
+                        ".." => {
+                            components.pop();
+                        }
+                        c => components.push(c),
+                    }
+                }
+                Some(components.join("/"))
+            }
+            _ => continue,
+        };
+        let relative = |it: &str| it.strip_prefix(root).unwrap().to_string();
+        entries.insert(relative(&name), target.as_deref().map(relative));
+    }
+    entries
+}
+
+#[test]
+fn create_with_dedup() {
+    setup();
+    let dir = format!("{}/dedup/in", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/dedup/dedup.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        &dir,
+        "--dedup",
+        "--unstable",
+    ]))
+    .unwrap();
+
+    let root = format!("{}/", dir.trim_start_matches('/'));
+    let entries = read_entries(
+        &format!("{}/dedup/dedup.pna", env!("CARGO_TARGET_TMPDIR")),
+        &root,
+    );
+    assert_eq!(entries.len(), DUPLICATES.len() + UNIQUES.len());
+    for name in UNIQUES {
+        assert_eq!(entries[name], None);
+    }
+    // NOTE: The first one found by the walk is stored as a file, and the others link to it.
+    let originals = DUPLICATES
+        .iter()
+        .filter(|it| entries[**it].is_none())
+        .collect::<Vec<_>>();
+    assert_eq!(originals.len(), 1);
+    for name in DUPLICATES.iter().filter(|it| !originals.contains(it)) {
+        assert_eq!(entries[*name].as_deref(), Some(*originals[0]));
+    }
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &format!("{}/dedup/dedup.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/dedup/out", env!("CARGO_TARGET_TMPDIR")),
+    ]))
+    .unwrap();
+    for name in DUPLICATES.iter().chain(&UNIQUES) {
+        assert_eq!(
+            fs::read(format!(
+                "{}/dedup/out/{root}{name}",
+                env!("CARGO_TARGET_TMPDIR")
+            ))
+            .unwrap(),
+            fs::read(format!("{dir}/{name}")).unwrap(),
+        );
+    }
+}
+
+#[test]
+fn create_without_dedup() {
+    setup();
+    let dir = format!("{}/without_dedup/in", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/without_dedup/archive.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        &dir,
+    ]))
+    .unwrap();
+
+    let root = format!("{}/", dir.trim_start_matches('/'));
+    let entries = read_entries(
+        &format!("{}/without_dedup/archive.pna", env!("CARGO_TARGET_TMPDIR")),
+        &root,
+    );
+    assert_eq!(entries.len(), DUPLICATES.len() + UNIQUES.len());
+    assert!(entries.values().all(Option::is_none));
+}
+
+#[cfg(unix)]
+#[test]
+fn create_with_dedup_follow_links() {
+    setup();
+    let dir = format!("{}/dedup_follow_links/in", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    std::os::unix::fs::symlink("sub", format!("{dir}/alias")).unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!(
+            "{}/dedup_follow_links/archive.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--overwrite",
+        "-r",
+        &dir,
+        "--follow-links",
+        "--dedup",
+        "--unstable",
+    ]))
+    .unwrap();
+
+    let root = format!("{}/", dir.trim_start_matches('/'));
+    let entries = read_entries(
+        &format!(
+            "{}/dedup_follow_links/archive.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        &root,
+    );
+    // NOTE: The files reached through the followed link are the same files as in `sub`.
+    assert_eq!(entries.len(), DUPLICATES.len() + UNIQUES.len() + 3);
+    assert_eq!(entries.values().filter(|it| it.is_none()).count(), 3);
+    assert_eq!(
+        entries["alias/e.txt"].is_some(),
+        entries["sub/e.txt"].is_none()
+    );
+}
@@ -0,0 +1,205 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const MODIFIED: Duration = Duration::from_secs(1_000_000_000);
+
+/// Creates an archive and extracts it to a directory of the same name, returns their paths.
+fn init_resource(name: &str) -> (String, String) {
+    let archive = format!("{}/diff/{name}.pna", env!("CARGO_TARGET_TMPDIR"));
+    let dir = format!("{}/diff/{name}", env!("CARGO_TARGET_TMPDIR"));
+    if Path::new(&dir).exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    let file = fs::File::create(&archive).unwrap();
+    let mut writer = Archive::write_header(file).unwrap();
+    for (name, content) in [
+        ("a.txt", "original text\n"),
+        ("b.txt", "another text\n"),
+        ("dir/c.txt", "nested text\n"),
+    ] {
+        writer
+            .add_entry({
+                let mut builder =
+                    EntryBuilder::new_file(name.into(), WriteOptions::builder().build()).unwrap();
+                builder.modified(MODIFIED);
+                builder.write_all(content.as_bytes()).unwrap();
+                builder.build().unwrap()
+            })
+            .unwrap();
+    }
+    writer
+        .add_entry(
+            EntryBuilder::new_symbolic_link("link.txt".into(), "a.txt".into())
+                .unwrap()
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    writer
+        .add_entry(
+            EntryBuilder::new_hard_link("hard.txt".into(), "a.txt".into())
+                .unwrap()
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    writer.finalize().unwrap();
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &archive,
+        "--overwrite",
+        "--keep-timestamp",
+        "--out-dir",
+        &dir,
+    ]))
+    .unwrap();
+    (archive, dir)
+}
+
+fn diff(archive: &str, dir: &str, args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "experimental", "diff", archive, "-C", dir])
+        .args(args);
+    cmd.assert()
+}
+
+fn stdout(assert: &assert_cmd::assert::Assert) -> String {
+    String::from_utf8(assert.get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn diff_identical() {
+    setup();
+    let (archive, dir) = init_resource("identical");
+    let assert = diff(&archive, &dir, &[]).success();
+    assert_eq!(stdout(&assert), "");
+}
+
+#[test]
+fn diff_content() {
+    setup();
+    let (archive, dir) = init_resource("content");
+    fs::write(format!("{dir}/b.txt"), "changed text\n").unwrap();
+    let assert = diff(&archive, &dir, &[]).code(1);
+    assert!(stdout(&assert).contains("content: b.txt\n"));
+
+    let assert = diff(&archive, &dir, &["a.txt"]).success();
+    assert_eq!(stdout(&assert), "");
+}
+
+#[test]
+fn diff_mtime() {
+    setup();
+    let (archive, dir) = init_resource("mtime");
+    fs::File::options()
+        .write(true)
+        .open(format!("{dir}/b.txt"))
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    let expected = "mtime: b.txt: archive 2001-09-09T01:46:40Z, filesystem 1970-01-01T00:00:00Z\n";
+
+    let assert = diff(&archive, &dir, &[]).code(1);
+    assert_eq!(stdout(&assert), expected);
+
+    let assert = diff(&archive, &dir, &["--exit-code-map", "metadata=0"]).success();
+    assert_eq!(stdout(&assert), expected);
+
+    diff(&archive, &dir, &["--exit-code-map", "metadata=4"]).code(4);
+    diff(&archive, &dir, &["--exit-code-map", "metadata=9"]).code(3);
+}
+
+#[test]
+fn diff_missing() {
+    setup();
+    let (archive, dir) = init_resource("missing");
+    fs::remove_file(format!("{dir}/b.txt")).unwrap();
+    let assert = diff(&archive, &dir, &[]).code(1);
+    assert_eq!(stdout(&assert), "missing: b.txt\n");
+}
+
+#[test]
+fn diff_unreadable_keep_going() {
+    setup();
+    let (archive, dir) = init_resource("unreadable");
+    // NOTE: `dir/c.txt` cannot be looked up, because `dir` is not a directory.
+    fs::remove_dir_all(format!("{dir}/dir")).unwrap();
+    fs::write(format!("{dir}/dir"), "not a directory\n").unwrap();
+    fs::write(format!("{dir}/b.txt"), "changed text\n").unwrap();
+
+    diff(&archive, &dir, &[]).code(1);
+
+    let assert = diff(&archive, &dir, &["--keep-going"]).code(2);
+    let stdout = stdout(&assert);
+    assert!(stdout.contains("content: b.txt\n"));
+    assert!(stdout.contains("unreadable: dir/c.txt: "));
+
+    diff(
+        &archive,
+        &dir,
+        &["--keep-going", "--exit-code-map", "unreadable=0,content=5"],
+    )
+    .code(5);
+}
+
+#[cfg(unix)]
+#[test]
+fn diff_symlink_target() {
+    setup();
+    let (archive, dir) = init_resource("symlink");
+    fs::remove_file(format!("{dir}/link.txt")).unwrap();
+    std::os::unix::fs::symlink("b.txt", format!("{dir}/link.txt")).unwrap();
+    let assert = diff(&archive, &dir, &[]).code(1);
+    assert_eq!(
+        stdout(&assert),
+        "link: link.txt: archive -> a.txt, filesystem -> b.txt\n"
+    );
+}
+
+#[test]
+fn diff_hardlink() {
+    setup();
+    let (archive, dir) = init_resource("hardlink");
+    fs::remove_file(format!("{dir}/hard.txt")).unwrap();
+    fs::copy(format!("{dir}/a.txt"), format!("{dir}/hard.txt")).unwrap();
+    let assert = diff(&archive, &dir, &[]).code(1);
+    assert_eq!(stdout(&assert), "hardlink: hard.txt: not linked to a.txt\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn diff_xattr() {
+    setup();
+    let (archive, dir) = init_resource("xattr");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "xattr",
+        "set",
+        &archive,
+        "--name",
+        "user.pna",
+        "--value",
+        "pna developers!",
+        "b.txt",
+    ]))
+    .unwrap();
+    diff(&archive, &dir, &["b.txt"]).success();
+
+    let assert = diff(&archive, &dir, &["--check-xattr", "b.txt"]).code(1);
+    assert!(stdout(&assert).contains("xattr: b.txt: user.pna is missing in filesystem\n"));
+}
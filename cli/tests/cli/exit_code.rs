@@ -0,0 +1,135 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{create_archive, setup};
+use std::{fs, path::Path};
+
+const FILES: &[&str] = &[
+    "../resources/test/raw/empty.txt",
+    "../resources/test/raw/text.txt",
+];
+
+#[test]
+fn exit_code_usage_error() {
+    setup();
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["x", "--no-such-option"]);
+    cmd.assert().code(3);
+}
+
+#[test]
+fn exit_code_invalid_pattern_argument() {
+    setup();
+    let path = format!(
+        "{}/exit_code/invalid_pattern.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    create_archive(&path, FILES, &[]).unwrap();
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "list", &path, "[invalid"]);
+    cmd.assert().code(3);
+}
+
+#[test]
+fn exit_code_missing_archive() {
+    setup();
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--quiet",
+        "x",
+        &format!("{}/exit_code/missing.pna", env!("CARGO_TARGET_TMPDIR")),
+    ]);
+    cmd.assert().code(1);
+}
+
+#[test]
+fn exit_code_wrong_password() {
+    setup();
+    let path = format!(
+        "{}/exit_code/wrong_password.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    create_archive(
+        &path,
+        FILES,
+        &["--zstd", "--aes", "ctr", "--password", "password"],
+    )
+    .unwrap();
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--quiet",
+        "x",
+        &path,
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/exit_code/wrong_password/", env!("CARGO_TARGET_TMPDIR")),
+        "--password",
+        "wrong",
+    ]);
+    cmd.assert().code(4);
+}
+
+#[test]
+fn exit_code_pattern_no_match() {
+    setup();
+    let path = format!(
+        "{}/exit_code/pattern_no_match.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    create_archive(&path, FILES, &[]).unwrap();
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--quiet",
+        "x",
+        &path,
+        "--overwrite",
+        "--out-dir",
+        &format!(
+            "{}/exit_code/pattern_no_match/",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "**/text.txt",
+        "no/such/entry",
+    ]);
+    cmd.assert().code(2);
+    assert!(Path::new(&format!(
+        "{}/exit_code/pattern_no_match/resources/test/raw/text.txt",
+        env!("CARGO_TARGET_TMPDIR")
+    ))
+    .exists());
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "list", &path, "no/such/entry"]);
+    cmd.assert().code(2);
+}
+
+#[test]
+fn exit_code_partial_extraction() {
+    setup();
+    let path = format!(
+        "{}/exit_code/partial_extraction.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    create_archive(&path, FILES, &[]).unwrap();
+    let out_dir = format!(
+        "{}/exit_code/partial_extraction",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let _ = fs::remove_dir_all(&out_dir);
+    fs::create_dir_all(format!("{out_dir}/resources/test/raw")).unwrap();
+    fs::write(format!("{out_dir}/resources/test/raw/empty.txt"), "").unwrap();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--quiet",
+        "x",
+        &path,
+        "--out-dir",
+        &out_dir,
+        "--no-fail-fast",
+    ]);
+    cmd.assert().code(2);
+    assert!(Path::new(&format!("{out_dir}/resources/test/raw/text.txt")).exists());
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "x", &path, "--out-dir", &out_dir]);
+    cmd.assert().code(1);
+}
@@ -0,0 +1,147 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{collections::BTreeSet, fs, io::Write, path::Path};
+
+fn init_resource(path: &str) {
+    fs::create_dir_all(Path::new(path).parent().unwrap()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for file in [
+        "root/images/a.png",
+        "root/images/b.jpg",
+        "root/docs/c.txt",
+        "root/docs/d.png",
+        "root/.git/config",
+        "root/e.txt",
+    ] {
+        let mut builder = EntryBuilder::new_file(file.into(), WriteOptions::store()).unwrap();
+        builder.write_all(b"content").unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, args: &[&str]) -> std::io::Result<()> {
+    let _ = fs::remove_dir_all(out_dir);
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "x",
+            archive,
+            "--overwrite",
+            "--out-dir",
+            out_dir,
+            "--unstable",
+        ]
+        .into_iter()
+        .chain(args.iter().copied()),
+    ))
+}
+
+/// Returns the relative paths of the files under `dir`.
+fn files(dir: &Path) -> BTreeSet<String> {
+    fn walk(base: &Path, dir: &Path, files: &mut BTreeSet<String>) {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                walk(base, &path, files);
+            } else {
+                let name = path.strip_prefix(base).unwrap().to_string_lossy();
+                files.insert(name.replace('\\', "/"));
+            }
+        }
+    }
+    let mut files = BTreeSet::new();
+    walk(dir, dir, &mut files);
+    files
+}
+
+#[test]
+fn extract_exclude_with_patterns() {
+    setup();
+    let base = format!("{}/extract_filter/exclude", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+    let out_dir = format!("{base}/out");
+    extract(
+        &archive,
+        &out_dir,
+        &["--exclude", "*.png", "root/images", "root/docs/*"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        files(Path::new(&out_dir)),
+        BTreeSet::from(["root/images/b.jpg".into(), "root/docs/c.txt".into()]),
+    );
+}
+
+#[test]
+fn extract_exclude_takes_precedence() {
+    setup();
+    let base = format!("{}/extract_filter/precedence", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+    let out_dir = format!("{base}/out");
+    // NOTE: The pattern matches only the excluded entry, so it is reported as unmatched.
+    assert!(extract(
+        &archive,
+        &out_dir,
+        &[
+            "--exclude",
+            "*.png",
+            "--include",
+            "*.png",
+            "root/images/a.png"
+        ],
+    )
+    .is_err());
+    assert!(!Path::new(&format!("{out_dir}/root/images/a.png")).exists());
+
+    extract(
+        &archive,
+        &out_dir,
+        &["--include", "*.txt", "--exclude", "root/docs"],
+    )
+    .unwrap();
+    assert_eq!(
+        files(Path::new(&out_dir)),
+        BTreeSet::from(["root/e.txt".into()]),
+    );
+}
+
+#[test]
+fn extract_exclude_from_null_and_vcs() {
+    setup();
+    let base = format!(
+        "{}/extract_filter/exclude_from",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+    let exclude_from = format!("{base}/exclude.txt");
+    fs::write(&exclude_from, "*.jpg\0root/docs/c.txt\0").unwrap();
+    let out_dir = format!("{base}/out");
+    extract(
+        &archive,
+        &out_dir,
+        &[
+            "--exclude-from",
+            &exclude_from,
+            "--null",
+            "--exclude-vcs",
+            "--strip-components",
+            "1",
+        ],
+    )
+    .unwrap();
+
+    // NOTE: The filters match the names in the archive, before --strip-components is applied.
+    assert_eq!(
+        files(Path::new(&out_dir)),
+        BTreeSet::from(["images/a.png".into(), "docs/d.png".into(), "e.txt".into(),]),
+    );
+}
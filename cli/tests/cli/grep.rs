@@ -0,0 +1,105 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{create_archive, setup};
+
+const FILES: &[&str] = &["-r", "../resources/test/raw"];
+
+fn grep(args: &[&str]) -> String {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "experimental", "grep"]).args(args);
+    let assert = cmd.assert().success();
+    String::from_utf8(assert.get_output().stdout.clone()).unwrap()
+}
+
+#[test]
+fn grep_normal_entries() {
+    setup();
+    let path = format!("{}/grep/normal.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+    assert_eq!(
+        grep(&["text", &path]),
+        "resources/test/raw/text.txt:1:text file\n"
+    );
+    assert_eq!(
+        grep(&["-i", "TEXT", &path]),
+        "resources/test/raw/text.txt:1:text file\n"
+    );
+    assert_eq!(
+        grep(&["-l", "text", &path]),
+        "resources/test/raw/text.txt\n"
+    );
+    let count = grep(&["-c", "text", &path, "**/text.txt", "**/empty.txt"]);
+    let mut lines = count.lines().collect::<Vec<_>>();
+    lines.sort();
+    assert_eq!(
+        lines,
+        [
+            "resources/test/raw/empty.txt:0",
+            "resources/test/raw/text.txt:1"
+        ]
+    );
+}
+
+#[test]
+fn grep_solid_entries() {
+    setup();
+    let path = format!("{}/grep/solid.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &["--solid"]).unwrap();
+    assert_eq!(
+        grep(&["text", &path]),
+        "resources/test/raw/text.txt:1:text file\n"
+    );
+}
+
+#[test]
+fn grep_encrypted_entries() {
+    setup();
+    let path = format!("{}/grep/encrypted.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &["--aes", "ctr", "--password", "password"]).unwrap();
+    assert_eq!(
+        grep(&["text", &path, "--password", "password"]),
+        "resources/test/raw/text.txt:1:text file\n"
+    );
+}
+
+#[test]
+fn grep_binary_files() {
+    setup();
+    let path = format!("{}/grep/binary.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+    assert!(!grep(&["-l", "PNG", &path]).contains("icon.png"));
+    assert!(grep(&["-l", "--binary-files", "text", "PNG", &path]).contains("icon.png"));
+}
+
+#[test]
+fn grep_exclude_and_max_filesize() {
+    setup();
+    let path = format!("{}/grep/filter.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+    assert_eq!(grep(&["text", &path, "--exclude", "**/text.txt"]), "");
+    assert_eq!(grep(&["text", &path, "--max-filesize", "1"]), "");
+}
+
+#[test]
+fn grep_with_threads() {
+    setup();
+    let path = format!("{}/grep/threads.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+    let grep = |threads: &str| {
+        let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+        cmd.args([
+            "--quiet",
+            "--threads",
+            threads,
+            "experimental",
+            "grep",
+            "-c",
+            "",
+            &path,
+        ]);
+        cmd.assert().success().get_output().stdout.clone()
+    };
+    let single = grep("1");
+    assert!(1 < single.iter().filter(|it| **it == b'\n').count());
+    assert_eq!(single, grep("2"));
+    assert_eq!(single, grep("4"));
+}
@@ -115,3 +115,103 @@ fn hardlink() {
         .unwrap()
     );
 }
+
+#[test]
+fn hardlink_list_groups() {
+    setup();
+    init_resource(format!(
+        "{}/hardlink_list_groups.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    ));
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "list",
+        &format!("{}/hardlink_list_groups.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--hardlinks",
+        "--check",
+        "--unstable",
+    ]))
+    .unwrap();
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn hardlink_list_groups_jsonl() {
+    setup();
+    init_resource(format!(
+        "{}/hardlink_list_groups_jsonl.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    ));
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "list",
+        &format!(
+            "{}/hardlink_list_groups_jsonl.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--hardlinks",
+        "--format",
+        "jsonl",
+        "--unstable",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        concat!(
+            r#"{"target":"origin1.txt","size":14,"links":["linked1.txt","dir/linked1.txt"],"dangling":false}"#,
+            "\n",
+            r#"{"target":"dir/origin2.txt","size":19,"links":["dir/linked2.txt","linked2.txt"],"dangling":false}"#,
+            "\n",
+        )
+    );
+}
+
+#[test]
+fn hardlink_list_dangling() {
+    setup();
+    let path = format!("{}/hardlink_list_dangling.pna", env!("CARGO_TARGET_TMPDIR"));
+    let file = fs::File::create(&path).unwrap();
+    let mut writer = Archive::write_header(file).unwrap();
+    writer
+        .add_entry({
+            let mut builder =
+                EntryBuilder::new_file("origin.txt".into(), WriteOptions::builder().build())
+                    .unwrap();
+            builder.write_all(b"original text\n").unwrap();
+            builder.build().unwrap()
+        })
+        .unwrap();
+    writer
+        .add_entry({
+            let builder =
+                EntryBuilder::new_hard_link("linked.txt".into(), "origin.txt".into()).unwrap();
+            builder.build().unwrap()
+        })
+        .unwrap();
+    writer
+        .add_entry({
+            let builder =
+                EntryBuilder::new_hard_link("dangling.txt".into(), "missing.txt".into()).unwrap();
+            builder.build().unwrap()
+        })
+        .unwrap();
+    writer.finalize().unwrap();
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "list",
+        &path,
+        "--hardlinks",
+        "--unstable",
+    ]))
+    .unwrap();
+    assert!(command::entry(cli::Cli::parse_from([
+        "pna",
+        "list",
+        &path,
+        "--hardlinks",
+        "--check",
+        "--unstable",
+    ]))
+    .is_err());
+}
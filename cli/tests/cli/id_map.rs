@@ -0,0 +1,122 @@
+#![cfg(unix)]
+use crate::utils::setup;
+use clap::Parser;
+use pna::Archive;
+use portable_network_archive::{cli, command};
+use std::{fs, io, os::unix::fs::MetadataExt};
+
+fn init_resource(base: &str) -> (u32, u32) {
+    let _ = fs::remove_dir_all(base);
+    fs::create_dir_all(format!("{base}/in")).unwrap();
+    let file = format!("{base}/in/file.txt");
+    fs::write(&file, "content").unwrap();
+    let meta = fs::metadata(file).unwrap();
+    (meta.uid(), meta.gid())
+}
+
+fn run(args: &[&str]) -> io::Result<()> {
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet"].iter().chain(args).copied(),
+    ))
+}
+
+fn stored_owner(archive: &str) -> (u64, String, u64, String) {
+    let mut archive = Archive::read_header(fs::File::open(archive).unwrap()).unwrap();
+    let entry = archive.entries_skip_solid().next().unwrap().unwrap();
+    let p = entry.metadata().permission().unwrap();
+    (p.uid(), p.uname().into(), p.gid(), p.gname().into())
+}
+
+#[test]
+fn create_with_id_map() {
+    setup();
+    let base = format!("{}/id_map/create", env!("CARGO_TARGET_TMPDIR"));
+    let (uid, gid) = init_resource(&base);
+    let archive = format!("{base}/archive.pna");
+    let uid_map = format!("{uid}:100000:1");
+    let gid_map = format!("{gid}:200000:1");
+
+    run(&[
+        "c",
+        &archive,
+        "--keep-permission",
+        "--uid-map",
+        &uid_map,
+        "--gid-map",
+        &gid_map,
+        "--unstable",
+        &format!("{base}/in/file.txt"),
+    ])
+    .unwrap();
+    assert_eq!(
+        stored_owner(&archive),
+        (100000, String::new(), 200000, String::new())
+    );
+}
+
+#[test]
+fn create_with_unmapped_id() {
+    setup();
+    let base = format!("{}/id_map/unmapped", env!("CARGO_TARGET_TMPDIR"));
+    let (uid, gid) = init_resource(&base);
+    let archive = format!("{base}/archive.pna");
+    let uid_map = format!("{}:100000:1", uid.wrapping_add(1));
+    let gid_map = format!("{gid}:200000:1");
+    let args = [
+        "c",
+        &archive,
+        "--overwrite",
+        "--keep-permission",
+        "--uid-map",
+        &uid_map,
+        "--gid-map",
+        &gid_map,
+        "--unstable",
+        &format!("{base}/in/file.txt"),
+    ];
+
+    let err = run(&args).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    run(&[&args[..], &["--unmapped", "squash-to=65534"]].concat()).unwrap();
+    assert_eq!(
+        stored_owner(&archive),
+        (65534, String::new(), 200000, String::new())
+    );
+}
+
+#[test]
+fn id_map_round_trip() {
+    setup();
+    let base = format!("{}/id_map/round_trip", env!("CARGO_TARGET_TMPDIR"));
+    let (uid, gid) = init_resource(&base);
+    let archive = format!("{base}/archive.pna");
+    let file = format!("{base}/in/file.txt");
+    let uid_map = format!("{uid}:100000:1");
+    let gid_map = format!("{gid}:200000:1");
+    let maps = ["--uid-map", &uid_map, "--gid-map", &gid_map, "--unstable"];
+
+    run(&[
+        &["c", &archive, "--keep-permission"][..],
+        &maps,
+        &[&file[..]],
+    ]
+    .concat())
+    .unwrap();
+    run(&[
+        &[
+            "x",
+            &archive,
+            "--keep-permission",
+            "--out-dir",
+            &format!("{base}/out"),
+        ][..],
+        &maps,
+    ]
+    .concat())
+    .unwrap();
+
+    // NOTE: The absolute source path is stored without its root.
+    let meta = fs::metadata(format!("{base}/out/{}", file.trim_start_matches('/'))).unwrap();
+    assert_eq!((meta.uid(), meta.gid()), (uid, gid));
+}
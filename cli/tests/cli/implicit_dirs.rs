@@ -0,0 +1,103 @@
+#![cfg(unix)]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, Permission, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    io::Write,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const MODIFIED: Duration = Duration::from_secs(1_000_000_000);
+
+fn init_resource(path: &str) {
+    fs::create_dir_all(Path::new(path).parent().unwrap()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for (dir, mode) in [("docs", 0o751), ("docs/guide", 0o711), ("other", 0o700)] {
+        let mut builder = EntryBuilder::new_dir(dir.into());
+        builder.modified(MODIFIED);
+        builder.permission(Permission::new(0, "".into(), 0, "".into(), mode));
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    for file in ["docs/guide/index.md", "other/file.txt"] {
+        let mut builder = EntryBuilder::new_file(file.into(), WriteOptions::store()).unwrap();
+        builder.permission(Permission::new(0, "".into(), 0, "".into(), 0o644));
+        builder.write_all(b"content").unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) {
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "x",
+            archive,
+            "--out-dir",
+            out_dir,
+            "--keep-permission",
+            "--keep-timestamp",
+        ]
+        .into_iter()
+        .chain(options.iter().copied()),
+    ))
+    .unwrap();
+}
+
+fn mode(path: &str) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o777
+}
+
+fn modified(path: &str) -> SystemTime {
+    fs::metadata(path).unwrap().modified().unwrap()
+}
+
+#[test]
+fn implicit_dirs_restore_metadata_by_default() {
+    setup();
+    let base = format!("{}/implicit_dirs/metadata", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+    let out_dir = format!("{base}/out");
+
+    extract(&archive, &out_dir, &["docs/guide/index.md"]);
+
+    for (dir, mode_in_archive) in [("docs", 0o751), ("docs/guide", 0o711)] {
+        let dir = format!("{out_dir}/{dir}");
+        assert_eq!(mode(&dir), mode_in_archive, "{dir}");
+        assert_eq!(modified(&dir), SystemTime::UNIX_EPOCH + MODIFIED, "{dir}");
+    }
+    assert_eq!(mode(&format!("{out_dir}/docs/guide/index.md")), 0o644);
+    assert!(!Path::new(&format!("{out_dir}/other")).exists());
+}
+
+#[test]
+fn implicit_dirs_bare() {
+    setup();
+    let base = format!("{}/implicit_dirs/bare", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+    let out_dir = format!("{base}/out");
+
+    extract(
+        &archive,
+        &out_dir,
+        &["docs/guide/index.md", "--implicit-dirs", "bare"],
+    );
+
+    for dir in ["docs", "docs/guide"] {
+        let dir = format!("{out_dir}/{dir}");
+        assert_ne!(modified(&dir), SystemTime::UNIX_EPOCH + MODIFIED, "{dir}");
+    }
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/docs/guide/index.md")).unwrap(),
+        "content"
+    );
+}
@@ -0,0 +1,71 @@
+use serde_json::Value;
+use std::fs;
+
+fn info(args: &[&str], stdin: Option<Vec<u8>>) -> Value {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["experimental", "info", "--format", "json"]);
+    cmd.args(args);
+    if let Some(stdin) = stdin {
+        cmd.write_stdin(stdin);
+    }
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).unwrap()
+}
+
+#[test]
+fn info_multipart() {
+    let info = info(&["../resources/test/multipart.part1.pna"], None);
+    let parts = info["parts"].as_array().unwrap();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0]["archive_number"], 0);
+    assert_eq!(parts[0]["has_next_part"], true);
+    assert_eq!(parts[0]["size"], 564);
+    assert_eq!(parts[0]["chunks"], 5);
+    assert_eq!(parts[0]["entries"], 1);
+    assert_eq!(parts[1]["archive_number"], 1);
+    assert_eq!(parts[1]["has_next_part"], false);
+    assert_eq!(parts[1]["size"], 421);
+    assert_eq!(parts[1]["chunks"], 4);
+    assert_eq!(parts[1]["entries"], 0);
+
+    let total = &info["total"];
+    assert_eq!(total["size"], 985);
+    assert_eq!(total["chunks"], 9);
+    assert_eq!(total["overhead"], 2 * 8 + 9 * 12);
+    assert_eq!(total["entries"], 1);
+    assert_eq!(total["unknown_chunks"], 0);
+    assert_eq!(total["chunk_types"]["AHED"], 2);
+    assert_eq!(total["chunk_types"]["ANXT"], 1);
+    assert_eq!(total["chunk_types"]["FDAT"], 2);
+}
+
+#[test]
+fn info_stdin() {
+    let mut archive = fs::read("../resources/test/multipart.part1.pna").unwrap();
+    archive.extend(fs::read("../resources/test/multipart.part2.pna").unwrap());
+    let from_stdin = info(&[], Some(archive));
+    let from_file = info(&["../resources/test/multipart.part1.pna"], None);
+    assert_eq!(from_stdin, from_file);
+}
+
+#[test]
+fn info_without_password() {
+    let info = info(&["../resources/test/zstd_aes_ctr.pna"], None);
+    let total = &info["total"];
+    assert_eq!(total["size"], 104365);
+    assert_eq!(total["chunks"], 38);
+    assert_eq!(total["entries"], 9);
+    assert_eq!(total["solid_entries"], 0);
+    assert_eq!(total["chunk_types"]["PHSF"], 9);
+    assert_eq!(total["features"], serde_json::json!(["password"]));
+}
+
+#[test]
+fn info_solid() {
+    let info = info(&["../resources/test/solid_zstd.pna"], None);
+    let total = &info["total"];
+    assert_eq!(total["entries"], 0);
+    assert_eq!(total["solid_entries"], 1);
+    assert_eq!(total["chunk_types"]["SDAT"], 4);
+    assert_eq!(total["features"], serde_json::json!(["solid"]));
+}
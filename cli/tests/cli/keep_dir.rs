@@ -0,0 +1,189 @@
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+fn init_resource(path: &str) {
+    fs::create_dir_all(Path::new(path).parent().unwrap()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for dir in ["empty", "dir"] {
+        let builder = EntryBuilder::new_dir(dir.into());
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    let mut builder = EntryBuilder::new_file("dir/file.txt".into(), WriteOptions::store()).unwrap();
+    builder.write_all(b"content").unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) {
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet", "x", archive, "--out-dir", out_dir]
+            .into_iter()
+            .chain(options.iter().copied()),
+    ))
+    .unwrap();
+}
+
+#[test]
+fn extract_empty_directory_by_default() {
+    setup();
+    let base = format!("{}/keep_dir/default", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+
+    extract(&archive, &format!("{base}/out"), &[]);
+    assert!(Path::new(&format!("{base}/out/empty")).is_dir());
+    assert_eq!(
+        fs::read_to_string(format!("{base}/out/dir/file.txt")).unwrap(),
+        "content"
+    );
+}
+
+#[test]
+fn extract_no_keep_dir() {
+    setup();
+    let base = format!("{}/keep_dir/no_keep_dir", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+
+    extract(&archive, &format!("{base}/out"), &["--no-keep-dir"]);
+    assert!(!Path::new(&format!("{base}/out/empty")).exists());
+    assert_eq!(
+        fs::read_to_string(format!("{base}/out/dir/file.txt")).unwrap(),
+        "content"
+    );
+}
+
+#[test]
+fn extract_file_in_stored_directory() {
+    setup();
+    let base = format!("{}/keep_dir/file_in_dir", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+
+    extract(&archive, &format!("{base}/out"), &["dir/file.txt"]);
+    assert!(!Path::new(&format!("{base}/out/empty")).exists());
+    assert_eq!(
+        fs::read_to_string(format!("{base}/out/dir/file.txt")).unwrap(),
+        "content"
+    );
+}
+
+// NOTE: The stored paths of the absolute source paths are compared as unix paths.
+#[cfg(unix)]
+#[test]
+fn empty_directory_round_trip() {
+    setup();
+    let base = format!("{}/keep_dir/round_trip", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let src = format!("{base}/src");
+    fs::create_dir_all(format!("{src}/empty")).unwrap();
+    fs::create_dir_all(format!("{src}/dir")).unwrap();
+    fs::write(format!("{src}/dir/file.txt"), "content").unwrap();
+    let archive = format!("{base}/archive.pna");
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--keep-dir",
+        "-r",
+        &src,
+    ]))
+    .unwrap();
+    extract(&archive, &format!("{base}/out"), &[]);
+    let extracted = format!("{base}/out/{}", src.trim_start_matches('/'));
+    assert!(Path::new(&format!("{extracted}/empty")).is_dir());
+    assert_eq!(
+        fs::read_to_string(format!("{extracted}/dir/file.txt")).unwrap(),
+        "content"
+    );
+}
+
+fn set_dir_modified(path: &str, modified: SystemTime) {
+    #[cfg(not(windows))]
+    let dir = fs::File::open(path).unwrap();
+    #[cfg(windows)]
+    let dir = {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        fs::OpenOptions::new()
+            .access_mode(FILE_WRITE_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)
+            .unwrap()
+    };
+    dir.set_modified(modified).unwrap();
+}
+
+#[test]
+fn keep_nested_directory_timestamps() {
+    setup();
+    let base = format!("{}/keep_dir/timestamps", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let src = format!("{base}/src");
+    fs::create_dir_all(format!("{src}/a/b/c")).unwrap();
+    for (dir, file) in [("a", "1.txt"), ("a/b", "2.txt"), ("a/b/c", "3.txt")] {
+        fs::write(format!("{src}/{dir}/{file}"), file).unwrap();
+    }
+    let dirs = [
+        (
+            "a/b/c",
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_002_000),
+        ),
+        (
+            "a/b",
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_001_000),
+        ),
+        (
+            "a",
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000),
+        ),
+    ];
+    for (dir, modified) in dirs {
+        set_dir_modified(&format!("{src}/{dir}"), modified);
+    }
+    let archive = format!("{base}/archive.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--keep-dir",
+        "--keep-timestamp",
+        "-r",
+        &src,
+    ]))
+    .unwrap();
+
+    let out_dir = format!("{base}/out");
+    extract(&archive, &out_dir, &["--keep-timestamp"]);
+
+    // NOTE: The entry names of the source depend on the platform, so they are looked up in the archive.
+    let mut reader = Archive::read_header(fs::File::open(&archive).unwrap()).unwrap();
+    let names = reader
+        .entries_skip_solid()
+        .map(|it| it.unwrap().header().path().to_string())
+        .collect::<Vec<_>>();
+    for (dir, modified) in dirs {
+        let name = names
+            .iter()
+            .find(|it| it.ends_with(&format!("src/{dir}")))
+            .unwrap();
+        let extracted = fs::metadata(format!("{out_dir}/{name}")).unwrap();
+        assert!(extracted.is_dir());
+        assert_eq!(extracted.modified().unwrap(), modified, "{name}");
+    }
+}
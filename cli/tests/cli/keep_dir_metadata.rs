@@ -0,0 +1,127 @@
+#![cfg(unix)]
+use crate::utils::setup;
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// Removes `path` including read-only directories.
+fn remove_dir_all<P: AsRef<Path>>(path: P) {
+    if !path.as_ref().exists() {
+        return;
+    }
+    for entry in walkdir::WalkDir::new(&path) {
+        let entry = entry.unwrap();
+        if entry.file_type().is_dir() {
+            fs::set_permissions(entry.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+    fs::remove_dir_all(path).unwrap();
+}
+
+fn archive_and_extract(base: &str, src: &str, options: &[&str]) -> String {
+    let archive = format!("{base}/archive.pna");
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "c",
+            &archive,
+            "--overwrite",
+            "--keep-dir",
+            "-r",
+            src,
+        ]
+        .into_iter()
+        .chain(options.iter().copied()),
+    ))
+    .unwrap();
+    let out_dir = format!("{base}/out");
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "x",
+            &archive,
+            "--overwrite",
+            "--out-dir",
+            &out_dir,
+        ]
+        .into_iter()
+        .chain(options.iter().copied()),
+    ))
+    .unwrap();
+    format!("{out_dir}/{}", src.trim_start_matches('/'))
+}
+
+#[test]
+fn keep_read_only_directory_metadata() {
+    setup();
+    let base = format!(
+        "{}/keep_dir_metadata/read_only",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    remove_dir_all(&base);
+    let src = format!("{base}/src");
+    fs::create_dir_all(format!("{src}/dir")).unwrap();
+    fs::write(format!("{src}/dir/file.txt"), "text").unwrap();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    fs::File::open(format!("{src}/dir"))
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    fs::set_permissions(format!("{src}/dir"), fs::Permissions::from_mode(0o555)).unwrap();
+
+    let extracted = archive_and_extract(&base, &src, &["--keep-permission", "--keep-timestamp"]);
+
+    let metadata = fs::metadata(format!("{extracted}/dir")).unwrap();
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o555);
+    assert_eq!(metadata.modified().unwrap(), modified);
+    assert_eq!(
+        fs::read_to_string(format!("{extracted}/dir/file.txt")).unwrap(),
+        "text"
+    );
+}
+
+#[cfg(all(feature = "acl", target_os = "linux"))]
+#[test]
+fn keep_directory_default_acl() {
+    use exacl::{AclEntry, AclOption, Flag, Perm};
+
+    setup();
+    let base = format!(
+        "{}/keep_dir_metadata/default_acl",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    remove_dir_all(&base);
+    let src = format!("{base}/src");
+    fs::create_dir_all(format!("{src}/dir")).unwrap();
+    let dir = format!("{src}/dir");
+    let mut entries = exacl::getfacl(&dir, AclOption::ACCESS_ACL).unwrap();
+    entries.extend([
+        AclEntry::allow_user("", Perm::READ | Perm::WRITE, Flag::DEFAULT),
+        AclEntry::allow_group("", Perm::READ, Flag::DEFAULT),
+        AclEntry::allow_other(Perm::empty(), Flag::DEFAULT),
+    ]);
+    exacl::setfacl(&[&dir], &entries, None).unwrap();
+    fs::write(format!("{dir}/file.txt"), "text").unwrap();
+
+    let extracted = archive_and_extract(&base, &src, &["--keep-acl", "--unstable"]);
+
+    let default_acl = exacl::getfacl(format!("{extracted}/dir"), AclOption::DEFAULT_ACL).unwrap();
+    assert!(
+        default_acl.contains(&AclEntry::allow_group("", Perm::READ, Flag::DEFAULT)),
+        "{default_acl:?}"
+    );
+    // A file created after extraction inherits the default ACL instead of the umask.
+    let created = format!("{extracted}/dir/created.txt");
+    fs::File::create(&created).unwrap();
+    assert_eq!(
+        fs::metadata(created).unwrap().permissions().mode() & 0o777,
+        0o640
+    );
+}
@@ -0,0 +1,241 @@
+#![cfg(unix)]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, Permission, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    io::Write,
+    os::unix::fs::{MetadataExt, PermissionsExt},
+};
+
+fn run(args: &[&str]) {
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet"].iter().chain(args).copied(),
+    ))
+    .unwrap();
+}
+
+/// Returns the uid, user name, gid, group name and mode stored for `name` in the archive.
+fn stored_permission(archive: &str, name: &str) -> Option<(u64, String, u64, String, u16)> {
+    let mut archive = Archive::read_header(fs::File::open(archive).unwrap()).unwrap();
+    let entry = archive
+        .entries_skip_solid()
+        .map(Result::unwrap)
+        .find(|it| it.header().path().as_str().ends_with(&format!("/{name}")))
+        .unwrap();
+    entry.metadata().permission().map(|p| {
+        (
+            p.uid(),
+            p.uname().into(),
+            p.gid(),
+            p.gname().into(),
+            p.permissions() & 0o7777,
+        )
+    })
+}
+
+/// Whether the owner is kept, and the modes of the directory and the file stored in the archive.
+type StoredPermission = (bool, u16, u16);
+
+#[test]
+fn create_keep_owner_and_mode() {
+    setup();
+    let base = format!("{}/keep_owner_mode/create", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let src = format!("{base}/src");
+    fs::create_dir_all(format!("{src}/dir")).unwrap();
+    fs::write(format!("{src}/dir/file.txt"), "content").unwrap();
+    fs::set_permissions(format!("{src}/dir"), fs::Permissions::from_mode(0o700)).unwrap();
+    fs::set_permissions(
+        format!("{src}/dir/file.txt"),
+        fs::Permissions::from_mode(0o600),
+    )
+    .unwrap();
+    let meta = fs::metadata(format!("{src}/dir/file.txt")).unwrap();
+    let (uid, gid) = (u64::from(meta.uid()), u64::from(meta.gid()));
+
+    let cases: [(&str, &[&str], Option<StoredPermission>); 7] = [
+        ("none", &[], None),
+        (
+            "permission",
+            &["--keep-permission"],
+            Some((true, 0o700, 0o600)),
+        ),
+        (
+            "owner_and_mode",
+            &["--keep-owner", "--keep-mode"],
+            Some((true, 0o700, 0o600)),
+        ),
+        ("owner", &["--keep-owner"], Some((true, 0o755, 0o644))),
+        (
+            "permission_no_mode",
+            &["--keep-permission", "--no-keep-mode"],
+            Some((true, 0o755, 0o644)),
+        ),
+        ("mode", &["--keep-mode"], Some((false, 0o700, 0o600))),
+        (
+            "permission_no_owner",
+            &["--keep-permission", "--no-keep-owner"],
+            Some((false, 0o700, 0o600)),
+        ),
+    ];
+    for (name, options, expected) in cases {
+        let archive = format!("{base}/{name}.pna");
+        run(&[&["c", &archive, "--keep-dir", "-r", &src][..], options].concat());
+        for (entry, is_dir) in [("dir", true), ("dir/file.txt", false)] {
+            let stored = stored_permission(&archive, entry);
+            let Some((keep_owner, dir_mode, file_mode)) = expected else {
+                assert_eq!(stored, None, "{name}: {entry}");
+                continue;
+            };
+            let (stored_uid, stored_uname, stored_gid, stored_gname, stored_mode) = stored.unwrap();
+            assert_eq!(
+                stored_mode,
+                if is_dir { dir_mode } else { file_mode },
+                "{name}: {entry}"
+            );
+            if keep_owner {
+                assert_eq!((stored_uid, stored_gid), (uid, gid), "{name}: {entry}");
+            } else {
+                assert_eq!(
+                    (stored_uid, stored_uname, stored_gid, stored_gname),
+                    (0, String::new(), 0, String::new()),
+                    "{name}: {entry}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn extract_keep_owner_and_mode() {
+    setup();
+    let base = format!("{}/keep_owner_mode/extract", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(&base).unwrap();
+    let current = fs::metadata(&base).unwrap();
+    let current = (current.uid(), current.gid());
+    // NOTE: Only root can give the files to another owner.
+    let owner = if nix::unistd::geteuid().is_root() {
+        (12345, 23456)
+    } else {
+        current
+    };
+    let archive = format!("{base}/archive.pna");
+    let mut writer = Archive::write_header(fs::File::create(&archive).unwrap()).unwrap();
+    let mut builder = EntryBuilder::new_file("file.txt".into(), WriteOptions::store()).unwrap();
+    builder.permission(Permission::new(
+        owner.0.into(),
+        String::new(),
+        owner.1.into(),
+        String::new(),
+        0o640,
+    ));
+    builder.write_all(b"content").unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+
+    let extract = |name: &str, options: &[&str]| {
+        let out_dir = format!("{base}/{name}");
+        run(&[&["x", &archive, "--out-dir", &out_dir][..], options].concat());
+        let meta = fs::metadata(format!("{out_dir}/file.txt")).unwrap();
+        ((meta.uid(), meta.gid()), meta.mode() & 0o7777)
+    };
+    let (_, default_mode) = extract("none", &[]);
+    assert_ne!(default_mode, 0o640);
+
+    let cases: [(&str, &[&str], bool, bool); 8] = [
+        ("permission", &["--keep-permission"], true, true),
+        (
+            "owner_and_mode",
+            &["--keep-owner", "--keep-mode"],
+            true,
+            true,
+        ),
+        ("owner", &["--keep-owner"], true, false),
+        (
+            "permission_no_mode",
+            &["--keep-permission", "--no-keep-mode"],
+            true,
+            false,
+        ),
+        ("mode", &["--keep-mode"], false, true),
+        (
+            "permission_no_owner",
+            &["--keep-permission", "--no-keep-owner"],
+            false,
+            true,
+        ),
+        ("same_owner", &["--same-owner"], true, false),
+        (
+            "permission_no_same_owner",
+            &["--keep-permission", "--no-same-owner"],
+            false,
+            true,
+        ),
+    ];
+    for (name, options, keep_owner, keep_mode) in cases {
+        assert_eq!(
+            extract(name, options),
+            (
+                if keep_owner { owner } else { current },
+                if keep_mode { 0o640 } else { default_mode },
+            ),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn extract_override_owner() {
+    setup();
+    let base = format!("{}/keep_owner_mode/override", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(&base).unwrap();
+    let current = fs::metadata(&base).unwrap();
+    let current = (current.uid(), current.gid());
+    let archive = format!("{base}/archive.pna");
+    let mut writer = Archive::write_header(fs::File::create(&archive).unwrap()).unwrap();
+    let mut builder = EntryBuilder::new_file("file.txt".into(), WriteOptions::store()).unwrap();
+    builder.permission(Permission::new(
+        12345,
+        "archived-user".into(),
+        23456,
+        "archived-group".into(),
+        0o640,
+    ));
+    builder.write_all(b"content").unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+
+    let extract = |name: &str, options: &[&str]| {
+        let out_dir = format!("{base}/{name}");
+        run(&[
+            &["x", &archive, "--out-dir", &out_dir, "--keep-permission"][..],
+            options,
+        ]
+        .concat());
+        let meta = fs::metadata(format!("{out_dir}/file.txt")).unwrap();
+        (meta.uid(), meta.gid())
+    };
+    if nix::unistd::geteuid().is_root() {
+        assert_eq!(
+            extract("ids", &["--uid", "54321", "--gid", "65432"]),
+            (54321, 65432)
+        );
+        assert_eq!(extract("names", &["--uname", "root", "--gid", "0"]), (0, 0));
+        assert_eq!(
+            extract("numeric", &["--numeric-owner", "--unknown-owner", "keep"]),
+            (12345, 23456)
+        );
+    } else {
+        // NOTE: Giving the files to another owner is not permitted,
+        //       so the owner is left as the extracting user instead of failing.
+        assert_eq!(extract("ids", &["--uid", "0", "--gid", "0"]), current);
+        assert_eq!(
+            extract("ids_no_same_owner", &["--uid", "0", "--no-same-owner"]),
+            current
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use crate::utils::{diff::diff, setup};
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Larger than the size of the files created in memory.
+const LARGE_FILE_SIZE: usize = 51 * 1024 * 1024;
+
+fn init_resource(dir: &str) {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(format!("{dir}/small.txt"), "small text\n").unwrap();
+    let content = (0..LARGE_FILE_SIZE)
+        .map(|i| (i % 251) as u8 ^ (i >> 20) as u8)
+        .collect::<Vec<_>>();
+    fs::write(format!("{dir}/large.bin"), content).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(format!("{dir}/large.bin"))
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000))
+        .unwrap();
+}
+
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            if let Some(found) = find_file(&path, name) {
+                return Some(found);
+            }
+        } else if path.file_name().is_some_and(|it| it == name) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+fn create_and_extract(src: &str, name: &str, options: &[&str]) -> String {
+    let archive = format!("{}/large_file/{name}.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/large_file/{name}/", env!("CARGO_TARGET_TMPDIR"));
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "c",
+            &archive,
+            "--overwrite",
+            "--keep-timestamp",
+            "-r",
+            src,
+        ]
+        .into_iter()
+        .chain(options.iter().copied()),
+    ))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &archive,
+        "--overwrite",
+        "--keep-timestamp",
+        "--out-dir",
+        &out_dir,
+    ]))
+    .unwrap();
+    out_dir
+}
+
+#[test]
+fn large_file_streaming_same_as_in_memory() {
+    setup();
+    let src = format!("{}/large_file/src", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&src);
+
+    // NOTE: Entries in a solid entry are always created in memory.
+    let in_memory = create_and_extract(&src, "in_memory", &["--solid"]);
+    let streaming = create_and_extract(&src, "streaming", &[]);
+
+    diff(&in_memory, &streaming).unwrap();
+    let (in_memory, streaming) = (
+        find_file(in_memory.as_ref(), "large.bin").unwrap(),
+        find_file(streaming.as_ref(), "large.bin").unwrap(),
+    );
+    let (in_memory, streaming) = (
+        fs::metadata(in_memory).unwrap(),
+        fs::metadata(streaming).unwrap(),
+    );
+    assert_eq!(streaming.len(), LARGE_FILE_SIZE as u64);
+    assert_eq!(streaming.modified().unwrap(), in_memory.modified().unwrap());
+}
@@ -0,0 +1,140 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use pna::{Archive, EntryBuilder, EntryName, SolidEntryBuilder, WriteOptions};
+use std::{fs, io::Write};
+
+fn init_resource(path: &str) {
+    fs::create_dir_all(format!("{}/lint", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    let long_component = "a".repeat(300);
+    let long_path = ["dir"; 70].join("/");
+    for name in [
+        "Readme.txt",
+        "README.txt",
+        "a:b.txt",
+        "trailing.",
+        long_component.as_str(),
+        long_path.as_str(),
+        "bad\u{FFFD}.txt",
+    ] {
+        let mut builder =
+            EntryBuilder::new_file(EntryName::from(name), WriteOptions::store()).unwrap();
+        builder.write_all(b"text").unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    let builder =
+        EntryBuilder::new_symbolic_link("dir/link".into(), "../../etc/passwd".into()).unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    let builder =
+        EntryBuilder::new_symbolic_link("dir/dangling".into(), "missing.txt".into()).unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    let builder =
+        EntryBuilder::new_symbolic_link("dir/readme".into(), "../README.txt".into()).unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+}
+
+fn lint(path: &str, options: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "experimental", "lint", path])
+        .args(options);
+    cmd.assert()
+}
+
+#[test]
+fn lint_all_checks() {
+    setup();
+    let path = format!("{}/lint/all_checks.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&path);
+
+    let assert = lint(&path, &[]).code(1);
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    for (severity, check) in [
+        ("error", "case-collision"),
+        ("error", "windows-name"),
+        ("error", "long-component"),
+        ("warning", "long-path"),
+        ("error", "symlink-escape"),
+        ("error", "links"),
+        ("warning", "non-utf8"),
+    ] {
+        assert!(
+            stdout
+                .lines()
+                .any(|it| it.starts_with(severity) && it.ends_with(&format!("[{check}]"))),
+            "{check} not reported:\n{stdout}"
+        );
+    }
+}
+
+#[test]
+fn lint_json_format() {
+    setup();
+    let path = format!("{}/lint/json_format.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&path);
+
+    let assert = lint(&path, &["--check", "symlink-escape", "--format", "json"]).code(1);
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let findings: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(
+        findings,
+        serde_json::json!([{
+            "check": "symlink-escape",
+            "severity": "error",
+            "path": "dir/link",
+            "message": "symbolic link to `../../etc/passwd` points outside the archive",
+        }])
+    );
+}
+
+#[test]
+fn lint_warnings_only() {
+    setup();
+    let path = format!("{}/lint/warnings_only.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&path);
+
+    lint(&path, &["--check", "long-path,non-utf8"]).success();
+}
+
+#[test]
+fn lint_links() {
+    setup();
+    let path = format!("{}/lint/links.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&path);
+
+    let assert = lint(&path, &["--check", "links", "--format", "json"]).code(1);
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    let findings: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(
+        findings,
+        serde_json::json!([{
+            "check": "links",
+            "severity": "error",
+            "path": "dir/dangling",
+            "message": "symbolic link to `missing.txt` does not resolve to an entry",
+        }])
+    );
+}
+
+#[test]
+fn lint_solid() {
+    setup();
+    let path = format!("{}/lint/solid.pna", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(format!("{}/lint", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+    let mut builder = SolidEntryBuilder::new(WriteOptions::builder().build()).unwrap();
+    for name in ["Readme.txt", "README.txt"] {
+        let mut entry =
+            EntryBuilder::new_file(EntryName::from(name), WriteOptions::store()).unwrap();
+        entry.write_all(b"text").unwrap();
+        builder.add_entry(entry.build().unwrap()).unwrap();
+    }
+    let mut writer = Archive::write_header(fs::File::create(&path).unwrap()).unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+
+    // The entries in solid entries are skipped without --solid.
+    lint(&path, &[]).success();
+    let assert = lint(&path, &["--solid"]).code(1);
+    let stdout = String::from_utf8(assert.get_output().stdout.clone()).unwrap();
+    assert!(stdout.contains("README.txt: differs only by case from `Readme.txt`"));
+}
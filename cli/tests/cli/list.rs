@@ -270,3 +270,170 @@ fn archive_list_solid_tree() {
     ]))
     .unwrap();
 }
+
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn archive_list_jsonl_threads() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/list_jsonl_threads.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+        "--keep-timestamp",
+        "--keep-permission",
+    ]))
+    .unwrap();
+    let list = |threads: &str| {
+        let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+        cmd.args([
+            "--threads",
+            threads,
+            "list",
+            "--format",
+            "jsonl",
+            &format!("{}/list_jsonl_threads.pna", env!("CARGO_TARGET_TMPDIR")),
+            "**/*.txt",
+            "--unstable",
+        ]);
+        cmd.assert().success().get_output().stdout.clone()
+    };
+    let single = list("1");
+    assert!(!single.is_empty());
+    assert_eq!(single, list("4"));
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn archive_list_csv_columns() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/list_csv_columns.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+    ]))
+    .unwrap();
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "list",
+        "--format",
+        "tsv",
+        "--columns",
+        "type,name",
+        "--delimiter",
+        ";",
+        &format!("{}/list_csv_columns.pna", env!("CARGO_TARGET_TMPDIR")),
+        "resources/test/raw/empty.txt",
+        "--unstable",
+    ]);
+    cmd.assert()
+        .success()
+        .stdout("type;name\nfile;resources/test/raw/empty.txt\n");
+}
+
+#[test]
+fn archive_list_csv_unknown_column() {
+    let result = cli::Cli::try_parse_from([
+        "pna",
+        "list",
+        "--format",
+        "csv",
+        "--columns",
+        "name,size",
+        "archive.pna",
+        "--unstable",
+    ]);
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("unknown column `size`"));
+    assert!(err.contains("raw_size"));
+}
+
+/// Creates an archive of a 1 KiB and a 2 KiB file stored without compression,
+/// returning the archive path and the entry name prefix of the files.
+#[cfg(not(target_family = "wasm"))]
+fn sized_archive(name: &str) -> (String, String) {
+    let dir = format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(format!("{dir}/in")).unwrap();
+    std::fs::write(format!("{dir}/in/a.txt"), vec![b'a'; 1024]).unwrap();
+    std::fs::write(format!("{dir}/in/b.txt"), vec![b'b'; 2048]).unwrap();
+    let archive = format!("{dir}/archive.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "--store",
+        &format!("{dir}/in/a.txt"),
+        &format!("{dir}/in/b.txt"),
+    ]))
+    .unwrap();
+    let prefix = format!("{}/in", dir.trim_start_matches('/'));
+    (archive, prefix)
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn archive_list_total() {
+    setup();
+    let (archive, prefix) = sized_archive("list_total");
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "list",
+        "--format",
+        "tsv",
+        "--columns",
+        "name,raw_size",
+        "--total",
+        &archive,
+        "--unstable",
+    ]);
+    cmd.assert().success().stdout(format!(
+        "name\traw_size\n{prefix}/a.txt\t1024\n{prefix}/b.txt\t2048\ntotal: entries 2, raw size 3072, compressed size 3072, ratio 100.0%\n"
+    ));
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn archive_list_total_counts_only_matched_entries() {
+    setup();
+    let (archive, prefix) = sized_archive("list_total_matched");
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["list", "--total", &archive, &format!("{prefix}/b.txt")]);
+    cmd.assert().success().stdout(format!(
+        "{prefix}/b.txt\ntotal: entries 1, raw size 2048, compressed size 2048, ratio 100.0%\n"
+    ));
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn archive_list_total_human_readable() {
+    setup();
+    let (archive, prefix) = sized_archive("list_total_human_readable");
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["list", "--total", "--human-readable", &archive]);
+    cmd.assert().success().stdout(format!(
+        "{prefix}/a.txt\n{prefix}/b.txt\ntotal: entries 2, raw size 3.0 KiB, compressed size 3.0 KiB, ratio 100.0%\n"
+    ));
+}
+
+#[cfg(not(target_family = "wasm"))]
+#[test]
+fn archive_list_table_human_readable() {
+    setup();
+    let (archive, _) = sized_archive("list_table_human_readable");
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["list", "-l", "--human-readable", &archive]);
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("1.0 KiB"), "{stdout}");
+    assert!(stdout.contains("2.0 KiB"), "{stdout}");
+}
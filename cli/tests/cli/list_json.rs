@@ -0,0 +1,95 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use pna::{Archive, EntryBuilder, SolidEntryBuilder, WriteOptions};
+use std::{fs, io::Write};
+
+const SPECIAL_NAMES: [&str; 3] = [
+    "dir/quote\"back\\slash.txt",
+    "unicode/日本語 ✓.txt",
+    "control/tab\tnew\nline.txt",
+];
+
+fn file_entry(name: &str) -> pna::NormalEntry {
+    let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+    builder.write_all(name.as_bytes()).unwrap();
+    builder.build().unwrap()
+}
+
+fn list_json(path: &str, args: &[&str]) -> serde_json::Value {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["list", "--format", "json", path, "--unstable"])
+        .args(args);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    serde_json::from_slice(&output).unwrap()
+}
+
+#[test]
+fn archive_list_json() {
+    setup();
+    let path = format!("{}/list_json/archive.pna", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(format!("{}/list_json", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(&path).unwrap()).unwrap();
+    for name in SPECIAL_NAMES {
+        writer.add_entry(file_entry(name)).unwrap();
+    }
+    let mut solid = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+    solid.add_entry(file_entry("solid.txt")).unwrap();
+    writer.add_entry(solid.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+
+    let json = list_json(&path, &["--solid"]);
+    assert_eq!(json["archive"], path.as_str());
+    let names = json["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|it| it["filename"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, [&SPECIAL_NAMES[..], &["solid.txt"]].concat());
+    let raw_size = [&SPECIAL_NAMES[..], &["solid.txt"]]
+        .concat()
+        .iter()
+        .map(|it| it.len() as u64)
+        .sum::<u64>();
+    assert_eq!(json["total_raw_size"], raw_size);
+    assert_eq!(
+        json["total_compressed_size"],
+        json["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|it| it["size"].as_u64().unwrap())
+            .sum::<u64>()
+    );
+    assert_eq!(json["entry_count"], 4);
+    assert_eq!(json["solid_entry_count"], 1);
+
+    // The totals are of the listed entries.
+    let json = list_json(&path, &["unicode/*"]);
+    assert_eq!(json["entries"][0]["filename"], SPECIAL_NAMES[1]);
+    assert_eq!(json["total_raw_size"], SPECIAL_NAMES[1].len());
+    assert_eq!(json["entry_count"], 1);
+    assert_eq!(json["solid_entry_count"], 0);
+}
+
+#[test]
+fn archive_list_json_empty() {
+    setup();
+    let path = format!("{}/list_json/empty.pna", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(format!("{}/list_json", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+    let writer = Archive::write_header(fs::File::create(&path).unwrap()).unwrap();
+    writer.finalize().unwrap();
+
+    let json = list_json(&path, &[]);
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "archive": path,
+            "entries": [],
+            "total_raw_size": 0,
+            "total_compressed_size": 0,
+            "entry_count": 0,
+            "solid_entry_count": 0,
+        })
+    );
+}
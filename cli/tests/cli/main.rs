@@ -1,27 +1,76 @@
 #![cfg_attr(target_os = "wasi", feature(wasi_ext))]
 mod acl;
 mod append;
+mod case_collision;
+mod cat;
+mod check_links;
 mod chmod;
 mod chown;
 #[cfg(not(target_family = "wasm"))]
 mod combination;
 mod concat;
+mod config;
+mod dedup;
 mod delete;
+mod diff;
 mod encrypt;
+mod exit_code;
+mod extract_filter;
+mod grep;
 mod hardlink;
+mod id_map;
+mod implicit_dirs;
+mod info;
 mod keep_acl;
 mod keep_all;
+mod keep_dir;
+mod keep_dir_metadata;
+mod keep_owner_mode;
+mod large_file;
+mod lint;
 mod list;
+mod list_json;
+mod merge;
+mod metadata_only;
 mod multipart;
+mod nanosecond_timestamp;
+mod no_recursion;
+mod normalize_paths;
+mod out_dir_mode;
+mod overwrite;
+mod password_command;
 mod password_from_file;
 mod password_hash;
+mod platform_tag;
+mod progress;
+mod put;
+mod recipient;
+mod repair;
+mod reproducible;
 mod restore_acl;
 mod restore_acl_0_19_1;
+mod resume;
+mod self_archive;
+mod signature;
+mod skip_existing;
+mod solid_block_size;
+mod solid_cache;
 mod solid_mode;
+mod sort;
 mod split;
+mod stdio_archive;
 mod strip;
 mod symlink;
+mod threads;
+mod to_stdout;
+mod transform;
+mod transform_conformance;
 mod update;
+mod url;
 mod user_group;
 pub mod utils;
+mod verify;
+mod version;
+mod windows_compat;
 mod xattr;
+mod zstd_dict;
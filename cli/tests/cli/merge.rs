@@ -0,0 +1,179 @@
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const MODIFIED: Duration = Duration::from_secs(1_000_000_000);
+
+fn init_resource(path: &str, files: &[(&str, &str)]) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for (name, content) in files {
+        let mut builder = EntryBuilder::new_file((*name).into(), WriteOptions::store()).unwrap();
+        builder.modified(MODIFIED);
+        builder.write_all(content.as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) {
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "x",
+            archive,
+            "--out-dir",
+            out_dir,
+            "--keep-timestamp",
+        ]
+        .into_iter()
+        .chain(options.iter().copied()),
+    ))
+    .unwrap();
+}
+
+/// Writes `content` to the file at `path`, keeping its modification time.
+fn tamper(path: &str, content: &str) {
+    let modified = fs::metadata(path).unwrap().modified().unwrap();
+    fs::write(path, content).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+fn read_report(path: &str) -> BTreeMap<String, String> {
+    fs::read_to_string(path)
+        .unwrap()
+        .lines()
+        .map(|line| {
+            let record = serde_json::from_str::<serde_json::Value>(line).unwrap();
+            (
+                record["name"].as_str().unwrap().to_string(),
+                record["action"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn merge_into_existing_directory() {
+    setup();
+    let base = format!("{}/merge/existing", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    let out_dir = format!("{base}/out");
+    let report = format!("{base}/report.jsonl");
+    init_resource(
+        &archive,
+        &[
+            ("identical.txt", "identical"),
+            ("modified.txt", "original"),
+            ("missing.txt", "missing"),
+            ("conflict.txt", "conflict"),
+        ],
+    );
+    extract(&archive, &out_dir, &[]);
+
+    fs::write(format!("{out_dir}/modified.txt"), "modified content").unwrap();
+    fs::remove_file(format!("{out_dir}/missing.txt")).unwrap();
+    fs::remove_file(format!("{out_dir}/conflict.txt")).unwrap();
+    fs::create_dir(format!("{out_dir}/conflict.txt")).unwrap();
+    fs::write(format!("{out_dir}/extra.txt"), "extra").unwrap();
+
+    extract(
+        &archive,
+        &out_dir,
+        &["--merge", "--report-file", &report, "--unstable"],
+    );
+    assert_eq!(
+        read_report(&report),
+        BTreeMap::from([
+            ("conflict.txt".into(), "conflict".into()),
+            ("identical.txt".into(), "unchanged".into()),
+            ("missing.txt".into(), "created".into()),
+            ("modified.txt".into(), "updated".into()),
+        ])
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/modified.txt")).unwrap(),
+        "original"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/missing.txt")).unwrap(),
+        "missing"
+    );
+    assert!(Path::new(&format!("{out_dir}/conflict.txt")).is_dir());
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/extra.txt")).unwrap(),
+        "extra"
+    );
+    assert_eq!(
+        fs::metadata(format!("{out_dir}/modified.txt"))
+            .unwrap()
+            .modified()
+            .unwrap(),
+        SystemTime::UNIX_EPOCH + MODIFIED
+    );
+}
+
+#[test]
+fn merge_verify_contents() {
+    setup();
+    let base = format!("{}/merge/verify", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    let out_dir = format!("{base}/out");
+    let report = format!("{base}/report.jsonl");
+    init_resource(&archive, &[("file.txt", "original")]);
+    extract(&archive, &out_dir, &[]);
+    // NOTE: The size and the modification time are the same as the entry.
+    tamper(&format!("{out_dir}/file.txt"), "tampered");
+
+    extract(
+        &archive,
+        &out_dir,
+        &["--merge", "--report-file", &report, "--unstable"],
+    );
+    assert_eq!(
+        read_report(&report),
+        BTreeMap::from([("file.txt".into(), "unchanged".into())])
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/file.txt")).unwrap(),
+        "tampered"
+    );
+
+    extract(
+        &archive,
+        &out_dir,
+        &[
+            "--merge",
+            "--merge-verify",
+            "--report-file",
+            &report,
+            "--unstable",
+        ],
+    );
+    assert_eq!(
+        read_report(&report),
+        BTreeMap::from([("file.txt".into(), "updated".into())])
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/file.txt")).unwrap(),
+        "original"
+    );
+}
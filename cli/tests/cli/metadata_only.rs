@@ -0,0 +1,88 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use pna::{Archive, EntryBuilder, ExtendedAttribute, Permission, WriteOptions};
+use std::{fs, io::Write, path::Path, time::Duration};
+
+fn init_resource(path: &str) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    let mut builder = EntryBuilder::new_file("dir/file.txt".into(), WriteOptions::store()).unwrap();
+    builder.modified(Duration::from_secs(1_700_000_000));
+    builder.permission(Permission::new(
+        1000,
+        "user".into(),
+        100,
+        "group".into(),
+        0o640,
+    ));
+    builder.add_xattr(ExtendedAttribute::new(
+        "user.binary".into(),
+        vec![0x00, 0xff, 0xfe, 0x01],
+    ));
+    builder.write_all(b"content").unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    writer
+        .add_entry(EntryBuilder::new_dir("dir".into()).build().unwrap())
+        .unwrap();
+    writer.finalize().unwrap();
+}
+
+#[test]
+fn extract_metadata_only() {
+    setup();
+    let base = format!("{}/metadata_only", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}/archive.pna");
+    let out_dir = format!("{base}/out");
+    init_resource(&archive);
+    let _ = fs::remove_dir_all(&out_dir);
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "x",
+        &archive,
+        "--metadata-only",
+        "--format",
+        "jsonl",
+        "--out-dir",
+        &out_dir,
+        "--unstable",
+    ]);
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let lines = String::from_utf8(output).unwrap();
+    let entries = lines
+        .lines()
+        .map(|it| serde_json::from_str::<serde_json::Value>(it).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(entries.len(), 2);
+    assert!(!Path::new(&out_dir).join("dir").exists());
+
+    let file = &entries[0];
+    for field in [
+        "header",
+        "solid",
+        "metadata",
+        "xattrs",
+        "acl",
+        "extra_chunks",
+    ] {
+        assert!(file.get(field).is_some(), "missing field {field}");
+    }
+    assert_eq!(file["header"]["path"], "dir/file.txt");
+    assert_eq!(file["header"]["data_kind"], "file");
+    assert_eq!(file["solid"], false);
+    assert_eq!(file["metadata"]["raw_file_size"], 7);
+    assert_eq!(
+        file["metadata"]["modified"],
+        serde_json::json!({"seconds": 1_700_000_000u64, "nanoseconds": 0})
+    );
+    assert_eq!(file["metadata"]["created"], serde_json::Value::Null);
+    assert_eq!(file["metadata"]["permission"]["uname"], "user");
+    assert_eq!(file["metadata"]["permission"]["mode"], 0o640);
+    assert_eq!(
+        file["xattrs"],
+        serde_json::json!([{"name": "user.binary", "size": 4, "value": "AP/+AQ=="}])
+    );
+    assert_eq!(entries[1]["header"]["data_kind"], "directory");
+}
@@ -1,6 +1,8 @@
 use crate::utils::setup;
 use clap::Parser;
+use pna::Archive;
 use portable_network_archive::{cli, command};
+use std::fs;
 
 #[test]
 fn multipart_archive() {
@@ -28,3 +30,42 @@ fn multipart_archive() {
     ]))
     .unwrap();
 }
+
+#[test]
+fn multipart_archive_id() {
+    setup();
+    let read_archive_id = |name: &str| {
+        let path = format!("{}/{name}", env!("CARGO_TARGET_TMPDIR"));
+        Archive::read_header(fs::File::open(path).unwrap())
+            .unwrap()
+            .archive_id()
+    };
+    for (name, options) in [
+        ("multipart_no_id", &[][..]),
+        ("multipart_id", &["--archive-id"]),
+    ] {
+        command::entry(cli::Cli::parse_from(
+            [
+                "pna",
+                "--quiet",
+                "c",
+                &format!("{}/{name}.pna", env!("CARGO_TARGET_TMPDIR")),
+                "--overwrite",
+                "../resources/test/multipart_test.txt",
+                "--unstable",
+                "--split",
+                // NOTE: Each part holds the 28-byte aIDN chunk with --archive-id.
+                "138",
+            ]
+            .into_iter()
+            .chain(options.iter().copied()),
+        ))
+        .unwrap();
+    }
+    // NOTE: Parts without the aIDN chunk can be read by pna 0.21.0 or earlier.
+    assert_eq!(read_archive_id("multipart_no_id.part1.pna"), None);
+    assert_eq!(read_archive_id("multipart_no_id.part2.pna"), None);
+    let id = read_archive_id("multipart_id.part1.pna");
+    assert!(id.is_some());
+    assert_eq!(id, read_archive_id("multipart_id.part2.pna"));
+}
@@ -0,0 +1,60 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    time::{Duration, SystemTime},
+};
+
+/// Modification time with a fractional part representable on the file systems of 100ns precision.
+const MODIFIED: Duration = Duration::new(1_700_000_000, 123_456_700);
+
+#[test]
+fn nanosecond_timestamp_round_trip() {
+    setup();
+    let base = format!("{}/nanosecond_timestamp", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let file = format!("{base}/in/file.txt");
+    fs::create_dir_all(format!("{base}/in")).unwrap();
+    fs::write(&file, "content").unwrap();
+    fs::File::options()
+        .write(true)
+        .open(&file)
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH + MODIFIED)
+        .unwrap();
+    let archive = format!("{base}/archive.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "--keep-timestamp",
+        &file,
+    ]))
+    .unwrap();
+
+    let mut read = pna::Archive::read_header(fs::File::open(&archive).unwrap()).unwrap();
+    let entry = read.entries_skip_solid().next().unwrap().unwrap();
+    assert_eq!(entry.metadata().modified(), Some(MODIFIED));
+
+    let out_dir = format!("{base}/out");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &archive,
+        "--overwrite",
+        "--keep-timestamp",
+        "--out-dir",
+        &out_dir,
+    ]))
+    .unwrap();
+    let modified = fs::metadata(format!("{out_dir}/{}", entry.header().path()))
+        .unwrap()
+        .modified()
+        .unwrap();
+    assert_eq!(modified, SystemTime::UNIX_EPOCH + MODIFIED);
+}
@@ -0,0 +1,88 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{fs, io::Write, path::Path};
+
+fn init_resource(path: &str) {
+    fs::create_dir_all(Path::new(path).parent().unwrap()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for dir in ["dir", "dir/sub"] {
+        let builder = EntryBuilder::new_dir(dir.into());
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    for file in ["dir/a.txt", "dir/sub/b.txt", "directory.txt"] {
+        let mut builder = EntryBuilder::new_file(file.into(), WriteOptions::store()).unwrap();
+        builder.write_all(b"content").unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, args: &[&str]) -> std::io::Result<()> {
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "x",
+            archive,
+            "--overwrite",
+            "--out-dir",
+            out_dir,
+        ]
+        .into_iter()
+        .chain(args.iter().copied()),
+    ))
+}
+
+#[test]
+fn extract_directory_recursively() {
+    setup();
+    let base = format!("{}/no_recursion/recursion", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+    for pattern in ["dir", "dir/", "./dir"] {
+        let out_dir = format!("{base}/out");
+        let _ = fs::remove_dir_all(&out_dir);
+        extract(&archive, &out_dir, &[pattern]).unwrap();
+
+        assert!(
+            Path::new(&format!("{out_dir}/dir/a.txt")).is_file(),
+            "{pattern}"
+        );
+        assert!(
+            Path::new(&format!("{out_dir}/dir/sub/b.txt")).is_file(),
+            "{pattern}"
+        );
+        assert!(
+            !Path::new(&format!("{out_dir}/directory.txt")).exists(),
+            "{pattern}"
+        );
+    }
+}
+
+#[test]
+fn extract_directory_no_recursion() {
+    setup();
+    let base = format!("{}/no_recursion/no_recursion", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+    let out_dir = format!("{base}/out");
+    let _ = fs::remove_dir_all(&out_dir);
+    extract(&archive, &out_dir, &["--no-recursion", "dir"]).unwrap();
+
+    assert!(Path::new(&format!("{out_dir}/dir")).is_dir());
+    assert!(!Path::new(&format!("{out_dir}/dir/a.txt")).exists());
+    assert!(!Path::new(&format!("{out_dir}/dir/sub")).exists());
+}
+
+#[test]
+fn extract_unmatched_pattern() {
+    setup();
+    let base = format!("{}/no_recursion/unmatched", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}/archive.pna");
+    init_resource(&archive);
+    let out_dir = format!("{base}/out");
+    assert!(extract(&archive, &out_dir, &["di"]).is_err());
+}
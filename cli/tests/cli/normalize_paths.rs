@@ -0,0 +1,76 @@
+#![cfg(unix)]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{fs, io::Write, path::Path};
+
+fn init_resource(path: &str, names: &[&str]) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for name in names {
+        let mut builder = EntryBuilder::new_file((*name).into(), WriteOptions::store()).unwrap();
+        builder.write_all(name.as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) -> std::io::Result<()> {
+    let _ = fs::remove_dir_all(out_dir);
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet", "x", archive, "--out-dir", out_dir]
+            .into_iter()
+            .chain(options.iter().copied()),
+    ))
+}
+
+#[test]
+fn extract_backslash_name_verbatim() {
+    setup();
+    let archive = format!(
+        "{}/normalize_paths/verbatim.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let out_dir = format!("{}/normalize_paths/verbatim", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, &["dir\\sub\\file.txt"]);
+    extract(&archive, &out_dir, &[]).unwrap();
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir\\sub\\file.txt")).unwrap(),
+        "dir\\sub\\file.txt"
+    );
+    assert!(!Path::new(&format!("{out_dir}/dir")).exists());
+}
+
+#[test]
+fn extract_backslash_name_normalized() {
+    setup();
+    let archive = format!(
+        "{}/normalize_paths/normalized.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let out_dir = format!("{}/normalize_paths/normalized", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, &["dir\\sub\\file.txt"]);
+    extract(&archive, &out_dir, &["--normalize-paths"]).unwrap();
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/sub/file.txt")).unwrap(),
+        "dir\\sub\\file.txt"
+    );
+}
+
+#[test]
+fn extract_normalized_name_collision() {
+    setup();
+    let archive = format!(
+        "{}/normalize_paths/collision.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let out_dir = format!("{}/normalize_paths/collision", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, &["dir/file.txt", "dir\\file.txt"]);
+    // NOTE: The names do not collide when extracted verbatim.
+    extract(&archive, &out_dir, &[]).unwrap();
+    let err = extract(&archive, &out_dir, &["--normalize-paths"]).unwrap_err();
+    assert!(err.to_string().contains("collides with dir/file.txt"));
+}
@@ -0,0 +1,65 @@
+#![cfg(unix)]
+use crate::utils::{create_archive, setup};
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+
+fn mode<P: AsRef<Path>>(path: P) -> u32 {
+    fs::metadata(path).unwrap().permissions().mode() & 0o7777
+}
+
+#[test]
+fn out_dir_mode_created_parents() {
+    setup();
+    let base = format!("{}/out_dir_mode/created", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}.pna");
+    create_archive(&archive, &["../resources/test/raw/text.txt"], &[]).unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &archive,
+        "--out-dir",
+        &format!("{base}/a/b"),
+        "--out-dir-mode",
+        "700",
+    ]))
+    .unwrap();
+
+    for dir in [
+        "",
+        "/a",
+        "/a/b",
+        "/a/b/resources",
+        "/a/b/resources/test",
+        "/a/b/resources/test/raw",
+    ] {
+        assert_eq!(mode(format!("{base}{dir}")), 0o700, "{base}{dir}");
+    }
+}
+
+#[test]
+fn out_dir_mode_keep_existing() {
+    setup();
+    let base = format!("{}/out_dir_mode/existing", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(&base).unwrap();
+    fs::set_permissions(&base, fs::Permissions::from_mode(0o755)).unwrap();
+    let archive = format!("{base}.pna");
+    create_archive(&archive, &["../resources/test/raw/text.txt"], &[]).unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &archive,
+        "--out-dir",
+        &base,
+        "--out-dir-mode",
+        "700",
+    ]))
+    .unwrap();
+
+    assert_eq!(mode(&base), 0o755);
+    assert_eq!(mode(format!("{base}/resources")), 0o700);
+}
@@ -0,0 +1,105 @@
+use crate::utils::{create_archive, setup};
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::fs;
+
+const FILES: &[&str] = &["-r", "../resources/test/raw"];
+
+const TEXT: &[u8] = b"important data, not an archive\n";
+
+fn init_text(path: &str) {
+    fs::create_dir_all(std::path::Path::new(path).parent().unwrap()).unwrap();
+    fs::write(path, TEXT).unwrap();
+}
+
+fn is_pna(path: &str) -> bool {
+    fs::read(path).unwrap().starts_with(pna::PNA_HEADER)
+}
+
+#[test]
+fn overwrite_text_file_without_force() {
+    setup();
+    let path = format!(
+        "{}/overwrite/without_force.txt",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    init_text(&path);
+
+    assert!(create_archive(&path, FILES, &[]).is_err());
+    assert_eq!(fs::read(&path).unwrap(), TEXT);
+}
+
+#[test]
+fn overwrite_text_file_with_force() {
+    setup();
+    let path = format!("{}/overwrite/with_force.txt", env!("CARGO_TARGET_TMPDIR"));
+    init_text(&path);
+
+    create_archive(&path, FILES, &["--force"]).unwrap();
+    assert!(is_pna(&path));
+}
+
+#[test]
+fn overwrite_archive() {
+    setup();
+    let path = format!("{}/overwrite/archive.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&path, FILES, &[]).unwrap();
+
+    create_archive(&path, FILES, &[]).unwrap();
+    assert!(is_pna(&path));
+}
+
+#[test]
+fn overwrite_backup() {
+    setup();
+    let path = format!("{}/overwrite/backup.txt", env!("CARGO_TARGET_TMPDIR"));
+    init_text(&path);
+
+    create_archive(&path, FILES, &["--backup"]).unwrap();
+    assert!(is_pna(&path));
+    assert_eq!(fs::read(format!("{path}~")).unwrap(), TEXT);
+}
+
+#[test]
+fn overwrite_backup_suffix() {
+    setup();
+    let path = format!(
+        "{}/overwrite/backup_suffix.txt",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    init_text(&path);
+
+    create_archive(&path, FILES, &["--backup=.bak"]).unwrap();
+    assert!(is_pna(&path));
+    assert_eq!(fs::read(format!("{path}.bak")).unwrap(), TEXT);
+}
+
+#[test]
+fn overwrite_transform_output_text_file() {
+    setup();
+    let archive = format!("{}/overwrite/transform.pna", env!("CARGO_TARGET_TMPDIR"));
+    let output = format!("{}/overwrite/transform.txt", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&archive, FILES, &[]).unwrap();
+    init_text(&output);
+
+    let delete = |options: &[&str]| {
+        let mut args = vec![
+            "pna",
+            "--quiet",
+            "experimental",
+            "delete",
+            archive.as_str(),
+            "--output",
+            output.as_str(),
+        ];
+        args.extend_from_slice(options);
+        args.push("resources/test/raw/text.txt");
+        command::entry(cli::Cli::parse_from(args))
+    };
+    assert!(delete(&[]).is_err());
+    assert_eq!(fs::read(&output).unwrap(), TEXT);
+
+    delete(&["--backup"]).unwrap();
+    assert!(is_pna(&output));
+    assert_eq!(fs::read(format!("{output}~")).unwrap(), TEXT);
+}
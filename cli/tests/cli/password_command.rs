@@ -0,0 +1,79 @@
+#![cfg(unix)]
+use crate::utils::setup;
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::{fs, os::unix::fs::PermissionsExt, path::PathBuf};
+
+fn write_script(name: &str, script: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, script).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn archive_password_command() {
+    setup();
+    let script = write_script(
+        "password_command.sh",
+        "#!/bin/sh\nprintf 'archive_password_command\\n'\n",
+    );
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/password_command.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+        "--password-command",
+        script.to_str().unwrap(),
+        "--aes",
+        "ctr",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &format!("{}/password_command.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/password_command/", env!("CARGO_TARGET_TMPDIR")),
+        "--password",
+        "archive_password_command",
+    ]))
+    .unwrap();
+}
+
+#[test]
+fn archive_password_command_failure() {
+    setup();
+    let script = write_script(
+        "password_command_failure.sh",
+        "#!/bin/sh\necho 'vault is locked' >&2\nexit 3\n",
+    );
+    let err = command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!(
+            "{}/password_command_failure.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+        "--password-command",
+        script.to_str().unwrap(),
+        "--aes",
+        "ctr",
+    ]))
+    .unwrap_err();
+    assert!(err.to_string().contains("vault is locked"), "{err}");
+    assert!(!PathBuf::from(format!(
+        "{}/password_command_failure.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    ))
+    .exists());
+}
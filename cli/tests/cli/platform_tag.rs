@@ -0,0 +1,107 @@
+use crate::utils::setup;
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::{collections::BTreeSet, fs, path::Path};
+
+fn init_resource(dir: &str) {
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(dir).unwrap();
+    for name in ["common.txt", "desktop.ini", "native.sh"] {
+        fs::write(format!("{dir}/{name}"), name).unwrap();
+    }
+}
+
+/// Returns the names of the regular files under `dir`, recursively.
+fn file_names(dir: &Path) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            names.extend(file_names(&path));
+        } else {
+            names.insert(path.file_name().unwrap().to_string_lossy().into_owned());
+        }
+    }
+    names
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) -> BTreeSet<String> {
+    let _ = fs::remove_dir_all(out_dir);
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet", "x", archive, "--out-dir", out_dir]
+            .into_iter()
+            .chain(options.iter().copied()),
+    ))
+    .unwrap();
+    file_names(Path::new(out_dir))
+}
+
+#[test]
+fn extract_platform_tagged_entries() {
+    setup();
+    let src = format!("{}/platform_tag/src", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{}/platform_tag/tagged.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/platform_tag/out", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&src);
+    let native = format!("os={},plan9", std::env::consts::OS);
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "-r",
+        &src,
+        "--unstable",
+        "--platform-tag",
+        "os=plan9",
+        "**/desktop.ini",
+        "--platform-tag",
+        &native,
+        "**/native.sh",
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        extract(&archive, &out_dir, &[]),
+        BTreeSet::from(["common.txt".into(), "native.sh".into()])
+    );
+    assert_eq!(
+        extract(&archive, &out_dir, &["--all-platforms"]),
+        BTreeSet::from([
+            "common.txt".into(),
+            "desktop.ini".into(),
+            "native.sh".into()
+        ])
+    );
+
+    // NOTE: The tags are kept even if all the other private chunks are stripped.
+    command::entry(cli::Cli::parse_from(["pna", "--quiet", "strip", &archive])).unwrap();
+    assert_eq!(
+        extract(&archive, &out_dir, &[]),
+        BTreeSet::from(["common.txt".into(), "native.sh".into()])
+    );
+}
+
+#[test]
+fn create_with_invalid_platform_tag() {
+    setup();
+    let src = format!("{}/platform_tag/invalid_src", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{}/platform_tag/invalid.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&src);
+    let err = command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "-r",
+        &src,
+        "--unstable",
+        "--platform-tag",
+        "arch=x86_64",
+        "**/native.sh",
+    ]))
+    .unwrap_err();
+    assert!(err.to_string().contains("unsupported platform selector"));
+}
@@ -0,0 +1,36 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{diff::diff, setup};
+
+#[test]
+fn progress_keeps_stdio_archive_intact() {
+    setup();
+    let out_dir = format!("{}/progress/stdio", env!("CARGO_TARGET_TMPDIR"));
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--progress",
+        "experimental",
+        "stdio",
+        "-c",
+        "-r",
+        "../lib",
+        #[cfg(windows)]
+        "--unstable",
+    ]);
+    let assert = cmd.assert().success();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.write_stdin(assert.get_output().stdout.as_slice());
+    cmd.args([
+        "--progress",
+        "experimental",
+        "stdio",
+        "-x",
+        "--overwrite",
+        "--out-dir",
+        &out_dir,
+        #[cfg(windows)]
+        "--unstable",
+    ]);
+    cmd.assert().success().stdout("");
+    diff("../lib", format!("{out_dir}/lib")).unwrap();
+}
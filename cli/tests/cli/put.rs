@@ -0,0 +1,142 @@
+#![cfg(unix)]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, Permission, SolidEntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{fs, io::Write, os::unix::fs::PermissionsExt};
+
+fn entry(name: &str, content: &str, option: WriteOptions) -> pna::NormalEntry {
+    let mut builder = EntryBuilder::new_file(name.into(), option).unwrap();
+    builder.permission(Permission::new(
+        1000,
+        "user".into(),
+        1000,
+        "group".into(),
+        0o640,
+    ));
+    builder.write_all(content.as_bytes()).unwrap();
+    builder.build().unwrap()
+}
+
+fn write_archive(path: &str, solid: bool) {
+    let mut archive = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    let entries = [
+        entry("dir/config.txt", "old", WriteOptions::builder().build()),
+        entry("dir/other.txt", "other", WriteOptions::builder().build()),
+    ];
+    if solid {
+        let mut builder = SolidEntryBuilder::new(WriteOptions::builder().build()).unwrap();
+        for entry in entries {
+            builder.add_entry(entry).unwrap();
+        }
+        archive.add_entry(builder.build().unwrap()).unwrap();
+    } else {
+        for entry in entries {
+            archive.add_entry(entry).unwrap();
+        }
+    }
+    archive.finalize().unwrap();
+}
+
+fn put(archive: &str, name: &str, content: &str, options: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "experimental", "put", archive, name])
+        .args(options)
+        .write_stdin(content);
+    cmd.assert()
+}
+
+fn extract(archive: &str, out_dir: &str) {
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        archive,
+        "--overwrite",
+        "--keep-mode",
+        "--out-dir",
+        out_dir,
+    ]))
+    .unwrap();
+}
+
+fn put_replaces_content(name: &str, solid: bool) {
+    setup();
+    let base = format!("{}/put/{name}", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(&base).unwrap();
+    let archive = format!("{base}/archive.pna");
+    write_archive(&archive, solid);
+
+    put(&archive, "dir/config.txt", "new", &[]).success();
+
+    let out_dir = format!("{base}/out");
+    extract(&archive, &out_dir);
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/config.txt")).unwrap(),
+        "new"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/other.txt")).unwrap(),
+        "other"
+    );
+    let mode = fs::metadata(format!("{out_dir}/dir/config.txt"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o640);
+}
+
+#[test]
+fn put_replaces_content_of_normal_entry() {
+    put_replaces_content("normal", false);
+}
+
+#[test]
+fn put_replaces_content_of_solid_entry() {
+    put_replaces_content("solid", true);
+}
+
+#[test]
+fn put_reset_metadata() {
+    setup();
+    let base = format!("{}/put/reset_metadata", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(&base).unwrap();
+    let archive = format!("{base}/archive.pna");
+    write_archive(&archive, false);
+
+    put(&archive, "dir/config.txt", "new", &["--reset-metadata"]).success();
+
+    let mut archive = Archive::read_header(fs::File::open(&archive).unwrap()).unwrap();
+    let entry = archive
+        .entries_skip_solid()
+        .map(Result::unwrap)
+        .find(|it| it.header().path() == "dir/config.txt")
+        .unwrap();
+    assert!(entry.metadata().permission().is_none());
+}
+
+#[test]
+fn put_missing_entry() {
+    setup();
+    let base = format!("{}/put/missing", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    fs::create_dir_all(&base).unwrap();
+    let archive = format!("{base}/archive.pna");
+    write_archive(&archive, false);
+
+    put(&archive, "dir/new.txt", "created", &[]).failure();
+    put(&archive, "dir/new.txt", "created", &["--create"]).success();
+
+    let out_dir = format!("{base}/out");
+    extract(&archive, &out_dir);
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/new.txt")).unwrap(),
+        "created"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/config.txt")).unwrap(),
+        "old"
+    );
+}
@@ -0,0 +1,196 @@
+use crate::utils::{diff::diff, setup};
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::fs;
+
+const IDENTITY_1: &str =
+    "AGE-SECRET-KEY-1QYPQXPQ9QCRSSZG2PVXQ6RS0ZQG3YYC5Z5TPWXQERGD3C8G7RUSQGPQYEE";
+const RECIPIENT_1: &str = "age1q73he0q5yzfu3d64msd3p6rvksnrwjk3d2598mgtmlqt9wrdr37q2vrn72";
+const IDENTITY_2: &str =
+    "AGE-SECRET-KEY-1YY3ZXFP9YCNJS2F29VKZ6T30XQCNYVE5X5MRWWPE8GANC0F78AQQ2X9KSF";
+const RECIPIENT_2: &str = "age1tp56lazs2jtn9ja2a409m7dnpfk6x89su46zht266js6w6835eascutqdx";
+const IDENTITY_3: &str =
+    "AGE-SECRET-KEY-1G9PYX3Z9GER5SJ22FDXY6NJ02PG4Y56524T9WKZETFD4CH27TASQJTETDY";
+
+fn write_identity(name: &str, identity: &str) -> String {
+    let path = format!("{}/{name}.txt", env!("CARGO_TARGET_TMPDIR"));
+    fs::write(&path, format!("# test identity\n{identity}\n")).unwrap();
+    path
+}
+
+#[test]
+fn recipient_archive() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/recipient.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+        "--recipient",
+        RECIPIENT_1,
+        "--unstable",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &format!("{}/recipient.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/recipient/", env!("CARGO_TARGET_TMPDIR")),
+        "--identity",
+        &write_identity("recipient_identity", IDENTITY_1),
+        "--unstable",
+    ]))
+    .unwrap();
+    diff(
+        "../resources/test/raw",
+        format!(
+            "{}/recipient/resources/test/raw",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+    )
+    .unwrap();
+}
+
+#[test]
+fn multiple_recipients_archive() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/multiple_recipients.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+        "--camellia",
+        "cbc",
+        "--recipient",
+        RECIPIENT_1,
+        "--recipient",
+        RECIPIENT_2,
+        "--unstable",
+    ]))
+    .unwrap();
+    for (i, identity) in [IDENTITY_1, IDENTITY_2].into_iter().enumerate() {
+        command::entry(cli::Cli::parse_from([
+            "pna",
+            "--quiet",
+            "x",
+            &format!("{}/multiple_recipients.pna", env!("CARGO_TARGET_TMPDIR")),
+            "--overwrite",
+            "--out-dir",
+            &format!("{}/multiple_recipients/{i}/", env!("CARGO_TARGET_TMPDIR")),
+            "--identity",
+            &write_identity(&format!("multiple_recipients_identity_{i}"), identity),
+            "--unstable",
+        ]))
+        .unwrap();
+        diff(
+            "../resources/test/raw",
+            format!(
+                "{}/multiple_recipients/{i}/resources/test/raw",
+                env!("CARGO_TARGET_TMPDIR")
+            ),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn recipient_archive_wrong_identity() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!(
+            "{}/recipient_wrong_identity.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+        "--recipient",
+        RECIPIENT_1,
+        "--unstable",
+    ]))
+    .unwrap();
+    let result = command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &format!(
+            "{}/recipient_wrong_identity.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/recipient_wrong_identity/", env!("CARGO_TARGET_TMPDIR")),
+        "--identity",
+        &write_identity("recipient_wrong_identity", IDENTITY_3),
+        "--unstable",
+    ]));
+    assert!(result.is_err());
+}
+
+#[test]
+fn recipient_and_password_archive() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/recipient_and_password.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw/images",
+        "--recipient",
+        RECIPIENT_1,
+        "--unstable",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "append",
+        &format!("{}/recipient_and_password.pna", env!("CARGO_TARGET_TMPDIR")),
+        "-r",
+        "../resources/test/raw/pna",
+        "--password",
+        "password",
+        "--aes",
+        "ctr",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &format!("{}/recipient_and_password.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/recipient_and_password/", env!("CARGO_TARGET_TMPDIR")),
+        "--password",
+        "password",
+        "--identity",
+        &write_identity("recipient_and_password_identity", IDENTITY_1),
+        "--unstable",
+    ]))
+    .unwrap();
+    for dir in ["images", "pna"] {
+        diff(
+            format!("../resources/test/raw/{dir}"),
+            format!(
+                "{}/recipient_and_password/resources/test/raw/{dir}",
+                env!("CARGO_TARGET_TMPDIR")
+            ),
+        )
+        .unwrap();
+    }
+}
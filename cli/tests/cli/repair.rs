@@ -0,0 +1,79 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use std::{fs, io::Write, path::Path};
+
+fn init_resource(path: &str) {
+    fs::create_dir_all(Path::new(path).parent().unwrap()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(name.repeat(10).as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn entry_names(path: &str) -> Vec<String> {
+    let mut archive = Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    archive
+        .entries_skip_solid()
+        .map(|entry| entry.unwrap().header().path().to_string())
+        .collect()
+}
+
+#[test]
+fn repair_broken_data() {
+    setup();
+    let base = format!("{}/repair/broken_data", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}/archive.pna");
+    let output = format!("{base}/repaired.pna");
+    init_resource(&archive);
+    let mut bytes = fs::read(&archive).unwrap();
+    // Flip bytes in the FDAT chunk of b.txt, whose contents are stored as is.
+    let offset = bytes
+        .windows(10)
+        .position(|it| it == b"b.txtb.txt")
+        .unwrap();
+    bytes[offset..offset + 4]
+        .iter_mut()
+        .for_each(|b| *b ^= 0xff);
+    fs::write(&archive, bytes).unwrap();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "experimental",
+        "repair",
+        &archive,
+        "--output",
+        &output,
+        "--force",
+    ]);
+    let assert = cmd.assert().code(2);
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("b.txt"), "{stderr}");
+
+    assert_eq!(entry_names(&output), ["a.txt", "c.txt"]);
+}
+
+#[test]
+fn repair_intact() {
+    setup();
+    let base = format!("{}/repair/intact", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}/archive.pna");
+    let output = format!("{base}/repaired.pna");
+    init_resource(&archive);
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "experimental",
+        "repair",
+        &archive,
+        "--output",
+        &output,
+        "--force",
+    ]);
+    cmd.assert().success();
+
+    assert_eq!(entry_names(&output), ["a.txt", "b.txt", "c.txt"]);
+}
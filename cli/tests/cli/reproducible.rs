@@ -0,0 +1,127 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use pna::Archive;
+use std::{fs, path::Path, time::Duration};
+
+const FILES: [&str; 4] = ["a.txt", "dir/b.txt", "dir/sub/c.txt", "z.txt"];
+
+/// Creates the files of `FILES` under `dir` in the given order of the files.
+fn init_resource<'a>(dir: &Path, files: impl IntoIterator<Item = &'a str>) {
+    if dir.exists() {
+        fs::remove_dir_all(dir).unwrap();
+    }
+    for name in files {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, name.repeat(10)).unwrap();
+    }
+}
+
+fn create_reproducible(dir: &str, archive: &str, extra: &[&str]) {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.env("SOURCE_DATE_EPOCH", "1700000000").args([
+        "--quiet",
+        "c",
+        archive,
+        "--overwrite",
+        "--keep-dir",
+        "--keep-timestamp",
+        "--keep-permission",
+        "-r",
+        dir,
+        "--reproducible",
+        "--unstable",
+    ]);
+    cmd.args(extra);
+    cmd.assert().success();
+}
+
+#[test]
+fn create_reproducible_archive() {
+    setup();
+    let base = format!("{}/reproducible", env!("CARGO_TARGET_TMPDIR"));
+    let dir = format!("{base}/in");
+    let (first, second) = (format!("{base}/1.pna"), format!("{base}/2.pna"));
+
+    init_resource(Path::new(&dir), FILES);
+    create_reproducible(&dir, &first, &[]);
+    // NOTE: Sleep so that the timestamps of the recreated files differ, if they were not clamped.
+    std::thread::sleep(Duration::from_millis(10));
+    init_resource(Path::new(&dir), FILES.into_iter().rev());
+    create_reproducible(&dir, &second, &[]);
+
+    assert_eq!(fs::read(&first).unwrap(), fs::read(&second).unwrap());
+
+    let mut archive = Archive::read_header(fs::File::open(&first).unwrap()).unwrap();
+    let mut names = Vec::new();
+    for entry in archive.entries_skip_solid() {
+        let entry = entry.unwrap();
+        assert_eq!(
+            entry.metadata().modified(),
+            Some(Duration::from_secs(1_700_000_000))
+        );
+        if let Some(permission) = entry.metadata().permission() {
+            #[cfg(unix)]
+            assert_eq!(permission.uid(), 0);
+            assert_eq!(permission.uname(), "");
+        }
+        names.push(entry.header().path().to_string());
+    }
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+}
+
+#[test]
+fn create_reproducible_split_archive() {
+    setup();
+    let base = format!("{}/reproducible_split", env!("CARGO_TARGET_TMPDIR"));
+    let dir = format!("{base}/in");
+    let (first, second) = (format!("{base}/1.pna"), format!("{base}/2.pna"));
+
+    init_resource(Path::new(&dir), FILES);
+    create_reproducible(&dir, &first, &["--split", "200", "--archive-id"]);
+    std::thread::sleep(Duration::from_millis(10));
+    init_resource(Path::new(&dir), FILES.into_iter().rev());
+    create_reproducible(&dir, &second, &["--split", "200", "--archive-id"]);
+
+    let part = |archive: &str, n: usize| archive.replace(".pna", &format!(".part{n}.pna"));
+    assert!(Path::new(&part(&first, 2)).exists());
+    let mut n = 1;
+    while Path::new(&part(&first, n)).exists() {
+        assert_eq!(
+            fs::read(part(&first, n)).unwrap(),
+            fs::read(part(&second, n)).unwrap(),
+            "part {n}"
+        );
+        n += 1;
+    }
+    assert!(!Path::new(&part(&second, n)).exists());
+
+    let archive = Archive::read_header(fs::File::open(part(&first, 1)).unwrap()).unwrap();
+    assert!(archive.archive_id().is_some());
+}
+
+#[test]
+fn create_reproducible_rejects_encryption() {
+    setup();
+    let base = format!("{}/reproducible_encryption", env!("CARGO_TARGET_TMPDIR"));
+    let dir = format!("{base}/in");
+    init_resource(Path::new(&dir), FILES);
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--quiet",
+        "c",
+        &format!("{base}/archive.pna"),
+        "--overwrite",
+        "-r",
+        &dir,
+        "--reproducible",
+        "--password",
+        "password",
+        "--unstable",
+    ]);
+    let assert = cmd.assert().failure();
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("--reproducible"), "{stderr}");
+}
@@ -0,0 +1,105 @@
+use crate::utils::{create_archive, diff::diff, setup};
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::{fs, io, path::Path};
+
+const FILES: &[&str] = &["-r", "../resources/test/raw"];
+
+fn extract(archive: &str, out_dir: &str, state: &str) -> io::Result<()> {
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        archive,
+        "--out-dir",
+        out_dir,
+        "--resume",
+        state,
+        "--unstable",
+    ]))
+}
+
+fn completed_entries(state: &str) -> Vec<String> {
+    let mut names = fs::read_to_string(state)
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let record = serde_json::from_str::<serde_json::Value>(line).unwrap();
+            record["name"].as_str().unwrap().to_string()
+        })
+        .collect::<Vec<_>>();
+    names.sort();
+    names
+}
+
+fn resume_interrupted(name: &str, create_options: &[&str]) {
+    setup();
+    let base = format!("{}/resume/{name}", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    let out_dir = format!("{base}/out");
+    let state = format!("{base}/state.json");
+    fs::create_dir_all(&base).unwrap();
+    create_archive(&archive, FILES, create_options).unwrap();
+
+    // NOTE: A directory in place of the file interrupts the extraction with an error.
+    let blocker = format!("{out_dir}/resources/test/raw/text.txt");
+    fs::create_dir_all(&blocker).unwrap();
+    assert!(extract(&archive, &out_dir, &state).is_err());
+    let completed = completed_entries(&state);
+    assert!(!completed.is_empty());
+    assert!(!completed.contains(&"resources/test/raw/text.txt".to_string()));
+
+    // Completed entries are not extracted again.
+    let marker = format!("{out_dir}/resources/test/raw/parent/child.txt");
+    fs::write(&marker, "extracted before the interruption").unwrap();
+    fs::remove_dir(&blocker).unwrap();
+    extract(&archive, &out_dir, &state).unwrap();
+    assert_eq!(
+        fs::read_to_string(&marker).unwrap(),
+        "extracted before the interruption"
+    );
+    assert!(completed_entries(&state).contains(&"resources/test/raw/text.txt".to_string()));
+
+    fs::copy("../resources/test/raw/parent/child.txt", &marker).unwrap();
+    diff(
+        "../resources/test/raw",
+        format!("{out_dir}/resources/test/raw"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn resume_interrupted_extraction() {
+    resume_interrupted("normal", &[]);
+}
+
+#[test]
+fn resume_interrupted_solid_extraction() {
+    resume_interrupted("solid", &["--solid"]);
+}
+
+#[test]
+fn resume_interrupted_compressed_extraction() {
+    resume_interrupted("compressed", &["--zstd"]);
+}
+
+#[test]
+fn resume_refuses_another_archive() {
+    setup();
+    let base = format!("{}/resume/another_archive", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    let other = format!("{base}/other.pna");
+    let out_dir = format!("{base}/out");
+    let state = format!("{base}/state.json");
+    fs::create_dir_all(&base).unwrap();
+    create_archive(&archive, FILES, &[]).unwrap();
+    create_archive(&other, FILES, &["--solid"]).unwrap();
+
+    extract(&archive, &out_dir, &state).unwrap();
+    let err = extract(&other, &format!("{base}/other"), &state).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(!Path::new(&format!("{base}/other")).exists());
+}
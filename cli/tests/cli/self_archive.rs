@@ -0,0 +1,95 @@
+use crate::utils::setup;
+use clap::Parser;
+use pna::Archive;
+use portable_network_archive::{cli, command};
+use std::fs;
+
+fn entry_names(path: &str) -> Vec<String> {
+    let mut archive = Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    archive
+        .entries_skip_solid()
+        .map(|it| it.unwrap().header().path().to_string())
+        .collect()
+}
+
+fn init_resource(dir: &str) {
+    let _ = fs::remove_dir_all(dir);
+    fs::create_dir_all(dir).unwrap();
+    fs::write(format!("{dir}/a.txt"), "a").unwrap();
+    fs::write(format!("{dir}/b.txt"), "b").unwrap();
+}
+
+#[test]
+fn create_archive_inside_source_directory() {
+    setup();
+    let dir = format!("{}/self_archive/create", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    let archive = format!("{dir}/self.pna");
+    // NOTE: The second run finds the archive written by the first one in the source directory.
+    for _ in 0..2 {
+        command::entry(cli::Cli::parse_from([
+            "pna",
+            "--quiet",
+            "c",
+            &archive,
+            "--overwrite",
+            "-r",
+            &dir,
+        ]))
+        .unwrap();
+    }
+    let names = entry_names(&archive);
+    assert_eq!(names.len(), 2);
+    assert!(names.iter().all(|it| !it.ends_with("self.pna")));
+}
+
+#[test]
+fn create_archive_skip_split_parts() {
+    setup();
+    let dir = format!("{}/self_archive/split_parts", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    fs::write(format!("{dir}/self.part1.pna"), "part1").unwrap();
+    fs::write(format!("{dir}/self.part2.pna"), "part2").unwrap();
+    fs::write(format!("{dir}/self.partial.pna"), "partial").unwrap();
+    let archive = format!("{dir}/self.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "-r",
+        &dir,
+    ]))
+    .unwrap();
+    let mut names = entry_names(&archive)
+        .into_iter()
+        .filter_map(|it| it.rsplit('/').next().map(String::from))
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["a.txt", "b.txt", "self.partial.pna"]);
+}
+
+#[test]
+fn append_archive_inside_source_directory() {
+    setup();
+    let dir = format!("{}/self_archive/append", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    let archive = format!("{dir}/self.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        &format!("{dir}/a.txt"),
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna", "--quiet", "a", &archive, "-r", &dir,
+    ]))
+    .unwrap();
+    let names = entry_names(&archive);
+    assert_eq!(names.len(), 3);
+    assert!(names.iter().all(|it| !it.ends_with("self.pna")));
+}
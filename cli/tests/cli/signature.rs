@@ -0,0 +1,136 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use base64::Engine;
+use ed25519_dalek::SigningKey;
+use pna::{Archive, Chunk, EntryBuilder, EntryName, ExtendedAttribute, ReadEntry, WriteOptions};
+use std::{fs, io::Write};
+
+const SEED: [u8; 32] = [7; 32];
+
+fn init_resource(dir: &str) {
+    fs::create_dir_all(dir).unwrap();
+    let engine = base64::engine::general_purpose::STANDARD;
+    let key = SigningKey::from_bytes(&SEED);
+    fs::write(
+        format!("{dir}/key"),
+        format!("# secret key\n{}\n", engine.encode(key.to_bytes())),
+    )
+    .unwrap();
+    fs::write(
+        format!("{dir}/key.pub"),
+        engine.encode(key.verifying_key().to_bytes()),
+    )
+    .unwrap();
+
+    let mut writer =
+        Archive::write_header(fs::File::create(format!("{dir}/archive.pna")).unwrap()).unwrap();
+    for name in ["a.txt", "b.txt"] {
+        let mut builder =
+            EntryBuilder::new_file(EntryName::from(name), WriteOptions::store()).unwrap();
+        builder.write_all(name.as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn pna(args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "experimental"]).args(args);
+    cmd.assert()
+}
+
+fn stdout_lines(assert: assert_cmd::assert::Assert) -> Vec<String> {
+    String::from_utf8(assert.get_output().stdout.clone())
+        .unwrap()
+        .lines()
+        .map(Into::into)
+        .collect()
+}
+
+#[test]
+fn signature_sign_and_verify() {
+    setup();
+    let dir = format!("{}/signature/verify", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    let archive = format!("{dir}/archive.pna");
+
+    pna(&["sign", "--key", &format!("{dir}/key"), &archive]).success();
+    // Signing again replaces the signatures.
+    pna(&["sign", "--key", &format!("{dir}/key"), &archive]).success();
+
+    let assert = pna(&[
+        "verify-signatures",
+        "--pubkey",
+        &format!("{dir}/key.pub"),
+        &archive,
+    ])
+    .success();
+    assert_eq!(
+        stdout_lines(assert),
+        ["ok: a.txt", "ok: b.txt", "ok: (archive)"]
+    );
+}
+
+#[test]
+fn signature_tampered_entry() {
+    setup();
+    let dir = format!("{}/signature/tampered", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    let archive = format!("{dir}/archive.pna");
+    pna(&["sign", "--key", &format!("{dir}/key"), &archive]).success();
+
+    // Rewrite the archive with an extended attribute added to `b.txt`, keeping all signatures.
+    let bytes = fs::read(&archive).unwrap();
+    let archive_signature = pna::read_as_chunks(bytes.as_slice())
+        .unwrap()
+        .map(Result::unwrap)
+        .find(|it| it.ty() == pna::ChunkType::private(*b"asIG").unwrap())
+        .map(|it| pna::RawChunk::from_data(it.ty(), it.data()))
+        .unwrap();
+    let mut reader = Archive::read_header(bytes.as_slice()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(&archive).unwrap()).unwrap();
+    for entry in reader.entries() {
+        let ReadEntry::Normal(entry) = entry.unwrap() else {
+            unreachable!()
+        };
+        let entry = if entry.header().path().as_str() == "b.txt" {
+            entry.with_xattrs(&[ExtendedAttribute::new("user.x".into(), b"y".to_vec())])
+        } else {
+            entry
+        };
+        writer.add_entry(entry).unwrap();
+    }
+    writer.finalize_with_chunks([archive_signature]).unwrap();
+
+    let assert = pna(&[
+        "verify-signatures",
+        "--pubkey",
+        &format!("{dir}/key.pub"),
+        &archive,
+    ])
+    .code(4);
+    assert_eq!(
+        stdout_lines(assert),
+        ["ok: a.txt", "tampered: b.txt", "tampered: (archive)"]
+    );
+}
+
+#[test]
+fn signature_unsigned_entries() {
+    setup();
+    let dir = format!("{}/signature/unsigned", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    let archive = format!("{dir}/archive.pna");
+
+    let assert = pna(&[
+        "verify-signatures",
+        "--pubkey",
+        &format!("{dir}/key.pub"),
+        &archive,
+    ])
+    .code(4);
+    assert_eq!(
+        stdout_lines(assert),
+        ["unsigned: a.txt", "unsigned: b.txt", "unsigned: (archive)"]
+    );
+}
@@ -0,0 +1,209 @@
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    io::Write,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+const MODIFIED: Duration = Duration::from_secs(1_000_000_000);
+const FILES: [(&str, &str); 6] = [
+    ("dir/a.txt", "a content"),
+    ("dir/b.txt", "b content"),
+    ("dir/c.txt", "c content"),
+    ("dir/d.txt", "d content"),
+    ("dir/e.txt", "e content"),
+    ("dir/f.txt", "f content"),
+];
+
+fn init_resource(path: &str) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    let mut builder = EntryBuilder::new_dir("dir".into());
+    builder.modified(MODIFIED);
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    for (name, content) in FILES {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.modified(MODIFIED);
+        builder.write_all(content.as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer
+        .add_entry(
+            EntryBuilder::new_symbolic_link("dir/symlink".into(), "a.txt".into())
+                .unwrap()
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    writer
+        .add_entry(
+            EntryBuilder::new_hard_link("dir/hardlink".into(), "a.txt".into())
+                .unwrap()
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) -> std::io::Result<()> {
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "x",
+            archive,
+            "--out-dir",
+            out_dir,
+            "--keep-timestamp",
+        ]
+        .into_iter()
+        .chain(options.iter().copied()),
+    ))
+}
+
+/// Writes `content` to the file at `path`, setting its modification time to `modified`.
+fn tamper(path: &str, content: &str, modified: SystemTime) {
+    fs::write(path, content).unwrap();
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+fn modified(path: &str) -> SystemTime {
+    fs::metadata(path).unwrap().modified().unwrap()
+}
+
+#[test]
+fn skip_existing_resumes_interrupted_extraction() {
+    setup();
+    let base = format!("{}/skip_existing/resume", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    let out_dir = format!("{base}/out");
+    init_resource(&archive);
+
+    // Simulate an interruption after the first half of the files,
+    // with the last one of them left half-written.
+    extract(
+        &archive,
+        &out_dir,
+        &["dir/a.txt", "dir/b.txt", "dir/c.txt", "dir/symlink"],
+    )
+    .unwrap();
+    fs::write(format!("{out_dir}/dir/c.txt"), "c con").unwrap();
+    // NOTE: Same size and modification time as the entry, so that the file is regarded as extracted.
+    tamper(
+        &format!("{out_dir}/dir/a.txt"),
+        "A CONTENT",
+        SystemTime::UNIX_EPOCH + MODIFIED,
+    );
+    let b_modified = modified(&format!("{out_dir}/dir/b.txt"));
+
+    extract(&archive, &out_dir, &["--skip-existing", "--unstable"]).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/a.txt")).unwrap(),
+        "A CONTENT"
+    );
+    assert_eq!(modified(&format!("{out_dir}/dir/b.txt")), b_modified);
+    for (name, content) in &FILES[1..] {
+        assert_eq!(
+            fs::read_to_string(format!("{out_dir}/{name}")).unwrap(),
+            *content
+        );
+        assert_eq!(
+            modified(&format!("{out_dir}/{name}")),
+            SystemTime::UNIX_EPOCH + MODIFIED
+        );
+    }
+    assert_eq!(
+        fs::read_link(format!("{out_dir}/dir/symlink")).unwrap(),
+        Path::new("a.txt")
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/hardlink")).unwrap(),
+        "A CONTENT"
+    );
+
+    // Everything is extracted, so that the third run skips all the entries.
+    extract(&archive, &out_dir, &["--skip-existing", "--unstable"]).unwrap();
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/a.txt")).unwrap(),
+        "A CONTENT"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn skip_existing_links() {
+    use std::os::unix::fs::MetadataExt;
+
+    setup();
+    let base = format!("{}/skip_existing/links", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    let out_dir = format!("{base}/out");
+    init_resource(&archive);
+    extract(&archive, &out_dir, &[]).unwrap();
+    let symlink = format!("{out_dir}/dir/symlink");
+    let symlink_ino = fs::symlink_metadata(&symlink).unwrap().ino();
+
+    // A hard link to another file is not the same link, so it is replaced.
+    let hardlink = format!("{out_dir}/dir/hardlink");
+    fs::remove_file(&hardlink).unwrap();
+    fs::hard_link(format!("{out_dir}/dir/b.txt"), &hardlink).unwrap();
+
+    extract(&archive, &out_dir, &["--skip-existing", "--unstable"]).unwrap();
+    assert_eq!(fs::symlink_metadata(&symlink).unwrap().ino(), symlink_ino);
+    assert_eq!(
+        fs::metadata(&hardlink).unwrap().ino(),
+        fs::metadata(format!("{out_dir}/dir/a.txt")).unwrap().ino()
+    );
+}
+
+#[test]
+fn keep_newer_files() {
+    setup();
+    let base = format!("{}/skip_existing/keep_newer", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&base);
+    let archive = format!("{base}/archive.pna");
+    let out_dir = format!("{base}/out");
+    init_resource(&archive);
+    extract(&archive, &out_dir, &[]).unwrap();
+
+    let newer = SystemTime::UNIX_EPOCH + MODIFIED + Duration::from_secs(3600);
+    let older = SystemTime::UNIX_EPOCH + MODIFIED - Duration::from_secs(3600);
+    tamper(&format!("{out_dir}/dir/d.txt"), "newer", newer);
+    tamper(&format!("{out_dir}/dir/e.txt"), "older", older);
+    // NOTE: A file of the same modification time is not newer, even with the same size.
+    tamper(
+        &format!("{out_dir}/dir/f.txt"),
+        "F CONTENT",
+        SystemTime::UNIX_EPOCH + MODIFIED,
+    );
+
+    extract(&archive, &out_dir, &["--keep-newer-files", "--unstable"]).unwrap();
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/d.txt")).unwrap(),
+        "newer"
+    );
+    assert_eq!(modified(&format!("{out_dir}/dir/d.txt")), newer);
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/e.txt")).unwrap(),
+        "e content"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dir/f.txt")).unwrap(),
+        "f content"
+    );
+}
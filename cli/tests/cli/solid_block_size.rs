@@ -0,0 +1,126 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{create_archive, setup};
+use clap::Parser;
+use pna::{Archive, ReadEntry};
+use portable_network_archive::{cli, command};
+use std::{fs, path::Path};
+
+const FILES: [(&str, usize); 4] = [
+    ("a.bin", 40 * 1024),
+    ("b.bin", 40 * 1024),
+    ("c.bin", 200 * 1024),
+    ("d.bin", 10 * 1024),
+];
+
+fn init_resource<P: AsRef<Path>>(dir: P) {
+    let dir = dir.as_ref();
+    if dir.exists() {
+        fs::remove_dir_all(dir).unwrap();
+    }
+    fs::create_dir_all(dir).unwrap();
+    for (i, (name, size)) in FILES.into_iter().enumerate() {
+        fs::write(dir.join(name), vec![i as u8; size]).unwrap();
+    }
+}
+
+/// Creates a solid archive of the files of [FILES] and returns its path.
+fn create_solid_archive(name: &str, options: &[&str]) -> String {
+    let dir = format!("{}/solid_block_size/{name}", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&dir);
+    let path = format!(
+        "{}/solid_block_size/{name}.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let files = FILES.map(|(name, _)| format!("{dir}/{name}"));
+    let options = ["--solid"].into_iter().chain(options.iter().copied());
+    create_archive(
+        &path,
+        &files.each_ref().map(String::as_str),
+        &options.collect::<Vec<_>>(),
+    )
+    .unwrap();
+    path
+}
+
+/// Returns the file names of the entries in each solid entry of the archive.
+fn read_blocks(path: &str) -> Vec<Vec<String>> {
+    let mut archive = Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    archive
+        .entries()
+        .map(|entry| match entry.unwrap() {
+            ReadEntry::Solid(solid) => solid
+                .entries(None)
+                .unwrap()
+                .map(|entry| {
+                    let entry = entry.unwrap();
+                    let name = entry.header().path().to_string();
+                    name.rsplit('/').next().unwrap().to_string()
+                })
+                .collect(),
+            ReadEntry::Normal(_) => panic!("expected only solid entries"),
+        })
+        .collect()
+}
+
+#[test]
+fn solid_block_size() {
+    setup();
+    let path = create_solid_archive(
+        "solid_block_size",
+        &["--solid-block-size", "100KiB", "--unstable"],
+    );
+    assert_eq!(
+        read_blocks(&path),
+        [vec!["a.bin", "b.bin"], vec!["c.bin"], vec!["d.bin"]],
+    );
+}
+
+#[test]
+fn solid_without_block_size() {
+    setup();
+    let path = create_solid_archive("solid_without_block_size", &[]);
+    assert_eq!(
+        read_blocks(&path),
+        [vec!["a.bin", "b.bin", "c.bin", "d.bin"]],
+    );
+}
+
+#[test]
+fn solid_block_size_extract_single_file() {
+    setup();
+    let path = create_solid_archive(
+        "solid_block_size_extract_single_file",
+        &["--solid-block-size", "100KiB", "--unstable", "--zstd=3"],
+    );
+    let mut archive = Archive::read_header(fs::File::open(&path).unwrap()).unwrap();
+    let name = archive
+        .entries_with_password(None)
+        .map(|entry| entry.unwrap().header().path().to_string())
+        .find(|name| name.ends_with("/c.bin"))
+        .unwrap();
+    let out_dir = format!(
+        "{}/solid_block_size/extract_single_file",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    if Path::new(&out_dir).exists() {
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &path,
+        "--overwrite",
+        "--out-dir",
+        &out_dir,
+        &name,
+    ]))
+    .unwrap();
+    assert_eq!(
+        fs::read(Path::new(&out_dir).join(&name)).unwrap(),
+        vec![2; 200 * 1024],
+    );
+    assert!(!Path::new(&out_dir)
+        .join(name.replace("/c.bin", "/d.bin"))
+        .exists());
+}
@@ -0,0 +1,189 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{create_archive, setup};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+const FILES: &[&str] = &["-r", "../resources/test/raw"];
+
+fn extract_with_cache(archive: &str, cache_dir: &str, out_dir: &str, options: &[&str]) {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--quiet",
+        "x",
+        archive,
+        "--overwrite",
+        "--out-dir",
+        out_dir,
+        "--solid-cache",
+        cache_dir,
+        "--unstable",
+    ])
+    .args(options);
+    cmd.assert().success();
+}
+
+/// Returns the cache files in `cache_dir`, sorted by name.
+fn cache_files(cache_dir: &str) -> Vec<PathBuf> {
+    let mut files = fs::read_dir(cache_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|it| it == "cache"))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path).unwrap().modified().unwrap()
+}
+
+fn set_modified(path: &Path, time: SystemTime) {
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(time)
+        .unwrap();
+}
+
+fn assert_extracted(out_dir: &str) {
+    for name in ["text.txt", "first/second/third/pna.txt"] {
+        assert_eq!(
+            fs::read(format!("{out_dir}/resources/test/raw/{name}")).unwrap(),
+            fs::read(format!("../resources/test/raw/{name}")).unwrap(),
+        );
+    }
+}
+
+#[test]
+fn solid_cache_reuse() {
+    setup();
+    let archive = format!("{}/solid_cache/reuse.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&archive, FILES, &["--solid"]).unwrap();
+    let base = format!("{}/solid_cache/reuse", env!("CARGO_TARGET_TMPDIR"));
+    let cache_dir = format!("{base}/cache");
+    let _ = fs::remove_dir_all(&cache_dir);
+
+    extract_with_cache(&archive, &cache_dir, &format!("{base}/out1"), &[]);
+    let files = cache_files(&cache_dir);
+    assert_eq!(files.len(), 1);
+    let contents = fs::read(&files[0]).unwrap();
+    #[cfg(unix)]
+    let inode = std::os::unix::fs::MetadataExt::ino(&fs::metadata(&files[0]).unwrap());
+    let old = SystemTime::now() - Duration::from_secs(3600);
+    set_modified(&files[0], old);
+
+    extract_with_cache(&archive, &cache_dir, &format!("{base}/out2"), &[]);
+    // The cached file is read in place and its use is recorded in the modification time.
+    assert_eq!(cache_files(&cache_dir), files);
+    assert_eq!(fs::read(&files[0]).unwrap(), contents);
+    #[cfg(unix)]
+    assert_eq!(
+        std::os::unix::fs::MetadataExt::ino(&fs::metadata(&files[0]).unwrap()),
+        inode
+    );
+    assert!(modified(&files[0]) > old);
+    assert_extracted(&format!("{base}/out2"));
+}
+
+#[test]
+fn solid_cache_evict_least_recently_used() {
+    setup();
+    let base = format!("{}/solid_cache/evict", env!("CARGO_TARGET_TMPDIR"));
+    let cache_dir = format!("{base}/cache");
+    let _ = fs::remove_dir_all(&cache_dir);
+    // NOTE: The archives are compressed differently, so each of them is cached in its own file
+    //       of the same size.
+    let archives = [("a", "--deflate"), ("b", "--zstd"), ("c", "--xz")].map(|(name, method)| {
+        let archive = format!("{base}/{name}.pna");
+        create_archive(&archive, FILES, &["--solid", method]).unwrap();
+        archive
+    });
+
+    extract_with_cache(&archives[0], &cache_dir, &format!("{base}/out"), &[]);
+    let a = cache_files(&cache_dir).remove(0);
+    let size = fs::metadata(&a).unwrap().len();
+    extract_with_cache(&archives[1], &cache_dir, &format!("{base}/out"), &[]);
+    let b = cache_files(&cache_dir)
+        .into_iter()
+        .find(|it| *it != a)
+        .unwrap();
+    let now = SystemTime::now();
+    set_modified(&a, now - Duration::from_secs(7200));
+    set_modified(&b, now - Duration::from_secs(3600));
+    // Using the cache of `a` makes `b` the least recently used one.
+    extract_with_cache(&archives[0], &cache_dir, &format!("{base}/out"), &[]);
+
+    let limit = (size * 5 / 2).to_string();
+    extract_with_cache(
+        &archives[2],
+        &cache_dir,
+        &format!("{base}/out"),
+        &["--solid-cache-limit", &limit],
+    );
+    let files = cache_files(&cache_dir);
+    assert_eq!(files.len(), 2);
+    assert!(files.contains(&a));
+    assert!(!files.contains(&b));
+    assert_extracted(&format!("{base}/out"));
+}
+
+#[test]
+fn solid_cache_replace_corrupted_file() {
+    setup();
+    let archive = format!("{}/solid_cache/corrupted.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(&archive, FILES, &["--solid"]).unwrap();
+    let base = format!("{}/solid_cache/corrupted", env!("CARGO_TARGET_TMPDIR"));
+    let cache_dir = format!("{base}/cache");
+    let _ = fs::remove_dir_all(&cache_dir);
+
+    extract_with_cache(&archive, &cache_dir, &format!("{base}/out1"), &[]);
+    let files = cache_files(&cache_dir);
+    assert_eq!(files.len(), 1);
+    let contents = fs::read(&files[0]).unwrap();
+    let mut corrupted = contents.clone();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 0xff;
+    fs::write(&files[0], &corrupted).unwrap();
+
+    extract_with_cache(&archive, &cache_dir, &format!("{base}/out2"), &[]);
+    assert_extracted(&format!("{base}/out2"));
+    // The corrupted file is replaced by the decompressed contents.
+    assert_eq!(cache_files(&cache_dir), files);
+    assert_eq!(fs::read(&files[0]).unwrap(), contents);
+}
+
+#[test]
+fn solid_cache_skip_encrypted() {
+    setup();
+    let archive = format!("{}/solid_cache/encrypted.pna", env!("CARGO_TARGET_TMPDIR"));
+    create_archive(
+        &archive,
+        FILES,
+        &[
+            "--solid",
+            "--password",
+            "password",
+            "--aes",
+            "ctr",
+            "--argon2",
+            "t=1,m=50",
+        ],
+    )
+    .unwrap();
+    let base = format!("{}/solid_cache/encrypted", env!("CARGO_TARGET_TMPDIR"));
+    let cache_dir = format!("{base}/cache");
+    let _ = fs::remove_dir_all(&cache_dir);
+
+    extract_with_cache(
+        &archive,
+        &cache_dir,
+        &format!("{base}/out"),
+        &["--password", "password"],
+    );
+    assert_extracted(&format!("{base}/out"));
+    assert_eq!(fs::read_dir(Path::new(&cache_dir)).unwrap().count(), 0);
+}
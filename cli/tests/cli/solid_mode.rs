@@ -1,4 +1,4 @@
-use crate::utils::setup;
+use crate::utils::{diff::diff, setup};
 use clap::Parser;
 use portable_network_archive::{cli, command};
 
@@ -109,3 +109,41 @@ fn solid_deflate_archive() {
     ]))
     .unwrap();
 }
+
+#[test]
+fn solid_packed_metadata_archive() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/solid_packed_metadata.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+        "--solid",
+        "--packed-metadata",
+        "--keep-timestamp",
+        "--keep-permission",
+        "--unstable",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &format!("{}/solid_packed_metadata.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/solid_packed_metadata/", env!("CARGO_TARGET_TMPDIR")),
+    ]))
+    .unwrap();
+    diff(
+        "../resources/test/raw",
+        format!(
+            "{}/solid_packed_metadata/resources/test/raw",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+    )
+    .unwrap();
+}
@@ -0,0 +1,92 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use clap::Parser;
+use pna::Archive;
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// Files of the fixture tree with their sizes and modification times in seconds.
+const FILES: [(&str, usize, u64); 3] = [
+    ("b.txt", 3, 3000),
+    ("a.txt", 10, 2000),
+    ("dir/c.txt", 1, 1000),
+];
+
+fn init_resource(dir: &Path) {
+    if dir.exists() {
+        fs::remove_dir_all(dir).unwrap();
+    }
+    fs::create_dir_all(dir.join("dir")).unwrap();
+    for (name, size, modified) in FILES {
+        let path = dir.join(name);
+        fs::write(&path, "x".repeat(size)).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(modified))
+            .unwrap();
+    }
+}
+
+/// Creates an archive of `dir` sorted in `order` and returns the names of its entries relative to `dir`,
+/// which is empty for `dir` itself.
+fn create_sorted(dir: &str, order: &str) -> Vec<String> {
+    let archive = format!("{dir}.{order}.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "--keep-dir",
+        "-r",
+        dir,
+        "--sort",
+        order,
+        "--unstable",
+    ]))
+    .unwrap();
+    let root = dir.trim_start_matches('/');
+    let mut archive = Archive::read_header(fs::File::open(&archive).unwrap()).unwrap();
+    archive
+        .entries_skip_solid()
+        .map(|entry| {
+            let name = entry.unwrap().header().path().to_string();
+            let name = name.strip_prefix(root).unwrap();
+            name.trim_start_matches('/').to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn create_sort() {
+    setup();
+    let dir = format!("{}/sort/in", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(Path::new(&dir));
+
+    assert_eq!(
+        create_sorted(&dir, "name"),
+        ["", "a.txt", "b.txt", "dir", "dir/c.txt"],
+    );
+    // NOTE: Directories precede their contents regardless of their own modification times and sizes.
+    assert_eq!(
+        create_sorted(&dir, "mtime"),
+        ["", "dir", "dir/c.txt", "a.txt", "b.txt"],
+    );
+    assert_eq!(
+        create_sorted(&dir, "size"),
+        ["", "dir", "dir/c.txt", "b.txt", "a.txt"],
+    );
+
+    let mut names = create_sorted(&dir, "none");
+    assert_eq!(names.first().map(String::as_str), Some(""));
+    let position = |name: &str| names.iter().position(|it| it == name).unwrap();
+    assert!(position("dir") < position("dir/c.txt"));
+    names.sort();
+    assert_eq!(names, ["", "a.txt", "b.txt", "dir", "dir/c.txt"]);
+}
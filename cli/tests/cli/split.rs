@@ -1,6 +1,55 @@
-use crate::utils::setup;
+use crate::utils::{diff::diff, setup};
 use clap::Parser;
+use pna::Archive;
 use portable_network_archive::{cli, command};
+use std::{fs, io, path::PathBuf};
+
+fn create_split_on_entry_source(name: &str) -> String {
+    let path = format!("{}/{name}/{name}.pna", env!("CARGO_TARGET_TMPDIR"));
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "create",
+        &path,
+        "--overwrite",
+        "--store",
+        "-r",
+        "../resources/test/raw/pna",
+        "../resources/test/raw/first",
+        "../resources/test/raw/text.txt",
+        "../resources/test/raw/images/icon.png",
+        "../resources/test/raw/images/icon.svg",
+    ]))
+    .unwrap();
+    path
+}
+
+fn split_parts(name: &str) -> Vec<PathBuf> {
+    (1..)
+        .map(|n| {
+            PathBuf::from(format!(
+                "{}/{name}/out/{name}.part{n}.pna",
+                env!("CARGO_TARGET_TMPDIR")
+            ))
+        })
+        .take_while(|it| it.exists())
+        .collect()
+}
+
+fn read_entry_names(archive: &mut Archive<fs::File>) -> Vec<String> {
+    archive
+        .entries_skip_solid()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            io::copy(
+                &mut entry.reader(pna::ReadOptions::builder().build()).unwrap(),
+                &mut io::sink(),
+            )
+            .unwrap();
+            entry.header().path().to_string()
+        })
+        .collect()
+}
 
 #[test]
 fn split_archive() {
@@ -36,3 +85,294 @@ fn split_archive() {
     ]))
     .unwrap();
 }
+
+#[test]
+fn split_archive_on_entry() {
+    setup();
+    let source = create_split_on_entry_source("split_on_entry");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "split",
+        &source,
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/split_on_entry/out", env!("CARGO_TARGET_TMPDIR")),
+        "--max-size",
+        "80kb",
+        "--split-on-entry",
+        "--unstable",
+    ]))
+    .unwrap();
+    let parts = split_parts("split_on_entry");
+    assert!(parts.len() > 1);
+
+    // Each part is a complete archive on its own.
+    let mut names = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        let mut archive = Archive::read_header(fs::File::open(part).unwrap()).unwrap();
+        names.extend(read_entry_names(&mut archive));
+        assert!(!archive.has_incomplete_entry());
+        assert_eq!(archive.has_next_archive(), i + 1 < parts.len());
+
+        let standalone = format!(
+            "{}/split_on_entry/standalone{i}.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        );
+        fs::copy(part, &standalone).unwrap();
+        let out_dir = format!(
+            "{}/split_on_entry/standalone{i}/",
+            env!("CARGO_TARGET_TMPDIR")
+        );
+        let _ = fs::remove_dir_all(&out_dir);
+        command::entry(cli::Cli::parse_from([
+            "pna",
+            "--quiet",
+            "x",
+            &standalone,
+            "--overwrite",
+            "--out-dir",
+            &out_dir,
+        ]))
+        .unwrap();
+        assert!(fs::read_dir(&out_dir).unwrap().next().is_some());
+    }
+
+    // Reading the chained parts yields every entry exactly once.
+    let mut archive = Archive::read_header(fs::File::open(&source).unwrap()).unwrap();
+    let mut expected = read_entry_names(&mut archive);
+    let mut sorted = names.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(sorted.len(), names.len());
+    names.sort();
+    expected.sort();
+    assert_eq!(names, expected);
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &parts[0].to_string_lossy(),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/split_on_entry/chained/", env!("CARGO_TARGET_TMPDIR")),
+    ]))
+    .unwrap();
+    for dir in ["pna", "first"] {
+        diff(
+            format!("../resources/test/raw/{dir}"),
+            format!(
+                "{}/split_on_entry/chained/resources/test/raw/{dir}",
+                env!("CARGO_TARGET_TMPDIR")
+            ),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn split_archive_entries_per_part() {
+    setup();
+    let source = create_split_on_entry_source("split_entries_per_part");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "split",
+        &source,
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/split_entries_per_part/out", env!("CARGO_TARGET_TMPDIR")),
+        "--entries-per-part",
+        "2",
+        "--unstable",
+    ]))
+    .unwrap();
+    let parts = split_parts("split_entries_per_part");
+    let mut total = 0;
+    for part in &parts {
+        let mut archive = Archive::read_header(fs::File::open(part).unwrap()).unwrap();
+        let names = read_entry_names(&mut archive);
+        assert!(!names.is_empty() && names.len() <= 2);
+        total += names.len();
+    }
+    let mut archive = Archive::read_header(fs::File::open(&source).unwrap()).unwrap();
+    assert_eq!(total, read_entry_names(&mut archive).len());
+    assert_eq!(parts.len(), total.div_ceil(2));
+}
+
+#[test]
+fn split_archive_on_entry_strict() {
+    setup();
+    let source = create_split_on_entry_source("split_on_entry_strict");
+    assert!(command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "split",
+        &source,
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/split_on_entry_strict/out", env!("CARGO_TARGET_TMPDIR")),
+        "--max-size",
+        "10kb",
+        "--split-on-entry",
+        "--strict",
+        "--unstable",
+    ]))
+    .is_err());
+}
+
+#[test]
+fn split_archive_strict_requires_split_on_entry() {
+    setup();
+    assert!(cli::Cli::try_parse_from([
+        "pna",
+        "split",
+        "split.pna",
+        "--max-size",
+        "10kb",
+        "--strict",
+        "--unstable",
+    ])
+    .is_err());
+}
+
+#[test]
+fn extract_part_with_cut_off_entry() {
+    setup();
+    let source = create_split_on_entry_source("split_cut_off");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "split",
+        &source,
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/split_cut_off/out", env!("CARGO_TARGET_TMPDIR")),
+        "--max-size",
+        "10kb",
+    ]))
+    .unwrap();
+    let parts = split_parts("split_cut_off");
+    let mut archive = Archive::read_header(fs::File::open(&parts[0]).unwrap()).unwrap();
+    read_entry_names(&mut archive);
+    assert!(archive.has_incomplete_entry());
+
+    let standalone = format!(
+        "{}/split_cut_off/standalone.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    fs::copy(&parts[0], &standalone).unwrap();
+    let err = command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &standalone,
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/split_cut_off/standalone/", env!("CARGO_TARGET_TMPDIR")),
+    ]))
+    .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn split_archive_into_parts() {
+    setup();
+    let source = create_split_on_entry_source("split_into_parts");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "split",
+        &source,
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/split_into_parts/out", env!("CARGO_TARGET_TMPDIR")),
+        "--parts",
+        "3",
+    ]))
+    .unwrap();
+    let parts = split_parts("split_into_parts");
+    assert_eq!(parts.len(), 3);
+
+    // Each part can be read up to its ANXT.
+    for (i, part) in parts.iter().enumerate() {
+        let mut archive = Archive::read_header(fs::File::open(part).unwrap()).unwrap();
+        for entry in archive.raw_entries() {
+            entry.unwrap();
+        }
+        assert_eq!(archive.has_next_archive(), i + 1 < parts.len());
+    }
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &parts[0].to_string_lossy(),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/split_into_parts/chained/", env!("CARGO_TARGET_TMPDIR")),
+    ]))
+    .unwrap();
+    for dir in ["pna", "first"] {
+        diff(
+            format!("../resources/test/raw/{dir}"),
+            format!(
+                "{}/split_into_parts/chained/resources/test/raw/{dir}",
+                env!("CARGO_TARGET_TMPDIR")
+            ),
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn split_archive_max_size_with_units() {
+    setup();
+    let source = create_split_on_entry_source("split_max_size_units");
+    for (size, max) in [("20kB", 20_000), ("20KiB", 20 * 1024), ("19.5KiB", 19_968)] {
+        let _ = fs::remove_dir_all(format!(
+            "{}/split_max_size_units/out",
+            env!("CARGO_TARGET_TMPDIR")
+        ));
+        command::entry(cli::Cli::parse_from([
+            "pna",
+            "--quiet",
+            "split",
+            &source,
+            "--overwrite",
+            "--out-dir",
+            &format!("{}/split_max_size_units/out", env!("CARGO_TARGET_TMPDIR")),
+            "--max-size",
+            size,
+        ]))
+        .unwrap();
+        let parts = split_parts("split_max_size_units");
+        assert!(parts.len() > 1);
+        for part in parts {
+            assert!(fs::metadata(part).unwrap().len() <= max);
+        }
+    }
+}
+
+#[test]
+fn split_archive_max_size_too_small() {
+    setup();
+    let source = create_split_on_entry_source("split_max_size_too_small");
+    let err = command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "split",
+        &source,
+        "--overwrite",
+        "--out-dir",
+        &format!(
+            "{}/split_max_size_too_small/out",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--max-size",
+        "10B",
+    ]))
+    .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
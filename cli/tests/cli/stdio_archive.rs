@@ -0,0 +1,79 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{diff::diff, setup};
+use itertools::Itertools;
+
+const OPTIONS: &[&[&str]] = &[
+    &[],
+    &["--keep-dir", "--keep-timestamp"],
+    &["--store"],
+    &["--xz", "--solid"],
+    &["--aes", "ctr", "--password", "password"],
+    &["--camellia", "cbc", "--solid", "--password", "password"],
+];
+
+/// Creates an archive of `../lib` written to stdout.
+fn create_to_stdout(options: &[&str]) -> Vec<u8> {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--quiet",
+        "c",
+        "-",
+        "-r",
+        "../lib",
+        #[cfg(windows)]
+        "--unstable",
+    ])
+    .args(options);
+    cmd.assert().success().get_output().stdout.clone()
+}
+
+#[test]
+fn create_and_extract_through_pipe() {
+    setup();
+    for options in OPTIONS {
+        let out_dir = format!(
+            "{}/stdio_archive/{}",
+            env!("CARGO_TARGET_TMPDIR"),
+            options.iter().join("")
+        );
+        let archive = create_to_stdout(options);
+
+        let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+        cmd.write_stdin(archive);
+        cmd.args([
+            "--quiet",
+            "x",
+            "-",
+            "--overwrite",
+            "--out-dir",
+            &out_dir,
+            "--password",
+            "password",
+            #[cfg(windows)]
+            "--unstable",
+        ]);
+        cmd.assert().success();
+        diff("../lib", format!("{out_dir}/lib")).unwrap();
+    }
+}
+
+#[test]
+fn list_from_stdin() {
+    setup();
+    let archive = create_to_stdout(&[]);
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.write_stdin(archive);
+    cmd.args(["--quiet", "ls", "-"]);
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.lines().any(|line| line == "lib/Cargo.toml"));
+}
+
+#[test]
+fn split_to_stdout() {
+    setup();
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "c", "-", "-r", "../lib", "--split", "1MB"]);
+    let assert = cmd.assert().failure();
+    assert!(assert.get_output().stdout.is_empty());
+}
@@ -0,0 +1,58 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{diff::diff, setup};
+use clap::Parser;
+use portable_network_archive::{cli, command};
+
+#[test]
+fn create_with_threads() {
+    setup();
+    let create = |threads: &str| {
+        command::entry(cli::Cli::parse_from([
+            "pna",
+            "--quiet",
+            "--threads",
+            threads,
+            "c",
+            &format!("{}/threads/{threads}.pna", env!("CARGO_TARGET_TMPDIR")),
+            "--overwrite",
+            "-r",
+            "../resources/test/raw",
+            "--zstd",
+        ]))
+        .unwrap();
+        let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+        cmd.args([
+            "list",
+            "--format",
+            "jsonl",
+            &format!("{}/threads/{threads}.pna", env!("CARGO_TARGET_TMPDIR")),
+            "--unstable",
+        ]);
+        cmd.assert().success().get_output().stdout.clone()
+    };
+    let single = create("1");
+    assert!(!single.is_empty());
+    assert_eq!(single, create("4"));
+
+    // NOTE: With a single thread, the entries are extracted without a thread pool.
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "--threads",
+        "1",
+        "x",
+        &format!("{}/threads/4.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/threads/out/", env!("CARGO_TARGET_TMPDIR")),
+    ]))
+    .unwrap();
+    diff(
+        "../resources/test/raw",
+        format!(
+            "{}/threads/out/resources/test/raw",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+    )
+    .unwrap();
+}
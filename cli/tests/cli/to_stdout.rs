@@ -0,0 +1,100 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use pna::{Archive, CipherMode, Encryption, EntryBuilder, Metadata, WriteOptions};
+use std::{fs, io::Write};
+
+fn init_resource(path: &str) {
+    fs::create_dir_all(format!("{}/to_stdout", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    writer
+        .add_entry(EntryBuilder::new_dir("dir".into()).build().unwrap())
+        .unwrap();
+    for (name, content) in [("dir/b.txt", "b content\n"), ("a.txt", "a content\n")] {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(content.as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer
+        .add_entry(
+            EntryBuilder::new_symbolic_link("dir/link".into(), "b.txt".into())
+                .unwrap()
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    writer.finalize().unwrap();
+}
+
+fn extract_to_stdout(args: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "x", "--to-stdout"]).args(args);
+    cmd.assert()
+}
+
+#[test]
+fn to_stdout_single_entry() {
+    setup();
+    let archive = format!("{}/to_stdout/single.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    extract_to_stdout(&[&archive, "a.txt"])
+        .success()
+        .stdout("a content\n");
+}
+
+#[test]
+fn to_stdout_multiple_entries_in_archive_order() {
+    setup();
+    let archive = format!("{}/to_stdout/multiple.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    extract_to_stdout(&[&archive, "a.txt", "dir/b.txt"])
+        .success()
+        .stdout("b content\na content\n");
+    // Directories and symbolic links are skipped.
+    extract_to_stdout(&[&archive, "*"])
+        .success()
+        .stdout("b content\na content\n");
+}
+
+#[test]
+fn to_stdout_encrypted_solid_entries() {
+    setup();
+    let archive = format!("{}/to_stdout/solid.pna", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(format!("{}/to_stdout", env!("CARGO_TARGET_TMPDIR"))).unwrap();
+    let option = WriteOptions::builder()
+        .encryption(Encryption::Aes)
+        .cipher_mode(CipherMode::CTR)
+        .password(Some("password"))
+        .build();
+    let mut writer =
+        Archive::write_solid_header(fs::File::create(&archive).unwrap(), option).unwrap();
+    for (name, content) in [("b.txt", "b content\n"), ("a.txt", "a content\n")] {
+        writer
+            .write_file(name.into(), Metadata::new(), |w| {
+                w.write_all(content.as_bytes())
+            })
+            .unwrap();
+    }
+    writer.finalize().unwrap();
+
+    extract_to_stdout(&[&archive, "*.txt", "--password", "password"])
+        .success()
+        .stdout("b content\na content\n");
+}
+
+#[test]
+fn to_stdout_requires_patterns() {
+    setup();
+    let archive = format!("{}/to_stdout/no_patterns.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    extract_to_stdout(&[&archive]).failure().stdout("");
+}
+
+#[test]
+fn to_stdout_unmatched_pattern() {
+    setup();
+    let archive = format!("{}/to_stdout/unmatched.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    extract_to_stdout(&[&archive, "a.txt", "missing.txt"])
+        .failure()
+        .stdout("a content\n");
+}
@@ -0,0 +1,213 @@
+#![cfg(unix)]
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{fs, io::Write, path::Path};
+
+/// Creates an archive with `src/a.txt`, and a hard link and a symbolic link to it in `src`
+/// whose targets are `target`.
+fn init_resource(path: &str, target: &str) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    let mut builder = EntryBuilder::new_file("src/a.txt".into(), WriteOptions::store()).unwrap();
+    builder.write_all(b"a").unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    let builder = EntryBuilder::new_hard_link("src/hard.txt".into(), target.into()).unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    let builder = EntryBuilder::new_symbolic_link("src/sym.txt".into(), target.into()).unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) -> std::io::Result<()> {
+    let _ = fs::remove_dir_all(out_dir);
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet", "x", archive, "--out-dir", out_dir]
+            .into_iter()
+            .chain(options.iter().copied()),
+    ))
+}
+
+#[test]
+fn extract_transform_link_targets() {
+    setup();
+    let archive = format!("{}/transform/links.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/transform/links", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, "../src/a.txt");
+    extract(&archive, &out_dir, &["--transform", "s,src,dst,g"]).unwrap();
+
+    assert!(!Path::new(&format!("{out_dir}/src")).exists());
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/a.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/hard.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_link(format!("{out_dir}/dst/sym.txt")).unwrap(),
+        Path::new("../dst/a.txt")
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/sym.txt")).unwrap(),
+        "a"
+    );
+}
+
+#[test]
+fn extract_transform_without_symlink_targets() {
+    setup();
+    let archive = format!("{}/transform/no_symlink.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/transform/no_symlink", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, "../src/a.txt");
+    extract(&archive, &out_dir, &["--transform", "s,src,dst,gS"]).unwrap();
+
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/hard.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_link(format!("{out_dir}/dst/sym.txt")).unwrap(),
+        Path::new("../src/a.txt")
+    );
+}
+
+#[test]
+fn extract_transform_without_hardlink_targets() {
+    setup();
+    let archive = format!("{}/transform/no_hardlink.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/transform/no_hardlink", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, "../src/a.txt");
+    // NOTE: The hard link target is not renamed, so it does not exist.
+    extract(&archive, &out_dir, &["--transform", "s,src,dst,gH"]).unwrap_err();
+
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/a.txt")).unwrap(),
+        "a"
+    );
+    assert!(!Path::new(&format!("{out_dir}/dst/hard.txt")).exists());
+}
+
+#[test]
+fn extract_bsd_substitution() {
+    setup();
+    let archive = format!("{}/transform/bsd.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/transform/bsd", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, "../src/a.txt");
+    // NOTE: Only the first matching pattern is applied.
+    extract(
+        &archive,
+        &out_dir,
+        &["-s", ",src,dst,g", "-s", ",dst,other,g"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/hard.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_link(format!("{out_dir}/dst/sym.txt")).unwrap(),
+        Path::new("../dst/a.txt")
+    );
+}
+
+#[test]
+fn extract_transform_then_strip_components() {
+    setup();
+    let archive = format!("{}/transform/strip.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/transform/strip", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, "a.txt");
+    extract(
+        &archive,
+        &out_dir,
+        &[
+            "--transform",
+            "s,^src/,top/dst/,",
+            "--strip-components",
+            "1",
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/a.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/hard.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/dst/sym.txt")).unwrap(),
+        "a"
+    );
+    assert!(!Path::new(&format!("{out_dir}/a.txt")).exists());
+}
+
+#[test]
+fn extract_strip_components_link_targets() {
+    setup();
+    let archive = format!("{}/transform/strip_links.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/transform/strip_links", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, "../src/a.txt");
+    extract(&archive, &out_dir, &["--strip-components", "1"]).unwrap();
+
+    assert_eq!(fs::read_to_string(format!("{out_dir}/a.txt")).unwrap(), "a");
+    // NOTE: The targets are stripped as well, so the links point to the stripped a.txt.
+    assert_eq!(
+        fs::read_to_string(format!("{out_dir}/hard.txt")).unwrap(),
+        "a"
+    );
+    assert_eq!(
+        fs::read_link(format!("{out_dir}/sym.txt")).unwrap(),
+        Path::new("a.txt")
+    );
+    assert!(!Path::new(&format!("{out_dir}/../src")).exists());
+}
+
+#[test]
+fn extract_strip_components_more_than_depth() {
+    setup();
+    let archive = format!("{}/transform/strip_all.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/transform/strip_all", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive, "a.txt");
+    extract(&archive, &out_dir, &["--strip-components", "2"]).unwrap();
+
+    // NOTE: Every entry has 2 components, so nothing is left to extract.
+    let extracted = fs::read_dir(&out_dir)
+        .map(|it| it.count())
+        .unwrap_or_default();
+    assert_eq!(extracted, 0);
+}
+
+#[test]
+fn extract_strip_components_keeps_outer_symlink_targets() {
+    setup();
+    let archive = format!("{}/transform/strip_outer.pna", env!("CARGO_TARGET_TMPDIR"));
+    let out_dir = format!("{}/transform/strip_outer", env!("CARGO_TARGET_TMPDIR"));
+    fs::create_dir_all(Path::new(&archive).parent().unwrap()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(&archive).unwrap()).unwrap();
+    for (name, target) in [
+        ("top/dir/absolute", "/etc/hosts"),
+        ("top/dir/outside", "../../../outside.txt"),
+    ] {
+        let builder = EntryBuilder::new_symbolic_link(name.into(), target.into()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+    extract(&archive, &out_dir, &["--strip-components", "1"]).unwrap();
+
+    assert_eq!(
+        fs::read_link(format!("{out_dir}/dir/absolute")).unwrap(),
+        Path::new("/etc/hosts")
+    );
+    assert_eq!(
+        fs::read_link(format!("{out_dir}/dir/outside")).unwrap(),
+        Path::new("../../../outside.txt")
+    );
+}
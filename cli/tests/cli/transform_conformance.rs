@@ -0,0 +1,311 @@
+//! Checks that the commands transforming archives keep what they are not meant to change,
+//! including the private chunks unknown to this program.
+use crate::utils::setup;
+use clap::Parser;
+use pna::{
+    prelude::*, Archive, ChunkType, DataKind, EntryBuilder, ExtendedAttribute, NormalEntry,
+    Permission, RawChunk, ReadEntry, ReadOptions, SolidEntryBuilder, WriteOptions,
+};
+use portable_network_archive::{cli, command};
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+    time::Duration,
+};
+
+/// Build provenance attached by a downstream tool, unknown to this program.
+fn provenance() -> ChunkType {
+    ChunkType::private(*b"bpRV").unwrap()
+}
+
+fn new_entry(name: &str, data_kind: DataKind, option: WriteOptions) -> NormalEntry {
+    let mut builder = match data_kind {
+        DataKind::Directory => EntryBuilder::new_dir(name.into()),
+        _ => EntryBuilder::new_file(name.into(), option).unwrap(),
+    };
+    builder
+        .created(Duration::from_secs(1_000_000_000))
+        .modified(Duration::from_secs(1_100_000_000))
+        .accessed(Duration::from_secs(1_200_000_000))
+        .permission(Permission::new(
+            1000,
+            "user".into(),
+            1000,
+            "group".into(),
+            0o644,
+        ))
+        .add_xattr(ExtendedAttribute::new(
+            "user.provenance".into(),
+            name.as_bytes().into(),
+        ))
+        .add_extra_chunk(RawChunk::from_data(
+            ChunkType::private(*b"faCl").unwrap(),
+            "linux",
+        ))
+        .add_extra_chunk(RawChunk::from_data(
+            ChunkType::private(*b"faCe").unwrap(),
+            ":u::allow:r,w",
+        ))
+        .add_extra_chunk(RawChunk::from_data(
+            provenance(),
+            format!("built from {name}"),
+        ));
+    if data_kind == DataKind::File {
+        builder.write_all(name.repeat(64).as_bytes()).unwrap();
+    }
+    builder.build().unwrap()
+}
+
+/// Creates an archive whose entries carry unknown private chunks, extended attributes, ACLs and full metadata.
+fn init_fixture(path: &Path) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    writer
+        .add_entry(new_entry("dir", DataKind::Directory, WriteOptions::store()))
+        .unwrap();
+    writer
+        .add_entry(new_entry(
+            "dir/compressed.txt",
+            DataKind::File,
+            WriteOptions::builder()
+                .compression(pna::Compression::ZStandard)
+                .build(),
+        ))
+        .unwrap();
+    writer
+        .add_entry(new_entry(
+            "dir/stored.txt",
+            DataKind::File,
+            WriteOptions::store(),
+        ))
+        .unwrap();
+    let mut solid = SolidEntryBuilder::new(
+        WriteOptions::builder()
+            .compression(pna::Compression::ZStandard)
+            .build(),
+    )
+    .unwrap();
+    solid
+        .add_entry(new_entry(
+            "solid/a.txt",
+            DataKind::File,
+            WriteOptions::store(),
+        ))
+        .unwrap();
+    solid
+        .add_entry(new_entry(
+            "solid/b.txt",
+            DataKind::File,
+            WriteOptions::store(),
+        ))
+        .unwrap();
+    writer.add_entry(solid.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+}
+
+/// What the commands must keep, unless they are meant to change it.
+#[derive(Clone, PartialEq, Debug)]
+struct EntrySnapshot {
+    path: String,
+    data_kind: DataKind,
+    created: Option<Duration>,
+    modified: Option<Duration>,
+    accessed: Option<Duration>,
+    permission: Option<Permission>,
+    xattrs: Vec<ExtendedAttribute>,
+    extra_chunks: Vec<(ChunkType, Vec<u8>)>,
+    content: Vec<u8>,
+}
+
+impl EntrySnapshot {
+    fn new(entry: &NormalEntry) -> Self {
+        let mut content = Vec::new();
+        entry
+            .reader(ReadOptions::builder().build())
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        Self {
+            path: entry.header().path().to_string(),
+            data_kind: entry.header().data_kind(),
+            created: entry.metadata().created(),
+            modified: entry.metadata().modified(),
+            accessed: entry.metadata().accessed(),
+            permission: entry.metadata().permission().cloned(),
+            xattrs: entry.xattrs().to_vec(),
+            extra_chunks: entry
+                .extra_chunks()
+                .iter()
+                .map(|it| (it.ty(), it.data().to_vec()))
+                .collect(),
+            content,
+        }
+    }
+}
+
+fn snapshots(path: &Path) -> Vec<EntrySnapshot> {
+    let mut archive = Archive::read_header(fs::File::open(path).unwrap()).unwrap();
+    let mut snapshots = Vec::new();
+    for entry in archive.entries() {
+        match entry.unwrap() {
+            ReadEntry::Solid(solid) => {
+                for entry in solid.entries(None).unwrap() {
+                    snapshots.push(EntrySnapshot::new(&entry.unwrap()));
+                }
+            }
+            ReadEntry::Normal(entry) => snapshots.push(EntrySnapshot::new(&entry)),
+        }
+    }
+    snapshots
+}
+
+fn run(args: &[&str]) {
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet"].iter().chain(args).copied(),
+    ))
+    .unwrap();
+}
+
+/// Runs `transform` that reads the fixture archive at the first path and writes the archive at the second path,
+/// and asserts that the entries are kept except for the changes applied by `expected`.
+fn assert_preserved(
+    name: &str,
+    transform: impl FnOnce(&str, &str),
+    expected: impl FnMut(&mut EntrySnapshot),
+) {
+    setup();
+    let base = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("transform_conformance")
+        .join(name);
+    let _ = fs::remove_dir_all(&base);
+    let source = base.join("source.pna");
+    let output = base.join("output.pna");
+    init_fixture(&source);
+    let mut expected_snapshots = snapshots(&source);
+    expected_snapshots.iter_mut().for_each(expected);
+
+    transform(source.to_str().unwrap(), output.to_str().unwrap());
+
+    let actual = snapshots(&output);
+    assert_eq!(actual, expected_snapshots);
+    assert!(actual
+        .iter()
+        .all(|it| it.extra_chunks.iter().any(|(ty, _)| *ty == provenance())));
+}
+
+/// Runs the command given by `args` on a copy of the fixture archive, which is replaced by the command.
+fn in_place<'a>(args: &'a [&'a str]) -> impl FnOnce(&str, &str) + 'a {
+    move |source, output| {
+        fs::copy(source, output).unwrap();
+        run(&[args, &[output][..]].concat());
+    }
+}
+
+const ALL_ENTRIES: [&str; 5] = [
+    "dir",
+    "dir/compressed.txt",
+    "dir/stored.txt",
+    "solid/a.txt",
+    "solid/b.txt",
+];
+
+fn with_mode(snapshot: &mut EntrySnapshot, mode: u16) {
+    let p = snapshot.permission.as_ref().unwrap();
+    snapshot.permission = Some(Permission::new(
+        p.uid(),
+        p.uname().into(),
+        p.gid(),
+        p.gname().into(),
+        mode,
+    ));
+}
+
+#[test]
+fn chmod_preserves_entries() {
+    assert_preserved(
+        "chmod",
+        |source, output| {
+            fs::copy(source, output).unwrap();
+            run(&[&["experimental", "chmod", output, "700"][..], &ALL_ENTRIES].concat());
+        },
+        |it| with_mode(it, 0o700),
+    );
+}
+
+#[test]
+fn chmod_unsolid_preserves_entries() {
+    assert_preserved(
+        "chmod_unsolid",
+        |source, output| {
+            fs::copy(source, output).unwrap();
+            run(&[
+                &["experimental", "chmod", output, "--unsolid", "600"][..],
+                &ALL_ENTRIES,
+            ]
+            .concat());
+        },
+        |it| with_mode(it, 0o600),
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn chown_preserves_entries() {
+    assert_preserved(
+        "chown",
+        |source, output| {
+            fs::copy(source, output).unwrap();
+            run(&[&["experimental", "chown", output, "root"][..], &ALL_ENTRIES].concat());
+        },
+        |it| {
+            let p = it.permission.as_ref().unwrap();
+            it.permission = Some(Permission::new(
+                0,
+                "root".into(),
+                p.gid(),
+                p.gname().into(),
+                p.permissions(),
+            ));
+        },
+    );
+}
+
+#[test]
+fn update_without_files_preserves_entries() {
+    assert_preserved("update", in_place(&["experimental", "update"]), |_| ());
+}
+
+#[test]
+fn migrate_preserves_entries() {
+    assert_preserved(
+        "migrate",
+        |source, output| run(&["experimental", "migrate", source, "--output", output]),
+        |_| (),
+    );
+}
+
+#[test]
+fn concat_preserves_entries() {
+    assert_preserved(
+        "concat",
+        |source, output| run(&["concat", output, source]),
+        |_| (),
+    );
+}
+
+#[test]
+fn split_and_concat_preserves_entries() {
+    assert_preserved(
+        "split",
+        |source, output| {
+            let parts = Path::new(output).with_file_name("parts");
+            let parts = parts.to_str().unwrap();
+            run(&["split", source, "--out-dir", parts, "--max-size", "1kb"]);
+            let first = format!("{parts}/source.part1.pna");
+            assert!(Path::new(&format!("{parts}/source.part2.pna")).exists());
+            run(&["concat", output, &first]);
+        },
+        |_| (),
+    );
+}
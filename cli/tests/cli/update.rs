@@ -1,6 +1,7 @@
 use crate::utils::setup;
 use clap::Parser;
 use portable_network_archive::{cli, command};
+use std::{collections::BTreeSet, fs, path::Path};
 
 #[test]
 fn archive_update_newer_mtime() {
@@ -63,3 +64,71 @@ fn archive_update_newer_mtime() {
     ]))
     .unwrap();
 }
+
+/// Names of the entries in `archive`, relative to the directory `update_exclude/src`.
+fn entry_names(archive: &Path) -> BTreeSet<String> {
+    let mut archive = pna::Archive::read_header(fs::File::open(archive).unwrap()).unwrap();
+    archive
+        .entries_skip_solid()
+        .map(|entry| {
+            let name = entry.unwrap().header().path().to_string();
+            let (_, name) = name.split_once("update_exclude/src/").unwrap();
+            name.to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn archive_update_gitignore_and_exclude_from() {
+    setup();
+    let base = Path::new(env!("CARGO_TARGET_TMPDIR")).join("update_exclude");
+    let root = base.join("src");
+    let archive = base.join("update_exclude.pna");
+    let exclude_from = base.join("exclude.txt");
+    if base.exists() {
+        fs::remove_dir_all(&base).unwrap();
+    }
+    fs::create_dir_all(root.join("keep")).unwrap();
+    fs::write(root.join("a.txt"), "a").unwrap();
+    fs::write(root.join("keep/k.txt"), "k").unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        archive.to_str().unwrap(),
+        "--overwrite",
+        "-r",
+        root.to_str().unwrap(),
+    ]))
+    .unwrap();
+
+    fs::create_dir_all(root.join("build")).unwrap();
+    fs::write(root.join("build/out.bin"), "out").unwrap();
+    fs::write(root.join("new.txt"), "new").unwrap();
+    fs::write(root.join("new.log"), "log").unwrap();
+    fs::write(root.join("cache.tmp"), "tmp").unwrap();
+    fs::write(root.join(".gitignore"), "*.tmp\n").unwrap();
+    fs::write(&exclude_from, "*.log\n\n**/build\n").unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "update",
+        archive.to_str().unwrap(),
+        "-r",
+        root.to_str().unwrap(),
+        "--gitignore",
+        "--exclude-from",
+        exclude_from.to_str().unwrap(),
+        "--unstable",
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        entry_names(&archive),
+        [".gitignore", "a.txt", "keep/k.txt", "new.txt"]
+            .into_iter()
+            .map(String::from)
+            .collect::<BTreeSet<_>>()
+    );
+}
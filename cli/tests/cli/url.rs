@@ -0,0 +1,149 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::fs;
+
+fn init_resource(archive: &str) {
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        archive,
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+    ]))
+    .unwrap();
+}
+
+fn list(archive: &str, options: &[&str]) -> assert_cmd::assert::Assert {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["--quiet", "list", archive]).args(options);
+    cmd.assert()
+}
+
+/// Returns the relative paths and the contents of the files under `dir`.
+#[cfg(feature = "http")]
+fn read_tree(dir: &str) -> Vec<(String, Vec<u8>)> {
+    let mut files = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .map(Result::unwrap)
+        .filter(|it| it.file_type().is_file())
+        .map(|it| {
+            let path = it.path().strip_prefix(dir).unwrap();
+            (path.display().to_string(), fs::read(it.path()).unwrap())
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+/// Serves `archive` at `/archive.pna`, and at `/private.pna` only for a request with `Authorization: Bearer token`.
+#[cfg(feature = "http")]
+fn serve(archive: &str) -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let port = server.server_addr().to_ip().unwrap().port();
+    let archive = archive.to_string();
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let authorized = request
+                .headers()
+                .iter()
+                .any(|it| it.field.equiv("Authorization") && it.value.as_str() == "Bearer token");
+            let found = match request.url() {
+                "/archive.pna" => true,
+                "/private.pna" => authorized,
+                _ => false,
+            };
+            let _ = if found {
+                request.respond(tiny_http::Response::from_file(
+                    fs::File::open(&archive).unwrap(),
+                ))
+            } else {
+                request.respond(tiny_http::Response::empty(404))
+            };
+        }
+    });
+    format!("http://127.0.0.1:{port}")
+}
+
+#[cfg(unix)]
+#[test]
+fn url_list_file_url() {
+    setup();
+    let archive = format!("{}/url/file_url.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    let url = format!("file://{}", fs::canonicalize(&archive).unwrap().display());
+
+    let expected = list(&archive, &[]).success().get_output().stdout.clone();
+    let actual = list(&url, &[]).success().get_output().stdout.clone();
+    assert_eq!(actual, expected);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn url_list_http() {
+    setup();
+    let archive = format!("{}/url/list_http.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    let base = serve(&archive);
+
+    let expected = list(&archive, &["-l"])
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let actual = list(&format!("{base}/archive.pna"), &["-l"])
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    assert_eq!(actual, expected);
+    list(&format!("{base}/missing.pna"), &[]).failure();
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn url_list_http_header() {
+    setup();
+    let archive = format!("{}/url/http_header.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    let base = serve(&archive);
+    let url = format!("{base}/private.pna");
+
+    list(&url, &[]).failure();
+    list(&url, &["--http-header", "Authorization: Bearer token"]).success();
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn url_extract_http() {
+    setup();
+    let dir = format!("{}/url/extract_http", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{dir}.pna");
+    init_resource(&archive);
+    let base = serve(&archive);
+
+    for (source, out_dir) in [
+        (archive.clone(), format!("{dir}/file")),
+        (format!("{base}/archive.pna"), format!("{dir}/http")),
+    ] {
+        if std::path::Path::new(&out_dir).exists() {
+            fs::remove_dir_all(&out_dir).unwrap();
+        }
+        command::entry(cli::Cli::parse_from([
+            "pna",
+            "--quiet",
+            "x",
+            &source,
+            "--overwrite",
+            "--out-dir",
+            &out_dir,
+        ]))
+        .unwrap();
+    }
+    let expected = read_tree(&format!("{dir}/file"));
+    assert!(!expected.is_empty());
+    assert_eq!(read_tree(&format!("{dir}/http")), expected);
+}
@@ -1,6 +1,20 @@
 pub mod diff;
 
+use clap::Parser;
+use portable_network_archive::{cli, command};
+use std::io;
+
 pub fn setup() {
     #[cfg(target_os = "wasi")]
     std::env::set_current_dir(env!("CARGO_MANIFEST_DIR")).expect("Failed to set current dir");
 }
+
+/// Creates the archive at `path` of `files` with `pna c --overwrite` and `options`.
+pub fn create_archive(path: &str, files: &[&str], options: &[&str]) -> io::Result<()> {
+    command::entry(cli::Cli::parse_from(
+        ["pna", "--quiet", "c", path, "--overwrite"]
+            .into_iter()
+            .chain(options.iter().copied())
+            .chain(files.iter().copied()),
+    ))
+}
@@ -1,6 +1,6 @@
 use std::collections::HashSet;
 use std::fs;
-use std::io::{self, Read};
+use std::io;
 use std::path::{Path, PathBuf};
 
 pub fn diff<P1: AsRef<Path>, P2: AsRef<Path>>(dir1: P1, dir2: P2) -> io::Result<()> {
@@ -73,14 +73,5 @@ fn read_dir_recursively(dir: &Path) -> io::Result<std::collections::HashMap<Path
 }
 
 fn compare_files(file1: &Path, file2: &Path) -> io::Result<bool> {
-    let mut f1 = fs::File::open(file1)?;
-    let mut f2 = fs::File::open(file2)?;
-
-    let mut buffer1 = Vec::new();
-    let mut buffer2 = Vec::new();
-
-    f1.read_to_end(&mut buffer1)?;
-    f2.read_to_end(&mut buffer2)?;
-
-    Ok(buffer1 == buffer2)
+    pna::compare_readers(fs::File::open(file1)?, fs::File::open(file2)?)
 }
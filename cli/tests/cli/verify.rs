@@ -0,0 +1,144 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::setup;
+use pna::{Archive, EntryBuilder, WriteOptions};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{fs, io::Write, path::Path};
+
+fn init_resource(path: &str) {
+    fs::create_dir_all(Path::new(path).parent().unwrap()).unwrap();
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    writer
+        .add_entry(EntryBuilder::new_dir("dir".into()).build().unwrap())
+        .unwrap();
+    for name in ["dir/a.txt", "dir/b.txt"] {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(name.repeat(10).as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn verify_jsonl(archive: &str) -> (bool, Vec<Value>) {
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["experimental", "verify", "--format", "jsonl", archive]);
+    let output = cmd.output().unwrap();
+    let statuses = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    (output.status.success(), statuses)
+}
+
+#[test]
+fn verify_intact() {
+    setup();
+    let archive = format!("{}/verify/intact/archive.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+
+    let (success, statuses) = verify_jsonl(&archive);
+    assert!(success);
+    assert_eq!(statuses.len(), 3);
+    assert!(statuses.iter().all(|it| it["ok"] == true));
+    assert_eq!(statuses[0]["path"], "dir");
+    assert_eq!(statuses[0]["size"], Value::Null);
+    assert_eq!(statuses[1]["path"], "dir/a.txt");
+    assert_eq!(statuses[1]["size"], 90);
+}
+
+#[test]
+fn verify_broken_data() {
+    setup();
+    let archive = format!("{}/verify/broken/archive.pna", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(&archive);
+    let mut bytes = fs::read(&archive).unwrap();
+    let offset = bytes
+        .windows(18)
+        .position(|it| it == b"dir/b.txtdir/b.txt")
+        .unwrap();
+    bytes[offset] ^= 0xff;
+    fs::write(&archive, bytes).unwrap();
+
+    let (success, statuses) = verify_jsonl(&archive);
+    assert!(!success);
+    // The broken chunk stops reading the archive.
+    assert_eq!(statuses.len(), 2);
+}
+
+#[test]
+fn verify_solid_and_encrypted() {
+    setup();
+    let archive = format!("{}/verify/solid.pna", env!("CARGO_TARGET_TMPDIR"));
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "--solid",
+        "--aes",
+        "ctr",
+        "--password",
+        "password",
+        "-r",
+        "../resources/test/raw",
+        #[cfg(windows)]
+        "--unstable",
+    ]);
+    cmd.assert().success();
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["experimental", "verify", &archive, "--password", "password"]);
+    let assert = cmd.assert().success();
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.lines().all(|line| line.starts_with("OK")));
+    assert!(stdout.contains("raw/text.txt"));
+
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args(["experimental", "verify", &archive, "--password", "wrong"]);
+    cmd.assert().failure();
+}
+
+#[test]
+fn verify_split_archive() {
+    setup();
+    let (success, statuses) = verify_jsonl("../resources/test/multipart.part1.pna");
+    assert!(success);
+    assert_eq!(statuses.len(), 1);
+}
+
+#[test]
+fn verify_part_digests() {
+    setup();
+    let mut cmd = assert_cmd::Command::cargo_bin("pna").unwrap();
+    cmd.args([
+        "experimental",
+        "verify",
+        "--format",
+        "jsonl",
+        "--part-digests",
+        "../resources/test/multipart.part1.pna",
+    ]);
+    let output = cmd.output().unwrap();
+    assert!(output.status.success());
+    let parts = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .filter(|it| it.get("sha256").is_some())
+        .collect::<Vec<_>>();
+    assert_eq!(parts.len(), 2);
+    for (i, part) in parts.iter().enumerate() {
+        let path = format!("../resources/test/multipart.part{}.pna", i + 1);
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(part["part"], i + 1);
+        assert_eq!(part["path"], path);
+        assert_eq!(part["size"], bytes.len());
+        let digest = Sha256::digest(&bytes)
+            .iter()
+            .map(|it| format!("{it:02x}"))
+            .collect::<String>();
+        assert_eq!(part["sha256"], digest);
+    }
+}
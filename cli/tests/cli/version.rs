@@ -0,0 +1,62 @@
+use crate::utils::setup;
+
+fn version_json() -> serde_json::Value {
+    let assert = assert_cmd::Command::cargo_bin("pna")
+        .unwrap()
+        .args(["version", "--format", "json"])
+        .assert()
+        .success();
+    serde_json::from_slice(&assert.get_output().stdout).unwrap()
+}
+
+#[test]
+fn version_json_has_required_keys() {
+    setup();
+    let json = version_json();
+
+    assert_eq!(json["schema_version"], 1);
+    for key in ["cli", "pna", "libpna"] {
+        assert!(json["versions"][key].is_string(), "versions.{key}");
+    }
+    assert_eq!(json["versions"]["cli"], env!("CARGO_PKG_VERSION"));
+    for key in ["archive", "entry"] {
+        let versions = json["format"][key].as_array().unwrap();
+        assert!(versions.contains(&"0.0".into()), "format.{key}");
+    }
+    for key in ["acl", "memmap", "http", "zlib-ng"] {
+        assert!(json["features"][key].is_boolean(), "features.{key}");
+    }
+    for key in [
+        "compression",
+        "encryption",
+        "cipher_mode",
+        "password_hash",
+        "key_wrap",
+        "signature",
+    ] {
+        assert!(json["algorithms"][key]["read"].is_array(), "{key}.read");
+        assert!(json["algorithms"][key]["write"].is_array(), "{key}.write");
+    }
+}
+
+#[test]
+fn version_json_chunk_types() {
+    setup();
+    let json = version_json();
+
+    let chunk_types = json["chunk_types"].as_array().unwrap();
+    for ty in [
+        "AHED", "AEND", "FHED", "FDAT", "FEND", "SHED", "SDAT", "SEND",
+    ] {
+        let chunk_type = chunk_types
+            .iter()
+            .find(|it| it["type"] == ty)
+            .unwrap_or_else(|| panic!("{ty} is not listed"));
+        assert_eq!(chunk_type["critical"], true, "{ty}");
+        assert_eq!(chunk_type["private"], false, "{ty}");
+        assert_eq!(chunk_type["read"], true, "{ty}");
+        assert_eq!(chunk_type["write"], true, "{ty}");
+    }
+    let fsiz = chunk_types.iter().find(|it| it["type"] == "fSIZ").unwrap();
+    assert_eq!(fsiz["critical"], false);
+}
@@ -0,0 +1,138 @@
+use crate::utils::setup;
+use clap::Parser;
+use pna::{Archive, EntryBuilder, EntryName, WriteOptions};
+use portable_network_archive::{cli, command};
+use std::{fs, io::Write, path::Path};
+
+fn long_path() -> String {
+    ["long_directory_name"; 16].join("/") + "/file.txt"
+}
+
+fn init_resource(path: &str) {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    let mut writer = Archive::write_header(fs::File::create(path).unwrap()).unwrap();
+    for name in [
+        "con.txt",
+        "aux/file.txt",
+        "normal.txt",
+        long_path().as_str(),
+    ] {
+        let mut builder =
+            EntryBuilder::new_file(EntryName::from(name), WriteOptions::store()).unwrap();
+        builder.write_all(name.as_bytes()).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+fn extract(archive: &str, out_dir: &str, options: &[&str]) {
+    let _ = fs::remove_dir_all(out_dir);
+    command::entry(cli::Cli::parse_from(
+        [
+            "pna",
+            "--quiet",
+            "x",
+            archive,
+            "--overwrite",
+            "--out-dir",
+            out_dir,
+        ]
+        .into_iter()
+        .chain(options.iter().copied()),
+    ))
+    .unwrap();
+}
+
+#[test]
+fn windows_compat_long_path() {
+    setup();
+    let base = format!("{}/windows_compat/long_path", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}.pna");
+    init_resource(&archive);
+    extract(&archive, &base, &["--windows-compat", "rename"]);
+
+    let name = long_path();
+    assert!(name.len() > 300);
+    let path = fs::canonicalize(&base).unwrap().join(&name);
+    assert_eq!(fs::read_to_string(path).unwrap(), name);
+}
+
+#[test]
+fn windows_compat_rename() {
+    setup();
+    let base = format!("{}/windows_compat/rename", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}.pna");
+    init_resource(&archive);
+    extract(&archive, &base, &["--windows-compat", "rename"]);
+
+    let out_dir = fs::canonicalize(&base).unwrap();
+    assert_eq!(
+        fs::read_to_string(out_dir.join("con_.txt")).unwrap(),
+        "con.txt"
+    );
+    assert_eq!(
+        fs::read_to_string(out_dir.join("aux_/file.txt")).unwrap(),
+        "aux/file.txt"
+    );
+    assert!(out_dir.join("normal.txt").exists());
+}
+
+#[test]
+fn windows_compat_rename_with_suffix() {
+    setup();
+    let base = format!("{}/windows_compat/suffix", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}.pna");
+    init_resource(&archive);
+    extract(
+        &archive,
+        &base,
+        &[
+            "--windows-compat",
+            "rename",
+            "--windows-compat-suffix",
+            "-reserved",
+        ],
+    );
+
+    let out_dir = fs::canonicalize(&base).unwrap();
+    assert!(out_dir.join("con-reserved.txt").exists());
+    assert!(out_dir.join("aux-reserved/file.txt").exists());
+}
+
+#[test]
+fn windows_compat_skip() {
+    setup();
+    let base = format!("{}/windows_compat/skip", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}.pna");
+    init_resource(&archive);
+    extract(&archive, &base, &["--windows-compat", "skip"]);
+
+    let out_dir = fs::canonicalize(&base).unwrap();
+    assert!(!out_dir.join("con.txt").exists());
+    assert!(!out_dir.join("aux").exists());
+    assert!(out_dir.join("normal.txt").exists());
+    assert!(out_dir.join(long_path()).exists());
+}
+
+#[test]
+fn windows_compat_allow() {
+    setup();
+    let base = format!("{}/windows_compat/allow", env!("CARGO_TARGET_TMPDIR"));
+    let archive = format!("{base}.pna");
+    init_resource(&archive);
+    extract(&archive, &base, &["--windows-compat", "allow"]);
+
+    // NOTE: On Windows, reserved names are accessible only with the extended-length prefix,
+    //       which the canonical path has.
+    let out_dir = fs::canonicalize(&base).unwrap();
+    assert_eq!(
+        fs::read_to_string(out_dir.join("con.txt")).unwrap(),
+        "con.txt"
+    );
+    assert_eq!(
+        fs::read_to_string(out_dir.join("aux/file.txt")).unwrap(),
+        "aux/file.txt"
+    );
+}
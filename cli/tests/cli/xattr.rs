@@ -1,6 +1,7 @@
 use crate::utils::setup;
 use clap::Parser;
 use portable_network_archive::{cli, command};
+use std::fs;
 
 #[test]
 fn archive_xattr_set() {
@@ -123,3 +124,200 @@ fn archive_xattr_remove() {
     ]))
     .unwrap();
 }
+
+#[test]
+fn extract_xattr_filter() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!("{}/xattr_filter.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "xattr",
+        "set",
+        &format!("{}/xattr_filter.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--name",
+        "user.large",
+        "--value",
+        &"a".repeat(1024),
+        "resources/test/raw/empty.txt",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "xattr",
+        "set",
+        &format!("{}/xattr_filter.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--name",
+        "unsupported.name",
+        "--value",
+        "pna developers!",
+        "resources/test/raw/empty.txt",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &format!("{}/xattr_filter.pna", env!("CARGO_TARGET_TMPDIR")),
+        "--overwrite",
+        "--out-dir",
+        &format!("{}/xattr_filter/", env!("CARGO_TARGET_TMPDIR")),
+        "--keep-xattr",
+        "--xattr-max-size",
+        "16",
+        "--xattr-namespace",
+        "user",
+        "--unstable",
+    ]))
+    .unwrap();
+}
+
+#[test]
+fn extract_xattr_unsupported_namespace() {
+    setup();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &format!(
+            "{}/xattr_unsupported_namespace.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--overwrite",
+        "-r",
+        "../resources/test/raw",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "xattr",
+        "set",
+        &format!(
+            "{}/xattr_unsupported_namespace.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--name",
+        "unsupported.name",
+        "--value",
+        "pna developers!",
+        "resources/test/raw/empty.txt",
+    ]))
+    .unwrap();
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        &format!(
+            "{}/xattr_unsupported_namespace.pna",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--overwrite",
+        "--out-dir",
+        &format!(
+            "{}/xattr_unsupported_namespace/",
+            env!("CARGO_TARGET_TMPDIR")
+        ),
+        "--keep-xattr",
+    ]))
+    .unwrap();
+}
+
+#[test]
+fn migrate_decompress_xattr() {
+    setup();
+    let archive = format!("{}/xattr_compressed.pna", env!("CARGO_TARGET_TMPDIR"));
+    let migrated = format!("{}/xattr_decompressed.pna", env!("CARGO_TARGET_TMPDIR"));
+    let value = vec![b'a'; 4 * 1024 * 1024];
+    let mut builder =
+        pna::EntryBuilder::new_file("file.txt".into(), pna::WriteOptions::store()).unwrap();
+    builder.add_xattr(
+        pna::ExtendedAttribute::new("user.fork".into(), value.clone()).with_compression(true),
+    );
+    let mut writer = pna::Archive::write_header(fs::File::create(&archive).unwrap()).unwrap();
+    writer.add_entry(builder.build().unwrap()).unwrap();
+    writer.finalize().unwrap();
+
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "experimental",
+        "migrate",
+        &archive,
+        "--output",
+        &migrated,
+        "--decompress-xattr",
+    ]))
+    .unwrap();
+
+    assert!(fs::metadata(&archive).unwrap().len() < fs::metadata(&migrated).unwrap().len());
+    let mut reader = pna::Archive::read_header(fs::File::open(&migrated).unwrap()).unwrap();
+    let entry = reader.entries_skip_solid().next().unwrap().unwrap();
+    let xattr = &entry.xattrs()[0];
+    assert!(!xattr.is_compressed());
+    assert_eq!(xattr.name(), "user.fork");
+    assert_eq!(xattr.value(), value);
+}
+
+#[cfg(all(unix, not(target_os = "netbsd")))]
+#[test]
+fn archive_xattr_reproducible() {
+    setup();
+    let dir = format!("{}/xattr_reproducible", env!("CARGO_TARGET_TMPDIR"));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(format!("{dir}/in")).unwrap();
+    for file in ["a.txt", "b.txt"] {
+        let path = format!("{dir}/in/{file}");
+        fs::write(&path, file).unwrap();
+        for name in ["user.c", "user.a", "user.b"] {
+            xattr::set(&path, name, name.as_bytes()).unwrap();
+        }
+    }
+    let archives = ["1.pna", "2.pna"].map(|name| {
+        let archive = format!("{dir}/{name}");
+        command::entry(cli::Cli::parse_from([
+            "pna",
+            "--quiet",
+            "c",
+            &archive,
+            "--overwrite",
+            "-r",
+            &format!("{dir}/in"),
+            "--keep-xattr",
+        ]))
+        .unwrap();
+        let mut reader = pna::Archive::read_header(fs::File::open(&archive).unwrap()).unwrap();
+        let mut entries = reader
+            .entries_skip_solid()
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap();
+        entries.sort_by(|a, b| a.header().path().cmp(b.header().path()));
+        entries
+    });
+
+    // NOTE: The archive ids differ between the runs, so compare the entries.
+    assert_eq!(archives[0], archives[1]);
+    for entry in &archives[0] {
+        if entry.header().data_kind() == pna::DataKind::File {
+            let names = entry
+                .xattrs()
+                .iter()
+                .map(|it| it.name())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["user.a", "user.b", "user.c"]);
+        }
+    }
+}
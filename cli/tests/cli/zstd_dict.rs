@@ -0,0 +1,123 @@
+#![cfg(not(target_family = "wasm"))]
+use crate::utils::{diff::diff, setup};
+use clap::Parser;
+use pna::Archive;
+use portable_network_archive::{cli, command};
+use std::{fs, path::Path};
+
+fn init_resource<P: AsRef<Path>>(dir: P) -> Vec<Vec<u8>> {
+    let dir = dir.as_ref();
+    if dir.exists() {
+        fs::remove_dir_all(dir).unwrap();
+    }
+    fs::create_dir_all(dir).unwrap();
+    (0..128)
+        .map(|i| {
+            let contents = format!(
+                r#"{{"id":{i},"level":"info","message":"request {} handled in {}ms"}}"#,
+                i * 31,
+                i % 17
+            )
+            .into_bytes();
+            fs::write(dir.join(format!("{i}.json")), &contents).unwrap();
+            contents
+        })
+        .collect()
+}
+
+/// Extracts `archive` into `{base}/out` and compares it with `{base}/in`.
+fn extract_and_diff(archive: &str, base: &str) {
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "x",
+        archive,
+        "--overwrite",
+        "--out-dir",
+        &format!("{base}/out"),
+    ]))
+    .unwrap();
+    diff(
+        format!("{base}/in"),
+        format!("{base}/out/{}/in", base.trim_start_matches('/')),
+    )
+    .unwrap();
+}
+
+#[test]
+fn create_with_zstd_dict() {
+    setup();
+    let base = format!("{}/zstd_dict/zstd_dict", env!("CARGO_TARGET_TMPDIR"));
+    let samples = init_resource(format!("{base}/in"));
+    let dictionary = pna::train_zstd_dictionary(&samples, 4 * 1024).unwrap();
+    fs::write(format!("{base}/dictionary"), &dictionary).unwrap();
+    let archive = format!("{base}/zstd_dict.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "-r",
+        &format!("{base}/in"),
+        "--zstd-dict",
+        &format!("{base}/dictionary"),
+        "--unstable",
+    ]))
+    .unwrap();
+
+    let mut reader = Archive::read_header(fs::File::open(&archive).unwrap()).unwrap();
+    assert_eq!(reader.entries_skip_solid().count(), 128);
+    assert_eq!(reader.zstd_dictionary(), Some(dictionary.as_slice()));
+
+    extract_and_diff(&archive, &base);
+}
+
+#[test]
+fn create_with_train_dict() {
+    setup();
+    let base = format!("{}/zstd_dict/train_dict", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(format!("{base}/in"));
+    let archive = format!("{base}/train_dict.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "-r",
+        &format!("{base}/in"),
+        "--train-dict",
+        "--unstable",
+    ]))
+    .unwrap();
+
+    let mut reader = Archive::read_header(fs::File::open(&archive).unwrap()).unwrap();
+    assert_eq!(reader.entries_skip_solid().count(), 128);
+    assert!(reader.zstd_dictionary().is_some());
+
+    extract_and_diff(&archive, &base);
+}
+
+#[test]
+fn create_with_zstd_dict_solid() {
+    setup();
+    let base = format!("{}/zstd_dict/solid", env!("CARGO_TARGET_TMPDIR"));
+    init_resource(format!("{base}/in"));
+    let archive = format!("{base}/solid.pna");
+    command::entry(cli::Cli::parse_from([
+        "pna",
+        "--quiet",
+        "c",
+        &archive,
+        "--overwrite",
+        "--solid",
+        "-r",
+        &format!("{base}/in"),
+        "--train-dict",
+        "--unstable",
+    ]))
+    .unwrap();
+
+    extract_and_diff(&archive, &base);
+}
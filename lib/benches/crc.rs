@@ -0,0 +1,51 @@
+#![feature(test)]
+extern crate test;
+
+use libpna::{prelude::*, ChunkType};
+use test::Bencher;
+
+/// Size of the data of a chunk measured by a single iteration.
+const DATA_SIZE: usize = 1024 * 1024;
+
+fn data() -> Vec<u8> {
+    (0..DATA_SIZE).map(|i| (i % 251) as u8).collect()
+}
+
+/// Byte-wise table-based CRC32, as a baseline for the accelerated implementation.
+fn table_crc(ty: &[u8; 4], data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 {
+                (c >> 1) ^ 0xEDB8_8320
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    let mut crc = !0u32;
+    for byte in ty.iter().chain(data) {
+        crc = table[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+#[bench]
+fn chunk_crc(b: &mut Bencher) {
+    let data = data();
+    b.bytes = DATA_SIZE as u64;
+    b.iter(|| test::black_box((ChunkType::FDAT, &data[..]).crc()))
+}
+
+#[bench]
+fn chunk_crc_table(b: &mut Bencher) {
+    let data = data();
+    assert_eq!(
+        table_crc(b"FDAT", &data),
+        (ChunkType::FDAT, &data[..]).crc()
+    );
+    b.bytes = DATA_SIZE as u64;
+    b.iter(|| test::black_box(table_crc(b"FDAT", &data)))
+}
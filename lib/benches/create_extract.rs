@@ -2,8 +2,8 @@
 extern crate test;
 
 use libpna::{
-    Archive, CipherMode, Compression, Encryption, EntryBuilder, ReadEntry, ReadOptions,
-    WriteOptions, WriteOptionsBuilder,
+    Archive, CipherMode, Compression, Encryption, EntryBuilder, HashAlgorithm, ReadEntry,
+    ReadOptions, WriteOptions, WriteOptionsBuilder,
 };
 use std::io::{self, prelude::*};
 use test::Bencher;
@@ -296,3 +296,43 @@ fn read_empty_archive(b: &mut Bencher) {
         }
     })
 }
+
+fn bench_read_many_encrypted_entries(b: &mut Bencher, reuse_salt: bool) {
+    let options = WriteOptions::builder()
+        .encryption(Encryption::Aes)
+        .cipher_mode(CipherMode::CTR)
+        .hash_algorithm(HashAlgorithm::pbkdf2_sha256_with(Some(1000)))
+        .password(Some("password"))
+        .reuse_salt(reuse_salt)
+        .build();
+    let mut writer = Archive::write_header(Vec::new()).unwrap();
+    for i in 0..1000 {
+        let mut builder = EntryBuilder::new_file(format!("{i}").into(), &options).unwrap();
+        builder.write_all(&[24; 16]).unwrap();
+        writer.add_entry(builder.build().unwrap()).unwrap();
+    }
+    let vec = writer.finalize().unwrap();
+
+    b.iter(|| {
+        let read_options = ReadOptions::with_password(Some("password"));
+        let mut reader = Archive::read_header(vec.as_slice()).unwrap();
+        for item in reader.entries_skip_solid() {
+            let mut buf = Vec::with_capacity(16);
+            item.unwrap()
+                .reader(&read_options)
+                .unwrap()
+                .read_to_end(&mut buf)
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn read_many_aes_entries(b: &mut Bencher) {
+    bench_read_many_encrypted_entries(b, false);
+}
+
+#[bench]
+fn read_many_aes_entries_reuse_salt(b: &mut Bencher) {
+    bench_read_many_encrypted_entries(b, true);
+}
@@ -0,0 +1,42 @@
+#![feature(test)]
+extern crate test;
+
+use libpna::{Compression, EntryBuilder, NormalEntry, SolidEntryBuilder, WriteOptions};
+use std::io::prelude::*;
+use test::Bencher;
+
+fn entries() -> Vec<NormalEntry> {
+    (0..1000)
+        .map(|i| {
+            let mut builder =
+                EntryBuilder::new_file(format!("dir/{i}.txt").into(), WriteOptions::store())
+                    .unwrap();
+            builder.write_all(&[(i % 256) as u8; 111]).unwrap();
+            builder.build().unwrap()
+        })
+        .collect()
+}
+
+fn options() -> WriteOptions {
+    WriteOptions::builder()
+        .compression(Compression::ZStandard)
+        .build()
+}
+
+#[bench]
+fn resolid_incremental(b: &mut Bencher) {
+    let entries = entries();
+    b.iter(|| {
+        let mut builder = SolidEntryBuilder::new(options()).unwrap();
+        for entry in entries.iter().cloned() {
+            builder.add_entry(entry).unwrap();
+        }
+        builder.build().unwrap()
+    })
+}
+
+#[bench]
+fn resolid_from_entries(b: &mut Bencher) {
+    let entries = entries();
+    b.iter(|| SolidEntryBuilder::from_entries(entries.iter().cloned(), options()).unwrap())
+}
@@ -1,15 +1,27 @@
 mod header;
+mod indexed;
+mod merge;
 mod read;
+mod salvage;
+mod scan;
+mod summary;
 mod write;
 
 use crate::{
     chunk::{ChunkStreamWriter, RawChunk},
     cipher::CipherWriter,
     compress::CompressionWriter,
+    entry::PackedWriter,
 };
 pub use header::*;
+pub use indexed::*;
+pub use merge::*;
 pub(crate) use read::*;
-use std::io::prelude::*;
+pub use salvage::*;
+pub use scan::*;
+use std::{io::prelude::*, sync::Arc};
+pub use summary::*;
+pub use write::EntryWriter;
 
 /// An object providing access to a PNA file.
 /// An instance of an [Archive] can be read and/or written.
@@ -55,25 +67,89 @@ use std::io::prelude::*;
 pub struct Archive<T> {
     inner: T,
     header: ArchiveHeader,
+    archive_id: Option<ArchiveId>,
+    // zstd dictionary read from or written to the archive
+    zstd_dictionary: Option<Arc<[u8]>>,
+    // application-defined archive-level chunks read from or written to the archive
+    extra_chunks: Vec<RawChunk>,
+    // number of bytes written by this archive, only used in writer mode
+    bytes_written: u64,
     // following fields are only use in reader mode
     next_archive: bool,
     buf: Vec<RawChunk>,
+    // sizes of the data chunks of the entry in `buf` that were skipped by [Archive::entries_metadata]
+    skipped_chunk_sizes: Vec<usize>,
+    pending: Option<RawChunk>,
 }
 
 impl<T> Archive<T> {
-    const fn new(inner: T, header: ArchiveHeader) -> Self {
-        Self::with_buffer(inner, header, Vec::new())
+    const fn new(inner: T, header: ArchiveHeader, archive_id: Option<ArchiveId>) -> Self {
+        Self::with_buffer(inner, header, archive_id, Vec::new(), None)
     }
 
-    const fn with_buffer(inner: T, header: ArchiveHeader, buf: Vec<RawChunk>) -> Self {
+    const fn with_buffer(
+        inner: T,
+        header: ArchiveHeader,
+        archive_id: Option<ArchiveId>,
+        buf: Vec<RawChunk>,
+        pending: Option<RawChunk>,
+    ) -> Self {
         Self {
             inner,
             header,
+            archive_id,
+            zstd_dictionary: None,
+            extra_chunks: Vec::new(),
+            bytes_written: 0,
             next_archive: false,
             buf,
+            skipped_chunk_sizes: Vec::new(),
+            pending,
         }
     }
 
+    /// Returns the identifier of the archive recorded in the [aIDN] chunk.
+    ///
+    /// Every part of a split archive shares the same identifier.
+    /// Returns `None` if the archive was created without an identifier.
+    ///
+    /// [aIDN]: crate::chunk::ChunkType::aIDN
+    #[inline]
+    pub const fn archive_id(&self) -> Option<ArchiveId> {
+        self.archive_id
+    }
+
+    /// Returns the information recorded in the [AHED] chunk of the archive.
+    ///
+    /// [AHED]: crate::chunk::ChunkType::AHED
+    #[inline]
+    pub const fn header_info(&self) -> ArchiveInfo {
+        self.header.info()
+    }
+
+    /// Returns the application-defined private ancillary chunks of the archive, which are not in any entry.
+    ///
+    /// When reading, these are the chunks read so far, so the chunks written by [Archive::write_header_with]
+    /// are available once reading the entries has started, and the chunks written by
+    /// [Archive::finalize_with_chunks] once all the entries are read.
+    /// When writing, these are the chunks written by [Archive::write_header_with].
+    #[inline]
+    pub fn archive_extra_chunks(&self) -> &[RawChunk] {
+        &self.extra_chunks
+    }
+
+    /// Returns the zstd dictionary recorded in the [zDIC] chunk.
+    ///
+    /// When reading, this is the dictionary read so far, which is set once an entry using it has been read.
+    /// When writing, this is the dictionary written for the entries added so far.
+    /// The entries read from the archive use the dictionary without setting it to [ReadOptions](crate::ReadOptions).
+    ///
+    /// [zDIC]: crate::chunk::ChunkType::zDIC
+    #[inline]
+    pub fn zstd_dictionary(&self) -> Option<&[u8]> {
+        self.zstd_dictionary.as_deref()
+    }
+
     /// Returns `true` if [ANXT] chunk is appeared before call this method calling.
     ///
     /// # Returns
@@ -85,6 +161,15 @@ impl<T> Archive<T> {
     pub const fn has_next_archive(&self) -> bool {
         self.next_archive
     }
+
+    /// Returns `true` if an entry read so far continues into the next archive in the series.
+    ///
+    /// When this returns `false` after all entries are read, the entries of this archive are complete
+    /// and it can be read without the following archives.
+    #[inline]
+    pub fn has_incomplete_entry(&self) -> bool {
+        !self.buf.is_empty()
+    }
 }
 
 /// An object providing writing to a solid mode PNA file.
@@ -111,13 +196,15 @@ impl<T> Archive<T> {
 /// ```
 pub struct SolidArchive<T: Write> {
     archive_header: ArchiveHeader,
-    inner: CompressionWriter<CipherWriter<ChunkStreamWriter<T>>>,
+    archive_id: Option<ArchiveId>,
+    zstd_dictionary: Option<Arc<[u8]>>,
+    inner: PackedWriter<CompressionWriter<CipherWriter<ChunkStreamWriter<T>>>>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::entry::*;
+    use crate::{entry::*, train_zstd_dictionary, Identity};
     use std::io::{self, Cursor};
     use std::time::Duration;
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
@@ -378,7 +465,8 @@ mod tests {
         let mut reader =
             Archive::read_header(archive.as_slice()).expect("failed to read archive header");
 
-        let mut writer = Archive::write_header(Vec::new()).expect("failed to write archive header");
+        let mut writer = Archive::write_header_with_id(Vec::new(), reader.archive_id())
+            .expect("failed to write archive header");
 
         for entry in reader.raw_entries() {
             writer
@@ -424,6 +512,412 @@ mod tests {
         assert!(entries.next().is_none());
     }
 
+    #[test]
+    fn append_to_archive_without_id() {
+        let empty = include_bytes!("../../resources/test/empty.pna");
+        let mut appender = Archive::read_header(Cursor::new(empty.to_vec())).unwrap();
+        assert_eq!(appender.archive_id(), None);
+        appender.seek_to_end().unwrap();
+        appender
+            .add_entry({
+                let builder =
+                    EntryBuilder::new_file("text.txt".into(), WriteOptions::builder().build())
+                        .unwrap();
+                builder.build().unwrap()
+            })
+            .unwrap();
+        let appended = appender.finalize().unwrap().into_inner();
+
+        let mut reader = Archive::read_header(appended.as_slice()).unwrap();
+        let mut entries = reader.entries_skip_solid();
+        assert!(entries.next().is_some());
+        assert!(entries.next().is_none());
+    }
+
+    fn text_entry(name: &str) -> NormalEntry {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(name.as_bytes()).unwrap();
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn append_to_vec() {
+        let mut writer = Archive::write_header(Vec::new()).unwrap();
+        writer.add_entry(text_entry("text1.txt")).unwrap();
+        let archive = writer.finalize().unwrap();
+
+        let mut appender = Archive::append_to_vec(archive.clone()).unwrap();
+        assert!(!appender.has_next_archive());
+        appender.add_entry(text_entry("text2.txt")).unwrap();
+        let appended = appender.finalize_to_vec().unwrap();
+
+        let mut expected = Archive::read_header(Cursor::new(archive)).unwrap();
+        expected.seek_to_end().unwrap();
+        expected.add_entry(text_entry("text2.txt")).unwrap();
+        assert_eq!(appended, expected.finalize().unwrap().into_inner());
+
+        let mut reader = Archive::read_header(appended.as_slice()).unwrap();
+        let names = reader
+            .entries_skip_solid()
+            .map(|it| it.unwrap().header().path().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["text1.txt", "text2.txt"]);
+    }
+
+    #[test]
+    fn append_to_vec_without_entries() {
+        let empty = include_bytes!("../../resources/test/empty.pna");
+        let mut appender = Archive::append_to_vec(empty.to_vec()).unwrap();
+        assert_eq!(appender.archive_id(), None);
+        appender.add_entry(text_entry("text.txt")).unwrap();
+        let appended = appender.finalize_to_vec().unwrap();
+
+        let mut reader = Archive::read_header(appended.as_slice()).unwrap();
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+    }
+
+    #[test]
+    fn append_to_vec_with_next_archive() {
+        let mut parts = create_split_archive();
+        let archive_id = Archive::read_header(parts[0].as_slice())
+            .unwrap()
+            .archive_id();
+        let mut appender = Archive::append_to_vec(parts.remove(0)).unwrap();
+        assert!(appender.has_next_archive());
+        assert_eq!(appender.archive_id(), archive_id);
+        appender.add_entry(text_entry("text3.txt")).unwrap();
+        let appended = appender.finalize_to_vec().unwrap();
+
+        let mut reader = Archive::read_header(appended.as_slice()).unwrap();
+        assert_eq!(reader.entries_skip_solid().count(), 2);
+        assert!(reader.has_next_archive());
+        let mut reader = reader.read_next_archive(parts[0].as_slice()).unwrap();
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+    }
+
+    #[test]
+    fn append_to_truncated_vec() {
+        let mut writer = Archive::write_header(Vec::new()).unwrap();
+        writer.add_entry(text_entry("text.txt")).unwrap();
+        let archive = writer.finalize().unwrap();
+
+        let err = Archive::append_to_vec(archive[..archive.len() - 1].to_vec())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(Archive::append_to_vec(archive[..PNA_HEADER.len()].to_vec()).is_err());
+        assert!(Archive::append_to_vec(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn compressed_xattr_round_trip() {
+        let value = (0..4 * 1024 * 1024)
+            .map(|it| (it % 251) as u8)
+            .collect::<Vec<_>>();
+        let write = |compress: bool| {
+            let mut builder =
+                EntryBuilder::new_file("file.txt".into(), WriteOptions::store()).unwrap();
+            builder.add_xattr(
+                ExtendedAttribute::new("com.apple.ResourceFork".into(), value.clone())
+                    .with_compression(compress),
+            );
+            let mut writer = Archive::write_header(Vec::new()).unwrap();
+            writer.add_entry(builder.build().unwrap()).unwrap();
+            writer.finalize().unwrap()
+        };
+        let raw = write(false);
+        let compressed = write(true);
+        assert!(compressed.len() * 10 < raw.len());
+
+        let mut reader = Archive::read_header(compressed.as_slice()).unwrap();
+        let entry = reader.entries_skip_solid().next().unwrap().unwrap();
+        let xattr = &entry.xattrs()[0];
+        assert!(xattr.is_compressed());
+        assert_eq!(xattr.name(), "com.apple.ResourceFork");
+        assert_eq!(xattr.value(), value);
+        assert!(entry.extra_chunks().is_empty());
+    }
+
+    fn zstd_dictionary_samples(seed: usize) -> Vec<Vec<u8>> {
+        (0..256)
+            .map(|i| {
+                format!(
+                    r#"{{"id":{i},"seed":{seed},"level":"info","message":"request {} handled in {}ms"}}"#,
+                    i * seed,
+                    i % 17
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    fn create_zstd_dictionary_archive(dictionary: &[u8], samples: &[Vec<u8>]) -> Vec<u8> {
+        let options = WriteOptions::builder()
+            .compression(Compression::ZStandard)
+            .zstd_dictionary(Some(dictionary.to_vec()))
+            .build();
+        let mut writer = Archive::write_header(Vec::new()).unwrap();
+        for (i, sample) in samples.iter().enumerate() {
+            let mut builder = EntryBuilder::new_file(format!("{i}.json").into(), &options).unwrap();
+            builder.write_all(sample).unwrap();
+            writer.add_entry(builder.build().unwrap()).unwrap();
+        }
+        writer.finalize().unwrap()
+    }
+
+    #[test]
+    fn zstd_dictionary_round_trip() {
+        let samples = zstd_dictionary_samples(1);
+        let dictionary = train_zstd_dictionary(&samples, 4 * 1024).unwrap();
+        let archive = create_zstd_dictionary_archive(&dictionary, &samples[..8]);
+
+        let mut reader = Archive::read_header(archive.as_slice()).unwrap();
+        let mut chunks = 0;
+        for (i, entry) in reader.entries_skip_solid().enumerate() {
+            let entry = entry.unwrap();
+            let mut body = Vec::new();
+            entry
+                .reader(ReadOptions::builder().build())
+                .unwrap()
+                .read_to_end(&mut body)
+                .unwrap();
+            assert_eq!(body, samples[i]);
+            chunks += 1;
+        }
+        assert_eq!(chunks, 8);
+        assert_eq!(reader.zstd_dictionary(), Some(dictionary.as_slice()));
+        assert_eq!(
+            archive.windows(4).filter(|it| *it == b"zDIC").count(),
+            1,
+            "dictionary must be stored once per archive"
+        );
+    }
+
+    #[test]
+    fn zstd_dictionary_copy_raw_entries() {
+        let samples = zstd_dictionary_samples(1);
+        let dictionary = train_zstd_dictionary(&samples, 4 * 1024).unwrap();
+        let archive = create_zstd_dictionary_archive(&dictionary, &samples[..2]);
+
+        let mut reader = Archive::read_header(archive.as_slice()).unwrap();
+        let mut writer = Archive::write_header_with_id(Vec::new(), reader.archive_id()).unwrap();
+        for entry in reader.raw_entries() {
+            writer.add_entry(entry.unwrap()).unwrap();
+        }
+        assert_eq!(archive, writer.finalize().unwrap());
+    }
+
+    #[test]
+    fn zstd_dictionary_wrong_dictionary() {
+        let samples = zstd_dictionary_samples(1);
+        let dictionary = train_zstd_dictionary(&samples, 4 * 1024).unwrap();
+        let other = train_zstd_dictionary(&zstd_dictionary_samples(7), 2 * 1024).unwrap();
+        let archive = create_zstd_dictionary_archive(&dictionary, &samples[..1]);
+
+        let mut reader = Archive::read_header(archive.as_slice()).unwrap();
+        let entry = reader.entries_skip_solid().next().unwrap().unwrap();
+        let err = entry
+            .reader(ReadOptions::builder().zstd_dictionary(Some(other)).build())
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("different dictionary"));
+    }
+
+    #[test]
+    fn zstd_dictionary_absent_dictionary() {
+        let samples = zstd_dictionary_samples(1);
+        let dictionary = train_zstd_dictionary(&samples, 4 * 1024).unwrap();
+        let archive = create_zstd_dictionary_archive(&dictionary, &samples[..1]);
+
+        // Drop the dictionary chunk, as a reader unaware of it would do.
+        let mut reader = Archive::read_header(archive.as_slice()).unwrap();
+        let mut writer = Archive::write_header(Vec::new()).unwrap();
+        for entry in reader.entries_skip_solid() {
+            writer
+                .add_entry(RawEntry(entry.unwrap().into_chunks()))
+                .unwrap();
+        }
+        let archive = writer.finalize().unwrap();
+
+        let mut reader = Archive::read_header(archive.as_slice()).unwrap();
+        assert_eq!(reader.zstd_dictionary(), None);
+        let entry = reader.entries_skip_solid().next().unwrap().unwrap();
+        let err = entry.reader(ReadOptions::builder().build()).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("no dictionary"));
+    }
+
+    #[test]
+    fn zstd_dictionary_solid_archive() {
+        let samples = zstd_dictionary_samples(1);
+        let dictionary = train_zstd_dictionary(&samples, 4 * 1024).unwrap();
+        let options = WriteOptions::builder()
+            .compression(Compression::ZStandard)
+            .zstd_dictionary(Some(dictionary))
+            .build();
+        let mut writer = Archive::write_solid_header(Vec::new(), options).unwrap();
+        for (i, sample) in samples[..4].iter().enumerate() {
+            let mut builder =
+                EntryBuilder::new_file(format!("{i}.json").into(), WriteOptions::store()).unwrap();
+            builder.write_all(sample).unwrap();
+            writer.add_entry(builder.build().unwrap()).unwrap();
+        }
+        let archive = writer.finalize().unwrap();
+
+        let mut reader = Archive::read_header(archive.as_slice()).unwrap();
+        for (i, entry) in reader.entries_with_password(None).enumerate() {
+            let mut body = Vec::new();
+            entry
+                .unwrap()
+                .reader(ReadOptions::builder().build())
+                .unwrap()
+                .read_to_end(&mut body)
+                .unwrap();
+            assert_eq!(body, samples[i]);
+        }
+    }
+
+    #[test]
+    fn finalize_and_split() {
+        let mut writer = Archive::write_header(Vec::new()).unwrap();
+        writer.add_entry(text_entry("text1.txt")).unwrap();
+        let (part1, mut writer) = writer.finalize_and_split(Vec::new()).unwrap();
+        writer.add_entry(text_entry("text2.txt")).unwrap();
+        let part2 = writer.finalize().unwrap();
+
+        let mut reader = Archive::read_header(part1.as_slice()).unwrap();
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+        assert!(reader.has_next_archive());
+        let mut reader = reader.read_next_archive(part2.as_slice()).unwrap();
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+        assert!(!reader.has_next_archive());
+    }
+
+    fn create_split_archive() -> Vec<Vec<u8>> {
+        let mut part1 = Vec::new();
+        let mut part2 = Vec::new();
+        let mut writer = Archive::write_header_with(&mut part1, ArchiveOptions::new()).unwrap();
+        writer
+            .add_entry_part(EntryPart::from({
+                let mut builder =
+                    EntryBuilder::new_file("text1.txt".into(), WriteOptions::store()).unwrap();
+                builder.write_all(b"text1").unwrap();
+                builder.build().unwrap()
+            }))
+            .unwrap();
+        let mut writer = writer.split_to_next_archive(&mut part2).unwrap();
+        writer
+            .add_entry_part(EntryPart::from({
+                let mut builder =
+                    EntryBuilder::new_file("text2.txt".into(), WriteOptions::store()).unwrap();
+                builder.write_all(b"text2").unwrap();
+                builder.build().unwrap()
+            }))
+            .unwrap();
+        writer.finalize().unwrap();
+        vec![part1, part2]
+    }
+
+    #[test]
+    fn split_archive_id() {
+        let parts = create_split_archive();
+        let mut reader = Archive::read_header(parts[0].as_slice()).unwrap();
+        let archive_id = reader.archive_id();
+        assert!(archive_id.is_some());
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+        assert!(reader.has_next_archive());
+        assert!(!reader.has_incomplete_entry());
+        let mut reader = reader.read_next_archive(parts[1].as_slice()).unwrap();
+        assert_eq!(reader.archive_id(), archive_id);
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+        assert!(!reader.has_next_archive());
+    }
+
+    #[test]
+    fn append_to_last_part_of_split_archive() {
+        let mut parts = create_split_archive();
+        let archive_id = Archive::read_header(parts[0].as_slice())
+            .unwrap()
+            .archive_id();
+        let mut appender = Archive::read_header(io::Cursor::new(parts.pop().unwrap())).unwrap();
+        appender.seek_to_end().unwrap();
+        assert!(!appender.has_next_archive());
+        assert_eq!(appender.archive_id(), archive_id);
+        appender.add_entry(text_entry("text3.txt")).unwrap();
+        let (part2, mut appender) = appender
+            .finalize_and_split(io::Cursor::new(Vec::new()))
+            .unwrap();
+        appender.add_entry(text_entry("text4.txt")).unwrap();
+        parts.push(part2.into_inner());
+        parts.push(appender.finalize().unwrap().into_inner());
+
+        let mut reader = Archive::read_header(parts[0].as_slice()).unwrap();
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+        let mut reader = reader.read_next_archive(parts[1].as_slice()).unwrap();
+        assert_eq!(reader.entries_skip_solid().count(), 2);
+        assert!(reader.has_next_archive());
+        let mut reader = reader.read_next_archive(parts[2].as_slice()).unwrap();
+        assert_eq!(reader.archive_id(), archive_id);
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+        assert!(!reader.has_next_archive());
+    }
+
+    /// Reads the entries in the way pna 0.21.0 does,
+    /// which takes all the chunks following the AHED chunk for entries.
+    fn read_entries_as_0_21_0(bytes: &[u8]) -> io::Result<usize> {
+        use crate::chunk::{ChunkReader, ChunkType};
+        let mut reader = ChunkReader::from(&bytes[PNA_HEADER.len()..]);
+        assert_eq!(reader.read_chunk()?.ty, ChunkType::AHED);
+        let mut entries = 0;
+        let mut chunks = Vec::new();
+        loop {
+            let chunk = reader.read_chunk()?;
+            match chunk.ty {
+                ChunkType::FEND | ChunkType::SEND => {
+                    chunks.push(chunk);
+                    ReadEntry::try_from(RawEntry(std::mem::take(&mut chunks)))?;
+                    entries += 1;
+                }
+                ChunkType::ANXT => (),
+                ChunkType::AEND => return Ok(entries),
+                _ => chunks.push(chunk),
+            }
+        }
+    }
+
+    #[test]
+    fn archive_without_id_is_readable_by_0_21_0() {
+        let mut writer = Archive::write_header(Vec::new()).unwrap();
+        assert_eq!(writer.archive_id(), None);
+        writer.add_entry(text_entry("text.txt")).unwrap();
+        let archive = writer.finalize().unwrap();
+        assert_eq!(read_entries_as_0_21_0(&archive).unwrap(), 1);
+
+        // NOTE: pna 0.21.0 takes the aIDN chunk for the beginning of an entry.
+        let mut writer = Archive::write_header_with(Vec::new(), ArchiveOptions::new()).unwrap();
+        assert!(writer.archive_id().is_some());
+        writer.add_entry(text_entry("text.txt")).unwrap();
+        let archive = writer.finalize().unwrap();
+        assert!(read_entries_as_0_21_0(&archive).is_err());
+    }
+
+    #[test]
+    fn reject_mixed_split_archive() {
+        let run1 = create_split_archive();
+        let run2 = create_split_archive();
+        let mut reader = Archive::read_header(run1[0].as_slice()).unwrap();
+        assert_eq!(reader.entries_skip_solid().count(), 1);
+        let Err(err) = reader.read_next_archive(run2[1].as_slice()) else {
+            panic!("archive parts of different runs must not be mixed");
+        };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut reader = Archive::read_header_from_slice(&run1[0]).unwrap();
+        assert_eq!(reader.entries_slice().count(), 1);
+        assert!(reader.read_next_archive_from_slice(&run2[1]).is_err());
+    }
+
     #[test]
     fn metadata() {
         let original_entry = {
@@ -472,4 +966,217 @@ mod tests {
             read_entry.metadata().raw_file_size()
         );
     }
+
+    fn read_with_identities(archive: &[u8], identities: &[Identity]) -> io::Result<Vec<u8>> {
+        let mut archive = Archive::read_header(archive)?;
+        let entry = archive.entries_skip_solid().next().unwrap()?;
+        let mut reader = entry.reader(
+            ReadOptions::builder()
+                .identities(identities.iter().cloned())
+                .build(),
+        )?;
+        let mut dist = Vec::new();
+        reader.read_to_end(&mut dist)?;
+        Ok(dist)
+    }
+
+    #[test]
+    fn recipient_archive() {
+        let identity = Identity::generate().unwrap();
+        let archive = create_archive(
+            b"plain text",
+            WriteOptions::builder()
+                .encryption(Encryption::Aes)
+                .recipients([identity.to_public()])
+                .build(),
+        )
+        .unwrap();
+        assert_eq!(
+            read_with_identities(&archive, &[identity]).unwrap(),
+            b"plain text"
+        );
+    }
+
+    #[test]
+    fn multiple_recipients_archive() {
+        let identities = [Identity::generate().unwrap(), Identity::generate().unwrap()];
+        let archive = create_archive(
+            b"plain text",
+            WriteOptions::builder()
+                .encryption(Encryption::Camellia)
+                .cipher_mode(CipherMode::CBC)
+                .recipients(identities.iter().map(Identity::to_public))
+                .build(),
+        )
+        .unwrap();
+        for identity in identities {
+            assert_eq!(
+                read_with_identities(&archive, &[identity]).unwrap(),
+                b"plain text"
+            );
+        }
+    }
+
+    #[test]
+    fn recipient_archive_wrong_identity() {
+        let archive = create_archive(
+            b"plain text",
+            WriteOptions::builder()
+                .encryption(Encryption::Aes)
+                .recipients([Identity::generate().unwrap().to_public()])
+                .build(),
+        )
+        .unwrap();
+        assert!(read_with_identities(&archive, &[Identity::generate().unwrap()]).is_err());
+        assert!(read_with_identities(&archive, &[]).is_err());
+    }
+
+    #[test]
+    fn mixed_recipient_and_password_archive() {
+        let identity = Identity::generate().unwrap();
+        let mut writer = Archive::write_header(Vec::new()).unwrap();
+        for (name, options) in [
+            (
+                "recipient",
+                WriteOptions::builder()
+                    .encryption(Encryption::Aes)
+                    .recipients([identity.to_public()])
+                    .build(),
+            ),
+            (
+                "password",
+                WriteOptions::builder()
+                    .encryption(Encryption::Aes)
+                    .hash_algorithm(HashAlgorithm::pbkdf2_sha256_with(Some(1)))
+                    .password(Some("password"))
+                    .build(),
+            ),
+        ] {
+            let mut builder = EntryBuilder::new_file(name.into(), options).unwrap();
+            builder.write_all(name.as_bytes()).unwrap();
+            writer.add_entry(builder.build().unwrap()).unwrap();
+        }
+        let archive = writer.finalize().unwrap();
+
+        let read_options = ReadOptions::builder()
+            .password(Some("password"))
+            .identities([identity])
+            .build();
+        let mut archive = Archive::read_header(archive.as_slice()).unwrap();
+        for entry in archive.entries_skip_solid() {
+            let entry = entry.unwrap();
+            let mut dist = String::new();
+            entry
+                .reader(&read_options)
+                .unwrap()
+                .read_to_string(&mut dist)
+                .unwrap();
+            assert_eq!(entry.header().path().as_str(), dist);
+        }
+    }
+
+    #[test]
+    fn solid_recipient_archive_unsupported() {
+        let options = WriteOptions::builder()
+            .encryption(Encryption::Aes)
+            .recipients([Identity::generate().unwrap().to_public()])
+            .build();
+        assert!(Archive::write_solid_header(Vec::new(), &options).is_err());
+        assert!(SolidEntryBuilder::new(&options).is_err());
+    }
+
+    fn create_many_entries_archive(count: usize, option: WriteOptions) -> io::Result<Vec<u8>> {
+        let mut writer = Archive::write_header(Vec::new())?;
+        for i in 0..count {
+            let mut builder = EntryBuilder::new_file(format!("{i}.txt").into(), &option)?;
+            builder.write_all(i.to_string().as_bytes())?;
+            writer.add_entry(builder.build()?)?;
+        }
+        writer.finalize()
+    }
+
+    fn read_many_entries_archive(archive: &[u8], option: ReadOptions) -> io::Result<usize> {
+        let mut archive = Archive::read_header(archive)?;
+        let mut count = 0;
+        for (i, entry) in archive.entries_skip_solid().enumerate() {
+            let mut content = String::new();
+            entry?.reader(&option)?.read_to_string(&mut content)?;
+            assert_eq!(content, i.to_string());
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn many_entries_write_options(reuse_salt: bool) -> WriteOptions {
+        WriteOptions::builder()
+            .encryption(Encryption::Aes)
+            .hash_algorithm(HashAlgorithm::pbkdf2_sha256_with(Some(1)))
+            .password(Some("password"))
+            .reuse_salt(reuse_salt)
+            .build()
+    }
+
+    #[test]
+    fn reuse_salt_derives_key_once() {
+        let derivations = crate::hash::derivations();
+        let archive = create_many_entries_archive(1000, many_entries_write_options(true)).unwrap();
+        assert_eq!(crate::hash::derivations() - derivations, 1);
+
+        let derivations = crate::hash::derivations();
+        let count =
+            read_many_entries_archive(&archive, ReadOptions::with_password(Some("password")))
+                .unwrap();
+        assert_eq!(count, 1000);
+        assert_eq!(crate::hash::derivations() - derivations, 1);
+    }
+
+    #[test]
+    fn distinct_salts_derive_key_per_entry() {
+        let derivations = crate::hash::derivations();
+        let archive = create_many_entries_archive(100, many_entries_write_options(false)).unwrap();
+        assert_eq!(crate::hash::derivations() - derivations, 100);
+
+        let derivations = crate::hash::derivations();
+        let count =
+            read_many_entries_archive(&archive, ReadOptions::with_password(Some("password")))
+                .unwrap();
+        assert_eq!(count, 100);
+        assert_eq!(crate::hash::derivations() - derivations, 100);
+    }
+
+    #[test]
+    fn reuse_salt_shares_salt_between_entries() {
+        let archive = create_many_entries_archive(2, many_entries_write_options(true)).unwrap();
+        let mut archive = Archive::read_header(archive.as_slice()).unwrap();
+        let entries = archive
+            .entries_skip_solid()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert!(entries[0].phsf.is_some());
+        assert_eq!(entries[0].phsf, entries[1].phsf);
+
+        let archive = create_many_entries_archive(2, many_entries_write_options(false)).unwrap();
+        let mut archive = Archive::read_header(archive.as_slice()).unwrap();
+        let entries = archive
+            .entries_skip_solid()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_ne!(entries[0].phsf, entries[1].phsf);
+    }
+
+    #[test]
+    fn key_cache_is_not_shared_between_passwords() {
+        let archive = create_many_entries_archive(1, many_entries_write_options(true)).unwrap();
+        let mut archive = Archive::read_header(archive.as_slice()).unwrap();
+        let entry = archive.entries_skip_solid().next().unwrap().unwrap();
+        let read = |option: ReadOptions| {
+            let mut content = Vec::new();
+            entry.reader(option)?.read_to_end(&mut content)?;
+            io::Result::Ok(content)
+        };
+        let wrong = ReadOptions::with_password(Some("wrong"));
+        assert_ne!(read(wrong.clone()).unwrap(), b"0");
+        let correct = wrong.into_builder().password(Some("password")).build();
+        assert_eq!(read(correct).unwrap(), b"0");
+    }
 }
@@ -1,8 +1,126 @@
+use crate::chunk::{ChunkType, RawChunk};
 use std::io;
 
 /// The magic number of Portable-Network-Archive
 pub const PNA_HEADER: &[u8; 8] = b"\x89PNA\r\n\x1A\n";
 
+/// Identifier of an archive shared by all parts of a split archive.
+pub type ArchiveId = [u8; 16];
+
+#[inline]
+pub(crate) fn archive_id_from_bytes(bytes: &[u8]) -> io::Result<ArchiveId> {
+    bytes
+        .try_into()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Returns `true` if a chunk of `ty` is an application-defined archive-level chunk, when it is not in an entry.
+#[inline]
+pub(crate) const fn is_archive_extra_chunk(ty: ChunkType) -> bool {
+    ty.is_private() && !ty.is_critical()
+}
+
+/// Returns an error if `ty` is not a type of application-defined archive-level chunks.
+#[inline]
+pub(crate) fn check_archive_extra_chunk(ty: ChunkType) -> io::Result<()> {
+    if is_archive_extra_chunk(ty) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{ty}` is not a private ancillary chunk"),
+        ))
+    }
+}
+
+/// Information recorded in the [AHED] chunk of an archive.
+///
+/// [AHED]: crate::ChunkType::AHED
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct ArchiveInfo {
+    major: u8,
+    minor: u8,
+    archive_number: u32,
+}
+
+impl ArchiveInfo {
+    /// Returns the major version of the format the archive is written in.
+    #[inline]
+    pub const fn major(&self) -> u8 {
+        self.major
+    }
+
+    /// Returns the minor version of the format the archive is written in.
+    #[inline]
+    pub const fn minor(&self) -> u8 {
+        self.minor
+    }
+
+    /// Returns the number of the archive in a split archive, starting from `0`.
+    #[inline]
+    pub const fn archive_number(&self) -> u32 {
+        self.archive_number
+    }
+}
+
+/// Options for writing the header of an archive by [Archive::write_header_with].
+///
+/// # Examples
+/// ```
+/// use libpna::{ArchiveOptions, ChunkType, RawChunk};
+///
+/// let ty = ChunkType::private(*b"myAr").unwrap();
+/// let options = ArchiveOptions::new().with_extra_chunk(RawChunk::from_data(ty, b"my tool 1.0"));
+/// ```
+///
+/// [Archive::write_header_with]: crate::Archive::write_header_with
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ArchiveOptions {
+    // `None` to generate a random identifier
+    archive_id: Option<Option<ArchiveId>>,
+    extra_chunks: Vec<RawChunk>,
+}
+
+impl ArchiveOptions {
+    /// Creates a new [ArchiveOptions] with a random archive identifier and no extra chunks.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            archive_id: None,
+            extra_chunks: Vec::new(),
+        }
+    }
+
+    /// Sets the identifier of the archive recorded in the [aIDN] chunk, or `None` not to write the chunk.
+    ///
+    /// [aIDN]: crate::ChunkType::aIDN
+    #[inline]
+    pub fn with_archive_id(mut self, archive_id: Option<ArchiveId>) -> Self {
+        self.archive_id = Some(archive_id);
+        self
+    }
+
+    /// Adds an application-defined chunk written between the archive header and the first entry.
+    ///
+    /// The chunk must be a private ancillary chunk, such as one of the types made by [ChunkType::private]
+    /// with a lowercase first letter.
+    #[inline]
+    pub fn with_extra_chunk(mut self, chunk: impl Into<RawChunk>) -> Self {
+        self.extra_chunks.push(chunk.into());
+        self
+    }
+
+    #[inline]
+    pub(crate) fn archive_id(&self) -> Option<Option<ArchiveId>> {
+        self.archive_id
+    }
+
+    #[inline]
+    pub(crate) fn into_extra_chunks(self) -> Vec<RawChunk> {
+        self.extra_chunks
+    }
+}
+
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub(crate) struct ArchiveHeader {
     pub(crate) major: u8,
@@ -43,6 +161,15 @@ impl ArchiveHeader {
         Self::new(major, minor, archive_number)
     }
 
+    #[inline]
+    pub(crate) const fn info(&self) -> ArchiveInfo {
+        ArchiveInfo {
+            major: self.major,
+            minor: self.minor,
+            archive_number: self.archive_number,
+        }
+    }
+
     #[inline]
     pub(crate) fn try_from_bytes(bytes: &[u8]) -> io::Result<Self> {
         Ok(Self::from_bytes(bytes.try_into().map_err(|e| {
@@ -57,6 +184,13 @@ mod tests {
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
+    #[test]
+    fn archive_id_try_from_bytes() {
+        assert!(archive_id_from_bytes(&[0u8; 15]).is_err());
+        assert_eq!(archive_id_from_bytes(&[1u8; 16]).unwrap(), [1u8; 16]);
+        assert!(archive_id_from_bytes(&[0u8; 17]).is_err());
+    }
+
     #[test]
     fn header_to_bytes() {
         assert_eq!(
@@ -0,0 +1,360 @@
+use crate::{
+    archive::{Archive, EntryRecord},
+    chunk::{ChunkReader, ChunkType},
+    entry::{EntryName, NormalEntry, Password, RawEntry, ReadEntry},
+};
+#[cfg(any(unix, windows))]
+use std::fs;
+use std::{
+    io::{self, BufReader, Read},
+    sync::Arc,
+};
+
+/// A source of bytes that can be read at any offset through a shared reference.
+///
+/// Unlike [Read], reading does not move a cursor, so the same source can be read from multiple threads at once.
+/// A memory map can be read through the `[u8]` implementation.
+pub trait ReadAt {
+    /// Reads bytes starting at `offset` into `buf`, and returns the number of bytes read.
+    /// Returns `Ok(0)` if `offset` is at or past the end of the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading.
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Reads exactly `buf.len()` bytes starting at `offset` into `buf`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of [io::ErrorKind::UnexpectedEof] if the source ends before `buf` is filled,
+    /// or if an I/O error occurs while reading.
+    #[inline]
+    fn read_exact_at(&self, mut offset: u64, mut buf: &mut [u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            match self.read_at(offset, buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    buf = &mut buf[n..];
+                    offset += n as u64;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e),
+            }
+        }
+        if buf.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "failed to fill whole buffer",
+            ))
+        }
+    }
+}
+
+impl ReadAt for [u8] {
+    #[inline]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        let start = usize::try_from(offset).map_or(self.len(), |it| it.min(self.len()));
+        let src = &self[start..];
+        let len = src.len().min(buf.len());
+        buf[..len].copy_from_slice(&src[..len]);
+        Ok(len)
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    #[inline]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        self.as_slice().read_at(offset, buf)
+    }
+}
+
+#[cfg(any(unix, windows))]
+impl ReadAt for fs::File {
+    #[inline]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        #[cfg(unix)]
+        use std::os::unix::fs::FileExt;
+        #[cfg(windows)]
+        use std::os::windows::fs::FileExt;
+        #[cfg(unix)]
+        return FileExt::read_at(self, buf, offset);
+        #[cfg(windows)]
+        return FileExt::seek_read(self, buf, offset);
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for &T {
+    #[inline]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    #[inline]
+    fn read_at(&self, offset: u64, buf: &mut [u8]) -> io::Result<usize> {
+        (**self).read_at(offset, buf)
+    }
+}
+
+/// [Read] adapter reading a [ReadAt] source sequentially from an offset.
+struct ReadAtReader<'r, R: ?Sized> {
+    inner: &'r R,
+    offset: u64,
+}
+
+impl<R: ReadAt + ?Sized> Read for ReadAtReader<'_, R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read_at(self.offset, buf)?;
+        self.offset += n as u64;
+        Ok(n)
+    }
+}
+
+/// An archive indexed on open, whose entries can be read in any order through a shared reference.
+///
+/// Each read of an entry reads its chunks at the indexed location and decodes them independently,
+/// so multiple threads can read entries of the same archive at the same time.
+/// Split archives are not supported.
+///
+/// # Examples
+/// ```no_run
+/// use libpna::{ArchiveIndexed, ReadOptions};
+/// use std::{fs, io, thread};
+///
+/// # fn main() -> io::Result<()> {
+/// let archive = ArchiveIndexed::open(fs::File::open("foo.pna")?)?;
+/// thread::scope(|s| {
+///     for index in 0..archive.len() {
+///         let archive = &archive;
+///         s.spawn(move || -> io::Result<()> {
+///             let entry = archive.entry(index)?;
+///             let reader = entry.reader(ReadOptions::builder().build())?;
+///             io::copy(&mut io::BufReader::new(reader), &mut io::sink())?;
+///             Ok(())
+///         });
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+pub struct ArchiveIndexed<R> {
+    inner: R,
+    records: Vec<EntryRecord>,
+    password: Option<Password>,
+    zstd_dictionary: Option<Arc<[u8]>>,
+}
+
+impl<R: ReadAt> ArchiveIndexed<R> {
+    /// Scans the archive in `inner` once and builds the table of its entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading, if the archive is broken,
+    /// or if a solid entry is encrypted. Use [ArchiveIndexed::open_with_password] for encrypted solid entries.
+    #[inline]
+    pub fn open(inner: R) -> io::Result<Self> {
+        Self::open_with_password(inner, None)
+    }
+
+    /// Same as [ArchiveIndexed::open], but decrypts solid entries with `password`.
+    /// The password is kept to decrypt the solid entries again when their entries are read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading, if the archive is broken,
+    /// or if a solid entry can not be decrypted with `password`.
+    #[inline]
+    pub fn open_with_password(inner: R, password: Option<&str>) -> io::Result<Self> {
+        // NOTE: The archive has no entry index, so the entries are located by scanning the chunks.
+        let mut records = Vec::new();
+        let reader = BufReader::new(ReadAtReader {
+            inner: &inner,
+            offset: 0,
+        });
+        let zstd_dictionary =
+            Archive::scan_for_dictionary(reader, password, |record| records.push(record))?;
+        Ok(Self {
+            inner,
+            records,
+            password: password.map(Into::into),
+            zstd_dictionary,
+        })
+    }
+
+    /// Returns the number of entries in the archive, counting the entries in solid entries.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Returns `true` if the archive has no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Returns the records of the entries in the order they are stored.
+    #[inline]
+    pub fn entries(&self) -> &[EntryRecord] {
+        &self.records
+    }
+
+    /// Returns the index of the first entry named `name`, normalized in the same way as [EntryName].
+    #[inline]
+    pub fn position(&self, name: &str) -> Option<usize> {
+        let name = EntryName::from(name);
+        self.records.iter().position(|it| it.name() == &name)
+    }
+
+    /// Reads the entry at `index`.
+    ///
+    /// An entry in a solid entry is read by decoding the solid entry containing it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `index` is out of range, if an I/O error occurs while reading,
+    /// or if the entry is broken.
+    #[inline]
+    pub fn entry(&self, index: usize) -> io::Result<NormalEntry> {
+        let record = self.records.get(index).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("entry index {index} is out of range"),
+            )
+        })?;
+        let mut bytes = vec![0; record.length() as usize];
+        self.inner.read_exact_at(record.offset(), &mut bytes)?;
+        let mut reader = ChunkReader::from(bytes.as_slice());
+        let mut chunks = Vec::new();
+        loop {
+            let chunk = reader.read_chunk()?;
+            let ty = chunk.ty;
+            chunks.push(chunk);
+            if ty == ChunkType::FEND || ty == ChunkType::SEND {
+                break;
+            }
+        }
+        match ReadEntry::try_from(RawEntry(chunks))?
+            .with_zstd_dictionary(self.zstd_dictionary.as_ref())
+        {
+            ReadEntry::Normal(entry) => Ok(entry),
+            ReadEntry::Solid(solid) => {
+                let position = self.records[..index]
+                    .iter()
+                    .rev()
+                    .take_while(|it| it.solid_index() == record.solid_index())
+                    .count();
+                let entry = solid
+                    .entries(self.password.as_ref().map(Password::as_str))?
+                    .nth(position);
+                entry.unwrap_or_else(|| {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "entry not found in the solid entry",
+                    ))
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntryBuilder, ReadOptions, SolidEntryBuilder, WriteOptions};
+    use std::io::Write;
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn file_entry(name: &str, content: &[u8]) -> NormalEntry {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(content).unwrap();
+        builder.build().unwrap()
+    }
+
+    fn archive() -> Vec<u8> {
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        archive
+            .add_entry(file_entry("a.txt", &[b'a'; 100_000]))
+            .unwrap();
+        let mut solid = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        solid.add_entry(file_entry("b.txt", b"b")).unwrap();
+        solid.add_entry(file_entry("c.txt", b"c")).unwrap();
+        archive.add_entry(solid.build().unwrap()).unwrap();
+        archive
+            .add_entry(file_entry("d.txt", &[b'd'; 100_000]))
+            .unwrap();
+        archive.finalize().unwrap()
+    }
+
+    fn read_content(entry: NormalEntry) -> Vec<u8> {
+        let mut content = Vec::new();
+        entry
+            .reader(ReadOptions::builder().build())
+            .unwrap()
+            .read_to_end(&mut content)
+            .unwrap();
+        content
+    }
+
+    fn sequential_contents(bytes: &[u8]) -> Vec<Vec<u8>> {
+        let mut archive = Archive::read_header(bytes).unwrap();
+        archive
+            .entries_with_password(None)
+            .map(|it| read_content(it.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn read_at_slice() {
+        let bytes = [1u8, 2, 3];
+        let mut buf = [0; 2];
+        assert_eq!(bytes.read_at(2, &mut buf).unwrap(), 1);
+        assert_eq!(buf[0], 3);
+        assert_eq!(bytes.read_at(5, &mut buf).unwrap(), 0);
+        assert_eq!(
+            bytes.read_exact_at(2, &mut buf).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn read_entries_in_any_order() {
+        let bytes = archive();
+        let sequential = sequential_contents(&bytes);
+        let archive = ArchiveIndexed::open(bytes.as_slice()).unwrap();
+        assert_eq!(archive.len(), 4);
+        for index in [3, 2, 0, 1, 2] {
+            assert_eq!(
+                read_content(archive.entry(index).unwrap()),
+                sequential[index]
+            );
+        }
+        assert_eq!(archive.position("./c.txt"), Some(2));
+        assert_eq!(archive.position("missing.txt"), None);
+        assert_eq!(
+            archive.entry(4).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn read_entries_concurrently() {
+        let bytes = archive();
+        let sequential = sequential_contents(&bytes);
+        let archive = ArchiveIndexed::open(Arc::new(bytes)).unwrap();
+        let archive = &archive;
+        let contents = std::thread::scope(|s| {
+            let handles =
+                [0, 3].map(|index| s.spawn(move || read_content(archive.entry(index).unwrap())));
+            handles.map(|it| it.join().unwrap())
+        });
+        assert_eq!(contents, [sequential[0].clone(), sequential[3].clone()]);
+    }
+}
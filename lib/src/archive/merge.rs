@@ -0,0 +1,241 @@
+use crate::{
+    archive::{Archive, Entries},
+    entry::{NormalEntry, ReadEntry},
+};
+use std::io::{self, Read, Write};
+
+/// Which entry to keep when entries with the same name are found while merging archives.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Keep {
+    /// Keep the entry that has already been selected and discard the other.
+    Left,
+    /// Keep the other entry and discard the entry that has already been selected.
+    Right,
+    /// Keep both entries.
+    Both,
+}
+
+fn next_normal_entry<R: Read>(entries: &mut Entries<R>) -> io::Result<Option<NormalEntry>> {
+    match entries.next().transpose()? {
+        Some(ReadEntry::Normal(entry)) => Ok(Some(entry)),
+        Some(ReadEntry::Solid(_)) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Merging archives that contain solid mode entries is not supported",
+        )),
+        None => Ok(None),
+    }
+}
+
+impl<W: Write> Archive<W> {
+    /// Merges entries of the archives whose entries are sorted by name into this archive,
+    /// keeping the entries sorted by name.
+    ///
+    /// Entries are copied as is, without decompression or recompression.
+    /// When entries with the same name are found in multiple archives, `resolver` is called
+    /// with the entry that has already been selected and the entry of the archive that follows it in `archives`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs, if an archive contains solid mode entries,
+    /// or if entries of an archive are not sorted by name.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libpna::{Archive, Keep};
+    /// use std::fs::File;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut archives = vec![
+    ///     Archive::read_header(File::open("day1.pna")?)?,
+    ///     Archive::read_header(File::open("day2.pna")?)?,
+    /// ];
+    /// let mut archive = Archive::write_header(File::create("month.pna")?)?;
+    /// archive.merge_sorted(&mut archives, |_, _| Keep::Right)?;
+    /// archive.finalize()?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn merge_sorted<R, F>(
+        &mut self,
+        archives: &mut [Archive<R>],
+        mut resolver: F,
+    ) -> io::Result<()>
+    where
+        R: Read,
+        F: FnMut(&NormalEntry, &NormalEntry) -> Keep,
+    {
+        let mut entries = archives
+            .iter_mut()
+            .map(|it| it.entries())
+            .collect::<Vec<_>>();
+        let mut heads = entries
+            .iter_mut()
+            .map(next_normal_entry)
+            .collect::<io::Result<Vec<_>>>()?;
+        while let Some(name) = heads
+            .iter()
+            .flatten()
+            .map(|it| it.header().path())
+            .min()
+            .cloned()
+        {
+            let mut selected = Vec::<NormalEntry>::new();
+            for (head, entries) in heads.iter_mut().zip(entries.iter_mut()) {
+                if head.as_ref().map(|it| it.header().path()) != Some(&name) {
+                    continue;
+                }
+                let next = next_normal_entry(entries)?;
+                if let Some(next) = &next {
+                    if next.header().path() < &name {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "Entries are not sorted by name (`{}` follows `{}`)",
+                                next.header().path(),
+                                name
+                            ),
+                        ));
+                    }
+                }
+                let Some(entry) = std::mem::replace(head, next) else {
+                    continue;
+                };
+                match selected.last() {
+                    None => selected.push(entry),
+                    Some(left) => match resolver(left, &entry) {
+                        Keep::Left => (),
+                        Keep::Right => selected = vec![entry],
+                        Keep::Both => selected.push(entry),
+                    },
+                }
+            }
+            for entry in selected {
+                self.add_entry(entry)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntryBuilder, ReadOptions, WriteOptions};
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn create_archive(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        for (name, content) in entries {
+            let mut builder =
+                EntryBuilder::new_file((*name).into(), WriteOptions::store()).unwrap();
+            builder.write_all(content.as_bytes()).unwrap();
+            archive.add_entry(builder.build().unwrap()).unwrap();
+        }
+        archive.finalize().unwrap()
+    }
+
+    fn merge(keep: Keep) -> Vec<(String, String)> {
+        let sources = [
+            create_archive(&[("a", "1-a"), ("c", "1-c"), ("e", "1-e")]),
+            create_archive(&[("b", "2-b"), ("c", "2-c"), ("f", "2-f")]),
+            create_archive(&[("c", "3-c"), ("d", "3-d"), ("e", "3-e")]),
+        ];
+        let mut archives = sources
+            .iter()
+            .map(|it| Archive::read_header(it.as_slice()).unwrap())
+            .collect::<Vec<_>>();
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        archive.merge_sorted(&mut archives, |_, _| keep).unwrap();
+        let merged = archive.finalize().unwrap();
+
+        let mut archive = Archive::read_header(merged.as_slice()).unwrap();
+        archive
+            .entries_skip_solid()
+            .map(|entry| {
+                let entry = entry.unwrap();
+                let mut content = String::new();
+                entry
+                    .reader(ReadOptions::builder().build())
+                    .unwrap()
+                    .read_to_string(&mut content)
+                    .unwrap();
+                (entry.header().path().to_string(), content)
+            })
+            .collect()
+    }
+
+    fn pairs(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(name, content)| (name.to_string(), content.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn merge_sorted_keep_left() {
+        assert_eq!(
+            merge(Keep::Left),
+            pairs(&[
+                ("a", "1-a"),
+                ("b", "2-b"),
+                ("c", "1-c"),
+                ("d", "3-d"),
+                ("e", "1-e"),
+                ("f", "2-f"),
+            ])
+        );
+    }
+
+    #[test]
+    fn merge_sorted_keep_right() {
+        assert_eq!(
+            merge(Keep::Right),
+            pairs(&[
+                ("a", "1-a"),
+                ("b", "2-b"),
+                ("c", "3-c"),
+                ("d", "3-d"),
+                ("e", "3-e"),
+                ("f", "2-f"),
+            ])
+        );
+    }
+
+    #[test]
+    fn merge_sorted_keep_both() {
+        assert_eq!(
+            merge(Keep::Both),
+            pairs(&[
+                ("a", "1-a"),
+                ("b", "2-b"),
+                ("c", "1-c"),
+                ("c", "2-c"),
+                ("c", "3-c"),
+                ("d", "3-d"),
+                ("e", "1-e"),
+                ("e", "3-e"),
+                ("f", "2-f"),
+            ])
+        );
+    }
+
+    #[test]
+    fn merge_unsorted() {
+        let sources = [
+            create_archive(&[("b", "1-b"), ("a", "1-a")]),
+            create_archive(&[("c", "2-c")]),
+        ];
+        let mut archives = sources
+            .iter()
+            .map(|it| Archive::read_header(it.as_slice()).unwrap())
+            .collect::<Vec<_>>();
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        assert!(archive
+            .merge_sorted(&mut archives, |_, _| Keep::Both)
+            .is_err());
+    }
+}
@@ -1,9 +1,13 @@
+mod find;
 mod slice;
 
 use crate::{
-    archive::{Archive, ArchiveHeader, PNA_HEADER},
-    chunk::{Chunk, ChunkReader, ChunkType, RawChunk},
-    entry::{Entry, NormalEntry, RawEntry, ReadEntry},
+    archive::{
+        archive_id_from_bytes, is_archive_extra_chunk, Archive, ArchiveHeader, ArchiveId,
+        PNA_HEADER,
+    },
+    chunk::{Chunk, ChunkExt, ChunkReader, ChunkType, RawChunk},
+    entry::{ArchiveRawEntry, Entry, NormalEntry, RawEntry, ReadEntry},
 };
 #[cfg(feature = "unstable-async")]
 use futures_io::AsyncRead;
@@ -65,7 +69,9 @@ impl<R: Read> Archive<R> {
             ));
         }
         let header = ArchiveHeader::try_from_bytes(chunk.data())?;
-        Ok(Self::with_buffer(reader, header, buf))
+        let chunk = chunk_reader.read_chunk()?;
+        let (archive_id, pending) = split_archive_id(chunk)?;
+        Ok(Self::with_buffer(reader, header, archive_id, buf, pending))
     }
 
     /// Reads the next raw entry (from `FHED` to `FEND` chunk) from the archive.
@@ -82,17 +88,24 @@ impl<R: Read> Archive<R> {
         swap(&mut self.buf, &mut chunks);
         let mut reader = ChunkReader::from(&mut self.inner);
         loop {
-            let chunk = reader.read_chunk()?;
+            let chunk = match self.pending.take() {
+                Some(chunk) => chunk,
+                None => reader.read_chunk()?,
+            };
             match chunk.ty {
                 ChunkType::FEND | ChunkType::SEND => {
                     chunks.push(chunk);
                     break;
                 }
                 ChunkType::ANXT => self.next_archive = true,
+                ChunkType::zDIC => self.zstd_dictionary = Some(chunk.data.into()),
                 ChunkType::AEND => {
                     self.buf = chunks;
                     return Ok(None);
                 }
+                ty if chunks.is_empty() && is_archive_extra_chunk(ty) => {
+                    self.extra_chunks.push(chunk)
+                }
                 _ => chunks.push(chunk),
             }
         }
@@ -111,7 +124,9 @@ impl<R: Read> Archive<R> {
     fn read_entry(&mut self) -> io::Result<Option<ReadEntry>> {
         let entry = self.next_raw_item()?;
         match entry {
-            Some(entry) => Ok(Some(entry.try_into()?)),
+            Some(entry) => Ok(Some(
+                ReadEntry::try_from(entry)?.with_zstd_dictionary(self.zstd_dictionary.as_ref()),
+            )),
             None => Ok(None),
         }
     }
@@ -225,16 +240,12 @@ impl<R: Read> Archive<R> {
     #[inline]
     pub fn read_next_archive<OR: Read>(self, reader: OR) -> io::Result<Archive<OR>> {
         let current_header = self.header;
-        let next = Archive::<OR>::read_header_with_buffer(reader, self.buf)?;
-        if current_header.archive_number + 1 != next.header.archive_number {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!(
-                    "Next archive number must be +1 (current: {}, detected: {})",
-                    current_header.archive_number, next.header.archive_number
-                ),
-            ));
-        }
+        let current_id = self.archive_id;
+        let mut next = Archive::<OR>::read_header_with_buffer(reader, self.buf)?;
+        next.skipped_chunk_sizes = self.skipped_chunk_sizes;
+        next.zstd_dictionary = self.zstd_dictionary;
+        next.extra_chunks = self.extra_chunks;
+        verify_next_archive(&current_header, current_id, &next.header, next.archive_id)?;
         Ok(next)
     }
 }
@@ -259,7 +270,9 @@ impl<R: AsyncRead + Unpin> Archive<R> {
             ));
         }
         let header = ArchiveHeader::try_from_bytes(chunk.data())?;
-        Ok(Self::with_buffer(reader, header, buf))
+        let chunk = chunk_reader.read_chunk_async().await?;
+        let (archive_id, pending) = split_archive_id(chunk)?;
+        Ok(Self::with_buffer(reader, header, archive_id, buf, pending))
     }
 
     async fn next_raw_item_async(&mut self) -> io::Result<Option<RawEntry>> {
@@ -267,17 +280,24 @@ impl<R: AsyncRead + Unpin> Archive<R> {
         swap(&mut self.buf, &mut chunks);
         let mut reader = ChunkReader::from(&mut self.inner);
         loop {
-            let chunk = reader.read_chunk_async().await?;
+            let chunk = match self.pending.take() {
+                Some(chunk) => chunk,
+                None => reader.read_chunk_async().await?,
+            };
             match chunk.ty {
                 ChunkType::FEND | ChunkType::SEND => {
                     chunks.push(chunk);
                     break;
                 }
                 ChunkType::ANXT => self.next_archive = true,
+                ChunkType::zDIC => self.zstd_dictionary = Some(chunk.data.into()),
                 ChunkType::AEND => {
                     self.buf = chunks;
                     return Ok(None);
                 }
+                ty if chunks.is_empty() && is_archive_extra_chunk(ty) => {
+                    self.extra_chunks.push(chunk)
+                }
                 _ => chunks.push(chunk),
             }
         }
@@ -291,7 +311,9 @@ impl<R: AsyncRead + Unpin> Archive<R> {
         loop {
             let entry = self.next_raw_item_async().await?;
             match entry {
-                Some(entry) => match entry.try_into()? {
+                Some(entry) => match ReadEntry::try_from(entry)?
+                    .with_zstd_dictionary(self.zstd_dictionary.as_ref())
+                {
                     ReadEntry::Solid(_) => continue,
                     ReadEntry::Normal(entry) => return Ok(Some(entry)),
                 },
@@ -301,14 +323,55 @@ impl<R: AsyncRead + Unpin> Archive<R> {
     }
 }
 
+/// Splits the chunk following the [`ChunkType::AHED`] chunk into an archive id and a chunk to be read next.
+#[inline]
+fn split_archive_id(chunk: RawChunk) -> io::Result<(Option<ArchiveId>, Option<RawChunk>)> {
+    if chunk.ty == ChunkType::aIDN {
+        Ok((Some(archive_id_from_bytes(chunk.data())?), None))
+    } else {
+        Ok((None, Some(chunk)))
+    }
+}
+
+pub(crate) fn verify_next_archive(
+    current_header: &ArchiveHeader,
+    current_id: Option<ArchiveId>,
+    next_header: &ArchiveHeader,
+    next_id: Option<ArchiveId>,
+) -> io::Result<()> {
+    if current_header.archive_number + 1 != next_header.archive_number {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Next archive number must be +1 (current: {}, detected: {})",
+                current_header.archive_number, next_header.archive_number
+            ),
+        ));
+    }
+    // NOTE: Archives created by older versions have no id, so skip the validation.
+    if let (Some(current), Some(next)) = (current_id, next_id) {
+        if current != next {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Next archive is not a part of the same archive (archive id mismatch)",
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub(crate) struct RawEntries<'r, R>(&'r mut Archive<R>);
 
 impl<R: Read> Iterator for RawEntries<'_, R> {
-    type Item = io::Result<RawEntry>;
+    type Item = io::Result<ArchiveRawEntry>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next_raw_item().transpose()
+        let entry = self.0.next_raw_item().transpose()?;
+        Some(entry.map(|entry| ArchiveRawEntry {
+            entry,
+            zstd_dictionary: self.0.zstd_dictionary.clone(),
+        }))
     }
 }
 
@@ -397,6 +460,9 @@ impl<R: Read> Iterator for NormalEntries<'_, R> {
 impl<R: Read + Seek> Archive<R> {
     /// Seek the cursor to the end of the archive marker.
     ///
+    /// This also works on any part of a split archive, which makes it possible to
+    /// append entries to the last part of a multipart archive.
+    ///
     /// # Examples
     /// For appending entry to the existing archive.
     /// ```no_run
@@ -418,6 +484,15 @@ impl<R: Read + Seek> Archive<R> {
     /// ```
     #[inline]
     pub fn seek_to_end(&mut self) -> io::Result<()> {
+        if let Some(chunk) = self.pending.take() {
+            if chunk.ty == ChunkType::AEND {
+                self.inner
+                    .seek(SeekFrom::Current(-(chunk.bytes_len() as i64)))?;
+                return Ok(());
+            } else if chunk.ty == ChunkType::ANXT {
+                self.next_archive = true;
+            }
+        }
         let mut reader = ChunkReader::from(&mut self.inner);
         let byte = loop {
             let (ty, byte_length) = reader.skip_chunk()?;
@@ -0,0 +1,310 @@
+use super::{read_pna_header, split_archive_id};
+use crate::{
+    archive::Archive,
+    chunk::{ChunkReader, ChunkType},
+    entry::{EntryName, NormalEntry},
+};
+use std::io::{self, Read, Seek, SeekFrom};
+
+impl<R: Read> Archive<R> {
+    /// Finds the entry named `name`, reading the archive forward from the current position.
+    ///
+    /// `name` is normalized in the same way as [EntryName], so `./dir/file.txt` matches `dir/file.txt`.
+    /// Entries in solid entries are skipped. Use [Archive::find_entry_with_password] to find them.
+    ///
+    /// The entries before the found entry are consumed, so the next call starts after the found entry.
+    /// If no entry is found, all the remaining entries are consumed.
+    /// For a [Seek] reader, use [Archive::rewind_entries] to search from the first entry again.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use libpna::{Archive, ReadOptions};
+    /// use std::{fs, io};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::open("foo.pna")?;
+    /// let mut archive = Archive::read_header(file)?;
+    /// if let Some(entry) = archive.find_entry("config/app.toml")? {
+    ///     let reader = entry.reader(ReadOptions::builder().build())?;
+    ///     let config = io::read_to_string(reader)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading from the archive.
+    #[inline]
+    pub fn find_entry(&mut self, name: &str) -> io::Result<Option<NormalEntry>> {
+        let name = EntryName::from(name);
+        for entry in self.entries_skip_solid() {
+            let entry = entry?;
+            if entry.header().path() == &name {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Same as [Archive::find_entry], but also finds the entries in solid entries,
+    /// decrypting them with `password`.
+    ///
+    /// When the entry is found in a solid entry, the following entries in the same solid entry are consumed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use libpna::Archive;
+    /// use std::{fs, io};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::open("foo.pna")?;
+    /// let mut archive = Archive::read_header(file)?;
+    /// let entry = archive.find_entry_with_password("config/app.toml", Some("password"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading from the archive,
+    /// or if a solid entry can not be decrypted with `password`.
+    #[inline]
+    pub fn find_entry_with_password(
+        &mut self,
+        name: &str,
+        password: Option<&str>,
+    ) -> io::Result<Option<NormalEntry>> {
+        let name = EntryName::from(name);
+        for entry in self.entries_with_password(password) {
+            let entry = entry?;
+            if entry.header().path() == &name {
+                return Ok(Some(entry));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns an iterator over the entries whose names match `pattern`, excluding entries in solid mode.
+    ///
+    /// In `pattern`, `*` matches any sequence of characters including `/`,
+    /// and `?` matches any single character. Other characters match themselves.
+    /// `pattern` is normalized in the same way as [EntryName].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use libpna::Archive;
+    /// use std::{fs, io};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::open("foo.pna")?;
+    /// let mut archive = Archive::read_header(file)?;
+    /// for entry in archive.find_entries_glob("config/*.toml") {
+    ///     println!("{}", entry?.header().path());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn find_entries_glob(
+        &mut self,
+        pattern: &str,
+    ) -> impl Iterator<Item = io::Result<NormalEntry>> + '_ {
+        let pattern = EntryName::from(pattern);
+        self.entries_skip_solid()
+            .filter(move |it| matches_entry(&pattern, it))
+    }
+
+    /// Same as [Archive::find_entries_glob], but also returns the entries in solid entries,
+    /// decrypting them with `password`.
+    #[inline]
+    pub fn find_entries_glob_with_password<'a>(
+        &'a mut self,
+        pattern: &str,
+        password: Option<&'a str>,
+    ) -> impl Iterator<Item = io::Result<NormalEntry>> + 'a {
+        let pattern = EntryName::from(pattern);
+        self.entries_with_password(password)
+            .filter(move |it| matches_entry(&pattern, it))
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Moves the cursor back to the first entry, so that the entries can be read again.
+    ///
+    /// The archive header must be at the start of the reader.
+    /// This rewinds the current part only, even after reading through split archives.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use libpna::Archive;
+    /// use std::{fs, io};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::open("foo.pna")?;
+    /// let mut archive = Archive::read_header(file)?;
+    /// let readme = archive.find_entry("README.md")?;
+    /// archive.rewind_entries()?;
+    /// let license = archive.find_entry("LICENSE")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while seeking or reading the archive header.
+    #[inline]
+    pub fn rewind_entries(&mut self) -> io::Result<()> {
+        // NOTE: Entries are read sequentially, because the archive has no entry index.
+        self.inner.seek(SeekFrom::Start(0))?;
+        read_pna_header(&mut self.inner)?;
+        let mut reader = ChunkReader::from(&mut self.inner);
+        let (ty, _) = reader.skip_chunk()?;
+        if ty != ChunkType::AHED {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected Chunk `{ty}`"),
+            ));
+        }
+        let (_, pending) = split_archive_id(reader.read_chunk()?)?;
+        self.buf.clear();
+        self.extra_chunks.clear();
+        self.pending = pending;
+        self.next_archive = false;
+        Ok(())
+    }
+}
+
+#[inline]
+fn matches_entry(pattern: &EntryName, entry: &io::Result<NormalEntry>) -> bool {
+    match entry {
+        Ok(entry) => glob_match(pattern.as_str(), entry.header().path().as_str()),
+        Err(_) => true,
+    }
+}
+
+/// Returns `true` if `name` matches `pattern`, where `*` matches any sequence and `?` matches any character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    // Position of the last `*` and the position in `name` it is matched up to.
+    let mut star = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, n));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntryBuilder, ReadOptions, SolidEntryBuilder, WriteOptions};
+    use std::io::{prelude::*, Cursor};
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn file_entry(name: &str) -> NormalEntry {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(name.as_bytes()).unwrap();
+        builder.build().unwrap()
+    }
+
+    fn archive() -> Vec<u8> {
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        archive.add_entry(file_entry("a.txt")).unwrap();
+        let mut solid = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        solid.add_entry(file_entry("dir/b.txt")).unwrap();
+        solid.add_entry(file_entry("dir/c.txt")).unwrap();
+        archive.add_entry(solid.build().unwrap()).unwrap();
+        archive.add_entry(file_entry("dir/d.txt")).unwrap();
+        archive.finalize().unwrap()
+    }
+
+    fn read_content(entry: NormalEntry) -> String {
+        io::read_to_string(entry.reader(ReadOptions::builder().build()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn find_entry_found() {
+        let bytes = archive();
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        let entry = archive.find_entry("./dir/d.txt").unwrap().unwrap();
+        assert_eq!(read_content(entry), "dir/d.txt");
+    }
+
+    #[test]
+    fn find_entry_not_found() {
+        let bytes = archive();
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        assert!(archive.find_entry("missing.txt").unwrap().is_none());
+
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        assert!(archive.find_entry("dir/d.txt").unwrap().is_some());
+        // The preceding entries are consumed.
+        assert!(archive.find_entry("a.txt").unwrap().is_none());
+    }
+
+    #[test]
+    fn find_entry_in_solid() {
+        let bytes = archive();
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        assert!(archive.find_entry("dir/c.txt").unwrap().is_none());
+
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        let entry = archive
+            .find_entry_with_password("dir/c.txt", None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(read_content(entry), "dir/c.txt");
+    }
+
+    #[test]
+    fn find_entry_repeatedly_with_rewind() {
+        let mut archive = Archive::read_header(Cursor::new(archive())).unwrap();
+        for name in ["dir/d.txt", "a.txt", "dir/d.txt"] {
+            let entry = archive.find_entry(name).unwrap().unwrap();
+            assert_eq!(read_content(entry), name);
+            archive.rewind_entries().unwrap();
+        }
+        assert_eq!(archive.entries().count(), 3);
+    }
+
+    #[test]
+    fn find_entries_by_glob() {
+        let bytes = archive();
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        let names = archive
+            .find_entries_glob_with_password("dir/*.txt", None)
+            .map(|it| it.unwrap().header().path().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["dir/b.txt", "dir/c.txt", "dir/d.txt"]);
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("*", "a/b.txt"));
+        assert!(glob_match("a/?.txt", "a/b.txt"));
+        assert!(glob_match("*.t*t", "a.txt"));
+        assert!(!glob_match("*.toml", "a.txt"));
+        assert!(!glob_match("a?", "a"));
+        assert!(glob_match("a**", "a"));
+    }
+}
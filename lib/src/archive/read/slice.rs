@@ -1,6 +1,8 @@
 use crate::{
-    archive::ArchiveHeader, chunk::read_chunk_from_slice, entry::RawEntry, Archive, Chunk,
-    ChunkType, Entry, NormalEntry, RawChunk, ReadEntry, PNA_HEADER,
+    archive::{archive_id_from_bytes, is_archive_extra_chunk, verify_next_archive, ArchiveHeader},
+    chunk::read_chunk_from_slice,
+    entry::{ArchiveRawEntry, RawEntry},
+    Archive, Chunk, ChunkType, Entry, NormalEntry, RawChunk, ReadEntry, PNA_HEADER,
 };
 use std::borrow::Cow;
 use std::io;
@@ -44,7 +46,13 @@ impl<'d> Archive<&'d [u8]> {
             ));
         }
         let header = ArchiveHeader::try_from_bytes(chunk.data())?;
-        Ok(Self::with_buffer(r, header, buf))
+        let (chunk, rest) = read_chunk_from_slice(r)?;
+        if chunk.ty == ChunkType::aIDN {
+            let archive_id = archive_id_from_bytes(chunk.data())?;
+            Ok(Self::with_buffer(rest, header, Some(archive_id), buf, None))
+        } else {
+            Ok(Self::with_buffer(r, header, None, buf, None))
+        }
     }
 
     /// Reads the next raw entry (from `FHED` to `FEND` chunk) from the archive.
@@ -69,10 +77,14 @@ impl<'d> Archive<&'d [u8]> {
                     break;
                 }
                 ChunkType::ANXT => self.next_archive = true,
+                ChunkType::zDIC => self.zstd_dictionary = Some(chunk.data().into()),
                 ChunkType::AEND => {
                     self.buf = chunks.into_iter().map(Into::into).collect::<Vec<_>>();
                     return Ok(None);
                 }
+                ty if chunks.is_empty() && is_archive_extra_chunk(ty) => {
+                    self.extra_chunks.push(chunk.into())
+                }
                 _ => chunks.push(chunk.into()),
             }
         }
@@ -91,7 +103,9 @@ impl<'d> Archive<&'d [u8]> {
     fn read_entry_slice(&mut self) -> io::Result<Option<ReadEntry<Cow<'d, [u8]>>>> {
         let entry = self.next_raw_item_slice()?;
         match entry {
-            Some(entry) => Ok(Some(entry.try_into()?)),
+            Some(entry) => Ok(Some(
+                ReadEntry::try_from(entry)?.with_zstd_dictionary(self.zstd_dictionary.as_ref()),
+            )),
             None => Ok(None),
         }
     }
@@ -174,16 +188,12 @@ impl<'d> Archive<&'d [u8]> {
     #[inline]
     pub fn read_next_archive_from_slice(self, bytes: &[u8]) -> io::Result<Archive<&[u8]>> {
         let current_header = self.header;
-        let next = Archive::read_header_from_slice_with_buffer(bytes, self.buf)?;
-        if current_header.archive_number + 1 != next.header.archive_number {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                format!(
-                    "Next archive number must be +1 (current: {}, detected: {})",
-                    current_header.archive_number, next.header.archive_number
-                ),
-            ));
-        }
+        let current_id = self.archive_id;
+        let mut next = Archive::read_header_from_slice_with_buffer(bytes, self.buf)?;
+        next.skipped_chunk_sizes = self.skipped_chunk_sizes;
+        next.zstd_dictionary = self.zstd_dictionary;
+        next.extra_chunks = self.extra_chunks;
+        verify_next_archive(&current_header, current_id, &next.header, next.archive_id)?;
         Ok(next)
     }
 }
@@ -191,11 +201,15 @@ impl<'d> Archive<&'d [u8]> {
 pub(crate) struct RawEntries<'a, 'r>(&'a mut Archive<&'r [u8]>);
 
 impl<'r> Iterator for RawEntries<'_, 'r> {
-    type Item = io::Result<RawEntry<Cow<'r, [u8]>>>;
+    type Item = io::Result<ArchiveRawEntry<Cow<'r, [u8]>>>;
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next_raw_item_slice().transpose()
+        let entry = self.0.next_raw_item_slice().transpose()?;
+        Some(entry.map(|entry| ArchiveRawEntry {
+            entry,
+            zstd_dictionary: self.0.zstd_dictionary.clone(),
+        }))
     }
 }
 
@@ -268,7 +282,7 @@ mod tests {
     #[test]
     fn read_header() {
         let result = read_header_from_slice(PNA_HEADER).unwrap();
-        assert_eq!(result, &[]);
+        assert_eq!(result, &[] as &[u8]);
     }
 
     #[test]
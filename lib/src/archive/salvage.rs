@@ -0,0 +1,412 @@
+use crate::{
+    archive::{is_archive_extra_chunk, read_header_from_slice, Archive, PNA_HEADER},
+    chunk::{try_read_chunk_from_slice, ChunkError, ChunkType, RawChunk},
+    entry::{EntryHeader, EntryName, RawEntry, ReadEntry},
+};
+use std::{collections::VecDeque, io, mem, sync::Arc};
+
+/// Why a part of an archive is dropped by [Archive::salvage_from_slice].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum DamageKind {
+    /// A chunk whose crc sum does not match its contents, and the chunks up to the next entry.
+    BrokenChunk,
+    /// Intact chunks that do not form an entry, such as an entry without its end chunk.
+    BrokenEntry,
+    /// The archive ends in the middle of an entry or without the end of archive chunk.
+    Truncated,
+}
+
+/// Part of an archive dropped by [Archive::salvage_from_slice].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Damage {
+    offset: u64,
+    length: u64,
+    kind: DamageKind,
+    name: Option<EntryName>,
+}
+
+impl Damage {
+    /// Byte offset of the dropped part from the start of the archive.
+    #[inline]
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Byte length of the dropped part, `0` for an archive that just misses its end.
+    #[inline]
+    pub const fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Why the part is dropped.
+    #[inline]
+    pub const fn kind(&self) -> DamageKind {
+        self.kind
+    }
+
+    /// Name of the dropped entry, if its header chunk is intact.
+    #[inline]
+    pub const fn name(&self) -> Option<&EntryName> {
+        self.name.as_ref()
+    }
+}
+
+/// Item of [Archive::salvage_from_slice].
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug)]
+pub enum Salvaged<'d> {
+    /// Entry whose chunks are all intact.
+    Entry(ReadEntry<&'d [u8]>),
+    /// Part of the archive that is dropped.
+    Damage(Damage),
+}
+
+impl<'d> Archive<&'d [u8]> {
+    /// Reads the entries of a damaged archive, skipping the broken parts of it.
+    ///
+    /// Unlike [Archive::entries_slice], reading does not stop at a broken chunk.
+    /// The entry containing the broken chunk is dropped, and reading resumes at the next intact
+    /// chunk that starts an entry or ends the archive. The returned iterator yields the intact entries
+    /// and the dropped parts in the order they are stored.
+    ///
+    /// A chunk is considered intact when its crc sum matches, so an entry whose contents are damaged
+    /// only after the chunks are made, e.g. by a wrong password, is still yielded.
+    ///
+    /// # Examples
+    /// ```
+    /// use libpna::{Archive, Salvaged};
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let bytes = include_bytes!("../../../resources/test/zstd.pna");
+    /// let mut output = Archive::write_header(Vec::new())?;
+    /// for item in Archive::salvage_from_slice(&bytes[..])? {
+    ///     match item {
+    ///         Salvaged::Entry(entry) => {
+    ///             output.add_entry(entry)?;
+    ///         }
+    ///         Salvaged::Damage(damage) => {
+    ///             eprintln!("{} bytes at {} dropped", damage.length(), damage.offset());
+    ///         }
+    ///     }
+    /// }
+    /// output.finalize()?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `bytes` does not start with the magic number of PNA.
+    #[inline]
+    pub fn salvage_from_slice(bytes: &'d [u8]) -> io::Result<impl Iterator<Item = Salvaged<'d>>> {
+        if bytes.len() < PNA_HEADER.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "It's not PNA"));
+        }
+        read_header_from_slice(bytes)?;
+        Ok(Salvage {
+            bytes,
+            pos: PNA_HEADER.len(),
+            chunks: Vec::new(),
+            entry_start: 0,
+            zstd_dictionary: None,
+            pending: None,
+            ready: VecDeque::new(),
+            done: false,
+        })
+    }
+}
+
+struct Salvage<'d> {
+    bytes: &'d [u8],
+    pos: usize,
+    /// Chunks of the entry being read.
+    chunks: Vec<RawChunk<&'d [u8]>>,
+    entry_start: usize,
+    zstd_dictionary: Option<Arc<[u8]>>,
+    /// Damage that may be merged with the following one.
+    pending: Option<Damage>,
+    ready: VecDeque<Salvaged<'d>>,
+    done: bool,
+}
+
+impl<'d> Salvage<'d> {
+    fn push_damage(&mut self, start: usize, end: usize, kind: DamageKind, name: Option<EntryName>) {
+        let damage = Damage {
+            offset: start as u64,
+            length: (end - start) as u64,
+            kind,
+            name,
+        };
+        // NOTE: Stray chunks are reported together instead of one by one.
+        if let Some(pending) = &mut self.pending {
+            if pending.kind == damage.kind
+                && pending.name.is_none()
+                && damage.name.is_none()
+                && pending.offset + pending.length == damage.offset
+            {
+                pending.length += damage.length;
+                return;
+            }
+        }
+        self.ready
+            .extend(self.pending.replace(damage).map(Salvaged::Damage));
+    }
+
+    fn push_entry(&mut self, entry: ReadEntry<&'d [u8]>) {
+        self.ready.extend(self.pending.take().map(Salvaged::Damage));
+        self.ready.push_back(Salvaged::Entry(entry));
+    }
+
+    /// Drops the entry being read, and returns its start and name.
+    fn take_entry(&mut self) -> Option<(usize, Option<EntryName>)> {
+        let chunks = mem::take(&mut self.chunks);
+        Some((self.entry_start, entry_name(chunks.first()?)))
+    }
+
+    /// Drops the entry being read as [DamageKind::BrokenEntry] if any.
+    fn drop_entry(&mut self, end: usize) {
+        if let Some((start, name)) = self.take_entry() {
+            self.push_damage(start, end, DamageKind::BrokenEntry, name);
+        }
+    }
+
+    /// Returns the offset of the first intact chunk after `pos` that starts an entry or ends the archive.
+    fn find_boundary(&self, pos: usize) -> Option<usize> {
+        const BOUNDARIES: [ChunkType; 3] = [ChunkType::FHED, ChunkType::SHED, ChunkType::AEND];
+        let ty_offset = mem::size_of::<u32>();
+        (pos..self.bytes.len()).find(|&offset| {
+            let Some(ty) = self.bytes.get(offset + ty_offset..) else {
+                return false;
+            };
+            BOUNDARIES.iter().any(|it| ty.starts_with(&it.0))
+                && try_read_chunk_from_slice(&self.bytes[offset..]).is_ok()
+        })
+    }
+
+    fn read_chunk(&mut self, chunk: RawChunk<&'d [u8]>, start: usize) {
+        match chunk.ty {
+            ChunkType::FHED | ChunkType::SHED => {
+                self.drop_entry(start);
+                self.entry_start = start;
+                self.chunks.push(chunk);
+            }
+            ChunkType::FEND | ChunkType::SEND if !self.chunks.is_empty() => {
+                self.chunks.push(chunk);
+                let chunks = mem::take(&mut self.chunks);
+                let name = entry_name(&chunks[0]);
+                match ReadEntry::try_from(RawEntry(chunks)) {
+                    Ok(entry) => {
+                        self.push_entry(entry.with_zstd_dictionary(self.zstd_dictionary.as_ref()))
+                    }
+                    Err(_) => {
+                        self.push_damage(self.entry_start, self.pos, DamageKind::BrokenEntry, name)
+                    }
+                }
+            }
+            ChunkType::ANXT => (),
+            ChunkType::zDIC => self.zstd_dictionary = Some(chunk.data.into()),
+            ChunkType::AEND => {
+                self.drop_entry(start);
+                self.done = true;
+            }
+            _ if !self.chunks.is_empty() => self.chunks.push(chunk),
+            ChunkType::AHED | ChunkType::aIDN => (),
+            ty if is_archive_extra_chunk(ty) => (),
+            _ => self.push_damage(start, self.pos, DamageKind::BrokenEntry, None),
+        }
+    }
+
+    fn step(&mut self) {
+        let len = self.bytes.len();
+        if self.pos == len {
+            self.done = true;
+            let (start, name) = self.take_entry().unwrap_or((len, None));
+            self.push_damage(start, len, DamageKind::Truncated, name);
+            return;
+        }
+        match try_read_chunk_from_slice(&self.bytes[self.pos..]) {
+            Ok((chunk, rest)) => {
+                let start = mem::replace(&mut self.pos, len - rest.len());
+                self.read_chunk(chunk, start);
+            }
+            Err(e) => {
+                let (start, name) = self.take_entry().unwrap_or((self.pos, None));
+                match self.find_boundary(self.pos + 1) {
+                    Some(next) => {
+                        self.push_damage(start, next, DamageKind::BrokenChunk, name);
+                        self.pos = next;
+                    }
+                    None => {
+                        let kind = match e {
+                            ChunkError::Truncated => DamageKind::Truncated,
+                            ChunkError::BrokenCrc => DamageKind::BrokenChunk,
+                        };
+                        self.push_damage(start, len, kind, name);
+                        self.done = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the name of the entry starting with `first`, if it is an intact entry header.
+fn entry_name(first: &RawChunk<&[u8]>) -> Option<EntryName> {
+    if first.ty != ChunkType::FHED {
+        return None;
+    }
+    let header = EntryHeader::try_from(first.data).ok()?;
+    Some(header.path().clone())
+}
+
+impl<'d> Iterator for Salvage<'d> {
+    type Item = Salvaged<'d>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+            if self.done {
+                return self.pending.take().map(Salvaged::Damage);
+            }
+            self.step();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntryBuilder, SolidEntryBuilder, WriteOptions};
+    use std::io::Write;
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn file_entry(name: &str) -> crate::NormalEntry {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(name.repeat(10).as_bytes()).unwrap();
+        builder.build().unwrap()
+    }
+
+    fn archive() -> Vec<u8> {
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        archive.add_entry(file_entry("a.txt")).unwrap();
+        archive.add_entry(file_entry("b.txt")).unwrap();
+        let mut solid = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        solid.add_entry(file_entry("c.txt")).unwrap();
+        archive.add_entry(solid.build().unwrap()).unwrap();
+        archive.add_entry(file_entry("d.txt")).unwrap();
+        archive.finalize().unwrap()
+    }
+
+    /// Returns the names of the salvaged entries, and the damages.
+    fn salvage(bytes: &[u8]) -> (Vec<String>, Vec<Damage>) {
+        let mut names = Vec::new();
+        let mut damages = Vec::new();
+        for item in Archive::salvage_from_slice(bytes).unwrap() {
+            match item {
+                Salvaged::Entry(ReadEntry::Normal(entry)) => {
+                    names.push(entry.header().path().to_string())
+                }
+                Salvaged::Entry(ReadEntry::Solid(solid)) => {
+                    for entry in solid.entries(None).unwrap() {
+                        names.push(entry.unwrap().header().path().to_string());
+                    }
+                }
+                Salvaged::Damage(damage) => damages.push(damage),
+            }
+        }
+        (names, damages)
+    }
+
+    /// Returns the offset of the `n`th occurrence of `pattern` in `bytes`.
+    fn find(bytes: &[u8], pattern: &[u8], n: usize) -> usize {
+        bytes
+            .windows(pattern.len())
+            .enumerate()
+            .filter(|(_, it)| *it == pattern)
+            .nth(n)
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn intact() {
+        let (names, damages) = salvage(&archive());
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt", "d.txt"]);
+        assert!(damages.is_empty());
+    }
+
+    #[test]
+    fn broken_data() {
+        let mut bytes = archive();
+        // The contents of b.txt are stored as is.
+        let offset = find(&bytes, b"b.txtb.txt", 0);
+        bytes[offset + 3] ^= 0xff;
+        let (names, damages) = salvage(&bytes);
+        assert_eq!(names, ["a.txt", "c.txt", "d.txt"]);
+        assert_eq!(damages.len(), 1);
+        assert_eq!(damages[0].kind(), DamageKind::BrokenChunk);
+        assert_eq!(damages[0].name().map(|it| it.as_str()), Some("b.txt"));
+        let start = damages[0].offset() as usize;
+        let end = start + damages[0].length() as usize;
+        assert_eq!(&bytes[start + 4..start + 8], b"FHED");
+        assert_eq!(&bytes[end + 4..end + 8], b"SHED");
+    }
+
+    #[test]
+    fn broken_length() {
+        let mut bytes = archive();
+        // The length of the FDAT chunk of a.txt
+        let offset = find(&bytes, b"FDAT", 0) - 4;
+        bytes[offset] = 0xff;
+        let (names, damages) = salvage(&bytes);
+        assert_eq!(names, ["b.txt", "c.txt", "d.txt"]);
+        assert_eq!(damages.len(), 1);
+        assert_eq!(damages[0].kind(), DamageKind::BrokenChunk);
+        assert_eq!(damages[0].name().map(|it| it.as_str()), Some("a.txt"));
+    }
+
+    #[test]
+    fn missing_end_chunk() {
+        let bytes = archive();
+        let fend = find(&bytes, b"FEND", 1) - 4;
+        let bytes = [&bytes[..fend], &bytes[fend + 12..]].concat();
+        let (names, damages) = salvage(&bytes);
+        assert_eq!(names, ["a.txt", "c.txt", "d.txt"]);
+        assert_eq!(damages.len(), 1);
+        assert_eq!(damages[0].kind(), DamageKind::BrokenEntry);
+        assert_eq!(damages[0].name().map(|it| it.as_str()), Some("b.txt"));
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = archive();
+        let offset = find(&bytes, b"d.txtd.txt", 0);
+        let (names, damages) = salvage(&bytes[..offset]);
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt"]);
+        assert_eq!(damages.len(), 1);
+        assert_eq!(damages[0].kind(), DamageKind::Truncated);
+        assert_eq!(damages[0].name().map(|it| it.as_str()), Some("d.txt"));
+        assert_eq!(damages[0].offset() + damages[0].length(), offset as u64);
+    }
+
+    #[test]
+    fn missing_end_of_archive() {
+        let bytes = archive();
+        let (names, damages) = salvage(&bytes[..bytes.len() - 12]);
+        assert_eq!(names, ["a.txt", "b.txt", "c.txt", "d.txt"]);
+        assert_eq!(damages.len(), 1);
+        assert_eq!(damages[0].kind(), DamageKind::Truncated);
+        assert_eq!(damages[0].length(), 0);
+    }
+
+    #[test]
+    fn not_pna() {
+        assert!(Archive::salvage_from_slice(b"PNA").is_err());
+        assert!(Archive::salvage_from_slice(b"not a pna archive").is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use crate::{
+    archive::{is_archive_extra_chunk, read_pna_header, Archive, PNA_HEADER},
+    chunk::{ChunkExt, ChunkReader, ChunkType},
+    entry::{DataKind, EntryName, Metadata, NormalEntry, RawEntry, ReadEntry},
+};
+use std::{
+    io::{self, Read},
+    mem,
+    sync::Arc,
+};
+
+/// Location and information of an entry found by [Archive::scan].
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct EntryRecord {
+    name: EntryName,
+    data_kind: DataKind,
+    offset: u64,
+    length: u64,
+    solid_index: Option<usize>,
+    metadata: Metadata,
+}
+
+impl EntryRecord {
+    #[inline]
+    fn new<T>(
+        entry: &NormalEntry<T>,
+        offset: u64,
+        length: u64,
+        solid_index: Option<usize>,
+    ) -> Self {
+        Self {
+            name: entry.header().path().clone(),
+            data_kind: entry.header().data_kind(),
+            offset,
+            length,
+            solid_index,
+            metadata: entry.metadata().clone(),
+        }
+    }
+
+    /// Name of the entry.
+    #[inline]
+    pub const fn name(&self) -> &EntryName {
+        &self.name
+    }
+
+    /// Data kind of the entry.
+    #[inline]
+    pub const fn data_kind(&self) -> DataKind {
+        self.data_kind
+    }
+
+    /// Byte offset of the first chunk of the entry from the start of the archive.
+    /// For an entry in a solid entry, the offset of the solid entry.
+    #[inline]
+    pub const fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Total byte length of the chunks of the entry.
+    /// For an entry in a solid entry, the length of the solid entry.
+    #[inline]
+    pub const fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Index of the solid entry containing the entry among the solid entries in the archive,
+    /// or `None` if the entry is not in a solid entry.
+    #[inline]
+    pub const fn solid_index(&self) -> Option<usize> {
+        self.solid_index
+    }
+
+    /// Metadata of the entry.
+    #[inline]
+    pub const fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+impl<R: Read> Archive<R> {
+    /// Scans an archive from `reader` and calls `visitor` with the [EntryRecord] of each entry,
+    /// in the order they are stored.
+    ///
+    /// The offsets are counted while reading the chunks, so `reader` does not need to be [Seek](io::Seek).
+    /// Entries in solid entries are reported with the location of the solid entry that contains them.
+    /// An entry that continues to the next part of a split archive is not reported.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use libpna::Archive;
+    /// use std::{fs, io};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::open("foo.pna")?;
+    /// Archive::scan(file, |record| {
+    ///     println!("{} {} {}", record.name(), record.offset(), record.length());
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading from `reader`, if the archive is broken,
+    /// or if a solid entry is encrypted. Use [Archive::scan_with_password] for encrypted solid entries.
+    #[inline]
+    pub fn scan(reader: R, visitor: impl FnMut(EntryRecord)) -> io::Result<()> {
+        Self::scan_with_password(reader, None, visitor)
+    }
+
+    /// Same as [Archive::scan], but decrypts solid entries with `password`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading from `reader`, if the archive is broken,
+    /// or if a solid entry can not be decrypted with `password`.
+    #[inline]
+    pub fn scan_with_password(
+        reader: R,
+        password: Option<&str>,
+        visitor: impl FnMut(EntryRecord),
+    ) -> io::Result<()> {
+        Self::scan_for_dictionary(reader, password, visitor).map(|_| ())
+    }
+
+    /// Same as [Archive::scan_with_password], but returns the zstd dictionary of the archive.
+    pub(crate) fn scan_for_dictionary(
+        mut reader: R,
+        password: Option<&str>,
+        mut visitor: impl FnMut(EntryRecord),
+    ) -> io::Result<Option<Arc<[u8]>>> {
+        read_pna_header(&mut reader)?;
+        let mut chunk_reader = ChunkReader::from(reader);
+        let header = chunk_reader.read_chunk()?;
+        if header.ty != ChunkType::AHED {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Unexpected Chunk `{}`", header.ty),
+            ));
+        }
+        let mut offset = (PNA_HEADER.len() + header.bytes_len()) as u64;
+        let mut start = offset;
+        let mut solid_index = 0;
+        let mut chunks = Vec::new();
+        let mut zstd_dictionary = None;
+        loop {
+            let chunk = chunk_reader.read_chunk()?;
+            let chunk_len = chunk.bytes_len() as u64;
+            match chunk.ty {
+                ChunkType::aIDN | ChunkType::ANXT => (),
+                ChunkType::zDIC => zstd_dictionary = Some(chunk.data.into()),
+                ChunkType::AEND => break,
+                ty if chunks.is_empty() && is_archive_extra_chunk(ty) => (),
+                ty => {
+                    if chunks.is_empty() {
+                        start = offset;
+                    }
+                    chunks.push(chunk);
+                    if ty == ChunkType::FEND || ty == ChunkType::SEND {
+                        let length = offset + chunk_len - start;
+                        match ReadEntry::try_from(RawEntry(mem::take(&mut chunks)))?
+                            .with_zstd_dictionary(zstd_dictionary.as_ref())
+                        {
+                            ReadEntry::Normal(entry) => {
+                                visitor(EntryRecord::new(&entry, start, length, None))
+                            }
+                            ReadEntry::Solid(solid) => {
+                                for entry in solid.entries(password)? {
+                                    visitor(EntryRecord::new(
+                                        &entry?,
+                                        start,
+                                        length,
+                                        Some(solid_index),
+                                    ));
+                                }
+                                solid_index += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            offset += chunk_len;
+        }
+        Ok(zstd_dictionary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chunk::MIN_CHUNK_BYTES_SIZE, EntryBuilder, SolidEntryBuilder, WriteOptions};
+    use std::io::{Cursor, Seek, SeekFrom, Write};
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn file_entry(name: &str) -> NormalEntry {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(name.as_bytes()).unwrap();
+        builder.build().unwrap()
+    }
+
+    fn read_chunk_type_at(reader: &mut Cursor<&[u8]>, offset: u64) -> ChunkType {
+        reader.seek(SeekFrom::Start(offset)).unwrap();
+        ChunkReader::from(reader).read_chunk().unwrap().ty
+    }
+
+    #[test]
+    fn scan_offsets() {
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        archive.add_entry(file_entry("a.txt")).unwrap();
+        let mut solid = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        solid.add_entry(file_entry("b.txt")).unwrap();
+        solid.add_entry(file_entry("c.txt")).unwrap();
+        archive.add_entry(solid.build().unwrap()).unwrap();
+        archive.add_entry(file_entry("d.txt")).unwrap();
+        let bytes = archive.finalize().unwrap();
+
+        let mut records = Vec::new();
+        Archive::scan(bytes.as_slice(), |record| records.push(record)).unwrap();
+
+        assert_eq!(
+            records
+                .iter()
+                .map(|it| (it.name().as_str(), it.solid_index()))
+                .collect::<Vec<_>>(),
+            [
+                ("a.txt", None),
+                ("b.txt", Some(0)),
+                ("c.txt", Some(0)),
+                ("d.txt", None)
+            ]
+        );
+        let mut reader = Cursor::new(bytes.as_slice());
+        for record in &records {
+            let (first, last) = if record.solid_index().is_some() {
+                (ChunkType::SHED, ChunkType::SEND)
+            } else {
+                (ChunkType::FHED, ChunkType::FEND)
+            };
+            assert_eq!(read_chunk_type_at(&mut reader, record.offset()), first);
+            // The end chunk has no data.
+            let end = record.offset() + record.length() - MIN_CHUNK_BYTES_SIZE as u64;
+            assert_eq!(read_chunk_type_at(&mut reader, end), last);
+        }
+        assert_eq!(records[1].offset(), records[2].offset());
+        assert_eq!(
+            records[2].offset() + records[2].length(),
+            records[3].offset()
+        );
+    }
+}
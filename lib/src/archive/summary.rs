@@ -0,0 +1,367 @@
+use crate::{
+    archive::{is_archive_extra_chunk, Archive},
+    chunk::{Chunk, ChunkReader, ChunkType, RawChunk},
+    entry::{DataKind, EntryHeader, ExtendedAttribute, Metadata, NormalEntry, RawEntry},
+};
+use std::{
+    io::{self, Read, Seek, SeekFrom},
+    mem,
+};
+
+/// Header and metadata of an entry read by [Archive::entries_metadata] without reading the data of a file entry.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct EntrySummary {
+    entry: NormalEntry,
+    chunk_sizes: Vec<usize>,
+}
+
+impl EntrySummary {
+    /// Header of the entry.
+    #[inline]
+    pub fn header(&self) -> &EntryHeader {
+        self.entry.header()
+    }
+
+    /// Metadata of the entry.
+    /// The compressed size includes the size of the data chunks that were skipped.
+    #[inline]
+    pub fn metadata(&self) -> &Metadata {
+        self.entry.metadata()
+    }
+
+    /// Extended attributes of the entry.
+    #[inline]
+    pub fn xattrs(&self) -> &[ExtendedAttribute] {
+        self.entry.xattrs()
+    }
+
+    /// Sizes of the data chunks of the entry, in the order they are stored.
+    #[inline]
+    pub fn chunk_sizes(&self) -> &[usize] {
+        &self.chunk_sizes
+    }
+
+    /// Returns the entry read without the data of a file entry.
+    ///
+    /// The data of the other kinds of entries, such as the target of a link, is kept and can be read.
+    /// The data chunks of a file entry are empty, so it must not be read or added to an archive.
+    #[inline]
+    pub fn entry(&self) -> &NormalEntry {
+        &self.entry
+    }
+}
+
+/// An iterator over the [EntrySummary] of the entries in an archive.
+///
+/// This struct is created by [Archive::entries_metadata] and [Archive::entries_metadata_with_seek].
+pub struct EntrySummaries<'r, R> {
+    archive: &'r mut Archive<R>,
+    skip: fn(&mut R, u64) -> io::Result<()>,
+    solid_entries: usize,
+}
+
+impl<R> EntrySummaries<'_, R> {
+    /// Number of solid entries skipped so far.
+    /// The entries in solid entries are not reported because their headers are in the compressed data.
+    #[inline]
+    pub fn solid_entry_count(&self) -> usize {
+        self.solid_entries
+    }
+}
+
+impl<R: Read> Iterator for EntrySummaries<'_, R> {
+    type Item = io::Result<EntrySummary>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.archive.next_summary(self.skip) {
+                Ok(Some(Summary::Normal(summary))) => return Some(Ok(summary)),
+                Ok(Some(Summary::Solid)) => self.solid_entries += 1,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Summary {
+    Normal(EntrySummary),
+    Solid,
+}
+
+/// Returns `true` if the data chunks of the entry starting with `chunk` are skipped.
+#[inline]
+fn skips_data(chunk: &RawChunk) -> io::Result<bool> {
+    match chunk.ty {
+        ChunkType::SHED => Ok(true),
+        ChunkType::FHED => Ok(EntryHeader::try_from(chunk.data())?.data_kind() == DataKind::File),
+        _ => Ok(false),
+    }
+}
+
+fn discard<R: Read>(reader: &mut R, length: u64) -> io::Result<()> {
+    if io::copy(&mut reader.take(length), &mut io::sink())? != length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+fn seek_forward<R: Seek>(reader: &mut R, length: u64) -> io::Result<()> {
+    reader.seek(SeekFrom::Current(length as i64))?;
+    Ok(())
+}
+
+impl<R: Read> Archive<R> {
+    /// Returns an iterator over the [EntrySummary] of the entries in the archive.
+    ///
+    /// The data chunks of file entries and solid entries are read and discarded without being
+    /// decoded or checked, so listing an archive does not hold the data in memory.
+    /// Use [Archive::entries_metadata_with_seek] to seek over them when the reader supports it.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use libpna::Archive;
+    /// use std::fs;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::open("foo.pna")?;
+    /// let mut archive = Archive::read_header(file)?;
+    /// for summary in archive.entries_metadata() {
+    ///     let summary = summary?;
+    ///     println!("{} {}", summary.header().path(), summary.metadata().compressed_size());
+    /// }
+    /// #    Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn entries_metadata(&mut self) -> EntrySummaries<'_, R> {
+        EntrySummaries {
+            archive: self,
+            skip: discard,
+            solid_entries: 0,
+        }
+    }
+
+    /// Counts the remaining entries in the archive by reading only the lengths and types of the chunks.
+    ///
+    /// A solid entry is counted as one entry, and an entry that continues from the previous part
+    /// of a split archive is counted in the part it ends in.
+    /// The data of the chunks are read and discarded without being checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading from the archive.
+    #[inline]
+    pub fn entry_count(&mut self) -> io::Result<usize> {
+        let mut count = 0;
+        let mut pending = self.pending.take().map(|chunk| chunk.ty);
+        let mut reader = ChunkReader::from(&mut self.inner);
+        loop {
+            let ty = match pending.take() {
+                Some(ty) => ty,
+                None => {
+                    let (length, ty) = reader.read_chunk_head()?;
+                    discard(reader.r, u64::from(length) + mem::size_of::<u32>() as u64)?;
+                    ty
+                }
+            };
+            match ty {
+                ChunkType::FEND | ChunkType::SEND => count += 1,
+                ChunkType::ANXT => self.next_archive = true,
+                ChunkType::AEND => return Ok(count),
+                _ => (),
+            }
+        }
+    }
+
+    fn next_summary(
+        &mut self,
+        skip: fn(&mut R, u64) -> io::Result<()>,
+    ) -> io::Result<Option<Summary>> {
+        let mut chunks = mem::take(&mut self.buf);
+        let mut chunk_sizes = mem::take(&mut self.skipped_chunk_sizes);
+        let mut skip_data = match chunks.first() {
+            Some(chunk) => skips_data(chunk)?,
+            None => false,
+        };
+        let mut reader = ChunkReader::from(&mut self.inner);
+        loop {
+            let chunk = match self.pending.take() {
+                Some(chunk) => chunk,
+                None => {
+                    let (length, ty) = reader.read_chunk_head()?;
+                    if skip_data && (ty == ChunkType::FDAT || ty == ChunkType::SDAT) {
+                        skip(reader.r, u64::from(length) + mem::size_of::<u32>() as u64)?;
+                        chunk_sizes.push(length as usize);
+                        continue;
+                    }
+                    reader.read_chunk_body(length, ty)?
+                }
+            };
+            match chunk.ty {
+                ChunkType::FEND | ChunkType::SEND => {
+                    chunks.push(chunk);
+                    break;
+                }
+                ChunkType::ANXT => self.next_archive = true,
+                ChunkType::zDIC => self.zstd_dictionary = Some(chunk.data.into()),
+                ChunkType::AEND => {
+                    self.buf = chunks;
+                    self.skipped_chunk_sizes = chunk_sizes;
+                    return Ok(None);
+                }
+                ChunkType::FDAT | ChunkType::SDAT if skip_data => {
+                    chunk_sizes.push(chunk.data.len())
+                }
+                ty if chunks.is_empty() && is_archive_extra_chunk(ty) => {
+                    self.extra_chunks.push(chunk)
+                }
+                _ => {
+                    if chunks.is_empty() {
+                        skip_data = skips_data(&chunk)?;
+                    }
+                    chunks.push(chunk);
+                }
+            }
+        }
+        if chunks
+            .first()
+            .is_some_and(|chunk| chunk.ty == ChunkType::SHED)
+        {
+            return Ok(Some(Summary::Solid));
+        }
+        let mut entry = NormalEntry::try_from(RawEntry(chunks))?;
+        entry.zstd_dictionary = self.zstd_dictionary.clone();
+        if skip_data {
+            entry.metadata.compressed_size = chunk_sizes.iter().sum();
+        } else {
+            chunk_sizes = entry.data.iter().map(Vec::len).collect();
+        }
+        Ok(Some(Summary::Normal(EntrySummary { entry, chunk_sizes })))
+    }
+}
+
+impl<R: Read + Seek> Archive<R> {
+    /// Same as [Archive::entries_metadata], but seeks over the data chunks of file entries and solid entries
+    /// instead of reading them, so that only the chunks of headers and metadata are read.
+    #[inline]
+    pub fn entries_metadata_with_seek(&mut self) -> EntrySummaries<'_, R> {
+        EntrySummaries {
+            archive: self,
+            skip: seek_forward,
+            solid_entries: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntryBuilder, EntryPart, ReadOptions, SolidEntryBuilder, WriteOptions};
+    use std::io::{Cursor, Write};
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn archive() -> Vec<u8> {
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        let mut builder = EntryBuilder::new_file("a.txt".into(), WriteOptions::store()).unwrap();
+        builder.write_all(b"content of a").unwrap();
+        archive.add_entry(builder.build().unwrap()).unwrap();
+        let mut solid = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        let mut builder = EntryBuilder::new_file("b.txt".into(), WriteOptions::store()).unwrap();
+        builder.write_all(b"content of b").unwrap();
+        solid.add_entry(builder.build().unwrap()).unwrap();
+        archive.add_entry(solid.build().unwrap()).unwrap();
+        let builder = EntryBuilder::new_symbolic_link("link".into(), "a.txt".into()).unwrap();
+        archive.add_entry(builder.build().unwrap()).unwrap();
+        let mut writer = archive
+            .entry_writer_with_chunk_size("large.bin".into(), WriteOptions::store(), 4)
+            .unwrap();
+        writer.write_all(b"0123456789").unwrap();
+        writer.finish().unwrap();
+        archive.finalize().unwrap()
+    }
+
+    fn assert_summaries(summaries: &mut EntrySummaries<impl Read>) {
+        let summaries_read = summaries.collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(
+            summaries_read
+                .iter()
+                .map(|it| (it.header().path().as_str(), it.chunk_sizes().to_vec()))
+                .collect::<Vec<_>>(),
+            [
+                ("a.txt", vec![12]),
+                ("link", vec![5]),
+                ("large.bin", vec![4, 4, 2])
+            ]
+        );
+        assert_eq!(summaries_read[2].metadata().compressed_size(), 10);
+        assert!(summaries_read[0].entry().data_chunks().is_empty());
+        let target = io::read_to_string(
+            summaries_read[1]
+                .entry()
+                .reader(ReadOptions::builder().build())
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(target, "a.txt");
+        assert_eq!(summaries.solid_entry_count(), 1);
+    }
+
+    #[test]
+    fn entries_metadata() {
+        let bytes = archive();
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        assert_summaries(&mut archive.entries_metadata());
+    }
+
+    #[test]
+    fn entries_metadata_with_seek() {
+        let bytes = archive();
+        let mut archive = Archive::read_header(Cursor::new(bytes)).unwrap();
+        assert_summaries(&mut archive.entries_metadata_with_seek());
+    }
+
+    #[test]
+    fn entries_metadata_across_parts() {
+        let mut builder =
+            EntryBuilder::new_file("split.bin".into(), WriteOptions::store()).unwrap();
+        builder.write_all(&[0; 100]).unwrap();
+        let part = EntryPart::from(builder.build().unwrap());
+        let max_bytes_len = part.bytes_len() - 50;
+        let (head, tail) = part.split(max_bytes_len);
+        let mut part1 = Vec::new();
+        let mut part2 = Vec::new();
+        let mut writer = Archive::write_header(&mut part1).unwrap();
+        writer.add_entry_part(head).unwrap();
+        let mut writer = writer.split_to_next_archive(&mut part2).unwrap();
+        writer.add_entry_part(tail.unwrap()).unwrap();
+        writer.finalize().unwrap();
+
+        let mut archive = Archive::read_header(part1.as_slice()).unwrap();
+        assert_eq!(archive.entries_metadata().count(), 0);
+        assert!(archive.has_next_archive());
+        assert!(archive.has_incomplete_entry());
+        let mut archive = archive.read_next_archive(part2.as_slice()).unwrap();
+        let summaries = archive
+            .entries_metadata()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].header().path().as_str(), "split.bin");
+        assert_eq!(summaries[0].chunk_sizes().len(), 2);
+        assert_eq!(summaries[0].chunk_sizes().iter().sum::<usize>(), 100);
+        assert_eq!(summaries[0].metadata().compressed_size(), 100);
+    }
+
+    #[test]
+    fn entry_count() {
+        let bytes = archive();
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        assert_eq!(archive.entry_count().unwrap(), 4);
+        assert!(!archive.has_next_archive());
+    }
+}
@@ -1,19 +1,32 @@
 use crate::{
-    archive::{Archive, ArchiveHeader, SolidArchive, PNA_HEADER},
-    chunk::{Chunk, ChunkExt, ChunkStreamWriter, ChunkType, RawChunk},
+    archive::{
+        check_archive_extra_chunk, Archive, ArchiveHeader, ArchiveId, ArchiveOptions, SolidArchive,
+        PNA_HEADER,
+    },
+    chunk::{Chunk, ChunkBufWriter, ChunkExt, ChunkStreamWriter, ChunkType, RawChunk},
     cipher::CipherWriter,
     compress::CompressionWriter,
     entry::{
-        get_writer, get_writer_context, Entry, EntryHeader, EntryName, EntryPart, Metadata,
-        NormalEntry, SealedEntryExt, SolidHeader, WriteCipher, WriteOption, WriteOptions,
+        get_writer, get_writer_context, merge_zstd_dictionary, recipients_unsupported_in_solid,
+        timestamp_to_bytes, Entry, EntryHeader, EntryName, EntryPart, ExtendedAttribute, Metadata,
+        NormalEntry, PackedWriter, Permission, SealedEntryExt, SolidHeader, WriteCipher,
+        WriteOption, WriteOptions,
     },
     io::TryIntoInner,
+    random::random_bytes,
+    util::slice::skip_while,
 };
 #[cfg(feature = "unstable-async")]
 use futures_io::AsyncWrite;
 #[cfg(feature = "unstable-async")]
 use futures_util::AsyncWriteExt;
-use std::io::{self, Write};
+use std::{
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    sync::Arc,
+    time::Duration,
+};
 
 /// Writer that compresses and encrypts according to the given options.
 pub struct EntryDataWriter<W: Write>(CompressionWriter<CipherWriter<ChunkStreamWriter<W>>>);
@@ -34,7 +47,9 @@ impl<W: Write> Write for EntryDataWriter<W> {
 pub struct SolidArchiveEntryDataWriter<'w, W: Write>(
     CompressionWriter<
         CipherWriter<
-            ChunkStreamWriter<&'w mut CompressionWriter<CipherWriter<ChunkStreamWriter<W>>>>,
+            ChunkStreamWriter<
+                &'w mut PackedWriter<CompressionWriter<CipherWriter<ChunkStreamWriter<W>>>>,
+            >,
         >,
     >,
 );
@@ -51,9 +66,224 @@ impl<W: Write> Write for SolidArchiveEntryDataWriter<'_, W> {
     }
 }
 
+/// Upper bound of the data passed to the compressor at once,
+/// leaving room for the padding of the block cipher in a chunk.
+const MAX_WRITE_LENGTH: usize = (u32::MAX / 2) as usize;
+
+/// Size of the data chunks written by [`Archive::entry_writer`].
+const DEFAULT_STREAM_CHUNK_SIZE: usize = 1024 * 1024;
+
+struct CountingWriter<W> {
+    inner: W,
+    count: usize,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.inner.write(buf)?;
+        self.count += len;
+        Ok(len)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writer that streams the data of a regular file entry into the archive.
+///
+/// Unlike [`EntryBuilder`](crate::EntryBuilder), the compressed data is not kept in memory
+/// but written to the archive as soon as the compressor produces it.
+/// Therefore, the metadata is written after the data when [`EntryWriter::finish`] is called.
+/// The data is written in chunks of a fixed size, so at most one chunk of the data is buffered.
+///
+/// Created by [`Archive::entry_writer`].
+pub struct EntryWriter<'a, W: Write> {
+    data: CompressionWriter<CipherWriter<ChunkBufWriter<CountingWriter<&'a mut W>>>>,
+    created: Option<Duration>,
+    last_modified: Option<Duration>,
+    accessed: Option<Duration>,
+    permission: Option<Permission>,
+    store_file_size: bool,
+    file_size: u128,
+    xattrs: Vec<ExtendedAttribute>,
+    extra_chunks: Vec<RawChunk>,
+    bytes_written: &'a mut u64,
+}
+
+impl<'a, W: Write> EntryWriter<'a, W> {
+    fn new(
+        inner: &'a mut W,
+        bytes_written: &'a mut u64,
+        name: EntryName,
+        option: impl WriteOption,
+        chunk_size: usize,
+    ) -> io::Result<Self> {
+        if chunk_size == 0 || u32::try_from(chunk_size).is_err() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("chunk size must be between 1 and {} bytes", u32::MAX),
+            ));
+        }
+        let header = EntryHeader::for_file(
+            option.compression(),
+            option.encryption(),
+            option.cipher_mode(),
+            name,
+        );
+        let context = get_writer_context(option)?;
+        let mut writer = CountingWriter { inner, count: 0 };
+        (ChunkType::FHED, header.to_bytes()).write_chunk_in(&mut writer)?;
+        if let Some(WriteCipher { context: c, .. }) = &context.cipher {
+            if let Some(phsf) = &c.phsf {
+                (ChunkType::PHSF, phsf.as_bytes()).write_chunk_in(&mut writer)?;
+            }
+            for wrapped_key in &c.wrapped_keys {
+                (ChunkType::WKEY, wrapped_key.to_bytes()).write_chunk_in(&mut writer)?;
+            }
+            (ChunkType::FDAT, &c.iv[..]).write_chunk_in(&mut writer)?;
+        }
+        let data = get_writer(
+            ChunkBufWriter::new(ChunkType::FDAT, writer, chunk_size),
+            &context,
+        )?;
+        Ok(Self {
+            data,
+            created: None,
+            last_modified: None,
+            accessed: None,
+            permission: None,
+            store_file_size: true,
+            file_size: 0,
+            xattrs: Vec::new(),
+            extra_chunks: Vec::new(),
+            bytes_written,
+        })
+    }
+
+    /// Sets the creation timestamp of the entry.
+    #[inline]
+    pub fn created(&mut self, since_unix_epoch: Duration) -> &mut Self {
+        self.created = Some(since_unix_epoch);
+        self
+    }
+
+    /// Sets the last modified timestamp of the entry.
+    #[inline]
+    pub fn modified(&mut self, since_unix_epoch: Duration) -> &mut Self {
+        self.last_modified = Some(since_unix_epoch);
+        self
+    }
+
+    /// Sets the last accessed timestamp of the entry.
+    #[inline]
+    pub fn accessed(&mut self, since_unix_epoch: Duration) -> &mut Self {
+        self.accessed = Some(since_unix_epoch);
+        self
+    }
+
+    /// Sets the permission of the entry.
+    #[inline]
+    pub fn permission(&mut self, permission: Permission) -> &mut Self {
+        self.permission = Some(permission);
+        self
+    }
+
+    /// Sets whether to store the raw file size of the entry. Defaults to `true`.
+    #[inline]
+    pub fn file_size(&mut self, store: bool) -> &mut Self {
+        self.store_file_size = store;
+        self
+    }
+
+    /// Adds an extended attribute to the entry.
+    #[inline]
+    pub fn add_xattr(&mut self, xattr: ExtendedAttribute) -> &mut Self {
+        self.xattrs.push(xattr);
+        self
+    }
+
+    /// Adds an extra chunk to the entry.
+    #[inline]
+    pub fn add_extra_chunk<T: Into<RawChunk>>(&mut self, chunk: T) -> &mut Self {
+        self.extra_chunks.push(chunk.into());
+        self
+    }
+
+    /// Finishes the data and writes the metadata and the end of the entry.
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written to the archive for this entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while finishing the data or writing the metadata.
+    /// The entry is left incomplete in that case, so the archive should be discarded.
+    #[inline]
+    pub fn finish(self) -> io::Result<usize> {
+        let mut writer = self
+            .data
+            .try_into_inner()?
+            .try_into_inner()?
+            .try_into_inner()?;
+        if self.store_file_size {
+            (
+                ChunkType::fSIZ,
+                skip_while(&self.file_size.to_be_bytes(), |i| *i == 0),
+            )
+                .write_chunk_in(&mut writer)?;
+        }
+        if let Some(c) = self.created {
+            (ChunkType::cTIM, timestamp_to_bytes(c)).write_chunk_in(&mut writer)?;
+        }
+        if let Some(m) = self.last_modified {
+            (ChunkType::mTIM, timestamp_to_bytes(m)).write_chunk_in(&mut writer)?;
+        }
+        if let Some(a) = self.accessed {
+            (ChunkType::aTIM, timestamp_to_bytes(a)).write_chunk_in(&mut writer)?;
+        }
+        if let Some(p) = &self.permission {
+            (ChunkType::fPRM, p.to_bytes()).write_chunk_in(&mut writer)?;
+        }
+        for xattr in &self.xattrs {
+            xattr.to_chunk().write_chunk_in(&mut writer)?;
+        }
+        for chunk in &self.extra_chunks {
+            chunk.write_chunk_in(&mut writer)?;
+        }
+        (ChunkType::FEND, []).write_chunk_in(&mut writer)?;
+        *self.bytes_written += writer.count as u64;
+        Ok(writer.count)
+    }
+}
+
+impl<W: Write> Write for EntryWriter<'_, W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let buf = &buf[..buf.len().min(MAX_WRITE_LENGTH)];
+        let len = self.data.write(buf)?;
+        self.file_size += len as u128;
+        Ok(len)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.flush()
+    }
+}
+
 impl<W: Write> Archive<W> {
     /// Writes the archive header to the given `Write` object and return a new [Archive].
     ///
+    /// The archive does not record an archive identifier, so that it can be read by pna 0.21.0 or earlier,
+    /// which fail on the [aIDN] chunk. Use [Archive::write_header_with], which records a random identifier,
+    /// for an archive to be split, so that the parts of different archives are not mixed up.
+    ///
+    /// [aIDN]: crate::chunk::ChunkType::aIDN
+    ///
     /// # Arguments
     ///
     /// * `write` - The [Write] object to write the header to.
@@ -82,15 +312,105 @@ impl<W: Write> Archive<W> {
     /// ```
     #[inline]
     pub fn write_header(write: W) -> io::Result<Self> {
+        Self::write_header_with_id(write, None)
+    }
+
+    /// Writes the archive header with the given archive identifier to the given `Write` object and return a new [Archive].
+    ///
+    /// If `archive_id` is `None`, the [aIDN] chunk is not written.
+    /// This is useful for copying an archive while keeping its identifier.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while writing header to the writer.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libpna::Archive;
+    /// use std::fs;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let src = Archive::read_header(fs::File::open("src.pna")?)?;
+    /// let file = fs::File::create("example.pna")?;
+    /// let mut archive = Archive::write_header_with_id(file, src.archive_id())?;
+    /// archive.finalize()?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [aIDN]: crate::chunk::ChunkType::aIDN
+    #[inline]
+    pub fn write_header_with_id(write: W, archive_id: Option<ArchiveId>) -> io::Result<Self> {
+        let header = ArchiveHeader::new(0, 0, 0);
+        Self::write_archive_header(write, header, archive_id, Vec::new())
+    }
+
+    /// Writes the archive header with the given options to the given `Write` object and return a new [Archive].
+    ///
+    /// The extra chunks of `options` are written between the archive header and the first entry,
+    /// and are read by [Archive::archive_extra_chunks].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an extra chunk is not a private ancillary chunk,
+    /// or if an I/O error occurs while writing header to the writer.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libpna::{Archive, ArchiveOptions, ChunkType, RawChunk};
+    /// use std::fs;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let ty = ChunkType::private(*b"myAr").unwrap();
+    /// let options = ArchiveOptions::new().with_extra_chunk(RawChunk::from_data(ty, b"my tool 1.0"));
+    /// let file = fs::File::create("example.pna")?;
+    /// let mut archive = Archive::write_header_with(file, options)?;
+    /// archive.finalize()?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn write_header_with(write: W, options: ArchiveOptions) -> io::Result<Self> {
+        let archive_id = match options.archive_id() {
+            Some(archive_id) => archive_id,
+            None => {
+                let mut archive_id = ArchiveId::default();
+                random_bytes(&mut archive_id)?;
+                Some(archive_id)
+            }
+        };
+        let extra_chunks = options.into_extra_chunks();
+        for chunk in &extra_chunks {
+            check_archive_extra_chunk(chunk.ty())?;
+        }
         let header = ArchiveHeader::new(0, 0, 0);
-        Self::write_header_with(write, header)
+        Self::write_archive_header(write, header, archive_id, extra_chunks)
     }
 
     #[inline]
-    fn write_header_with(mut write: W, header: ArchiveHeader) -> io::Result<Self> {
+    fn write_archive_header(
+        mut write: W,
+        header: ArchiveHeader,
+        archive_id: Option<ArchiveId>,
+        extra_chunks: Vec<RawChunk>,
+    ) -> io::Result<Self> {
         write.write_all(PNA_HEADER)?;
-        (ChunkType::AHED, header.to_bytes()).write_chunk_in(&mut write)?;
-        Ok(Self::new(write, header))
+        let mut len = PNA_HEADER.len();
+        len += (ChunkType::AHED, header.to_bytes()).write_chunk_in(&mut write)?;
+        if let Some(id) = archive_id {
+            len += (ChunkType::aIDN, id).write_chunk_in(&mut write)?;
+        }
+        for chunk in &extra_chunks {
+            len += chunk.write_chunk_in(&mut write)?;
+        }
+        let mut archive = Self::new(write, header, archive_id);
+        archive.extra_chunks = extra_chunks;
+        archive.bytes_written = len as u64;
+        Ok(archive)
     }
 
     /// Write a regular file as normal entry into archive.
@@ -126,6 +446,7 @@ impl<W: Write> Archive<W> {
     where
         F: FnMut(&mut EntryDataWriter<&mut W>) -> io::Result<()>,
     {
+        self.write_zstd_dictionary(option.zstd_dictionary())?;
         let header = EntryHeader::for_file(
             option.compression(),
             option.encryption(),
@@ -134,20 +455,25 @@ impl<W: Write> Archive<W> {
         );
         (ChunkType::FHED, header.to_bytes()).write_chunk_in(&mut self.inner)?;
         if let Some(c) = metadata.created {
-            (ChunkType::cTIM, c.as_secs().to_be_bytes()).write_chunk_in(&mut self.inner)?;
+            (ChunkType::cTIM, timestamp_to_bytes(c)).write_chunk_in(&mut self.inner)?;
         }
         if let Some(m) = metadata.modified {
-            (ChunkType::mTIM, m.as_secs().to_be_bytes()).write_chunk_in(&mut self.inner)?;
+            (ChunkType::mTIM, timestamp_to_bytes(m)).write_chunk_in(&mut self.inner)?;
         }
         if let Some(a) = metadata.accessed {
-            (ChunkType::aTIM, a.as_secs().to_be_bytes()).write_chunk_in(&mut self.inner)?;
+            (ChunkType::aTIM, timestamp_to_bytes(a)).write_chunk_in(&mut self.inner)?;
         }
         if let Some(p) = metadata.permission {
             (ChunkType::fPRM, p.to_bytes()).write_chunk_in(&mut self.inner)?;
         }
         let context = get_writer_context(option)?;
         if let Some(WriteCipher { context: c, .. }) = &context.cipher {
-            (ChunkType::PHSF, c.phsf.as_bytes()).write_chunk_in(&mut self.inner)?;
+            if let Some(phsf) = &c.phsf {
+                (ChunkType::PHSF, phsf.as_bytes()).write_chunk_in(&mut self.inner)?;
+            }
+            for wrapped_key in &c.wrapped_keys {
+                (ChunkType::WKEY, wrapped_key.to_bytes()).write_chunk_in(&mut self.inner)?;
+            }
             (ChunkType::FDAT, &c.iv[..]).write_chunk_in(&mut self.inner)?;
         }
         {
@@ -161,6 +487,166 @@ impl<W: Write> Archive<W> {
         Ok(())
     }
 
+    /// Starts a regular file entry whose data is streamed into the archive.
+    ///
+    /// The header of the entry is written immediately, and the data is written as it is
+    /// compressed and encrypted, so the memory usage does not depend on the size of the file.
+    /// The data is written in chunks of 1 MiB, use [`Archive::entry_writer_with_chunk_size`]
+    /// to change it. The entry is completed by [`EntryWriter::finish`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while writing the header of the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libpna::{Archive, WriteOptions};
+    /// use std::fs;
+    /// use std::io::{self, prelude::*};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::create("example.pna")?;
+    /// let mut archive = Archive::write_header(file)?;
+    /// let mut writer = archive.entry_writer("large.bin".into(), WriteOptions::builder().build())?;
+    /// io::copy(&mut fs::File::open("large.bin")?, &mut writer)?;
+    /// writer.finish()?;
+    /// archive.finalize()?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn entry_writer(
+        &mut self,
+        name: EntryName,
+        option: impl WriteOption,
+    ) -> io::Result<EntryWriter<'_, W>> {
+        self.write_zstd_dictionary(option.zstd_dictionary())?;
+        EntryWriter::new(
+            &mut self.inner,
+            &mut self.bytes_written,
+            name,
+            option,
+            DEFAULT_STREAM_CHUNK_SIZE,
+        )
+    }
+
+    /// Starts a regular file entry like [`Archive::entry_writer`],
+    /// writing the data in chunks of `chunk_size` bytes.
+    ///
+    /// Larger chunks reduce the overhead of the chunk headers,
+    /// while smaller chunks reduce the memory used to buffer the data.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `chunk_size` is 0 or larger than [`u32::MAX`],
+    /// or an I/O error occurs while writing the header of the entry.
+    #[inline]
+    pub fn entry_writer_with_chunk_size(
+        &mut self,
+        name: EntryName,
+        option: impl WriteOption,
+        chunk_size: usize,
+    ) -> io::Result<EntryWriter<'_, W>> {
+        self.write_zstd_dictionary(option.zstd_dictionary())?;
+        EntryWriter::new(
+            &mut self.inner,
+            &mut self.bytes_written,
+            name,
+            option,
+            chunk_size,
+        )
+    }
+
+    /// Adds a regular file entry whose data is read from `reader` and streamed into the archive.
+    ///
+    /// This is a shorthand of [`Archive::entry_writer`] for the entries without metadata.
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written to the archive for this entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while reading `reader` or writing the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libpna::{Archive, WriteOptions};
+    /// use std::fs;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::create("example.pna")?;
+    /// let mut archive = Archive::write_header(file)?;
+    /// archive.add_entry_from_reader(
+    ///     "large.bin".into(),
+    ///     fs::File::open("large.bin")?,
+    ///     WriteOptions::builder().build(),
+    /// )?;
+    /// archive.finalize()?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn add_entry_from_reader<R: Read>(
+        &mut self,
+        name: EntryName,
+        mut reader: R,
+        option: impl WriteOption,
+    ) -> io::Result<usize> {
+        let mut writer = self.entry_writer(name, option)?;
+        io::copy(&mut reader, &mut writer)?;
+        writer.finish()
+    }
+
+    /// Adds a regular file entry whose data is written by `f` and streamed into the archive.
+    ///
+    /// This is a shorthand of [`Archive::entry_writer`] for the entries without metadata.
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes written to the archive for this entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `f` returns an error or an I/O error occurs while writing the entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libpna::{Archive, WriteOptions};
+    /// use std::fs;
+    /// use std::io::{self, prelude::*};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = fs::File::create("example.pna")?;
+    /// let mut archive = Archive::write_header(file)?;
+    /// archive.add_entry_streaming(
+    ///     "large.bin".into(),
+    ///     WriteOptions::builder().build(),
+    ///     |writer| io::copy(&mut fs::File::open("large.bin")?, writer).map(|_| ()),
+    /// )?;
+    /// archive.finalize()?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn add_entry_streaming<F>(
+        &mut self,
+        name: EntryName,
+        option: impl WriteOption,
+        f: F,
+    ) -> io::Result<usize>
+    where
+        F: FnOnce(&mut dyn Write) -> io::Result<()>,
+    {
+        let mut writer = self.entry_writer(name, option)?;
+        f(&mut writer)?;
+        writer.finish()
+    }
+
     /// Adds a new entry to the archive.
     ///
     /// # Arguments
@@ -186,7 +672,25 @@ impl<W: Write> Archive<W> {
     /// ```
     #[inline]
     pub fn add_entry(&mut self, entry: impl Entry) -> io::Result<usize> {
-        entry.write_in(&mut self.inner)
+        let len = self.write_zstd_dictionary(entry.zstd_dictionary())?;
+        let entry_len = entry.write_in(&mut self.inner)?;
+        self.bytes_written += entry_len as u64;
+        Ok(len + entry_len)
+    }
+
+    /// Writes the [`ChunkType::zDIC`] chunk before the first entry compressed with a zstd dictionary.
+    fn write_zstd_dictionary(&mut self, dictionary: Option<&Arc<[u8]>>) -> io::Result<usize> {
+        if !merge_zstd_dictionary(&mut self.zstd_dictionary, dictionary)? {
+            return Ok(0);
+        }
+        let len = match &self.zstd_dictionary {
+            Some(dictionary) => {
+                (ChunkType::zDIC, &dictionary[..]).write_chunk_in(&mut self.inner)?
+            }
+            None => 0,
+        };
+        self.bytes_written += len as u64;
+        Ok(len)
     }
 
     /// Adds a part of an entry to the archive.
@@ -224,12 +728,42 @@ impl<W: Write> Archive<W> {
         for chunk in entry_part.0 {
             written_len += chunk.write_chunk_in(&mut self.inner)?;
         }
+        self.bytes_written += written_len as u64;
         Ok(written_len)
     }
 
+    /// Returns the number of bytes written to the underlying writer so far.
+    ///
+    /// The archive header and the entries added by [Archive::add_entry], [Archive::add_entry_part]
+    /// and the finished [EntryWriter]s are counted, but the entries written by [Archive::write_file] are not.
+    /// The end marker written by [Archive::finalize] is not counted either,
+    /// so the finalized archive is [MIN_CHUNK_BYTES_SIZE](crate::MIN_CHUNK_BYTES_SIZE) bytes longer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{Archive, EntryBuilder, WriteOptions, MIN_CHUNK_BYTES_SIZE};
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut archive = Archive::write_header(Vec::new())?;
+    /// archive.add_entry(EntryBuilder::new_file("foo.txt".into(), WriteOptions::store())?.build()?)?;
+    /// let bytes_written = archive.bytes_written();
+    /// let bytes = archive.finalize()?;
+    /// assert_eq!(bytes.len() as u64, bytes_written + MIN_CHUNK_BYTES_SIZE as u64);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
     #[inline]
     fn add_next_archive_marker(&mut self) -> io::Result<usize> {
-        (ChunkType::ANXT, []).write_chunk_in(&mut self.inner)
+        let len = (ChunkType::ANXT, []).write_chunk_in(&mut self.inner)?;
+        self.bytes_written += len as u64;
+        Ok(len)
     }
 
     /// Split to the next archive.
@@ -254,12 +788,47 @@ impl<W: Write> Archive<W> {
     /// # }
     /// ```
     #[inline]
-    pub fn split_to_next_archive<OW: Write>(mut self, writer: OW) -> io::Result<Archive<OW>> {
+    pub fn split_to_next_archive<OW: Write>(self, writer: OW) -> io::Result<Archive<OW>> {
+        self.finalize_and_split(writer).map(|(_, next)| next)
+    }
+
+    /// Split to the next archive, returning the writer of this finalized archive together with the next archive.
+    ///
+    /// Unlike [Archive::split_to_next_archive], this allows the finished part to be
+    /// synced or renamed before the next part is completed.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use libpna::{Archive, EntryBuilder, EntryPart, WriteOptions};
+    /// # use std::fs::File;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let part1_file = File::create("example.part1.pna")?;
+    /// let mut archive_part1 = Archive::write_header(part1_file)?;
+    /// let entry =
+    ///     EntryBuilder::new_file("example.txt".into(), WriteOptions::builder().build())?.build()?;
+    /// archive_part1.add_entry_part(EntryPart::from(entry))?;
+    ///
+    /// let part2_file = File::create("example.part2.pna")?;
+    /// let (part1_file, archive_part2) = archive_part1.finalize_and_split(part2_file)?;
+    /// part1_file.sync_all()?;
+    /// archive_part2.finalize()?;
+    /// #    Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn finalize_and_split<OW: Write>(mut self, writer: OW) -> io::Result<(W, Archive<OW>)> {
         let next_archive_number = self.header.archive_number + 1;
         let header = ArchiveHeader::new(0, 0, next_archive_number);
+        let archive_id = self.archive_id;
+        // NOTE: The parts are read in order, so the dictionary is written only in the part it is first used.
+        let zstd_dictionary = self.zstd_dictionary.take();
         self.add_next_archive_marker()?;
-        self.finalize()?;
-        Archive::write_header_with(writer, header)
+        let inner = self.finalize()?;
+        let mut next = Archive::write_archive_header(writer, header, archive_id, Vec::new())?;
+        next.zstd_dictionary = zstd_dictionary;
+        Ok((inner, next))
     }
 
     /// Write an end marker to finalize the archive.
@@ -287,6 +856,146 @@ impl<W: Write> Archive<W> {
         (ChunkType::AEND, []).write_chunk_in(&mut self.inner)?;
         Ok(self.inner)
     }
+
+    /// Writes `chunks` after the entries, then writes an end marker to finalize the archive.
+    ///
+    /// The chunks must be private ancillary chunks, so that readers not knowing them can skip them.
+    /// They are not read as entries, use [read_as_chunks](crate::read_as_chunks) to read them.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use std::io;
+    /// # use std::fs::File;
+    /// use libpna::{Archive, ChunkType, RawChunk};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let file = File::create("foo.pna")?;
+    /// let archive = Archive::write_header(file)?;
+    /// let ty = ChunkType::private(*b"myTy").unwrap();
+    /// archive.finalize_with_chunks([RawChunk::from_data(ty, b"some data")])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a chunk is not a private ancillary chunk, or if an I/O error occurs while writing.
+    #[inline]
+    pub fn finalize_with_chunks<T: Chunk>(
+        mut self,
+        chunks: impl IntoIterator<Item = T>,
+    ) -> io::Result<W> {
+        for chunk in chunks {
+            check_archive_extra_chunk(chunk.ty())?;
+            chunk.write_chunk_in(&mut self.inner)?;
+        }
+        self.finalize()
+    }
+}
+
+impl Archive<fs::File> {
+    /// Opens the archive file at `path` for appending entries to it.
+    ///
+    /// The file is opened for reading and writing, and the returned [Archive] is positioned at
+    /// the end of archive marker, which is overwritten by the added entries.
+    /// Call [Archive::finalize] to write the end of archive marker again after adding entries.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # use std::io::{self, prelude::*};
+    /// use libpna::{Archive, EntryBuilder, WriteOptions};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut archive = Archive::open_for_append("foo.pna")?;
+    /// let mut entry = EntryBuilder::new_file("bar.txt".into(), WriteOptions::builder().build())?;
+    /// entry.write_all(b"content")?;
+    /// archive.add_entry(entry.build()?)?;
+    /// archive.finalize()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can not be opened, is not an archive, or is a part of
+    /// a multipart archive followed by other parts, which must be appended to its last part.
+    #[inline]
+    pub fn open_for_append<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = fs::File::options().read(true).write(true).open(path)?;
+        let mut archive = Archive::read_header(file)?;
+        archive.seek_to_end()?;
+        if archive.has_next_archive() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} is followed by other parts of the archive, append to its last part instead",
+                    path.display()
+                ),
+            ));
+        }
+        Ok(archive)
+    }
+}
+
+impl Archive<io::Cursor<Vec<u8>>> {
+    /// Opens the archive in `vec` for appending entries to it without reading the existing entries.
+    ///
+    /// The end of archive marker at the end of `vec` is removed, and the entries added to
+    /// the returned [Archive] follow the existing entries.
+    /// Use [Archive::finalize_to_vec] to get the bytes of the appended archive.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::io::{self, prelude::*};
+    /// use libpna::{Archive, EntryBuilder, WriteOptions};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let bytes = Archive::write_header(Vec::new())?.finalize()?;
+    /// let mut archive = Archive::append_to_vec(bytes)?;
+    /// let mut entry = EntryBuilder::new_file("foo.txt".into(), WriteOptions::builder().build())?;
+    /// entry.write_all(b"content")?;
+    /// archive.add_entry(entry.build()?)?;
+    /// let bytes = archive.finalize_to_vec()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `vec` does not start with an archive header or does not end with an end of archive marker.
+    #[inline]
+    pub fn append_to_vec(mut vec: Vec<u8>) -> io::Result<Self> {
+        let (header, archive_id) = {
+            let archive = Archive::read_header(vec.as_slice())?;
+            (archive.header, archive.archive_id)
+        };
+        let end_marker = (ChunkType::AEND, []).to_bytes();
+        if !vec.ends_with(&end_marker) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "archive does not end with the end of archive marker",
+            ));
+        }
+        vec.truncate(vec.len() - end_marker.len());
+        let next_archive = vec.ends_with(&(ChunkType::ANXT, []).to_bytes());
+        let position = vec.len() as u64;
+        let mut cursor = io::Cursor::new(vec);
+        cursor.set_position(position);
+        let mut archive = Self::new(cursor, header, archive_id);
+        archive.next_archive = next_archive;
+        Ok(archive)
+    }
+
+    /// Writes an end marker to finalize the archive and returns its bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while writing the end marker.
+    #[inline]
+    pub fn finalize_to_vec(self) -> io::Result<Vec<u8>> {
+        self.finalize().map(io::Cursor::into_inner)
+    }
 }
 
 #[cfg(feature = "unstable-async")]
@@ -296,17 +1005,26 @@ impl<W: AsyncWrite + Unpin> Archive<W> {
     #[inline]
     pub async fn write_header_async(write: W) -> io::Result<Self> {
         let header = ArchiveHeader::new(0, 0, 0);
-        Self::write_header_with_async(write, header).await
+        Self::write_header_with_async(write, header, None).await
     }
 
     #[inline]
-    async fn write_header_with_async(mut write: W, header: ArchiveHeader) -> io::Result<Self> {
+    async fn write_header_with_async(
+        mut write: W,
+        header: ArchiveHeader,
+        archive_id: Option<ArchiveId>,
+    ) -> io::Result<Self> {
         write.write_all(PNA_HEADER).await?;
         let mut chunk_writer = crate::chunk::ChunkWriter::from(&mut write);
         chunk_writer
             .write_chunk_async((ChunkType::AHED, header.to_bytes()))
             .await?;
-        Ok(Self::new(write, header))
+        if let Some(id) = archive_id {
+            chunk_writer
+                .write_chunk_async((ChunkType::aIDN, id))
+                .await?;
+        }
+        Ok(Self::new(write, header, archive_id))
     }
 
     /// Adds a new entry to the archive.
@@ -314,6 +1032,11 @@ impl<W: AsyncWrite + Unpin> Archive<W> {
     #[inline]
     pub async fn add_entry_async(&mut self, entry: impl Entry) -> io::Result<usize> {
         let mut bytes = Vec::new();
+        if merge_zstd_dictionary(&mut self.zstd_dictionary, entry.zstd_dictionary())? {
+            if let Some(dictionary) = &self.zstd_dictionary {
+                (ChunkType::zDIC, &dictionary[..]).write_chunk_in(&mut bytes)?;
+            }
+        }
         entry.write_in(&mut bytes)?;
         self.inner.write_all(&bytes).await?;
         Ok(bytes.len())
@@ -365,31 +1088,85 @@ impl<W: Write> Archive<W> {
     #[inline]
     pub fn write_solid_header(write: W, option: impl WriteOption) -> io::Result<SolidArchive<W>> {
         let archive = Self::write_header(write)?;
-        archive.into_solid_archive(option)
+        archive.into_solid_archive(option, false)
     }
 
+    /// Writes the archive header to the given `Write` object and return a new [SolidArchive]
+    /// that stores the entries in the packed encoding.
+    ///
+    /// See [SolidEntryBuilder::new_packed](crate::SolidEntryBuilder::new_packed) for the packed encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an I/O error occurs while writing header to the writer.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libpna::{Archive, WriteOptions};
+    /// use std::fs::File;
+    /// # use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let option = WriteOptions::builder().build();
+    /// let file = File::create("example.pna")?;
+    /// let mut archive = Archive::write_packed_solid_header(file, option)?;
+    /// archive.finalize()?;
+    /// #    Ok(())
+    /// # }
+    /// ```
     #[inline]
-    fn into_solid_archive(mut self, option: impl WriteOption) -> io::Result<SolidArchive<W>> {
-        let header = SolidHeader::new(
+    pub fn write_packed_solid_header(
+        write: W,
+        option: impl WriteOption,
+    ) -> io::Result<SolidArchive<W>> {
+        let archive = Self::write_header(write)?;
+        archive.into_solid_archive(option, true)
+    }
+
+    #[inline]
+    fn into_solid_archive(
+        mut self,
+        option: impl WriteOption,
+        packed: bool,
+    ) -> io::Result<SolidArchive<W>> {
+        let mut header = SolidHeader::new(
             option.compression(),
             option.encryption(),
             option.cipher_mode(),
         );
+        if packed {
+            header = header.packed();
+        }
         let context = get_writer_context(option)?;
 
+        if let Some(WriteCipher { context: c, .. }) = &context.cipher {
+            if !c.wrapped_keys.is_empty() {
+                return Err(recipients_unsupported_in_solid());
+            }
+        }
+        self.write_zstd_dictionary(context.zstd_dictionary.as_ref())?;
+
         (ChunkType::SHED, header.to_bytes()).write_chunk_in(&mut self.inner)?;
         if let Some(WriteCipher { context: c, .. }) = &context.cipher {
-            (ChunkType::PHSF, c.phsf.as_bytes()).write_chunk_in(&mut self.inner)?;
+            if let Some(phsf) = &c.phsf {
+                (ChunkType::PHSF, phsf.as_bytes()).write_chunk_in(&mut self.inner)?;
+            }
             (ChunkType::SDAT, c.iv.as_slice()).write_chunk_in(&mut self.inner)?;
         }
         self.inner.flush()?;
-        let writer = get_writer(
-            ChunkStreamWriter::new(ChunkType::SDAT, self.inner),
-            &context,
+        let writer = PackedWriter::new(
+            get_writer(
+                ChunkStreamWriter::new(ChunkType::SDAT, self.inner),
+                &context,
+            )?,
+            packed,
         )?;
 
         Ok(SolidArchive {
             archive_header: self.header,
+            archive_id: self.archive_id,
+            zstd_dictionary: self.zstd_dictionary,
             inner: writer,
         })
     }
@@ -424,6 +1201,16 @@ impl<W: Write> SolidArchive<W> {
     where
         NormalEntry<T>: Entry,
     {
+        // NOTE: The entries in a solid entry are read with the dictionary of the archive,
+        //       which is already written before the solid entry.
+        if entry.zstd_dictionary().is_some()
+            && entry.zstd_dictionary() != self.zstd_dictionary.as_ref()
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Entries in a solid archive must be compressed with the zstd dictionary of the solid archive",
+            ));
+        }
         entry.write_in(&mut self.inner)
     }
 
@@ -461,20 +1248,25 @@ impl<W: Write> SolidArchive<W> {
         );
         (ChunkType::FHED, header.to_bytes()).write_chunk_in(&mut self.inner)?;
         if let Some(c) = metadata.created {
-            (ChunkType::cTIM, c.as_secs().to_be_bytes()).write_chunk_in(&mut self.inner)?;
+            (ChunkType::cTIM, timestamp_to_bytes(c)).write_chunk_in(&mut self.inner)?;
         }
         if let Some(m) = metadata.modified {
-            (ChunkType::mTIM, m.as_secs().to_be_bytes()).write_chunk_in(&mut self.inner)?;
+            (ChunkType::mTIM, timestamp_to_bytes(m)).write_chunk_in(&mut self.inner)?;
         }
         if let Some(a) = metadata.accessed {
-            (ChunkType::aTIM, a.as_secs().to_be_bytes()).write_chunk_in(&mut self.inner)?;
+            (ChunkType::aTIM, timestamp_to_bytes(a)).write_chunk_in(&mut self.inner)?;
         }
         if let Some(p) = metadata.permission {
             (ChunkType::fPRM, p.to_bytes()).write_chunk_in(&mut self.inner)?;
         }
         let context = get_writer_context(option)?;
         if let Some(WriteCipher { context: c, .. }) = &context.cipher {
-            (ChunkType::PHSF, c.phsf.as_bytes()).write_chunk_in(&mut self.inner)?;
+            if let Some(phsf) = &c.phsf {
+                (ChunkType::PHSF, phsf.as_bytes()).write_chunk_in(&mut self.inner)?;
+            }
+            for wrapped_key in &c.wrapped_keys {
+                (ChunkType::WKEY, wrapped_key.to_bytes()).write_chunk_in(&mut self.inner)?;
+            }
             (ChunkType::FDAT, &c.iv[..]).write_chunk_in(&mut self.inner)?;
         }
         {
@@ -518,9 +1310,16 @@ impl<W: Write> SolidArchive<W> {
     #[inline]
     fn finalize_solid_entry(mut self) -> io::Result<Archive<W>> {
         self.inner.flush()?;
-        let mut inner = self.inner.try_into_inner()?.try_into_inner()?.into_inner();
+        let mut inner = self
+            .inner
+            .into_inner()
+            .try_into_inner()?
+            .try_into_inner()?
+            .into_inner();
         (ChunkType::SEND, []).write_chunk_in(&mut inner)?;
-        Ok(Archive::new(inner, self.archive_header))
+        let mut archive = Archive::new(inner, self.archive_header, self.archive_id);
+        archive.zstd_dictionary = self.zstd_dictionary;
+        Ok(archive)
     }
 }
 
@@ -528,7 +1327,6 @@ impl<W: Write> SolidArchive<W> {
 mod tests {
     use super::*;
     use crate::ReadOptions;
-    use std::io::Read;
     #[cfg(all(target_family = "wasm", target_os = "unknown"))]
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
@@ -540,6 +1338,164 @@ mod tests {
         assert_eq!(file.as_slice(), expected.as_slice());
     }
 
+    #[test]
+    fn encode_with_archive_id() {
+        let writer = Archive::write_header_with(Vec::new(), ArchiveOptions::new())
+            .expect("failed to write header");
+        let archive_id = writer.archive_id();
+        assert!(archive_id.is_some());
+        let file = writer.finalize().expect("failed to finalize");
+        let mut reader = Archive::read_header(file.as_slice()).expect("failed to read archive");
+        assert_eq!(reader.archive_id(), archive_id);
+        assert!(reader.entries().next().is_none());
+    }
+
+    #[test]
+    fn finalize_with_private_chunks() {
+        let ty = ChunkType::private(*b"myTy").unwrap();
+        let mut writer = Archive::write_header(Vec::new()).expect("failed to write header");
+        writer
+            .write_file(
+                EntryName::from_lossy("text.txt"),
+                Metadata::new(),
+                WriteOptions::store(),
+                |writer| writer.write_all(b"text"),
+            )
+            .expect("failed to write");
+        let file = writer
+            .finalize_with_chunks([RawChunk::from_data(ty, b"data")])
+            .expect("failed to finalize");
+
+        let mut reader = Archive::read_header(&file[..]).expect("failed to read archive");
+        assert_eq!(reader.entries().count(), 1);
+        assert_eq!(
+            reader.archive_extra_chunks(),
+            [RawChunk::from_data(ty, b"data")]
+        );
+        assert!(!reader.has_incomplete_entry());
+        let chunks = crate::read_as_chunks(&file[..])
+            .expect("failed to read chunks")
+            .collect::<io::Result<Vec<_>>>()
+            .expect("failed to read chunks");
+        let types = chunks
+            .iter()
+            .rev()
+            .take(2)
+            .map(|it| it.ty())
+            .collect::<Vec<_>>();
+        assert_eq!(types, [ChunkType::AEND, ty]);
+        assert_eq!(chunks[chunks.len() - 2].data(), b"data");
+    }
+
+    #[test]
+    fn write_header_with_private_chunks() {
+        let ty = ChunkType::private(*b"myAr").unwrap();
+        let options = ArchiveOptions::new()
+            .with_archive_id(Some([1; 16]))
+            .with_extra_chunk(RawChunk::from_data(ty, b"my tool 1.0"));
+        let mut writer = Archive::write_header_with(Vec::new(), options).unwrap();
+        assert_eq!(writer.archive_id(), Some([1; 16]));
+        writer
+            .write_file(
+                EntryName::from_lossy("text.txt"),
+                Metadata::new(),
+                WriteOptions::store(),
+                |writer| writer.write_all(b"text"),
+            )
+            .unwrap();
+        let file = writer.finalize().unwrap();
+
+        let mut reader = Archive::read_header(&file[..]).unwrap();
+        assert_eq!(reader.archive_id(), Some([1; 16]));
+        assert!(reader.archive_extra_chunks().is_empty());
+        let entry = reader.entries_skip_solid().next().unwrap().unwrap();
+        assert!(entry.extra_chunks().is_empty());
+        assert_eq!(
+            reader.archive_extra_chunks(),
+            [RawChunk::from_data(ty, b"my tool 1.0")]
+        );
+        assert!(reader.entries().next().is_none());
+
+        let mut reader = Archive::read_header_from_slice(&file[..]).unwrap();
+        assert_eq!(reader.entries_slice().count(), 1);
+        assert_eq!(
+            reader.archive_extra_chunks(),
+            [RawChunk::from_data(ty, b"my tool 1.0")]
+        );
+    }
+
+    #[test]
+    fn write_header_with_public_chunks() {
+        let options =
+            ArchiveOptions::new().with_extra_chunk(RawChunk::from_data(ChunkType::cTIM, [0; 8]));
+        let result = Archive::write_header_with(Vec::new(), options);
+        assert_eq!(result.err().unwrap().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn header_info() {
+        let writer = Archive::write_header(Vec::new()).unwrap();
+        let (part1, writer) = writer.finalize_and_split(Vec::new()).unwrap();
+        assert_eq!(writer.header_info().archive_number(), 1);
+        let part2 = writer.finalize().unwrap();
+
+        let reader = Archive::read_header(&part1[..]).unwrap();
+        let info = reader.header_info();
+        assert_eq!(
+            (info.major(), info.minor(), info.archive_number()),
+            (0, 0, 0)
+        );
+        let reader = reader.read_next_archive(&part2[..]).unwrap();
+        assert_eq!(reader.header_info().archive_number(), 1);
+    }
+
+    #[test]
+    fn bytes_written_matches_buffer_len() {
+        let entry = |name: &str, data: &[u8]| {
+            let mut builder =
+                crate::EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+            Write::write_all(&mut builder, data).unwrap();
+            builder.build().unwrap()
+        };
+        let mut writer = Archive::write_header(Vec::new()).unwrap();
+        assert_eq!(writer.bytes_written(), writer.inner.len() as u64);
+        for (i, data) in [&b""[..], b"text", &[7; 3000]].into_iter().enumerate() {
+            let entry = entry(&format!("{i}.txt"), data);
+            let len = entry.estimated_bytes_len();
+            assert_eq!(writer.add_entry(entry).unwrap(), len);
+        }
+        writer
+            .add_entry_part(EntryPart::from(entry("part.txt", b"part")))
+            .unwrap();
+        writer
+            .add_entry_from_reader("reader.txt".into(), &b"reader"[..], WriteOptions::store())
+            .unwrap();
+        let bytes_written = writer.bytes_written();
+        assert_eq!(bytes_written, writer.inner.len() as u64);
+
+        let file = writer.finalize().unwrap();
+        assert_eq!(
+            file.len() as u64,
+            bytes_written + crate::MIN_CHUNK_BYTES_SIZE as u64
+        );
+    }
+
+    #[test]
+    fn bytes_written_of_next_archive() {
+        let writer = Archive::write_header(Vec::new()).unwrap();
+        let header_len = writer.bytes_written();
+        let (_, writer) = writer.finalize_and_split(Vec::new()).unwrap();
+        assert_eq!(writer.bytes_written(), header_len);
+        assert_eq!(writer.bytes_written(), writer.inner.len() as u64);
+    }
+
+    #[test]
+    fn finalize_with_public_chunks() {
+        let writer = Archive::write_header(Vec::new()).expect("failed to write header");
+        let result = writer.finalize_with_chunks([RawChunk::from_data(ChunkType::cTIM, [0; 8])]);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
     #[test]
     fn archive_write_file_entry() {
         let option = WriteOptions::builder().build();
@@ -598,6 +1554,186 @@ mod tests {
         assert_eq!(&data[..], b"text");
     }
 
+    fn read_file_entry(file: &[u8], password: Option<&str>) -> (NormalEntry, Vec<u8>) {
+        let mut reader = Archive::read_header(file).expect("failed to read archive");
+        let entry = reader
+            .entries_with_password(password)
+            .next()
+            .expect("failed to get entry")
+            .expect("failed to read entry");
+        let mut data = Vec::new();
+        entry
+            .reader(ReadOptions::with_password(password))
+            .expect("failed to read entry data")
+            .read_to_end(&mut data)
+            .expect("failed to read data");
+        (entry, data)
+    }
+
+    #[test]
+    fn entry_writer_same_as_builder() {
+        let option = WriteOptions::builder()
+            .compression(crate::Compression::ZStandard)
+            .encryption(crate::Encryption::Aes)
+            .cipher_mode(crate::CipherMode::CBC)
+            .hash_algorithm(crate::HashAlgorithm::pbkdf2_sha256_with(Some(1)))
+            .password(Some("password"))
+            .build();
+        let src = b"streaming text ".repeat(10_000);
+        let permission = Permission::new(1000, "user".into(), 100, "group".into(), 0o644);
+        let xattr = ExtendedAttribute::new("user.name".into(), b"value".to_vec());
+
+        let mut writer = Archive::write_header(Vec::new()).expect("failed to write header");
+        let mut builder = crate::EntryBuilder::new_file("text.txt".into(), option.clone())
+            .expect("failed to create builder");
+        builder
+            .modified(Duration::from_secs(1_000_000_000))
+            .permission(permission.clone())
+            .add_xattr(xattr.clone());
+        Write::write_all(&mut builder, &src).expect("failed to write");
+        writer
+            .add_entry(builder.build().expect("failed to build"))
+            .expect("failed to add entry");
+        let buffered = writer.finalize().expect("failed to finalize");
+
+        let mut writer = Archive::write_header(Vec::new()).expect("failed to write header");
+        let mut entry_writer = writer
+            .entry_writer("text.txt".into(), option)
+            .expect("failed to start entry");
+        entry_writer
+            .modified(Duration::from_secs(1_000_000_000))
+            .permission(permission.clone())
+            .add_xattr(xattr.clone());
+        entry_writer.write_all(&src).expect("failed to write");
+        entry_writer.finish().expect("failed to finish entry");
+        let streamed = writer.finalize().expect("failed to finalize");
+
+        let (buffered_entry, buffered_data) = read_file_entry(&buffered, Some("password"));
+        let (streamed_entry, streamed_data) = read_file_entry(&streamed, Some("password"));
+        assert_eq!(streamed_data, src);
+        assert_eq!(streamed_data, buffered_data);
+        assert_eq!(streamed_entry.header(), buffered_entry.header());
+        let (streamed_meta, buffered_meta) = (streamed_entry.metadata(), buffered_entry.metadata());
+        assert_eq!(streamed_meta.raw_file_size(), Some(src.len() as u128));
+        assert_eq!(streamed_meta.raw_file_size(), buffered_meta.raw_file_size());
+        assert_eq!(streamed_meta.modified(), buffered_meta.modified());
+        assert_eq!(streamed_meta.permission(), Some(&permission));
+        assert_eq!(streamed_entry.xattrs(), [xattr]);
+    }
+
+    #[test]
+    fn add_entry_streaming() {
+        let mut writer = Archive::write_header(Vec::new()).expect("failed to write header");
+        writer
+            .add_entry_streaming("text.txt".into(), WriteOptions::store(), |writer| {
+                writer.write_all(b"text")
+            })
+            .expect("failed to add entry");
+        let file = writer.finalize().expect("failed to finalize");
+        let (entry, data) = read_file_entry(&file, None);
+        assert_eq!(entry.header().path(), "text.txt");
+        assert_eq!(&data[..], b"text");
+    }
+
+    #[test]
+    fn add_entry_from_reader() {
+        let src = b"streaming text ".repeat(10_000);
+        let mut writer = Archive::write_header(Vec::new()).expect("failed to write header");
+        writer
+            .add_entry_from_reader("text.txt".into(), &src[..], WriteOptions::store())
+            .expect("failed to add entry");
+        let file = writer.finalize().expect("failed to finalize");
+        let (entry, data) = read_file_entry(&file, None);
+        assert_eq!(entry.header().path(), "text.txt");
+        assert_eq!(entry.metadata().raw_file_size(), Some(src.len() as u128));
+        assert_eq!(data, src);
+    }
+
+    #[test]
+    fn entry_writer_with_chunk_size() {
+        let src = b"streaming text ".repeat(10_000);
+        let mut writer = Archive::write_header(Vec::new()).expect("failed to write header");
+        let mut entry_writer = writer
+            .entry_writer_with_chunk_size("text.txt".into(), WriteOptions::store(), 4096)
+            .expect("failed to start entry");
+        for line in src.chunks(15) {
+            entry_writer.write_all(line).expect("failed to write");
+        }
+        entry_writer.finish().expect("failed to finish entry");
+        let file = writer.finalize().expect("failed to finalize");
+
+        let (_, data) = read_file_entry(&file, None);
+        assert_eq!(data, src);
+        let sizes = crate::read_as_chunks(&file[..])
+            .expect("failed to read chunks")
+            .map(|it| it.expect("failed to read chunk"))
+            .filter(|it| it.ty() == ChunkType::FDAT)
+            .map(|it| it.data().len())
+            .collect::<Vec<_>>();
+        assert_eq!(sizes.len(), src.len().div_ceil(4096));
+        assert!(sizes[..sizes.len() - 1].iter().all(|it| *it == 4096));
+        assert_eq!(sizes.iter().sum::<usize>(), src.len());
+    }
+
+    #[test]
+    fn entry_writer_with_invalid_chunk_size() {
+        let mut writer = Archive::write_header(Vec::new()).expect("failed to write header");
+        let result =
+            writer.entry_writer_with_chunk_size("text.txt".into(), WriteOptions::store(), 0);
+        assert_eq!(
+            result.err().map(|it| it.kind()),
+            Some(io::ErrorKind::InvalidInput)
+        );
+    }
+
+    /// Writer that only counts the bytes written into it.
+    struct InstrumentedWriter(std::rc::Rc<std::cell::Cell<usize>>);
+
+    impl Write for InstrumentedWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.set(self.0.get() + buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn entry_writer_does_not_buffer_data() {
+        const BLOCK: usize = 64 * 1024;
+        const BOUND: usize = 1024 * 1024;
+        let written = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut writer = Archive::write_header(InstrumentedWriter(written.clone()))
+            .expect("failed to write header");
+        let option = WriteOptions::builder()
+            .compression(crate::Compression::ZStandard)
+            .build();
+        let mut entry_writer = writer
+            .entry_writer("large.bin".into(), option)
+            .expect("failed to start entry");
+        // NOTE: Incompressible data, so that the output of the compressor is as large as the input.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut block = vec![0; BLOCK];
+        for i in 1..=64 {
+            for b in block.iter_mut() {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                *b = state as u8;
+            }
+            entry_writer.write_all(&block).expect("failed to write");
+            assert!(
+                i * BLOCK - written.get() < BOUND,
+                "{} bytes are buffered",
+                i * BLOCK - written.get()
+            );
+        }
+        entry_writer.finish().expect("failed to finish entry");
+        assert!(written.get() > 64 * BLOCK);
+    }
+
     #[cfg(feature = "unstable-async")]
     #[tokio::test]
     async fn encode_async() {
@@ -606,7 +1742,10 @@ mod tests {
 
         let bytes = {
             let file = io::Cursor::new(Vec::new()).compat_write();
-            let writer = Archive::write_header_async(file).await.unwrap();
+            let header = ArchiveHeader::new(0, 0, 0);
+            let writer = Archive::write_header_with_async(file, header, None)
+                .await
+                .unwrap();
             writer
                 .finalize_async()
                 .await
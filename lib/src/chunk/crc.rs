@@ -1 +1,81 @@
+//! CRC32 (ISO-HDLC, the same as PNG and zlib) of chunks.
+//!
+//! [crc32fast] uses the SSE4.2/PCLMULQDQ or ARMv8 CRC instructions when the CPU supports them,
+//! and falls back to a portable table-based implementation otherwise.
 pub(super) use crc32fast::Hasher as Crc32;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{Chunk, ChunkType};
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    /// Bitwise reference implementation of the polynomial used by PNA.
+    fn reference(data: &[u8]) -> u32 {
+        let mut crc = !0u32;
+        for byte in data {
+            crc ^= u32::from(*byte);
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+
+    fn checksum(data: &[u8]) -> u32 {
+        let mut hasher = Crc32::new();
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    #[test]
+    fn known_vectors() {
+        let vectors: [(&[u8], u32); 6] = [
+            (b"", 0x0000_0000),
+            (b"a", 0xE8B7_BE43),
+            (b"abc", 0x3524_41C2),
+            (b"123456789", 0xCBF4_3926),
+            (b"The quick brown fox jumps over the lazy dog", 0x414F_A339),
+            (b"FDAT\xAA\xBB\xCC\xDD", 0x47F3_2B10),
+        ];
+        for (data, expected) in vectors {
+            assert_eq!(checksum(data), expected, "{data:?}");
+            assert_eq!(reference(data), expected, "{data:?}");
+        }
+    }
+
+    #[test]
+    fn chunk_vectors() {
+        assert_eq!(
+            (ChunkType::FDAT, [0xAA, 0xBB, 0xCC, 0xDD]).crc(),
+            0x47F3_2B10
+        );
+        assert_eq!((ChunkType::AEND, []).crc(), 0x6BF6_486D);
+        assert_eq!((ChunkType::FEND, []).crc(), reference(b"FEND"));
+    }
+
+    #[test]
+    fn matches_reference_on_large_unaligned_input() {
+        let data = (0..1024 * 1024 + 13)
+            .map(|i| (i * 31 % 257) as u8)
+            .collect::<Vec<_>>();
+        for offset in 0..8 {
+            assert_eq!(checksum(&data[offset..]), reference(&data[offset..]));
+        }
+    }
+
+    #[test]
+    fn incremental_update() {
+        let data = (0..10_000).map(|i| i as u8).collect::<Vec<_>>();
+        let mut hasher = Crc32::new();
+        for part in data.chunks(997) {
+            hasher.update(part);
+        }
+        assert_eq!(hasher.finalize(), checksum(&data));
+    }
+}
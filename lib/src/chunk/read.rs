@@ -14,8 +14,12 @@ pub(crate) struct ChunkReader<R> {
 
 impl<R: Read> ChunkReader<R> {
     pub(crate) fn read_chunk(&mut self) -> io::Result<RawChunk> {
-        let mut crc_hasher = Crc32::new();
+        let (length, ty) = self.read_chunk_head()?;
+        self.read_chunk_body(length, ty)
+    }
 
+    /// Reads the length and the type of the next chunk.
+    pub(crate) fn read_chunk_head(&mut self) -> io::Result<(u32, ChunkType)> {
         // read chunk length
         let mut length = [0u8; mem::size_of::<u32>()];
         self.r.read_exact(&mut length)?;
@@ -25,7 +29,14 @@ impl<R: Read> ChunkReader<R> {
         let mut ty = [0u8; mem::size_of::<ChunkType>()];
         self.r.read_exact(&mut ty)?;
 
-        crc_hasher.update(&ty);
+        Ok((length, ChunkType(ty)))
+    }
+
+    /// Reads the data and the crc sum of the chunk whose length and type are read by
+    /// [ChunkReader::read_chunk_head].
+    pub(crate) fn read_chunk_body(&mut self, length: u32, ty: ChunkType) -> io::Result<RawChunk> {
+        let mut crc_hasher = Crc32::new();
+        crc_hasher.update(&ty.0);
 
         // read chunk data
         let mut data = vec![0; length as usize];
@@ -43,11 +54,34 @@ impl<R: Read> ChunkReader<R> {
         }
         Ok(RawChunk {
             length,
-            ty: ChunkType(ty),
+            ty,
             data,
             crc,
         })
     }
+
+    /// Reads through the data of the chunk whose length and type are read by
+    /// [ChunkReader::read_chunk_head] without keeping it, and checks the crc sum.
+    pub(crate) fn skip_chunk_body(&mut self, length: u32, ty: ChunkType) -> io::Result<()> {
+        let mut crc_hasher = Crc32::new();
+        crc_hasher.update(&ty.0);
+
+        let mut buf = [0u8; 8 * 1024];
+        let mut remaining = length as usize;
+        while remaining > 0 {
+            let n = remaining.min(buf.len());
+            self.r.read_exact(&mut buf[..n])?;
+            crc_hasher.update(&buf[..n]);
+            remaining -= n;
+        }
+
+        let mut crc = [0u8; mem::size_of::<u32>()];
+        self.r.read_exact(&mut crc)?;
+        if u32::from_be_bytes(crc) != crc_hasher.finalize() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Broken chunk"));
+        }
+        Ok(())
+    }
 }
 
 #[cfg(feature = "unstable-async")]
@@ -118,34 +152,64 @@ impl<R> From<R> for ChunkReader<R> {
     }
 }
 
+/// Reason why a chunk can not be read by [try_read_chunk_from_slice].
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) enum ChunkError {
+    /// The bytes end in the middle of the chunk.
+    Truncated,
+    /// The crc sum of the chunk does not match its type and data.
+    BrokenCrc,
+}
+
+impl From<ChunkError> for io::Error {
+    #[inline]
+    fn from(value: ChunkError) -> Self {
+        match value {
+            ChunkError::Truncated => io::ErrorKind::UnexpectedEof.into(),
+            ChunkError::BrokenCrc => io::Error::new(io::ErrorKind::InvalidData, "Broken chunk"),
+        }
+    }
+}
+
+#[inline]
 pub(crate) fn read_chunk_from_slice(bytes: &[u8]) -> io::Result<(RawChunk<&[u8]>, &[u8])> {
+    try_read_chunk_from_slice(bytes).map_err(Into::into)
+}
+
+/// Same as [read_chunk_from_slice], but tells a truncated chunk from a broken one,
+/// so that a damaged archive can be read past the broken chunk.
+pub(crate) fn try_read_chunk_from_slice(
+    bytes: &[u8],
+) -> Result<(RawChunk<&[u8]>, &[u8]), ChunkError> {
     let mut crc_hasher = Crc32::new();
 
     // read chunk length
     let (length, r) = bytes
         .split_first_chunk::<{ mem::size_of::<u32>() }>()
-        .ok_or(io::ErrorKind::UnexpectedEof)?;
+        .ok_or(ChunkError::Truncated)?;
     let length = u32::from_be_bytes(*length);
 
     // read a chunk type
     let (ty, r) = r
         .split_first_chunk::<{ mem::size_of::<ChunkType>() }>()
-        .ok_or(io::ErrorKind::UnexpectedEof)?;
+        .ok_or(ChunkError::Truncated)?;
     crc_hasher.update(&ty[..]);
 
     // read chunk data
-    // TODO: use split_at_checked instead
+    if r.len() < length as usize {
+        return Err(ChunkError::Truncated);
+    }
     let (data, r) = r.split_at(length as usize);
     crc_hasher.update(data);
 
     // read crc sum
     let (crc, r) = r
         .split_first_chunk::<{ mem::size_of::<u32>() }>()
-        .ok_or(io::ErrorKind::UnexpectedEof)?;
+        .ok_or(ChunkError::Truncated)?;
     let crc = u32::from_be_bytes(*crc);
 
     if crc != crc_hasher.finalize() {
-        return Err(io::Error::new(io::ErrorKind::InvalidData, "Broken chunk"));
+        return Err(ChunkError::BrokenCrc);
     }
     Ok((
         RawChunk {
@@ -46,6 +46,8 @@ impl ChunkType {
     pub const FHED: ChunkType = ChunkType(*b"FHED");
     /// Password hash string format
     pub const PHSF: ChunkType = ChunkType(*b"PHSF");
+    /// Content key wrapped for a recipient
+    pub const WKEY: ChunkType = ChunkType(*b"WKEY");
     /// Entry data stream
     pub const FDAT: ChunkType = ChunkType(*b"FDAT");
     /// Entry data stream end marker
@@ -58,6 +60,9 @@ impl ChunkType {
     pub const SEND: ChunkType = ChunkType(*b"SEND");
 
     // -- Auxiliary chunks --
+    /// Archive identifier
+    #[allow(non_upper_case_globals)]
+    pub const aIDN: ChunkType = ChunkType(*b"aIDN");
     /// Raw file size
     #[allow(non_upper_case_globals)]
     pub const fSIZ: ChunkType = ChunkType(*b"fSIZ");
@@ -76,6 +81,12 @@ impl ChunkType {
     /// Extended attribute
     #[allow(non_upper_case_globals)]
     pub const xATR: ChunkType = ChunkType(*b"xATR");
+    /// Extended attribute with zstd compressed value
+    #[allow(non_upper_case_globals)]
+    pub const xATZ: ChunkType = ChunkType(*b"xATZ");
+    /// Zstandard dictionary shared by the entries of the archive
+    #[allow(non_upper_case_globals)]
+    pub const zDIC: ChunkType = ChunkType(*b"zDIC");
 
     /// Returns the length of the chunk type code.
     ///
@@ -1,4 +1,7 @@
-use crate::chunk::{Chunk, ChunkExt, ChunkType};
+use crate::{
+    chunk::{Chunk, ChunkExt, ChunkType},
+    io::TryIntoInner,
+};
 #[cfg(feature = "unstable-async")]
 use futures_io::AsyncWrite;
 #[cfg(feature = "unstable-async")]
@@ -75,6 +78,69 @@ impl<W: Write> Write for ChunkStreamWriter<W> {
     }
 }
 
+/// Writer that buffers the data and writes it as chunks of `capacity` bytes,
+/// except for the last chunk, which is written when the writer is flushed or finished.
+pub(crate) struct ChunkBufWriter<W> {
+    ty: ChunkType,
+    w: ChunkWriter<W>,
+    buf: Vec<u8>,
+    capacity: usize,
+}
+
+impl<W: Write> ChunkBufWriter<W> {
+    #[inline]
+    pub(crate) fn new(ty: ChunkType, inner: W, capacity: usize) -> Self {
+        Self {
+            ty,
+            w: ChunkWriter::from(inner),
+            buf: Vec::new(),
+            capacity,
+        }
+    }
+
+    #[inline]
+    fn write_buffered(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.w.write_chunk((self.ty, self.buf.as_slice()))?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for ChunkBufWriter<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.buf.is_empty() && buf.len() >= self.capacity {
+            self.w.write_chunk((self.ty, &buf[..self.capacity]))?;
+            return Ok(self.capacity);
+        }
+        if self.buf.capacity() == 0 {
+            self.buf.reserve_exact(self.capacity);
+        }
+        let len = buf.len().min(self.capacity - self.buf.len());
+        self.buf.extend_from_slice(&buf[..len]);
+        if self.buf.len() == self.capacity {
+            self.write_buffered()?;
+        }
+        Ok(len)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buffered()?;
+        self.w.w.flush()
+    }
+}
+
+impl<W: Write> TryIntoInner<W> for ChunkBufWriter<W> {
+    #[inline]
+    fn try_into_inner(mut self) -> io::Result<W> {
+        self.write_buffered()?;
+        Ok(self.w.w)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,4 +175,21 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn buffered_chunks_have_capacity() {
+        let mut writer = ChunkBufWriter::new(ChunkType::FDAT, Vec::new(), 4);
+        writer.write_all(b"ab").unwrap();
+        writer.write_all(b"cdefghij").unwrap();
+        writer.write_all(b"k").unwrap();
+        let bytes = writer.try_into_inner().unwrap();
+        let mut rest = &bytes[..];
+        let mut chunks = Vec::new();
+        while !rest.is_empty() {
+            let (chunk, r) = crate::chunk::read_chunk_from_slice(rest).unwrap();
+            chunks.push(chunk.data().to_vec());
+            rest = r;
+        }
+        assert_eq!(chunks, [&b"abcd"[..], b"efgh", b"ijk"]);
+    }
 }
@@ -1,4 +1,5 @@
 mod block;
+mod recipient;
 mod stream;
 
 use crate::io::TryIntoInner;
@@ -8,6 +9,9 @@ use cipher::block_padding::Pkcs7;
 use ctr::{flavors::Ctr128BE, CtrCore};
 use std::io::{self, Read, Write};
 
+pub(crate) use recipient::{unwrap_key, wrap_key, WrappedKey};
+pub use recipient::{Identity, KeyParseError, Recipient};
+
 type CtrReader<R, C, F> = stream::StreamCipherReader<R, CtrCore<C, F>>;
 type CtrWriter<W, C, F> = stream::StreamCipherWriter<W, CtrCore<C, F>>;
 pub(crate) type Ctr128BEReader<R, C> = CtrReader<R, C, Ctr128BE>;
@@ -28,6 +32,19 @@ pub(crate) enum CipherWriter<W: Write> {
     CtrCamellia(Ctr128BEWriter<W, Camellia256>),
 }
 
+impl<W: Write> CipherWriter<W> {
+    #[inline]
+    pub(crate) const fn get_ref(&self) -> &W {
+        match self {
+            Self::No(w) => w,
+            Self::CbcAes(w) => w.get_ref(),
+            Self::CbcCamellia(w) => w.get_ref(),
+            Self::CtrAes(w) => w.get_ref(),
+            Self::CtrCamellia(w) => w.get_ref(),
+        }
+    }
+}
+
 impl<W: Write> Write for CipherWriter<W> {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
@@ -29,6 +29,11 @@ where
             buf: Vec::with_capacity(cbc::Encryptor::<C>::block_size()),
         })
     }
+
+    #[inline]
+    pub(crate) const fn get_ref(&self) -> &W {
+        &self.w
+    }
 }
 
 impl<W, C, P> CbcBlockCipherEncryptWriter<W, C, P>
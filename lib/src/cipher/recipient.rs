@@ -0,0 +1,403 @@
+use crate::random;
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::{
+    error::Error,
+    fmt::{self, Debug, Display, Formatter},
+    io,
+    str::FromStr,
+};
+use x25519_dalek::{PublicKey, SharedSecret, StaticSecret};
+use zeroize::{Zeroize, Zeroizing};
+
+const RECIPIENT_HRP: &str = "age";
+const IDENTITY_HRP: &str = "age-secret-key-";
+const WRAP_INFO: &[u8] = b"pna/X25519";
+const KEY_SIZE: usize = 32;
+const TAG_SIZE: usize = 16;
+
+/// Error of parsing [Recipient] or [Identity].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum KeyParseError {
+    /// The string is not a valid bech32 string.
+    InvalidEncoding,
+    /// The human readable prefix does not match the kind of the key.
+    InvalidPrefix,
+    /// The decoded key has an invalid length.
+    InvalidLength,
+}
+
+impl Display for KeyParseError {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(
+            match self {
+                Self::InvalidEncoding => "key is not a valid bech32 string",
+                Self::InvalidPrefix => "key has an unexpected prefix",
+                Self::InvalidLength => "key has an invalid length",
+            },
+            f,
+        )
+    }
+}
+
+impl Error for KeyParseError {}
+
+/// An X25519 public key that entries can be encrypted to.
+///
+/// The text form is compatible with the recipients of [age](https://age-encryption.org) (`age1...`).
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Recipient([u8; KEY_SIZE]);
+
+impl FromStr for Recipient {
+    type Err = KeyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        bech32::decode(s, RECIPIENT_HRP).map(Self)
+    }
+}
+
+impl Display for Recipient {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&bech32::encode(RECIPIENT_HRP, &self.0))
+    }
+}
+
+/// An X25519 private key that decrypts entries encrypted to its [Recipient].
+///
+/// The text form is compatible with the identities of [age](https://age-encryption.org) (`AGE-SECRET-KEY-1...`).
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Identity([u8; KEY_SIZE]);
+
+impl Identity {
+    /// Generates a new random identity.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the random number generator fails.
+    ///
+    /// # Examples
+    /// ```
+    /// use libpna::Identity;
+    ///
+    /// let identity = Identity::generate().unwrap();
+    /// let recipient = identity.to_public();
+    /// ```
+    #[inline]
+    pub fn generate() -> io::Result<Self> {
+        let mut bytes = [0; KEY_SIZE];
+        random::random_bytes(&mut bytes)?;
+        Ok(Self(bytes))
+    }
+
+    /// Returns the [Recipient] corresponding to this identity.
+    #[inline]
+    pub fn to_public(&self) -> Recipient {
+        Recipient(PublicKey::from(&self.secret()).to_bytes())
+    }
+
+    fn secret(&self) -> StaticSecret {
+        StaticSecret::from(self.0)
+    }
+}
+
+impl Drop for Identity {
+    #[inline]
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Debug for Identity {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Identity").field(&"[REDACTED]").finish()
+    }
+}
+
+impl FromStr for Identity {
+    type Err = KeyParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        bech32::decode(s, IDENTITY_HRP).map(Self)
+    }
+}
+
+impl Display for Identity {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&bech32::encode(IDENTITY_HRP, &self.0).to_ascii_uppercase())
+    }
+}
+
+/// A content key encrypted to a [Recipient].
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct WrappedKey {
+    ephemeral: [u8; KEY_SIZE],
+    body: [u8; KEY_SIZE + TAG_SIZE],
+}
+
+impl WrappedKey {
+    pub(crate) fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.ephemeral.len() + self.body.len());
+        bytes.extend_from_slice(&self.ephemeral);
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    pub(crate) fn try_from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, "Invalid wrapped key length");
+        if bytes.len() != KEY_SIZE * 2 + TAG_SIZE {
+            return Err(invalid());
+        }
+        let (ephemeral, body) = bytes.split_at(KEY_SIZE);
+        Ok(Self {
+            ephemeral: ephemeral.try_into().map_err(|_| invalid())?,
+            body: body.try_into().map_err(|_| invalid())?,
+        })
+    }
+}
+
+fn wrapping_cipher(
+    shared: SharedSecret,
+    ephemeral: &PublicKey,
+    recipient: &PublicKey,
+) -> io::Result<Aes256Gcm> {
+    if !shared.was_contributory() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid X25519 public key",
+        ));
+    }
+    let mut salt = [0; KEY_SIZE * 2];
+    salt[..KEY_SIZE].copy_from_slice(ephemeral.as_bytes());
+    salt[KEY_SIZE..].copy_from_slice(recipient.as_bytes());
+    let mut key = Zeroizing::new([0; KEY_SIZE]);
+    Hkdf::<Sha256>::new(Some(&salt), shared.as_bytes())
+        .expand(WRAP_INFO, key.as_mut_slice())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+    Aes256Gcm::new_from_slice(key.as_slice())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Encrypts `key` to `recipient` with a newly generated ephemeral key.
+pub(crate) fn wrap_key(key: &[u8], recipient: &Recipient) -> io::Result<WrappedKey> {
+    let ephemeral_secret = Identity::generate()?.secret();
+    let ephemeral = PublicKey::from(&ephemeral_secret);
+    let recipient = PublicKey::from(recipient.0);
+    let shared = ephemeral_secret.diffie_hellman(&recipient);
+    let body = wrapping_cipher(shared, &ephemeral, &recipient)?
+        .encrypt(Nonce::from_slice(&[0; 12]), key)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Failed to wrap key"))?;
+    Ok(WrappedKey {
+        ephemeral: ephemeral.to_bytes(),
+        body: body
+            .try_into()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid key length"))?,
+    })
+}
+
+/// Decrypts the content key from `wrapped_keys` with one of `identities`.
+pub(crate) fn unwrap_key(
+    wrapped_keys: &[WrappedKey],
+    identities: &[Identity],
+) -> io::Result<Zeroizing<Vec<u8>>> {
+    if identities.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Identity was not provided",
+        ));
+    }
+    for identity in identities {
+        let secret = identity.secret();
+        let recipient = PublicKey::from(&secret);
+        for wrapped in wrapped_keys {
+            let ephemeral = PublicKey::from(wrapped.ephemeral);
+            let shared = secret.diffie_hellman(&ephemeral);
+            let Ok(cipher) = wrapping_cipher(shared, &ephemeral, &recipient) else {
+                continue;
+            };
+            if let Ok(key) = cipher.decrypt(Nonce::from_slice(&[0; 12]), wrapped.body.as_slice()) {
+                return Ok(Zeroizing::new(key));
+            }
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "No identity matched the recipients of the entry",
+    ))
+}
+
+mod bech32 {
+    use super::KeyParseError;
+
+    const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    const CHECKSUM_LENGTH: usize = 6;
+
+    fn polymod(values: impl IntoIterator<Item = u8>) -> u32 {
+        let mut checksum = 1u32;
+        for v in values {
+            let top = checksum >> 25;
+            checksum = ((checksum & 0x1ffffff) << 5) ^ u32::from(v);
+            for (i, g) in GENERATOR.iter().enumerate() {
+                if (top >> i) & 1 == 1 {
+                    checksum ^= g;
+                }
+            }
+        }
+        checksum
+    }
+
+    fn expand_hrp(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+        hrp.bytes()
+            .map(|b| b >> 5)
+            .chain([0])
+            .chain(hrp.bytes().map(|b| b & 31))
+    }
+
+    fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+        let mut acc = 0u32;
+        let mut bits = 0u32;
+        let max = (1u32 << to) - 1;
+        let mut result = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+        for &value in data {
+            acc = (acc << from) | u32::from(value);
+            bits += from;
+            while bits >= to {
+                bits -= to;
+                result.push(((acc >> bits) & max) as u8);
+            }
+        }
+        if pad {
+            if bits > 0 {
+                result.push(((acc << (to - bits)) & max) as u8);
+            }
+        } else if bits >= from || ((acc << (to - bits)) & max) != 0 {
+            return None;
+        }
+        Some(result)
+    }
+
+    pub(super) fn encode(hrp: &str, data: &[u8]) -> String {
+        let data = convert_bits(data, 8, 5, true).unwrap_or_default();
+        let checksum = polymod(
+            expand_hrp(hrp)
+                .chain(data.iter().copied())
+                .chain([0; CHECKSUM_LENGTH]),
+        ) ^ 1;
+        let mut result = String::with_capacity(hrp.len() + 1 + data.len() + CHECKSUM_LENGTH);
+        result.push_str(hrp);
+        result.push('1');
+        for v in data
+            .iter()
+            .copied()
+            .chain((0..CHECKSUM_LENGTH).map(|i| ((checksum >> (5 * (5 - i))) & 31) as u8))
+        {
+            result.push(CHARSET[usize::from(v)] as char);
+        }
+        result
+    }
+
+    pub(super) fn decode<const N: usize>(s: &str, hrp: &str) -> Result<[u8; N], KeyParseError> {
+        if s.bytes().any(|b| b.is_ascii_lowercase()) && s.bytes().any(|b| b.is_ascii_uppercase()) {
+            return Err(KeyParseError::InvalidEncoding);
+        }
+        let s = s.to_ascii_lowercase();
+        let (prefix, data) = s.rsplit_once('1').ok_or(KeyParseError::InvalidEncoding)?;
+        if prefix != hrp {
+            return Err(KeyParseError::InvalidPrefix);
+        }
+        let data = data
+            .bytes()
+            .map(|b| CHARSET.iter().position(|c| *c == b).map(|p| p as u8))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(KeyParseError::InvalidEncoding)?;
+        if data.len() < CHECKSUM_LENGTH || polymod(expand_hrp(hrp).chain(data.iter().copied())) != 1
+        {
+            return Err(KeyParseError::InvalidEncoding);
+        }
+        let data = convert_bits(&data[..data.len() - CHECKSUM_LENGTH], 5, 8, false)
+            .ok_or(KeyParseError::InvalidEncoding)?;
+        data.try_into().map_err(|_| KeyParseError::InvalidLength)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::ManuallyDrop;
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn parse_age_keys() {
+        let identity = Identity::from_str(
+            "AGE-SECRET-KEY-1QYPQXPQ9QCRSSZG2PVXQ6RS0ZQG3YYC5Z5TPWXQERGD3C8G7RUSQGPQYEE",
+        )
+        .unwrap();
+        assert_eq!(
+            identity.to_public().to_string(),
+            "age1q73he0q5yzfu3d64msd3p6rvksnrwjk3d2598mgtmlqt9wrdr37q2vrn72"
+        );
+        assert_eq!(
+            identity.to_string(),
+            "AGE-SECRET-KEY-1QYPQXPQ9QCRSSZG2PVXQ6RS0ZQG3YYC5Z5TPWXQERGD3C8G7RUSQGPQYEE"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_keys() {
+        let recipient = Identity::generate().unwrap().to_public().to_string();
+        assert_eq!(
+            Identity::from_str(&recipient),
+            Err(KeyParseError::InvalidPrefix)
+        );
+        let mut broken = recipient.clone();
+        broken.pop();
+        broken.push(if recipient.ends_with('q') { 'p' } else { 'q' });
+        assert_eq!(
+            Recipient::from_str(&broken),
+            Err(KeyParseError::InvalidEncoding)
+        );
+        assert_eq!(
+            Recipient::from_str(&recipient.to_ascii_uppercase()).map(|it| it.to_string()),
+            Ok(recipient)
+        );
+    }
+
+    #[test]
+    fn identity_is_zeroized_on_drop() {
+        let mut identity = ManuallyDrop::new(Identity([7; KEY_SIZE]));
+        // SAFETY: the identity is dropped only once, and the storage of a byte array stays readable after the drop.
+        unsafe { ManuallyDrop::drop(&mut identity) };
+        assert_eq!(identity.0, [0; KEY_SIZE]);
+    }
+
+    #[test]
+    fn wrap_unwrap() {
+        let identities = [Identity::generate().unwrap(), Identity::generate().unwrap()];
+        let key = [7; KEY_SIZE];
+        let wrapped = identities
+            .iter()
+            .map(|it| wrap_key(&key, &it.to_public()))
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        for identity in &identities {
+            assert_eq!(
+                *unwrap_key(&wrapped, std::slice::from_ref(identity)).unwrap(),
+                key
+            );
+        }
+        let wrapped = WrappedKey::try_from_bytes(&wrapped[0].to_bytes()).unwrap();
+        assert!(unwrap_key(&[wrapped], &[Identity::generate().unwrap()]).is_err());
+    }
+}
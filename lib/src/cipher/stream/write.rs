@@ -30,6 +30,11 @@ where
         })
     }
 
+    #[inline]
+    pub(crate) const fn get_ref(&self) -> &W {
+        &self.w
+    }
+
     pub(crate) fn finish(self) -> io::Result<W> {
         Ok(self.w)
     }
@@ -8,6 +8,21 @@ pub(crate) mod deflate;
 pub(crate) mod xz;
 pub(crate) mod zstandard;
 
+/// Trains a zstd dictionary from the given samples.
+///
+/// The trained dictionary can be used with [`WriteOptionsBuilder::zstd_dictionary`].
+/// `max_size` is the maximum size of the dictionary in bytes.
+///
+/// # Errors
+///
+/// Returns an error if the training failed, for example when there are too few samples.
+///
+/// [`WriteOptionsBuilder::zstd_dictionary`]: crate::WriteOptionsBuilder::zstd_dictionary
+#[inline]
+pub fn train_zstd_dictionary<S: AsRef<[u8]>>(samples: &[S], max_size: usize) -> Result<Vec<u8>> {
+    zstd::dict::from_samples(samples, max_size)
+}
+
 pub(crate) enum CompressionWriter<W: Write> {
     No(W),
     Deflate(ZlibEncoder<W>),
@@ -15,6 +30,18 @@ pub(crate) enum CompressionWriter<W: Write> {
     Xz(XzEncoder<W>),
 }
 
+impl<W: Write> CompressionWriter<W> {
+    #[inline]
+    pub(crate) fn get_ref(&self) -> &W {
+        match self {
+            Self::No(w) => w,
+            Self::Deflate(w) => w.get_ref(),
+            Self::ZStd(w) => w.get_ref(),
+            Self::Xz(w) => w.get_ref(),
+        }
+    }
+}
+
 impl<W: Write> Write for CompressionWriter<W> {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
@@ -1,25 +1,35 @@
 mod attr;
 mod builder;
+mod compare;
+mod digest;
 mod header;
 mod meta;
 mod name;
 mod options;
+mod packed;
 mod read;
 mod reference;
+#[cfg(feature = "serde")]
+mod serialize;
 mod write;
 
-pub use self::{attr::*, builder::*, header::*, meta::*, name::*, options::*, reference::*};
-pub(crate) use self::{private::*, read::*, write::*};
+pub use self::{
+    attr::*, builder::*, compare::*, header::*, meta::*, name::*, options::*, reference::*,
+};
+pub(crate) use self::{packed::*, private::*, read::*, write::*};
 use crate::{
     chunk::{
         chunk_data_split, Chunk, ChunkExt, ChunkReader, ChunkType, RawChunk, MIN_CHUNK_BYTES_SIZE,
     },
+    cipher::WrappedKey,
     util::slice::skip_while,
 };
 use std::{
     borrow::Cow,
     collections::VecDeque,
     io::{self, Read, Write},
+    mem,
+    sync::Arc,
     time::Duration,
 };
 
@@ -28,11 +38,44 @@ mod private {
     pub trait SealedEntryExt {
         fn into_chunks(self) -> Vec<RawChunk>;
         fn write_in<W: Write>(&self, writer: &mut W) -> io::Result<usize>;
+        /// Returns the zstd dictionary the data of the entry is compressed with.
+        #[inline]
+        fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+            None
+        }
     }
 }
 
 /// Archive entry.
-pub trait Entry: SealedEntryExt {}
+pub trait Entry: SealedEntryExt {
+    /// Returns the number of bytes the entry takes when it is added to an archive.
+    ///
+    /// This is the sum of the lengths of its chunks, as [EntryPart::bytes_len] is.
+    /// The zstd dictionary that the entry may be compressed with is written to an archive
+    /// only once before the first entry using it, so it is not included.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{Archive, Entry, EntryBuilder, WriteOptions};
+    /// # use std::io::{self, prelude::*};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut builder = EntryBuilder::new_file("foo.txt".into(), WriteOptions::store())?;
+    /// builder.write_all(b"content")?;
+    /// let entry = builder.build()?;
+    /// let len = entry.estimated_bytes_len();
+    /// let mut archive = Archive::write_header(Vec::new())?;
+    /// assert_eq!(archive.add_entry(entry)?, len);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    fn estimated_bytes_len(&self) -> usize {
+        // NOTE: io::Sink never fails to write.
+        self.write_in(&mut io::sink()).unwrap_or_default()
+    }
+}
 
 /// Chunks from `FHED` to `FEND`, containing `FHED` and `FEND`
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
@@ -88,6 +131,34 @@ impl SealedEntryExt for RawEntry<Cow<'_, [u8]>> {
 
 impl<T> Entry for RawEntry<T> where RawEntry<T>: SealedEntryExt {}
 
+/// [RawEntry] read from an archive, with the zstd dictionary of the archive.
+pub(crate) struct ArchiveRawEntry<T = Vec<u8>> {
+    pub(crate) entry: RawEntry<T>,
+    pub(crate) zstd_dictionary: Option<Arc<[u8]>>,
+}
+
+impl<T> SealedEntryExt for ArchiveRawEntry<T>
+where
+    RawEntry<T>: SealedEntryExt,
+{
+    #[inline]
+    fn into_chunks(self) -> Vec<RawChunk> {
+        self.entry.into_chunks()
+    }
+
+    #[inline]
+    fn write_in<W: Write>(&self, writer: &mut W) -> io::Result<usize> {
+        self.entry.write_in(writer)
+    }
+
+    #[inline]
+    fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+        self.zstd_dictionary.as_ref()
+    }
+}
+
+impl<T> Entry for ArchiveRawEntry<T> where RawEntry<T>: SealedEntryExt {}
+
 impl<'a> From<RawEntry<Cow<'a, [u8]>>> for RawEntry<Vec<u8>> {
     #[inline]
     fn from(value: RawEntry<Cow<'a, [u8]>>) -> Self {
@@ -117,12 +188,46 @@ impl<'a> From<RawEntry<&'a [u8]>> for RawEntry<Cow<'a, [u8]>> {
 }
 
 /// Reader for Entry data.
-pub struct EntryDataReader<'r>(EntryReader<crate::io::FlattenReader<'r>>);
+pub struct EntryDataReader<'r> {
+    inner: EntryReader<crate::io::FlattenReader<'r>>,
+    processed: u64,
+}
+
+impl EntryDataReader<'_> {
+    /// Returns the number of bytes of the entry data read so far,
+    /// after decryption and decompression.
+    ///
+    /// Callers extracting a large file can poll this to report the progress.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{EntryBuilder, ReadOptions, WriteOptions};
+    /// use std::io::{self, Read, Write};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut builder = EntryBuilder::new_file("example.txt".into(), WriteOptions::builder().build())?;
+    /// builder.write_all(b"content")?;
+    /// let entry = builder.build()?;
+    /// let mut reader = entry.reader(ReadOptions::builder().build())?;
+    /// assert_eq!(reader.bytes_processed(), 0);
+    /// reader.read_exact(&mut [0; 4])?;
+    /// assert_eq!(reader.bytes_processed(), 4);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub const fn bytes_processed(&self) -> u64 {
+        self.processed
+    }
+}
 
 impl Read for EntryDataReader<'_> {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.read(buf)
+        let n = self.inner.read(buf)?;
+        self.processed += n as u64;
+        Ok(n)
     }
 }
 
@@ -168,10 +273,57 @@ where
             ReadEntry::Solid(s) => s.write_in(writer),
         }
     }
+
+    #[inline]
+    fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+        match self {
+            ReadEntry::Normal(r) => r.zstd_dictionary(),
+            ReadEntry::Solid(s) => s.zstd_dictionary(),
+        }
+    }
 }
 
 impl<T> Entry for ReadEntry<T> where ReadEntry<T>: SealedEntryExt {}
 
+/// Sets `current` to the zstd `dictionary` of an entry added to an archive or a solid entry,
+/// and returns `true` if `current` was not set before.
+///
+/// # Errors
+///
+/// Returns an error if `current` is set to a different dictionary,
+/// since all the entries of an archive share one dictionary.
+pub(crate) fn merge_zstd_dictionary(
+    current: &mut Option<Arc<[u8]>>,
+    dictionary: Option<&Arc<[u8]>>,
+) -> io::Result<bool> {
+    match (current.as_ref(), dictionary) {
+        (_, None) => Ok(false),
+        (None, Some(dictionary)) => {
+            *current = Some(dictionary.clone());
+            Ok(true)
+        }
+        (Some(current), Some(dictionary)) if current == dictionary => Ok(false),
+        (Some(_), Some(_)) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Entries compressed with different zstd dictionaries cannot be in one archive",
+        )),
+    }
+}
+
+impl<T> ReadEntry<T> {
+    /// Sets the zstd dictionary of the archive the entry is read from.
+    #[inline]
+    pub(crate) fn with_zstd_dictionary(mut self, dictionary: Option<&Arc<[u8]>>) -> Self {
+        if let Some(dictionary) = dictionary {
+            match &mut self {
+                Self::Normal(r) => r.zstd_dictionary = Some(dictionary.clone()),
+                Self::Solid(s) => s.zstd_dictionary = Some(dictionary.clone()),
+            }
+        }
+        self
+    }
+}
+
 impl<T> TryFrom<RawEntry<T>> for ReadEntry<T>
 where
     RawChunk<T>: Chunk,
@@ -246,9 +398,9 @@ impl<'a> From<ReadEntry<&'a [u8]>> for ReadEntry<Cow<'a, [u8]>> {
     }
 }
 
-pub(crate) struct EntryIterator<'s>(EntryReader<crate::io::FlattenReader<'s>>);
+pub(crate) struct EntryIterator<R>(R);
 
-impl Iterator for EntryIterator<'_> {
+impl<R: Read> Iterator for EntryIterator<R> {
     type Item = io::Result<NormalEntry>;
 
     #[inline]
@@ -273,6 +425,53 @@ impl Iterator for EntryIterator<'_> {
     }
 }
 
+impl<R: Read> EntryIterator<R> {
+    /// Reads through the entries until the one named `name` and returns it,
+    /// or `None` if no entry is left.
+    ///
+    /// The data chunks of the entries before it are drained without being kept.
+    fn find_by_name(&mut self, name: &str) -> Option<io::Result<NormalEntry>> {
+        let mut chunk_reader = ChunkReader::from(&mut self.0);
+        loop {
+            let head = match chunk_reader.read_chunk() {
+                Ok(chunk) => chunk,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            let found = match head.ty {
+                ChunkType::FHED => match EntryHeader::try_from(head.data()) {
+                    Ok(header) => header.path().as_str() == name,
+                    Err(e) => return Some(Err(e)),
+                },
+                _ => true,
+            };
+            let mut chunks = vec![head];
+            loop {
+                let (length, ty) = match chunk_reader.read_chunk_head() {
+                    Ok(head) => head,
+                    Err(e) => return Some(Err(e)),
+                };
+                let body = if !found && ty == ChunkType::FDAT {
+                    chunk_reader.skip_chunk_body(length, ty).map(|_| None)
+                } else {
+                    chunk_reader.read_chunk_body(length, ty).map(Some)
+                };
+                match body {
+                    Ok(Some(chunk)) if found => chunks.push(chunk),
+                    Ok(_) => (),
+                    Err(e) => return Some(Err(e)),
+                }
+                if ty == ChunkType::FEND {
+                    break;
+                }
+            }
+            if found {
+                return Some(RawEntry(chunks).try_into());
+            }
+        }
+    }
+}
+
 /// A solid mode entry.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct SolidEntry<T = Vec<u8>> {
@@ -280,6 +479,7 @@ pub struct SolidEntry<T = Vec<u8>> {
     phsf: Option<String>,
     data: Vec<T>,
     extra: Vec<RawChunk<T>>,
+    zstd_dictionary: Option<Arc<[u8]>>,
 }
 
 impl<T> SolidEntry<T>
@@ -306,6 +506,11 @@ where
 }
 
 impl SealedEntryExt for SolidEntry<Vec<u8>> {
+    #[inline]
+    fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+        self.zstd_dictionary.as_ref()
+    }
+
     fn into_chunks(self) -> Vec<RawChunk> {
         let mut chunks = vec![];
         chunks.push(RawChunk::from_data(ChunkType::SHED, self.header.to_bytes()));
@@ -328,6 +533,11 @@ impl SealedEntryExt for SolidEntry<Vec<u8>> {
 }
 
 impl SealedEntryExt for SolidEntry<&[u8]> {
+    #[inline]
+    fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+        self.zstd_dictionary.as_ref()
+    }
+
     fn into_chunks(self) -> Vec<RawChunk> {
         let mut chunks = vec![];
         chunks.push(RawChunk::from_data(ChunkType::SHED, self.header.to_bytes()));
@@ -350,6 +560,11 @@ impl SealedEntryExt for SolidEntry<&[u8]> {
 }
 
 impl SealedEntryExt for SolidEntry<Cow<'_, [u8]>> {
+    #[inline]
+    fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+        self.zstd_dictionary.as_ref()
+    }
+
     fn into_chunks(self) -> Vec<RawChunk> {
         let mut chunks = vec![];
         chunks.push(RawChunk::from_data(ChunkType::SHED, self.header.to_bytes()));
@@ -373,6 +588,61 @@ impl SealedEntryExt for SolidEntry<Cow<'_, [u8]>> {
 
 impl<T> Entry for SolidEntry<T> where SolidEntry<T>: SealedEntryExt {}
 
+impl<T: AsRef<[u8]>> ReadEntry<T> {
+    /// Returns an estimate in bytes of the peak memory required to read this entry.
+    ///
+    /// The estimate is the sum of:
+    /// - the stored size of the entry, since all data chunks of an entry are resident while it is read.
+    ///   For archives read from a slice, the chunks borrow the slice and are not copied,
+    ///   while for archives read from a [Read] the chunks are owned copies of the read data.
+    ///   In both cases the chunks are read through without any further copy.
+    /// - an upper bound of the working memory of the decompressor, such as the window of
+    ///   ZStandard and the dictionary of XZ.
+    ///
+    /// The entries in a [SolidEntry] are decompressed and materialized one by one,
+    /// so reading them additionally requires the stored size of each entry contained while it is alive.
+    /// That size is not known until decompressed and is not included in the estimate.
+    ///
+    /// # Examples
+    /// ```
+    /// use libpna::{EntryBuilder, ReadEntry, WriteOptions};
+    /// # use std::io::{self, Write};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut builder = EntryBuilder::new_file("file.txt".into(), WriteOptions::store())?;
+    /// builder.write_all(b"text")?;
+    /// let entry = ReadEntry::from(builder.build()?);
+    /// assert_eq!(entry.estimated_peak_memory(), 4);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn estimated_peak_memory(&self) -> usize {
+        match self {
+            Self::Solid(s) => s
+                .stored_size()
+                .saturating_add(decoder_memory_upper_bound(s.header.compression)),
+            Self::Normal(r) => r
+                .stored_size()
+                .saturating_add(decoder_memory_upper_bound(r.header.compression)),
+        }
+    }
+}
+
+/// Upper bound of the working memory of the decompressor for data written by this library.
+#[inline]
+const fn decoder_memory_upper_bound(compression: Compression) -> usize {
+    match compression {
+        Compression::No => 0,
+        // 32 KiB sliding window.
+        Compression::Deflate => 32 * 1024,
+        // Window of the highest compression level.
+        Compression::ZStandard => 128 * 1024 * 1024,
+        // Dictionary of the highest preset.
+        Compression::XZ => 64 * 1024 * 1024,
+    }
+}
+
 impl<T> SolidEntry<T> {
     /// Returns solid mode information header reference.
     #[inline]
@@ -380,11 +650,27 @@ impl<T> SolidEntry<T> {
         &self.header
     }
 
+    /// Password hash of the [SolidEntry] in the PHC string format,
+    /// or `None` if the entry is not encrypted with a password.
+    #[inline]
+    pub fn password_hash(&self) -> Option<&str> {
+        self.phsf.as_deref()
+    }
+
     /// Extra chunks.
     #[inline]
     pub fn extra_chunks(&self) -> &[RawChunk<T>] {
         &self.extra
     }
+
+    /// Data chunks of the [SolidEntry] as stored in the archive, that is, compressed and encrypted.
+    ///
+    /// The first chunk of an encrypted [SolidEntry] is the initialization vector of the cipher.
+    /// The total size of the chunks is [`SolidEntry::stored_size`].
+    #[inline]
+    pub fn data_chunks(&self) -> &[T] {
+        &self.data
+    }
 }
 
 impl<T: AsRef<[u8]>> SolidEntry<T> {
@@ -423,16 +709,153 @@ impl<T: AsRef<[u8]>> SolidEntry<T> {
         &self,
         password: Option<&str>,
     ) -> io::Result<impl Iterator<Item = io::Result<NormalEntry>> + '_> {
+        // NOTE: The entries in a solid entry share the dictionary of the archive with it.
+        Ok(EntryIterator(self.contents(password)?).map(|entry| {
+            entry.map(|mut entry| {
+                entry.zstd_dictionary = self.zstd_dictionary.clone();
+                entry
+            })
+        }))
+    }
+
+    /// Returns a reader of the data of the entry named `name` in the [SolidEntry],
+    /// or `None` if there is no such entry.
+    ///
+    /// The entries before it are still decrypted and decompressed to read through them,
+    /// but unlike [SolidEntry::entries] their data is drained without being kept,
+    /// so only the data of the found entry is held in memory.
+    ///
+    /// # Example
+    /// ```
+    /// use libpna::{Archive, EntryBuilder, ReadEntry, SolidEntryBuilder, WriteOptions};
+    /// # use std::io::{self, Read, Write};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut builder = SolidEntryBuilder::new(WriteOptions::store())?;
+    /// for (name, text) in [("a.txt", "first"), ("b.txt", "second")] {
+    ///     let mut entry = EntryBuilder::new_file(name.into(), WriteOptions::store())?;
+    ///     entry.write_all(text.as_bytes())?;
+    ///     builder.add_entry(entry.build()?)?;
+    /// }
+    /// let mut archive = Archive::write_header(Vec::new())?;
+    /// archive.add_entry(builder.build()?)?;
+    /// let bytes = archive.finalize()?;
+    ///
+    /// let mut archive = Archive::read_header_from_slice(&bytes)?;
+    /// for entry in archive.entries_slice() {
+    ///     if let ReadEntry::Solid(solid) = entry? {
+    ///         let mut text = String::new();
+    ///         solid.reader_for("b.txt", None)?.unwrap().read_to_string(&mut text)?;
+    ///         assert_eq!(text, "second");
+    ///         assert!(solid.reader_for("c.txt", None)?.is_none());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the password is required but not provided,
+    /// or if the entries before the found one are broken.
+    #[inline]
+    pub fn reader_for(
+        &self,
+        name: &str,
+        password: Option<&str>,
+    ) -> io::Result<Option<EntryDataReader<'static>>> {
+        let Some(entry) = EntryIterator(self.contents(password)?)
+            .find_by_name(name)
+            .transpose()?
+        else {
+            return Ok(None);
+        };
+        let mut entry = entry;
+        entry.zstd_dictionary = self.zstd_dictionary.clone();
+        let data = mem::take(&mut entry.data);
+        entry
+            .data_reader(
+                crate::io::FlattenReader::from_owned(data),
+                ReadOptions::with_password(password),
+            )
+            .map(Some)
+    }
+
+    /// Returns an iterator over the data of the [SolidEntry] as stored in the archive,
+    /// that is, compressed and encrypted.
+    #[inline]
+    pub fn raw_data(&self) -> impl Iterator<Item = &[u8]> {
+        self.data.iter().map(|it| it.as_ref())
+    }
+
+    /// Returns the total size in bytes of the data chunks of the [SolidEntry],
+    /// that is, the size of the compressed and encrypted contents.
+    #[inline]
+    pub fn stored_size(&self) -> usize {
+        self.data.iter().map(|it| it.as_ref().len()).sum()
+    }
+
+    /// Returns a reader of the decrypted and decompressed contents of the [SolidEntry].
+    ///
+    /// The contents are the chunks of the entries, converted back from the packed encoding
+    /// if [SolidHeader::is_packed] is `true`.
+    /// They can be stored and parsed into entries later with
+    /// [SolidEntry::entries_from_contents], without decrypting and decompressing again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the password is required but not provided,
+    /// or if the version, the compression or encryption method is not supported.
+    #[inline]
+    pub fn contents(&self, password: Option<&str>) -> io::Result<impl Read + '_> {
+        self.header.check_version()?;
         let reader = decrypt_reader(
             crate::io::FlattenReader::new(self.data.iter().map(|it| it.as_ref()).collect()),
             self.header.encryption,
             self.header.cipher_mode,
             self.phsf.as_deref(),
-            password.as_ref().map(|it| it.as_bytes()),
+            &[],
+            ReadOptions::with_password(password),
+        )?;
+        let reader = decompress_reader(
+            reader,
+            self.header.compression,
+            self.zstd_dictionary.as_deref(),
         )?;
-        let reader = decompress_reader(reader, self.header.compression)?;
+        Ok(PackedReader::new(
+            EntryReader(reader),
+            self.header.is_packed(),
+        ))
+    }
+}
 
-        Ok(EntryIterator(EntryReader(reader)))
+impl SolidEntry {
+    /// Returns an iterator over the entries in the contents of a [SolidEntry]
+    /// read from [SolidEntry::contents].
+    ///
+    /// # Example
+    /// ```no_run
+    /// use libpna::{Archive, ReadEntry, SolidEntry};
+    /// use std::{fs, io};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut archive = Archive::read_header(fs::File::open("foo.pna")?)?;
+    /// for entry in archive.entries() {
+    ///     if let ReadEntry::Solid(solid_entry) = entry? {
+    ///         io::copy(&mut solid_entry.contents(None)?, &mut fs::File::create("solid.bin")?)?;
+    ///     }
+    /// }
+    /// for entry in SolidEntry::entries_from_contents(fs::File::open("solid.bin")?) {
+    ///     println!("{}", entry?.header().path());
+    /// }
+    /// #    Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn entries_from_contents<R: Read>(
+        reader: R,
+    ) -> impl Iterator<Item = io::Result<NormalEntry>> {
+        EntryIterator(reader)
     }
 }
 
@@ -444,6 +867,7 @@ impl<'a> From<SolidEntry<Cow<'a, [u8]>>> for SolidEntry<Vec<u8>> {
             phsf: value.phsf,
             data: value.data.into_iter().map(Into::into).collect(),
             extra: value.extra.into_iter().map(Into::into).collect(),
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -456,6 +880,7 @@ impl<'a> From<SolidEntry<&'a [u8]>> for SolidEntry<Vec<u8>> {
             phsf: value.phsf,
             data: value.data.into_iter().map(Into::into).collect(),
             extra: value.extra.into_iter().map(Into::into).collect(),
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -468,6 +893,7 @@ impl<'a> From<SolidEntry<&'a [u8]>> for SolidEntry<Cow<'a, [u8]>> {
             phsf: value.phsf,
             data: value.data.into_iter().map(Into::into).collect(),
             extra: value.extra.into_iter().map(Into::into).collect(),
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -480,6 +906,7 @@ impl From<SolidEntry<Vec<u8>>> for SolidEntry<Cow<'_, [u8]>> {
             phsf: value.phsf,
             data: value.data.into_iter().map(Into::into).collect(),
             extra: value.extra.into_iter().map(Into::into).collect(),
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -524,6 +951,7 @@ where
             match chunk.ty() {
                 ChunkType::SHED => info = Some(SolidHeader::try_from(chunk.data())?),
                 ChunkType::SDAT => data.push(chunk.data),
+                ChunkType::SEND => break,
                 ChunkType::PHSF => {
                     phsf = Some(
                         String::from_utf8(chunk.data().into())
@@ -544,6 +972,7 @@ where
             phsf,
             data,
             extra,
+            zstd_dictionary: None,
         })
     }
 }
@@ -553,10 +982,12 @@ where
 pub struct NormalEntry<T = Vec<u8>> {
     pub(crate) header: EntryHeader,
     pub(crate) phsf: Option<String>,
+    pub(crate) wrapped_keys: Vec<WrappedKey>,
     pub(crate) extra: Vec<RawChunk<T>>,
     pub(crate) data: Vec<T>,
     pub(crate) metadata: Metadata,
     pub(crate) xattrs: Vec<ExtendedAttribute>,
+    pub(crate) zstd_dictionary: Option<Arc<[u8]>>,
 }
 
 #[deprecated(
@@ -593,6 +1024,7 @@ where
         let mut info = None;
         let mut size = None;
         let mut phsf = None;
+        let mut wrapped_keys = vec![];
         let mut ctime = None;
         let mut mtime = None;
         let mut atime = None;
@@ -607,6 +1039,7 @@ where
                             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
                     );
                 }
+                ChunkType::WKEY => wrapped_keys.push(WrappedKey::try_from_bytes(chunk.data())?),
                 ChunkType::FDAT => {
                     compressed_size += chunk.data().len();
                     data.push(chunk.data);
@@ -617,6 +1050,9 @@ where
                 ChunkType::aTIM => atime = Some(timestamp(chunk.data())?),
                 ChunkType::fPRM => permission = Some(Permission::try_from_bytes(chunk.data())?),
                 ChunkType::xATR => xattrs.push(ExtendedAttribute::try_from_bytes(chunk.data())?),
+                ChunkType::xATZ => {
+                    xattrs.push(ExtendedAttribute::try_from_compressed_bytes(chunk.data())?)
+                }
                 _ => extra.push(chunk),
             }
         }
@@ -638,6 +1074,7 @@ where
         Ok(Self {
             header,
             phsf,
+            wrapped_keys,
             extra,
             metadata: Metadata {
                 raw_file_size: size,
@@ -649,6 +1086,7 @@ where
             },
             data,
             xattrs,
+            zstd_dictionary: None,
         })
     }
 }
@@ -686,25 +1124,28 @@ where
         if let Some(p) = &self.phsf {
             total += (ChunkType::PHSF, p.as_bytes()).write_chunk_in(writer)?;
         }
+        for wrapped_key in &self.wrapped_keys {
+            total += (ChunkType::WKEY, wrapped_key.to_bytes()).write_chunk_in(writer)?;
+        }
         for data_chunk in &self.data {
             for data_unit in data_chunk.as_ref().chunks(u32::MAX as usize) {
                 total += (ChunkType::FDAT, data_unit).write_chunk_in(writer)?;
             }
         }
         if let Some(c) = created {
-            total += (ChunkType::cTIM, c.as_secs().to_be_bytes()).write_chunk_in(writer)?;
+            total += (ChunkType::cTIM, timestamp_to_bytes(*c)).write_chunk_in(writer)?;
         }
         if let Some(d) = modified {
-            total += (ChunkType::mTIM, d.as_secs().to_be_bytes()).write_chunk_in(writer)?;
+            total += (ChunkType::mTIM, timestamp_to_bytes(*d)).write_chunk_in(writer)?;
         }
         if let Some(a) = accessed {
-            total += (ChunkType::aTIM, a.as_secs().to_be_bytes()).write_chunk_in(writer)?;
+            total += (ChunkType::aTIM, timestamp_to_bytes(*a)).write_chunk_in(writer)?;
         }
         if let Some(p) = permission {
             total += (ChunkType::fPRM, p.to_bytes()).write_chunk_in(writer)?;
         }
         for xattr in &self.xattrs {
-            total += (ChunkType::xATR, xattr.to_bytes()).write_chunk_in(writer)?;
+            total += xattr.to_chunk().write_chunk_in(writer)?;
         }
         total += (ChunkType::FEND, []).write_chunk_in(writer)?;
         Ok(total)
@@ -712,6 +1153,11 @@ where
 }
 
 impl SealedEntryExt for NormalEntry<Vec<u8>> {
+    #[inline]
+    fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+        self.zstd_dictionary.as_ref()
+    }
+
     fn into_chunks(self) -> Vec<RawChunk> {
         let Metadata {
             raw_file_size,
@@ -734,34 +1180,28 @@ impl SealedEntryExt for NormalEntry<Vec<u8>> {
         if let Some(p) = self.phsf {
             vec.push(RawChunk::from_data(ChunkType::PHSF, p.into_bytes()));
         }
+        for wrapped_key in self.wrapped_keys {
+            vec.push(RawChunk::from_data(ChunkType::WKEY, wrapped_key.to_bytes()));
+        }
         for data_chunk in self.data {
             for data_unit in data_chunk.chunks(u32::MAX as usize) {
                 vec.push(RawChunk::from_data(ChunkType::FDAT, data_unit));
             }
         }
         if let Some(c) = created {
-            vec.push(RawChunk::from_data(
-                ChunkType::cTIM,
-                c.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::cTIM, timestamp_to_bytes(c)));
         }
         if let Some(d) = modified {
-            vec.push(RawChunk::from_data(
-                ChunkType::mTIM,
-                d.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::mTIM, timestamp_to_bytes(d)));
         }
         if let Some(a) = accessed {
-            vec.push(RawChunk::from_data(
-                ChunkType::aTIM,
-                a.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::aTIM, timestamp_to_bytes(a)));
         }
         if let Some(p) = permission {
             vec.push(RawChunk::from_data(ChunkType::fPRM, p.to_bytes()));
         }
         for xattr in self.xattrs {
-            vec.push(RawChunk::from_data(ChunkType::xATR, xattr.to_bytes()));
+            vec.push(xattr.to_chunk());
         }
         vec.push(RawChunk::from_data(ChunkType::FEND, Vec::new()));
         vec
@@ -774,6 +1214,11 @@ impl SealedEntryExt for NormalEntry<Vec<u8>> {
 }
 
 impl SealedEntryExt for NormalEntry<&[u8]> {
+    #[inline]
+    fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+        self.zstd_dictionary.as_ref()
+    }
+
     fn into_chunks(self) -> Vec<RawChunk> {
         let Metadata {
             raw_file_size,
@@ -796,34 +1241,28 @@ impl SealedEntryExt for NormalEntry<&[u8]> {
         if let Some(p) = self.phsf {
             vec.push(RawChunk::from_data(ChunkType::PHSF, p.into_bytes()));
         }
+        for wrapped_key in self.wrapped_keys {
+            vec.push(RawChunk::from_data(ChunkType::WKEY, wrapped_key.to_bytes()));
+        }
         for data_chunk in self.data {
             for data_unit in data_chunk.chunks(u32::MAX as usize) {
                 vec.push(RawChunk::from_data(ChunkType::FDAT, data_unit));
             }
         }
         if let Some(c) = created {
-            vec.push(RawChunk::from_data(
-                ChunkType::cTIM,
-                c.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::cTIM, timestamp_to_bytes(c)));
         }
         if let Some(d) = modified {
-            vec.push(RawChunk::from_data(
-                ChunkType::mTIM,
-                d.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::mTIM, timestamp_to_bytes(d)));
         }
         if let Some(a) = accessed {
-            vec.push(RawChunk::from_data(
-                ChunkType::aTIM,
-                a.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::aTIM, timestamp_to_bytes(a)));
         }
         if let Some(p) = permission {
             vec.push(RawChunk::from_data(ChunkType::fPRM, p.to_bytes()));
         }
         for xattr in self.xattrs {
-            vec.push(RawChunk::from_data(ChunkType::xATR, xattr.to_bytes()));
+            vec.push(xattr.to_chunk());
         }
         vec.push(RawChunk::from_data(ChunkType::FEND, Vec::new()));
         vec
@@ -836,6 +1275,11 @@ impl SealedEntryExt for NormalEntry<&[u8]> {
 }
 
 impl SealedEntryExt for NormalEntry<Cow<'_, [u8]>> {
+    #[inline]
+    fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+        self.zstd_dictionary.as_ref()
+    }
+
     fn into_chunks(self) -> Vec<RawChunk> {
         let Metadata {
             raw_file_size,
@@ -858,34 +1302,28 @@ impl SealedEntryExt for NormalEntry<Cow<'_, [u8]>> {
         if let Some(p) = self.phsf {
             vec.push(RawChunk::from_data(ChunkType::PHSF, p.into_bytes()));
         }
+        for wrapped_key in self.wrapped_keys {
+            vec.push(RawChunk::from_data(ChunkType::WKEY, wrapped_key.to_bytes()));
+        }
         for data_chunk in self.data {
             for data_unit in data_chunk.chunks(u32::MAX as usize) {
                 vec.push(RawChunk::from_data(ChunkType::FDAT, data_unit));
             }
         }
         if let Some(c) = created {
-            vec.push(RawChunk::from_data(
-                ChunkType::cTIM,
-                c.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::cTIM, timestamp_to_bytes(c)));
         }
         if let Some(d) = modified {
-            vec.push(RawChunk::from_data(
-                ChunkType::mTIM,
-                d.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::mTIM, timestamp_to_bytes(d)));
         }
         if let Some(a) = accessed {
-            vec.push(RawChunk::from_data(
-                ChunkType::aTIM,
-                a.as_secs().to_be_bytes(),
-            ));
+            vec.push(RawChunk::from_data(ChunkType::aTIM, timestamp_to_bytes(a)));
         }
         if let Some(p) = permission {
             vec.push(RawChunk::from_data(ChunkType::fPRM, p.to_bytes()));
         }
         for xattr in self.xattrs {
-            vec.push(RawChunk::from_data(ChunkType::xATR, xattr.to_bytes()));
+            vec.push(xattr.to_chunk());
         }
         vec.push(RawChunk::from_data(ChunkType::FEND, Vec::new()));
         vec
@@ -899,6 +1337,75 @@ impl SealedEntryExt for NormalEntry<Cow<'_, [u8]>> {
 
 impl<T> Entry for NormalEntry<T> where NormalEntry<T>: SealedEntryExt {}
 
+impl NormalEntry {
+    /// Creates an entry from the data chunks that are already compressed,
+    /// without compressing them again.
+    ///
+    /// Each item of `data` is stored as one FDAT chunk, in the given order.
+    /// The chunks are concatenated when the entry is read,
+    /// so the compressed stream may be divided at any byte.
+    /// The size of the compressed data is computed from `data`,
+    /// and the other fields of `metadata` are stored as given,
+    /// so [`Metadata::with_raw_file_size`] should be set to the size of the uncompressed data.
+    ///
+    /// The caller is responsible for `data` being compressed with [`EntryHeader::compression`].
+    /// Data that does not match the header is not detected until the entry is read.
+    ///
+    /// # Examples
+    /// ```
+    /// use libpna::{Compression, EntryHeader, Metadata, NormalEntry, ReadOptions};
+    /// use std::io::{self, Read};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let header = EntryHeader::for_compressed_file(Compression::No, "example.txt".into());
+    /// let metadata = Metadata::new().with_raw_file_size(Some(7));
+    /// let entry = NormalEntry::from_raw_parts(header, metadata, [b"content".to_vec()])?;
+    /// let mut content = String::new();
+    /// entry
+    ///     .reader(ReadOptions::builder().build())?
+    ///     .read_to_string(&mut content)?;
+    /// assert_eq!(content, "content");
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header declares encryption, which needs the cipher parameters
+    /// that can not be given here, or if an item of `data` does not fit in a chunk.
+    #[inline]
+    pub fn from_raw_parts(
+        header: EntryHeader,
+        mut metadata: Metadata,
+        data: impl IntoIterator<Item = Vec<u8>>,
+    ) -> io::Result<Self> {
+        if header.encryption != Encryption::No {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Entries from raw parts can not be encrypted",
+            ));
+        }
+        let data = data.into_iter().collect::<Vec<_>>();
+        if data.iter().any(|it| it.len() > u32::MAX as usize) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("A data chunk must not exceed {} bytes", u32::MAX),
+            ));
+        }
+        metadata.compressed_size = data.iter().map(Vec::len).sum();
+        Ok(Self {
+            header,
+            phsf: None,
+            wrapped_keys: Vec::new(),
+            extra: Vec::new(),
+            data,
+            metadata,
+            xattrs: Vec::new(),
+            zstd_dictionary: None,
+        })
+    }
+}
+
 impl<T> NormalEntry<T> {
     /// Information in the header of the entry.
     #[inline]
@@ -912,18 +1419,36 @@ impl<T> NormalEntry<T> {
         &self.metadata
     }
 
-    /// Extended attributes of the entry.
+    /// Extended attributes of the entry, in the order they are stored in the archive.
     #[inline]
     pub fn xattrs(&self) -> &[ExtendedAttribute] {
         &self.xattrs
     }
 
-    /// Extra chunks.
+    /// Password hash of the entry in the PHC string format,
+    /// or `None` if the entry is not encrypted with a password.
+    ///
+    /// The hash records the algorithm and the parameters used to derive the key from the password.
+    #[inline]
+    pub fn password_hash(&self) -> Option<&str> {
+        self.phsf.as_deref()
+    }
+
+    /// Extra chunks, in the order they are stored in the archive.
     #[inline]
     pub fn extra_chunks(&self) -> &[RawChunk<T>] {
         &self.extra
     }
 
+    /// Data chunks of the entry as stored in the archive, that is, compressed and encrypted.
+    ///
+    /// The first chunk of an encrypted entry is the initialization vector of the cipher.
+    /// The total size of the chunks is [`NormalEntry::stored_size`].
+    #[inline]
+    pub fn data_chunks(&self) -> &[T] {
+        &self.data
+    }
+
     /// Apply metadata to the entry.
     ///
     /// # Example
@@ -990,8 +1515,19 @@ impl<T: Clone> NormalEntry<T> {
 }
 
 impl<T: AsRef<[u8]>> NormalEntry<T> {
+    /// Returns the total size in bytes of the data chunks of this [`NormalEntry`],
+    /// that is, the size of the compressed and encrypted data.
+    ///
+    /// The data chunks are resident in memory as long as this entry is alive.
+    #[inline]
+    pub fn stored_size(&self) -> usize {
+        self.data.iter().map(|it| it.as_ref().len()).sum()
+    }
+
     /// Return the reader of this [`NormalEntry`].
     ///
+    /// The reader reads the data chunks of this entry in place without copying them.
+    ///
     /// # Examples
     /// ```no_run
     /// use libpna::{Archive, ReadOptions};
@@ -1012,17 +1548,70 @@ impl<T: AsRef<[u8]>> NormalEntry<T> {
     /// ```
     #[inline]
     pub fn reader(&self, option: impl ReadOption) -> io::Result<EntryDataReader> {
-        let raw_data_reader =
-            crate::io::FlattenReader::new(self.data.iter().map(|it| it.as_ref()).collect());
+        self.data_reader(
+            crate::io::FlattenReader::new(self.data.iter().map(|it| it.as_ref()).collect()),
+            option,
+        )
+    }
+
+    /// Returns the reader of the data read by `raw_data_reader`, decrypted and decompressed as this [`NormalEntry`] is.
+    #[inline]
+    fn data_reader<'r>(
+        &self,
+        raw_data_reader: crate::io::FlattenReader<'r>,
+        option: impl ReadOption,
+    ) -> io::Result<EntryDataReader<'r>> {
         let decrypt_reader = decrypt_reader(
             raw_data_reader,
             self.header.encryption,
             self.header.cipher_mode,
             self.phsf.as_deref(),
-            option.password().map(|it| it.as_bytes()),
+            &self.wrapped_keys,
+            &option,
         )?;
-        let reader = decompress_reader(decrypt_reader, self.header.compression)?;
-        Ok(EntryDataReader(EntryReader(reader)))
+        let reader = decompress_reader(
+            decrypt_reader,
+            self.header.compression,
+            option.zstd_dictionary().or(self.zstd_dictionary.as_deref()),
+        )?;
+        Ok(EntryDataReader {
+            inner: EntryReader(reader),
+            processed: 0,
+        })
+    }
+
+    /// Decrypts and decompresses the data of this [`NormalEntry`] without keeping it,
+    /// and returns the number of the decoded bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// use libpna::{EntryBuilder, ReadOptions, WriteOptions};
+    /// use std::io::{self, Write};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut builder = EntryBuilder::new_file("example.txt".into(), WriteOptions::builder().build())?;
+    /// builder.write_all(b"content")?;
+    /// let entry = builder.build()?;
+    /// assert_eq!(entry.verify(ReadOptions::builder().build())?, 7);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data can not be decrypted or decompressed,
+    /// or if the number of the decoded bytes differs from the file size recorded in the entry.
+    #[inline]
+    pub fn verify(&self, option: impl ReadOption) -> io::Result<u64> {
+        let mut reader = self.reader(option)?;
+        let size = io::copy(&mut reader, &mut io::sink())?;
+        match self.metadata.raw_file_size {
+            Some(expected) if expected != u128::from(size) => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Decoded {size} bytes, but the entry records {expected} bytes"),
+            )),
+            _ => Ok(size),
+        }
     }
 }
 
@@ -1032,10 +1621,12 @@ impl<'a> From<NormalEntry<Cow<'a, [u8]>>> for NormalEntry<Vec<u8>> {
         Self {
             header: value.header,
             phsf: value.phsf,
+            wrapped_keys: value.wrapped_keys,
             extra: value.extra.into_iter().map(Into::into).collect(),
             data: value.data.into_iter().map(Into::into).collect(),
             metadata: value.metadata,
             xattrs: value.xattrs,
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -1046,10 +1637,12 @@ impl<'a> From<NormalEntry<&'a [u8]>> for NormalEntry<Vec<u8>> {
         Self {
             header: value.header,
             phsf: value.phsf,
+            wrapped_keys: value.wrapped_keys,
             extra: value.extra.into_iter().map(Into::into).collect(),
             data: value.data.into_iter().map(Into::into).collect(),
             metadata: value.metadata,
             xattrs: value.xattrs,
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -1060,10 +1653,12 @@ impl From<NormalEntry<Vec<u8>>> for NormalEntry<Cow<'_, [u8]>> {
         Self {
             header: value.header,
             phsf: value.phsf,
+            wrapped_keys: value.wrapped_keys,
             extra: value.extra.into_iter().map(Into::into).collect(),
             data: value.data.into_iter().map(Into::into).collect(),
             metadata: value.metadata,
             xattrs: value.xattrs,
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -1074,10 +1669,12 @@ impl<'a> From<NormalEntry<&'a [u8]>> for NormalEntry<Cow<'a, [u8]>> {
         Self {
             header: value.header,
             phsf: value.phsf,
+            wrapped_keys: value.wrapped_keys,
             extra: value.extra.into_iter().map(Into::into).collect(),
             data: value.data.into_iter().map(Into::into).collect(),
             metadata: value.metadata,
             xattrs: value.xattrs,
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -1096,6 +1693,19 @@ where
         self.0.iter().map(|chunk| chunk.bytes_len()).sum()
     }
 
+    /// Length in bytes of the largest chunk that cannot be divided by `split`.
+    ///
+    /// Only data chunks can be divided, so a part smaller than this may not be able to hold the next chunk.
+    #[inline]
+    pub fn max_unsplittable_len(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|chunk| !chunk.is_stream_chunk())
+            .map(|chunk| chunk.bytes_len())
+            .max()
+            .unwrap_or_default()
+    }
+
     /// Get reference.
     #[doc(hidden)]
     #[inline]
@@ -1188,13 +1798,41 @@ impl SealedEntryExt for ChunkSolidEntries {
     }
 }
 
+/// Decodes the data of a cTIM, mTIM or aTIM chunk, the seconds since the unix epoch
+/// optionally followed by the nanoseconds.
 #[inline]
 fn timestamp(bytes: &[u8]) -> io::Result<Duration> {
-    Ok(Duration::from_secs(u64::from_be_bytes(
-        bytes
-            .try_into()
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
-    )))
+    let invalid = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid timestamp of {} bytes", bytes.len()),
+        )
+    };
+    let (secs, nanos) = bytes
+        .split_first_chunk::<{ mem::size_of::<u64>() }>()
+        .ok_or_else(invalid)?;
+    let secs = u64::from_be_bytes(*secs);
+    let nanos = match nanos {
+        [] => 0,
+        nanos => u32::from_be_bytes(nanos.try_into().map_err(|_| invalid())?),
+    };
+    if nanos >= 1_000_000_000 {
+        return Err(invalid());
+    }
+    Ok(Duration::new(secs, nanos))
+}
+
+/// Encodes `timestamp` as the data of a cTIM, mTIM or aTIM chunk.
+///
+/// The nanoseconds are appended only when they are not zero,
+/// so that the timestamps of whole seconds stay readable by the versions without nanoseconds.
+#[inline]
+pub(crate) fn timestamp_to_bytes(timestamp: Duration) -> Vec<u8> {
+    let mut bytes = timestamp.as_secs().to_be_bytes().to_vec();
+    if timestamp.subsec_nanos() != 0 {
+        bytes.extend_from_slice(&timestamp.subsec_nanos().to_be_bytes());
+    }
+    bytes
 }
 
 #[inline]
@@ -1223,6 +1861,296 @@ mod tests {
         assert_eq!(u128::MAX, u128_from_be_bytes_last(&u128::MAX.to_be_bytes()));
     }
 
+    #[test]
+    fn timestamp_bytes() {
+        let whole = Duration::from_secs(1_700_000_000);
+        assert_eq!(timestamp_to_bytes(whole), 1_700_000_000u64.to_be_bytes());
+        assert_eq!(timestamp(&timestamp_to_bytes(whole)).unwrap(), whole);
+
+        let precise = Duration::new(1_700_000_000, 123_456_789);
+        assert_eq!(timestamp_to_bytes(precise).len(), 12);
+        assert_eq!(timestamp(&timestamp_to_bytes(precise)).unwrap(), precise);
+
+        assert!(timestamp(&[0; 4]).is_err());
+        assert!(timestamp(&[0; 10]).is_err());
+        assert!(timestamp(&[[0; 8].as_slice(), &1_000_000_000u32.to_be_bytes()].concat()).is_err());
+    }
+
+    #[test]
+    fn nanosecond_timestamps() {
+        let mut builder = EntryBuilder::new_file("a.txt".into(), WriteOptions::store()).unwrap();
+        builder.created(Duration::new(1, 1));
+        builder.modified(Duration::new(2, 999_999_999));
+        builder.accessed(Duration::from_secs(3));
+        let mut archive = crate::Archive::write_header(Vec::new()).unwrap();
+        archive.add_entry(builder.build().unwrap()).unwrap();
+        let file = archive.finalize().unwrap();
+
+        let entry = read_normal_entry(&file);
+        assert_eq!(entry.metadata().created(), Some(Duration::new(1, 1)));
+        assert_eq!(
+            entry.metadata().modified(),
+            Some(Duration::new(2, 999_999_999))
+        );
+        assert_eq!(entry.metadata().accessed(), Some(Duration::from_secs(3)));
+    }
+
+    fn read_normal_entry(file: &[u8]) -> NormalEntry {
+        let mut archive = crate::Archive::read_header(file).unwrap();
+        let entry = archive.entries_skip_solid().next().unwrap().unwrap();
+        entry
+    }
+
+    #[test]
+    fn normal_entry_data_chunks() {
+        let src = b"chunked data".repeat(100);
+        let mut archive = crate::Archive::write_header(Vec::new()).unwrap();
+        let mut writer = archive
+            .entry_writer_with_chunk_size("a.txt".into(), WriteOptions::store(), 100)
+            .unwrap();
+        writer.write_all(&src).unwrap();
+        writer.finish().unwrap();
+        let file = archive.finalize().unwrap();
+
+        let entry = read_normal_entry(&file);
+        let sizes = entry.data_chunks().iter().map(Vec::len).collect::<Vec<_>>();
+        assert_eq!(sizes, [100; 12]);
+        assert_eq!(entry.data_chunks().concat(), src);
+        assert_eq!(sizes.iter().sum::<usize>(), entry.stored_size());
+    }
+
+    #[test]
+    fn encrypted_entry_data_chunks() {
+        let option = WriteOptions::builder()
+            .encryption(Encryption::Aes)
+            .cipher_mode(CipherMode::CTR)
+            .hash_algorithm(HashAlgorithm::pbkdf2_sha256_with(Some(1)))
+            .password(Some("password"))
+            .build();
+        let mut archive = crate::Archive::write_header(Vec::new()).unwrap();
+        let mut writer = archive
+            .entry_writer_with_chunk_size("a.txt".into(), option, 64)
+            .unwrap();
+        writer.write_all(&[0; 200]).unwrap();
+        writer.finish().unwrap();
+        let file = archive.finalize().unwrap();
+
+        let entry = read_normal_entry(&file);
+        let sizes = entry.data_chunks().iter().map(Vec::len).collect::<Vec<_>>();
+        // The initialization vector is followed by the encrypted data.
+        assert_eq!(sizes, [16, 64, 64, 64, 8]);
+        assert_eq!(sizes.iter().sum::<usize>(), entry.stored_size());
+    }
+
+    #[test]
+    fn verify_normal_entry() {
+        let option = WriteOptions::builder()
+            .compression(Compression::ZStandard)
+            .encryption(Encryption::Aes)
+            .hash_algorithm(HashAlgorithm::pbkdf2_sha256_with(Some(1)))
+            .password(Some("password"))
+            .build();
+        let mut builder = EntryBuilder::new_file("a.txt".into(), option).unwrap();
+        builder.file_size(true);
+        builder.write_all(&[1; 1000]).unwrap();
+        let entry = builder.build().unwrap();
+
+        assert_eq!(
+            entry
+                .verify(ReadOptions::with_password(Some("password")))
+                .unwrap(),
+            1000
+        );
+        assert!(entry
+            .verify(ReadOptions::with_password(Some("wrong")))
+            .is_err());
+
+        let mut broken = entry.clone();
+        let last = broken.data.last_mut().unwrap();
+        let len = last.len();
+        last[len - 1] ^= 0xff;
+        assert!(broken
+            .verify(ReadOptions::with_password(Some("password")))
+            .is_err());
+
+        let mut metadata = entry.metadata().clone();
+        metadata.raw_file_size = Some(999);
+        let entry = NormalEntry { metadata, ..entry };
+        assert_eq!(
+            entry
+                .verify(ReadOptions::with_password(Some("password")))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn from_raw_parts_with_externally_compressed_data() {
+        let data = (0..100_000u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect::<Vec<_>>();
+        let compressed = zstd::stream::encode_all(data.as_slice(), 3).unwrap();
+        let (first, second) = compressed.split_at(compressed.len() / 3);
+        let entry = NormalEntry::from_raw_parts(
+            EntryHeader::for_compressed_file(Compression::ZStandard, "blob.bin".into()),
+            Metadata::new()
+                .with_raw_file_size(Some(data.len() as u128))
+                .with_modified(Some(Duration::from_secs(42))),
+            [first.to_vec(), second.to_vec()],
+        )
+        .unwrap();
+        assert_eq!(entry.data_chunks(), [first, second]);
+        assert_eq!(entry.metadata().compressed_size(), compressed.len());
+
+        let mut decoded = Vec::new();
+        entry
+            .reader(ReadOptions::builder().build())
+            .unwrap()
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, data);
+        assert_eq!(
+            entry.verify(ReadOptions::builder().build()).unwrap(),
+            data.len() as u64
+        );
+
+        let mut archive = crate::Archive::write_header(Vec::new()).unwrap();
+        archive.add_entry(entry.clone()).unwrap();
+        let archive = archive.finalize().unwrap();
+        let mut archive = crate::Archive::read_header(archive.as_slice()).unwrap();
+        let read = archive.entries_skip_solid().next().unwrap().unwrap();
+        assert_eq!(read.header(), entry.header());
+        assert_eq!(read.metadata(), entry.metadata());
+        let mut decoded = Vec::new();
+        read.reader(ReadOptions::builder().build())
+            .unwrap()
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn from_raw_parts_rejects_encryption() {
+        let encrypted = EntryBuilder::new_file(
+            "encrypted.txt".into(),
+            WriteOptions::builder()
+                .encryption(Encryption::Aes)
+                .password(Some("password"))
+                .build(),
+        )
+        .unwrap()
+        .build()
+        .unwrap();
+        let err = NormalEntry::from_raw_parts(
+            encrypted.header().clone(),
+            Metadata::new(),
+            encrypted.data_chunks().to_vec(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn solid_entry_data_chunks() {
+        let mut builder = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        for name in ["a.txt", "b.txt"] {
+            let mut entry = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+            entry.write_all(name.as_bytes()).unwrap();
+            builder.add_entry(entry.build().unwrap()).unwrap();
+        }
+        let solid = builder.build_as_entry().unwrap();
+
+        assert!(!solid.data_chunks().is_empty());
+        assert_eq!(
+            solid.data_chunks().iter().map(Vec::len).sum::<usize>(),
+            solid.stored_size()
+        );
+        assert!(solid
+            .data_chunks()
+            .iter()
+            .map(Vec::as_slice)
+            .eq(solid.raw_data()));
+    }
+
+    #[test]
+    fn solid_entry_contents() {
+        let mut builder = SolidEntryBuilder::new(WriteOptions::builder().build()).unwrap();
+        for name in ["a.txt", "b.txt"] {
+            let mut entry = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+            entry.write_all(name.as_bytes()).unwrap();
+            builder.add_entry(entry.build().unwrap()).unwrap();
+        }
+        let solid = builder.build_as_entry().unwrap();
+
+        let mut contents = Vec::new();
+        solid
+            .contents(None)
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        let entries = SolidEntry::entries_from_contents(contents.as_slice())
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            entries,
+            solid
+                .entries(None)
+                .unwrap()
+                .collect::<io::Result<Vec<_>>>()
+                .unwrap()
+        );
+        assert_eq!(entries.len(), 2);
+    }
+
+    #[test]
+    fn packed_solid_entry() {
+        let option = WriteOptions::builder()
+            .compression(Compression::ZStandard)
+            .encryption(Encryption::Aes)
+            .password(Some("password"))
+            .build();
+        let mut packed = SolidEntryBuilder::new_packed(&option).unwrap();
+        let mut solid = SolidEntryBuilder::new(&option).unwrap();
+        for name in ["dir/a.txt", "dir/ab.txt", "b.txt"] {
+            let mut entry = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+            entry.write_all(name.as_bytes()).unwrap();
+            let entry = entry.build().unwrap();
+            packed.add_entry(entry.clone()).unwrap();
+            solid.add_entry(entry).unwrap();
+        }
+        let packed = packed.build_as_entry().unwrap();
+        let solid = solid.build_as_entry().unwrap();
+        assert!(packed.header().is_packed());
+        assert!(!solid.header().is_packed());
+
+        let entries = |solid: &SolidEntry| {
+            solid
+                .entries(Some("password"))
+                .unwrap()
+                .collect::<io::Result<Vec<_>>>()
+                .unwrap()
+        };
+        assert_eq!(entries(&packed), entries(&solid));
+        assert_eq!(entries(&packed).len(), 3);
+    }
+
+    #[test]
+    fn solid_entry_stored_size() {
+        let mut builder = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        let mut entry = EntryBuilder::new_file("a.txt".into(), WriteOptions::store()).unwrap();
+        entry.write_all(b"a").unwrap();
+        builder.add_entry(entry.build().unwrap()).unwrap();
+        let solid = builder.build_as_entry().unwrap();
+
+        let stored_size = solid.stored_size();
+        assert_eq!(
+            stored_size,
+            solid.raw_data().map(<[u8]>::len).sum::<usize>()
+        );
+        assert_eq!(ReadEntry::from(solid).estimated_peak_memory(), stored_size);
+    }
+
     mod entry_part_split {
         use super::*;
         use std::sync::LazyLock;
@@ -1343,5 +2271,19 @@ mod tests {
                 ]))
             )
         }
+
+        #[test]
+        fn max_unsplittable_len_skips_data_chunks() {
+            let part = EntryPart::from(TEST_ENTRY.clone());
+            assert_eq!(26, part.max_unsplittable_len());
+            assert_eq!(
+                12,
+                EntryPart(vec![
+                    RawChunk::from_data(ChunkType::FDAT, vec![0; 100]),
+                    RawChunk::from_data(ChunkType::FEND, vec![]),
+                ])
+                .max_unsplittable_len()
+            );
+        }
     }
 }
@@ -1,10 +1,15 @@
-use std::{io, mem};
+use crate::chunk::{ChunkType, RawChunk};
+use std::{
+    io::{self, Read},
+    mem,
+};
 
 /// Entry extended attribute.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct ExtendedAttribute {
     name: String,
     value: Vec<u8>,
+    compressed: bool,
 }
 
 impl ExtendedAttribute {
@@ -18,7 +23,45 @@ impl ExtendedAttribute {
     /// ```
     #[inline]
     pub const fn new(name: String, value: Vec<u8>) -> Self {
-        Self { name, value }
+        Self {
+            name,
+            value,
+            compressed: false,
+        }
+    }
+
+    /// Sets whether the value is stored compressed with zstd.
+    ///
+    /// A compressed attribute is written as a `xATZ` chunk instead of a `xATR` chunk,
+    /// unless compressing does not make the value smaller.
+    /// Readers that do not know the `xATZ` chunk keep it as an unknown chunk
+    /// and do not see the attribute.
+    ///
+    /// # Example
+    /// ```
+    /// use libpna::ExtendedAttribute;
+    ///
+    /// let xattr = ExtendedAttribute::new("name".into(), b"value".into()).with_compression(true);
+    /// assert!(xattr.is_compressed());
+    /// ```
+    #[inline]
+    pub const fn with_compression(mut self, compressed: bool) -> Self {
+        self.compressed = compressed;
+        self
+    }
+
+    /// Returns `true` if the value is stored compressed.
+    ///
+    /// # Example
+    /// ```
+    /// use libpna::ExtendedAttribute;
+    ///
+    /// let xattr = ExtendedAttribute::new("name".into(), b"value".into());
+    /// assert!(!xattr.is_compressed());
+    /// ```
+    #[inline]
+    pub const fn is_compressed(&self) -> bool {
+        self.compressed
     }
 
     /// Attribute name
@@ -64,7 +107,34 @@ impl ExtendedAttribute {
         let len = u32::from_be_bytes(*len) as usize;
         let value = value.get(..len).ok_or(io::ErrorKind::UnexpectedEof)?;
         let value = value.to_vec();
-        Ok(Self { name, value })
+        Ok(Self::new(name, value))
+    }
+
+    pub(crate) fn try_from_compressed_bytes(value: &[u8]) -> io::Result<Self> {
+        let (len, value) = value
+            .split_first_chunk::<{ mem::size_of::<u32>() }>()
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+        let len = u32::from_be_bytes(*len) as usize;
+        let (name, value) = value
+            .split_at_checked(len)
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+        let name = String::from_utf8(name.to_vec()).map_err(|_| io::ErrorKind::InvalidData)?;
+
+        let (len, value) = value
+            .split_first_chunk::<{ mem::size_of::<u32>() }>()
+            .ok_or(io::ErrorKind::UnexpectedEof)?;
+        let len = u32::from_be_bytes(*len) as usize;
+        let mut decompressed = Vec::with_capacity(len);
+        zstd::stream::read::Decoder::new(value)?
+            .take(len as u64 + 1)
+            .read_to_end(&mut decompressed)?;
+        if decompressed.len() != len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "decompressed extended attribute size mismatch",
+            ));
+        }
+        Ok(Self::new(name, decompressed).with_compression(true))
     }
 
     pub(crate) fn to_bytes(&self) -> Vec<u8> {
@@ -76,6 +146,26 @@ impl ExtendedAttribute {
         vec.extend_from_slice(&self.value);
         vec
     }
+
+    /// Converts to a `xATZ` chunk when the attribute is compressed and compressing
+    /// makes it smaller, otherwise to a `xATR` chunk.
+    pub(crate) fn to_chunk(&self) -> RawChunk {
+        if self.compressed {
+            if let Ok(compressed) = zstd::stream::encode_all(self.value.as_slice(), 0) {
+                if compressed.len() < self.value.len() {
+                    let mut vec = Vec::with_capacity(
+                        self.name.len() + compressed.len() + mem::size_of::<u32>() * 2,
+                    );
+                    vec.extend_from_slice(&(self.name.len() as u32).to_be_bytes());
+                    vec.extend_from_slice(self.name.as_bytes());
+                    vec.extend_from_slice(&(self.value.len() as u32).to_be_bytes());
+                    vec.extend_from_slice(&compressed);
+                    return RawChunk::from_data(ChunkType::xATZ, vec);
+                }
+            }
+        }
+        RawChunk::from_data(ChunkType::xATR, self.to_bytes())
+    }
 }
 
 #[cfg(test)]
@@ -92,4 +182,35 @@ mod tests {
             ExtendedAttribute::try_from_bytes(&xattr.to_bytes()).unwrap()
         );
     }
+
+    #[test]
+    fn compressed_xattr() {
+        let xattr = ExtendedAttribute::new("name".into(), vec![b'a'; 4096]).with_compression(true);
+        let chunk = xattr.to_chunk();
+        assert_eq!(chunk.ty, ChunkType::xATZ);
+        assert!(chunk.data.len() < 4096);
+        assert_eq!(
+            xattr,
+            ExtendedAttribute::try_from_compressed_bytes(&chunk.data).unwrap()
+        );
+    }
+
+    #[test]
+    fn compressed_xattr_incompressible() {
+        let xattr = ExtendedAttribute::new("name".into(), "value".into()).with_compression(true);
+        let chunk = xattr.to_chunk();
+        assert_eq!(chunk.ty, ChunkType::xATR);
+        assert_eq!(
+            ExtendedAttribute::try_from_bytes(&chunk.data).unwrap(),
+            xattr.with_compression(false)
+        );
+    }
+
+    #[test]
+    fn compressed_xattr_size_mismatch() {
+        let xattr = ExtendedAttribute::new("name".into(), vec![b'a'; 4096]).with_compression(true);
+        let mut data = xattr.to_chunk().data;
+        data[8..12].copy_from_slice(&4095u32.to_be_bytes());
+        assert!(ExtendedAttribute::try_from_compressed_bytes(&data).is_err());
+    }
 }
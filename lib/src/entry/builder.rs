@@ -1,11 +1,11 @@
 use crate::{
     chunk::RawChunk,
-    cipher::CipherWriter,
+    cipher::{CipherWriter, WrappedKey},
     compress::CompressionWriter,
     entry::{
-        get_writer, get_writer_context, private::SealedEntryExt, DataKind, Entry, EntryHeader,
-        EntryName, EntryReference, ExtendedAttribute, Metadata, NormalEntry, Permission,
-        SolidEntry, SolidHeader, WriteCipher, WriteOption, WriteOptions,
+        get_writer, get_writer_context, merge_zstd_dictionary, private::SealedEntryExt, DataKind,
+        Entry, EntryHeader, EntryName, EntryReference, ExtendedAttribute, Metadata, NormalEntry,
+        PackedWriter, Permission, SolidEntry, SolidHeader, WriteCipher, WriteOption, WriteOptions,
     },
     io::TryIntoInner,
 };
@@ -14,6 +14,7 @@ use crate::{
 use futures_io::AsyncWrite;
 use std::{
     io::{self, Write},
+    sync::Arc,
     time::Duration,
 };
 #[cfg(feature = "unstable-async")]
@@ -24,10 +25,25 @@ use std::{
 
 const MAX_CHUNK_DATA_LENGTH: usize = u32::MAX as usize;
 
+/// Entries whose data is smaller than this are serialized into a buffer at once
+/// before they are written into a solid entry.
+const SERIALIZE_BUFFER_THRESHOLD: usize = 64 * 1024;
+
+pub(crate) fn recipients_unsupported_in_solid() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Recipient encryption is not supported in solid mode",
+    )
+}
+
 /// A builder for creating a new [NormalEntry].
+///
+/// Extended attributes and extra chunks are written in the order they were added,
+/// so the same sequence of calls always produces the same entry bytes.
 pub struct EntryBuilder {
     header: EntryHeader,
     phsf: Option<String>,
+    wrapped_keys: Vec<WrappedKey>,
     iv: Option<Vec<u8>>,
     data: Option<CompressionWriter<CipherWriter<crate::io::FlattenWriter<MAX_CHUNK_DATA_LENGTH>>>>,
     created: Option<Duration>,
@@ -38,6 +54,7 @@ pub struct EntryBuilder {
     file_size: u128,
     xattrs: Vec<ExtendedAttribute>,
     extra_chunks: Vec<RawChunk>,
+    zstd_dictionary: Option<Arc<[u8]>>,
 }
 
 impl EntryBuilder {
@@ -45,6 +62,7 @@ impl EntryBuilder {
         Self {
             header,
             phsf: None,
+            wrapped_keys: Vec::new(),
             iv: None,
             data: None,
             created: None,
@@ -55,6 +73,7 @@ impl EntryBuilder {
             file_size: 0,
             xattrs: Vec::new(),
             extra_chunks: Vec::new(),
+            zstd_dictionary: None,
         }
     }
 
@@ -92,14 +111,16 @@ impl EntryBuilder {
         );
         let context = get_writer_context(option)?;
         let writer = get_writer(crate::io::FlattenWriter::new(), &context)?;
-        let (iv, phsf) = match context.cipher {
-            None => (None, None),
-            Some(WriteCipher { context: c, .. }) => (Some(c.iv), Some(c.phsf)),
+        let (iv, phsf, wrapped_keys) = match context.cipher {
+            None => (None, None, Vec::new()),
+            Some(WriteCipher { context: c, .. }) => (Some(c.iv), c.phsf, c.wrapped_keys),
         };
         Ok(Self {
             data: Some(writer),
             iv,
             phsf,
+            wrapped_keys,
+            zstd_dictionary: context.zstd_dictionary,
             ..Self::new(header)
         })
     }
@@ -132,14 +153,15 @@ impl EntryBuilder {
         let context = get_writer_context(option)?;
         let mut writer = get_writer(crate::io::FlattenWriter::new(), &context)?;
         writer.write_all(source.as_bytes())?;
-        let (iv, phsf) = match context.cipher {
-            None => (None, None),
-            Some(WriteCipher { context: c, .. }) => (Some(c.iv), Some(c.phsf)),
+        let (iv, phsf, wrapped_keys) = match context.cipher {
+            None => (None, None, Vec::new()),
+            Some(WriteCipher { context: c, .. }) => (Some(c.iv), c.phsf, c.wrapped_keys),
         };
         Ok(Self {
             data: Some(writer),
             iv,
             phsf,
+            wrapped_keys,
             ..Self::new(EntryHeader::for_symbolic_link(name))
         })
     }
@@ -172,14 +194,15 @@ impl EntryBuilder {
         let context = get_writer_context(option)?;
         let mut writer = get_writer(crate::io::FlattenWriter::new(), &context)?;
         writer.write_all(source.as_bytes())?;
-        let (iv, phsf) = match context.cipher {
-            None => (None, None),
-            Some(WriteCipher { context: c, .. }) => (Some(c.iv), Some(c.phsf)),
+        let (iv, phsf, wrapped_keys) = match context.cipher {
+            None => (None, None, Vec::new()),
+            Some(WriteCipher { context: c, .. }) => (Some(c.iv), c.phsf, c.wrapped_keys),
         };
         Ok(Self {
             data: Some(writer),
             iv,
             phsf,
+            wrapped_keys,
             ..Self::new(EntryHeader::for_hard_link(name))
         })
     }
@@ -261,6 +284,7 @@ impl EntryBuilder {
     }
 
     /// Adds [ExtendedAttribute] to the entry.
+    /// Extended attributes are written in the order they are added.
     ///
     /// # Arguments
     ///
@@ -276,6 +300,7 @@ impl EntryBuilder {
     }
 
     /// Adds extra chunk to the entry.
+    /// Extra chunks are written in the order they are added.
     ///
     /// # Arguments
     ///
@@ -290,6 +315,31 @@ impl EntryBuilder {
         self
     }
 
+    /// Returns the number of bytes of the file data written to this builder so far,
+    /// before compression and encryption.
+    ///
+    /// Callers writing a large file can poll this to report the progress.
+    /// Always 0 for the entries other than files, which have no data to write.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{EntryBuilder, WriteOptions};
+    /// use std::io::{self, Write};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut builder = EntryBuilder::new_file("example.txt".into(), WriteOptions::builder().build())?;
+    /// assert_eq!(builder.bytes_processed(), 0);
+    /// builder.write_all(b"content")?;
+    /// assert_eq!(builder.bytes_processed(), 7);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub const fn bytes_processed(&self) -> u128 {
+        self.file_size
+    }
+
     /// Builds the entry and returns a Result containing the new [NormalEntry].
     ///
     /// # Returns
@@ -319,10 +369,12 @@ impl EntryBuilder {
         Ok(NormalEntry {
             header: self.header,
             phsf: self.phsf,
+            wrapped_keys: self.wrapped_keys,
             extra: self.extra_chunks,
             data,
             metadata,
             xattrs: self.xattrs,
+            zstd_dictionary: self.zstd_dictionary,
         })
     }
 }
@@ -372,8 +424,12 @@ pub struct SolidEntryBuilder {
     header: SolidHeader,
     phsf: Option<String>,
     iv: Option<Vec<u8>>,
-    data: CompressionWriter<CipherWriter<crate::io::FlattenWriter<MAX_CHUNK_DATA_LENGTH>>>,
+    data: PackedWriter<
+        CompressionWriter<CipherWriter<crate::io::FlattenWriter<MAX_CHUNK_DATA_LENGTH>>>,
+    >,
     extra: Vec<RawChunk>,
+    entries: usize,
+    zstd_dictionary: Option<Arc<[u8]>>,
 }
 
 impl SolidEntryBuilder {
@@ -388,16 +444,53 @@ impl SolidEntryBuilder {
     /// A new [SolidEntryBuilder].
     #[inline]
     pub fn new(option: impl WriteOption) -> io::Result<Self> {
-        let header = SolidHeader::new(
+        Self::new_with_packed(option, false)
+    }
+
+    /// Creates a new [SolidEntryBuilder] that stores the entries in the packed encoding.
+    ///
+    /// The packed encoding compacts the chunk overhead of each entry,
+    /// which is dominant in a solid entry of many tiny files.
+    /// Readers of libpna 0.21.0 or earlier fail to read the entries in a packed solid entry.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use libpna::{EntryBuilder, SolidEntryBuilder, WriteOptions};
+    /// use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut builder = SolidEntryBuilder::new_packed(WriteOptions::builder().build())?;
+    /// builder.add_entry(EntryBuilder::new_dir("example".into()).build()?)?;
+    /// builder.build()?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn new_packed(option: impl WriteOption) -> io::Result<Self> {
+        Self::new_with_packed(option, true)
+    }
+
+    fn new_with_packed(option: impl WriteOption, packed: bool) -> io::Result<Self> {
+        let mut header = SolidHeader::new(
             option.compression(),
             option.encryption(),
             option.cipher_mode(),
         );
+        if packed {
+            header = header.packed();
+        }
         let context = get_writer_context(option)?;
-        let writer = get_writer(crate::io::FlattenWriter::new(), &context)?;
+        let writer = PackedWriter::new(
+            get_writer(crate::io::FlattenWriter::new(), &context)?,
+            packed,
+        )?;
         let (iv, phsf) = match context.cipher {
             None => (None, None),
-            Some(WriteCipher { context: c, .. }) => (Some(c.iv), Some(c.phsf)),
+            Some(WriteCipher { context: c, .. }) if c.wrapped_keys.is_empty() => {
+                (Some(c.iv), c.phsf)
+            }
+            Some(_) => return Err(recipients_unsupported_in_solid()),
         };
         Ok(Self {
             header,
@@ -405,6 +498,8 @@ impl SolidEntryBuilder {
             phsf,
             data: writer,
             extra: Vec::new(),
+            entries: 0,
+            zstd_dictionary: context.zstd_dictionary,
         })
     }
 
@@ -439,27 +534,155 @@ impl SolidEntryBuilder {
     where
         NormalEntry<T>: Entry,
     {
-        entry.write_in(&mut self.data)
+        merge_zstd_dictionary(&mut self.zstd_dictionary, entry.zstd_dictionary())?;
+        let len = entry.write_in(&mut self.data)?;
+        self.entries += 1;
+        Ok(len)
+    }
+
+    /// Creates a solid entry containing the given entries in one call.
+    ///
+    /// Unlike calling [`SolidEntryBuilder::add_entry`] for each entry, each entry is serialized at once
+    /// before it is compressed, which is faster for a large number of small entries.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if failed to initialize the compression or encryption,
+    /// or an I/O error occurs while writing the entries.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{Archive, EntryBuilder, SolidEntryBuilder, WriteOptions};
+    /// use std::io::{self, Write};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let entries = (0..3)
+    ///     .map(|i| {
+    ///         let mut builder =
+    ///             EntryBuilder::new_file(format!("{i}.txt").into(), WriteOptions::store())?;
+    ///         builder.write_all(b"content")?;
+    ///         builder.build()
+    ///     })
+    ///     .collect::<io::Result<Vec<_>>>()?;
+    /// let solid = SolidEntryBuilder::from_entries(entries, WriteOptions::builder().build())?;
+    /// let mut archive = Archive::write_header(Vec::new())?;
+    /// archive.add_entry(solid)?;
+    /// archive.finalize()?;
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn from_entries<T>(
+        entries: impl IntoIterator<Item = NormalEntry<T>>,
+        option: impl WriteOption,
+    ) -> io::Result<SolidEntry>
+    where
+        NormalEntry<T>: Entry,
+    {
+        let mut builder = Self::new(option)?;
+        let mut buf = Vec::new();
+        for entry in entries {
+            builder.add_entry_buffered(&entry, &mut buf)?;
+        }
+        builder.build_as_entry()
+    }
+
+    /// Same as [`SolidEntryBuilder::add_entry`], but serializes small entries into `buf` first,
+    /// so that the compressor is called once per entry instead of several times per chunk.
+    fn add_entry_buffered<T>(
+        &mut self,
+        entry: &NormalEntry<T>,
+        buf: &mut Vec<u8>,
+    ) -> io::Result<usize>
+    where
+        NormalEntry<T>: Entry,
+    {
+        merge_zstd_dictionary(&mut self.zstd_dictionary, entry.zstd_dictionary())?;
+        let len = if SERIALIZE_BUFFER_THRESHOLD < entry.metadata().compressed_size() {
+            entry.write_in(&mut self.data)?
+        } else {
+            buf.clear();
+            let len = entry.write_in(buf)?;
+            self.data.write_all(buf)?;
+            len
+        };
+        self.entries += 1;
+        Ok(len)
     }
 
     /// Adds extra chunk to the solid entry.
+    ///
+    /// Extra chunks are written after the solid header and are returned by [`SolidEntry::extra_chunks`].
     #[inline]
     pub fn add_extra_chunk<T: Into<RawChunk>>(&mut self, chunk: T) {
         self.extra.push(chunk.into());
     }
 
-    fn build_as_entry(self) -> io::Result<SolidEntry> {
+    /// Returns the header of the solid entry being built,
+    /// which holds the compression, encryption and cipher mode given at construction.
+    #[inline]
+    pub const fn header(&self) -> &SolidHeader {
+        &self.header
+    }
+
+    /// Returns the number of entries added to this builder so far.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{EntryBuilder, SolidEntryBuilder, WriteOptions};
+    /// use std::io;
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let mut builder = SolidEntryBuilder::new(WriteOptions::builder().build())?;
+    /// assert_eq!(builder.entries_len(), 0);
+    /// builder.add_entry(EntryBuilder::new_dir("example".into()).build()?)?;
+    /// assert_eq!(builder.entries_len(), 1);
+    /// #     Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub const fn entries_len(&self) -> usize {
+        self.entries
+    }
+
+    /// Returns the number of bytes of the solid entry data written out so far,
+    /// after compression and encryption.
+    ///
+    /// This is an estimate to decide when to start a new solid entry:
+    /// data still buffered by the compressor or the cipher is not counted,
+    /// so the final size after [`SolidEntryBuilder::build`] is larger by up to that amount.
+    #[inline]
+    pub fn current_compressed_len(&self) -> usize {
+        self.data
+            .get_ref()
+            .get_ref()
+            .get_ref()
+            .inner
+            .iter()
+            .map(Vec::len)
+            .sum()
+    }
+
+    pub(crate) fn build_as_entry(self) -> io::Result<SolidEntry> {
         Ok(SolidEntry {
             header: self.header,
             phsf: self.phsf,
             data: {
-                let mut data = self.data.try_into_inner()?.try_into_inner()?.inner;
+                let mut data = self
+                    .data
+                    .into_inner()
+                    .try_into_inner()?
+                    .try_into_inner()?
+                    .inner;
                 if let Some(iv) = self.iv {
                     data.insert(0, iv);
                 }
                 data
             },
             extra: self.extra,
+            zstd_dictionary: self.zstd_dictionary,
         })
     }
 
@@ -505,6 +728,93 @@ mod tests {
         );
     }
 
+    fn file_entry(name: &str, content: &[u8]) -> NormalEntry {
+        let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+        builder.write_all(content).unwrap();
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn solid_entry_from_entries() {
+        let entries = (0..100)
+            .map(|i| {
+                file_entry(
+                    &format!("dir/{i}.txt"),
+                    format!("text{i}").repeat(i).as_bytes(),
+                )
+            })
+            .chain([file_entry(
+                "large.bin",
+                &[7; SERIALIZE_BUFFER_THRESHOLD + 1],
+            )])
+            .collect::<Vec<_>>();
+
+        let mut builder = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        for entry in entries.iter().cloned() {
+            builder.add_entry(entry).unwrap();
+        }
+        let incremental = builder.build_as_entry().unwrap();
+        let from_entries =
+            SolidEntryBuilder::from_entries(entries.iter().cloned(), WriteOptions::store())
+                .unwrap();
+        assert_eq!(from_entries.header, incremental.header);
+        assert!(from_entries.data.concat() == incremental.data.concat());
+
+        let read = from_entries
+            .entries(None)
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, entries);
+    }
+
+    #[test]
+    fn solid_entry_from_entries_compressed() {
+        let option = WriteOptions::builder()
+            .compression(crate::Compression::ZStandard)
+            .encryption(crate::Encryption::Aes)
+            .cipher_mode(crate::CipherMode::CTR)
+            .hash_algorithm(crate::HashAlgorithm::pbkdf2_sha256_with(Some(1)))
+            .password(Some("password"))
+            .build();
+        let entries = (0..100)
+            .map(|i| file_entry(&format!("{i}.txt"), format!("text{i}").repeat(i).as_bytes()))
+            .collect::<Vec<_>>();
+        let solid = SolidEntryBuilder::from_entries(entries.iter().cloned(), option).unwrap();
+        let read = solid
+            .entries(Some("password"))
+            .unwrap()
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(read, entries);
+    }
+
+    #[test]
+    fn solid_entry_reader_for() {
+        let option = WriteOptions::builder()
+            .compression(crate::Compression::ZStandard)
+            .encryption(crate::Encryption::Aes)
+            .cipher_mode(crate::CipherMode::CTR)
+            .hash_algorithm(crate::HashAlgorithm::pbkdf2_sha256_with(Some(1)))
+            .password(Some("password"))
+            .build();
+        let entries = (0..10)
+            .map(|i| file_entry(&format!("{i}.txt"), format!("text{i}").repeat(i).as_bytes()))
+            .collect::<Vec<_>>();
+        let solid = SolidEntryBuilder::from_entries(entries.iter().cloned(), option).unwrap();
+        for name in ["0.txt", "5.txt", "9.txt"] {
+            let mut read = Vec::new();
+            let mut reader = solid.reader_for(name, Some("password")).unwrap().unwrap();
+            io::Read::read_to_end(&mut reader, &mut read).unwrap();
+            let i = name.trim_end_matches(".txt").parse::<usize>().unwrap();
+            assert_eq!(read, format!("text{i}").repeat(i).as_bytes());
+        }
+        assert!(solid
+            .reader_for("10.txt", Some("password"))
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn solid_entry_extra_chunk() {
         let mut builder = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
@@ -519,4 +829,51 @@ mod tests {
             &RawChunk::from_data(unsafe { ChunkType::from_unchecked(*b"abCd") }, []),
         );
     }
+
+    #[test]
+    fn solid_entry_extra_chunk_round_trip() {
+        let chunk = RawChunk::from_data(unsafe { ChunkType::from_unchecked(*b"abCd") }, *b"data");
+        let mut builder = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        builder.add_entry(file_entry("a.txt", b"a")).unwrap();
+        builder.add_extra_chunk(chunk.clone());
+        let mut archive = crate::Archive::write_header(Vec::new()).unwrap();
+        archive.add_entry(builder.build().unwrap()).unwrap();
+        let buf = archive.finalize().unwrap();
+
+        let mut archive = crate::Archive::read_header(buf.as_slice()).unwrap();
+        let entries = archive.entries().collect::<io::Result<Vec<_>>>().unwrap();
+        let [crate::ReadEntry::Solid(solid)] = entries.as_slice() else {
+            panic!("expected a solid entry");
+        };
+        assert_eq!(solid.extra_chunks(), [chunk]);
+    }
+
+    #[test]
+    fn solid_entry_entries_len() {
+        let entries = (0..3)
+            .map(|i| file_entry(&format!("{i}.txt"), b"text"))
+            .collect::<Vec<_>>();
+        let mut builder = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        assert_eq!(builder.entries_len(), 0);
+        for entry in entries.iter().cloned() {
+            builder.add_entry(entry).unwrap();
+        }
+        assert_eq!(builder.entries_len(), 3);
+        let mut buf = Vec::new();
+        for entry in &entries {
+            builder.add_entry_buffered(entry, &mut buf).unwrap();
+        }
+        assert_eq!(builder.entries_len(), 6);
+    }
+
+    #[test]
+    fn solid_entry_current_compressed_len() {
+        let mut builder = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        assert_eq!(builder.current_compressed_len(), 0);
+        builder.add_entry(file_entry("a.bin", &[0; 1024])).unwrap();
+        let len = builder.current_compressed_len();
+        assert!(1024 < len);
+        let entry = builder.build_as_entry().unwrap();
+        assert_eq!(len, entry.data.iter().map(Vec::len).sum::<usize>());
+    }
 }
@@ -0,0 +1,369 @@
+//! Comparison of entries in bounded memory.
+use super::{ExtendedAttribute, NormalEntry, ReadOption};
+use crate::chunk::{Chunk, RawChunk};
+use std::{
+    io::{self, Read},
+    time::Duration,
+};
+
+/// Size of the buffers used to compare the data of entries.
+const COMPARE_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Aspects of entries compared by [compare_entries].
+///
+/// The name, data kind and content are compared by default, as well as all the metadata.
+/// The way the entries are stored, such as the compression and encryption, is never compared.
+///
+/// # Examples
+/// ```
+/// use libpna::EntryEq;
+/// use std::time::Duration;
+///
+/// // Compare only the contents and the modified times within a second.
+/// let eq = EntryEq::new()
+///     .with_name(false)
+///     .with_permission(false)
+///     .with_xattrs(false)
+///     .with_extra_chunks(false)
+///     .with_timestamps(Some(Duration::from_secs(1)));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct EntryEq {
+    name: bool,
+    content: bool,
+    timestamps: Option<Duration>,
+    permission: bool,
+    xattrs: bool,
+    extra_chunks: bool,
+}
+
+impl EntryEq {
+    /// Creates a new [EntryEq] comparing all the aspects, with exactly equal timestamps.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            name: true,
+            content: true,
+            timestamps: Some(Duration::ZERO),
+            permission: true,
+            xattrs: true,
+            extra_chunks: true,
+        }
+    }
+
+    /// Sets whether the names of the entries are compared.
+    #[inline]
+    pub const fn with_name(mut self, compare: bool) -> Self {
+        self.name = compare;
+        self
+    }
+
+    /// Sets whether the data kinds and the contents of the entries are compared.
+    ///
+    /// The contents are read from the entries in lockstep,
+    /// so the memory used does not depend on the size of the entries.
+    #[inline]
+    pub const fn with_content(mut self, compare: bool) -> Self {
+        self.content = compare;
+        self
+    }
+
+    /// Sets the tolerance of the created, modified and accessed times of the entries,
+    /// or `None` not to compare them.
+    /// A timestamp missing in only one of the entries is always a difference.
+    #[inline]
+    pub const fn with_timestamps(mut self, tolerance: Option<Duration>) -> Self {
+        self.timestamps = tolerance;
+        self
+    }
+
+    /// Sets whether the owners and the permissions of the entries are compared.
+    #[inline]
+    pub const fn with_permission(mut self, compare: bool) -> Self {
+        self.permission = compare;
+        self
+    }
+
+    /// Sets whether the extended attributes of the entries are compared.
+    ///
+    /// The extended attributes are compared regardless of their order and whether they are compressed.
+    #[inline]
+    pub const fn with_xattrs(mut self, compare: bool) -> Self {
+        self.xattrs = compare;
+        self
+    }
+
+    /// Sets whether the extra chunks of the entries are compared, in their order.
+    #[inline]
+    pub const fn with_extra_chunks(mut self, compare: bool) -> Self {
+        self.extra_chunks = compare;
+        self
+    }
+}
+
+impl Default for EntryEq {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns `true` if the entries `a` and `b` are equal in the aspects selected by `eq`.
+///
+/// The metadata are compared before the contents, which are read with `a_option` and `b_option`
+/// only if everything else is equal.
+///
+/// # Errors
+/// Returns an error if the content of either entry cannot be read,
+/// for example if the password is wrong.
+///
+/// # Examples
+/// ```
+/// use libpna::{compare_entries, Encryption, EntryBuilder, EntryEq, ReadOptions, WriteOptions};
+/// use std::io::{self, Write};
+///
+/// # fn main() -> io::Result<()> {
+/// let option = WriteOptions::builder()
+///     .encryption(Encryption::Aes)
+///     .password(Some("password"))
+///     .build();
+/// let mut a = EntryBuilder::new_file("file.txt".into(), WriteOptions::store())?;
+/// a.write_all(b"text")?;
+/// let mut b = EntryBuilder::new_file("file.txt".into(), option)?;
+/// b.write_all(b"text")?;
+/// let (a, b) = (a.build()?, b.build()?);
+///
+/// let a_option = ReadOptions::builder().build();
+/// let b_option = ReadOptions::with_password(Some("password"));
+/// assert!(compare_entries(&a, &a_option, &b, &b_option, &EntryEq::new())?);
+/// # Ok(())
+/// # }
+/// ```
+#[inline]
+pub fn compare_entries<T, U>(
+    a: &NormalEntry<T>,
+    a_option: impl ReadOption,
+    b: &NormalEntry<U>,
+    b_option: impl ReadOption,
+    eq: &EntryEq,
+) -> io::Result<bool>
+where
+    T: AsRef<[u8]>,
+    U: AsRef<[u8]>,
+    RawChunk<T>: Chunk,
+    RawChunk<U>: Chunk,
+{
+    if eq.name && a.header().path() != b.header().path() {
+        return Ok(false);
+    }
+    if eq.content && a.header().data_kind() != b.header().data_kind() {
+        return Ok(false);
+    }
+    let (a_meta, b_meta) = (a.metadata(), b.metadata());
+    if let Some(tolerance) = eq.timestamps {
+        let within = |a: Option<Duration>, b: Option<Duration>| match (a, b) {
+            (Some(a), Some(b)) => a.abs_diff(b) <= tolerance,
+            (a, b) => a.is_none() && b.is_none(),
+        };
+        if !within(a_meta.created(), b_meta.created())
+            || !within(a_meta.modified(), b_meta.modified())
+            || !within(a_meta.accessed(), b_meta.accessed())
+        {
+            return Ok(false);
+        }
+    }
+    if eq.permission && a_meta.permission() != b_meta.permission() {
+        return Ok(false);
+    }
+    if eq.xattrs && sorted_xattrs(a.xattrs()) != sorted_xattrs(b.xattrs()) {
+        return Ok(false);
+    }
+    if eq.extra_chunks {
+        let (a_chunks, b_chunks) = (a.extra_chunks(), b.extra_chunks());
+        if a_chunks.len() != b_chunks.len()
+            || a_chunks
+                .iter()
+                .zip(b_chunks)
+                .any(|(a, b)| a.ty() != b.ty() || a.data() != b.data())
+        {
+            return Ok(false);
+        }
+    }
+    if eq.content {
+        let sizes = (a_meta.raw_file_size(), b_meta.raw_file_size());
+        if let (Some(a_size), Some(b_size)) = sizes {
+            if a_size != b_size {
+                return Ok(false);
+            }
+        }
+        return compare_readers(a.reader(a_option)?, b.reader(b_option)?);
+    }
+    Ok(true)
+}
+
+/// Returns `true` if the readers `a` and `b` read the same bytes until their ends.
+///
+/// The readers are read in lockstep with fixed size buffers,
+/// and the comparison stops at the first difference.
+///
+/// # Examples
+/// ```
+/// use libpna::compare_readers;
+/// # use std::io;
+///
+/// # fn main() -> io::Result<()> {
+/// assert!(compare_readers(&b"text"[..], &b"text"[..])?);
+/// assert!(!compare_readers(&b"text"[..], &b"text!"[..])?);
+/// # Ok(())
+/// # }
+/// ```
+#[inline]
+pub fn compare_readers(mut a: impl Read, mut b: impl Read) -> io::Result<bool> {
+    let mut a_buf = vec![0; COMPARE_BUFFER_SIZE];
+    let mut b_buf = vec![0; COMPARE_BUFFER_SIZE];
+    loop {
+        let n = read_full(&mut a, &mut a_buf)?;
+        // NOTE: Read one more byte at the end of `a` to detect a longer `b`.
+        let m = read_full(&mut b, &mut b_buf[..n.max(1)])?;
+        if n != m || a_buf[..n] != b_buf[..m] {
+            return Ok(false);
+        }
+        if n == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+fn sorted_xattrs(xattrs: &[ExtendedAttribute]) -> Vec<(&str, &[u8])> {
+    let mut xattrs = xattrs
+        .iter()
+        .map(|it| (it.name(), it.value()))
+        .collect::<Vec<_>>();
+    xattrs.sort_unstable();
+    xattrs
+}
+
+/// Reads into `buf` until it is full or the end of `reader`, returns the number of bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut total = 0;
+    while total < buf.len() {
+        match reader.read(&mut buf[total..]) {
+            Ok(0) => break,
+            Ok(n) => total += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        EntryBuilder, Metadata, Permission, ReadOptions, WriteOptions, WriteOptionsBuilder,
+    };
+    use std::io::Write;
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn file_entry(name: &str, content: &[u8], option: WriteOptions) -> NormalEntry {
+        let mut builder = EntryBuilder::new_file(name.into(), option).unwrap();
+        builder.write_all(content).unwrap();
+        builder.build().unwrap()
+    }
+
+    fn compare(a: &NormalEntry, b: &NormalEntry, eq: &EntryEq) -> bool {
+        let option = ReadOptions::builder().build();
+        compare_entries(a, &option, b, &option, eq).unwrap()
+    }
+
+    fn large_content() -> Vec<u8> {
+        (0..3 * COMPARE_BUFFER_SIZE + 17)
+            .map(|i| (i % 251) as u8)
+            .collect()
+    }
+
+    #[test]
+    fn equal_entries() {
+        let content = large_content();
+        let a = file_entry("file.bin", &content, WriteOptions::store());
+        let b = file_entry(
+            "file.bin",
+            &content,
+            WriteOptionsBuilder::default()
+                .compression(crate::Compression::ZStandard)
+                .build(),
+        );
+        assert!(compare(&a, &b, &EntryEq::new()));
+    }
+
+    #[test]
+    fn content_differs_at_last_byte() {
+        let content = large_content();
+        let mut other = content.clone();
+        *other.last_mut().unwrap() ^= 1;
+        let a = file_entry("file.bin", &content, WriteOptions::store());
+        let b = file_entry("file.bin", &other, WriteOptions::store());
+        assert!(!compare(&a, &b, &EntryEq::new()));
+        assert!(compare(&a, &b, &EntryEq::new().with_content(false)));
+        let shorter = file_entry("file.bin", &content[1..], WriteOptions::store());
+        assert!(!compare(&a, &shorter, &EntryEq::new()));
+    }
+
+    #[test]
+    fn metadata_only_differs() {
+        let a = file_entry("file.txt", b"text", WriteOptions::store());
+        let metadata = Metadata::new()
+            .with_modified(Some(Duration::from_secs(10)))
+            .with_permission(Some(Permission::new(
+                1000,
+                "user".into(),
+                1000,
+                "group".into(),
+                0o644,
+            )));
+        let b = a.clone().with_metadata(metadata.clone());
+        assert!(!compare(&a, &b, &EntryEq::new()));
+        let eq = EntryEq::new().with_timestamps(None).with_permission(false);
+        assert!(compare(&a, &b, &eq));
+
+        let c = a
+            .clone()
+            .with_metadata(metadata.with_modified(Some(Duration::from_millis(10_500))));
+        let eq = EntryEq::new().with_permission(false);
+        assert!(!compare(&b, &c, &eq));
+        assert!(compare(
+            &b,
+            &c,
+            &eq.with_timestamps(Some(Duration::from_secs(1)))
+        ));
+    }
+
+    #[test]
+    fn encrypted_entries() {
+        let content = large_content();
+        let a = file_entry(
+            "file.bin",
+            &content,
+            WriteOptions::builder()
+                .encryption(crate::Encryption::Aes)
+                .password(Some("password a"))
+                .build(),
+        );
+        let b = file_entry(
+            "file.bin",
+            &content,
+            WriteOptions::builder()
+                .encryption(crate::Encryption::Camellia)
+                .password(Some("password b"))
+                .build(),
+        );
+        let a_option = ReadOptions::with_password(Some("password a"));
+        let b_option = ReadOptions::with_password(Some("password b"));
+        let eq = EntryEq::new();
+        assert!(compare_entries(&a, &a_option, &b, &b_option, &eq).unwrap());
+        // NOTE: A wrong password is either detected or gives a different content.
+        assert!(!compare_entries(&a, &a_option, &b, &a_option, &eq).unwrap_or(false));
+    }
+}
@@ -0,0 +1,124 @@
+//! Digests of the canonical chunk serialization of entries.
+//!
+//! An entry is serialized into the chunks in the order written by [Archive::add_entry](crate::Archive::add_entry),
+//! and each chunk contributes its length in big endian, its type and its data to the SHA-256 digest.
+//! The CRCs are left out, because they are derived from the other fields.
+use super::{private::SealedEntryExt, Entry, NormalEntry, ReadEntry, SolidEntry};
+use crate::chunk::{Chunk, ChunkType, RawChunk};
+use sha2::{Digest, Sha256};
+
+fn chunks_digest(chunks: Vec<RawChunk>, exclude: &[ChunkType]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for chunk in chunks.iter().filter(|it| !exclude.contains(&it.ty())) {
+        hasher.update(chunk.length().to_be_bytes());
+        hasher.update(chunk.ty().0);
+        hasher.update(chunk.data());
+    }
+    hasher.finalize().into()
+}
+
+impl<T> NormalEntry<T>
+where
+    Self: Entry + Clone,
+{
+    /// Returns the SHA-256 digest of the chunks of the entry, skipping the chunks whose types are in `exclude`.
+    ///
+    /// Excluding the chunk that stores a signature of the entry gives the same digest
+    /// before and after the signature is added.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::io;
+    /// use libpna::{ChunkType, EntryBuilder, RawChunk};
+    ///
+    /// # fn main() -> io::Result<()> {
+    /// let sign = ChunkType::private(*b"fsIG").unwrap();
+    /// let entry = EntryBuilder::new_dir("dir".into()).build()?;
+    /// let digest = entry.digest(&[sign]);
+    /// let entry = entry.with_extra_chunks(&[RawChunk::from_data(sign, vec![0; 64])]);
+    /// assert_eq!(entry.digest(&[sign]), digest);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn digest(&self, exclude: &[ChunkType]) -> [u8; 32] {
+        chunks_digest(self.clone().into_chunks(), exclude)
+    }
+}
+
+impl<T> SolidEntry<T>
+where
+    Self: Entry + Clone,
+{
+    /// Returns the SHA-256 digest of the chunks of the solid entry, skipping the chunks whose types are in `exclude`.
+    ///
+    /// The entries in the solid entry are digested in their compressed and encrypted form.
+    #[inline]
+    pub fn digest(&self, exclude: &[ChunkType]) -> [u8; 32] {
+        chunks_digest(self.clone().into_chunks(), exclude)
+    }
+}
+
+impl<T> ReadEntry<T>
+where
+    Self: Entry + Clone,
+{
+    /// Returns the SHA-256 digest of the chunks of the entry, skipping the chunks whose types are in `exclude`.
+    /// See [NormalEntry::digest] and [SolidEntry::digest].
+    #[inline]
+    pub fn digest(&self, exclude: &[ChunkType]) -> [u8; 32] {
+        chunks_digest(self.clone().into_chunks(), exclude)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Archive, EntryBuilder, SolidEntryBuilder, WriteOptions};
+    use std::io::Write;
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    const SIGN: ChunkType = unsafe { ChunkType::from_unchecked(*b"fsIG") };
+
+    fn file_entry(content: &[u8]) -> NormalEntry {
+        let mut builder = EntryBuilder::new_file("a.txt".into(), WriteOptions::store()).unwrap();
+        builder.write_all(content).unwrap();
+        builder.build().unwrap()
+    }
+
+    #[test]
+    fn digest_changes_with_content() {
+        assert_eq!(file_entry(b"a").digest(&[]), file_entry(b"a").digest(&[]));
+        assert_ne!(file_entry(b"a").digest(&[]), file_entry(b"b").digest(&[]));
+    }
+
+    #[test]
+    fn digest_excludes_chunks() {
+        let entry = file_entry(b"a");
+        let signed = entry
+            .clone()
+            .with_extra_chunks(&[RawChunk::from_data(SIGN, vec![1; 64])]);
+        assert_ne!(signed.digest(&[]), entry.digest(&[]));
+        assert_eq!(signed.digest(&[SIGN]), entry.digest(&[]));
+    }
+
+    #[test]
+    fn digest_of_read_entry() {
+        let entry = file_entry(b"a");
+        let digest = entry.digest(&[]);
+        assert_eq!(ReadEntry::Normal(entry).digest(&[]), digest);
+
+        let mut solid = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+        solid.add_entry(file_entry(b"a")).unwrap();
+        let mut archive = Archive::write_header(Vec::new()).unwrap();
+        archive.add_entry(solid.build().unwrap()).unwrap();
+        let bytes = archive.finalize().unwrap();
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        let Some(ReadEntry::Solid(solid)) = archive.entries().next().transpose().unwrap() else {
+            panic!("solid entry expected");
+        };
+        let digest = solid.digest(&[]);
+        assert_eq!(ReadEntry::Solid(solid).digest(&[]), digest);
+    }
+}
@@ -54,6 +54,16 @@ impl EntryHeader {
         Self::new_with_options(DataKind::File, compression, encryption, cipher_mode, path)
     }
 
+    /// Creates the header of a file entry whose data is compressed with `compression`
+    /// and not encrypted.
+    ///
+    /// This is used with [`NormalEntry::from_raw_parts`](crate::NormalEntry::from_raw_parts)
+    /// to store data compressed outside of this library.
+    #[inline]
+    pub const fn for_compressed_file(compression: Compression, path: EntryName) -> Self {
+        Self::for_file(compression, Encryption::No, CipherMode::CBC, path)
+    }
+
     #[inline]
     pub(crate) const fn for_dir(path: EntryName) -> Self {
         Self::new(DataKind::Directory, path)
@@ -145,6 +155,9 @@ impl TryFrom<&[u8]> for EntryHeader {
     }
 }
 
+/// Minor version of [SolidHeader] whose entries are stored in the packed encoding.
+const PACKED_SOLID_MINOR: u8 = 1;
+
 /// Represents the entry information header that is expressed in the [FHED] chunk.
 ///
 /// [FHED]: crate::ChunkType::FHED
@@ -172,6 +185,34 @@ impl SolidHeader {
         }
     }
 
+    /// Marks the solid entry to store the entries in the packed encoding.
+    #[inline]
+    pub(crate) const fn packed(mut self) -> Self {
+        self.minor = PACKED_SOLID_MINOR;
+        self
+    }
+
+    /// Returns `true` if the entries in the solid entry are stored in the packed encoding,
+    /// which compacts the per-entry chunk overhead.
+    #[inline]
+    pub const fn is_packed(&self) -> bool {
+        self.major == 0 && self.minor == PACKED_SOLID_MINOR
+    }
+
+    /// Returns an error if the version of the solid entry is newer than this library supports.
+    pub(crate) fn check_version(&self) -> io::Result<()> {
+        if self.major == 0 && self.minor <= PACKED_SOLID_MINOR {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                "solid entry version {}.{} is not supported",
+                self.major, self.minor
+            ),
+        ))
+    }
+
     /// Compression method of the solid entry.
     #[inline]
     pub const fn compression(&self) -> Compression {
@@ -259,6 +300,21 @@ mod tests {
         assert!(SolidHeader::try_from_bytes(&[0; 5]).is_ok());
     }
 
+    #[test]
+    fn solid_header_version() {
+        let header = SolidHeader::new(Compression::ZStandard, Encryption::No, CipherMode::CBC);
+        assert!(!header.is_packed());
+        assert!(header.check_version().is_ok());
+        let header = SolidHeader::try_from_bytes(&header.packed().to_bytes()).unwrap();
+        assert!(header.is_packed());
+        assert!(header.check_version().is_ok());
+        let header = SolidHeader::try_from_bytes(&[0, 2, 0, 0, 0]).unwrap();
+        assert_eq!(
+            header.check_version().unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+    }
+
     #[test]
     fn solid_header_to_from_bytes() {
         let header = SolidHeader::new(Compression::ZStandard, Encryption::Aes, CipherMode::CBC);
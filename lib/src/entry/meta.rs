@@ -108,6 +108,16 @@ impl Metadata {
         self
     }
 
+    /// Set raw file size of entry data in bytes.
+    ///
+    /// This is stored only by [`NormalEntry::from_raw_parts`](crate::NormalEntry::from_raw_parts),
+    /// other entries keep the size of the data written to them.
+    #[inline]
+    pub const fn with_raw_file_size(mut self, size: Option<u128>) -> Self {
+        self.raw_file_size = size;
+        self
+    }
+
     /// Raw file size of entry data in bytes
     #[inline]
     pub const fn raw_file_size(&self) -> Option<u128> {
@@ -12,21 +12,44 @@ pub struct EntryName(String);
 
 /// Error of invalid [EntryName].
 #[derive(Clone, Eq, PartialEq, Debug)]
-pub struct EntryNameError(Utf8Error);
+#[non_exhaustive]
+pub enum EntryNameError {
+    /// The name is not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// The name is empty or becomes empty after normalization.
+    Empty,
+    /// The name contains a `..` component after a normal component.
+    ParentDir,
+    /// The name contains a control character.
+    ControlCharacter,
+}
 
-impl Error for EntryNameError {}
+impl Error for EntryNameError {
+    #[inline]
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::InvalidUtf8(e) => Some(e),
+            Self::Empty | Self::ParentDir | Self::ControlCharacter => None,
+        }
+    }
+}
 
 impl Display for EntryNameError {
     #[inline]
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        Display::fmt(&self.0, f)
+        match self {
+            Self::InvalidUtf8(e) => Display::fmt(e, f),
+            Self::Empty => f.write_str("entry name is empty"),
+            Self::ParentDir => f.write_str("entry name contains `..` component"),
+            Self::ControlCharacter => f.write_str("entry name contains control character"),
+        }
     }
 }
 
 impl From<Utf8Error> for EntryNameError {
     #[inline]
     fn from(value: Utf8Error) -> Self {
-        Self(value)
+        Self::InvalidUtf8(value)
     }
 }
 
@@ -45,6 +68,64 @@ impl EntryName {
         Self(buf.join("/"))
     }
 
+    fn try_new_from_utf8path(path: &Utf8Path) -> Result<Self, EntryNameError> {
+        if path.as_str().chars().any(char::is_control) {
+            return Err(EntryNameError::ControlCharacter);
+        }
+        let mut buf = Vec::new();
+        for c in path.components() {
+            match c {
+                Utf8Component::Prefix(_) | Utf8Component::RootDir | Utf8Component::CurDir => (),
+                // NOTE: Leading `..` components are removed as well as tar does.
+                Utf8Component::ParentDir if buf.is_empty() => (),
+                Utf8Component::ParentDir => return Err(EntryNameError::ParentDir),
+                Utf8Component::Normal(p) => buf.push(p),
+            }
+        }
+        if buf.is_empty() {
+            return Err(EntryNameError::Empty);
+        }
+        Ok(Self(buf.join("/")))
+    }
+
+    /// Creates an [`EntryName`] with strict validation.
+    ///
+    /// Root, prefix, `.` components, leading `..` components and duplicate or trailing separators are removed.
+    /// Unlike [`EntryName::from`] and [`EntryName::from_lossy`], which drop every `..` component
+    /// and keep any character, the following names are rejected.
+    /// Other names, including the names of hidden files and names with `..` inside a component
+    /// such as `file..txt`, are normalized the same way as [`EntryName::from`] does.
+    ///
+    /// # Errors
+    ///
+    /// - [`EntryNameError::InvalidUtf8`] if the name is not valid UTF-8.
+    /// - [`EntryNameError::Empty`] if the name is empty or becomes empty after normalization.
+    /// - [`EntryNameError::ParentDir`] if the name contains a `..` component after a normal component.
+    /// - [`EntryNameError::ControlCharacter`] if the name contains a control character such as a newline.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{EntryName, EntryNameError};
+    ///
+    /// assert_eq!(EntryName::try_new("dir/./file").unwrap(), "dir/file");
+    /// assert_eq!(EntryName::try_new("dir//file/").unwrap(), "dir/file");
+    /// assert_eq!(EntryName::try_new("./").unwrap_err(), EntryNameError::Empty);
+    /// assert_eq!(
+    ///     EntryName::try_new("dir/../file").unwrap_err(),
+    ///     EntryNameError::ParentDir
+    /// );
+    /// assert_eq!(
+    ///     EntryName::try_new("new\nline").unwrap_err(),
+    ///     EntryNameError::ControlCharacter
+    /// );
+    /// ```
+    #[inline]
+    pub fn try_new<T: AsRef<Path>>(name: T) -> Result<Self, EntryNameError> {
+        let name = str::from_utf8(name.as_ref().as_os_str().as_encoded_bytes())?;
+        Self::try_new_from_utf8path(Utf8Path::new(name))
+    }
+
     #[inline]
     fn new_from_utf8(name: &str) -> Self {
         Self::new_from_utf8path(&Utf8PathBuf::from(name))
@@ -295,6 +376,85 @@ mod tests {
         assert_eq!(EntryName::from("test/test/"), EntryName::from("test/test"));
     }
 
+    #[test]
+    fn try_new_normalize() {
+        assert_eq!(EntryName::try_new("file").unwrap(), "file");
+        assert_eq!(EntryName::try_new("dir/./file").unwrap(), "dir/file");
+        assert_eq!(EntryName::try_new("./dir/file").unwrap(), "dir/file");
+        assert_eq!(EntryName::try_new("dir//file").unwrap(), "dir/file");
+        assert_eq!(EntryName::try_new("dir/file/").unwrap(), "dir/file");
+        assert_eq!(EntryName::try_new("dir/file/.").unwrap(), "dir/file");
+        assert_eq!(EntryName::try_new("/dir/file").unwrap(), "dir/file");
+        assert_eq!(EntryName::try_new("../dir/file").unwrap(), "dir/file");
+        assert_eq!(EntryName::try_new("../../dir/file").unwrap(), "dir/file");
+    }
+
+    #[test]
+    fn try_new_accept_as_from() {
+        for name in [
+            "file.txt",
+            ".hidden",
+            "dir/.hidden/file",
+            "..file",
+            "file..txt",
+            "dir/.../file",
+            "with space/and-symbols_!@#$%&()",
+            "日本語/ファイル.txt",
+            "dir\\file",
+        ] {
+            assert_eq!(EntryName::try_new(name).unwrap(), EntryName::from(name));
+        }
+    }
+
+    #[test]
+    fn try_new_reject() {
+        assert_eq!(EntryName::try_new("").unwrap_err(), EntryNameError::Empty);
+        assert_eq!(EntryName::try_new(".").unwrap_err(), EntryNameError::Empty);
+        assert_eq!(EntryName::try_new("/").unwrap_err(), EntryNameError::Empty);
+        assert_eq!(
+            EntryName::try_new("./.").unwrap_err(),
+            EntryNameError::Empty
+        );
+        assert_eq!(EntryName::try_new("..").unwrap_err(), EntryNameError::Empty);
+        assert_eq!(
+            EntryName::try_new("dir/../file").unwrap_err(),
+            EntryNameError::ParentDir
+        );
+        assert_eq!(
+            EntryName::try_new("dir/..").unwrap_err(),
+            EntryNameError::ParentDir
+        );
+        assert_eq!(
+            EntryName::try_new("new\nline").unwrap_err(),
+            EntryNameError::ControlCharacter
+        );
+        assert_eq!(
+            EntryName::try_new("tab\tname").unwrap_err(),
+            EntryNameError::ControlCharacter
+        );
+        assert_eq!(
+            EntryName::try_new("escape\x1b[0m").unwrap_err(),
+            EntryNameError::ControlCharacter
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn try_new_reject_non_utf8() {
+        use std::os::unix::ffi::OsStrExt;
+        assert!(matches!(
+            EntryName::try_new(OsStr::from_bytes(b"\xff")),
+            Err(EntryNameError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn lossy_normalize() {
+        assert_eq!(EntryName::from("dir/./file"), "dir/file");
+        assert_eq!(EntryName::from("dir//file"), "dir/file");
+        assert_eq!(EntryName::from("dir/../file"), "dir/file");
+    }
+
     #[cfg(target_os = "windows")]
     #[test]
     fn remove_prefix() {
@@ -1,6 +1,17 @@
-use crate::compress;
+use crate::{
+    compress,
+    hash::{ReadKeyCache, WriteKeyCache},
+    Identity, Recipient,
+};
 pub(crate) use private::*;
-use std::str::FromStr;
+use std::{
+    cmp::Ordering,
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    str::FromStr,
+    sync::Arc,
+};
+use zeroize::Zeroizing;
 
 mod private {
     use super::*;
@@ -14,29 +25,40 @@ mod private {
         XZ(compress::xz::XZCompressionLevel),
     }
 
+    /// Key source of the cipher.
+    #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+    pub(crate) enum CipherKey {
+        Password(Password),
+        Recipients(Vec<Recipient>),
+    }
+
     /// Cipher options.
     #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
     pub struct Cipher {
-        pub(crate) password: Password,
+        pub(crate) key: CipherKey,
         pub(crate) hash_algorithm: HashAlgorithm,
         pub(crate) cipher_algorithm: CipherAlgorithm,
         pub(crate) mode: CipherMode,
+        /// The key derived once for all the entries, if [`WriteOptionsBuilder::reuse_salt`] is set.
+        pub(crate) session: Option<WriteKeyCache>,
     }
 
     impl Cipher {
         /// Create new [Cipher]
         #[inline]
         pub(crate) const fn new(
-            password: Password,
+            key: CipherKey,
             hash_algorithm: HashAlgorithm,
             cipher_algorithm: CipherAlgorithm,
             mode: CipherMode,
+            session: Option<WriteKeyCache>,
         ) -> Self {
             Self {
-                password,
+                key,
                 hash_algorithm,
                 cipher_algorithm,
                 mode,
+                session,
             }
         }
     }
@@ -45,6 +67,7 @@ mod private {
     pub trait WriteOption {
         fn compress(&self) -> Compress;
         fn cipher(&self) -> Option<&Cipher>;
+        fn zstd_dictionary(&self) -> Option<&Arc<[u8]>>;
         #[inline]
         fn compression(&self) -> Compression {
             match self.compress() {
@@ -77,7 +100,18 @@ mod private {
 
         #[inline]
         fn password(&self) -> Option<&str> {
-            self.cipher().map(|it| it.password.0.as_str())
+            self.cipher().and_then(|it| match &it.key {
+                CipherKey::Password(password) => Some(password.as_str()),
+                CipherKey::Recipients(_) => None,
+            })
+        }
+
+        #[inline]
+        fn recipients(&self) -> &[Recipient] {
+            match self.cipher().map(|it| &it.key) {
+                Some(CipherKey::Recipients(recipients)) => recipients,
+                Some(CipherKey::Password(_)) | None => &[],
+            }
         }
     }
 
@@ -91,6 +125,11 @@ mod private {
         fn cipher(&self) -> Option<&Cipher> {
             self.cipher.as_ref()
         }
+
+        #[inline]
+        fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+            self.zstd_dictionary.as_ref()
+        }
     }
 
     impl<T> WriteOption for &T
@@ -106,11 +145,19 @@ mod private {
         fn cipher(&self) -> Option<&Cipher> {
             T::cipher(self)
         }
+
+        #[inline]
+        fn zstd_dictionary(&self) -> Option<&Arc<[u8]>> {
+            T::zstd_dictionary(self)
+        }
     }
 
     /// Entry read option getter trait.
     pub trait ReadOption {
         fn password(&self) -> Option<&str>;
+        fn identities(&self) -> &[Identity];
+        fn zstd_dictionary(&self) -> Option<&[u8]>;
+        fn key_cache(&self) -> Option<&ReadKeyCache>;
     }
 
     impl<T: ReadOption> ReadOption for &T {
@@ -118,12 +165,42 @@ mod private {
         fn password(&self) -> Option<&str> {
             T::password(self)
         }
+
+        #[inline]
+        fn identities(&self) -> &[Identity] {
+            T::identities(self)
+        }
+
+        #[inline]
+        fn zstd_dictionary(&self) -> Option<&[u8]> {
+            T::zstd_dictionary(self)
+        }
+
+        #[inline]
+        fn key_cache(&self) -> Option<&ReadKeyCache> {
+            T::key_cache(self)
+        }
     }
 
     impl ReadOption for ReadOptions {
         #[inline]
         fn password(&self) -> Option<&str> {
-            self.password.as_deref()
+            self.password.as_ref().map(Password::as_str)
+        }
+
+        #[inline]
+        fn identities(&self) -> &[Identity] {
+            &self.identities
+        }
+
+        #[inline]
+        fn zstd_dictionary(&self) -> Option<&[u8]> {
+            self.zstd_dictionary.as_deref()
+        }
+
+        #[inline]
+        fn key_cache(&self) -> Option<&ReadKeyCache> {
+            Some(&self.key_cache)
         }
     }
 }
@@ -270,10 +347,17 @@ pub enum CipherAlgorithm {
 }
 
 /// Password.
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-pub(crate) struct Password(String);
+///
+/// The password is zeroed out when dropped and never printed by [Debug].
+#[derive(Clone, Eq, PartialEq)]
+pub(crate) struct Password(Zeroizing<String>);
 
 impl Password {
+    #[inline]
+    pub(crate) fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
     #[inline]
     pub(crate) fn as_bytes(&self) -> &[u8] {
         self.0.as_bytes()
@@ -283,14 +367,42 @@ impl Password {
 impl From<String> for Password {
     #[inline]
     fn from(value: String) -> Self {
-        Self(value)
+        Self(Zeroizing::new(value))
     }
 }
 
 impl From<&str> for Password {
     #[inline]
     fn from(value: &str) -> Self {
-        Self(value.into())
+        Self(Zeroizing::new(value.into()))
+    }
+}
+
+impl PartialOrd for Password {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Password {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Hash for Password {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl Debug for Password {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Password").field(&"[REDACTED]").finish()
     }
 }
 
@@ -434,6 +546,7 @@ impl TryFrom<u8> for DataKind {
 pub struct WriteOptions {
     compress: Compress,
     cipher: Option<Cipher>,
+    zstd_dictionary: Option<Arc<[u8]>>,
 }
 
 impl WriteOptions {
@@ -453,6 +566,7 @@ impl WriteOptions {
         Self {
             compress: Compress::No,
             cipher: None,
+            zstd_dictionary: None,
         }
     }
 
@@ -501,7 +615,10 @@ pub struct WriteOptionsBuilder {
     encryption: Encryption,
     cipher_mode: CipherMode,
     hash_algorithm: HashAlgorithm,
-    password: Option<String>,
+    password: Option<Password>,
+    recipients: Vec<Recipient>,
+    zstd_dictionary: Option<Arc<[u8]>>,
+    reuse_salt: bool,
 }
 
 impl Default for WriteOptionsBuilder {
@@ -527,6 +644,9 @@ impl From<WriteOptions> for WriteOptionsBuilder {
             cipher_mode: value.cipher_mode(),
             hash_algorithm: value.hash_algorithm(),
             password: value.password().map(Into::into),
+            recipients: value.recipients().to_vec(),
+            reuse_salt: value.cipher.as_ref().is_some_and(|it| it.session.is_some()),
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -540,6 +660,9 @@ impl WriteOptionsBuilder {
             cipher_mode: CipherMode::CTR,
             hash_algorithm: HashAlgorithm::argon2id(),
             password: None,
+            recipients: Vec::new(),
+            zstd_dictionary: None,
+            reuse_salt: false,
         }
     }
 
@@ -585,19 +708,90 @@ impl WriteOptionsBuilder {
         self
     }
 
+    /// Set the recipients to this builder.
+    ///
+    /// When recipients are set, the content key of each entry is generated randomly
+    /// and encrypted to every recipient instead of being derived from the password.
+    #[inline]
+    pub fn recipients<I: IntoIterator<Item = Recipient>>(&mut self, recipients: I) -> &mut Self {
+        self.recipients = recipients.into_iter().collect();
+        self
+    }
+
+    /// Set the dictionary used to compress entries with [`Compression::ZStandard`] to this builder.
+    ///
+    /// The dictionary is stored in the archive the entries are added to,
+    /// and the entries cannot be read without it.
+    /// It is ignored by the other compression methods.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{Compression, WriteOptions};
+    ///
+    /// let option = WriteOptions::builder()
+    ///     .compression(Compression::ZStandard)
+    ///     .zstd_dictionary(Some(b"dictionary".to_vec()))
+    ///     .build();
+    /// ```
+    #[inline]
+    pub fn zstd_dictionary(&mut self, dictionary: Option<Vec<u8>>) -> &mut Self {
+        self.zstd_dictionary = dictionary.map(Into::into);
+        self
+    }
+
+    /// Set whether to reuse one salt for all the entries written with the built [WriteOptions] to this builder.
+    ///
+    /// By default, each entry encrypted with a password gets a new random salt,
+    /// so the key is derived from the password for every entry, both when writing and reading.
+    /// With the password hash algorithms designed to be slow, this dominates the time
+    /// to write or read many small entries.
+    ///
+    /// When set, the salt is generated and the key is derived only once, when the first entry is written,
+    /// and the [WriteOptions] built by one call of [WriteOptionsBuilder::build] and their clones
+    /// encrypt all the entries with that key.
+    /// Reading the entries with one [ReadOptions] then derives the key only once as well.
+    ///
+    /// # Security
+    ///
+    /// Each entry still gets a random initialization vector, but all the entries share one key.
+    /// An attacker guessing the password tries each guess against all the entries at once
+    /// instead of entry by entry, and anyone who learns the key of one entry can decrypt all of them.
+    /// Entries are also recognizable as encrypted with the same password from their identical salts.
+    ///
+    /// Defaults to `false`. It has no effect when the entries are encrypted to recipients.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use libpna::{Encryption, WriteOptions};
+    ///
+    /// let option = WriteOptions::builder()
+    ///     .encryption(Encryption::Aes)
+    ///     .password(Some("password"))
+    ///     .reuse_salt(true)
+    ///     .build();
+    /// ```
+    #[inline]
+    pub fn reuse_salt(&mut self, reuse_salt: bool) -> &mut Self {
+        self.reuse_salt = reuse_salt;
+        self
+    }
+
     /// Create new [WriteOptions] parameters set from this builder.
     ///
     /// ## Panics
     ///
-    /// Panic will occur when encryption is enabled and password is not provided.
+    /// Panic will occur when encryption is enabled and neither password nor recipients are provided.
     #[inline]
     pub fn build(&self) -> WriteOptions {
         let cipher = if self.encryption != Encryption::No {
             Some(Cipher::new(
-                self.password
-                    .as_deref()
-                    .expect("Password was not provided.")
-                    .into(),
+                if self.recipients.is_empty() {
+                    CipherKey::Password(self.password.clone().expect("Password was not provided."))
+                } else {
+                    CipherKey::Recipients(self.recipients.clone())
+                },
                 self.hash_algorithm,
                 match self.encryption {
                     Encryption::Aes => CipherAlgorithm::Aes,
@@ -605,6 +799,7 @@ impl WriteOptionsBuilder {
                     Encryption::No => unreachable!(),
                 },
                 self.cipher_mode,
+                (self.reuse_salt && self.recipients.is_empty()).then(WriteKeyCache::default),
             ))
         } else {
             None
@@ -617,14 +812,24 @@ impl WriteOptionsBuilder {
                 Compression::XZ => Compress::XZ(self.compression_level.into()),
             },
             cipher,
+            zstd_dictionary: match self.compression {
+                Compression::ZStandard => self.zstd_dictionary.clone(),
+                _ => None,
+            },
         }
     }
 }
 
 /// Options for reading an entry.
+///
+/// The keys derived from the password are cached in the [ReadOptions] and shared by its clones,
+/// so that reading many entries encrypted with the same salt derives the key only once.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct ReadOptions {
-    password: Option<String>,
+    password: Option<Password>,
+    identities: Vec<Identity>,
+    zstd_dictionary: Option<Arc<[u8]>>,
+    key_cache: ReadKeyCache,
 }
 
 impl ReadOptions {
@@ -639,7 +844,10 @@ impl ReadOptions {
     #[inline]
     pub fn with_password<T: Into<String>>(password: Option<T>) -> Self {
         Self {
-            password: password.map(Into::into),
+            password: password.map(|it| it.into().into()),
+            identities: Vec::new(),
+            zstd_dictionary: None,
+            key_cache: ReadKeyCache::default(),
         }
     }
 
@@ -682,7 +890,9 @@ impl ReadOptions {
 /// Builder for [`ReadOptions`].
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct ReadOptionsBuilder {
-    password: Option<String>,
+    password: Option<Password>,
+    identities: Vec<Identity>,
+    zstd_dictionary: Option<Arc<[u8]>>,
 }
 
 impl From<ReadOptions> for ReadOptionsBuilder {
@@ -690,6 +900,8 @@ impl From<ReadOptions> for ReadOptionsBuilder {
     fn from(value: ReadOptions) -> Self {
         Self {
             password: value.password,
+            identities: value.identities,
+            zstd_dictionary: value.zstd_dictionary,
         }
     }
 }
@@ -697,7 +909,34 @@ impl From<ReadOptions> for ReadOptionsBuilder {
 impl ReadOptionsBuilder {
     #[inline]
     const fn new() -> Self {
-        Self { password: None }
+        Self {
+            password: None,
+            identities: Vec::new(),
+            zstd_dictionary: None,
+        }
+    }
+
+    /// Set the password to this builder.
+    #[inline]
+    pub fn password<S: AsRef<str>>(&mut self, password: Option<S>) -> &mut Self {
+        self.password = password.map(|it| it.as_ref().into());
+        self
+    }
+
+    /// Set the identities used to decrypt entries encrypted to recipients to this builder.
+    #[inline]
+    pub fn identities<I: IntoIterator<Item = Identity>>(&mut self, identities: I) -> &mut Self {
+        self.identities = identities.into_iter().collect();
+        self
+    }
+
+    /// Set the dictionary used to decompress entries compressed with a zstd dictionary to this builder.
+    ///
+    /// Entries read from an archive use the dictionary stored in the archive, unless this is set.
+    #[inline]
+    pub fn zstd_dictionary(&mut self, dictionary: Option<Vec<u8>>) -> &mut Self {
+        self.zstd_dictionary = dictionary.map(Into::into);
+        self
     }
 
     /// Create a new [`ReadOptions`]
@@ -705,6 +944,58 @@ impl ReadOptionsBuilder {
     pub fn build(&self) -> ReadOptions {
         ReadOptions {
             password: self.password.clone(),
+            identities: self.identities.clone(),
+            zstd_dictionary: self.zstd_dictionary.clone(),
+            key_cache: ReadKeyCache::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    const PASSWORD: &str = "secret-password";
+
+    #[test]
+    fn password_debug_is_redacted() {
+        let password = Password::from(PASSWORD);
+        assert_eq!(format!("{password:?}"), r#"Password("[REDACTED]")"#);
+    }
+
+    #[test]
+    fn write_options_debug_redacts_password() {
+        let mut builder = WriteOptions::builder();
+        builder.encryption(Encryption::Aes).password(Some(PASSWORD));
+        let options = builder.build();
+        assert_eq!(options.password(), Some(PASSWORD));
+        for debug in [format!("{builder:?}"), format!("{options:?}")] {
+            assert!(!debug.contains(PASSWORD), "{debug}");
+            assert!(debug.contains("[REDACTED]"), "{debug}");
         }
     }
+
+    #[test]
+    fn read_options_debug_redacts_password() {
+        let mut builder = ReadOptions::builder();
+        builder.password(Some(PASSWORD));
+        let options = builder.build();
+        assert_eq!(options.password(), Some(PASSWORD));
+        for debug in [
+            format!("{builder:?}"),
+            format!("{options:?}"),
+            format!("{:?}", ReadOptions::with_password(Some(PASSWORD))),
+        ] {
+            assert!(!debug.contains(PASSWORD), "{debug}");
+            assert!(debug.contains("[REDACTED]"), "{debug}");
+        }
+    }
+
+    #[test]
+    fn password_compares_by_value() {
+        assert_eq!(Password::from("a"), Password::from(String::from("a")));
+        assert!(Password::from("a") < Password::from("b"));
+    }
 }
@@ -0,0 +1,402 @@
+//! Packed encoding of the entries in a solid entry.
+//!
+//! The contents of a packed solid entry start with [PACKED_MARKER], followed by the chunks of
+//! the entries, each written as its type, the length of its data in LEB128 and its data, without CRC.
+//! The name in a `FHED` chunk is written as the length in LEB128 of the prefix shared with the name
+//! of the previous entry, followed by the rest of the name.
+//!
+//! [PACKED_MARKER] is a zero-length chunk with a broken CRC, so that readers which do not know
+//! this encoding fail on it instead of reading the rest as chunks.
+use crate::chunk::{ChunkExt, ChunkType, RawChunk};
+use std::io::{self, Read, Write};
+
+/// Bytes at the start of the contents of a packed solid entry.
+pub(crate) const PACKED_MARKER: [u8; 12] = *b"\0\0\0\0PACK\0\0\0\0";
+
+/// Length of the fixed part of `FHED` before the name.
+const FHED_FIXED_LEN: usize = 6;
+
+fn write_varint(mut value: u64, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(mut next: impl FnMut() -> io::Result<Option<u8>>) -> io::Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = next()?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "unexpected end of varint")
+        })?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(broken("varint too long"))
+}
+
+fn broken(reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("broken packed solid entry: {reason}"),
+    )
+}
+
+enum WriteState {
+    /// Reading the length and the type of a chunk.
+    Head,
+    /// Reading the data of a chunk.
+    Data { ty: ChunkType, remaining: usize },
+    /// Skipping the CRC of a chunk.
+    Crc(usize),
+}
+
+/// Writer that converts chunks written to it into the packed encoding,
+/// or passes them through when not packed.
+pub(crate) struct PackedWriter<W> {
+    inner: W,
+    packed: bool,
+    state: WriteState,
+    head: Vec<u8>,
+    fhed: Vec<u8>,
+    name: Vec<u8>,
+}
+
+impl<W: Write> PackedWriter<W> {
+    #[inline]
+    pub(crate) fn new(mut inner: W, packed: bool) -> io::Result<Self> {
+        if packed {
+            inner.write_all(&PACKED_MARKER)?;
+        }
+        Ok(Self {
+            inner,
+            packed,
+            state: WriteState::Head,
+            head: Vec::with_capacity(8),
+            fhed: Vec::new(),
+            name: Vec::new(),
+        })
+    }
+
+    #[inline]
+    pub(crate) const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    #[inline]
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn start_chunk(&mut self, ty: ChunkType, length: usize) -> io::Result<()> {
+        if ty != ChunkType::FHED {
+            let mut head = ty.0.to_vec();
+            write_varint(length as u64, &mut head);
+            self.inner.write_all(&head)?;
+        }
+        self.state = WriteState::Data {
+            ty,
+            remaining: length,
+        };
+        self.end_data_if_done()
+    }
+
+    fn end_data_if_done(&mut self) -> io::Result<()> {
+        let WriteState::Data { ty, remaining: 0 } = self.state else {
+            return Ok(());
+        };
+        if ty == ChunkType::FHED {
+            self.write_fhed()?;
+        }
+        self.state = WriteState::Crc(4);
+        Ok(())
+    }
+
+    fn write_fhed(&mut self) -> io::Result<()> {
+        let fhed = std::mem::take(&mut self.fhed);
+        let Some((fixed, name)) = fhed.split_at_checked(FHED_FIXED_LEN) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "entry header too short",
+            ));
+        };
+        let shared = self
+            .name
+            .iter()
+            .zip(name)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut data = fixed.to_vec();
+        write_varint(shared as u64, &mut data);
+        data.extend_from_slice(&name[shared..]);
+
+        let mut head = ChunkType::FHED.0.to_vec();
+        write_varint(data.len() as u64, &mut head);
+        self.inner.write_all(&head)?;
+        self.inner.write_all(&data)?;
+        self.name = name.to_vec();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for PackedWriter<W> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.packed {
+            return self.inner.write(buf);
+        }
+        let mut rest = buf;
+        while !rest.is_empty() {
+            match &mut self.state {
+                WriteState::Head => {
+                    let n = (8 - self.head.len()).min(rest.len());
+                    self.head.extend_from_slice(&rest[..n]);
+                    rest = &rest[n..];
+                    if self.head.len() == 8 {
+                        let length = u32::from_be_bytes(self.head[..4].try_into().unwrap());
+                        let ty = ChunkType(self.head[4..].try_into().unwrap());
+                        self.head.clear();
+                        self.start_chunk(ty, length as usize)?;
+                    }
+                }
+                WriteState::Data { ty, remaining } => {
+                    let n = (*remaining).min(rest.len());
+                    if *ty == ChunkType::FHED {
+                        self.fhed.extend_from_slice(&rest[..n]);
+                    } else {
+                        self.inner.write_all(&rest[..n])?;
+                    }
+                    *remaining -= n;
+                    rest = &rest[n..];
+                    self.end_data_if_done()?;
+                }
+                WriteState::Crc(remaining) => {
+                    let n = (*remaining).min(rest.len());
+                    *remaining -= n;
+                    rest = &rest[n..];
+                    if *remaining == 0 {
+                        self.state = WriteState::Head;
+                    }
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reader that converts the packed encoding read from the inner reader back into chunks,
+/// or passes them through when not packed.
+pub(crate) struct PackedReader<R> {
+    inner: R,
+    packed: bool,
+    started: bool,
+    buf: Vec<u8>,
+    pos: usize,
+    name: Vec<u8>,
+}
+
+impl<R: Read> PackedReader<R> {
+    #[inline]
+    pub(crate) const fn new(inner: R, packed: bool) -> Self {
+        Self {
+            inner,
+            packed,
+            started: false,
+            buf: Vec::new(),
+            pos: 0,
+            name: Vec::new(),
+        }
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8];
+        loop {
+            return match self.inner.read(&mut byte) {
+                Ok(0) => Ok(None),
+                Ok(_) => Ok(Some(byte[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => Err(e),
+            };
+        }
+    }
+
+    /// Reads the next chunk into the buffer, returns `false` at the end of the contents.
+    fn fill_chunk(&mut self) -> io::Result<bool> {
+        if !self.started {
+            let mut marker = [0u8; PACKED_MARKER.len()];
+            self.inner.read_exact(&mut marker)?;
+            if marker != PACKED_MARKER {
+                return Err(broken("marker not found"));
+            }
+            self.started = true;
+        }
+        let Some(first) = self.read_byte()? else {
+            return Ok(false);
+        };
+        let mut ty = [first, 0, 0, 0];
+        self.inner.read_exact(&mut ty[1..])?;
+        let ty = ChunkType(ty);
+        let length = read_varint(|| self.read_byte())?;
+        let length = u32::try_from(length).map_err(|_| broken("chunk too large"))?;
+        let mut data = Vec::new();
+        (&mut self.inner)
+            .take(length.into())
+            .read_to_end(&mut data)?;
+        if data.len() != length as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "unexpected end of chunk data",
+            ));
+        }
+        if ty == ChunkType::FHED {
+            data = self.unpack_fhed(&data)?;
+        }
+        self.buf.clear();
+        self.pos = 0;
+        RawChunk::from_data(ty, data).write_chunk_in(&mut self.buf)?;
+        Ok(true)
+    }
+
+    fn unpack_fhed(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let (fixed, mut rest) = data
+            .split_at_checked(FHED_FIXED_LEN)
+            .ok_or_else(|| broken("entry header too short"))?;
+        let shared = read_varint(|| {
+            let Some((first, tail)) = rest.split_first() else {
+                return Ok(None);
+            };
+            rest = tail;
+            Ok(Some(*first))
+        })?;
+        let shared = self
+            .name
+            .get(..shared as usize)
+            .ok_or_else(|| broken("shared name prefix too long"))?;
+        let mut name = shared.to_vec();
+        name.extend_from_slice(rest);
+        let mut data = fixed.to_vec();
+        data.extend_from_slice(&name);
+        self.name = name;
+        Ok(data)
+    }
+}
+
+impl<R: Read> Read for PackedReader<R> {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.packed {
+            return self.inner.read(buf);
+        }
+        if self.pos == self.buf.len() && !self.fill_chunk()? {
+            return Ok(0);
+        }
+        let n = (&self.buf[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{chunk::ChunkReader, entry::EntryHeader};
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    fn chunks() -> Vec<RawChunk> {
+        let mut chunks = Vec::new();
+        for name in ["dir/a.txt", "dir/ab.txt", "other/b.txt", "dir"] {
+            let header = EntryHeader::for_dir(name.into());
+            chunks.push(RawChunk::from_data(ChunkType::FHED, header.to_bytes()));
+            chunks.push(RawChunk::from_data(ChunkType::FDAT, vec![7; 300]));
+            chunks.push(RawChunk::from_data(ChunkType::FEND, []));
+        }
+        chunks
+    }
+
+    fn chunk_bytes(chunks: &[RawChunk]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for chunk in chunks {
+            chunk.write_chunk_in(&mut bytes).unwrap();
+        }
+        bytes
+    }
+
+    fn pack(bytes: &[u8], write_size: usize) -> Vec<u8> {
+        let mut writer = PackedWriter::new(Vec::new(), true).unwrap();
+        for part in bytes.chunks(write_size) {
+            writer.write_all(part).unwrap();
+        }
+        writer.into_inner()
+    }
+
+    fn unpack(packed: &[u8]) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        PackedReader::new(packed, true).read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    #[test]
+    fn round_trip() {
+        let bytes = chunk_bytes(&chunks());
+        for write_size in [1, 3, 8, 13, bytes.len()] {
+            let packed = pack(&bytes, write_size);
+            assert!(packed.len() < bytes.len());
+            assert_eq!(unpack(&packed).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let packed = pack(&[], 1);
+        assert_eq!(packed, PACKED_MARKER);
+        assert!(unpack(&packed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn not_packed() {
+        let bytes = chunk_bytes(&chunks());
+        let mut writer = PackedWriter::new(Vec::new(), false).unwrap();
+        writer.write_all(&bytes).unwrap();
+        assert_eq!(writer.into_inner(), bytes);
+        let mut read = Vec::new();
+        PackedReader::new(&bytes[..], false)
+            .read_to_end(&mut read)
+            .unwrap();
+        assert_eq!(read, bytes);
+    }
+
+    #[test]
+    fn broken_packed_data() {
+        let packed = pack(&chunk_bytes(&chunks()), 1);
+        assert!(unpack(&packed[1..]).is_err());
+        assert!(unpack(&packed[..packed.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn marker_is_broken_chunk() {
+        let err = ChunkReader::from(&PACKED_MARKER[..])
+            .read_chunk()
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn varint() {
+        for value in [0, 1, 0x7f, 0x80, 0x3fff, 0x4000, u32::MAX as u64, u64::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(value, &mut bytes);
+            let mut iter = bytes.into_iter();
+            assert_eq!(read_varint(|| Ok(iter.next())).unwrap(), value);
+            assert!(iter.next().is_none());
+        }
+    }
+}
@@ -1,13 +1,19 @@
 use crate::{
-    cipher::{Ctr128BEReader, DecryptCbcAes256Reader, DecryptCbcCamellia256Reader, DecryptReader},
+    cipher::{
+        unwrap_key, Ctr128BEReader, DecryptCbcAes256Reader, DecryptCbcCamellia256Reader,
+        DecryptReader, WrappedKey,
+    },
     compress::DecompressReader,
+    entry::ReadOption,
     hash::verify_password,
     CipherMode, Compression, Encryption,
 };
 use aes::Aes256;
 use camellia::Camellia256;
 use crypto_common::BlockSizeUser;
-use std::io::{self, Read};
+use std::io::{self, BufRead, BufReader, Read};
+use zeroize::Zeroizing;
+use zstd::zstd_safe;
 
 /// Decrypt reader according to an encryption type.
 pub(crate) fn decrypt_reader<R: Read>(
@@ -15,24 +21,34 @@ pub(crate) fn decrypt_reader<R: Read>(
     encryption: Encryption,
     cipher_mode: CipherMode,
     phsf: Option<&str>,
-    password: Option<&[u8]>,
+    wrapped_keys: &[WrappedKey],
+    option: impl ReadOption,
 ) -> io::Result<DecryptReader<R>> {
     Ok(match encryption {
         Encryption::No => DecryptReader::No(reader),
         encryption @ (Encryption::Aes | Encryption::Camellia) => {
-            let s = phsf.ok_or_else(|| {
-                io::Error::new(io::ErrorKind::InvalidData, "`PHSF` chunk not found")
-            })?;
-            let phsf = verify_password(
-                s,
-                password.ok_or_else(|| {
+            let key = if wrapped_keys.is_empty() {
+                let s = phsf.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "`PHSF` chunk not found")
+                })?;
+                let password = option.password().ok_or_else(|| {
                     io::Error::new(io::ErrorKind::InvalidInput, "Password was not provided")
-                })?,
-            )?;
-            let hash = phsf
-                .hash
-                .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "Failed to get hash"))?;
-            let key = hash.as_bytes();
+                })?;
+                let derive = || {
+                    let phsf = verify_password(s, password.as_bytes())?;
+                    let hash = phsf.hash.ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::Unsupported, "Failed to get hash")
+                    })?;
+                    Ok(Zeroizing::new(hash.as_bytes().to_vec()))
+                };
+                match option.key_cache() {
+                    Some(cache) => cache.get_or_derive(s, derive)?,
+                    None => derive()?,
+                }
+            } else {
+                unwrap_key(wrapped_keys, option.identities())?
+            };
+            let key = key.as_slice();
             match (encryption, cipher_mode) {
                 (Encryption::Aes, CipherMode::CBC) => {
                     let mut iv = vec![0; Aes256::block_size()];
@@ -63,15 +79,44 @@ pub(crate) fn decrypt_reader<R: Read>(
 pub(crate) fn decompress_reader<R: Read>(
     reader: R,
     compression: Compression,
+    zstd_dictionary: Option<&[u8]>,
 ) -> io::Result<DecompressReader<R>> {
     Ok(match compression {
         Compression::No => DecompressReader::No(reader),
         Compression::Deflate => DecompressReader::Deflate(flate2::read::ZlibDecoder::new(reader)),
-        Compression::ZStandard => DecompressReader::ZStd(zstd::Decoder::new(reader)?),
+        Compression::ZStandard => {
+            let mut reader = BufReader::with_capacity(zstd_safe::DCtx::in_size(), reader);
+            check_zstd_dictionary(reader.fill_buf()?, zstd_dictionary)?;
+            DecompressReader::ZStd(zstd::Decoder::with_dictionary(
+                reader,
+                zstd_dictionary.unwrap_or_default(),
+            )?)
+        }
         Compression::XZ => DecompressReader::Xz(liblzma::read::XzDecoder::new(reader)),
     })
 }
 
+/// Checks that the zstd `frame` can be decompressed with `dictionary`,
+/// so that a missing or wrong dictionary is reported instead of a corrupted data error.
+///
+/// Frames compressed with a dictionary without an identifier cannot be checked.
+fn check_zstd_dictionary(frame: &[u8], dictionary: Option<&[u8]>) -> io::Result<()> {
+    let Some(expected) = zstd_safe::get_dict_id_from_frame(frame) else {
+        return Ok(());
+    };
+    match dictionary.map(zstd_safe::get_dict_id_from_dict) {
+        Some(Some(id)) if id == expected => Ok(()),
+        Some(_) => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Entry was compressed with the zstd dictionary {expected}, but a different dictionary was given"),
+        )),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Entry was compressed with the zstd dictionary {expected}, but no dictionary was given"),
+        )),
+    }
+}
+
 pub(crate) struct EntryReader<R: Read>(pub(crate) DecompressReader<DecryptReader<R>>);
 
 impl<R: Read> Read for EntryReader<R> {
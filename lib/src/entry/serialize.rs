@@ -0,0 +1,192 @@
+//! [Serialize] implementations for the metadata of entries, enabled by the `serde` feature.
+//!
+//! The schema is stable:
+//! - timestamps are objects of `seconds` and `nanoseconds` since the unix epoch,
+//! - values of extended attributes are standard base64 strings,
+//! - enums are lowercase strings such as `"file"` or `"zstandard"`.
+use super::{
+    CipherMode, Compression, DataKind, Encryption, EntryHeader, ExtendedAttribute, Metadata,
+    Permission,
+};
+use base64::Engine;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::time::Duration;
+
+/// Duration since the unix epoch, serialized with nanosecond precision.
+struct Timestamp(Duration);
+
+impl Serialize for Timestamp {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Timestamp", 2)?;
+        s.serialize_field("seconds", &self.0.as_secs())?;
+        s.serialize_field("nanoseconds", &self.0.subsec_nanos())?;
+        s.end()
+    }
+}
+
+impl Serialize for Metadata {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Metadata", 6)?;
+        s.serialize_field("raw_file_size", &self.raw_file_size)?;
+        s.serialize_field("compressed_size", &self.compressed_size)?;
+        s.serialize_field("created", &self.created.map(Timestamp))?;
+        s.serialize_field("modified", &self.modified.map(Timestamp))?;
+        s.serialize_field("accessed", &self.accessed.map(Timestamp))?;
+        s.serialize_field("permission", &self.permission)?;
+        s.end()
+    }
+}
+
+impl Serialize for Permission {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Permission", 5)?;
+        s.serialize_field("uid", &self.uid())?;
+        s.serialize_field("uname", self.uname())?;
+        s.serialize_field("gid", &self.gid())?;
+        s.serialize_field("gname", self.gname())?;
+        s.serialize_field("mode", &self.permissions())?;
+        s.end()
+    }
+}
+
+impl Serialize for ExtendedAttribute {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("ExtendedAttribute", 3)?;
+        s.serialize_field("name", self.name())?;
+        s.serialize_field("size", &self.value().len())?;
+        s.serialize_field(
+            "value",
+            &base64::engine::general_purpose::STANDARD.encode(self.value()),
+        )?;
+        s.end()
+    }
+}
+
+impl Serialize for EntryHeader {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("EntryHeader", 5)?;
+        s.serialize_field("path", self.path().as_str())?;
+        s.serialize_field("data_kind", &self.data_kind())?;
+        s.serialize_field("compression", &self.compression())?;
+        s.serialize_field("encryption", &self.encryption())?;
+        s.serialize_field("cipher_mode", &self.cipher_mode())?;
+        s.end()
+    }
+}
+
+impl Serialize for DataKind {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match self {
+            Self::File => "file",
+            Self::Directory => "directory",
+            Self::SymbolicLink => "symbolic_link",
+            Self::HardLink => "hard_link",
+        };
+        serializer.serialize_unit_variant("DataKind", *self as u32, name)
+    }
+}
+
+impl Serialize for Compression {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match self {
+            Self::No => "no",
+            Self::Deflate => "deflate",
+            Self::ZStandard => "zstandard",
+            Self::XZ => "xz",
+        };
+        serializer.serialize_unit_variant("Compression", *self as u32, name)
+    }
+}
+
+impl Serialize for Encryption {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match self {
+            Self::No => "no",
+            Self::Aes => "aes",
+            Self::Camellia => "camellia",
+        };
+        serializer.serialize_unit_variant("Encryption", *self as u32, name)
+    }
+}
+
+impl Serialize for CipherMode {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = match self {
+            Self::CBC => "cbc",
+            Self::CTR => "ctr",
+        };
+        serializer.serialize_unit_variant("CipherMode", *self as u32, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EntryBuilder, WriteOptions};
+    use std::io::Write;
+    #[cfg(all(target_family = "wasm", target_os = "unknown"))]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    #[test]
+    fn serialize_metadata() {
+        let metadata = Metadata::new()
+            .with_modified(Some(Duration::new(1_700_000_000, 123_456_789)))
+            .with_permission(Some(Permission::new(
+                1000,
+                "user".into(),
+                100,
+                "group".into(),
+                0o644,
+            )));
+        let value = serde_json::to_value(&metadata).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "raw_file_size": 0,
+                "compressed_size": 0,
+                "created": null,
+                "modified": {"seconds": 1_700_000_000, "nanoseconds": 123_456_789},
+                "accessed": null,
+                "permission": {"uid": 1000, "uname": "user", "gid": 100, "gname": "group", "mode": 0o644},
+            })
+        );
+    }
+
+    #[test]
+    fn serialize_binary_xattr_as_base64() {
+        let xattr = ExtendedAttribute::new("user.binary".into(), vec![0x00, 0xff, 0x10, 0x80]);
+        let value = serde_json::to_value(&xattr).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({"name": "user.binary", "size": 4, "value": "AP8QgA=="})
+        );
+    }
+
+    #[test]
+    fn serialize_header() {
+        let mut builder =
+            EntryBuilder::new_file("dir/file.txt".into(), WriteOptions::store()).unwrap();
+        builder.write_all(b"text").unwrap();
+        let entry = builder.build().unwrap();
+        let value = serde_json::to_value(entry.header()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "path": "dir/file.txt",
+                "data_kind": "file",
+                "compression": "no",
+                "encryption": "no",
+                "cipher_mode": "ctr",
+            })
+        );
+    }
+}
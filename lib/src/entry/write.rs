@@ -1,7 +1,10 @@
 use crate::{
-    cipher::{CipherWriter, Ctr128BEWriter, EncryptCbcAes256Writer, EncryptCbcCamellia256Writer},
+    cipher::{
+        wrap_key, CipherWriter, Ctr128BEWriter, EncryptCbcAes256Writer,
+        EncryptCbcCamellia256Writer, WrappedKey,
+    },
     compress::CompressionWriter,
-    entry::{CipherMode, Compress, HashAlgorithmParams, WriteOption},
+    entry::{CipherKey, CipherMode, Compress, HashAlgorithmParams, WriteOption},
     hash, random, Cipher, CipherAlgorithm, HashAlgorithm,
 };
 use aes::Aes256;
@@ -9,14 +12,19 @@ use camellia::Camellia256;
 use crypto_common::{BlockSizeUser, KeySizeUser};
 use flate2::write::ZlibEncoder;
 use liblzma::write::XzEncoder;
-use password_hash::{Output, SaltString};
-use std::io::{self, Write};
+use password_hash::SaltString;
+use std::{
+    io::{self, Write},
+    sync::Arc,
+};
+use zeroize::Zeroizing;
 use zstd::stream::write::Encoder as ZstdEncoder;
 
 pub(crate) struct CipherContext {
-    pub(crate) phsf: String,
+    pub(crate) phsf: Option<String>,
+    pub(crate) wrapped_keys: Vec<WrappedKey>,
     pub(crate) iv: Vec<u8>,
-    pub(crate) key: Output,
+    pub(crate) key: Zeroizing<Vec<u8>>,
     pub(crate) mode: CipherMode,
 }
 
@@ -28,17 +36,40 @@ pub(crate) struct WriteCipher {
 pub(crate) struct EntryWriterContext {
     pub(crate) compress: Compress,
     pub(crate) cipher: Option<WriteCipher>,
+    pub(crate) zstd_dictionary: Option<Arc<[u8]>>,
 }
 
 #[inline]
 fn to_hashed(cipher: &Cipher) -> io::Result<WriteCipher> {
-    let salt = random::salt_string();
-    let (key, phsf) = hash(
-        cipher.cipher_algorithm,
-        cipher.hash_algorithm,
-        cipher.password.as_bytes(),
-        &salt,
-    )?;
+    let (key, phsf, wrapped_keys) = match &cipher.key {
+        CipherKey::Password(password) => {
+            let derive = || {
+                let salt = random::salt_string();
+                hash(
+                    cipher.cipher_algorithm,
+                    cipher.hash_algorithm,
+                    password.as_bytes(),
+                    &salt,
+                )
+            };
+            let (key, phsf) = match &cipher.session {
+                Some(session) => session.get_or_derive(derive)?,
+                None => derive()?,
+            };
+            (key, Some(phsf), Vec::new())
+        }
+        CipherKey::Recipients(recipients) => {
+            let key = Zeroizing::new(match cipher.cipher_algorithm {
+                CipherAlgorithm::Aes => random::random_vec(Aes256::key_size()),
+                CipherAlgorithm::Camellia => random::random_vec(Camellia256::key_size()),
+            }?);
+            let wrapped_keys = recipients
+                .iter()
+                .map(|it| wrap_key(&key, it))
+                .collect::<io::Result<Vec<_>>>()?;
+            (key, None, wrapped_keys)
+        }
+    };
     let iv = match cipher.cipher_algorithm {
         CipherAlgorithm::Aes => random::random_vec(Aes256::block_size()),
         CipherAlgorithm::Camellia => random::random_vec(Camellia256::block_size()),
@@ -47,6 +78,7 @@ fn to_hashed(cipher: &Cipher) -> io::Result<WriteCipher> {
         algorithm: cipher.cipher_algorithm,
         context: CipherContext {
             phsf,
+            wrapped_keys,
             iv,
             key,
             mode: cipher.mode,
@@ -60,6 +92,7 @@ pub(crate) fn get_writer_context(option: impl WriteOption) -> io::Result<EntryWr
     Ok(EntryWriterContext {
         compress: option.compress(),
         cipher,
+        zstd_dictionary: option.zstd_dictionary().cloned(),
     })
 }
 
@@ -69,7 +102,7 @@ fn hash<'s, 'p: 's>(
     hash_algorithm: HashAlgorithm,
     password: &'p [u8],
     salt: &'s SaltString,
-) -> io::Result<(Output, String)> {
+) -> io::Result<(Zeroizing<Vec<u8>>, String)> {
     let mut password_hash = match (hash_algorithm.0, cipher_algorithm) {
         (
             HashAlgorithmParams::Argon2Id {
@@ -118,7 +151,10 @@ fn hash<'s, 'p: 's>(
         .hash
         .take()
         .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "Failed to get hash"))?;
-    Ok((hash, password_hash.to_string()))
+    Ok((
+        Zeroizing::new(hash.as_bytes().to_vec()),
+        password_hash.to_string(),
+    ))
 }
 
 #[inline]
@@ -137,7 +173,7 @@ fn encryption_writer<W: Write>(
                     mode: CipherMode::CBC,
                     ..
                 },
-        }) => CipherWriter::CbcAes(EncryptCbcAes256Writer::new(writer, key.as_bytes(), iv)?),
+        }) => CipherWriter::CbcAes(EncryptCbcAes256Writer::new(writer, key, iv)?),
         Some(WriteCipher {
             algorithm: CipherAlgorithm::Aes,
             context:
@@ -147,7 +183,7 @@ fn encryption_writer<W: Write>(
                     mode: CipherMode::CTR,
                     ..
                 },
-        }) => CipherWriter::CtrAes(Ctr128BEWriter::new(writer, key.as_bytes(), iv)?),
+        }) => CipherWriter::CtrAes(Ctr128BEWriter::new(writer, key, iv)?),
         Some(WriteCipher {
             algorithm: CipherAlgorithm::Camellia,
             context:
@@ -157,11 +193,7 @@ fn encryption_writer<W: Write>(
                     mode: CipherMode::CBC,
                     ..
                 },
-        }) => CipherWriter::CbcCamellia(EncryptCbcCamellia256Writer::new(
-            writer,
-            key.as_bytes(),
-            iv,
-        )?),
+        }) => CipherWriter::CbcCamellia(EncryptCbcCamellia256Writer::new(writer, key, iv)?),
         Some(WriteCipher {
             algorithm: CipherAlgorithm::Camellia,
             context:
@@ -171,7 +203,7 @@ fn encryption_writer<W: Write>(
                     mode: CipherMode::CTR,
                     ..
                 },
-        }) => CipherWriter::CtrCamellia(Ctr128BEWriter::new(writer, key.as_bytes(), iv)?),
+        }) => CipherWriter::CtrCamellia(Ctr128BEWriter::new(writer, key, iv)?),
     })
 }
 
@@ -179,13 +211,17 @@ fn encryption_writer<W: Write>(
 fn compression_writer<W: Write>(
     writer: W,
     algorithm: Compress,
+    zstd_dictionary: Option<&[u8]>,
 ) -> io::Result<CompressionWriter<W>> {
     Ok(match algorithm {
         Compress::No => CompressionWriter::No(writer),
         Compress::Deflate(level) => {
             CompressionWriter::Deflate(ZlibEncoder::new(writer, level.into()))
         }
-        Compress::ZStandard(level) => CompressionWriter::ZStd(ZstdEncoder::new(writer, level)?),
+        Compress::ZStandard(level) => CompressionWriter::ZStd(match zstd_dictionary {
+            Some(dictionary) => ZstdEncoder::with_dictionary(writer, level, dictionary)?,
+            None => ZstdEncoder::new(writer, level)?,
+        }),
         Compress::XZ(level) => CompressionWriter::Xz(XzEncoder::new(writer, level)),
     })
 }
@@ -196,5 +232,5 @@ pub(crate) fn get_writer<W: Write>(
     context: &EntryWriterContext,
 ) -> io::Result<CompressionWriter<CipherWriter<W>>> {
     let writer = encryption_writer(writer, &context.cipher)?;
-    compression_writer(writer, context.compress)
+    compression_writer(writer, context.compress, context.zstd_dictionary.as_deref())
 }
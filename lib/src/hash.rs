@@ -1,6 +1,137 @@
 use argon2::{Argon2, ParamsBuilder, Version};
 use password_hash::{PasswordHash, PasswordHasher, SaltString};
-use std::io;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{self, Debug, Formatter},
+    hash::{Hash, Hasher},
+    io,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+use zeroize::Zeroizing;
+
+/// Maximum number of the keys held by a [`KeyCache`] on the read side.
+/// The cache is cleared when it is full, so that reading entries encrypted with distinct salts
+/// does not hold a key per entry.
+const READ_KEY_CACHE_CAPACITY: usize = 64;
+
+/// Keys derived from passwords, shared by the clones of the options holding it.
+///
+/// The cache does not take part in the comparison and the hash of the options,
+/// because it only saves recomputing the keys.
+#[derive(Default)]
+pub struct KeyCache<T>(Arc<Mutex<T>>);
+
+impl<T> KeyCache<T> {
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, T> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Keys derived for reading, keyed by the PHC string of the derivation,
+/// that is, the algorithm, the parameters and the salt.
+pub(crate) type ReadKeyCache = KeyCache<HashMap<String, Zeroizing<Vec<u8>>>>;
+
+/// The key and the PHC string derived once for all the entries written with the same options.
+pub(crate) type WriteKeyCache = KeyCache<Option<(Zeroizing<Vec<u8>>, String)>>;
+
+impl ReadKeyCache {
+    /// Returns the key derived for `phsf`, deriving it with `derive` when not cached.
+    pub(crate) fn get_or_derive(
+        &self,
+        phsf: &str,
+        derive: impl FnOnce() -> io::Result<Zeroizing<Vec<u8>>>,
+    ) -> io::Result<Zeroizing<Vec<u8>>> {
+        if let Some(key) = self.lock().get(phsf) {
+            return Ok(key.clone());
+        }
+        // NOTE: The key is derived without holding the lock, so that the entries
+        //       encrypted with distinct salts can be read in parallel.
+        let key = derive()?;
+        let mut keys = self.lock();
+        if keys.len() >= READ_KEY_CACHE_CAPACITY {
+            keys.clear();
+        }
+        keys.insert(phsf.into(), key.clone());
+        Ok(key)
+    }
+}
+
+impl WriteKeyCache {
+    /// Returns the key and the PHC string derived first, deriving them with `derive` when not yet derived.
+    pub(crate) fn get_or_derive(
+        &self,
+        derive: impl FnOnce() -> io::Result<(Zeroizing<Vec<u8>>, String)>,
+    ) -> io::Result<(Zeroizing<Vec<u8>>, String)> {
+        let mut derived = self.lock();
+        if let Some(derived) = &*derived {
+            return Ok(derived.clone());
+        }
+        let (key, phsf) = derive()?;
+        *derived = Some((key.clone(), phsf.clone()));
+        Ok((key, phsf))
+    }
+}
+
+impl<T> Clone for KeyCache<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> PartialEq for KeyCache<T> {
+    #[inline]
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for KeyCache<T> {}
+
+impl<T> PartialOrd for KeyCache<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for KeyCache<T> {
+    #[inline]
+    fn cmp(&self, _: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl<T> Hash for KeyCache<T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl<T> Debug for KeyCache<T> {
+    #[inline]
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("KeyCache")
+    }
+}
+
+#[cfg(test)]
+thread_local! {
+    static DERIVATIONS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Returns the number of the keys derived from passwords on the current thread.
+#[cfg(test)]
+pub(crate) fn derivations() -> usize {
+    DERIVATIONS.get()
+}
+
+#[inline]
+fn count_derivation() {
+    #[cfg(test)]
+    DERIVATIONS.set(DERIVATIONS.get() + 1);
+}
 
 pub(crate) fn argon2_with_salt<'a>(
     password: &'a [u8],
@@ -11,6 +142,7 @@ pub(crate) fn argon2_with_salt<'a>(
     hash_length: usize,
     salt: &'a SaltString,
 ) -> io::Result<PasswordHash<'a>> {
+    count_derivation();
     let mut builder = ParamsBuilder::default();
     if let Some(time_cost) = time_cost {
         builder.t_cost(time_cost);
@@ -36,6 +168,7 @@ pub(crate) fn pbkdf2_with_salt<'a>(
     params: pbkdf2::Params,
     salt: &'a SaltString,
 ) -> io::Result<PasswordHash<'a>> {
+    count_derivation();
     pbkdf2::Pbkdf2
         .hash_password_customized(password, Some(algorithm.ident()), None, params, salt)
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
@@ -45,6 +178,7 @@ pub(crate) fn verify_password<'a>(
     phsf: &'a str,
     password: &'a [u8],
 ) -> io::Result<PasswordHash<'a>> {
+    count_derivation();
     let password_hash =
         PasswordHash::new(phsf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
     match password_hash.algorithm {
@@ -1,7 +1,7 @@
 mod finish;
 
 pub(crate) use self::finish::TryIntoInner;
-use std::io;
+use std::{borrow::Cow, io};
 
 pub(crate) struct FlattenWriter<const N: usize> {
     pub(crate) inner: Vec<Vec<u8>>,
@@ -29,32 +29,52 @@ impl<const N: usize> io::Write for FlattenWriter<N> {
     }
 }
 
+/// Reader of the concatenation of the chunks, which are borrowed or owned.
 pub(crate) struct FlattenReader<'r> {
     index: usize,
-    inner: Vec<&'r [u8]>,
+    offset: usize,
+    inner: Vec<Cow<'r, [u8]>>,
 }
 
 impl<'r> FlattenReader<'r> {
     #[inline]
-    pub(crate) const fn new(inner: Vec<&'r [u8]>) -> Self {
-        Self { index: 0, inner }
+    pub(crate) fn new(inner: Vec<&'r [u8]>) -> Self {
+        Self::from_cow(inner.into_iter().map(Cow::Borrowed).collect())
+    }
+
+    #[inline]
+    const fn from_cow(inner: Vec<Cow<'r, [u8]>>) -> Self {
+        Self {
+            index: 0,
+            offset: 0,
+            inner,
+        }
+    }
+}
+
+impl FlattenReader<'static> {
+    #[inline]
+    pub(crate) fn from_owned(inner: Vec<Vec<u8>>) -> Self {
+        Self::from_cow(inner.into_iter().map(Cow::Owned).collect())
     }
 }
 
 impl io::Read for FlattenReader<'_> {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        if let Some(c) = self.inner.get_mut(self.index) {
-            let s = c.read(buf);
-            if let Ok(0) = s {
+        while let Some(c) = self.inner.get(self.index) {
+            let rest = &c[self.offset..];
+            if rest.is_empty() {
                 self.index += 1;
-                self.read(buf)
-            } else {
-                s
+                self.offset = 0;
+                continue;
             }
-        } else {
-            Ok(0)
+            let n = rest.len().min(buf.len());
+            buf[..n].copy_from_slice(&rest[..n]);
+            self.offset += n;
+            return Ok(n);
         }
+        Ok(0)
     }
 }
 
@@ -80,4 +100,10 @@ mod tests {
         let reader = FlattenReader::new(vec![b"abc", b"", b"def"]);
         assert_eq!("abcdef", io::read_to_string(reader).unwrap());
     }
+
+    #[test]
+    fn flat_owned() {
+        let reader = FlattenReader::from_owned(vec![b"abc".to_vec(), Vec::new(), b"def".to_vec()]);
+        assert_eq!("abcdef", io::read_to_string(reader).unwrap());
+    }
 }
@@ -25,8 +25,13 @@ pub(crate) mod util;
 
 pub use archive::*;
 pub use chunk::*;
+pub use cipher::{Identity, KeyParseError, Recipient};
+pub use compress::train_zstd_dictionary;
 pub use entry::*;
 
+/// Version of this crate.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -3,8 +3,8 @@ use std::io::{self, Read, Write};
 
 /// Copy the entries to another archive
 fn copy_entries<R: Read, W: Write>(r: R, w: W) -> io::Result<()> {
-    let mut writer = Archive::write_header(w)?;
     let mut reader = Archive::read_header(r)?;
+    let mut writer = Archive::write_header_with_id(w, reader.archive_id())?;
     for entry in reader.entries() {
         writer.add_entry(entry?)?;
     }
@@ -0,0 +1,162 @@
+use libpna::{Archive, EntryBuilder, ReadEntry, ReadOptions, SolidEntryBuilder, WriteOptions};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    io::{self, Read, Write},
+};
+
+/// Allocator that counts the bytes allocated by the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.with(|it| it.set(it.get() + layout.size()));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.with(|it| it.set(it.get() + new_size.saturating_sub(layout.size())));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const ENTRY_SIZE: usize = 64 * 1024 * 1024;
+
+fn allocated_by<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.with(Cell::get);
+    let value = f();
+    (value, ALLOCATED.with(Cell::get) - before)
+}
+
+fn large_archive() -> Vec<u8> {
+    let mut builder = EntryBuilder::new_file("large.bin".into(), WriteOptions::store()).unwrap();
+    let data = vec![0x55; 1024 * 1024];
+    for _ in 0..ENTRY_SIZE / data.len() {
+        builder.write_all(&data).unwrap();
+    }
+    let mut archive = Archive::write_header(Vec::new()).unwrap();
+    archive.add_entry(builder.build().unwrap()).unwrap();
+    archive.finalize().unwrap()
+}
+
+#[test]
+fn read_large_entry_copies_data_once() {
+    let bytes = large_archive();
+
+    let (stored_size, allocated) = allocated_by(|| {
+        let mut archive = Archive::read_header(bytes.as_slice()).unwrap();
+        let entry = archive.entries_skip_solid().next().unwrap().unwrap();
+        io::copy(
+            &mut entry.reader(ReadOptions::builder().build()).unwrap(),
+            &mut io::sink(),
+        )
+        .unwrap();
+        entry.stored_size()
+    });
+    assert_eq!(stored_size, ENTRY_SIZE);
+    // Chunk data is copied out of the reader once and read in place after that.
+    assert!(allocated >= ENTRY_SIZE);
+    assert!(allocated < ENTRY_SIZE + ENTRY_SIZE / 8, "{allocated}");
+}
+
+#[test]
+fn read_large_entry_from_slice_without_copy() {
+    let bytes = large_archive();
+
+    let (estimated, allocated) = allocated_by(|| {
+        let mut archive = Archive::read_header_from_slice(&bytes).unwrap();
+        let entry = archive.entries_slice().next().unwrap().unwrap();
+        let ReadEntry::Normal(normal) = &entry else {
+            unreachable!()
+        };
+        io::copy(
+            &mut normal.reader(ReadOptions::builder().build()).unwrap(),
+            &mut io::sink(),
+        )
+        .unwrap();
+        entry.estimated_peak_memory()
+    });
+    assert_eq!(estimated, ENTRY_SIZE);
+    // Chunk data borrows the slice.
+    assert!(allocated < ENTRY_SIZE / 8, "{allocated}");
+}
+
+#[test]
+fn read_last_entry_of_solid_without_keeping_preceding_data() {
+    const ENTRY_COUNT: usize = 4;
+    let data = vec![0x55; ENTRY_SIZE / 4];
+    let mut solid = SolidEntryBuilder::new(WriteOptions::store()).unwrap();
+    for i in 0..ENTRY_COUNT {
+        let mut builder =
+            EntryBuilder::new_file(format!("{i}.bin").as_str().into(), WriteOptions::store())
+                .unwrap();
+        for _ in 0..4 {
+            builder.write_all(&data).unwrap();
+        }
+        solid.add_entry(builder.build().unwrap()).unwrap();
+    }
+    let mut archive = Archive::write_header(Vec::new()).unwrap();
+    archive.add_entry(solid.build().unwrap()).unwrap();
+    let bytes = archive.finalize().unwrap();
+    drop(data);
+
+    let (read, allocated) = allocated_by(|| {
+        let mut archive = Archive::read_header_from_slice(&bytes).unwrap();
+        let Some(Ok(ReadEntry::Solid(solid))) = archive.entries_slice().next() else {
+            unreachable!()
+        };
+        let name = format!("{}.bin", ENTRY_COUNT - 1);
+        let mut reader = solid.reader_for(&name, None).unwrap().unwrap();
+        io::copy(&mut reader, &mut io::sink()).unwrap()
+    });
+    assert_eq!(read, ENTRY_SIZE as u64);
+    // Only the data of the found entry is copied out of the solid entry.
+    assert!(allocated >= ENTRY_SIZE);
+    assert!(allocated < ENTRY_SIZE + ENTRY_SIZE / 8, "{allocated}");
+}
+
+/// Reader that produces `remaining` bytes without holding them in memory.
+struct SyntheticReader {
+    remaining: usize,
+}
+
+impl Read for SyntheticReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.remaining);
+        buf[..len].fill(0x55);
+        self.remaining -= len;
+        Ok(len)
+    }
+}
+
+#[test]
+fn write_large_entry_from_reader_without_buffering() {
+    const STREAM_SIZE: usize = 256 * 1024 * 1024;
+    let mut archive = Archive::write_header(io::sink()).unwrap();
+
+    let (written, allocated) = allocated_by(|| {
+        archive
+            .add_entry_from_reader(
+                "large.bin".into(),
+                SyntheticReader {
+                    remaining: STREAM_SIZE,
+                },
+                WriteOptions::store(),
+            )
+            .unwrap()
+    });
+    assert!(written > STREAM_SIZE);
+    // Only a chunk of the data is buffered at once.
+    assert!(allocated < STREAM_SIZE / 64, "{allocated}");
+}
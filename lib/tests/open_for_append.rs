@@ -0,0 +1,59 @@
+use libpna::{Archive, EntryBuilder, NormalEntry, ReadOptions, WriteOptions};
+use std::{
+    fs,
+    io::{self, Read, Write},
+};
+
+fn text_entry(name: &str) -> NormalEntry {
+    let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store()).unwrap();
+    builder.write_all(name.as_bytes()).unwrap();
+    builder.build().unwrap()
+}
+
+#[test]
+fn open_for_append() {
+    let path = format!("{}/open_for_append.pna", env!("CARGO_TARGET_TMPDIR"));
+    let mut writer = Archive::write_header(fs::File::create(&path).unwrap()).unwrap();
+    writer.add_entry(text_entry("text1.txt")).unwrap();
+    writer.finalize().unwrap();
+
+    let mut appender = Archive::open_for_append(&path).unwrap();
+    appender.add_entry(text_entry("text2.txt")).unwrap();
+    appender.finalize().unwrap();
+
+    let mut reader = Archive::read_header(fs::File::open(&path).unwrap()).unwrap();
+    let entries = reader
+        .entries_skip_solid()
+        .map(|entry| {
+            let entry = entry.unwrap();
+            let mut content = String::new();
+            entry
+                .reader(ReadOptions::builder().build())
+                .unwrap()
+                .read_to_string(&mut content)
+                .unwrap();
+            (entry.header().path().to_string(), content)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        entries,
+        [
+            ("text1.txt".to_string(), "text1.txt".to_string()),
+            ("text2.txt".to_string(), "text2.txt".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn open_for_append_multipart() {
+    let path = format!(
+        "{}/open_for_append_multipart.part1.pna",
+        env!("CARGO_TARGET_TMPDIR")
+    );
+    let part1 = include_bytes!("../../resources/test/multipart.part1.pna");
+    fs::write(&path, part1).unwrap();
+
+    let err = Archive::open_for_append(&path).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert_eq!(fs::read(&path).unwrap(), part1);
+}
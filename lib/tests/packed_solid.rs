@@ -0,0 +1,66 @@
+use libpna::{
+    Archive, Compression, EntryBuilder, NormalEntry, Permission, ReadEntry, SolidArchive,
+    WriteOptions,
+};
+use std::{io, time::Duration};
+
+/// Number of files in the synthetic tree, like a `node_modules` directory.
+const FILES: usize = 100_000;
+
+fn tiny_file(i: usize) -> io::Result<NormalEntry> {
+    let name = format!("node_modules/package-{}/lib/module-{i}.js", i / 100);
+    let mut builder = EntryBuilder::new_file(name.into(), WriteOptions::store())?;
+    io::Write::write_all(&mut builder, format!("module.exports = {i};\n").as_bytes())?;
+    builder
+        .modified(Duration::from_secs(1_700_000_000 + i as u64))
+        .permission(Permission::new(
+            1000,
+            "user".into(),
+            1000,
+            "group".into(),
+            0o644,
+        ));
+    builder.build()
+}
+
+fn write_archive(mut archive: SolidArchive<Vec<u8>>) -> io::Result<Vec<u8>> {
+    for i in 0..FILES {
+        archive.add_entry(tiny_file(i)?)?;
+    }
+    archive.finalize()
+}
+
+fn read_entries(archive: &[u8]) -> io::Result<Vec<NormalEntry>> {
+    let mut entries = Vec::new();
+    for entry in Archive::read_header(archive)?.entries() {
+        match entry? {
+            ReadEntry::Solid(solid) => {
+                for entry in solid.entries(None)? {
+                    entries.push(entry?);
+                }
+            }
+            ReadEntry::Normal(entry) => entries.push(entry),
+        }
+    }
+    Ok(entries)
+}
+
+#[test]
+fn packed_solid_tiny_files() {
+    let option = WriteOptions::builder()
+        .compression(Compression::ZStandard)
+        .build();
+    let solid = write_archive(Archive::write_solid_header(Vec::new(), &option).unwrap()).unwrap();
+    let packed =
+        write_archive(Archive::write_packed_solid_header(Vec::new(), &option).unwrap()).unwrap();
+
+    assert!(
+        packed.len() * 100 < solid.len() * 95,
+        "packed: {} bytes, solid: {} bytes",
+        packed.len(),
+        solid.len()
+    );
+    let entries = read_entries(&packed).unwrap();
+    assert_eq!(entries.len(), FILES);
+    assert_eq!(entries, read_entries(&solid).unwrap());
+}
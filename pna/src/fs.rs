@@ -1,6 +1,9 @@
 //! PNA file system utilities
 //!
 //! The purpose of this module is to provide file system utilities for PNA
+mod dir;
+
+pub use dir::*;
 use std::{io, os, path::Path};
 
 /// Creates a new symbolic link on the filesystem.
@@ -0,0 +1,613 @@
+use crate::{
+    prelude::*, Archive, Compression, DataKind, Encryption, EntryBuilder, EntryName,
+    EntryReference, Metadata, NormalEntry, Permission, ReadOptions, WriteOptions,
+};
+#[cfg(target_os = "macos")]
+use std::os::macos::fs::FileTimesExt;
+#[cfg(windows)]
+use std::os::windows::fs::FileTimesExt;
+use std::{
+    fs,
+    io::{self, prelude::*},
+    path::{Component, Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Options for [create_archive_from_dir].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct CreateDirOptions {
+    keep_permission: bool,
+    keep_timestamp: bool,
+    compression: Compression,
+    password: Option<String>,
+}
+
+impl CreateDirOptions {
+    /// Creates a new [CreateDirOptions] with the default settings.
+    ///
+    /// Entries are compressed with [Compression::ZStandard], not encrypted,
+    /// and neither permissions nor timestamps are stored.
+    ///
+    /// # Examples
+    /// ```
+    /// use pna::fs::CreateDirOptions;
+    ///
+    /// let options = CreateDirOptions::new();
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            keep_permission: false,
+            keep_timestamp: false,
+            compression: Compression::ZStandard,
+            password: None,
+        }
+    }
+
+    /// Sets whether to store the permissions of the files.
+    #[inline]
+    pub const fn with_keep_permission(mut self, keep_permission: bool) -> Self {
+        self.keep_permission = keep_permission;
+        self
+    }
+
+    /// Sets whether to store the timestamps of the files.
+    #[inline]
+    pub const fn with_keep_timestamp(mut self, keep_timestamp: bool) -> Self {
+        self.keep_timestamp = keep_timestamp;
+        self
+    }
+
+    /// Sets the compression method of the files.
+    #[inline]
+    pub const fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Sets the password to encrypt the files with.
+    /// The files are not encrypted when the password is `None`.
+    #[inline]
+    pub fn with_password<T: Into<String>>(mut self, password: Option<T>) -> Self {
+        self.password = password.map(Into::into);
+        self
+    }
+
+    fn write_options(&self) -> WriteOptions {
+        let mut builder = WriteOptions::builder();
+        builder.compression(self.compression);
+        if self.password.is_some() {
+            builder.encryption(Encryption::Aes);
+        }
+        builder.password(self.password.as_deref()).build()
+    }
+}
+
+impl Default for CreateDirOptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Options for [extract_archive_to_dir].
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct ExtractDirOptions {
+    keep_permission: bool,
+    keep_timestamp: bool,
+    password: Option<String>,
+}
+
+impl ExtractDirOptions {
+    /// Creates a new [ExtractDirOptions] with the default settings.
+    ///
+    /// Neither permissions nor timestamps are restored.
+    ///
+    /// # Examples
+    /// ```
+    /// use pna::fs::ExtractDirOptions;
+    ///
+    /// let options = ExtractDirOptions::new();
+    /// ```
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            keep_permission: false,
+            keep_timestamp: false,
+            password: None,
+        }
+    }
+
+    /// Sets whether to restore the permissions of the files.
+    ///
+    /// Only the permission bits are restored, the owner of the files is not changed.
+    #[inline]
+    pub const fn with_keep_permission(mut self, keep_permission: bool) -> Self {
+        self.keep_permission = keep_permission;
+        self
+    }
+
+    /// Sets whether to restore the timestamps of the files.
+    #[inline]
+    pub const fn with_keep_timestamp(mut self, keep_timestamp: bool) -> Self {
+        self.keep_timestamp = keep_timestamp;
+        self
+    }
+
+    /// Sets the password to decrypt the files with.
+    #[inline]
+    pub fn with_password<T: Into<String>>(mut self, password: Option<T>) -> Self {
+        self.password = password.map(Into::into);
+        self
+    }
+}
+
+/// Summary of [create_archive_from_dir] and [extract_archive_to_dir].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct DirSummary {
+    entries: usize,
+    bytes: u64,
+}
+
+impl DirSummary {
+    /// Number of the entries written.
+    #[inline]
+    pub const fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Total size of the regular files written, in bytes.
+    #[inline]
+    pub const fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+/// Creates an archive at `dest` from the contents of the directory `src_dir`.
+///
+/// The directory is walked recursively and the entries are named relative to `src_dir`.
+/// The data of the files is streamed into the archive, so the files are never read into memory as a whole.
+/// Symbolic links are stored as links and are not followed.
+///
+/// # Examples
+/// ```no_run
+/// use pna::{fs, Compression};
+///
+/// # fn main() -> std::io::Result<()> {
+/// let options = fs::CreateDirOptions::new()
+///     .with_keep_timestamp(true)
+///     .with_compression(Compression::XZ);
+/// let summary = fs::create_archive_from_dir("src", "src.pna", options)?;
+/// println!("{} entries", summary.entries());
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// Returns error when failed to read `src_dir` or to write the archive.
+#[inline]
+pub fn create_archive_from_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    src_dir: P,
+    dest: Q,
+    options: CreateDirOptions,
+) -> io::Result<DirSummary> {
+    let src_dir = src_dir.as_ref();
+    let dest = dest.as_ref();
+    let file = fs::File::create(dest)?;
+    // Do not archive the archive itself when it is created in `src_dir`.
+    let dest = fs::canonicalize(dest)?;
+    let mut archive = Archive::write_header(io::BufWriter::new(file))?;
+    let mut summary = DirSummary::default();
+    let write_options = options.write_options();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(dir) = dirs.pop() {
+        let mut children = fs::read_dir(src_dir.join(&dir))?
+            .map(|it| it.map(|it| it.file_name()))
+            .collect::<io::Result<Vec<_>>>()?;
+        children.sort();
+        let mut sub_dirs = Vec::new();
+        for child in children {
+            let name = dir.join(child);
+            let path = src_dir.join(&name);
+            let meta = fs::symlink_metadata(&path)?;
+            if meta.is_file() && fs::canonicalize(&path)? == dest {
+                continue;
+            }
+            add_path(
+                &mut archive,
+                &name,
+                &path,
+                &meta,
+                &options,
+                &write_options,
+                &mut summary,
+            )?;
+            if meta.is_dir() {
+                sub_dirs.push(name);
+            }
+        }
+        // Push in reverse so the sub directories are walked in order.
+        dirs.extend(sub_dirs.into_iter().rev());
+    }
+    archive.finalize()?.flush()?;
+    Ok(summary)
+}
+
+fn add_path<W: Write>(
+    archive: &mut Archive<W>,
+    name: &Path,
+    path: &Path,
+    meta: &fs::Metadata,
+    options: &CreateDirOptions,
+    write_options: &WriteOptions,
+    summary: &mut DirSummary,
+) -> io::Result<()> {
+    let entry_name = EntryName::try_from(name).map_err(io::Error::other)?;
+    let permission = options.keep_permission.then(|| permission(meta)).flatten();
+    let timestamps = if options.keep_timestamp {
+        [meta.created(), meta.modified(), meta.accessed()].map(|it| since_unix_epoch(it.ok()))
+    } else {
+        [None; 3]
+    };
+    if meta.is_file() {
+        let mut writer = archive.entry_writer(entry_name, write_options.clone())?;
+        let [created, modified, accessed] = timestamps;
+        if let Some(created) = created {
+            writer.created(created);
+        }
+        if let Some(modified) = modified {
+            writer.modified(modified);
+        }
+        if let Some(accessed) = accessed {
+            writer.accessed(accessed);
+        }
+        if let Some(permission) = permission {
+            writer.permission(permission);
+        }
+        summary.bytes += io::copy(&mut fs::File::open(path)?, &mut writer)?;
+        writer.finish()?;
+    } else {
+        let mut builder = if meta.is_dir() {
+            EntryBuilder::new_dir(entry_name)
+        } else if meta.is_symlink() {
+            let target = fs::read_link(path)?;
+            let target = EntryReference::try_from(target.as_path()).map_err(io::Error::other)?;
+            EntryBuilder::new_symbolic_link(entry_name, target)?
+        } else {
+            // Sockets, fifos and devices have nothing to archive.
+            return Ok(());
+        };
+        let [created, modified, accessed] = timestamps;
+        if let Some(created) = created {
+            builder.created(created);
+        }
+        if let Some(modified) = modified {
+            builder.modified(modified);
+        }
+        if let Some(accessed) = accessed {
+            builder.accessed(accessed);
+        }
+        if let Some(permission) = permission {
+            builder.permission(permission);
+        }
+        archive.add_entry(builder.build()?)?;
+    }
+    summary.entries += 1;
+    Ok(())
+}
+
+fn since_unix_epoch(time: Option<SystemTime>) -> Option<Duration> {
+    time?.duration_since(SystemTime::UNIX_EPOCH).ok()
+}
+
+#[cfg(unix)]
+fn permission(meta: &fs::Metadata) -> Option<Permission> {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+    Some(Permission::new(
+        meta.uid().into(),
+        String::new(),
+        meta.gid().into(),
+        String::new(),
+        (meta.permissions().mode() & 0o7777) as u16,
+    ))
+}
+
+#[cfg(not(unix))]
+fn permission(_meta: &fs::Metadata) -> Option<Permission> {
+    None
+}
+
+/// Extracts all the entries of the archive at `archive` into the directory `dest`.
+///
+/// The data of the files is streamed from the archive, so the files are never read into memory as a whole.
+/// Symbolic links and hard links are created after all the other entries,
+/// so that no file is written through a link extracted from the archive.
+///
+/// # Examples
+/// ```no_run
+/// use pna::fs;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let options = fs::ExtractDirOptions::new().with_keep_timestamp(true);
+/// let summary = fs::extract_archive_to_dir("src.pna", "out", options)?;
+/// println!("{} bytes", summary.bytes());
+/// #     Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+/// Returns error when failed to read the archive or to write the files,
+/// or the archive contains an entry whose name is not a relative path.
+#[inline]
+pub fn extract_archive_to_dir<P: AsRef<Path>, Q: AsRef<Path>>(
+    archive: P,
+    dest: Q,
+    options: ExtractDirOptions,
+) -> io::Result<DirSummary> {
+    let dest = dest.as_ref();
+    let file = fs::File::open(archive)?;
+    let mut archive = Archive::read_header(io::BufReader::new(file))?;
+    let read_options = ReadOptions::with_password(options.password.as_deref());
+    let mut summary = DirSummary::default();
+    let mut links = Vec::new();
+    let mut dirs = Vec::new();
+    fs::create_dir_all(dest)?;
+    for entry in archive
+        .entries()
+        .extract_solid_entries(options.password.as_deref())
+    {
+        let entry = entry?;
+        let path = dest.join(entry_path(&entry)?);
+        match entry.header().data_kind() {
+            DataKind::File => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut file = fs::File::create(&path)?;
+                summary.bytes += io::copy(&mut entry.reader(read_options.clone())?, &mut file)?;
+                if options.keep_timestamp {
+                    file.set_times(file_times(entry.metadata()))?;
+                }
+                drop(file);
+                if options.keep_permission {
+                    set_permission(&path, entry.metadata())?;
+                }
+            }
+            DataKind::Directory => {
+                fs::create_dir_all(&path)?;
+                dirs.push((path, entry.metadata().clone()));
+            }
+            DataKind::SymbolicLink | DataKind::HardLink => {
+                let mut target = String::new();
+                entry
+                    .reader(read_options.clone())?
+                    .read_to_string(&mut target)?;
+                links.push((path, entry.header().data_kind(), target));
+            }
+        }
+        summary.entries += 1;
+    }
+    for (path, kind, target) in links {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if kind == DataKind::SymbolicLink {
+            super::symlink(target, &path)?;
+        } else {
+            let original = path.parent().unwrap_or(dest).join(target);
+            fs::hard_link(original, &path)?;
+        }
+    }
+    // Children first, because restoring the metadata of a directory
+    // may prevent writing into it and writing into it updates its timestamps.
+    for (path, metadata) in dirs.into_iter().rev() {
+        if options.keep_timestamp {
+            open_dir(&path)?.set_times(file_times(&metadata))?;
+        }
+        if options.keep_permission {
+            set_permission(&path, &metadata)?;
+        }
+    }
+    Ok(summary)
+}
+
+fn entry_path(entry: &NormalEntry) -> io::Result<&Path> {
+    let path = entry.header().path().as_path();
+    if path
+        .components()
+        .all(|it| matches!(it, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(path)
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("entry name is not a relative path: {}", path.display()),
+        ))
+    }
+}
+
+fn file_times(metadata: &Metadata) -> fs::FileTimes {
+    let mut times = fs::FileTimes::new();
+    if let Some(accessed) = metadata.accessed_time() {
+        times = times.set_accessed(accessed);
+    }
+    if let Some(modified) = metadata.modified_time() {
+        times = times.set_modified(modified);
+    }
+    #[cfg(any(windows, target_os = "macos"))]
+    if let Some(created) = metadata.created_time() {
+        times = times.set_created(created);
+    }
+    times
+}
+
+#[cfg(not(windows))]
+fn open_dir(path: &Path) -> io::Result<fs::File> {
+    fs::File::open(path)
+}
+
+#[cfg(windows)]
+fn open_dir(path: &Path) -> io::Result<fs::File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    const FILE_WRITE_ATTRIBUTES: u32 = 0x0100;
+    const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+    fs::OpenOptions::new()
+        .access_mode(FILE_WRITE_ATTRIBUTES)
+        .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+}
+
+#[cfg(unix)]
+fn set_permission(path: &Path, metadata: &Metadata) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    match metadata.permission() {
+        Some(permission) => fs::set_permissions(
+            path,
+            fs::Permissions::from_mode(permission.permissions().into()),
+        ),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_permission(_path: &Path, _metadata: &Metadata) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("pna-fs-dir-{}-{name}", std::process::id()));
+        if root.exists() {
+            fs::remove_dir_all(&root).unwrap();
+        }
+        let src = root.join("src");
+        fs::create_dir_all(src.join("nested/deeper")).unwrap();
+        fs::create_dir_all(src.join("empty")).unwrap();
+        fs::write(src.join("a.txt"), b"alpha").unwrap();
+        fs::write(src.join("nested/b.txt"), b"bravo".repeat(1000)).unwrap();
+        fs::write(
+            src.join("nested/deeper/c.bin"),
+            (0..=255u8).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        root
+    }
+
+    fn assert_same_tree(expected: &Path, actual: &Path, keep_metadata: bool) {
+        let mut expected_names = fs::read_dir(expected)
+            .unwrap()
+            .map(|it| it.unwrap().file_name())
+            .collect::<Vec<_>>();
+        let mut actual_names = fs::read_dir(actual)
+            .unwrap()
+            .map(|it| it.unwrap().file_name())
+            .collect::<Vec<_>>();
+        expected_names.sort();
+        actual_names.sort();
+        assert_eq!(expected_names, actual_names);
+        for name in expected_names {
+            let (expected, actual) = (expected.join(&name), actual.join(&name));
+            let (expected_meta, actual_meta) = (
+                fs::symlink_metadata(&expected).unwrap(),
+                fs::symlink_metadata(&actual).unwrap(),
+            );
+            assert_eq!(expected_meta.file_type(), actual_meta.file_type());
+            if expected_meta.is_dir() {
+                assert_same_tree(&expected, &actual, keep_metadata);
+            } else if expected_meta.is_file() {
+                assert_eq!(fs::read(&expected).unwrap(), fs::read(&actual).unwrap());
+            }
+            if keep_metadata && !expected_meta.is_symlink() {
+                assert_eq!(
+                    expected_meta.modified().unwrap(),
+                    actual_meta.modified().unwrap()
+                );
+                assert_eq!(expected_meta.permissions(), actual_meta.permissions());
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let root = fixture("round_trip");
+        let archive = root.join("src.pna");
+        let summary =
+            create_archive_from_dir(root.join("src"), &archive, CreateDirOptions::new()).unwrap();
+        assert_eq!(summary.entries(), 6);
+        assert_eq!(summary.bytes(), 5 + 5000 + 256);
+
+        let extracted =
+            extract_archive_to_dir(&archive, root.join("out"), ExtractDirOptions::new()).unwrap();
+        assert_eq!(summary, extracted);
+        assert_same_tree(&root.join("src"), &root.join("out"), false);
+    }
+
+    #[test]
+    fn round_trip_with_metadata_and_password() {
+        let root = fixture("round_trip_with_metadata_and_password");
+        let src = root.join("src");
+        let past = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        for path in ["a.txt", "nested/b.txt", "nested/deeper/c.bin"] {
+            fs::File::options()
+                .write(true)
+                .open(src.join(path))
+                .unwrap()
+                .set_modified(past)
+                .unwrap();
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(src.join("a.txt"), fs::Permissions::from_mode(0o600)).unwrap();
+            fs::set_permissions(src.join("nested"), fs::Permissions::from_mode(0o750)).unwrap();
+            super::super::symlink("nested/b.txt", src.join("link")).unwrap();
+        }
+        for path in ["nested/deeper", "nested", "empty"] {
+            open_dir(&src.join(path))
+                .unwrap()
+                .set_modified(past)
+                .unwrap();
+        }
+        let archive = root.join("src.pna");
+        create_archive_from_dir(
+            &src,
+            &archive,
+            CreateDirOptions::new()
+                .with_keep_permission(true)
+                .with_keep_timestamp(true)
+                .with_compression(Compression::XZ)
+                .with_password(Some("password")),
+        )
+        .unwrap();
+
+        let wrong_password = extract_archive_to_dir(
+            &archive,
+            root.join("wrong"),
+            ExtractDirOptions::new().with_password(Some("wrong")),
+        );
+        assert!(wrong_password.is_err());
+
+        extract_archive_to_dir(
+            &archive,
+            root.join("out"),
+            ExtractDirOptions::new()
+                .with_keep_permission(true)
+                .with_keep_timestamp(true)
+                .with_password(Some("password")),
+        )
+        .unwrap();
+        assert_same_tree(&src, &root.join("out"), true);
+    }
+
+    #[test]
+    fn archive_in_src_dir_is_skipped() {
+        let root = fixture("archive_in_src_dir_is_skipped");
+        let src = root.join("src");
+        let summary =
+            create_archive_from_dir(&src, src.join("self.pna"), CreateDirOptions::new()).unwrap();
+        assert_eq!(summary.entries(), 6);
+    }
+}
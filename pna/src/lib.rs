@@ -17,6 +17,11 @@ pub mod fs;
 pub mod prelude;
 
 pub use libpna::*;
+/// Version of the libpna crate that this crate is built on.
+pub use libpna::VERSION as LIBPNA_VERSION;
+
+/// Version of this crate.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg(test)]
 mod tests {